@@ -283,6 +283,43 @@ impl ApiClient {
             .find(|a| a.short_name.to_lowercase() == name.to_lowercase()))
     }
 
+    // ============ Export ============
+
+    /// Download an attempt transcript as markdown, html or jsonl. Returns the
+    /// server-suggested file name alongside the body.
+    pub async fn export_task_attempt(
+        &self,
+        attempt_id: Uuid,
+        format: &str,
+    ) -> Result<(Option<String>, String)> {
+        let resp = self
+            .client
+            .get(format!(
+                "{}/api/task-attempts/{}/export",
+                self.base_url, attempt_id
+            ))
+            .query(&[("format", format)])
+            .send()
+            .await
+            .context("Failed to export task attempt")?;
+
+        if resp.status().is_success() {
+            let file_name = resp
+                .headers()
+                .get(reqwest::header::CONTENT_DISPOSITION)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split("filename=").nth(1))
+                .map(|name| name.trim_matches('"').to_string());
+            Ok((file_name, resp.text().await?))
+        } else if resp.status().as_u16() == 401 {
+            anyhow::bail!("Authentication required. Run: pcg config --set server.api_key=YOUR_KEY");
+        } else {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            anyhow::bail!("Failed to export task attempt: {} - {}", status, text);
+        }
+    }
+
     // ============ Health Check ============
 
     pub async fn health_check(&self) -> Result<bool> {
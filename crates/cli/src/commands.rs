@@ -2,8 +2,11 @@
 //!
 //! Handles non-interactive commands like status, tasks, projects, etc.
 
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
 use colored::Colorize;
+use uuid::Uuid;
 
 use crate::{
     api::{ApiClient, CreateTaskRequest},
@@ -241,6 +244,40 @@ pub async fn show_cost(api: &ApiClient, _session: Option<&str>) -> Result<()> {
     Ok(())
 }
 
+/// Export a task attempt transcript to a file (or stdout with `-o -`)
+pub async fn export_attempt(
+    api: &ApiClient,
+    attempt_id: Uuid,
+    format: &str,
+    output_path: Option<&Path>,
+) -> Result<()> {
+    let output = OutputHandler::new(false, false);
+
+    let (file_name, body) = api.export_task_attempt(attempt_id, format).await?;
+
+    if output_path == Some(Path::new("-")) {
+        print!("{}", body);
+        return Ok(());
+    }
+
+    let path = match (output_path, file_name) {
+        (Some(path), _) => path.to_path_buf(),
+        (None, Some(name)) => PathBuf::from(name),
+        (None, None) => PathBuf::from(format!("attempt-{}.{}", attempt_id, format)),
+    };
+    std::fs::write(&path, &body)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    output.print_success(&format!(
+        "Exported attempt {} ({} bytes) to {}",
+        attempt_id,
+        format_num(body.len() as i64),
+        path.display()
+    ));
+
+    Ok(())
+}
+
 /// Show current configuration
 pub fn show_config(config: &Config) -> Result<()> {
     let output = OutputHandler::new(false, false);
@@ -15,6 +15,7 @@ use std::path::PathBuf;
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use uuid::Uuid;
 
 /// PCG CLI - AI-Native Development Assistant
 #[derive(Parser)]
@@ -37,6 +38,7 @@ Examples:
   pcg --project "My Project"   # Start with specific project
   pcg status                   # Show current session status
   pcg tasks                    # List tasks in current project
+  pcg export <ATTEMPT_ID> -f html   # Export an attempt transcript
 "#)]
 struct Cli {
     /// Project name or ID to work with
@@ -102,6 +104,20 @@ enum Commands {
         session: Option<String>,
     },
 
+    /// Export a task attempt transcript (markdown, html or jsonl trajectory)
+    Export {
+        /// Task attempt ID
+        attempt_id: Uuid,
+
+        /// Output format: markdown, html or jsonl
+        #[arg(short, long, default_value = "markdown")]
+        format: String,
+
+        /// Output file ("-" for stdout); defaults to the server-suggested name
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
     /// Configuration management
     Config {
         /// Show current configuration
@@ -163,6 +179,13 @@ async fn main() -> Result<()> {
         Some(Commands::Cost { session }) => {
             commands::show_cost(&api, session.as_deref()).await?;
         }
+        Some(Commands::Export {
+            attempt_id,
+            format,
+            output,
+        }) => {
+            commands::export_attempt(&api, attempt_id, &format, output.as_deref()).await?;
+        }
         Some(Commands::Config { show, set }) => {
             if show {
                 commands::show_config(&config)?;
@@ -0,0 +1,135 @@
+-- Full-text search over execution logs and agent conversations
+-- Created: 2026-02-14
+-- Purpose: Make normalized coding-agent log entries and Nora/agent conversation
+-- messages searchable with project, agent, executor and date filters.
+--
+-- `log_search_entries` holds one row per searchable document together with the
+-- filter columns. `log_search_fts` is an external-content FTS5 index over it,
+-- kept in sync by the triggers below.
+
+CREATE TABLE IF NOT EXISTS log_search_entries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+
+    -- 'execution_log' or 'agent_message'
+    source_type TEXT NOT NULL CHECK(source_type IN ('execution_log', 'agent_message')),
+
+    -- execution_processes.id for logs, agent_conversations.id for messages
+    source_id BLOB NOT NULL,
+
+    -- Stable key within the source: normalized entry index or message id
+    entry_key TEXT NOT NULL,
+
+    -- assistant_message, tool_use, command, error, user_message, ...
+    entry_type TEXT NOT NULL,
+
+    -- Tool name for tool_use/command entries
+    tool_name TEXT,
+
+    -- Filter columns
+    project_id BLOB,
+    task_attempt_id BLOB,
+    agent_id BLOB,
+    executor TEXT,
+
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+
+    UNIQUE(source_type, source_id, entry_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_log_search_entries_project
+ON log_search_entries(project_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_log_search_entries_agent
+ON log_search_entries(agent_id, created_at);
+
+CREATE INDEX IF NOT EXISTS idx_log_search_entries_attempt
+ON log_search_entries(task_attempt_id);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS log_search_fts USING fts5(
+    content,
+    tool_name,
+    content = 'log_search_entries',
+    content_rowid = 'id',
+    tokenize = 'porter unicode61'
+);
+
+CREATE TRIGGER IF NOT EXISTS trg_log_search_entries_ai
+AFTER INSERT ON log_search_entries
+BEGIN
+    INSERT INTO log_search_fts(rowid, content, tool_name)
+    VALUES (NEW.id, NEW.content, NEW.tool_name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_log_search_entries_ad
+AFTER DELETE ON log_search_entries
+BEGIN
+    INSERT INTO log_search_fts(log_search_fts, rowid, content, tool_name)
+    VALUES ('delete', OLD.id, OLD.content, OLD.tool_name);
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_log_search_entries_au
+AFTER UPDATE ON log_search_entries
+BEGIN
+    INSERT INTO log_search_fts(log_search_fts, rowid, content, tool_name)
+    VALUES ('delete', OLD.id, OLD.content, OLD.tool_name);
+    INSERT INTO log_search_fts(rowid, content, tool_name)
+    VALUES (NEW.id, NEW.content, NEW.tool_name);
+END;
+
+-- Index conversation messages as they are written
+CREATE TRIGGER IF NOT EXISTS trg_agent_conversation_messages_search
+AFTER INSERT ON agent_conversation_messages
+WHEN NEW.role != 'system' AND length(trim(NEW.content)) > 0
+BEGIN
+    INSERT OR IGNORE INTO log_search_entries (
+        source_type, source_id, entry_key, entry_type, tool_name,
+        project_id, agent_id, content, created_at
+    )
+    SELECT
+        'agent_message',
+        NEW.conversation_id,
+        lower(hex(NEW.id)),
+        NEW.role || '_message',
+        NEW.tool_name,
+        c.project_id,
+        c.agent_id,
+        NEW.content,
+        NEW.created_at
+    FROM agent_conversations c
+    WHERE c.id = NEW.conversation_id;
+END;
+
+-- Drop indexed entries when their source goes away
+CREATE TRIGGER IF NOT EXISTS trg_execution_processes_search_cleanup
+AFTER DELETE ON execution_processes
+BEGIN
+    DELETE FROM log_search_entries
+    WHERE source_type = 'execution_log' AND source_id = OLD.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS trg_agent_conversations_search_cleanup
+AFTER DELETE ON agent_conversations
+BEGIN
+    DELETE FROM log_search_entries
+    WHERE source_type = 'agent_message' AND source_id = OLD.id;
+END;
+
+-- Backfill existing conversation history
+INSERT OR IGNORE INTO log_search_entries (
+    source_type, source_id, entry_key, entry_type, tool_name,
+    project_id, agent_id, content, created_at
+)
+SELECT
+    'agent_message',
+    m.conversation_id,
+    lower(hex(m.id)),
+    m.role || '_message',
+    m.tool_name,
+    c.project_id,
+    c.agent_id,
+    m.content,
+    m.created_at
+FROM agent_conversation_messages m
+JOIN agent_conversations c ON c.id = m.conversation_id
+WHERE m.role != 'system' AND length(trim(m.content)) > 0;
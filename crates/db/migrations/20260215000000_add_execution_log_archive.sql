@@ -0,0 +1,59 @@
+-- Cold storage and retention for execution logs
+-- Created: 2026-02-15
+-- Purpose: Move finished executions' JSONL logs out of db.sqlite into
+-- zstd-compressed segment files, with per-project retention policies and a
+-- record of every vacuum run.
+
+-- Index of compressed segment files. Each archived execution is split into one
+-- or more ordered segments; `path` is relative to the log archive root.
+CREATE TABLE IF NOT EXISTS execution_log_segments (
+    id BLOB PRIMARY KEY,
+    execution_id BLOB NOT NULL REFERENCES execution_processes(id) ON DELETE CASCADE,
+    segment_index INTEGER NOT NULL,
+    path TEXT NOT NULL,
+    codec TEXT NOT NULL DEFAULT 'zstd' CHECK(codec IN ('zstd')),
+    first_line INTEGER NOT NULL,
+    line_count INTEGER NOT NULL,
+    uncompressed_bytes INTEGER NOT NULL,
+    compressed_bytes INTEGER NOT NULL,
+    sha256 TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE(execution_id, segment_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_execution_log_segments_execution
+ON execution_log_segments(execution_id, segment_index);
+
+-- Per-project retention. Projects without a row use the built-in defaults.
+CREATE TABLE IF NOT EXISTS log_retention_policies (
+    project_id BLOB PRIMARY KEY REFERENCES projects(id) ON DELETE CASCADE,
+
+    -- Hours after completion before logs move to cold storage (0 = immediately)
+    archive_after_hours INTEGER NOT NULL DEFAULT 24 CHECK(archive_after_hours >= 0),
+
+    -- Days after completion before logs are deleted entirely (NULL = keep forever)
+    delete_after_days INTEGER CHECK(delete_after_days IS NULL OR delete_after_days > 0),
+
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+-- One row per retention/vacuum pass, so space reclaimed can be reported
+CREATE TABLE IF NOT EXISTS log_vacuum_runs (
+    id BLOB PRIMARY KEY,
+    executions_archived INTEGER NOT NULL DEFAULT 0,
+    executions_deleted INTEGER NOT NULL DEFAULT 0,
+    bytes_archived INTEGER NOT NULL DEFAULT 0,
+    bytes_compressed INTEGER NOT NULL DEFAULT 0,
+    bytes_deleted INTEGER NOT NULL DEFAULT 0,
+    db_bytes_before INTEGER NOT NULL DEFAULT 0,
+    db_bytes_after INTEGER NOT NULL DEFAULT 0,
+    vacuumed INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    started_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    finished_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_log_vacuum_runs_started
+ON log_vacuum_runs(started_at);
@@ -0,0 +1,7 @@
+-- Record which forge a PR/MR was opened on
+-- Created: 2026-02-16
+-- Purpose: PR merges can now live on GitHub, GitLab or Gitea/Forgejo. Existing
+-- rows were all created through the GitHub integration.
+
+ALTER TABLE merges ADD COLUMN pr_forge TEXT NOT NULL DEFAULT 'github'
+    CHECK (pr_forge IN ('github', 'gitlab', 'gitea'));
@@ -0,0 +1,21 @@
+-- Review and CI feedback relayed from open PRs back to the coding agent
+-- Created: 2026-02-17
+-- Purpose: Remember which review comments, change requests and failed checks
+-- have already been turned into a follow-up prompt, so each is sent once.
+
+CREATE TABLE IF NOT EXISTS pr_feedback_items (
+    id BLOB PRIMARY KEY,
+    merge_id BLOB NOT NULL REFERENCES merges(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK(kind IN ('review_comment', 'change_request', 'failed_check')),
+    -- Identifier assigned by the forge (comment/review/check run id)
+    external_id TEXT NOT NULL,
+    -- Items relayed together in one follow-up share a batch id
+    batch_id BLOB NOT NULL,
+    -- 1 when the batch was started as a follow-up without human review
+    auto_started INTEGER NOT NULL DEFAULT 0,
+    relayed_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE(merge_id, kind, external_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_pr_feedback_items_merge
+ON pr_feedback_items(merge_id, batch_id);
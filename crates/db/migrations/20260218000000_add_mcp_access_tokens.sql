@@ -0,0 +1,39 @@
+-- Bearer tokens and audit trail for the MCP servers served over HTTP
+-- Created: 2026-02-18
+-- Purpose: Let remote agents and IDEs reach the task and Nora MCP servers
+-- as a specific user, and record every tool call they make.
+
+CREATE TABLE IF NOT EXISTS mcp_access_tokens (
+    id BLOB PRIMARY KEY,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    -- SHA256 of the token; the token itself is only shown once
+    token_hash TEXT NOT NULL UNIQUE,
+    -- JSON array of servers the token may use, e.g. ["tasks", "nora"]
+    scopes TEXT NOT NULL DEFAULT '["tasks"]',
+    expires_at TEXT,
+    last_used_at TEXT,
+    revoked_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_mcp_access_tokens_user
+ON mcp_access_tokens(user_id);
+
+CREATE TABLE IF NOT EXISTS mcp_audit_log (
+    id BLOB PRIMARY KEY,
+    token_id BLOB REFERENCES mcp_access_tokens(id) ON DELETE SET NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Which MCP server handled the call ('tasks' or 'nora')
+    server TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    -- Tool arguments as sent by the client (JSON object)
+    arguments TEXT,
+    success INTEGER NOT NULL,
+    error TEXT,
+    duration_ms INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_mcp_audit_log_user
+ON mcp_audit_log(user_id, created_at);
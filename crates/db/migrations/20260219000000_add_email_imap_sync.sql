@@ -0,0 +1,40 @@
+-- IMAP/SMTP mailbox sync for email accounts
+-- Created: 2026-02-19
+-- Purpose: Store login credentials for custom IMAP servers, remember the
+-- UIDVALIDITY/UIDNEXT of every synced folder so sync can resume
+-- incrementally, and keep each message's IMAP location and Message-ID
+-- so flags can be refreshed and replies threaded.
+
+ALTER TABLE email_accounts ADD COLUMN imap_username TEXT;
+ALTER TABLE email_accounts ADD COLUMN imap_password TEXT;
+
+CREATE TABLE IF NOT EXISTS email_folders (
+    id BLOB PRIMARY KEY,
+    email_account_id BLOB NOT NULL REFERENCES email_accounts(id) ON DELETE CASCADE,
+    -- Mailbox name as reported by LIST, e.g. 'INBOX' or '[Gmail]/Sent Mail'
+    name TEXT NOT NULL,
+    role TEXT NOT NULL DEFAULT 'other' CHECK (role IN (
+        'inbox', 'sent', 'drafts', 'archive', 'spam', 'trash', 'other'
+    )),
+    uid_validity INTEGER,
+    -- Next UID to fetch; everything below has been synced
+    uid_next INTEGER NOT NULL DEFAULT 1,
+    message_count INTEGER NOT NULL DEFAULT 0,
+    last_synced_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+
+    UNIQUE(email_account_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_folders_account ON email_folders(email_account_id);
+
+ALTER TABLE email_messages ADD COLUMN message_id_header TEXT;
+ALTER TABLE email_messages ADD COLUMN imap_folder TEXT;
+ALTER TABLE email_messages ADD COLUMN imap_uid INTEGER;
+
+CREATE INDEX IF NOT EXISTS idx_email_messages_message_id
+ON email_messages(email_account_id, message_id_header);
+
+CREATE INDEX IF NOT EXISTS idx_email_messages_imap_location
+ON email_messages(email_account_id, imap_folder, imap_uid);
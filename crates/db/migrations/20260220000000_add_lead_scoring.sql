@@ -0,0 +1,39 @@
+-- Lead scoring engine
+-- Created: 2026-02-20
+-- Purpose: Per-project scoring rules, a history of every score change with
+-- the breakdown that produced it, and a manual adjustment that survives
+-- recalculation.
+
+CREATE TABLE IF NOT EXISTS lead_scoring_configs (
+    id BLOB PRIMARY KEY,
+    project_id BLOB NOT NULL UNIQUE REFERENCES projects(id) ON DELETE CASCADE,
+    is_enabled INTEGER NOT NULL DEFAULT 1,
+    -- JSON: field rules, lifecycle points, activity weights, decay, tiers
+    rules TEXT NOT NULL,
+    -- Scheduled recalculation applies decay even when nothing happens
+    recalc_interval_minutes INTEGER NOT NULL DEFAULT 360,
+    last_recalculated_at TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE TABLE IF NOT EXISTS lead_score_history (
+    id BLOB PRIMARY KEY,
+    crm_contact_id BLOB NOT NULL REFERENCES crm_contacts(id) ON DELETE CASCADE,
+    project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    score INTEGER NOT NULL,
+    previous_score INTEGER NOT NULL,
+    trigger TEXT NOT NULL CHECK (trigger IN (
+        'activity', 'contact_updated', 'schedule', 'manual', 'config_changed'
+    )),
+    -- JSON: the components that made up the score
+    breakdown TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_lead_score_history_contact
+ON lead_score_history(crm_contact_id, created_at);
+
+-- Points added through the manual lead-score endpoint, kept apart so the
+-- engine can recalculate without losing them
+ALTER TABLE crm_contacts ADD COLUMN lead_score_adjustment INTEGER NOT NULL DEFAULT 0;
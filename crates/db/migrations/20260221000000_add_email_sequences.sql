@@ -0,0 +1,93 @@
+-- Outbound email sequences
+-- Created: 2026-02-21
+-- Purpose: Ordered sequences of template emails, business-day waits and
+-- follow-up tasks that CRM contacts are enrolled in. Enrollments stop on a
+-- reply or bounce, and every send, open, reply, bounce, meeting and task is
+-- recorded against the step that caused it.
+
+-- Sending limits for sequence mail, in the account's local time
+ALTER TABLE email_accounts ADD COLUMN send_window_start TEXT NOT NULL DEFAULT '09:00';
+ALTER TABLE email_accounts ADD COLUMN send_window_end TEXT NOT NULL DEFAULT '17:00';
+ALTER TABLE email_accounts ADD COLUMN send_utc_offset_minutes INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE email_accounts ADD COLUMN daily_send_limit INTEGER NOT NULL DEFAULT 50;
+
+CREATE TABLE IF NOT EXISTS email_sequences (
+    id BLOB PRIMARY KEY,
+    project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    email_account_id BLOB NOT NULL REFERENCES email_accounts(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    description TEXT,
+    status TEXT NOT NULL DEFAULT 'draft' CHECK (status IN (
+        'draft', 'active', 'paused', 'archived'
+    )),
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_sequences_project ON email_sequences(project_id);
+
+CREATE TABLE IF NOT EXISTS email_sequence_steps (
+    id BLOB PRIMARY KEY,
+    sequence_id BLOB NOT NULL REFERENCES email_sequences(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    step_type TEXT NOT NULL CHECK (step_type IN ('email', 'wait', 'task')),
+    -- Email steps; a step without a subject replies in the previous thread
+    subject TEXT,
+    body_text TEXT,
+    body_html TEXT,
+    -- Wait steps
+    wait_business_days INTEGER,
+    -- Task steps
+    task_title TEXT,
+    task_description TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+
+    UNIQUE(sequence_id, position)
+);
+
+CREATE TABLE IF NOT EXISTS email_sequence_enrollments (
+    id BLOB PRIMARY KEY,
+    sequence_id BLOB NOT NULL REFERENCES email_sequences(id) ON DELETE CASCADE,
+    crm_contact_id BLOB NOT NULL REFERENCES crm_contacts(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'active' CHECK (status IN (
+        'active', 'paused', 'completed', 'replied', 'bounced', 'removed', 'failed'
+    )),
+    -- Position of the next step to run
+    current_step INTEGER NOT NULL DEFAULT 0,
+    next_run_at TEXT,
+    -- Last email sent, so follow-ups without a subject stay in its thread
+    last_message_id BLOB REFERENCES email_messages(id) ON DELETE SET NULL,
+    exit_reason TEXT,
+    enrolled_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    finished_at TEXT,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+
+    UNIQUE(sequence_id, crm_contact_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_sequence_enrollments_due
+ON email_sequence_enrollments(status, next_run_at);
+
+CREATE INDEX IF NOT EXISTS idx_email_sequence_enrollments_contact
+ON email_sequence_enrollments(crm_contact_id);
+
+CREATE TABLE IF NOT EXISTS email_sequence_events (
+    id BLOB PRIMARY KEY,
+    enrollment_id BLOB NOT NULL REFERENCES email_sequence_enrollments(id) ON DELETE CASCADE,
+    sequence_id BLOB NOT NULL REFERENCES email_sequences(id) ON DELETE CASCADE,
+    step_id BLOB REFERENCES email_sequence_steps(id) ON DELETE SET NULL,
+    event_type TEXT NOT NULL CHECK (event_type IN (
+        'sent', 'opened', 'replied', 'bounced', 'meeting_booked', 'task_created', 'failed'
+    )),
+    email_message_id BLOB REFERENCES email_messages(id) ON DELETE SET NULL,
+    task_id BLOB REFERENCES tasks(id) ON DELETE SET NULL,
+    detail TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_sequence_events_enrollment
+ON email_sequence_events(enrollment_id, event_type);
+
+CREATE INDEX IF NOT EXISTS idx_email_sequence_events_sequence
+ON email_sequence_events(sequence_id, step_id);
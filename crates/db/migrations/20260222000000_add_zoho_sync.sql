@@ -0,0 +1,42 @@
+-- Zoho CRM synchronisation
+-- Created: 2026-02-22
+-- Purpose: Track which local contacts, deals and activities are linked to
+-- which Zoho records, the field values both sides agreed on at the last sync
+-- (to tell which side changed a field), and the conflicts resolved on the way.
+
+-- Per-field conflict handling: {"default": "newest_wins", "fields": {"email": "source_wins"}}
+ALTER TABLE zoho_integrations ADD COLUMN conflict_rules TEXT;
+
+CREATE TABLE IF NOT EXISTS zoho_sync_records (
+    id BLOB PRIMARY KEY,
+    integration_id BLOB NOT NULL REFERENCES zoho_integrations(id) ON DELETE CASCADE,
+    entity_type TEXT NOT NULL CHECK (entity_type IN ('contact', 'deal', 'activity')),
+    local_id BLOB NOT NULL,
+    zoho_id TEXT NOT NULL,
+    -- Zoho's Modified_Time of the record when it was last synced
+    zoho_modified_at TEXT,
+    -- JSON object of Zoho field -> value both sides held after the last sync
+    snapshot TEXT,
+    synced_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+
+    UNIQUE(integration_id, entity_type, local_id),
+    UNIQUE(integration_id, entity_type, zoho_id)
+);
+
+CREATE TABLE IF NOT EXISTS zoho_sync_conflicts (
+    id BLOB PRIMARY KEY,
+    integration_id BLOB NOT NULL REFERENCES zoho_integrations(id) ON DELETE CASCADE,
+    entity_type TEXT NOT NULL CHECK (entity_type IN ('contact', 'deal', 'activity')),
+    local_id BLOB NOT NULL,
+    zoho_id TEXT NOT NULL,
+    -- Local field name, e.g. `email` or `custom_fields.region`
+    field TEXT NOT NULL,
+    local_value TEXT,
+    zoho_value TEXT,
+    strategy TEXT NOT NULL CHECK (strategy IN ('newest_wins', 'source_wins')),
+    winner TEXT NOT NULL CHECK (winner IN ('local', 'zoho')),
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_zoho_sync_conflicts_integration
+ON zoho_sync_conflicts(integration_id, created_at);
@@ -0,0 +1,22 @@
+-- Call transcripts and post-call follow-up
+-- Created: 2026-02-23
+-- Purpose: Keep every turn of the phone conversations Nora handles, and the
+-- action items pulled out of each call once it has been summarised.
+
+CREATE TABLE IF NOT EXISTS call_turns (
+    id BLOB PRIMARY KEY,
+    call_log_id BLOB NOT NULL REFERENCES call_logs(id) ON DELETE CASCADE,
+    speaker TEXT NOT NULL CHECK (speaker IN ('caller', 'nora')),
+    content TEXT NOT NULL,
+    -- Speech recognition confidence for caller turns (0.0 to 1.0)
+    confidence REAL,
+    spoken_at TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_call_turns_call_log ON call_turns(call_log_id, spoken_at);
+
+-- JSON array of {"title", "description", "due_date", "task_id"} from the summary
+ALTER TABLE call_logs ADD COLUMN action_items TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_call_logs_transcription_status ON call_logs(transcription_status);
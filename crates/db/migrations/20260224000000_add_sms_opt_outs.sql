@@ -0,0 +1,13 @@
+-- SMS opt-outs
+-- Created: 2026-02-24
+-- Purpose: Remember numbers that texted STOP so Nora never messages them
+-- again until they text START.
+
+CREATE TABLE IF NOT EXISTS sms_opt_outs (
+    id BLOB PRIMARY KEY,
+    -- E.164 number that opted out
+    phone_number TEXT NOT NULL UNIQUE,
+    -- Keyword the number sent (STOP, UNSUBSCRIBE, ...)
+    keyword TEXT NOT NULL,
+    opted_out_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
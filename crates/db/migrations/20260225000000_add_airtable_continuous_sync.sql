@@ -0,0 +1,57 @@
+-- Continuous Airtable synchronisation
+-- Created: 2026-02-25
+-- Purpose: Per-table field mappings for the scheduled Airtable sync, the
+-- field values both sides agreed on at the last sync (to tell which side
+-- changed a field), and records whose local task was deleted so the deletion
+-- can be pushed to Airtable.
+
+CREATE TABLE IF NOT EXISTS airtable_table_mappings (
+    id BLOB PRIMARY KEY,
+    connection_id BLOB NOT NULL REFERENCES airtable_bases(id) ON DELETE CASCADE,
+    airtable_table_id TEXT NOT NULL,
+    airtable_table_name TEXT,
+    -- Board new tasks are created on, and the only board pushed from when set
+    board_id BLOB,
+    -- JSON array of {"airtable_field": "Status", "task_field": "status", "values": {"Not started": "todo"}}
+    field_mappings TEXT NOT NULL DEFAULT '[]',
+    -- Last-modified-time field used to find changed records (default: LAST_MODIFIED_TIME())
+    modified_field TEXT,
+    import_new_records INTEGER NOT NULL DEFAULT 1,
+    push_new_tasks INTEGER NOT NULL DEFAULT 0,
+    delete_remote_records INTEGER NOT NULL DEFAULT 1,
+    -- Off: a task whose record was deleted in Airtable is cancelled and unlinked
+    delete_local_tasks INTEGER NOT NULL DEFAULT 0,
+    sync_enabled INTEGER NOT NULL DEFAULT 1,
+    last_pulled_at TEXT,
+    last_sync_error TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+
+    UNIQUE(connection_id, airtable_table_id)
+);
+
+-- JSON object of Airtable field -> value both sides held after the last sync
+ALTER TABLE airtable_record_links ADD COLUMN snapshot TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_airtable_record_links_table
+ON airtable_record_links(airtable_base_id, airtable_table_id);
+
+CREATE TABLE IF NOT EXISTS airtable_deleted_records (
+    id BLOB PRIMARY KEY,
+    airtable_base_id TEXT NOT NULL,
+    airtable_table_id TEXT,
+    airtable_record_id TEXT NOT NULL,
+    deleted_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_airtable_deleted_records_table
+ON airtable_deleted_records(airtable_base_id, airtable_table_id);
+
+-- Links removed because their task was deleted (not unlinked on purpose)
+CREATE TRIGGER IF NOT EXISTS airtable_record_links_task_deleted
+AFTER DELETE ON airtable_record_links
+WHEN NOT EXISTS (SELECT 1 FROM tasks WHERE id = OLD.task_id)
+BEGIN
+    INSERT INTO airtable_deleted_records (id, airtable_base_id, airtable_table_id, airtable_record_id)
+    VALUES (randomblob(16), OLD.airtable_base_id, OLD.airtable_table_id, OLD.airtable_record_id);
+END;
@@ -0,0 +1,161 @@
+-- CMS draft/publish workflow
+-- Created: 2026-02-26
+-- Purpose: The existing cms_* tables become the draft that editors change.
+-- The public site is served from published snapshots, every draft change and
+-- publish is kept as a revision, publishes can be scheduled and announced to
+-- webhooks.
+
+-- Live version of each site, product, FAQ item, page section and setting
+CREATE TABLE IF NOT EXISTS cms_published_entities (
+    site_id         BLOB NOT NULL REFERENCES cms_sites(id) ON DELETE CASCADE,
+    entity_type     TEXT NOT NULL
+                       CHECK (entity_type IN ('site','product','faq_item','page_section','setting')),
+    entity_id       BLOB NOT NULL,
+    -- JSON of the entity as it was published
+    data            TEXT NOT NULL,
+    -- NULL for content that was live before publishing existed
+    revision_id     BLOB,
+    published_at    TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    PRIMARY KEY (entity_type, entity_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cms_published_entities_site
+ON cms_published_entities(site_id, entity_type);
+
+-- History of draft edits and publishes, per entity
+CREATE TABLE IF NOT EXISTS cms_revisions (
+    id              BLOB PRIMARY KEY,
+    site_id         BLOB NOT NULL REFERENCES cms_sites(id) ON DELETE CASCADE,
+    entity_type     TEXT NOT NULL
+                       CHECK (entity_type IN ('site','product','faq_item','page_section','setting')),
+    entity_id       BLOB NOT NULL,
+    version         INTEGER NOT NULL,
+    action          TEXT NOT NULL
+                       CHECK (action IN ('created','updated','deleted','restored','published','unpublished')),
+    -- JSON of the entity this revision is about (its last state for deletes)
+    data            TEXT NOT NULL,
+    user_id         BLOB,
+    created_at      TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    UNIQUE(entity_type, entity_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cms_revisions_site ON cms_revisions(site_id, created_at);
+
+-- Publishes to run later. No entity means the whole site.
+CREATE TABLE IF NOT EXISTS cms_scheduled_publications (
+    id              BLOB PRIMARY KEY,
+    site_id         BLOB NOT NULL REFERENCES cms_sites(id) ON DELETE CASCADE,
+    entity_type     TEXT
+                       CHECK (entity_type IN ('site','product','faq_item','page_section','setting')),
+    entity_id       BLOB,
+    publish_at      TEXT NOT NULL,
+    status          TEXT NOT NULL DEFAULT 'pending'
+                       CHECK (status IN ('pending','completed','failed','cancelled')),
+    error           TEXT,
+    user_id         BLOB,
+    created_at      TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    completed_at    TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_cms_scheduled_publications_due
+ON cms_scheduled_publications(status, publish_at);
+
+-- Endpoints notified after every publish
+CREATE TABLE IF NOT EXISTS cms_webhooks (
+    id                  BLOB PRIMARY KEY,
+    site_id             BLOB NOT NULL REFERENCES cms_sites(id) ON DELETE CASCADE,
+    url                 TEXT NOT NULL,
+    -- Signs the body as X-Cms-Signature: sha256=<hex HMAC>
+    secret              TEXT,
+    is_active           INTEGER NOT NULL DEFAULT 1,
+    last_delivered_at   TEXT,
+    last_status_code    INTEGER,
+    last_error          TEXT,
+    created_at          TEXT NOT NULL DEFAULT (datetime('now', 'subsec')),
+    updated_at          TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_cms_webhooks_site ON cms_webhooks(site_id);
+
+-- Everything that is live today stays live: publish the current content.
+-- Ids are written as simple (unhyphenated) UUIDs and timestamps as RFC 3339.
+INSERT OR IGNORE INTO cms_published_entities (site_id, entity_type, entity_id, data)
+SELECT id, 'site', id, json_object(
+    'id', lower(hex(id)),
+    'slug', slug,
+    'name', name,
+    'domain', domain,
+    'theme_config', theme_config,
+    'is_active', json(CASE WHEN is_active THEN 'true' ELSE 'false' END),
+    'created_at', strftime('%Y-%m-%dT%H:%M:%fZ', created_at),
+    'updated_at', strftime('%Y-%m-%dT%H:%M:%fZ', updated_at)
+)
+FROM cms_sites
+WHERE typeof(id) = 'blob';
+
+INSERT OR IGNORE INTO cms_published_entities (site_id, entity_type, entity_id, data)
+SELECT site_id, 'product', id, json_object(
+    'id', lower(hex(id)),
+    'site_id', lower(hex(site_id)),
+    'slug', slug,
+    'name', name,
+    'short_description', short_description,
+    'long_description', long_description,
+    'price_cents', price_cents,
+    'currency', currency,
+    'stripe_price_id', stripe_price_id,
+    'image_url', image_url,
+    'gallery_images', gallery_images,
+    'specs', specs,
+    'features', features,
+    'is_active', json(CASE WHEN is_active THEN 'true' ELSE 'false' END),
+    'is_featured', json(CASE WHEN is_featured THEN 'true' ELSE 'false' END),
+    'stock_status', stock_status,
+    'sort_order', COALESCE(sort_order, 0),
+    'created_at', strftime('%Y-%m-%dT%H:%M:%fZ', created_at),
+    'updated_at', strftime('%Y-%m-%dT%H:%M:%fZ', updated_at)
+)
+FROM cms_products
+WHERE typeof(id) = 'blob' AND typeof(site_id) = 'blob';
+
+INSERT OR IGNORE INTO cms_published_entities (site_id, entity_type, entity_id, data)
+SELECT site_id, 'faq_item', id, json_object(
+    'id', lower(hex(id)),
+    'site_id', lower(hex(site_id)),
+    'category', category,
+    'question', question,
+    'answer', answer,
+    'sort_order', COALESCE(sort_order, 0),
+    'is_active', json(CASE WHEN is_active THEN 'true' ELSE 'false' END),
+    'created_at', strftime('%Y-%m-%dT%H:%M:%fZ', created_at),
+    'updated_at', strftime('%Y-%m-%dT%H:%M:%fZ', updated_at)
+)
+FROM cms_faq_items
+WHERE typeof(id) = 'blob' AND typeof(site_id) = 'blob';
+
+INSERT OR IGNORE INTO cms_published_entities (site_id, entity_type, entity_id, data)
+SELECT site_id, 'page_section', id, json_object(
+    'id', lower(hex(id)),
+    'site_id', lower(hex(site_id)),
+    'page_slug', page_slug,
+    'section_key', section_key,
+    'content', content,
+    'sort_order', COALESCE(sort_order, 0),
+    'is_active', json(CASE WHEN is_active THEN 'true' ELSE 'false' END),
+    'created_at', strftime('%Y-%m-%dT%H:%M:%fZ', created_at),
+    'updated_at', strftime('%Y-%m-%dT%H:%M:%fZ', updated_at)
+)
+FROM cms_page_sections
+WHERE typeof(id) = 'blob' AND typeof(site_id) = 'blob';
+
+INSERT OR IGNORE INTO cms_published_entities (site_id, entity_type, entity_id, data)
+SELECT site_id, 'setting', id, json_object(
+    'id', lower(hex(id)),
+    'site_id', lower(hex(site_id)),
+    'setting_key', setting_key,
+    'setting_value', setting_value,
+    'created_at', strftime('%Y-%m-%dT%H:%M:%fZ', created_at),
+    'updated_at', strftime('%Y-%m-%dT%H:%M:%fZ', updated_at)
+)
+FROM cms_site_settings
+WHERE typeof(id) = 'blob' AND typeof(site_id) = 'blob';
@@ -93,6 +93,26 @@ impl AirtableRecordLink {
     }
 }
 
+/// A link with what the scheduled sync needs to tell which side changed
+#[derive(Debug, Clone, FromRow)]
+pub struct AirtableLinkState {
+    #[sqlx(flatten)]
+    pub link: AirtableRecordLink,
+    /// JSON object of Airtable field -> value both sides held after the last sync
+    pub snapshot: Option<String>,
+    pub task_updated_at: DateTime<Utc>,
+}
+
+/// An Airtable record whose linked task was deleted locally
+#[derive(Debug, Clone, FromRow)]
+pub struct AirtableDeletedRecord {
+    pub id: Uuid,
+    pub airtable_base_id: String,
+    pub airtable_table_id: Option<String>,
+    pub airtable_record_id: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 pub struct CreateAirtableRecordLink {
     pub task_id: Uuid,
@@ -387,4 +407,161 @@ impl AirtableRecordLink {
             .await?;
         Ok(())
     }
+
+    /// Links to records in one table, with their last synced values
+    pub async fn find_states_by_table(
+        pool: &SqlitePool,
+        base_id: &str,
+        table_id: &str,
+    ) -> Result<Vec<AirtableLinkState>, sqlx::Error> {
+        sqlx::query_as::<_, AirtableLinkState>(
+            r#"SELECT l.*, t.updated_at AS task_updated_at
+            FROM airtable_record_links l
+            JOIN tasks t ON t.id = l.task_id
+            WHERE l.airtable_base_id = ?1 AND l.airtable_table_id = ?2"#,
+        )
+        .bind(base_id)
+        .bind(table_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Record a field sync. Success stores the agreed values and clears the
+    /// last error; failure only records the error. The deliverable sync
+    /// status is left alone either way.
+    pub async fn record_field_sync(
+        pool: &SqlitePool,
+        id: Uuid,
+        snapshot: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        sqlx::query(
+            r#"UPDATE airtable_record_links SET
+                snapshot = COALESCE(?2, snapshot),
+                last_sync_error = ?3,
+                last_synced_at = CASE WHEN ?3 IS NULL THEN ?4 ELSE last_synced_at END,
+                updated_at = ?4
+            WHERE id = ?1"#,
+        )
+        .bind(id)
+        .bind(snapshot)
+        .bind(error)
+        .bind(now)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records in one table whose tasks were deleted since the last sync
+    pub async fn find_deleted_records(
+        pool: &SqlitePool,
+        base_id: &str,
+        table_id: &str,
+    ) -> Result<Vec<AirtableDeletedRecord>, sqlx::Error> {
+        sqlx::query_as::<_, AirtableDeletedRecord>(
+            r#"SELECT * FROM airtable_deleted_records
+            WHERE airtable_base_id = ?1 AND airtable_table_id = ?2
+            ORDER BY deleted_at"#,
+        )
+        .bind(base_id)
+        .bind(table_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Forget a deleted record once the deletion has been handled
+    pub async fn clear_deleted_record(pool: &SqlitePool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM airtable_deleted_records WHERE id = ?1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_utils::{create_test_project, setup_test_pool};
+
+    async fn create_task(pool: &SqlitePool, project_id: Uuid) -> Uuid {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO tasks (id, project_id, title, status) VALUES (?1, ?2, 'Shot 010', 'todo')",
+        )
+        .bind(id)
+        .bind(project_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        id
+    }
+
+    async fn link(pool: &SqlitePool, task_id: Uuid, record_id: &str) -> AirtableRecordLink {
+        AirtableRecordLink::create(
+            pool,
+            CreateAirtableRecordLink {
+                task_id,
+                airtable_record_id: record_id.to_string(),
+                airtable_base_id: "appTracker".into(),
+                airtable_table_id: Some("tblShots".into()),
+                origin: AirtableOrigin::Airtable,
+                airtable_record_url: None,
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn only_deleted_tasks_leave_a_deleted_record() {
+        let pool = setup_test_pool().await;
+        let project_id = create_test_project(&pool).await;
+        let kept = create_task(&pool, project_id).await;
+        let deleted = create_task(&pool, project_id).await;
+        let unlinked = link(&pool, kept, "recKept").await;
+        link(&pool, deleted, "recDeleted").await;
+
+        AirtableRecordLink::record_field_sync(
+            &pool,
+            unlinked.id,
+            Some(r#"{"Shot":"Shot 010"}"#),
+            None,
+        )
+        .await
+        .unwrap();
+        let states = AirtableRecordLink::find_states_by_table(&pool, "appTracker", "tblShots")
+            .await
+            .unwrap();
+        assert_eq!(states.len(), 2);
+        let state = states.iter().find(|s| s.link.id == unlinked.id).unwrap();
+        assert_eq!(state.snapshot.as_deref(), Some(r#"{"Shot":"Shot 010"}"#));
+
+        // Unlinking on purpose is not a deletion to push
+        AirtableRecordLink::delete(&pool, unlinked.id)
+            .await
+            .unwrap();
+        sqlx::query("DELETE FROM tasks WHERE id = ?1")
+            .bind(deleted)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let records = AirtableRecordLink::find_deleted_records(&pool, "appTracker", "tblShots")
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].airtable_record_id, "recDeleted");
+
+        AirtableRecordLink::clear_deleted_record(&pool, records[0].id)
+            .await
+            .unwrap();
+        assert!(
+            AirtableRecordLink::find_deleted_records(&pool, "appTracker", "tblShots")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool, types::Json};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::task::Task;
+
+/// Task property an Airtable field is kept in step with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AirtableTaskField {
+    Title,
+    Description,
+    Status,
+    Priority,
+    DueDate,
+    Tags,
+    /// Value stored under this key in the task's custom properties
+    Custom(String),
+}
+
+impl AirtableTaskField {
+    /// Parse a mapping target: a task property name or `custom_fields.<name>`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "title" => Some(Self::Title),
+            "description" => Some(Self::Description),
+            "status" => Some(Self::Status),
+            "priority" => Some(Self::Priority),
+            "due_date" => Some(Self::DueDate),
+            "tags" => Some(Self::Tags),
+            other => other
+                .strip_prefix("custom_fields.")
+                .filter(|name| !name.is_empty())
+                .map(|name| Self::Custom(name.to_string())),
+        }
+    }
+}
+
+/// One Airtable field and the task property it maps to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+pub struct AirtableFieldMapping {
+    pub airtable_field: String,
+    /// `title`, `description`, `status`, `priority`, `due_date`, `tags` or
+    /// `custom_fields.<name>`
+    pub task_field: String,
+    /// Airtable option -> local value, for single selects such as status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<BTreeMap<String, String>>,
+}
+
+impl AirtableFieldMapping {
+    pub fn target(&self) -> Option<AirtableTaskField> {
+        AirtableTaskField::parse(&self.task_field)
+    }
+}
+
+/// How one table of a connected base is synced with the project's tasks
+#[derive(Debug, Clone, Serialize, Deserialize, TS, FromRow)]
+pub struct AirtableTableMapping {
+    pub id: Uuid,
+    pub connection_id: Uuid,
+    pub airtable_table_id: String,
+    pub airtable_table_name: Option<String>,
+    pub board_id: Option<Uuid>,
+    #[ts(type = "Array<AirtableFieldMapping>")]
+    pub field_mappings: Json<Vec<AirtableFieldMapping>>,
+    pub modified_field: Option<String>,
+    pub import_new_records: bool,
+    pub push_new_tasks: bool,
+    pub delete_remote_records: bool,
+    pub delete_local_tasks: bool,
+    pub sync_enabled: bool,
+    pub last_pulled_at: Option<DateTime<Utc>>,
+    pub last_sync_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+pub struct CreateAirtableTableMapping {
+    pub airtable_table_id: String,
+    pub airtable_table_name: Option<String>,
+    pub board_id: Option<Uuid>,
+    pub field_mappings: Vec<AirtableFieldMapping>,
+    pub modified_field: Option<String>,
+    pub import_new_records: Option<bool>,
+    pub push_new_tasks: Option<bool>,
+    pub delete_remote_records: Option<bool>,
+    pub delete_local_tasks: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+pub struct UpdateAirtableTableMapping {
+    pub airtable_table_name: Option<String>,
+    pub board_id: Option<Uuid>,
+    pub field_mappings: Option<Vec<AirtableFieldMapping>>,
+    pub modified_field: Option<String>,
+    pub import_new_records: Option<bool>,
+    pub push_new_tasks: Option<bool>,
+    pub delete_remote_records: Option<bool>,
+    pub delete_local_tasks: Option<bool>,
+    pub sync_enabled: Option<bool>,
+}
+
+impl AirtableTableMapping {
+    /// Find all table mappings for a base connection
+    pub async fn find_by_connection_id(
+        pool: &SqlitePool,
+        connection_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, AirtableTableMapping>(
+            r#"SELECT * FROM airtable_table_mappings
+            WHERE connection_id = ?1
+            ORDER BY created_at"#,
+        )
+        .bind(connection_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Find a table mapping by ID
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, AirtableTableMapping>(
+            r#"SELECT * FROM airtable_table_mappings WHERE id = ?1"#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    /// Mappings the scheduled sync should run: enabled, on an enabled connection
+    pub async fn find_enabled(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, AirtableTableMapping>(
+            r#"SELECT m.* FROM airtable_table_mappings m
+            JOIN airtable_bases b ON b.id = m.connection_id
+            WHERE m.sync_enabled = 1 AND b.sync_enabled = 1
+            ORDER BY m.created_at"#,
+        )
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Create a table mapping
+    pub async fn create(
+        pool: &SqlitePool,
+        connection_id: Uuid,
+        data: CreateAirtableTableMapping,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as::<_, AirtableTableMapping>(
+            r#"INSERT INTO airtable_table_mappings (
+                id, connection_id, airtable_table_id, airtable_table_name, board_id,
+                field_mappings, modified_field, import_new_records, push_new_tasks,
+                delete_remote_records, delete_local_tasks
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+            RETURNING *"#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(connection_id)
+        .bind(data.airtable_table_id)
+        .bind(data.airtable_table_name)
+        .bind(data.board_id)
+        .bind(Json(data.field_mappings))
+        .bind(data.modified_field)
+        .bind(data.import_new_records.unwrap_or(true))
+        .bind(data.push_new_tasks.unwrap_or(false))
+        .bind(data.delete_remote_records.unwrap_or(true))
+        .bind(data.delete_local_tasks.unwrap_or(false))
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Update a table mapping
+    pub async fn update(
+        pool: &SqlitePool,
+        id: Uuid,
+        data: UpdateAirtableTableMapping,
+    ) -> Result<Self, sqlx::Error> {
+        let current = Self::find_by_id(pool, id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        sqlx::query_as::<_, AirtableTableMapping>(
+            r#"UPDATE airtable_table_mappings SET
+                airtable_table_name = ?2,
+                board_id = ?3,
+                field_mappings = ?4,
+                modified_field = ?5,
+                import_new_records = ?6,
+                push_new_tasks = ?7,
+                delete_remote_records = ?8,
+                delete_local_tasks = ?9,
+                sync_enabled = ?10,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            RETURNING *"#,
+        )
+        .bind(id)
+        .bind(data.airtable_table_name.or(current.airtable_table_name))
+        .bind(data.board_id.or(current.board_id))
+        .bind(
+            data.field_mappings
+                .map(Json)
+                .unwrap_or(current.field_mappings),
+        )
+        .bind(data.modified_field.or(current.modified_field))
+        .bind(
+            data.import_new_records
+                .unwrap_or(current.import_new_records),
+        )
+        .bind(data.push_new_tasks.unwrap_or(current.push_new_tasks))
+        .bind(
+            data.delete_remote_records
+                .unwrap_or(current.delete_remote_records),
+        )
+        .bind(
+            data.delete_local_tasks
+                .unwrap_or(current.delete_local_tasks),
+        )
+        .bind(data.sync_enabled.unwrap_or(current.sync_enabled))
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Tasks created in the connection's project (and on the mapping's board,
+    /// when it has one) since the mapping was set up, not yet linked to any
+    /// Airtable record
+    pub async fn find_unlinked_tasks(&self, pool: &SqlitePool) -> Result<Vec<Task>, sqlx::Error> {
+        sqlx::query_as::<_, Task>(
+            r#"SELECT t.* FROM tasks t
+            JOIN airtable_table_mappings m ON m.id = ?1
+            JOIN airtable_bases b ON b.id = m.connection_id
+            WHERE t.project_id = b.project_id
+              AND (m.board_id IS NULL OR t.board_id = m.board_id)
+              AND t.created_at >= m.created_at
+              AND NOT EXISTS (SELECT 1 FROM airtable_record_links l WHERE l.task_id = t.id)
+            ORDER BY t.created_at"#,
+        )
+        .bind(self.id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Record the outcome of a sync run. `pulled_at` moves the change cursor
+    /// forward; it is left alone when the run failed.
+    pub async fn record_sync(
+        pool: &SqlitePool,
+        id: Uuid,
+        pulled_at: Option<DateTime<Utc>>,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"UPDATE airtable_table_mappings SET
+                last_pulled_at = COALESCE(?2, last_pulled_at),
+                last_sync_error = ?3,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1"#,
+        )
+        .bind(id)
+        .bind(pulled_at)
+        .bind(error)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a table mapping
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM airtable_table_mappings WHERE id = ?1")
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        airtable_base::{AirtableBase, CreateAirtableBase, UpdateAirtableBase},
+        test_utils::{create_test_project, setup_test_pool},
+    };
+
+    #[test]
+    fn parses_mapping_targets() {
+        assert_eq!(
+            AirtableTaskField::parse("due_date"),
+            Some(AirtableTaskField::DueDate)
+        );
+        assert_eq!(
+            AirtableTaskField::parse("custom_fields.region"),
+            Some(AirtableTaskField::Custom("region".into()))
+        );
+        assert_eq!(AirtableTaskField::parse("custom_fields."), None);
+        assert_eq!(AirtableTaskField::parse("assignee"), None);
+    }
+
+    #[tokio::test]
+    async fn only_enabled_mappings_on_enabled_bases_are_synced() {
+        let pool = setup_test_pool().await;
+        let project_id = create_test_project(&pool).await;
+        let connection = AirtableBase::create(
+            &pool,
+            CreateAirtableBase {
+                project_id,
+                airtable_base_id: "appTracker".into(),
+                airtable_base_name: Some("Production tracker".into()),
+                default_table_id: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let mapping = AirtableTableMapping::create(
+            &pool,
+            connection.id,
+            CreateAirtableTableMapping {
+                airtable_table_id: "tblShots".into(),
+                airtable_table_name: None,
+                board_id: None,
+                field_mappings: vec![AirtableFieldMapping {
+                    airtable_field: "Shot".into(),
+                    task_field: "title".into(),
+                    values: None,
+                }],
+                modified_field: None,
+                import_new_records: None,
+                push_new_tasks: Some(true),
+                delete_remote_records: None,
+                delete_local_tasks: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(mapping.import_new_records && mapping.push_new_tasks);
+        assert!(!mapping.delete_local_tasks);
+        assert_eq!(
+            mapping.field_mappings.0[0].target(),
+            Some(AirtableTaskField::Title)
+        );
+        assert_eq!(
+            AirtableTableMapping::find_enabled(&pool)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        AirtableBase::update(
+            &pool,
+            connection.id,
+            UpdateAirtableBase {
+                airtable_base_name: None,
+                sync_enabled: Some(false),
+                default_table_id: None,
+            },
+        )
+        .await
+        .unwrap();
+        assert!(
+            AirtableTableMapping::find_enabled(&pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let pulled_at = Utc::now();
+        AirtableTableMapping::record_sync(&pool, mapping.id, Some(pulled_at), None)
+            .await
+            .unwrap();
+        AirtableTableMapping::record_sync(&pool, mapping.id, None, Some("rate limited"))
+            .await
+            .unwrap();
+        let mapping = AirtableTableMapping::find_by_id(&pool, mapping.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(mapping.last_pulled_at, Some(pulled_at));
+        assert_eq!(mapping.last_sync_error.as_deref(), Some("rate limited"));
+    }
+}
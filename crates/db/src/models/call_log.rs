@@ -96,6 +96,8 @@ pub struct CallLog {
     pub price: Option<f64>,
     pub price_unit: Option<String>,
     pub metadata: Option<String>,
+    /// JSON array of follow-ups pulled from the call summary
+    pub action_items: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -133,7 +135,9 @@ pub struct UpdateCallLog {
     pub summary: Option<String>,
     pub sentiment: Option<String>,
     pub crm_contact_id: Option<Uuid>,
+    pub crm_deal_id: Option<Uuid>,
     pub price: Option<f64>,
+    pub action_items: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, TS)]
@@ -246,6 +250,8 @@ impl CallLog {
                 sentiment = COALESCE(?12, sentiment),
                 crm_contact_id = COALESCE(?13, crm_contact_id),
                 price = COALESCE(?14, price),
+                crm_deal_id = COALESCE(?15, crm_deal_id),
+                action_items = COALESCE(?16, action_items),
                 updated_at = datetime('now', 'subsec')
             WHERE id = ?1
             RETURNING *
@@ -265,11 +271,62 @@ impl CallLog {
         .bind(&data.sentiment)
         .bind(data.crm_contact_id)
         .bind(data.price)
+        .bind(data.crm_deal_id)
+        .bind(&data.action_items)
         .fetch_optional(pool)
         .await?
         .ok_or(CallLogError::NotFound)
     }
 
+    /// Calls not yet linked to a CRM contact, oldest first. Calls still
+    /// waiting on their transcript are left until the summary exists.
+    pub async fn find_unlinked(
+        pool: &SqlitePool,
+        created_after: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<Self>, CallLogError> {
+        let calls = sqlx::query_as::<_, CallLog>(
+            r#"
+            SELECT * FROM call_logs
+            WHERE crm_contact_id IS NULL
+              AND COALESCE(transcription_status, '') != 'pending'
+              AND (?1 IS NULL OR created_at > datetime(?1, 'subsec'))
+            ORDER BY created_at
+            LIMIT ?2
+            "#,
+        )
+        .bind(created_after)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(calls)
+    }
+
+    /// Finished calls still waiting on post-call processing that were last
+    /// touched before `updated_before`, oldest first
+    pub async fn find_pending_transcription(
+        pool: &SqlitePool,
+        updated_before: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Self>, CallLogError> {
+        let calls = sqlx::query_as::<_, CallLog>(
+            r#"
+            SELECT * FROM call_logs
+            WHERE transcription_status = 'pending'
+              AND updated_at < datetime(?1, 'subsec')
+            ORDER BY updated_at
+            LIMIT ?2
+            "#,
+        )
+        .bind(updated_before)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(calls)
+    }
+
     pub async fn get_stats(pool: &SqlitePool, project_id: Uuid) -> Result<CallStats, CallLogError> {
         #[derive(FromRow)]
         struct StatsRow {
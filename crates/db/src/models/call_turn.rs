@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum CallTurnError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum CallTurnSpeaker {
+    Caller,
+    Nora,
+}
+
+impl std::fmt::Display for CallTurnSpeaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallTurnSpeaker::Caller => write!(f, "caller"),
+            CallTurnSpeaker::Nora => write!(f, "nora"),
+        }
+    }
+}
+
+/// One utterance in a phone conversation handled by Nora
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CallTurn {
+    pub id: Uuid,
+    pub call_log_id: Uuid,
+    pub speaker: String,
+    pub content: String,
+    pub confidence: Option<f64>,
+    pub spoken_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateCallTurn {
+    pub call_log_id: Uuid,
+    pub speaker: CallTurnSpeaker,
+    pub content: String,
+    pub confidence: Option<f64>,
+    pub spoken_at: DateTime<Utc>,
+}
+
+impl CallTurn {
+    pub async fn create(pool: &SqlitePool, data: CreateCallTurn) -> Result<Self, CallTurnError> {
+        let turn = sqlx::query_as::<_, CallTurn>(
+            r#"
+            INSERT INTO call_turns (id, call_log_id, speaker, content, confidence, spoken_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(data.call_log_id)
+        .bind(data.speaker.to_string())
+        .bind(&data.content)
+        .bind(data.confidence)
+        .bind(data.spoken_at)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(turn)
+    }
+
+    /// Every turn of a call in the order it was spoken
+    pub async fn find_by_call(
+        pool: &SqlitePool,
+        call_log_id: Uuid,
+    ) -> Result<Vec<Self>, CallTurnError> {
+        let turns = sqlx::query_as::<_, CallTurn>(
+            r#"
+            SELECT * FROM call_turns
+            WHERE call_log_id = ?1
+            ORDER BY spoken_at, created_at
+            "#,
+        )
+        .bind(call_log_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(turns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::models::{
+        call_log::{CallDirection, CallLog, CallStatus, CreateCallLog, UpdateCallLog},
+        test_utils::{create_test_project, setup_test_pool},
+    };
+
+    #[tokio::test]
+    async fn turns_follow_the_call_and_hold_back_crm_linking() {
+        let pool = setup_test_pool().await;
+        let project_id = create_test_project(&pool).await;
+        let started = Utc::now() - Duration::minutes(5);
+
+        let call = CallLog::create(
+            &pool,
+            CreateCallLog {
+                project_id,
+                call_sid: format!("CA{}", Uuid::new_v4().simple()),
+                parent_call_sid: None,
+                account_sid: None,
+                from_number: "+447700900123".into(),
+                to_number: "+442071234567".into(),
+                from_formatted: None,
+                to_formatted: None,
+                caller_name: None,
+                direction: CallDirection::Inbound,
+                status: CallStatus::InProgress,
+                answered_by: None,
+                start_time: Some(started),
+            },
+        )
+        .await
+        .unwrap();
+
+        // Inserted out of order; reads come back in speaking order
+        for (offset, speaker, content) in [
+            (20, CallTurnSpeaker::Nora, "I'll send the quote over today."),
+            (
+                0,
+                CallTurnSpeaker::Caller,
+                "Can I get a quote for the retrofit?",
+            ),
+        ] {
+            CallTurn::create(
+                &pool,
+                CreateCallTurn {
+                    call_log_id: call.id,
+                    speaker,
+                    content: content.into(),
+                    confidence: (speaker == CallTurnSpeaker::Caller).then_some(0.92),
+                    spoken_at: started + Duration::seconds(offset),
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        let turns = CallTurn::find_by_call(&pool, call.id).await.unwrap();
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].speaker, "caller");
+        assert_eq!(turns[0].confidence, Some(0.92));
+        assert_eq!(turns[1].speaker, "nora");
+
+        CallLog::update(
+            &pool,
+            call.id,
+            UpdateCallLog {
+                status: Some(CallStatus::Completed),
+                transcription_status: Some("pending".into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(
+            CallLog::find_unlinked(&pool, None, 10)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let summarised = CallLog::update(
+            &pool,
+            call.id,
+            UpdateCallLog {
+                transcription_status: Some("completed".into()),
+                action_items: Some(r#"[{"title":"Send retrofit quote"}]"#.into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(summarised.action_items.is_some());
+        assert_eq!(
+            CallLog::find_unlinked(&pool, None, 10).await.unwrap().len(),
+            1
+        );
+
+        CallLog::delete(&pool, call.id).await.unwrap();
+        assert!(
+            CallTurn::find_by_call(&pool, call.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
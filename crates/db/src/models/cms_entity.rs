@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::{
+    cms_faq_item::CmsFaqItem, cms_page_section::CmsPageSection, cms_product::CmsProduct,
+    cms_site::CmsSite, cms_site_setting::CmsSiteSetting,
+};
+
+/// Kinds of CMS content that are drafted, published and revisioned
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Type, Serialize, Deserialize, TS)]
+#[sqlx(type_name = "cms_entity_type", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CmsEntityType {
+    Site,
+    Product,
+    FaqItem,
+    PageSection,
+    Setting,
+}
+
+/// A snapshot of one piece of CMS content. The cms_* tables hold the draft;
+/// published versions and revisions store this snapshot as JSON.
+#[derive(Debug, Clone)]
+pub enum CmsEntity {
+    Site(CmsSite),
+    Product(CmsProduct),
+    FaqItem(CmsFaqItem),
+    PageSection(CmsPageSection),
+    Setting(CmsSiteSetting),
+}
+
+impl CmsEntity {
+    pub fn entity_type(&self) -> CmsEntityType {
+        match self {
+            CmsEntity::Site(_) => CmsEntityType::Site,
+            CmsEntity::Product(_) => CmsEntityType::Product,
+            CmsEntity::FaqItem(_) => CmsEntityType::FaqItem,
+            CmsEntity::PageSection(_) => CmsEntityType::PageSection,
+            CmsEntity::Setting(_) => CmsEntityType::Setting,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        match self {
+            CmsEntity::Site(site) => site.id,
+            CmsEntity::Product(product) => product.id,
+            CmsEntity::FaqItem(item) => item.id,
+            CmsEntity::PageSection(section) => section.id,
+            CmsEntity::Setting(setting) => setting.id,
+        }
+    }
+
+    pub fn site_id(&self) -> Uuid {
+        match self {
+            CmsEntity::Site(site) => site.id,
+            CmsEntity::Product(product) => product.site_id,
+            CmsEntity::FaqItem(item) => item.site_id,
+            CmsEntity::PageSection(section) => section.site_id,
+            CmsEntity::Setting(setting) => setting.site_id,
+        }
+    }
+
+    /// Human-readable name for listings
+    pub fn label(&self) -> String {
+        match self {
+            CmsEntity::Site(site) => site.name.clone(),
+            CmsEntity::Product(product) => product.name.clone(),
+            CmsEntity::FaqItem(item) => item.question.clone(),
+            CmsEntity::PageSection(section) => {
+                format!("{}/{}", section.page_slug, section.section_key)
+            }
+            CmsEntity::Setting(setting) => setting.setting_key.clone(),
+        }
+    }
+
+    pub fn from_json(entity_type: CmsEntityType, data: &str) -> Result<Self, serde_json::Error> {
+        Ok(match entity_type {
+            CmsEntityType::Site => CmsEntity::Site(serde_json::from_str(data)?),
+            CmsEntityType::Product => CmsEntity::Product(serde_json::from_str(data)?),
+            CmsEntityType::FaqItem => CmsEntity::FaqItem(serde_json::from_str(data)?),
+            CmsEntityType::PageSection => CmsEntity::PageSection(serde_json::from_str(data)?),
+            CmsEntityType::Setting => CmsEntity::Setting(serde_json::from_str(data)?),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        self.to_value().to_string()
+    }
+
+    /// The entity as a JSON object. Two snapshots hold the same content when
+    /// their values are equal.
+    pub fn to_value(&self) -> Value {
+        let value = match self {
+            CmsEntity::Site(site) => serde_json::to_value(site),
+            CmsEntity::Product(product) => serde_json::to_value(product),
+            CmsEntity::FaqItem(item) => serde_json::to_value(item),
+            CmsEntity::PageSection(section) => serde_json::to_value(section),
+            CmsEntity::Setting(setting) => serde_json::to_value(setting),
+        };
+        value.expect("CMS models serialize to JSON")
+    }
+
+    /// Load the current draft of an entity
+    pub async fn find(
+        pool: &SqlitePool,
+        entity_type: CmsEntityType,
+        id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        Ok(match entity_type {
+            CmsEntityType::Site => CmsSite::find_by_id(pool, id).await?.map(CmsEntity::Site),
+            CmsEntityType::Product => CmsProduct::find_by_id(pool, id)
+                .await?
+                .map(CmsEntity::Product),
+            CmsEntityType::FaqItem => CmsFaqItem::find_by_id(pool, id)
+                .await?
+                .map(CmsEntity::FaqItem),
+            CmsEntityType::PageSection => CmsPageSection::find_by_id(pool, id)
+                .await?
+                .map(CmsEntity::PageSection),
+            CmsEntityType::Setting => CmsSiteSetting::find_by_id(pool, id)
+                .await?
+                .map(CmsEntity::Setting),
+        })
+    }
+
+    /// Load the drafts of a site and everything on it
+    pub async fn find_by_site(pool: &SqlitePool, site_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        let mut entities = Vec::new();
+        if let Some(site) = CmsSite::find_by_id(pool, site_id).await? {
+            entities.push(CmsEntity::Site(site));
+        }
+        entities.extend(
+            CmsProduct::find_by_site(pool, site_id)
+                .await?
+                .into_iter()
+                .map(CmsEntity::Product),
+        );
+        entities.extend(
+            CmsFaqItem::find_by_site(pool, site_id)
+                .await?
+                .into_iter()
+                .map(CmsEntity::FaqItem),
+        );
+        entities.extend(
+            CmsPageSection::find_by_site(pool, site_id)
+                .await?
+                .into_iter()
+                .map(CmsEntity::PageSection),
+        );
+        entities.extend(
+            CmsSiteSetting::find_by_site(pool, site_id)
+                .await?
+                .into_iter()
+                .map(CmsEntity::Setting),
+        );
+        Ok(entities)
+    }
+
+    /// Write this snapshot back as the draft, recreating the entity under its
+    /// original id if it was deleted
+    pub async fn save_draft(&self, pool: &SqlitePool) -> Result<Self, sqlx::Error> {
+        match self {
+            CmsEntity::Site(site) => {
+                sqlx::query(
+                    r#"INSERT INTO cms_sites (id, slug, name, domain, theme_config, is_active)
+                    VALUES ($1, $2, $3, $4, $5, $6)
+                    ON CONFLICT(id) DO UPDATE SET
+                        slug = excluded.slug,
+                        name = excluded.name,
+                        domain = excluded.domain,
+                        theme_config = excluded.theme_config,
+                        is_active = excluded.is_active,
+                        updated_at = datetime('now', 'subsec')"#,
+                )
+                .bind(site.id)
+                .bind(&site.slug)
+                .bind(&site.name)
+                .bind(&site.domain)
+                .bind(&site.theme_config)
+                .bind(site.is_active)
+                .execute(pool)
+                .await?;
+            }
+            CmsEntity::Product(product) => {
+                sqlx::query(
+                    r#"INSERT INTO cms_products (
+                        id, site_id, slug, name, short_description, long_description,
+                        price_cents, currency, stripe_price_id, image_url, gallery_images,
+                        specs, features, is_active, is_featured, stock_status, sort_order
+                    ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
+                    ON CONFLICT(id) DO UPDATE SET
+                        slug = excluded.slug,
+                        name = excluded.name,
+                        short_description = excluded.short_description,
+                        long_description = excluded.long_description,
+                        price_cents = excluded.price_cents,
+                        currency = excluded.currency,
+                        stripe_price_id = excluded.stripe_price_id,
+                        image_url = excluded.image_url,
+                        gallery_images = excluded.gallery_images,
+                        specs = excluded.specs,
+                        features = excluded.features,
+                        is_active = excluded.is_active,
+                        is_featured = excluded.is_featured,
+                        stock_status = excluded.stock_status,
+                        sort_order = excluded.sort_order,
+                        updated_at = datetime('now', 'subsec')"#,
+                )
+                .bind(product.id)
+                .bind(product.site_id)
+                .bind(&product.slug)
+                .bind(&product.name)
+                .bind(&product.short_description)
+                .bind(&product.long_description)
+                .bind(product.price_cents)
+                .bind(&product.currency)
+                .bind(&product.stripe_price_id)
+                .bind(&product.image_url)
+                .bind(&product.gallery_images)
+                .bind(&product.specs)
+                .bind(&product.features)
+                .bind(product.is_active)
+                .bind(product.is_featured)
+                .bind(&product.stock_status)
+                .bind(product.sort_order)
+                .execute(pool)
+                .await?;
+            }
+            CmsEntity::FaqItem(item) => {
+                sqlx::query(
+                    r#"INSERT INTO cms_faq_items (
+                        id, site_id, category, question, answer, sort_order, is_active
+                    ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    ON CONFLICT(id) DO UPDATE SET
+                        category = excluded.category,
+                        question = excluded.question,
+                        answer = excluded.answer,
+                        sort_order = excluded.sort_order,
+                        is_active = excluded.is_active,
+                        updated_at = datetime('now', 'subsec')"#,
+                )
+                .bind(item.id)
+                .bind(item.site_id)
+                .bind(&item.category)
+                .bind(&item.question)
+                .bind(&item.answer)
+                .bind(item.sort_order)
+                .bind(item.is_active)
+                .execute(pool)
+                .await?;
+            }
+            CmsEntity::PageSection(section) => {
+                sqlx::query(
+                    r#"INSERT INTO cms_page_sections (
+                        id, site_id, page_slug, section_key, content, sort_order, is_active
+                    ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                    ON CONFLICT(id) DO UPDATE SET
+                        page_slug = excluded.page_slug,
+                        section_key = excluded.section_key,
+                        content = excluded.content,
+                        sort_order = excluded.sort_order,
+                        is_active = excluded.is_active,
+                        updated_at = datetime('now', 'subsec')"#,
+                )
+                .bind(section.id)
+                .bind(section.site_id)
+                .bind(&section.page_slug)
+                .bind(&section.section_key)
+                .bind(&section.content)
+                .bind(section.sort_order)
+                .bind(section.is_active)
+                .execute(pool)
+                .await?;
+            }
+            CmsEntity::Setting(setting) => {
+                sqlx::query(
+                    r#"INSERT INTO cms_site_settings (id, site_id, setting_key, setting_value)
+                    VALUES ($1, $2, $3, $4)
+                    ON CONFLICT(id) DO UPDATE SET
+                        setting_key = excluded.setting_key,
+                        setting_value = excluded.setting_value,
+                        updated_at = datetime('now', 'subsec')"#,
+                )
+                .bind(setting.id)
+                .bind(setting.site_id)
+                .bind(&setting.setting_key)
+                .bind(&setting.setting_value)
+                .execute(pool)
+                .await?;
+            }
+        }
+
+        Self::find(pool, self.entity_type(), self.id())
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Serialize, de::DeserializeOwned};
+use sqlx::{FromRow, SqlitePool};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::{
+    cms_entity::{CmsEntity, CmsEntityType},
+    cms_faq_item::CmsFaqItem,
+    cms_page_section::CmsPageSection,
+    cms_product::CmsProduct,
+    cms_revision::{CmsRevision, CmsRevisionAction},
+    cms_site::CmsSite,
+    cms_site_setting::CmsSiteSetting,
+};
+
+/// The live version of a CMS entity, as served by the public site
+#[derive(Debug, Clone, FromRow)]
+pub struct CmsPublishedEntity {
+    pub site_id: Uuid,
+    pub entity_type: CmsEntityType,
+    pub entity_id: Uuid,
+    pub data: String, // JSON object
+    pub revision_id: Option<Uuid>,
+    pub published_at: DateTime<Utc>,
+}
+
+/// A change to what is live
+#[derive(Debug, Clone)]
+pub enum CmsPublication {
+    /// Make this snapshot the live version
+    Publish(CmsEntity),
+    /// Take the entity offline; holds the version that was live
+    Unpublish(CmsEntity),
+}
+
+/// Everything a site shows publicly, filtered to active content and in
+/// display order
+#[derive(Debug, Clone, Serialize, TS)]
+pub struct CmsPublishedContent {
+    pub site: CmsSite,
+    pub settings: HashMap<String, String>,
+    pub products: Vec<CmsProduct>,
+    pub faq_items: Vec<CmsFaqItem>,
+    pub page_sections: Vec<CmsPageSection>,
+}
+
+impl CmsPublishedEntity {
+    pub fn entity(&self) -> Result<CmsEntity, serde_json::Error> {
+        CmsEntity::from_json(self.entity_type, &self.data)
+    }
+
+    pub async fn find(
+        pool: &SqlitePool,
+        entity_type: CmsEntityType,
+        entity_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsPublishedEntity>(
+            r#"SELECT * FROM cms_published_entities WHERE entity_type = $1 AND entity_id = $2"#,
+        )
+        .bind(entity_type)
+        .bind(entity_id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn find_by_site(pool: &SqlitePool, site_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsPublishedEntity>(
+            r#"SELECT * FROM cms_published_entities WHERE site_id = $1"#,
+        )
+        .bind(site_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Published entities of one type, decoded into their model
+    pub async fn find_typed<T: DeserializeOwned>(
+        pool: &SqlitePool,
+        site_id: Uuid,
+        entity_type: CmsEntityType,
+    ) -> Result<Vec<T>, sqlx::Error> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"SELECT data FROM cms_published_entities WHERE site_id = $1 AND entity_type = $2"#,
+        )
+        .bind(site_id)
+        .bind(entity_type)
+        .fetch_all(pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(data,)| serde_json::from_str(&data).map_err(|e| sqlx::Error::Decode(e.into())))
+            .collect()
+    }
+
+    /// Apply publications in one transaction, recording a revision for each
+    pub async fn apply(
+        pool: &SqlitePool,
+        publications: &[CmsPublication],
+        user_id: Option<Uuid>,
+    ) -> Result<Vec<CmsRevision>, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        let mut revisions = Vec::with_capacity(publications.len());
+
+        for publication in publications {
+            let revision = match publication {
+                CmsPublication::Publish(entity) => {
+                    let revision =
+                        CmsRevision::insert(&mut tx, entity, CmsRevisionAction::Published, user_id)
+                            .await?;
+                    sqlx::query(
+                        r#"INSERT INTO cms_published_entities (site_id, entity_type, entity_id, data, revision_id)
+                        VALUES ($1, $2, $3, $4, $5)
+                        ON CONFLICT(entity_type, entity_id) DO UPDATE SET
+                            data = excluded.data,
+                            revision_id = excluded.revision_id,
+                            published_at = datetime('now', 'subsec')"#,
+                    )
+                    .bind(entity.site_id())
+                    .bind(entity.entity_type())
+                    .bind(entity.id())
+                    .bind(&revision.data)
+                    .bind(revision.id)
+                    .execute(&mut *tx)
+                    .await?;
+                    revision
+                }
+                CmsPublication::Unpublish(entity) => {
+                    sqlx::query(
+                        r#"DELETE FROM cms_published_entities WHERE entity_type = $1 AND entity_id = $2"#,
+                    )
+                    .bind(entity.entity_type())
+                    .bind(entity.id())
+                    .execute(&mut *tx)
+                    .await?;
+                    CmsRevision::insert(&mut tx, entity, CmsRevisionAction::Unpublished, user_id)
+                        .await?
+                }
+            };
+            revisions.push(revision);
+        }
+
+        tx.commit().await?;
+        Ok(revisions)
+    }
+}
+
+impl CmsPublishedContent {
+    /// Load the published content of a site, `None` if the site itself has
+    /// never been published
+    pub async fn load(pool: &SqlitePool, site_id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        let Some(site) =
+            CmsPublishedEntity::find_typed::<CmsSite>(pool, site_id, CmsEntityType::Site)
+                .await?
+                .pop()
+        else {
+            return Ok(None);
+        };
+
+        let settings =
+            CmsPublishedEntity::find_typed::<CmsSiteSetting>(pool, site_id, CmsEntityType::Setting)
+                .await?
+                .into_iter()
+                .map(|setting| (setting.setting_key, setting.setting_value))
+                .collect();
+
+        let mut products: Vec<CmsProduct> =
+            CmsPublishedEntity::find_typed(pool, site_id, CmsEntityType::Product).await?;
+        products.retain(|product| product.is_active);
+        products.sort_by(|a, b| (a.sort_order, &a.name).cmp(&(b.sort_order, &b.name)));
+
+        let mut faq_items: Vec<CmsFaqItem> =
+            CmsPublishedEntity::find_typed(pool, site_id, CmsEntityType::FaqItem).await?;
+        faq_items.retain(|item| item.is_active);
+        faq_items.sort_by_key(|item| (item.sort_order, item.created_at));
+
+        let mut page_sections: Vec<CmsPageSection> =
+            CmsPublishedEntity::find_typed(pool, site_id, CmsEntityType::PageSection).await?;
+        page_sections.retain(|section| section.is_active);
+        page_sections.sort_by(|a, b| {
+            (&a.page_slug, a.sort_order, &a.section_key).cmp(&(
+                &b.page_slug,
+                b.sort_order,
+                &b.section_key,
+            ))
+        });
+
+        Ok(Some(Self {
+            site,
+            settings,
+            products,
+            faq_items,
+            page_sections,
+        }))
+    }
+
+    pub fn product(&self, slug: &str) -> Option<&CmsProduct> {
+        self.products.iter().find(|product| product.slug == slug)
+    }
+
+    pub fn page_sections(&self, page_slug: &str) -> Vec<CmsPageSection> {
+        self.page_sections
+            .iter()
+            .filter(|section| section.page_slug == page_slug)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        cms_product::{CreateCmsProduct, UpdateCmsProduct},
+        test_utils::{create_test_cms_site, setup_test_pool},
+    };
+
+    fn product(slug: &str) -> CreateCmsProduct {
+        CreateCmsProduct {
+            slug: slug.to_string(),
+            name: slug.to_uppercase(),
+            short_description: None,
+            long_description: None,
+            price_cents: 1000,
+            currency: None,
+            stripe_price_id: None,
+            image_url: None,
+            gallery_images: None,
+            specs: None,
+            features: None,
+            is_featured: None,
+            stock_status: None,
+            sort_order: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn public_content_only_changes_when_published() {
+        let pool = setup_test_pool().await;
+        let site = create_test_cms_site(&pool).await;
+        let router = CmsProduct::create(&pool, site.id, &product("router"))
+            .await
+            .unwrap();
+        assert!(
+            CmsPublishedContent::load(&pool, site.id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let revisions = CmsPublishedEntity::apply(
+            &pool,
+            &[
+                CmsPublication::Publish(CmsEntity::Site(site.clone())),
+                CmsPublication::Publish(CmsEntity::Product(router.clone())),
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(revisions.len(), 2);
+
+        // A draft edit does not reach the public site
+        CmsProduct::update(
+            &pool,
+            router.id,
+            &UpdateCmsProduct {
+                slug: None,
+                name: Some("Broken".to_string()),
+                short_description: None,
+                long_description: None,
+                price_cents: None,
+                currency: None,
+                stripe_price_id: None,
+                image_url: None,
+                gallery_images: None,
+                specs: None,
+                features: None,
+                is_active: None,
+                is_featured: None,
+                stock_status: None,
+                sort_order: None,
+            },
+        )
+        .await
+        .unwrap();
+        let content = CmsPublishedContent::load(&pool, site.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(content.product("router").unwrap().name, "ROUTER");
+
+        CmsPublishedEntity::apply(
+            &pool,
+            &[CmsPublication::Unpublish(CmsEntity::Product(
+                router.clone(),
+            ))],
+            None,
+        )
+        .await
+        .unwrap();
+        let content = CmsPublishedContent::load(&pool, site.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(content.products.is_empty());
+        let history = CmsRevision::find_by_entity(&pool, CmsEntityType::Product, router.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            history.iter().map(|r| r.action).collect::<Vec<_>>(),
+            vec![CmsRevisionAction::Unpublished, CmsRevisionAction::Published]
+        );
+    }
+}
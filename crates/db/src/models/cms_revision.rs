@@ -0,0 +1,278 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, SqliteConnection, SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::cms_entity::{CmsEntity, CmsEntityType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Type, Serialize, Deserialize, TS)]
+#[sqlx(type_name = "cms_revision_action", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CmsRevisionAction {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+    Published,
+    Unpublished,
+}
+
+/// One entry in the history of a CMS entity. `data` is the entity as it was
+/// after the change, or just before it for deletes.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+pub struct CmsRevision {
+    pub id: Uuid,
+    pub site_id: Uuid,
+    pub entity_type: CmsEntityType,
+    pub entity_id: Uuid,
+    pub version: i64,
+    pub action: CmsRevisionAction,
+    pub data: String, // JSON object
+    pub user_id: Option<Uuid>,
+    #[ts(type = "Date")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A top-level field that differs between two versions of an entity
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+pub struct CmsFieldChange {
+    pub field: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl CmsRevision {
+    pub fn entity(&self) -> Result<CmsEntity, serde_json::Error> {
+        CmsEntity::from_json(self.entity_type, &self.data)
+    }
+
+    /// The entity after this revision, `None` once it was deleted
+    pub fn state(&self) -> Result<Option<Value>, serde_json::Error> {
+        if self.action == CmsRevisionAction::Deleted {
+            return Ok(None);
+        }
+        serde_json::from_str(&self.data).map(Some)
+    }
+
+    pub async fn record(
+        pool: &SqlitePool,
+        entity: &CmsEntity,
+        action: CmsRevisionAction,
+        user_id: Option<Uuid>,
+    ) -> Result<Self, sqlx::Error> {
+        let mut conn = pool.acquire().await?;
+        Self::insert(&mut conn, entity, action, user_id).await
+    }
+
+    /// Record a revision on an open connection, so it can share a
+    /// transaction with the change it describes
+    pub(crate) async fn insert(
+        conn: &mut SqliteConnection,
+        entity: &CmsEntity,
+        action: CmsRevisionAction,
+        user_id: Option<Uuid>,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as::<_, CmsRevision>(
+            r#"INSERT INTO cms_revisions (id, site_id, entity_type, entity_id, version, action, data, user_id)
+            SELECT $1, $2, $3, $4, COALESCE(MAX(version), 0) + 1, $5, $6, $7
+            FROM cms_revisions
+            WHERE entity_type = $3 AND entity_id = $4
+            RETURNING *"#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(entity.site_id())
+        .bind(entity.entity_type())
+        .bind(entity.id())
+        .bind(action)
+        .bind(entity.to_json())
+        .bind(user_id)
+        .fetch_one(conn)
+        .await
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsRevision>(r#"SELECT * FROM cms_revisions WHERE id = $1"#)
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    /// Most recent revisions across a site
+    pub async fn find_by_site(
+        pool: &SqlitePool,
+        site_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsRevision>(
+            r#"SELECT * FROM cms_revisions
+            WHERE site_id = $1
+            ORDER BY created_at DESC, version DESC
+            LIMIT $2"#,
+        )
+        .bind(site_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_by_entity(
+        pool: &SqlitePool,
+        entity_type: CmsEntityType,
+        entity_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsRevision>(
+            r#"SELECT * FROM cms_revisions
+            WHERE entity_type = $1 AND entity_id = $2
+            ORDER BY version DESC"#,
+        )
+        .bind(entity_type)
+        .bind(entity_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// The revision of the same entity just before this one
+    pub async fn find_previous(&self, pool: &SqlitePool) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsRevision>(
+            r#"SELECT * FROM cms_revisions
+            WHERE entity_type = $1 AND entity_id = $2 AND version < $3
+            ORDER BY version DESC
+            LIMIT 1"#,
+        )
+        .bind(self.entity_type)
+        .bind(self.entity_id)
+        .bind(self.version)
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+/// Field-by-field differences between two versions of an entity. Either side
+/// may be missing (not yet created, or deleted). `updated_at` changes with
+/// every save and is left out.
+pub fn diff_fields(before: Option<&Value>, after: Option<&Value>) -> Vec<CmsFieldChange> {
+    let empty = serde_json::Map::new();
+    let before_fields = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after_fields = after.and_then(Value::as_object).unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before_fields.keys().chain(after_fields.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter(|field| field.as_str() != "updated_at")
+        .filter_map(|field| {
+            let old = before_fields.get(field);
+            let new = after_fields.get(field);
+            (old != new).then(|| CmsFieldChange {
+                field: field.clone(),
+                before: old.cloned(),
+                after: new.cloned(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::{
+        cms_faq_item::{CmsFaqItem, CreateCmsFaqItem},
+        test_utils::{create_test_cms_site, setup_test_pool},
+    };
+
+    #[tokio::test]
+    async fn revisions_are_numbered_per_entity() {
+        let pool = setup_test_pool().await;
+        let site = create_test_cms_site(&pool).await;
+        let first = CmsFaqItem::create(
+            &pool,
+            site.id,
+            &CreateCmsFaqItem {
+                category: None,
+                question: "Do you ship abroad?".to_string(),
+                answer: "Yes".to_string(),
+                sort_order: None,
+            },
+        )
+        .await
+        .unwrap();
+        let second = CmsFaqItem::create(
+            &pool,
+            site.id,
+            &CreateCmsFaqItem {
+                category: None,
+                question: "Is there a warranty?".to_string(),
+                answer: "Two years".to_string(),
+                sort_order: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let created = CmsRevision::record(
+            &pool,
+            &CmsEntity::FaqItem(first.clone()),
+            CmsRevisionAction::Created,
+            None,
+        )
+        .await
+        .unwrap();
+        let other = CmsRevision::record(
+            &pool,
+            &CmsEntity::FaqItem(second),
+            CmsRevisionAction::Created,
+            None,
+        )
+        .await
+        .unwrap();
+        let mut edited = first.clone();
+        edited.answer = "Yes, to most countries".to_string();
+        let updated = CmsRevision::record(
+            &pool,
+            &CmsEntity::FaqItem(edited),
+            CmsRevisionAction::Updated,
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!((created.version, other.version, updated.version), (1, 1, 2));
+        let previous = updated.find_previous(&pool).await.unwrap().unwrap();
+        assert_eq!(previous.id, created.id);
+        let CmsEntity::FaqItem(restored) = previous.entity().unwrap() else {
+            panic!("expected an FAQ item");
+        };
+        assert_eq!(restored.answer, "Yes");
+        assert_eq!(restored.id, first.id);
+    }
+
+    #[test]
+    fn diff_reports_changed_fields_only() {
+        let before = json!({"name": "Router", "price_cents": 100, "updated_at": "a"});
+        let after = json!({"name": "Router", "price_cents": 120, "updated_at": "b", "sku": "R1"});
+
+        assert_eq!(
+            diff_fields(Some(&before), Some(&after)),
+            vec![
+                CmsFieldChange {
+                    field: "price_cents".to_string(),
+                    before: Some(json!(100)),
+                    after: Some(json!(120)),
+                },
+                CmsFieldChange {
+                    field: "sku".to_string(),
+                    before: None,
+                    after: Some(json!("R1")),
+                },
+            ]
+        );
+        assert_eq!(diff_fields(Some(&before), None).len(), 2);
+        assert!(diff_fields(Some(&after), Some(&after)).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::cms_entity::CmsEntityType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Type, Serialize, Deserialize, TS)]
+#[sqlx(type_name = "cms_schedule_status", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum CmsScheduleStatus {
+    Pending,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// A publish to run at a later time. Without an entity the whole site is
+/// published. The draft is taken as it is when the publish runs.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+pub struct CmsScheduledPublication {
+    pub id: Uuid,
+    pub site_id: Uuid,
+    pub entity_type: Option<CmsEntityType>,
+    pub entity_id: Option<Uuid>,
+    #[ts(type = "Date")]
+    pub publish_at: DateTime<Utc>,
+    pub status: CmsScheduleStatus,
+    pub error: Option<String>,
+    pub user_id: Option<Uuid>,
+    #[ts(type = "Date")]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date | null")]
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+pub struct CreateCmsScheduledPublication {
+    pub entity_type: Option<CmsEntityType>,
+    pub entity_id: Option<Uuid>,
+    #[ts(type = "Date")]
+    pub publish_at: DateTime<Utc>,
+}
+
+impl CmsScheduledPublication {
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsScheduledPublication>(
+            r#"SELECT * FROM cms_scheduled_publications WHERE id = $1"#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
+    pub async fn find_by_site(pool: &SqlitePool, site_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsScheduledPublication>(
+            r#"SELECT * FROM cms_scheduled_publications
+            WHERE site_id = $1
+            ORDER BY publish_at DESC"#,
+        )
+        .bind(site_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Pending publishes whose time has come, oldest first
+    pub async fn find_due(pool: &SqlitePool, now: DateTime<Utc>) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsScheduledPublication>(
+            r#"SELECT * FROM cms_scheduled_publications
+            WHERE status = 'pending' AND publish_at <= $1
+            ORDER BY publish_at ASC"#,
+        )
+        .bind(now)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn create(
+        pool: &SqlitePool,
+        site_id: Uuid,
+        data: &CreateCmsScheduledPublication,
+        user_id: Option<Uuid>,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as::<_, CmsScheduledPublication>(
+            r#"INSERT INTO cms_scheduled_publications (id, site_id, entity_type, entity_id, publish_at, user_id)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *"#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(site_id)
+        .bind(data.entity_type)
+        .bind(data.entity_id)
+        .bind(data.publish_at)
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Record how a due publish went
+    pub async fn finish(
+        pool: &SqlitePool,
+        id: Uuid,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        let status = if error.is_some() {
+            CmsScheduleStatus::Failed
+        } else {
+            CmsScheduleStatus::Completed
+        };
+        sqlx::query(
+            r#"UPDATE cms_scheduled_publications
+            SET status = $2, error = $3, completed_at = datetime('now', 'subsec')
+            WHERE id = $1"#,
+        )
+        .bind(id)
+        .bind(status)
+        .bind(error)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Cancel a publish that has not run yet
+    pub async fn cancel(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"UPDATE cms_scheduled_publications
+            SET status = 'cancelled', completed_at = datetime('now', 'subsec')
+            WHERE id = $1 AND status = 'pending'"#,
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -1,6 +1,6 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::SqlitePool;
+use sqlx::{FromRow, SqlitePool};
 use thiserror::Error;
 use ts_rs::TS;
 use uuid::Uuid;
@@ -13,7 +13,7 @@ pub enum CmsSiteSettingError {
     NotFound,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS, FromRow)]
 pub struct CmsSiteSetting {
     pub id: Uuid,
     pub site_id: Uuid,
@@ -51,6 +51,17 @@ impl CmsSiteSetting {
         .await
     }
 
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsSiteSetting>(
+            r#"SELECT id, site_id, setting_key, setting_value, created_at, updated_at
+            FROM cms_site_settings
+            WHERE id = $1"#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+    }
+
     pub async fn find_by_key(
         pool: &SqlitePool,
         site_id: Uuid,
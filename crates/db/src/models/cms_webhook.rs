@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// An endpoint that is told whenever a site publishes
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+pub struct CmsWebhook {
+    pub id: Uuid,
+    pub site_id: Uuid,
+    pub url: String,
+    /// Never returned by the API
+    #[serde(skip_serializing)]
+    #[ts(skip)]
+    pub secret: Option<String>,
+    pub is_active: bool,
+    #[ts(type = "Date | null")]
+    pub last_delivered_at: Option<DateTime<Utc>>,
+    pub last_status_code: Option<i64>,
+    pub last_error: Option<String>,
+    #[ts(type = "Date")]
+    pub created_at: DateTime<Utc>,
+    #[ts(type = "Date")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+pub struct CreateCmsWebhook {
+    pub url: String,
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+pub struct UpdateCmsWebhook {
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+impl CmsWebhook {
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsWebhook>(r#"SELECT * FROM cms_webhooks WHERE id = $1"#)
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn find_by_site(pool: &SqlitePool, site_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsWebhook>(
+            r#"SELECT * FROM cms_webhooks WHERE site_id = $1 ORDER BY created_at ASC"#,
+        )
+        .bind(site_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn find_active_by_site(
+        pool: &SqlitePool,
+        site_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as::<_, CmsWebhook>(
+            r#"SELECT * FROM cms_webhooks
+            WHERE site_id = $1 AND is_active = 1
+            ORDER BY created_at ASC"#,
+        )
+        .bind(site_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn create(
+        pool: &SqlitePool,
+        site_id: Uuid,
+        data: &CreateCmsWebhook,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as::<_, CmsWebhook>(
+            r#"INSERT INTO cms_webhooks (id, site_id, url, secret)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *"#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(site_id)
+        .bind(&data.url)
+        .bind(&data.secret)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn update(
+        pool: &SqlitePool,
+        id: Uuid,
+        data: &UpdateCmsWebhook,
+    ) -> Result<Self, sqlx::Error> {
+        let current = Self::find_by_id(pool, id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        let url = data.url.as_ref().unwrap_or(&current.url);
+        let secret = data.secret.as_ref().or(current.secret.as_ref());
+        let is_active = data.is_active.unwrap_or(current.is_active);
+
+        sqlx::query_as::<_, CmsWebhook>(
+            r#"UPDATE cms_webhooks
+            SET url = $2, secret = $3, is_active = $4, updated_at = datetime('now', 'subsec')
+            WHERE id = $1
+            RETURNING *"#,
+        )
+        .bind(id)
+        .bind(url)
+        .bind(secret)
+        .bind(is_active)
+        .fetch_one(pool)
+        .await
+    }
+
+    /// Record the outcome of the latest delivery
+    pub async fn record_delivery(
+        pool: &SqlitePool,
+        id: Uuid,
+        status_code: Option<i64>,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"UPDATE cms_webhooks
+            SET last_delivered_at = datetime('now', 'subsec'), last_status_code = $2, last_error = $3
+            WHERE id = $1"#,
+        )
+        .bind(id)
+        .bind(status_code)
+        .bind(error)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(r#"DELETE FROM cms_webhooks WHERE id = $1"#)
+            .bind(id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
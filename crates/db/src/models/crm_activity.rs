@@ -151,6 +151,8 @@ pub struct CreateCrmActivity {
     pub performed_by_agent_id: Option<Uuid>,
     pub metadata: Option<serde_json::Value>,
     pub duration_minutes: Option<i32>,
+    /// When the interaction happened; defaults to now
+    pub activity_at: Option<DateTime<Utc>>,
 }
 
 impl CrmActivity {
@@ -171,7 +173,10 @@ impl CrmActivity {
                 task_id, performed_by_user, performed_by_agent_id, metadata,
                 duration_minutes, activity_at
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, datetime('now', 'subsec'))
+            VALUES (
+                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15,
+                COALESCE(datetime(?16, 'subsec'), datetime('now', 'subsec'))
+            )
             RETURNING *
             "#,
         )
@@ -190,6 +195,7 @@ impl CrmActivity {
         .bind(data.performed_by_agent_id)
         .bind(metadata)
         .bind(data.duration_minutes)
+        .bind(data.activity_at)
         .fetch_one(pool)
         .await?;
 
@@ -207,6 +213,21 @@ impl CrmActivity {
         .ok_or(CrmActivityError::NotFound)
     }
 
+    /// Find the activities logged for an email message
+    pub async fn find_by_email_message(
+        pool: &SqlitePool,
+        email_message_id: Uuid,
+    ) -> Result<Vec<Self>, CrmActivityError> {
+        let activities = sqlx::query_as::<_, CrmActivity>(
+            r#"SELECT * FROM crm_activities WHERE email_message_id = ?1"#,
+        )
+        .bind(email_message_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(activities)
+    }
+
     /// Find all activities for a contact
     pub async fn find_by_contact(
         pool: &SqlitePool,
@@ -230,6 +251,27 @@ impl CrmActivity {
         Ok(activities)
     }
 
+    /// Activities of a contact since a point in time, newest first
+    pub async fn find_by_contact_since(
+        pool: &SqlitePool,
+        contact_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Self>, CrmActivityError> {
+        let activities = sqlx::query_as::<_, CrmActivity>(
+            r#"
+            SELECT * FROM crm_activities
+            WHERE crm_contact_id = ?1 AND activity_at >= datetime(?2, 'subsec')
+            ORDER BY activity_at DESC
+            "#,
+        )
+        .bind(contact_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(activities)
+    }
+
     /// Find all activities for a project
     pub async fn find_by_project(
         pool: &SqlitePool,
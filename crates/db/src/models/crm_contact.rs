@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, SqlitePool};
@@ -142,6 +144,8 @@ pub struct CrmContact {
     pub total_revenue: f64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Manual points on top of the scoring engine's result
+    pub lead_score_adjustment: i32,
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -216,6 +220,56 @@ pub struct ContactSearchParams {
 }
 
 impl CrmContact {
+    /// Non-empty text fields by column name, with custom fields as
+    /// `custom_fields.<key>`
+    pub fn field_values(&self) -> BTreeMap<String, String> {
+        let columns = [
+            ("first_name", &self.first_name),
+            ("last_name", &self.last_name),
+            ("full_name", &self.full_name),
+            ("email", &self.email),
+            ("phone", &self.phone),
+            ("mobile", &self.mobile),
+            ("company_name", &self.company_name),
+            ("job_title", &self.job_title),
+            ("department", &self.department),
+            ("linkedin_url", &self.linkedin_url),
+            ("twitter_handle", &self.twitter_handle),
+            ("website", &self.website),
+            ("source", &self.source),
+            ("tags", &self.tags),
+            ("city", &self.city),
+            ("state", &self.state),
+            ("country", &self.country),
+        ];
+        let mut fields: BTreeMap<String, String> = columns
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let value = value.as_deref()?.trim();
+                (!value.is_empty()).then(|| (name.to_string(), value.to_string()))
+            })
+            .collect();
+        fields.insert("lifecycle_stage".to_string(), self.lifecycle_stage.clone());
+
+        let custom: serde_json::Map<String, serde_json::Value> = self
+            .custom_fields
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default();
+        for (key, value) in custom {
+            let value = match value {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            if !value.trim().is_empty() {
+                fields.insert(format!("custom_fields.{}", key), value);
+            }
+        }
+
+        fields
+    }
+
     fn compute_full_name(first: &Option<String>, last: &Option<String>) -> Option<String> {
         match (first, last) {
             (Some(f), Some(l)) => Some(format!("{} {}", f.trim(), l.trim())),
@@ -304,6 +358,33 @@ impl CrmContact {
         Ok(contact)
     }
 
+    /// Find a contact whose phone or mobile matches an E.164 number, ignoring
+    /// spaces, dashes, dots and parentheses in the stored value
+    pub async fn find_by_phone(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        phone: &str,
+    ) -> Result<Option<Self>, CrmContactError> {
+        let contact = sqlx::query_as::<_, CrmContact>(
+            r#"
+            SELECT * FROM crm_contacts
+            WHERE project_id = ?1
+              AND (
+                REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(phone, ' ', ''), '-', ''), '.', ''), '(', ''), ')', '') = ?2
+                OR REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(mobile, ' ', ''), '-', ''), '.', ''), '(', ''), ')', '') = ?2
+              )
+            ORDER BY created_at
+            LIMIT 1
+            "#,
+        )
+        .bind(project_id)
+        .bind(phone)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(contact)
+    }
+
     pub async fn find_by_project(
         pool: &SqlitePool,
         project_id: Uuid,
@@ -562,6 +643,56 @@ impl CrmContact {
         Ok(())
     }
 
+    /// Like `record_contact_made`, but for an interaction at a given time.
+    /// Timestamps never move backwards, so older messages can be replayed.
+    pub async fn record_contact_made_at(
+        pool: &SqlitePool,
+        id: Uuid,
+        at: DateTime<Utc>,
+        is_email: bool,
+    ) -> Result<(), CrmContactError> {
+        sqlx::query(
+            r#"
+            UPDATE crm_contacts SET
+                last_contacted_at = MAX(COALESCE(last_contacted_at, ''), datetime(?2, 'subsec')),
+                last_activity_at = MAX(COALESCE(last_activity_at, ''), datetime(?2, 'subsec')),
+                email_count = email_count + ?3,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(at)
+        .bind(if is_email { 1 } else { 0 })
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Like `record_reply_received`, but for an interaction at a given time
+    pub async fn record_reply_received_at(
+        pool: &SqlitePool,
+        id: Uuid,
+        at: DateTime<Utc>,
+    ) -> Result<(), CrmContactError> {
+        sqlx::query(
+            r#"
+            UPDATE crm_contacts SET
+                last_replied_at = MAX(COALESCE(last_replied_at, ''), datetime(?2, 'subsec')),
+                last_activity_at = MAX(COALESCE(last_activity_at, ''), datetime(?2, 'subsec')),
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(at)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn update_lead_score(
         pool: &SqlitePool,
         id: Uuid,
@@ -571,6 +702,7 @@ impl CrmContact {
             r#"
             UPDATE crm_contacts SET
                 lead_score = MAX(0, lead_score + ?2),
+                lead_score_adjustment = lead_score_adjustment + ?2,
                 updated_at = datetime('now', 'subsec')
             WHERE id = ?1
             "#,
@@ -583,6 +715,43 @@ impl CrmContact {
         Ok(())
     }
 
+    /// Store a score computed by the lead scoring engine
+    pub async fn set_lead_score(
+        pool: &SqlitePool,
+        id: Uuid,
+        score: i32,
+    ) -> Result<(), CrmContactError> {
+        sqlx::query(
+            r#"
+            UPDATE crm_contacts SET
+                lead_score = ?2,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(score)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Ids of every contact in a project
+    pub async fn find_ids_by_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Vec<Uuid>, CrmContactError> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"SELECT id FROM crm_contacts WHERE project_id = ?1 ORDER BY created_at"#,
+        )
+        .bind(project_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(ids)
+    }
+
     pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<(), CrmContactError> {
         let result = sqlx::query(r#"DELETE FROM crm_contacts WHERE id = ?1"#)
             .bind(id)
@@ -642,4 +811,47 @@ impl CrmContact {
             gmail_contact_id: None,
         }).await
     }
+
+    /// Find or create a contact from an E.164 phone number
+    pub async fn find_or_create_from_phone(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        phone: &str,
+        name: Option<&str>,
+        source: ContactSource,
+    ) -> Result<Self, CrmContactError> {
+        if let Some(existing) = Self::find_by_phone(pool, project_id, phone).await? {
+            return Ok(existing);
+        }
+
+        let (first_name, last_name) = match name.map(str::trim).filter(|n| !n.is_empty()) {
+            Some(n) => match n.split_once(' ') {
+                Some((first, last)) => (Some(first.to_string()), Some(last.trim().to_string())),
+                None => (Some(n.to_string()), None),
+            },
+            None => (None, None),
+        };
+
+        Self::create(pool, CreateCrmContact {
+            project_id,
+            first_name,
+            last_name,
+            email: None,
+            phone: Some(phone.to_string()),
+            mobile: None,
+            avatar_url: None,
+            company_name: None,
+            job_title: None,
+            department: None,
+            linkedin_url: None,
+            twitter_handle: None,
+            website: None,
+            source: Some(source),
+            lifecycle_stage: Some(LifecycleStage::Lead),
+            tags: None,
+            custom_fields: None,
+            zoho_contact_id: None,
+            gmail_contact_id: None,
+        }).await
+    }
 }
@@ -243,6 +243,51 @@ impl CrmDeal {
         Ok(deals)
     }
 
+    /// Deals of a contact that are not closed yet, most recently active first
+    pub async fn find_open_by_contact(
+        pool: &SqlitePool,
+        contact_id: Uuid,
+    ) -> Result<Vec<Self>, CrmDealError> {
+        let deals = sqlx::query_as::<_, CrmDeal>(
+            r#"
+            SELECT d.* FROM crm_deals d
+            LEFT JOIN crm_pipeline_stages s ON s.id = d.crm_stage_id
+            WHERE d.crm_contact_id = ?1
+              AND d.actual_close_date IS NULL
+              AND d.stage NOT IN ('closed_won', 'closed_lost')
+              AND COALESCE(s.is_closed, 0) = 0
+            ORDER BY COALESCE(d.last_activity_at, d.updated_at) DESC
+            "#,
+        )
+        .bind(contact_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(deals)
+    }
+
+    /// Bump `last_activity_at` without moving it backwards
+    pub async fn record_activity(
+        pool: &SqlitePool,
+        id: Uuid,
+        at: DateTime<Utc>,
+    ) -> Result<(), CrmDealError> {
+        sqlx::query(
+            r#"
+            UPDATE crm_deals SET
+                last_activity_at = MAX(COALESCE(last_activity_at, ''), datetime(?2, 'subsec')),
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(at)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
     pub async fn update(
         pool: &SqlitePool,
         id: Uuid,
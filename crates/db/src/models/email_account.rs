@@ -89,6 +89,10 @@ pub struct EmailAccount {
     pub smtp_host: Option<String>,
     pub smtp_port: Option<i32>,
     pub use_ssl: Option<i32>,
+    pub imap_username: Option<String>,
+    #[serde(skip_serializing)]
+    #[ts(skip)]
+    pub imap_password: Option<String>,
     pub granted_scopes: Option<String>,
     pub storage_used_bytes: Option<i64>,
     pub storage_total_bytes: Option<i64>,
@@ -103,6 +107,13 @@ pub struct EmailAccount {
     pub signature: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Sequence mail is only sent between these `HH:MM` times on weekdays,
+    /// in the account's local time
+    pub send_window_start: String,
+    pub send_window_end: String,
+    pub send_utc_offset_minutes: i32,
+    /// Most sequence emails sent per local day
+    pub daily_send_limit: i32,
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -122,6 +133,9 @@ pub struct CreateEmailAccount {
     pub smtp_host: Option<String>,
     pub smtp_port: Option<i32>,
     pub use_ssl: Option<bool>,
+    /// IMAP/SMTP login, defaults to the email address
+    pub imap_username: Option<String>,
+    pub imap_password: Option<String>,
     pub granted_scopes: Option<Vec<String>>,
     pub metadata: Option<serde_json::Value>,
 }
@@ -139,6 +153,8 @@ pub struct UpdateEmailAccount {
     pub smtp_host: Option<String>,
     pub smtp_port: Option<i32>,
     pub use_ssl: Option<bool>,
+    pub imap_username: Option<String>,
+    pub imap_password: Option<String>,
     pub granted_scopes: Option<Vec<String>>,
     pub storage_used_bytes: Option<i64>,
     pub storage_total_bytes: Option<i64>,
@@ -150,6 +166,10 @@ pub struct UpdateEmailAccount {
     pub sync_frequency_minutes: Option<i32>,
     pub auto_reply_enabled: Option<bool>,
     pub signature: Option<String>,
+    pub send_window_start: Option<String>,
+    pub send_window_end: Option<String>,
+    pub send_utc_offset_minutes: Option<i32>,
+    pub daily_send_limit: Option<i32>,
 }
 
 impl EmailAccount {
@@ -173,9 +193,9 @@ impl EmailAccount {
                 id, project_id, provider, account_type, email_address,
                 display_name, avatar_url, access_token, refresh_token, token_expires_at,
                 imap_host, imap_port, smtp_host, smtp_port, use_ssl,
-                granted_scopes, metadata
+                granted_scopes, metadata, imap_username, imap_password
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
             RETURNING *
             "#,
         )
@@ -196,6 +216,8 @@ impl EmailAccount {
         .bind(use_ssl)
         .bind(granted_scopes)
         .bind(metadata)
+        .bind(&data.imap_username)
+        .bind(&data.imap_password)
         .fetch_one(pool)
         .await?;
 
@@ -321,6 +343,12 @@ impl EmailAccount {
                 sync_frequency_minutes = COALESCE(?20, sync_frequency_minutes),
                 auto_reply_enabled = COALESCE(?21, auto_reply_enabled),
                 signature = COALESCE(?22, signature),
+                imap_username = COALESCE(?23, imap_username),
+                imap_password = COALESCE(?24, imap_password),
+                send_window_start = COALESCE(?25, send_window_start),
+                send_window_end = COALESCE(?26, send_window_end),
+                send_utc_offset_minutes = COALESCE(?27, send_utc_offset_minutes),
+                daily_send_limit = COALESCE(?28, daily_send_limit),
                 updated_at = datetime('now', 'subsec')
             WHERE id = ?1
             RETURNING *
@@ -348,6 +376,12 @@ impl EmailAccount {
         .bind(data.sync_frequency_minutes)
         .bind(auto_reply_enabled)
         .bind(&data.signature)
+        .bind(&data.imap_username)
+        .bind(&data.imap_password)
+        .bind(&data.send_window_start)
+        .bind(&data.send_window_end)
+        .bind(data.send_utc_offset_minutes)
+        .bind(data.daily_send_limit)
         .fetch_optional(pool)
         .await?
         .ok_or(EmailAccountError::NotFound)
@@ -457,6 +491,8 @@ impl EmailAccount {
     /// Get provider-specific OAuth scopes for Gmail
     pub fn gmail_scopes() -> Vec<&'static str> {
         vec![
+            // IMAP and SMTP only accept tokens carrying the full mail scope
+            "https://mail.google.com/",
             "https://www.googleapis.com/auth/gmail.readonly",
             "https://www.googleapis.com/auth/gmail.send",
             "https://www.googleapis.com/auth/gmail.modify",
@@ -512,6 +548,8 @@ mod tests {
                 smtp_host: None,
                 smtp_port: None,
                 use_ssl: None,
+                imap_username: None,
+                imap_password: None,
                 granted_scopes: Some(vec!["gmail.readonly".into()]),
                 metadata: None,
             },
@@ -561,6 +599,8 @@ mod tests {
                 smtp_host: None,
                 smtp_port: None,
                 use_ssl: None,
+                imap_username: None,
+                imap_password: None,
                 granted_scopes: Some(vec!["ZohoMail.messages.READ".into()]),
                 metadata: None,
             },
@@ -594,6 +634,8 @@ mod tests {
                 smtp_host: None,
                 smtp_port: None,
                 use_ssl: None,
+                imap_username: None,
+                imap_password: None,
                 granted_scopes: None,
                 metadata: None,
             },
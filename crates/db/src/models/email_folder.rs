@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum EmailFolderError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error("Email folder not found")]
+    NotFound,
+}
+
+/// What a mailbox is used for, derived from its special-use attributes or name
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailFolderRole {
+    Inbox,
+    Sent,
+    Drafts,
+    Archive,
+    Spam,
+    Trash,
+    Other,
+}
+
+impl std::fmt::Display for EmailFolderRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            EmailFolderRole::Inbox => "inbox",
+            EmailFolderRole::Sent => "sent",
+            EmailFolderRole::Drafts => "drafts",
+            EmailFolderRole::Archive => "archive",
+            EmailFolderRole::Spam => "spam",
+            EmailFolderRole::Trash => "trash",
+            EmailFolderRole::Other => "other",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for EmailFolderRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "inbox" => Ok(EmailFolderRole::Inbox),
+            "sent" => Ok(EmailFolderRole::Sent),
+            "drafts" => Ok(EmailFolderRole::Drafts),
+            "archive" => Ok(EmailFolderRole::Archive),
+            "spam" => Ok(EmailFolderRole::Spam),
+            "trash" => Ok(EmailFolderRole::Trash),
+            "other" => Ok(EmailFolderRole::Other),
+            _ => Err(format!("Unknown email folder role: {}", s)),
+        }
+    }
+}
+
+/// Sync position of one IMAP mailbox of an email account
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EmailFolder {
+    pub id: Uuid,
+    pub email_account_id: Uuid,
+    pub name: String,
+    pub role: String,
+    pub uid_validity: Option<i64>,
+    pub uid_next: i64,
+    pub message_count: i64,
+    pub last_synced_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl EmailFolder {
+    pub fn role(&self) -> EmailFolderRole {
+        self.role.parse().unwrap_or(EmailFolderRole::Other)
+    }
+
+    /// Register a mailbox seen in LIST, keeping the sync position of known ones
+    pub async fn upsert(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        name: &str,
+        role: EmailFolderRole,
+    ) -> Result<Self, EmailFolderError> {
+        let id = Uuid::new_v4();
+        let folder = sqlx::query_as::<_, EmailFolder>(
+            r#"
+            INSERT INTO email_folders (id, email_account_id, name, role)
+            VALUES (?1, ?2, ?3, ?4)
+            ON CONFLICT(email_account_id, name) DO UPDATE SET
+                role = excluded.role,
+                updated_at = datetime('now', 'subsec')
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(email_account_id)
+        .bind(name)
+        .bind(role.to_string())
+        .fetch_one(pool)
+        .await?;
+
+        Ok(folder)
+    }
+
+    pub async fn find_by_account(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+    ) -> Result<Vec<Self>, EmailFolderError> {
+        let folders = sqlx::query_as::<_, EmailFolder>(
+            r#"SELECT * FROM email_folders WHERE email_account_id = ?1 ORDER BY name"#,
+        )
+        .bind(email_account_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(folders)
+    }
+
+    pub async fn find_by_name(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        name: &str,
+    ) -> Result<Self, EmailFolderError> {
+        sqlx::query_as::<_, EmailFolder>(
+            r#"SELECT * FROM email_folders WHERE email_account_id = ?1 AND name = ?2"#,
+        )
+        .bind(email_account_id)
+        .bind(name)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(EmailFolderError::NotFound)
+    }
+
+    /// Record how far the folder has been synced
+    pub async fn update_sync_state(
+        pool: &SqlitePool,
+        id: Uuid,
+        uid_validity: Option<i64>,
+        uid_next: i64,
+        message_count: i64,
+    ) -> Result<(), EmailFolderError> {
+        sqlx::query(
+            r#"
+            UPDATE email_folders SET
+                uid_validity = ?2,
+                uid_next = ?3,
+                message_count = ?4,
+                last_synced_at = datetime('now', 'subsec'),
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(uid_validity)
+        .bind(uid_next)
+        .bind(message_count)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Drop folders that no longer exist on the server
+    pub async fn delete_missing(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        keep: &[String],
+    ) -> Result<u64, EmailFolderError> {
+        let mut removed = 0;
+        for folder in Self::find_by_account(pool, email_account_id).await? {
+            if keep.contains(&folder.name) {
+                continue;
+            }
+            removed += sqlx::query(r#"DELETE FROM email_folders WHERE id = ?1"#)
+                .bind(folder.id)
+                .execute(pool)
+                .await?
+                .rows_affected();
+        }
+
+        Ok(removed)
+    }
+}
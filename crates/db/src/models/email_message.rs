@@ -75,6 +75,11 @@ pub struct EmailMessage {
     pub sent_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// `Message-ID` header without angle brackets
+    pub message_id_header: Option<String>,
+    /// IMAP mailbox the message was synced from
+    pub imap_folder: Option<String>,
+    pub imap_uid: Option<i64>,
 }
 
 #[derive(Debug, Deserialize, TS)]
@@ -101,6 +106,14 @@ pub struct CreateEmailMessage {
     pub is_starred: bool,
     pub is_draft: bool,
     pub is_sent: bool,
+    pub is_archived: bool,
+    pub is_spam: bool,
+    pub is_trash: bool,
+    pub in_reply_to: Option<String>,
+    pub references: Option<Vec<String>>,
+    pub message_id_header: Option<String>,
+    pub imap_folder: Option<String>,
+    pub imap_uid: Option<i64>,
     pub received_at: DateTime<Utc>,
     pub sent_at: Option<DateTime<Utc>>,
 }
@@ -169,6 +182,7 @@ impl EmailMessage {
         let bcc_addresses = data.bcc_addresses.map(|v| serde_json::to_string(&v).unwrap_or_default());
         let attachments = data.attachments.map(|v| v.to_string());
         let labels = data.labels.map(|v| serde_json::to_string(&v).unwrap_or_default());
+        let references = data.references.map(|v| serde_json::to_string(&v).unwrap_or_default());
 
         let message = sqlx::query_as::<_, EmailMessage>(
             r#"
@@ -177,9 +191,11 @@ impl EmailMessage {
                 from_address, from_name, to_addresses, cc_addresses, bcc_addresses,
                 reply_to, subject, body_text, body_html, snippet,
                 has_attachments, attachments, labels, is_read, is_starred,
-                is_draft, is_sent, received_at, sent_at
+                is_draft, is_sent, received_at, sent_at, is_archived,
+                is_spam, is_trash, in_reply_to, "references", message_id_header,
+                imap_folder, imap_uid
             )
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30, ?31, ?32)
             RETURNING *
             "#,
         )
@@ -207,6 +223,14 @@ impl EmailMessage {
         .bind(if data.is_sent { 1 } else { 0 })
         .bind(data.received_at)
         .bind(data.sent_at)
+        .bind(if data.is_archived { 1 } else { 0 })
+        .bind(if data.is_spam { 1 } else { 0 })
+        .bind(if data.is_trash { 1 } else { 0 })
+        .bind(&data.in_reply_to)
+        .bind(&references)
+        .bind(&data.message_id_header)
+        .bind(&data.imap_folder)
+        .bind(data.imap_uid)
         .fetch_one(pool)
         .await?;
 
@@ -266,16 +290,14 @@ impl EmailMessage {
 
         // For simplicity, using a more straightforward query approach
         let messages = sqlx::query_as::<_, EmailMessage>(
-            &format!(
-                r#"
-                SELECT * FROM email_messages
-                WHERE is_trash = 0 AND is_spam = 0
-                AND project_id = COALESCE(?1, project_id)
-                AND email_account_id = COALESCE(?2, email_account_id)
-                ORDER BY received_at DESC
-                LIMIT ?3 OFFSET ?4
-                "#
-            ),
+            r#"
+            SELECT * FROM email_messages
+            WHERE is_trash = 0 AND is_spam = 0
+            AND project_id = COALESCE(?1, project_id)
+            AND email_account_id = COALESCE(?2, email_account_id)
+            ORDER BY received_at DESC
+            LIMIT ?3 OFFSET ?4
+            "#,
         )
         .bind(filter.project_id)
         .bind(filter.email_account_id)
@@ -349,6 +371,186 @@ impl EmailMessage {
         Ok(messages)
     }
 
+    pub async fn find_by_provider_message_id(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        provider_message_id: &str,
+    ) -> Result<Option<Self>, EmailMessageError> {
+        let message = sqlx::query_as::<_, EmailMessage>(
+            r#"SELECT * FROM email_messages WHERE email_account_id = ?1 AND provider_message_id = ?2"#,
+        )
+        .bind(email_account_id)
+        .bind(provider_message_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(message)
+    }
+
+    /// Find the first stored message carrying any of the given `Message-ID`s,
+    /// used to attach a reply to its parent's thread
+    pub async fn find_by_message_id_headers(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        message_ids: &[String],
+    ) -> Result<Option<Self>, EmailMessageError> {
+        for message_id in message_ids {
+            let message = sqlx::query_as::<_, EmailMessage>(
+                r#"
+                SELECT * FROM email_messages
+                WHERE email_account_id = ?1 AND message_id_header = ?2
+                LIMIT 1
+                "#,
+            )
+            .bind(email_account_id)
+            .bind(message_id)
+            .fetch_optional(pool)
+            .await?;
+
+            if message.is_some() {
+                return Ok(message);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Delivered messages not yet linked to a CRM contact, oldest first
+    pub async fn find_unlinked(
+        pool: &SqlitePool,
+        created_after: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<Self>, EmailMessageError> {
+        let messages = sqlx::query_as::<_, EmailMessage>(
+            r#"
+            SELECT * FROM email_messages
+            WHERE crm_contact_id IS NULL
+              AND is_draft = 0 AND is_spam = 0 AND is_trash = 0
+              AND (?1 IS NULL OR created_at > datetime(?1, 'subsec'))
+            ORDER BY created_at
+            LIMIT ?2
+            "#,
+        )
+        .bind(created_after)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Replies received from a contact since a point in time
+    pub async fn find_replies_from_contact(
+        pool: &SqlitePool,
+        crm_contact_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Self>, EmailMessageError> {
+        let messages = sqlx::query_as::<_, EmailMessage>(
+            r#"
+            SELECT * FROM email_messages
+            WHERE crm_contact_id = ?1
+              AND is_sent = 0 AND is_spam = 0
+              AND in_reply_to IS NOT NULL
+              AND received_at >= ?2
+            ORDER BY received_at DESC
+            "#,
+        )
+        .bind(crm_contact_id)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Messages currently located in an IMAP folder, keyed by UID
+    pub async fn find_by_imap_folder(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        folder: &str,
+    ) -> Result<Vec<Self>, EmailMessageError> {
+        let messages = sqlx::query_as::<_, EmailMessage>(
+            r#"
+            SELECT * FROM email_messages
+            WHERE email_account_id = ?1 AND imap_folder = ?2 AND imap_uid IS NOT NULL
+            "#,
+        )
+        .bind(email_account_id)
+        .bind(folder)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Point a message at its IMAP location, or clear it with `None` once the
+    /// message has left the folder
+    pub async fn set_imap_location(
+        pool: &SqlitePool,
+        id: Uuid,
+        folder: Option<&str>,
+        uid: Option<i64>,
+    ) -> Result<(), EmailMessageError> {
+        sqlx::query(
+            r#"
+            UPDATE email_messages SET
+                imap_folder = ?2,
+                imap_uid = ?3,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(folder)
+        .bind(uid)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forget the IMAP location of every message in a folder, e.g. after the
+    /// server reset its UIDVALIDITY
+    pub async fn clear_imap_folder(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        folder: &str,
+    ) -> Result<(), EmailMessageError> {
+        sqlx::query(
+            r#"
+            UPDATE email_messages SET
+                imap_folder = NULL,
+                imap_uid = NULL,
+                updated_at = datetime('now', 'subsec')
+            WHERE email_account_id = ?1 AND imap_folder = ?2
+            "#,
+        )
+        .bind(email_account_id)
+        .bind(folder)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn count_unread_by_account(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+    ) -> Result<i64, EmailMessageError> {
+        let count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM email_messages
+            WHERE email_account_id = ?1 AND is_read = 0
+            AND is_trash = 0 AND is_spam = 0 AND is_sent = 0 AND is_draft = 0
+            "#,
+        )
+        .bind(email_account_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
     pub async fn update(
         pool: &SqlitePool,
         id: Uuid,
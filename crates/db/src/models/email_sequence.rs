@@ -0,0 +1,1058 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum EmailSequenceError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error("Email sequence not found")]
+    NotFound,
+    #[error("Sequence enrollment not found")]
+    EnrollmentNotFound,
+    #[error("Invalid sequence: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceStatus {
+    Draft,
+    Active,
+    Paused,
+    Archived,
+}
+
+impl std::fmt::Display for SequenceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SequenceStatus::Draft => "draft",
+            SequenceStatus::Active => "active",
+            SequenceStatus::Paused => "paused",
+            SequenceStatus::Archived => "archived",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for SequenceStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "draft" => Ok(SequenceStatus::Draft),
+            "active" => Ok(SequenceStatus::Active),
+            "paused" => Ok(SequenceStatus::Paused),
+            "archived" => Ok(SequenceStatus::Archived),
+            _ => Err(format!("Unknown sequence status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceStepType {
+    /// Send a template email
+    Email,
+    /// Wait a number of business days before the next step
+    Wait,
+    /// Create a task in the project, e.g. a call or LinkedIn touch
+    Task,
+}
+
+impl std::fmt::Display for SequenceStepType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SequenceStepType::Email => "email",
+            SequenceStepType::Wait => "wait",
+            SequenceStepType::Task => "task",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for SequenceStepType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "email" => Ok(SequenceStepType::Email),
+            "wait" => Ok(SequenceStepType::Wait),
+            "task" => Ok(SequenceStepType::Task),
+            _ => Err(format!("Unknown sequence step type: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum EnrollmentStatus {
+    Active,
+    Paused,
+    Completed,
+    Replied,
+    Bounced,
+    Removed,
+    Failed,
+}
+
+impl std::fmt::Display for EnrollmentStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            EnrollmentStatus::Active => "active",
+            EnrollmentStatus::Paused => "paused",
+            EnrollmentStatus::Completed => "completed",
+            EnrollmentStatus::Replied => "replied",
+            EnrollmentStatus::Bounced => "bounced",
+            EnrollmentStatus::Removed => "removed",
+            EnrollmentStatus::Failed => "failed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for EnrollmentStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "active" => Ok(EnrollmentStatus::Active),
+            "paused" => Ok(EnrollmentStatus::Paused),
+            "completed" => Ok(EnrollmentStatus::Completed),
+            "replied" => Ok(EnrollmentStatus::Replied),
+            "bounced" => Ok(EnrollmentStatus::Bounced),
+            "removed" => Ok(EnrollmentStatus::Removed),
+            "failed" => Ok(EnrollmentStatus::Failed),
+            _ => Err(format!("Unknown enrollment status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceEventType {
+    Sent,
+    Opened,
+    Replied,
+    Bounced,
+    MeetingBooked,
+    TaskCreated,
+    Failed,
+}
+
+impl std::fmt::Display for SequenceEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SequenceEventType::Sent => "sent",
+            SequenceEventType::Opened => "opened",
+            SequenceEventType::Replied => "replied",
+            SequenceEventType::Bounced => "bounced",
+            SequenceEventType::MeetingBooked => "meeting_booked",
+            SequenceEventType::TaskCreated => "task_created",
+            SequenceEventType::Failed => "failed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EmailSequence {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    /// Account the sequence sends from; its send window and daily cap apply
+    pub email_account_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateEmailSequence {
+    pub project_id: Uuid,
+    pub email_account_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateEmailSequence {
+    pub email_account_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<SequenceStatus>,
+}
+
+impl EmailSequence {
+    pub async fn create(
+        pool: &SqlitePool,
+        data: CreateEmailSequence,
+    ) -> Result<Self, EmailSequenceError> {
+        if data.name.trim().is_empty() {
+            return Err(EmailSequenceError::Invalid("name is required".into()));
+        }
+
+        let sequence = sqlx::query_as::<_, EmailSequence>(
+            r#"
+            INSERT INTO email_sequences (id, project_id, email_account_id, name, description)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(data.project_id)
+        .bind(data.email_account_id)
+        .bind(data.name.trim())
+        .bind(&data.description)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(sequence)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Self, EmailSequenceError> {
+        sqlx::query_as::<_, EmailSequence>(r#"SELECT * FROM email_sequences WHERE id = ?1"#)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(EmailSequenceError::NotFound)
+    }
+
+    pub async fn find_by_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Vec<Self>, EmailSequenceError> {
+        let sequences = sqlx::query_as::<_, EmailSequence>(
+            r#"
+            SELECT * FROM email_sequences
+            WHERE project_id = ?1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(project_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sequences)
+    }
+
+    pub async fn update(
+        pool: &SqlitePool,
+        id: Uuid,
+        data: UpdateEmailSequence,
+    ) -> Result<Self, EmailSequenceError> {
+        if data
+            .name
+            .as_deref()
+            .is_some_and(|name| name.trim().is_empty())
+        {
+            return Err(EmailSequenceError::Invalid("name is required".into()));
+        }
+
+        sqlx::query_as::<_, EmailSequence>(
+            r#"
+            UPDATE email_sequences SET
+                email_account_id = COALESCE(?2, email_account_id),
+                name = COALESCE(?3, name),
+                description = COALESCE(?4, description),
+                status = COALESCE(?5, status),
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(data.email_account_id)
+        .bind(data.name.as_deref().map(str::trim))
+        .bind(&data.description)
+        .bind(data.status.map(|s| s.to_string()))
+        .fetch_optional(pool)
+        .await?
+        .ok_or(EmailSequenceError::NotFound)
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<(), EmailSequenceError> {
+        let result = sqlx::query(r#"DELETE FROM email_sequences WHERE id = ?1"#)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(EmailSequenceError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EmailSequenceStep {
+    pub id: Uuid,
+    pub sequence_id: Uuid,
+    pub position: i32,
+    pub step_type: String,
+    /// Template with `{{field}}` or `{{field|fallback}}` merge fields. Email
+    /// steps without a subject reply in the thread of the previous email.
+    pub subject: Option<String>,
+    pub body_text: Option<String>,
+    pub body_html: Option<String>,
+    pub wait_business_days: Option<i32>,
+    pub task_title: Option<String>,
+    pub task_description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, TS)]
+#[ts(export)]
+pub struct SequenceStepInput {
+    pub step_type: SequenceStepType,
+    pub subject: Option<String>,
+    pub body_text: Option<String>,
+    pub body_html: Option<String>,
+    pub wait_business_days: Option<i32>,
+    pub task_title: Option<String>,
+    pub task_description: Option<String>,
+}
+
+impl SequenceStepInput {
+    fn validate(&self, position: usize) -> Result<(), EmailSequenceError> {
+        let blank = |value: &Option<String>| value.as_deref().is_none_or(|v| v.trim().is_empty());
+        let invalid = |reason: &str| {
+            Err(EmailSequenceError::Invalid(format!(
+                "step {}: {}",
+                position + 1,
+                reason
+            )))
+        };
+
+        match self.step_type {
+            SequenceStepType::Email if blank(&self.body_text) && blank(&self.body_html) => {
+                invalid("email steps need a body")
+            }
+            SequenceStepType::Email if position == 0 && blank(&self.subject) => {
+                invalid("the first email needs a subject")
+            }
+            SequenceStepType::Wait if self.wait_business_days.is_none_or(|days| days < 1) => {
+                invalid("wait steps need at least one business day")
+            }
+            SequenceStepType::Task if blank(&self.task_title) => invalid("task steps need a title"),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl EmailSequenceStep {
+    pub fn kind(&self) -> Option<SequenceStepType> {
+        self.step_type.parse().ok()
+    }
+
+    pub async fn find_by_sequence(
+        pool: &SqlitePool,
+        sequence_id: Uuid,
+    ) -> Result<Vec<Self>, EmailSequenceError> {
+        let steps = sqlx::query_as::<_, EmailSequenceStep>(
+            r#"
+            SELECT * FROM email_sequence_steps
+            WHERE sequence_id = ?1
+            ORDER BY position
+            "#,
+        )
+        .bind(sequence_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(steps)
+    }
+
+    pub async fn find_at_position(
+        pool: &SqlitePool,
+        sequence_id: Uuid,
+        position: i32,
+    ) -> Result<Option<Self>, EmailSequenceError> {
+        let step = sqlx::query_as::<_, EmailSequenceStep>(
+            r#"SELECT * FROM email_sequence_steps WHERE sequence_id = ?1 AND position = ?2"#,
+        )
+        .bind(sequence_id)
+        .bind(position)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(step)
+    }
+
+    /// Replace all steps of a sequence. Enrollments keep their position, so
+    /// steps should only be appended to a sequence that is already running.
+    pub async fn replace_all(
+        pool: &SqlitePool,
+        sequence_id: Uuid,
+        steps: Vec<SequenceStepInput>,
+    ) -> Result<Vec<Self>, EmailSequenceError> {
+        for (position, step) in steps.iter().enumerate() {
+            step.validate(position)?;
+        }
+
+        let mut tx = pool.begin().await?;
+        sqlx::query(r#"DELETE FROM email_sequence_steps WHERE sequence_id = ?1"#)
+            .bind(sequence_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let mut created = Vec::with_capacity(steps.len());
+        for (position, step) in steps.into_iter().enumerate() {
+            let row = sqlx::query_as::<_, EmailSequenceStep>(
+                r#"
+                INSERT INTO email_sequence_steps (
+                    id, sequence_id, position, step_type, subject, body_text, body_html,
+                    wait_business_days, task_title, task_description
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                RETURNING *
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(sequence_id)
+            .bind(position as i32)
+            .bind(step.step_type.to_string())
+            .bind(&step.subject)
+            .bind(&step.body_text)
+            .bind(&step.body_html)
+            .bind(step.wait_business_days)
+            .bind(&step.task_title)
+            .bind(&step.task_description)
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(row);
+        }
+        tx.commit().await?;
+
+        Ok(created)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EmailSequenceEnrollment {
+    pub id: Uuid,
+    pub sequence_id: Uuid,
+    pub crm_contact_id: Uuid,
+    pub status: String,
+    /// Position of the next step to run
+    pub current_step: i32,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_message_id: Option<Uuid>,
+    pub exit_reason: Option<String>,
+    pub enrolled_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl EmailSequenceEnrollment {
+    /// Enroll a contact; `None` when it is already enrolled in the sequence
+    pub async fn enroll(
+        pool: &SqlitePool,
+        sequence_id: Uuid,
+        crm_contact_id: Uuid,
+    ) -> Result<Option<Self>, EmailSequenceError> {
+        let enrollment = sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"
+            INSERT INTO email_sequence_enrollments (id, sequence_id, crm_contact_id, next_run_at)
+            VALUES (?1, ?2, ?3, datetime('now', 'subsec'))
+            ON CONFLICT(sequence_id, crm_contact_id) DO NOTHING
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(sequence_id)
+        .bind(crm_contact_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(enrollment)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Self, EmailSequenceError> {
+        sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"SELECT * FROM email_sequence_enrollments WHERE id = ?1"#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(EmailSequenceError::EnrollmentNotFound)
+    }
+
+    pub async fn find_by_sequence(
+        pool: &SqlitePool,
+        sequence_id: Uuid,
+        status: Option<EnrollmentStatus>,
+    ) -> Result<Vec<Self>, EmailSequenceError> {
+        let enrollments = sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"
+            SELECT * FROM email_sequence_enrollments
+            WHERE sequence_id = ?1 AND (?2 IS NULL OR status = ?2)
+            ORDER BY enrolled_at DESC
+            "#,
+        )
+        .bind(sequence_id)
+        .bind(status.map(|s| s.to_string()))
+        .fetch_all(pool)
+        .await?;
+
+        Ok(enrollments)
+    }
+
+    /// Active or paused enrollments of a contact across all sequences
+    pub async fn find_active_by_contact(
+        pool: &SqlitePool,
+        crm_contact_id: Uuid,
+    ) -> Result<Vec<Self>, EmailSequenceError> {
+        let enrollments = sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"
+            SELECT * FROM email_sequence_enrollments
+            WHERE crm_contact_id = ?1 AND status IN ('active', 'paused')
+            "#,
+        )
+        .bind(crm_contact_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(enrollments)
+    }
+
+    /// Active or paused enrollments of a project whose contact has this email address
+    pub async fn find_active_by_email(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        email: &str,
+    ) -> Result<Vec<Self>, EmailSequenceError> {
+        let enrollments = sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"
+            SELECT e.* FROM email_sequence_enrollments e
+            JOIN crm_contacts c ON c.id = e.crm_contact_id
+            WHERE c.project_id = ?1
+              AND LOWER(c.email) = LOWER(?2)
+              AND e.status IN ('active', 'paused')
+            "#,
+        )
+        .bind(project_id)
+        .bind(email)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(enrollments)
+    }
+
+    /// The enrollment that sent one of these `Message-ID`s from the account
+    pub async fn find_by_sent_message_ids(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        message_ids: &[String],
+    ) -> Result<Option<Self>, EmailSequenceError> {
+        if message_ids.is_empty() {
+            return Ok(None);
+        }
+        let ids = serde_json::to_string(message_ids).unwrap_or_default();
+
+        let enrollment = sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"
+            SELECT en.* FROM email_sequence_events ev
+            JOIN email_messages m ON m.id = ev.email_message_id
+            JOIN email_sequence_enrollments en ON en.id = ev.enrollment_id
+            WHERE ev.event_type = 'sent'
+              AND m.email_account_id = ?1
+              AND m.message_id_header IN (SELECT value FROM json_each(?2))
+            ORDER BY ev.created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(email_account_id)
+        .bind(ids)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(enrollment)
+    }
+
+    /// Active enrollments of active sequences whose next step is due
+    pub async fn find_due(pool: &SqlitePool, limit: i64) -> Result<Vec<Self>, EmailSequenceError> {
+        let enrollments = sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"
+            SELECT e.* FROM email_sequence_enrollments e
+            JOIN email_sequences s ON s.id = e.sequence_id
+            WHERE e.status = 'active'
+              AND s.status = 'active'
+              AND (e.next_run_at IS NULL OR e.next_run_at <= datetime('now', 'subsec'))
+            ORDER BY e.next_run_at
+            LIMIT ?1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(enrollments)
+    }
+
+    /// Enrollments that sent mail since `since`, for attributing meetings
+    pub async fn find_contacted_since(
+        pool: &SqlitePool,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Self>, EmailSequenceError> {
+        let enrollments = sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"
+            SELECT DISTINCT en.* FROM email_sequence_enrollments en
+            JOIN email_sequence_events ev ON ev.enrollment_id = en.id
+            WHERE ev.event_type = 'sent'
+              AND ev.created_at >= datetime(?1, 'subsec')
+            "#,
+        )
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(enrollments)
+    }
+
+    /// Move to step `current_step`, to run at `next_run_at`
+    pub async fn advance(
+        pool: &SqlitePool,
+        id: Uuid,
+        current_step: i32,
+        next_run_at: DateTime<Utc>,
+        last_message_id: Option<Uuid>,
+    ) -> Result<(), EmailSequenceError> {
+        sqlx::query(
+            r#"
+            UPDATE email_sequence_enrollments SET
+                current_step = ?2,
+                next_run_at = datetime(?3, 'subsec'),
+                last_message_id = COALESCE(?4, last_message_id),
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(current_step)
+        .bind(next_run_at)
+        .bind(last_message_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Retry the current step later, e.g. outside the send window
+    pub async fn reschedule(
+        pool: &SqlitePool,
+        id: Uuid,
+        next_run_at: DateTime<Utc>,
+    ) -> Result<(), EmailSequenceError> {
+        sqlx::query(
+            r#"
+            UPDATE email_sequence_enrollments SET
+                next_run_at = datetime(?2, 'subsec'),
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(next_run_at)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Take the enrollment out of the sequence. Returns false when it had
+    /// already finished, so a reply and a bounce cannot both end it.
+    pub async fn finish(
+        pool: &SqlitePool,
+        id: Uuid,
+        status: EnrollmentStatus,
+        reason: Option<&str>,
+    ) -> Result<bool, EmailSequenceError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE email_sequence_enrollments SET
+                status = ?2,
+                exit_reason = ?3,
+                next_run_at = NULL,
+                finished_at = datetime('now', 'subsec'),
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1 AND status IN ('active', 'paused')
+            "#,
+        )
+        .bind(id)
+        .bind(status.to_string())
+        .bind(reason)
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Pause or resume; a resumed enrollment runs its next step right away
+    pub async fn set_paused(
+        pool: &SqlitePool,
+        id: Uuid,
+        paused: bool,
+    ) -> Result<Self, EmailSequenceError> {
+        sqlx::query_as::<_, EmailSequenceEnrollment>(
+            r#"
+            UPDATE email_sequence_enrollments SET
+                status = CASE WHEN ?2 THEN 'paused' ELSE 'active' END,
+                next_run_at = CASE WHEN ?2 THEN next_run_at
+                    ELSE MAX(COALESCE(next_run_at, datetime('now', 'subsec')), datetime('now', 'subsec')) END,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1 AND status IN ('active', 'paused')
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(paused)
+        .fetch_optional(pool)
+        .await?
+        .ok_or(EmailSequenceError::EnrollmentNotFound)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EmailSequenceEvent {
+    pub id: Uuid,
+    pub enrollment_id: Uuid,
+    pub sequence_id: Uuid,
+    pub step_id: Option<Uuid>,
+    pub event_type: String,
+    pub email_message_id: Option<Uuid>,
+    pub task_id: Option<Uuid>,
+    pub detail: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateSequenceEvent {
+    /// Set when the id has to be known up front, e.g. for an open-tracking pixel
+    pub id: Option<Uuid>,
+    pub enrollment_id: Uuid,
+    pub sequence_id: Uuid,
+    pub step_id: Option<Uuid>,
+    pub event_type: SequenceEventType,
+    pub email_message_id: Option<Uuid>,
+    pub task_id: Option<Uuid>,
+    pub detail: Option<String>,
+}
+
+/// Outcomes of one step. Opens, replies, bounces and meetings are counted
+/// once per enrollment and credited to the last email sent before them.
+#[derive(Debug, Clone, FromRow, Serialize, TS)]
+#[ts(export)]
+pub struct SequenceStepStats {
+    pub step_id: Uuid,
+    pub position: i32,
+    pub step_type: String,
+    pub sent: i64,
+    pub opened: i64,
+    pub replied: i64,
+    pub bounced: i64,
+    pub meetings_booked: i64,
+    pub tasks_created: i64,
+    pub failed: i64,
+}
+
+impl EmailSequenceEvent {
+    pub async fn create(
+        pool: &SqlitePool,
+        data: CreateSequenceEvent,
+    ) -> Result<Self, EmailSequenceError> {
+        let event = sqlx::query_as::<_, EmailSequenceEvent>(
+            r#"
+            INSERT INTO email_sequence_events (
+                id, enrollment_id, sequence_id, step_id, event_type,
+                email_message_id, task_id, detail
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            RETURNING *
+            "#,
+        )
+        .bind(data.id.unwrap_or_else(Uuid::new_v4))
+        .bind(data.enrollment_id)
+        .bind(data.sequence_id)
+        .bind(data.step_id)
+        .bind(data.event_type.to_string())
+        .bind(data.email_message_id)
+        .bind(data.task_id)
+        .bind(&data.detail)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(event)
+    }
+
+    pub async fn find_by_id(
+        pool: &SqlitePool,
+        id: Uuid,
+    ) -> Result<Option<Self>, EmailSequenceError> {
+        let event = sqlx::query_as::<_, EmailSequenceEvent>(
+            r#"SELECT * FROM email_sequence_events WHERE id = ?1"#,
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(event)
+    }
+
+    pub async fn find_by_enrollment(
+        pool: &SqlitePool,
+        enrollment_id: Uuid,
+    ) -> Result<Vec<Self>, EmailSequenceError> {
+        let events = sqlx::query_as::<_, EmailSequenceEvent>(
+            r#"
+            SELECT * FROM email_sequence_events
+            WHERE enrollment_id = ?1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(enrollment_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(events)
+    }
+
+    /// The most recent email sent to an enrollment
+    pub async fn find_last_sent(
+        pool: &SqlitePool,
+        enrollment_id: Uuid,
+    ) -> Result<Option<Self>, EmailSequenceError> {
+        let event = sqlx::query_as::<_, EmailSequenceEvent>(
+            r#"
+            SELECT * FROM email_sequence_events
+            WHERE enrollment_id = ?1 AND event_type = 'sent'
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(enrollment_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(event)
+    }
+
+    pub async fn exists(
+        pool: &SqlitePool,
+        enrollment_id: Uuid,
+        event_type: SequenceEventType,
+        step_id: Option<Uuid>,
+    ) -> Result<bool, EmailSequenceError> {
+        let exists = sqlx::query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM email_sequence_events
+                WHERE enrollment_id = ?1 AND event_type = ?2 AND (?3 IS NULL OR step_id = ?3)
+            )
+            "#,
+        )
+        .bind(enrollment_id)
+        .bind(event_type.to_string())
+        .bind(step_id)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(exists)
+    }
+
+    /// Sequence emails an account has sent since `since`, across sequences
+    pub async fn count_sent_by_account_since(
+        pool: &SqlitePool,
+        email_account_id: Uuid,
+        since: DateTime<Utc>,
+    ) -> Result<i64, EmailSequenceError> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM email_sequence_events ev
+            JOIN email_messages m ON m.id = ev.email_message_id
+            WHERE ev.event_type = 'sent'
+              AND m.email_account_id = ?1
+              AND ev.created_at >= datetime(?2, 'subsec')
+            "#,
+        )
+        .bind(email_account_id)
+        .bind(since)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    pub async fn step_stats(
+        pool: &SqlitePool,
+        sequence_id: Uuid,
+    ) -> Result<Vec<SequenceStepStats>, EmailSequenceError> {
+        let stats = sqlx::query_as::<_, SequenceStepStats>(
+            r#"
+            SELECT
+                s.id AS step_id,
+                s.position,
+                s.step_type,
+                COUNT(DISTINCT CASE WHEN e.event_type = 'sent' THEN e.enrollment_id END) AS sent,
+                COUNT(DISTINCT CASE WHEN e.event_type IN ('opened', 'replied') THEN e.enrollment_id END) AS opened,
+                COUNT(DISTINCT CASE WHEN e.event_type = 'replied' THEN e.enrollment_id END) AS replied,
+                COUNT(DISTINCT CASE WHEN e.event_type = 'bounced' THEN e.enrollment_id END) AS bounced,
+                COUNT(DISTINCT CASE WHEN e.event_type = 'meeting_booked' THEN e.enrollment_id END) AS meetings_booked,
+                COUNT(DISTINCT CASE WHEN e.event_type = 'task_created' THEN e.enrollment_id END) AS tasks_created,
+                COUNT(DISTINCT CASE WHEN e.event_type = 'failed' THEN e.enrollment_id END) AS failed
+            FROM email_sequence_steps s
+            LEFT JOIN email_sequence_events e ON e.step_id = s.id
+            WHERE s.sequence_id = ?1
+            GROUP BY s.id
+            ORDER BY s.position
+            "#,
+        )
+        .bind(sequence_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{
+        email_account::{CreateEmailAccount, EmailAccount, EmailProvider},
+        test_utils::{create_test_project, setup_test_pool},
+    };
+
+    fn email(subject: Option<&str>, body: &str) -> SequenceStepInput {
+        SequenceStepInput {
+            step_type: SequenceStepType::Email,
+            subject: subject.map(Into::into),
+            body_text: Some(body.into()),
+            body_html: None,
+            wait_business_days: None,
+            task_title: None,
+            task_description: None,
+        }
+    }
+
+    fn wait(days: i32) -> SequenceStepInput {
+        SequenceStepInput {
+            step_type: SequenceStepType::Wait,
+            subject: None,
+            body_text: None,
+            body_html: None,
+            wait_business_days: Some(days),
+            task_title: None,
+            task_description: None,
+        }
+    }
+
+    async fn create_sequence(pool: &SqlitePool) -> EmailSequence {
+        let project_id = create_test_project(pool).await;
+        let account = EmailAccount::create(
+            pool,
+            CreateEmailAccount {
+                project_id,
+                provider: EmailProvider::ImapCustom,
+                account_type: None,
+                email_address: "sales@example.com".into(),
+                display_name: None,
+                avatar_url: None,
+                access_token: None,
+                refresh_token: None,
+                token_expires_at: None,
+                imap_host: None,
+                imap_port: None,
+                smtp_host: None,
+                smtp_port: None,
+                use_ssl: None,
+                imap_username: None,
+                imap_password: None,
+                granted_scopes: None,
+                metadata: None,
+            },
+        )
+        .await
+        .expect("failed to create email account");
+
+        EmailSequence::create(
+            pool,
+            CreateEmailSequence {
+                project_id,
+                email_account_id: account.id,
+                name: "Post-conference follow-up".into(),
+                description: None,
+            },
+        )
+        .await
+        .expect("failed to create sequence")
+    }
+
+    #[tokio::test]
+    async fn replaces_steps_in_order() {
+        let pool = setup_test_pool().await;
+        let sequence = create_sequence(&pool).await;
+        assert_eq!(sequence.status, "draft");
+
+        EmailSequenceStep::replace_all(&pool, sequence.id, vec![email(Some("Hi"), "First")])
+            .await
+            .unwrap();
+        let steps = EmailSequenceStep::replace_all(
+            &pool,
+            sequence.id,
+            vec![email(Some("Hi"), "First"), wait(3), email(None, "Bump")],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(steps.len(), 3);
+        let stored = EmailSequenceStep::find_by_sequence(&pool, sequence.id)
+            .await
+            .unwrap();
+        let kinds: Vec<_> = stored.iter().filter_map(|s| s.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                SequenceStepType::Email,
+                SequenceStepType::Wait,
+                SequenceStepType::Email
+            ]
+        );
+        assert_eq!(stored[1].wait_business_days, Some(3));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_steps() {
+        let pool = setup_test_pool().await;
+        let sequence = create_sequence(&pool).await;
+
+        for steps in [
+            vec![email(None, "No subject on the first email")],
+            vec![email(Some("Hi"), "First"), wait(0)],
+        ] {
+            let result = EmailSequenceStep::replace_all(&pool, sequence.id, steps).await;
+            assert!(matches!(result, Err(EmailSequenceError::Invalid(_))));
+        }
+        assert!(
+            EmailSequenceStep::find_by_sequence(&pool, sequence.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Cold storage index and retention policies for execution logs
+//!
+//! Finished executions have their JSONL logs moved out of
+//! `execution_process_logs` into compressed segment files. This module owns the
+//! database side of that: the segment index, per-project retention policies and
+//! the vacuum run history. File I/O lives in `services::log_archive`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Sqlite, SqlitePool, Transaction};
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// Defaults used for projects without a `log_retention_policies` row
+pub const DEFAULT_ARCHIVE_AFTER_HOURS: i64 = 24;
+pub const DEFAULT_DELETE_AFTER_DAYS: Option<i64> = None;
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ExecutionLogSegment {
+    pub id: Uuid,
+    pub execution_id: Uuid,
+    pub segment_index: i64,
+    /// Path relative to the log archive root
+    pub path: String,
+    pub codec: String,
+    pub first_line: i64,
+    pub line_count: i64,
+    pub uncompressed_bytes: i64,
+    pub compressed_bytes: i64,
+    pub sha256: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateExecutionLogSegment {
+    pub execution_id: Uuid,
+    pub segment_index: i64,
+    pub path: String,
+    pub first_line: i64,
+    pub line_count: i64,
+    pub uncompressed_bytes: i64,
+    pub compressed_bytes: i64,
+    pub sha256: String,
+}
+
+impl ExecutionLogSegment {
+    /// All segments of an execution in replay order
+    pub async fn find_by_execution_id(
+        pool: &SqlitePool,
+        execution_id: Uuid,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM execution_log_segments
+            WHERE execution_id = ?
+            ORDER BY segment_index ASC
+            "#,
+        )
+        .bind(execution_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    pub async fn exists_for_execution(
+        pool: &SqlitePool,
+        execution_id: Uuid,
+    ) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM execution_log_segments WHERE execution_id = ?",
+        )
+        .bind(execution_id)
+        .fetch_one(pool)
+        .await?;
+        Ok(count > 0)
+    }
+
+    /// Record the segments of an archived execution and drop its hot copy in
+    /// one transaction, so a log is never in neither place.
+    pub async fn commit_archive(
+        pool: &SqlitePool,
+        execution_id: Uuid,
+        segments: &[CreateExecutionLogSegment],
+    ) -> Result<(), sqlx::Error> {
+        let mut tx: Transaction<'_, Sqlite> = pool.begin().await?;
+
+        for segment in segments {
+            sqlx::query(
+                r#"
+                INSERT INTO execution_log_segments (
+                    id, execution_id, segment_index, path, codec,
+                    first_line, line_count, uncompressed_bytes, compressed_bytes, sha256
+                )
+                VALUES (?, ?, ?, ?, 'zstd', ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(segment.execution_id)
+            .bind(segment.segment_index)
+            .bind(&segment.path)
+            .bind(segment.first_line)
+            .bind(segment.line_count)
+            .bind(segment.uncompressed_bytes)
+            .bind(segment.compressed_bytes)
+            .bind(&segment.sha256)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query("DELETE FROM execution_process_logs WHERE execution_id = ?")
+            .bind(execution_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await
+    }
+
+    pub async fn delete_for_execution(
+        pool: &SqlitePool,
+        execution_id: Uuid,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM execution_log_segments WHERE execution_id = ?")
+            .bind(execution_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Total size of the cold archive as recorded in the index
+    pub async fn total_compressed_bytes(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT CAST(COALESCE(SUM(compressed_bytes), 0) AS INTEGER) FROM execution_log_segments",
+        )
+        .fetch_one(pool)
+        .await
+    }
+}
+
+/// An execution whose hot logs are due to move to cold storage
+#[derive(Debug, Clone, FromRow)]
+pub struct ArchiveCandidate {
+    pub execution_id: Uuid,
+    pub project_id: Uuid,
+    pub byte_size: i64,
+}
+
+/// An execution whose logs (hot or cold) have outlived their retention
+#[derive(Debug, Clone, FromRow)]
+pub struct ExpiredLogs {
+    pub execution_id: Uuid,
+    pub project_id: Uuid,
+    pub hot_bytes: i64,
+    pub cold_bytes: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LogRetentionPolicy {
+    pub project_id: Uuid,
+    pub archive_after_hours: i64,
+    pub delete_after_days: Option<i64>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, TS)]
+#[ts(export)]
+pub struct UpsertLogRetentionPolicy {
+    pub archive_after_hours: i64,
+    pub delete_after_days: Option<i64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl LogRetentionPolicy {
+    pub async fn find_by_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM log_retention_policies WHERE project_id = ?")
+            .bind(project_id)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn list(pool: &SqlitePool) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM log_retention_policies ORDER BY updated_at DESC")
+            .fetch_all(pool)
+            .await
+    }
+
+    pub async fn upsert(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        data: &UpsertLogRetentionPolicy,
+    ) -> Result<Self, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO log_retention_policies (
+                project_id, archive_after_hours, delete_after_days, enabled
+            )
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (project_id) DO UPDATE
+            SET archive_after_hours = excluded.archive_after_hours,
+                delete_after_days = excluded.delete_after_days,
+                enabled = excluded.enabled,
+                updated_at = datetime('now', 'subsec')
+            RETURNING *
+            "#,
+        )
+        .bind(project_id)
+        .bind(data.archive_after_hours)
+        .bind(data.delete_after_days)
+        .bind(data.enabled)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn delete(pool: &SqlitePool, project_id: Uuid) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM log_retention_policies WHERE project_id = ?")
+            .bind(project_id)
+            .execute(pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Finished executions whose hot logs are older than their project's
+    /// `archive_after_hours`
+    pub async fn find_archive_candidates(
+        pool: &SqlitePool,
+        limit: i64,
+    ) -> Result<Vec<ArchiveCandidate>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT epl.execution_id AS execution_id,
+                   t.project_id AS project_id,
+                   epl.byte_size AS byte_size
+            FROM execution_process_logs epl
+            JOIN execution_processes ep ON ep.id = epl.execution_id
+            JOIN task_attempts ta ON ta.id = ep.task_attempt_id
+            JOIN tasks t ON t.id = ta.task_id
+            LEFT JOIN log_retention_policies p ON p.project_id = t.project_id
+            WHERE ep.status != 'running'
+              AND ep.completed_at IS NOT NULL
+              AND COALESCE(p.enabled, 1) = 1
+              AND datetime(ep.completed_at) <= datetime(
+                    'now', '-' || COALESCE(p.archive_after_hours, ?) || ' hours')
+            ORDER BY ep.completed_at ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(DEFAULT_ARCHIVE_AFTER_HOURS)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Finished executions whose logs are older than their project's
+    /// `delete_after_days`, whether still hot or already archived
+    pub async fn find_expired(
+        pool: &SqlitePool,
+        limit: i64,
+    ) -> Result<Vec<ExpiredLogs>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT ep.id AS execution_id,
+                   t.project_id AS project_id,
+                   CAST(COALESCE((SELECT byte_size FROM execution_process_logs
+                                  WHERE execution_id = ep.id), 0) AS INTEGER) AS hot_bytes,
+                   CAST(COALESCE((SELECT SUM(compressed_bytes) FROM execution_log_segments
+                                  WHERE execution_id = ep.id), 0) AS INTEGER) AS cold_bytes
+            FROM execution_processes ep
+            JOIN task_attempts ta ON ta.id = ep.task_attempt_id
+            JOIN tasks t ON t.id = ta.task_id
+            LEFT JOIN log_retention_policies p ON p.project_id = t.project_id
+            WHERE ep.status != 'running'
+              AND ep.completed_at IS NOT NULL
+              AND COALESCE(p.enabled, 1) = 1
+              AND COALESCE(p.delete_after_days, ?) IS NOT NULL
+              AND datetime(ep.completed_at) <= datetime(
+                    'now', '-' || COALESCE(p.delete_after_days, ?) || ' days')
+              AND (EXISTS (SELECT 1 FROM execution_process_logs WHERE execution_id = ep.id)
+                   OR EXISTS (SELECT 1 FROM execution_log_segments WHERE execution_id = ep.id))
+            ORDER BY ep.completed_at ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(DEFAULT_DELETE_AFTER_DAYS)
+        .bind(DEFAULT_DELETE_AFTER_DAYS)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+/// Outcome of one retention/vacuum pass
+#[derive(Debug, Clone, Default, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LogVacuumRun {
+    pub id: Uuid,
+    pub executions_archived: i64,
+    pub executions_deleted: i64,
+    /// Uncompressed log bytes moved out of the database
+    pub bytes_archived: i64,
+    /// Size of the segment files written for them
+    pub bytes_compressed: i64,
+    /// Hot and cold bytes removed by `delete_after_days`
+    pub bytes_deleted: i64,
+    pub db_bytes_before: i64,
+    pub db_bytes_after: i64,
+    pub vacuumed: bool,
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl LogVacuumRun {
+    /// Bytes the database file shrank by during this run
+    pub fn db_bytes_reclaimed(&self) -> i64 {
+        (self.db_bytes_before - self.db_bytes_after).max(0)
+    }
+
+    pub async fn record(pool: &SqlitePool, run: &LogVacuumRun) -> Result<Self, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO log_vacuum_runs (
+                id, executions_archived, executions_deleted, bytes_archived,
+                bytes_compressed, bytes_deleted, db_bytes_before, db_bytes_after,
+                vacuumed, error, started_at, finished_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE(?, datetime('now', 'subsec')),
+                    datetime('now', 'subsec'))
+            RETURNING *
+            "#,
+        )
+        .bind(run.id)
+        .bind(run.executions_archived)
+        .bind(run.executions_deleted)
+        .bind(run.bytes_archived)
+        .bind(run.bytes_compressed)
+        .bind(run.bytes_deleted)
+        .bind(run.db_bytes_before)
+        .bind(run.db_bytes_after)
+        .bind(run.vacuumed)
+        .bind(&run.error)
+        .bind(run.started_at)
+        .fetch_one(pool)
+        .await
+    }
+
+    pub async fn find_recent(pool: &SqlitePool, limit: i64) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM log_vacuum_runs ORDER BY started_at DESC LIMIT ?")
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+    }
+}
@@ -73,6 +73,17 @@ impl ExecutionProcessLogs {
         .await
     }
 
+    /// Delete the stored logs of an execution process, returning bytes freed
+    pub async fn delete(pool: &SqlitePool, execution_id: Uuid) -> Result<i64, sqlx::Error> {
+        let freed: Option<i64> = sqlx::query_scalar(
+            "DELETE FROM execution_process_logs WHERE execution_id = ? RETURNING byte_size",
+        )
+        .bind(execution_id)
+        .fetch_optional(pool)
+        .await?;
+        Ok(freed.unwrap_or(0))
+    }
+
     /// Parse JSONL logs back into Vec<LogMsg>
     pub fn parse_logs(&self) -> Result<Vec<LogMsg>, serde_json::Error> {
         let mut messages = Vec::new();
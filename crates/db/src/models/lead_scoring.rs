@@ -0,0 +1,568 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool, types::Json};
+use thiserror::Error;
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum LeadScoringError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error("Lead scoring config not found")]
+    NotFound,
+    #[error("Invalid lead scoring rules: {0}")]
+    InvalidRules(String),
+}
+
+/// How a field rule compares a contact field with its value
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldRuleOperator {
+    Present,
+    Missing,
+    /// Case-insensitive equality
+    Equals,
+    /// Case-insensitive substring match
+    Contains,
+}
+
+/// Points for a contact field, e.g. `job_title contains "director"`.
+/// `field` is a `crm_contact` column or `custom_fields.<key>`.
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+pub struct FieldRule {
+    pub field: String,
+    pub operator: FieldRuleOperator,
+    #[serde(default)]
+    pub value: Option<String>,
+    pub points: i32,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Points for the number of activities in a recent window
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+pub struct FrequencyRule {
+    pub window_days: i64,
+    pub points_per_activity: f64,
+    pub max_points: f64,
+}
+
+/// Points when the last activity happened within `within_days`
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+pub struct RecencyTier {
+    pub within_days: i64,
+    pub points: i32,
+}
+
+/// Points when the contact's open deals add up to at least `min_amount`
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+pub struct DealValueTier {
+    pub min_amount: f64,
+    pub points: i32,
+}
+
+/// A per-event signal (replies, mentions) that decays with age
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+pub struct EventRule {
+    pub points: f64,
+    pub max_points: f64,
+}
+
+/// Everything the scoring engine needs to score a contact.
+///
+/// Activity, reply and mention points halve every `half_life_days`; field,
+/// lifecycle and deal value points don't decay. Only the first matching
+/// recency tier and the highest matching deal value tier count.
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+#[serde(default)]
+pub struct LeadScoringRules {
+    pub field_rules: Vec<FieldRule>,
+    /// Points by `lifecycle_stage`
+    pub lifecycle_points: BTreeMap<String, i32>,
+    /// Points by `crm_activity.activity_type`
+    pub activity_points: BTreeMap<String, f64>,
+    pub half_life_days: f64,
+    /// Activity, replies and mentions older than this are ignored
+    pub lookback_days: i64,
+    pub frequency: FrequencyRule,
+    pub recency: Vec<RecencyTier>,
+    pub email_replies: EventRule,
+    pub deal_value: Vec<DealValueTier>,
+    pub social_mentions: EventRule,
+    pub max_score: i32,
+}
+
+impl Default for LeadScoringRules {
+    fn default() -> Self {
+        let field_rule = |field: &str, points: i32| FieldRule {
+            field: field.to_string(),
+            operator: FieldRuleOperator::Present,
+            value: None,
+            points,
+            label: None,
+        };
+
+        Self {
+            field_rules: vec![
+                field_rule("job_title", 5),
+                field_rule("company_name", 5),
+                field_rule("phone", 3),
+                field_rule("linkedin_url", 2),
+            ],
+            lifecycle_points: [
+                ("subscriber", 0),
+                ("lead", 5),
+                ("mql", 15),
+                ("sql", 25),
+                ("opportunity", 30),
+                ("customer", 35),
+                ("evangelist", 35),
+                ("churned", -20),
+            ]
+            .into_iter()
+            .map(|(stage, points)| (stage.to_string(), points))
+            .collect(),
+            activity_points: [
+                ("email_sent", 1.0),
+                ("email_received", 6.0),
+                ("email_opened", 2.0),
+                ("email_clicked", 4.0),
+                ("call_made", 3.0),
+                ("call_received", 8.0),
+                ("meeting_scheduled", 10.0),
+                ("meeting_completed", 15.0),
+                ("form_submitted", 10.0),
+                ("page_visited", 1.0),
+                ("document_viewed", 4.0),
+                ("social_mention", 3.0),
+                ("social_dm", 5.0),
+                ("social_comment", 2.0),
+                ("custom", 2.0),
+            ]
+            .into_iter()
+            .map(|(activity, points)| (activity.to_string(), points))
+            .collect(),
+            half_life_days: 30.0,
+            lookback_days: 180,
+            frequency: FrequencyRule {
+                window_days: 30,
+                points_per_activity: 1.0,
+                max_points: 10.0,
+            },
+            recency: vec![
+                RecencyTier {
+                    within_days: 3,
+                    points: 10,
+                },
+                RecencyTier {
+                    within_days: 14,
+                    points: 5,
+                },
+                RecencyTier {
+                    within_days: 30,
+                    points: 2,
+                },
+            ],
+            email_replies: EventRule {
+                points: 5.0,
+                max_points: 20.0,
+            },
+            deal_value: vec![
+                DealValueTier {
+                    min_amount: 1_000.0,
+                    points: 5,
+                },
+                DealValueTier {
+                    min_amount: 10_000.0,
+                    points: 10,
+                },
+                DealValueTier {
+                    min_amount: 50_000.0,
+                    points: 20,
+                },
+            ],
+            social_mentions: EventRule {
+                points: 2.0,
+                max_points: 10.0,
+            },
+            max_score: 100,
+        }
+    }
+}
+
+impl LeadScoringRules {
+    pub fn validate(&self) -> Result<(), LeadScoringError> {
+        if self.half_life_days <= 0.0 {
+            return Err(LeadScoringError::InvalidRules(
+                "half_life_days must be positive".into(),
+            ));
+        }
+        if self.lookback_days <= 0 || self.frequency.window_days <= 0 {
+            return Err(LeadScoringError::InvalidRules(
+                "lookback_days and frequency.window_days must be positive".into(),
+            ));
+        }
+        if self.max_score <= 0 {
+            return Err(LeadScoringError::InvalidRules(
+                "max_score must be positive".into(),
+            ));
+        }
+        if let Some(rule) = self.field_rules.iter().find(|rule| {
+            matches!(
+                rule.operator,
+                FieldRuleOperator::Equals | FieldRuleOperator::Contains
+            ) && rule.value.is_none()
+        }) {
+            return Err(LeadScoringError::InvalidRules(format!(
+                "field rule on {} needs a value",
+                rule.field
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// What a score component was derived from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum LeadScoreCategory {
+    Field,
+    Lifecycle,
+    Activity,
+    Frequency,
+    Recency,
+    EmailReplies,
+    DealValue,
+    SocialMentions,
+    ManualAdjustment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+pub struct LeadScoreComponent {
+    pub category: LeadScoreCategory,
+    pub label: String,
+    pub points: f64,
+}
+
+/// A score and the components that add up to it
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+pub struct LeadScoreBreakdown {
+    pub crm_contact_id: Uuid,
+    /// Sum of the components, rounded and clamped to `0..=max_score`
+    pub score: i32,
+    pub raw_points: f64,
+    pub components: Vec<LeadScoreComponent>,
+    pub computed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LeadScoringConfig {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub is_enabled: bool,
+    #[ts(type = "LeadScoringRules")]
+    pub rules: Json<LeadScoringRules>,
+    pub recalc_interval_minutes: i64,
+    pub last_recalculated_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+#[ts(export)]
+pub struct UpsertLeadScoringConfig {
+    pub is_enabled: Option<bool>,
+    pub rules: Option<LeadScoringRules>,
+    pub recalc_interval_minutes: Option<i64>,
+}
+
+impl LeadScoringConfig {
+    pub async fn find_by_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Option<Self>, LeadScoringError> {
+        let config = sqlx::query_as::<_, LeadScoringConfig>(
+            r#"SELECT * FROM lead_scoring_configs WHERE project_id = ?1"#,
+        )
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(config)
+    }
+
+    /// The project's config, created with the default rules on first use
+    pub async fn find_or_create(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Self, LeadScoringError> {
+        if let Some(config) = Self::find_by_project(pool, project_id).await? {
+            return Ok(config);
+        }
+        Self::upsert(
+            pool,
+            project_id,
+            UpsertLeadScoringConfig {
+                is_enabled: None,
+                rules: None,
+                recalc_interval_minutes: None,
+            },
+        )
+        .await
+    }
+
+    pub async fn upsert(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        data: UpsertLeadScoringConfig,
+    ) -> Result<Self, LeadScoringError> {
+        if let Some(rules) = &data.rules {
+            rules.validate()?;
+        }
+        if data
+            .recalc_interval_minutes
+            .is_some_and(|minutes| minutes < 1)
+        {
+            return Err(LeadScoringError::InvalidRules(
+                "recalc_interval_minutes must be at least 1".into(),
+            ));
+        }
+        let rules = data.rules.map(serde_json::to_string).transpose()?;
+        let default_rules = serde_json::to_string(&LeadScoringRules::default())?;
+
+        let config = sqlx::query_as::<_, LeadScoringConfig>(
+            r#"
+            INSERT INTO lead_scoring_configs (
+                id, project_id, is_enabled, rules, recalc_interval_minutes
+            )
+            VALUES (?1, ?2, COALESCE(?3, 1), COALESCE(?4, ?5), COALESCE(?6, 360))
+            ON CONFLICT(project_id) DO UPDATE SET
+                is_enabled = COALESCE(?3, is_enabled),
+                rules = COALESCE(?4, rules),
+                recalc_interval_minutes = COALESCE(?6, recalc_interval_minutes),
+                updated_at = datetime('now', 'subsec')
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(project_id)
+        .bind(data.is_enabled)
+        .bind(rules)
+        .bind(default_rules)
+        .bind(data.recalc_interval_minutes)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(config)
+    }
+
+    /// Projects with contacts whose scores are due for their scheduled
+    /// recalculation. Projects without a config are always due.
+    pub async fn find_due_project_ids(pool: &SqlitePool) -> Result<Vec<Uuid>, LeadScoringError> {
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT DISTINCT c.project_id
+            FROM crm_contacts c
+            LEFT JOIN lead_scoring_configs l ON l.project_id = c.project_id
+            WHERE l.id IS NULL
+               OR (
+                    l.is_enabled = 1
+                    AND (
+                        l.last_recalculated_at IS NULL
+                        OR l.last_recalculated_at <= datetime('now', '-' || l.recalc_interval_minutes || ' minutes')
+                    )
+               )
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    pub async fn mark_recalculated(pool: &SqlitePool, id: Uuid) -> Result<(), LeadScoringError> {
+        sqlx::query(
+            r#"
+            UPDATE lead_scoring_configs SET
+                last_recalculated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// What caused a score to be recalculated
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum LeadScoreTrigger {
+    Activity,
+    ContactUpdated,
+    Schedule,
+    Manual,
+    ConfigChanged,
+}
+
+impl std::fmt::Display for LeadScoreTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            LeadScoreTrigger::Activity => "activity",
+            LeadScoreTrigger::ContactUpdated => "contact_updated",
+            LeadScoreTrigger::Schedule => "schedule",
+            LeadScoreTrigger::Manual => "manual",
+            LeadScoreTrigger::ConfigChanged => "config_changed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LeadScoreHistory {
+    pub id: Uuid,
+    pub crm_contact_id: Uuid,
+    pub project_id: Uuid,
+    pub score: i32,
+    pub previous_score: i32,
+    pub trigger: String,
+    #[ts(type = "LeadScoreBreakdown")]
+    pub breakdown: Json<LeadScoreBreakdown>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl LeadScoreHistory {
+    pub async fn create(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        previous_score: i32,
+        trigger: LeadScoreTrigger,
+        breakdown: &LeadScoreBreakdown,
+    ) -> Result<Self, LeadScoringError> {
+        let entry = sqlx::query_as::<_, LeadScoreHistory>(
+            r#"
+            INSERT INTO lead_score_history (
+                id, crm_contact_id, project_id, score, previous_score, trigger, breakdown
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(breakdown.crm_contact_id)
+        .bind(project_id)
+        .bind(breakdown.score)
+        .bind(previous_score)
+        .bind(trigger.to_string())
+        .bind(serde_json::to_string(breakdown)?)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(entry)
+    }
+
+    pub async fn find_by_contact(
+        pool: &SqlitePool,
+        crm_contact_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, LeadScoringError> {
+        let entries = sqlx::query_as::<_, LeadScoreHistory>(
+            r#"
+            SELECT * FROM lead_score_history
+            WHERE crm_contact_id = ?1
+            ORDER BY created_at DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(crm_contact_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_utils::{create_test_project, setup_test_pool};
+
+    #[test]
+    fn default_rules_are_valid() {
+        assert!(LeadScoringRules::default().validate().is_ok());
+    }
+
+    #[test]
+    fn partial_rules_fill_in_defaults() {
+        let rules: LeadScoringRules =
+            serde_json::from_str(r#"{ "max_score": 50, "half_life_days": 7 }"#).unwrap();
+        assert_eq!(rules.max_score, 50);
+        assert_eq!(rules.half_life_days, 7.0);
+        assert_eq!(rules.recency, LeadScoringRules::default().recency);
+    }
+
+    #[test]
+    fn rejects_value_rules_without_value() {
+        let mut rules = LeadScoringRules::default();
+        rules.field_rules.push(FieldRule {
+            field: "job_title".into(),
+            operator: FieldRuleOperator::Contains,
+            value: None,
+            points: 5,
+            label: None,
+        });
+        assert!(matches!(
+            rules.validate(),
+            Err(LeadScoringError::InvalidRules(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn upsert_keeps_unset_fields() {
+        let pool = setup_test_pool().await;
+        let project_id = create_test_project(&pool).await;
+
+        let created = LeadScoringConfig::find_or_create(&pool, project_id)
+            .await
+            .unwrap();
+        assert!(created.is_enabled);
+        assert_eq!(created.rules.0, LeadScoringRules::default());
+
+        let updated = LeadScoringConfig::upsert(
+            &pool,
+            project_id,
+            UpsertLeadScoringConfig {
+                is_enabled: Some(false),
+                rules: None,
+                recalc_interval_minutes: Some(60),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.id, created.id);
+        assert!(!updated.is_enabled);
+        assert_eq!(updated.recalc_interval_minutes, 60);
+        assert_eq!(updated.rules.0, LeadScoringRules::default());
+    }
+}
@@ -0,0 +1,670 @@
+//! Full-text search over execution logs and agent conversations
+//!
+//! Normalized coding-agent log entries are indexed as they are produced and
+//! agent conversation messages are indexed by a database trigger. Both land in
+//! `log_search_entries`, which backs the `log_search_fts` FTS5 index.
+
+use chrono::{DateTime, Utc};
+use executors::logs::{ActionType, NormalizedEntry, NormalizedEntryType};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use thiserror::Error;
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// Maximum number of characters of a single entry that is indexed
+const MAX_INDEXED_CHARS: usize = 16 * 1024;
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+
+pub const HIGHLIGHT_START: &str = "<mark>";
+pub const HIGHLIGHT_END: &str = "</mark>";
+
+/// Private-use characters FTS5 wraps matches in. The snippet is HTML-escaped
+/// before they are swapped for the real highlight tags.
+const MATCH_START: char = '\u{E000}';
+const MATCH_END: char = '\u{E001}';
+
+#[derive(Debug, Error)]
+pub enum LogSearchError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error("Search query is empty")]
+    EmptyQuery,
+}
+
+/// Where an indexed document came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum LogSearchSource {
+    ExecutionLog,
+    AgentMessage,
+}
+
+impl LogSearchSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogSearchSource::ExecutionLog => "execution_log",
+            LogSearchSource::AgentMessage => "agent_message",
+        }
+    }
+}
+
+/// Filter columns shared by every entry of one execution process
+#[derive(Debug, Clone, FromRow)]
+pub struct LogSearchContext {
+    pub execution_id: Uuid,
+    pub task_attempt_id: Uuid,
+    pub project_id: Uuid,
+    pub agent_id: Option<Uuid>,
+    pub executor: String,
+}
+
+impl LogSearchContext {
+    pub async fn for_execution(
+        pool: &SqlitePool,
+        execution_id: Uuid,
+    ) -> Result<Option<Self>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT ep.id AS execution_id,
+                   ta.id AS task_attempt_id,
+                   t.project_id AS project_id,
+                   t.agent_id AS agent_id,
+                   ta.executor AS executor
+            FROM execution_processes ep
+            JOIN task_attempts ta ON ta.id = ep.task_attempt_id
+            JOIN tasks t ON t.id = ta.task_id
+            WHERE ep.id = ?
+            "#,
+        )
+        .bind(execution_id)
+        .fetch_optional(pool)
+        .await
+    }
+}
+
+/// Searchable projection of a normalized log entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexableEntry {
+    pub entry_type: &'static str,
+    pub tool_name: Option<String>,
+    pub content: String,
+}
+
+impl IndexableEntry {
+    /// Project a normalized entry onto the text we want to search. Returns
+    /// `None` for entry kinds that are not worth indexing (thinking, loading,
+    /// system chatter).
+    pub fn from_normalized(entry: &NormalizedEntry) -> Option<Self> {
+        let (entry_type, tool_name, content) = match &entry.entry_type {
+            NormalizedEntryType::UserMessage => ("user_message", None, entry.content.clone()),
+            NormalizedEntryType::AssistantMessage => {
+                ("assistant_message", None, entry.content.clone())
+            }
+            NormalizedEntryType::ErrorMessage => ("error", None, entry.content.clone()),
+            NormalizedEntryType::ToolUse {
+                tool_name,
+                action_type,
+                ..
+            } => {
+                let mut parts = vec![entry.content.clone()];
+                let entry_type = match action_type {
+                    ActionType::CommandRun { command, result } => {
+                        parts.push(command.clone());
+                        if let Some(output) = result.as_ref().and_then(|r| r.output.as_ref()) {
+                            parts.push(output.clone());
+                        }
+                        "command"
+                    }
+                    ActionType::FileRead { path } => {
+                        parts.push(path.clone());
+                        "tool_use"
+                    }
+                    ActionType::FileEdit { path, .. } => {
+                        parts.push(path.clone());
+                        "tool_use"
+                    }
+                    ActionType::Search { query } => {
+                        parts.push(query.clone());
+                        "tool_use"
+                    }
+                    ActionType::WebFetch { url } => {
+                        parts.push(url.clone());
+                        "tool_use"
+                    }
+                    ActionType::Tool { arguments, .. } => {
+                        if let Some(arguments) = arguments {
+                            parts.push(arguments.to_string());
+                        }
+                        "tool_use"
+                    }
+                    ActionType::TaskCreate { description } => {
+                        parts.push(description.clone());
+                        "tool_use"
+                    }
+                    ActionType::PlanPresentation { plan } => {
+                        parts.push(plan.clone());
+                        "tool_use"
+                    }
+                    ActionType::TodoManagement { .. } | ActionType::Other { .. } => "tool_use",
+                };
+                parts.dedup();
+                (entry_type, Some(tool_name.clone()), parts.join("\n"))
+            }
+            NormalizedEntryType::SystemMessage
+            | NormalizedEntryType::Thinking
+            | NormalizedEntryType::Loading => return None,
+        };
+
+        let content =
+            truncate_chars(content.trim(), MAX_INDEXED_CHARS).replace([MATCH_START, MATCH_END], "");
+        if content.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            entry_type,
+            tool_name,
+            content,
+        })
+    }
+}
+
+/// Escape a raw FTS5 snippet for HTML and turn its match markers into
+/// highlight tags
+fn highlight_snippet(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        match c {
+            MATCH_START => out.push_str(HIGHLIGHT_START),
+            MATCH_END => out.push_str(HIGHLIGHT_END),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn truncate_chars(s: &str, max: usize) -> String {
+    match s.char_indices().nth(max) {
+        Some((idx, _)) => s[..idx].to_string(),
+        None => s.to_string(),
+    }
+}
+
+/// Turn free-form user input into a safe FTS5 query. Every whitespace
+/// separated term is quoted so punctuation in paths like `src/auth.rs` does not
+/// trip the FTS5 parser; a trailing `*` is kept as a prefix match. Terms are
+/// ANDed together.
+pub fn to_fts_query(raw: &str) -> Option<String> {
+    let terms: Vec<String> = raw
+        .split_whitespace()
+        .filter_map(|term| {
+            let (body, prefix) = match term.strip_suffix('*') {
+                Some(body) => (body, true),
+                None => (term, false),
+            };
+            if body.is_empty() {
+                return None;
+            }
+            let quoted = format!("\"{}\"", body.replace('"', "\"\""));
+            Some(if prefix { format!("{quoted}*") } else { quoted })
+        })
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// Query parameters for a search
+#[derive(Debug, Clone, Default, Deserialize, TS)]
+#[ts(export)]
+pub struct LogSearchQuery {
+    pub q: String,
+    pub project_id: Option<Uuid>,
+    pub agent_id: Option<Uuid>,
+    pub task_attempt_id: Option<Uuid>,
+    pub executor: Option<String>,
+    pub source: Option<LogSearchSource>,
+    pub entry_type: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// A single ranked search result
+#[derive(Debug, Clone, FromRow, Serialize, TS)]
+#[ts(export)]
+pub struct LogSearchHit {
+    pub source_type: String,
+    /// Execution process id for logs, conversation id for agent messages
+    pub source_id: Uuid,
+    pub entry_key: String,
+    pub entry_type: String,
+    pub tool_name: Option<String>,
+    pub project_id: Option<Uuid>,
+    pub task_attempt_id: Option<Uuid>,
+    pub task_id: Option<Uuid>,
+    pub task_title: Option<String>,
+    pub agent_id: Option<Uuid>,
+    pub executor: Option<String>,
+    /// HTML-escaped matching excerpt with terms wrapped in `<mark>` tags
+    pub snippet: String,
+    /// bm25 score; lower is more relevant
+    pub rank: f64,
+    pub created_at: DateTime<Utc>,
+}
+
+pub struct LogSearchEntry;
+
+impl LogSearchEntry {
+    /// Insert or refresh the indexed copy of a normalized log entry. Tool calls
+    /// are re-emitted with updated status/results under the same index, so the
+    /// row is keyed by `(execution_id, entry_index)`.
+    pub async fn index_normalized_entry(
+        pool: &SqlitePool,
+        ctx: &LogSearchContext,
+        entry_index: usize,
+        entry: &NormalizedEntry,
+    ) -> Result<(), sqlx::Error> {
+        let mut conn = pool.acquire().await?;
+        Self::upsert(&mut conn, ctx, entry_index, entry).await?;
+        Ok(())
+    }
+
+    /// Replace every indexed entry of an execution with `entries` in one
+    /// transaction, so searches never see a half-rebuilt index. Returns the
+    /// number of entries indexed.
+    pub async fn reindex_execution(
+        pool: &SqlitePool,
+        ctx: &LogSearchContext,
+        entries: &[(usize, NormalizedEntry)],
+    ) -> Result<usize, sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            "DELETE FROM log_search_entries WHERE source_type = 'execution_log' AND source_id = ?",
+        )
+        .bind(ctx.execution_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let mut indexed = 0;
+        for (entry_index, entry) in entries {
+            if Self::upsert(&mut tx, ctx, *entry_index, entry).await? {
+                indexed += 1;
+            }
+        }
+        tx.commit().await?;
+        Ok(indexed)
+    }
+
+    /// Returns whether the entry was worth indexing
+    async fn upsert(
+        conn: &mut SqliteConnection,
+        ctx: &LogSearchContext,
+        entry_index: usize,
+        entry: &NormalizedEntry,
+    ) -> Result<bool, sqlx::Error> {
+        let Some(indexable) = IndexableEntry::from_normalized(entry) else {
+            return Ok(false);
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO log_search_entries (
+                source_type, source_id, entry_key, entry_type, tool_name,
+                project_id, task_attempt_id, agent_id, executor, content
+            )
+            VALUES ('execution_log', ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (source_type, source_id, entry_key) DO UPDATE
+            SET entry_type = excluded.entry_type,
+                tool_name = excluded.tool_name,
+                content = excluded.content
+            WHERE content != excluded.content
+               OR entry_type != excluded.entry_type
+            "#,
+        )
+        .bind(ctx.execution_id)
+        .bind(entry_index.to_string())
+        .bind(indexable.entry_type)
+        .bind(&indexable.tool_name)
+        .bind(ctx.project_id)
+        .bind(ctx.task_attempt_id)
+        .bind(ctx.agent_id)
+        .bind(&ctx.executor)
+        .bind(&indexable.content)
+        .execute(conn)
+        .await?;
+
+        Ok(true)
+    }
+
+    /// Run a ranked full-text search with optional filters
+    pub async fn search(
+        pool: &SqlitePool,
+        query: &LogSearchQuery,
+    ) -> Result<Vec<LogSearchHit>, LogSearchError> {
+        let fts_query = to_fts_query(&query.q).ok_or(LogSearchError::EmptyQuery)?;
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let offset = query.offset.unwrap_or(0).max(0);
+
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            r#"SELECT e.source_type, e.source_id, e.entry_key, e.entry_type, e.tool_name,
+                      e.project_id, e.task_attempt_id, ta.task_id AS task_id,
+                      t.title AS task_title, e.agent_id, e.executor,
+                      snippet(log_search_fts, 0, "#,
+        );
+        builder.push_bind(MATCH_START.to_string());
+        builder.push(", ");
+        builder.push_bind(MATCH_END.to_string());
+        builder.push(
+            r#", '…', 24) AS snippet,
+                      bm25(log_search_fts) AS rank,
+                      e.created_at
+               FROM log_search_fts
+               JOIN log_search_entries e ON e.id = log_search_fts.rowid
+               LEFT JOIN task_attempts ta ON ta.id = e.task_attempt_id
+               LEFT JOIN tasks t ON t.id = ta.task_id
+               WHERE log_search_fts MATCH "#,
+        );
+        builder.push_bind(fts_query);
+
+        if let Some(project_id) = query.project_id {
+            builder.push(" AND e.project_id = ").push_bind(project_id);
+        }
+        if let Some(agent_id) = query.agent_id {
+            builder.push(" AND e.agent_id = ").push_bind(agent_id);
+        }
+        if let Some(task_attempt_id) = query.task_attempt_id {
+            builder
+                .push(" AND e.task_attempt_id = ")
+                .push_bind(task_attempt_id);
+        }
+        if let Some(executor) = &query.executor {
+            builder
+                .push(" AND e.executor = ")
+                .push_bind(executor.to_uppercase());
+        }
+        if let Some(source) = query.source {
+            builder
+                .push(" AND e.source_type = ")
+                .push_bind(source.as_str());
+        }
+        if let Some(entry_type) = &query.entry_type {
+            builder
+                .push(" AND e.entry_type = ")
+                .push_bind(entry_type.clone());
+        }
+        if let Some(since) = query.since {
+            builder
+                .push(" AND datetime(e.created_at) >= datetime(")
+                .push_bind(since)
+                .push(")");
+        }
+        if let Some(until) = query.until {
+            builder
+                .push(" AND datetime(e.created_at) < datetime(")
+                .push_bind(until)
+                .push(")");
+        }
+
+        builder
+            .push(" ORDER BY rank LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let mut hits = builder
+            .build_query_as::<LogSearchHit>()
+            .fetch_all(pool)
+            .await?;
+        for hit in &mut hits {
+            hit.snippet = highlight_snippet(&hit.snippet);
+        }
+        Ok(hits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use executors::logs::{CommandRunResult, ToolStatus};
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+    use super::*;
+
+    async fn setup_pool() -> SqlitePool {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .expect("invalid sqlite config")
+            .foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .expect("failed to open sqlite memory db");
+        sqlx::migrate!("./migrations")
+            .run(&pool)
+            .await
+            .expect("failed to run migrations");
+        pool
+    }
+
+    async fn create_execution(pool: &SqlitePool) -> LogSearchContext {
+        let (project_id, task_id, attempt_id, execution_id) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        sqlx::query("INSERT INTO projects (id, name, git_repo_path) VALUES (?, 'Search', ?)")
+            .bind(project_id)
+            .bind(format!("/tmp/search-{project_id}"))
+            .execute(pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO tasks (id, project_id, title) VALUES (?, ?, 'Fix login')")
+            .bind(task_id)
+            .bind(project_id)
+            .execute(pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO task_attempts (id, task_id, executor) VALUES (?, ?, 'CLAUDE_CODE')",
+        )
+        .bind(attempt_id)
+        .bind(task_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO execution_processes (id, task_attempt_id, executor_action)
+             VALUES (?, ?, '{}')",
+        )
+        .bind(execution_id)
+        .bind(attempt_id)
+        .execute(pool)
+        .await
+        .unwrap();
+        LogSearchContext::for_execution(pool, execution_id)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    fn search_query(ctx: &LogSearchContext, q: &str) -> LogSearchQuery {
+        LogSearchQuery {
+            q: q.to_string(),
+            project_id: Some(ctx.project_id),
+            ..Default::default()
+        }
+    }
+
+    fn entry(entry_type: NormalizedEntryType, content: &str) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type,
+            content: content.to_string(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn fts_query_quotes_terms_and_keeps_prefix() {
+        assert_eq!(
+            to_fts_query("auth middleware").as_deref(),
+            Some("\"auth\" \"middleware\"")
+        );
+        assert_eq!(
+            to_fts_query("src/auth.rs midd*").as_deref(),
+            Some("\"src/auth.rs\" \"midd\"*")
+        );
+        assert_eq!(
+            to_fts_query("say \"hi\"").as_deref(),
+            Some("\"say\" \"\"\"hi\"\"\"")
+        );
+        assert_eq!(to_fts_query("   * "), None);
+    }
+
+    #[test]
+    fn command_entries_include_command_and_output() {
+        let e = entry(
+            NormalizedEntryType::ToolUse {
+                tool_name: "bash".into(),
+                action_type: ActionType::CommandRun {
+                    command: "cargo test -p auth".into(),
+                    result: Some(CommandRunResult {
+                        exit_status: None,
+                        output: Some("test result: ok".into()),
+                    }),
+                },
+                status: ToolStatus::Success,
+            },
+            "cargo test -p auth",
+        );
+
+        let indexable = IndexableEntry::from_normalized(&e).unwrap();
+        assert_eq!(indexable.entry_type, "command");
+        assert_eq!(indexable.tool_name.as_deref(), Some("bash"));
+        assert_eq!(indexable.content, "cargo test -p auth\ntest result: ok");
+    }
+
+    #[test]
+    fn file_edits_index_the_path() {
+        let e = entry(
+            NormalizedEntryType::ToolUse {
+                tool_name: "edit".into(),
+                action_type: ActionType::FileEdit {
+                    path: "src/middleware/auth.rs".into(),
+                    changes: vec![],
+                },
+                status: ToolStatus::Success,
+            },
+            "Edit auth middleware",
+        );
+
+        let indexable = IndexableEntry::from_normalized(&e).unwrap();
+        assert_eq!(indexable.entry_type, "tool_use");
+        assert!(indexable.content.contains("src/middleware/auth.rs"));
+    }
+
+    #[test]
+    fn thinking_and_empty_entries_are_skipped() {
+        assert!(
+            IndexableEntry::from_normalized(&entry(NormalizedEntryType::Thinking, "hmm")).is_none()
+        );
+        assert!(
+            IndexableEntry::from_normalized(&entry(NormalizedEntryType::AssistantMessage, "  "))
+                .is_none()
+        );
+        assert_eq!(
+            IndexableEntry::from_normalized(&entry(NormalizedEntryType::ErrorMessage, "boom"))
+                .unwrap()
+                .entry_type,
+            "error"
+        );
+    }
+
+    #[test]
+    fn snippets_are_escaped_around_highlights() {
+        let raw = format!("<script>x</script> & {MATCH_START}auth{MATCH_END} \"ok\"");
+        assert_eq!(
+            highlight_snippet(&raw),
+            "&lt;script&gt;x&lt;/script&gt; &amp; <mark>auth</mark> &quot;ok&quot;"
+        );
+    }
+
+    #[tokio::test]
+    async fn indexed_logs_are_searchable_and_reindex_replaces_them() {
+        let pool = setup_pool().await;
+        let ctx = create_execution(&pool).await;
+        let entries = vec![
+            (
+                0,
+                entry(
+                    NormalizedEntryType::AssistantMessage,
+                    "Fixed the <b>auth</b> middleware redirect loop",
+                ),
+            ),
+            (
+                1,
+                entry(NormalizedEntryType::Thinking, "middleware thoughts"),
+            ),
+        ];
+        let indexed = LogSearchEntry::reindex_execution(&pool, &ctx, &entries)
+            .await
+            .unwrap();
+        assert_eq!(indexed, 1);
+
+        let hits = LogSearchEntry::search(&pool, &search_query(&ctx, "midd*"))
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        let hit = &hits[0];
+        assert_eq!(hit.source_id, ctx.execution_id);
+        assert_eq!(hit.entry_key, "0");
+        assert_eq!(hit.task_title.as_deref(), Some("Fix login"));
+        assert!(hit.snippet.contains("<mark>middleware</mark>"));
+        assert!(hit.snippet.contains("&lt;b&gt;auth&lt;/b&gt;"));
+
+        let other_project = LogSearchQuery {
+            project_id: Some(Uuid::new_v4()),
+            ..search_query(&ctx, "middleware")
+        };
+        assert!(
+            LogSearchEntry::search(&pool, &other_project)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let entries = vec![(
+            0,
+            entry(NormalizedEntryType::ErrorMessage, "panic in router"),
+        )];
+        LogSearchEntry::reindex_execution(&pool, &ctx, &entries)
+            .await
+            .unwrap();
+        assert!(
+            LogSearchEntry::search(&pool, &search_query(&ctx, "middleware"))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let hits = LogSearchEntry::search(&pool, &search_query(&ctx, "router"))
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entry_type, "error");
+    }
+}
@@ -0,0 +1,391 @@
+//! Bearer tokens and tool-call audit log for the HTTP MCP endpoints
+//!
+//! Each token belongs to a user and carries that user's permissions into the
+//! MCP servers. Tokens are stored hashed with the same SHA256 scheme as
+//! session tokens; the plaintext is returned once, at creation.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{FromRow, SqlitePool, types::Json};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::services::AuthService;
+
+/// Prefix that makes MCP tokens recognisable in configs and secret scanners
+pub const MCP_TOKEN_PREFIX: &str = "pcg_mcp_";
+
+/// MCP server a token may talk to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum McpScope {
+    Tasks,
+    Nora,
+}
+
+impl McpScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            McpScope::Tasks => "tasks",
+            McpScope::Nora => "nora",
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct McpAccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    #[serde(skip)]
+    #[ts(skip)]
+    pub token_hash: String,
+    #[ts(type = "McpScope[]")]
+    pub scopes: Json<Vec<McpScope>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, TS)]
+#[ts(export)]
+pub struct CreateMcpAccessToken {
+    pub name: String,
+    pub scopes: Vec<McpScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// The user behind a valid token
+#[derive(Debug, Clone)]
+pub struct McpTokenIdentity {
+    pub token_id: Uuid,
+    pub user_id: Uuid,
+    pub is_admin: bool,
+    pub scopes: Vec<McpScope>,
+}
+
+impl McpTokenIdentity {
+    pub fn allows(&self, scope: McpScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
+
+/// Outcome of presenting a bearer token
+#[derive(Debug, Clone)]
+pub enum McpTokenAuth {
+    Valid(McpTokenIdentity),
+    /// A known token that is revoked, expired or belongs to a deactivated user
+    Rejected {
+        token_id: Uuid,
+    },
+    /// No token matches
+    Unknown,
+}
+
+#[derive(FromRow)]
+struct TokenUserRow {
+    id: Uuid,
+    user_id: Uuid,
+    scopes: Json<Vec<McpScope>>,
+    expires_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+    is_admin: bool,
+    is_active: bool,
+}
+
+impl McpAccessToken {
+    /// Create a token for `user_id`. Returns the row and the plaintext token,
+    /// which is not stored.
+    pub async fn create(
+        pool: &SqlitePool,
+        user_id: Uuid,
+        data: &CreateMcpAccessToken,
+    ) -> Result<(Self, String), sqlx::Error> {
+        let token = format!(
+            "{}{}{}",
+            MCP_TOKEN_PREFIX,
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+        let mut scopes = Vec::new();
+        for scope in &data.scopes {
+            if !scopes.contains(scope) {
+                scopes.push(*scope);
+            }
+        }
+
+        let row = sqlx::query_as(
+            r#"
+            INSERT INTO mcp_access_tokens (id, user_id, name, token_hash, scopes, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(&data.name)
+        .bind(AuthService::hash_session_token(&token))
+        .bind(Json(scopes))
+        .bind(data.expires_at)
+        .fetch_one(pool)
+        .await?;
+
+        Ok((row, token))
+    }
+
+    pub async fn find_by_user(pool: &SqlitePool, user_id: Uuid) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM mcp_access_tokens WHERE user_id = ? ORDER BY created_at DESC")
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Revoke one of `user_id`'s tokens. Returns false if there was no such
+    /// live token.
+    pub async fn revoke(pool: &SqlitePool, id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE mcp_access_tokens SET revoked_at = ?
+            WHERE id = ? AND user_id = ? AND revoked_at IS NULL
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Resolve a presented bearer token. Revoked or expired tokens and
+    /// tokens of deactivated users are rejected with their id, so callers can
+    /// drop anything cached for them.
+    pub async fn authenticate(pool: &SqlitePool, token: &str) -> Result<McpTokenAuth, sqlx::Error> {
+        if !token.starts_with(MCP_TOKEN_PREFIX) {
+            return Ok(McpTokenAuth::Unknown);
+        }
+        let row: Option<TokenUserRow> = sqlx::query_as(
+            r#"
+            SELECT t.id, t.user_id, t.scopes, t.expires_at, t.revoked_at,
+                   u.is_admin, u.is_active
+            FROM mcp_access_tokens t
+            JOIN users u ON t.user_id = u.id
+            WHERE t.token_hash = ?
+            "#,
+        )
+        .bind(AuthService::hash_session_token(token))
+        .fetch_optional(pool)
+        .await?;
+
+        let Some(row) = row else {
+            return Ok(McpTokenAuth::Unknown);
+        };
+        let expired = row.expires_at.is_some_and(|at| at <= Utc::now());
+        if row.revoked_at.is_some() || expired || !row.is_active {
+            return Ok(McpTokenAuth::Rejected { token_id: row.id });
+        }
+
+        sqlx::query("UPDATE mcp_access_tokens SET last_used_at = ? WHERE id = ?")
+            .bind(Utc::now())
+            .bind(row.id)
+            .execute(pool)
+            .await?;
+
+        Ok(McpTokenAuth::Valid(McpTokenIdentity {
+            token_id: row.id,
+            user_id: row.user_id,
+            is_admin: row.is_admin,
+            scopes: row.scopes.0,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct McpAuditEntry {
+    pub id: Uuid,
+    pub token_id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub server: String,
+    pub tool: String,
+    #[ts(type = "Record<string, unknown> | null")]
+    pub arguments: Option<Json<Value>>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateMcpAuditEntry {
+    pub token_id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub server: McpScope,
+    pub tool: String,
+    pub arguments: Option<Value>,
+    pub success: bool,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+}
+
+impl McpAuditEntry {
+    pub async fn record(pool: &SqlitePool, data: &CreateMcpAuditEntry) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO mcp_audit_log
+                (id, token_id, user_id, server, tool, arguments, success, error, duration_ms)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(data.token_id)
+        .bind(data.user_id)
+        .bind(data.server.as_str())
+        .bind(&data.tool)
+        .bind(data.arguments.clone().map(Json))
+        .bind(data.success)
+        .bind(&data.error)
+        .bind(data.duration_ms)
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Most recent calls, optionally limited to one user
+    pub async fn find_recent(
+        pool: &SqlitePool,
+        user_id: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT * FROM mcp_audit_log
+            WHERE (?1 IS NULL OR user_id = ?1)
+            ORDER BY created_at DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(user_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::models::test_utils::{create_test_user, setup_test_pool};
+
+    fn token_data(
+        scopes: Vec<McpScope>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> CreateMcpAccessToken {
+        CreateMcpAccessToken {
+            name: "ci".to_string(),
+            scopes,
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn authenticate_accepts_only_live_tokens() {
+        let pool = setup_test_pool().await;
+        let user_id = create_test_user(&pool, false).await;
+
+        let (token, secret) =
+            McpAccessToken::create(&pool, user_id, &token_data(vec![McpScope::Tasks], None))
+                .await
+                .unwrap();
+        let McpTokenAuth::Valid(identity) =
+            McpAccessToken::authenticate(&pool, &secret).await.unwrap()
+        else {
+            panic!("live token was not accepted");
+        };
+        assert_eq!(identity.token_id, token.id);
+        assert_eq!(identity.user_id, user_id);
+        assert!(!identity.is_admin);
+
+        // Right prefix, wrong secret
+        let wrong = format!("{}{}", MCP_TOKEN_PREFIX, Uuid::new_v4().simple());
+        assert!(matches!(
+            McpAccessToken::authenticate(&pool, &wrong).await.unwrap(),
+            McpTokenAuth::Unknown
+        ));
+        assert!(matches!(
+            McpAccessToken::authenticate(&pool, "not-a-token")
+                .await
+                .unwrap(),
+            McpTokenAuth::Unknown
+        ));
+
+        assert!(
+            McpAccessToken::revoke(&pool, token.id, user_id)
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            McpAccessToken::authenticate(&pool, &secret).await.unwrap(),
+            McpTokenAuth::Rejected { token_id } if token_id == token.id
+        ));
+    }
+
+    #[tokio::test]
+    async fn authenticate_rejects_expired_tokens() {
+        let pool = setup_test_pool().await;
+        let user_id = create_test_user(&pool, false).await;
+
+        // Creation checks expiry in the route, so the model accepts a past date
+        let expired_at = Utc::now() - Duration::minutes(1);
+        let (token, secret) = McpAccessToken::create(
+            &pool,
+            user_id,
+            &token_data(vec![McpScope::Tasks], Some(expired_at)),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(
+            McpAccessToken::authenticate(&pool, &secret).await.unwrap(),
+            McpTokenAuth::Rejected { token_id } if token_id == token.id
+        ));
+    }
+
+    #[tokio::test]
+    async fn revoke_is_limited_to_the_owner() {
+        let pool = setup_test_pool().await;
+        let owner = create_test_user(&pool, false).await;
+        let other = create_test_user(&pool, true).await;
+        let (token, secret) =
+            McpAccessToken::create(&pool, owner, &token_data(vec![McpScope::Tasks], None))
+                .await
+                .unwrap();
+
+        assert!(
+            !McpAccessToken::revoke(&pool, token.id, other)
+                .await
+                .unwrap()
+        );
+        assert!(matches!(
+            McpAccessToken::authenticate(&pool, &secret).await.unwrap(),
+            McpTokenAuth::Valid(_)
+        ));
+    }
+
+    #[test]
+    fn scopes_gate_each_server() {
+        let identity = McpTokenIdentity {
+            token_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            is_admin: true,
+            scopes: vec![McpScope::Tasks],
+        };
+        assert!(identity.allows(McpScope::Tasks));
+        // Being an admin does not widen what the token was issued for
+        assert!(!identity.allows(McpScope::Nora));
+    }
+}
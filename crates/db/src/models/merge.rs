@@ -14,6 +14,38 @@ pub enum MergeStatus {
     Unknown,
 }
 
+/// Code forge hosting a pull/merge request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize, TS, Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum ForgeType {
+    #[default]
+    Github,
+    Gitlab,
+    /// Gitea and Forgejo share the same API
+    Gitea,
+}
+
+impl ForgeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ForgeType::Github => "github",
+            ForgeType::Gitlab => "gitlab",
+            ForgeType::Gitea => "gitea",
+        }
+    }
+}
+
+impl std::fmt::Display for ForgeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ForgeType::Github => "GitHub",
+            ForgeType::Gitlab => "GitLab",
+            ForgeType::Gitea => "Gitea",
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Merge {
@@ -37,6 +69,7 @@ pub struct PrMerge {
     pub task_attempt_id: Uuid,
     pub created_at: DateTime<Utc>,
     pub target_branch_name: String,
+    pub forge: ForgeType,
     pub pr_info: PullRequestInfo,
 }
 
@@ -68,6 +101,7 @@ struct MergeRow {
     pr_status: Option<MergeStatus>,
     pr_merged_at: Option<DateTime<Utc>>,
     pr_merge_commit_sha: Option<String>,
+    pr_forge: ForgeType,
     created_at: DateTime<Utc>,
 }
 
@@ -104,6 +138,7 @@ impl Merge {
                 pr_status as "pr_status?: MergeStatus",
                 pr_merged_at as "pr_merged_at?: DateTime<Utc>",
                 pr_merge_commit_sha,
+                pr_forge as "pr_forge!: ForgeType",
                 created_at as "created_at!: DateTime<Utc>",
                 target_branch_name as "target_branch_name!: String"
             "#,
@@ -124,6 +159,7 @@ impl Merge {
         target_branch_name: &str,
         pr_number: i64,
         pr_url: &str,
+        forge: ForgeType,
     ) -> Result<PrMerge, sqlx::Error> {
         let id = Uuid::new_v4();
         let now = Utc::now();
@@ -131,8 +167,8 @@ impl Merge {
         sqlx::query_as!(
             MergeRow,
             r#"INSERT INTO merges (
-                id, task_attempt_id, merge_type, pr_number, pr_url, pr_status, created_at, target_branch_name
-            ) VALUES ($1, $2, 'pr', $3, $4, 'open', $5, $6)
+                id, task_attempt_id, merge_type, pr_number, pr_url, pr_status, pr_forge, created_at, target_branch_name
+            ) VALUES ($1, $2, 'pr', $3, $4, 'open', $5, $6, $7)
             RETURNING 
                 id as "id!: Uuid",
                 task_attempt_id as "task_attempt_id!: Uuid",
@@ -143,6 +179,7 @@ impl Merge {
                 pr_status as "pr_status?: MergeStatus",
                 pr_merged_at as "pr_merged_at?: DateTime<Utc>",
                 pr_merge_commit_sha,
+                pr_forge as "pr_forge!: ForgeType",
                 created_at as "created_at!: DateTime<Utc>",
                 target_branch_name as "target_branch_name!: String"
             "#,
@@ -150,6 +187,7 @@ impl Merge {
             task_attempt_id,
             pr_number,
             pr_url,
+            forge,
             now,
             target_branch_name
         )
@@ -172,6 +210,7 @@ impl Merge {
                 pr_status as "pr_status?: MergeStatus",
                 pr_merged_at as "pr_merged_at?: DateTime<Utc>",
                 pr_merge_commit_sha,
+                pr_forge as "pr_forge!: ForgeType",
                 created_at as "created_at!: DateTime<Utc>",
                 target_branch_name as "target_branch_name!: String"
                FROM merges 
@@ -231,8 +270,9 @@ impl Merge {
                 pr_status as "pr_status?: MergeStatus",
                 pr_merged_at as "pr_merged_at?: DateTime<Utc>",
                 pr_merge_commit_sha,
-                target_branch_name as "target_branch_name!: String",
-                created_at as "created_at!: DateTime<Utc>"
+                pr_forge as "pr_forge!: ForgeType",
+                created_at as "created_at!: DateTime<Utc>",
+                target_branch_name as "target_branch_name!: String"
             FROM merges 
             WHERE task_attempt_id = $1
             ORDER BY created_at DESC"#,
@@ -277,6 +317,7 @@ impl From<MergeRow> for PrMerge {
             id: row.id,
             task_attempt_id: row.task_attempt_id,
             target_branch_name: row.target_branch_name,
+            forge: row.pr_forge,
             pr_info: PullRequestInfo {
                 number: row.pr_number.expect("pr merge must have pr_number"),
                 url: row.pr_url.expect("pr merge must have pr_url"),
@@ -4,13 +4,19 @@ pub mod agent_conversation;
 pub mod agent_flow;
 pub mod brand_profile;
 pub mod agent_flow_event;
+pub mod cms_entity;
 pub mod cms_faq_item;
 pub mod cms_page_section;
 pub mod cms_product;
+pub mod cms_published_entity;
+pub mod cms_revision;
+pub mod cms_scheduled_publication;
 pub mod cms_site;
 pub mod cms_site_setting;
+pub mod cms_webhook;
 pub mod airtable_base;
 pub mod airtable_record_link;
+pub mod airtable_table_mapping;
 pub mod agent_task_plan;
 pub mod agent_wallet;
 pub mod approval_gate;
@@ -25,19 +31,23 @@ pub mod custom_field_definition;
 pub mod cinematic_brief;
 pub mod context_injection;
 pub mod execution_checkpoint;
+pub mod execution_log_archive;
 pub mod execution_artifact;
 pub mod execution_handoff;
 pub mod execution_pause_history;
 pub mod execution_process;
 pub mod execution_slot;
 pub mod execution_process_logs;
+pub mod log_search;
 pub mod execution_summary;
 pub mod executor_session;
 pub mod follow_up_draft;
 pub mod image;
 pub mod dropbox_source;
+pub mod mcp_access;
 pub mod media_batch;
 pub mod merge;
+pub mod pr_feedback;
 pub mod nora_config;
 pub mod project;
 pub mod project_asset;
@@ -58,12 +68,24 @@ pub mod social_account;
 pub mod social_post;
 pub mod social_mention;
 pub mod email_account;
+pub mod email_folder;
+pub mod email_message;
+pub mod email_sequence;
 pub mod crm_contact;
+pub mod crm_activity;
+pub mod crm_deal;
+pub mod crm_pipeline;
+pub mod call_log;
+pub mod call_turn;
+pub mod sms_message;
+pub mod sms_opt_out;
+pub mod lead_scoring;
+pub mod zoho_integration;
 pub mod model_pricing;
 pub mod vibe_deposit;
 pub mod vibe_transaction;
 pub mod peer_node;
 pub mod peer_reward;
 
-#[cfg(test)]
-pub(crate) mod test_utils;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
@@ -0,0 +1,98 @@
+//! Bookkeeping for PR review and CI feedback relayed to coding agents
+//!
+//! `services::pr_monitor` polls open PRs for review comments, change requests
+//! and failed checks. Each item is recorded here once it has been turned into a
+//! follow-up prompt so later polls only pick up what is new.
+
+use serde::{Deserialize, Serialize};
+use sqlx::{SqlitePool, Type};
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, TS, Type)]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum PrFeedbackKind {
+    ReviewComment,
+    ChangeRequest,
+    FailedCheck,
+}
+
+/// Forge-side identity of a feedback item
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrFeedbackKey {
+    pub kind: PrFeedbackKind,
+    pub external_id: String,
+}
+
+pub struct PrFeedbackItem;
+
+impl PrFeedbackItem {
+    /// The subset of `keys` that has not been relayed for this merge yet
+    pub async fn filter_unseen(
+        pool: &SqlitePool,
+        merge_id: Uuid,
+        keys: Vec<PrFeedbackKey>,
+    ) -> Result<Vec<PrFeedbackKey>, sqlx::Error> {
+        let seen: Vec<(PrFeedbackKind, String)> =
+            sqlx::query_as("SELECT kind, external_id FROM pr_feedback_items WHERE merge_id = ?")
+                .bind(merge_id)
+                .fetch_all(pool)
+                .await?;
+
+        Ok(keys
+            .into_iter()
+            .filter(|key| {
+                !seen
+                    .iter()
+                    .any(|(kind, id)| *kind == key.kind && *id == key.external_id)
+            })
+            .collect())
+    }
+
+    /// Record `keys` as relayed together in one follow-up
+    pub async fn record_batch(
+        pool: &SqlitePool,
+        merge_id: Uuid,
+        keys: &[PrFeedbackKey],
+        auto_started: bool,
+    ) -> Result<Uuid, sqlx::Error> {
+        let batch_id = Uuid::new_v4();
+        let mut tx = pool.begin().await?;
+        for key in keys {
+            sqlx::query(
+                r#"
+                INSERT INTO pr_feedback_items (id, merge_id, kind, external_id, batch_id, auto_started)
+                VALUES (?, ?, ?, ?, ?, ?)
+                ON CONFLICT(merge_id, kind, external_id) DO NOTHING
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(merge_id)
+            .bind(key.kind)
+            .bind(&key.external_id)
+            .bind(batch_id)
+            .bind(auto_started)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(batch_id)
+    }
+
+    /// Number of feedback rounds that were started without a human in the loop
+    pub async fn count_auto_started_batches(
+        pool: &SqlitePool,
+        merge_id: Uuid,
+    ) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT COUNT(DISTINCT batch_id) FROM pr_feedback_items
+            WHERE merge_id = ? AND auto_started = 1
+            "#,
+        )
+        .bind(merge_id)
+        .fetch_one(pool)
+        .await
+    }
+}
@@ -133,6 +133,7 @@ pub struct UpdateSmsMessage {
     pub auto_response: Option<String>,
     pub sentiment: Option<String>,
     pub crm_contact_id: Option<Uuid>,
+    pub crm_deal_id: Option<Uuid>,
     pub is_read: Option<bool>,
     pub is_starred: Option<bool>,
     pub needs_response: Option<bool>,
@@ -292,6 +293,7 @@ impl SmsMessage {
                 needs_response = COALESCE(?10, needs_response),
                 responded_at = COALESCE(?11, responded_at),
                 price = COALESCE(?12, price),
+                crm_deal_id = COALESCE(?13, crm_deal_id),
                 updated_at = datetime('now', 'subsec')
             WHERE id = ?1
             RETURNING *
@@ -309,11 +311,35 @@ impl SmsMessage {
         .bind(data.needs_response.map(|b| if b { 1 } else { 0 }))
         .bind(data.responded_at)
         .bind(data.price)
+        .bind(data.crm_deal_id)
         .fetch_optional(pool)
         .await?
         .ok_or(SmsMessageError::NotFound)
     }
 
+    /// Messages not yet linked to a CRM contact, oldest first
+    pub async fn find_unlinked(
+        pool: &SqlitePool,
+        created_after: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<Self>, SmsMessageError> {
+        let messages = sqlx::query_as::<_, SmsMessage>(
+            r#"
+            SELECT * FROM sms_messages
+            WHERE crm_contact_id IS NULL
+              AND (?1 IS NULL OR created_at > datetime(?1, 'subsec'))
+            ORDER BY created_at
+            LIMIT ?2
+            "#,
+        )
+        .bind(created_after)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(messages)
+    }
+
     pub async fn mark_as_read(pool: &SqlitePool, id: Uuid) -> Result<(), SmsMessageError> {
         sqlx::query(r#"UPDATE sms_messages SET is_read = 1, updated_at = datetime('now', 'subsec') WHERE id = ?1"#)
             .bind(id)
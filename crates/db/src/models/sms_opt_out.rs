@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+use ts_rs::TS;
+use uuid::Uuid;
+
+#[derive(Debug, Error)]
+pub enum SmsOptOutError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+/// A number that asked not to receive any more texts
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SmsOptOut {
+    pub id: Uuid,
+    pub phone_number: String,
+    pub keyword: String,
+    pub opted_out_at: DateTime<Utc>,
+}
+
+impl SmsOptOut {
+    /// Record an opt-out. Repeating it keeps the original date but remembers
+    /// the latest keyword.
+    pub async fn opt_out(
+        pool: &SqlitePool,
+        phone_number: &str,
+        keyword: &str,
+    ) -> Result<Self, SmsOptOutError> {
+        let opt_out = sqlx::query_as::<_, SmsOptOut>(
+            r#"
+            INSERT INTO sms_opt_outs (id, phone_number, keyword)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT(phone_number) DO UPDATE SET keyword = excluded.keyword
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(phone_number)
+        .bind(keyword)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(opt_out)
+    }
+
+    /// Allow texting a number again. Returns whether it had opted out.
+    pub async fn opt_in(pool: &SqlitePool, phone_number: &str) -> Result<bool, SmsOptOutError> {
+        let result = sqlx::query(r#"DELETE FROM sms_opt_outs WHERE phone_number = ?1"#)
+            .bind(phone_number)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn is_opted_out(
+        pool: &SqlitePool,
+        phone_number: &str,
+    ) -> Result<bool, SmsOptOutError> {
+        let found: Option<i64> =
+            sqlx::query_scalar(r#"SELECT 1 FROM sms_opt_outs WHERE phone_number = ?1"#)
+                .bind(phone_number)
+                .fetch_optional(pool)
+                .await?;
+
+        Ok(found.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_utils::setup_test_pool;
+
+    #[tokio::test]
+    async fn stop_and_start_round_trip() {
+        let pool = setup_test_pool().await;
+        let number = "+447700900456";
+
+        assert!(!SmsOptOut::is_opted_out(&pool, number).await.unwrap());
+
+        let first = SmsOptOut::opt_out(&pool, number, "STOP").await.unwrap();
+        let again = SmsOptOut::opt_out(&pool, number, "UNSUBSCRIBE")
+            .await
+            .unwrap();
+        assert_eq!(first.id, again.id);
+        assert_eq!(again.keyword, "UNSUBSCRIBE");
+        assert!(SmsOptOut::is_opted_out(&pool, number).await.unwrap());
+
+        assert!(SmsOptOut::opt_in(&pool, number).await.unwrap());
+        assert!(!SmsOptOut::opt_in(&pool, number).await.unwrap());
+        assert!(!SmsOptOut::is_opted_out(&pool, number).await.unwrap());
+    }
+}
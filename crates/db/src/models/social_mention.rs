@@ -261,6 +261,31 @@ impl SocialMention {
         Ok(mentions)
     }
 
+    /// Mentions written by a handle (without `@`, case-insensitive) since a point in time
+    pub async fn find_by_author(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        author_username: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Self>, SocialMentionError> {
+        let mentions = sqlx::query_as::<_, SocialMention>(
+            r#"
+            SELECT * FROM social_mentions
+            WHERE project_id = ?1
+              AND LOWER(LTRIM(author_username, '@')) = LOWER(LTRIM(?2, '@'))
+              AND datetime(received_at) >= datetime(?3)
+            ORDER BY received_at DESC
+            "#,
+        )
+        .bind(project_id)
+        .bind(author_username)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(mentions)
+    }
+
     pub async fn update(
         pool: &SqlitePool,
         id: Uuid,
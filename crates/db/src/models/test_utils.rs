@@ -3,10 +3,11 @@ use std::str::FromStr;
 use sqlx::{sqlite::{SqliteConnectOptions, SqlitePoolOptions}, SqlitePool};
 use uuid::Uuid;
 
+use super::cms_site::{CmsSite, CreateCmsSite};
 use super::project::{CreateProject, Project};
 use super::social_account::{CreateSocialAccount, SocialAccount, SocialPlatform};
 
-pub(crate) async fn setup_test_pool() -> SqlitePool {
+pub async fn setup_test_pool() -> SqlitePool {
     let options = SqliteConnectOptions::from_str("sqlite::memory:?cache=shared")
         .expect("invalid sqlite config")
         .create_if_missing(true)
@@ -167,6 +168,8 @@ async fn bootstrap_schema(pool: &SqlitePool) {
             smtp_host TEXT,
             smtp_port INTEGER,
             use_ssl INTEGER DEFAULT 1,
+            imap_username TEXT,
+            imap_password TEXT,
             granted_scopes TEXT,
             storage_used_bytes INTEGER,
             storage_total_bytes INTEGER,
@@ -181,9 +184,400 @@ async fn bootstrap_schema(pool: &SqlitePool) {
             signature TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            send_window_start TEXT NOT NULL DEFAULT '09:00',
+            send_window_end TEXT NOT NULL DEFAULT '17:00',
+            send_utc_offset_minutes INTEGER NOT NULL DEFAULT 0,
+            daily_send_limit INTEGER NOT NULL DEFAULT 50,
             UNIQUE(project_id, provider, email_address)
         );
         "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS lead_scoring_configs (
+            id BLOB PRIMARY KEY,
+            project_id BLOB NOT NULL UNIQUE REFERENCES projects(id) ON DELETE CASCADE,
+            is_enabled INTEGER NOT NULL DEFAULT 1,
+            rules TEXT NOT NULL,
+            recalc_interval_minutes INTEGER NOT NULL DEFAULT 360,
+            last_recalculated_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS email_sequences (
+            id BLOB PRIMARY KEY,
+            project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            email_account_id BLOB NOT NULL REFERENCES email_accounts(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            description TEXT,
+            status TEXT NOT NULL DEFAULT 'draft',
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS email_sequence_steps (
+            id BLOB PRIMARY KEY,
+            sequence_id BLOB NOT NULL REFERENCES email_sequences(id) ON DELETE CASCADE,
+            position INTEGER NOT NULL,
+            step_type TEXT NOT NULL,
+            subject TEXT,
+            body_text TEXT,
+            body_html TEXT,
+            wait_business_days INTEGER,
+            task_title TEXT,
+            task_description TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            UNIQUE(sequence_id, position)
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS custom_field_definitions (
+            id BLOB PRIMARY KEY,
+            project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            field_type TEXT NOT NULL,
+            required INTEGER NOT NULL DEFAULT 0,
+            options TEXT,
+            default_value TEXT,
+            metadata TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS crm_contacts (
+            id BLOB PRIMARY KEY,
+            project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            first_name TEXT,
+            last_name TEXT,
+            full_name TEXT,
+            email TEXT,
+            phone TEXT,
+            mobile TEXT,
+            avatar_url TEXT,
+            company_name TEXT,
+            job_title TEXT,
+            department TEXT,
+            linkedin_url TEXT,
+            twitter_handle TEXT,
+            website TEXT,
+            source TEXT,
+            lifecycle_stage TEXT DEFAULT 'lead',
+            lead_score INTEGER DEFAULT 0,
+            last_activity_at TEXT,
+            last_contacted_at TEXT,
+            last_replied_at TEXT,
+            owner_user_id TEXT,
+            assigned_agent_id BLOB REFERENCES agents(id) ON DELETE SET NULL,
+            zoho_contact_id TEXT,
+            gmail_contact_id TEXT,
+            external_ids TEXT,
+            tags TEXT,
+            lists TEXT,
+            custom_fields TEXT,
+            address_line1 TEXT,
+            address_line2 TEXT,
+            city TEXT,
+            state TEXT,
+            postal_code TEXT,
+            country TEXT,
+            email_opt_in INTEGER DEFAULT 1,
+            sms_opt_in INTEGER DEFAULT 0,
+            do_not_contact INTEGER DEFAULT 0,
+            email_count INTEGER DEFAULT 0,
+            meeting_count INTEGER DEFAULT 0,
+            deal_count INTEGER DEFAULT 0,
+            total_revenue REAL DEFAULT 0.0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            lead_score_adjustment INTEGER NOT NULL DEFAULT 0,
+            UNIQUE(project_id, email)
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS zoho_integrations (
+            id BLOB PRIMARY KEY,
+            project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            zoho_org_id TEXT,
+            zoho_domain TEXT,
+            access_token TEXT,
+            refresh_token TEXT,
+            token_expires_at TEXT,
+            granted_scopes TEXT,
+            sync_contacts INTEGER DEFAULT 1,
+            sync_deals INTEGER DEFAULT 1,
+            sync_activities INTEGER DEFAULT 1,
+            sync_direction TEXT DEFAULT 'bidirectional',
+            contact_field_mapping TEXT,
+            deal_field_mapping TEXT,
+            last_contact_sync_at TEXT,
+            last_deal_sync_at TEXT,
+            last_activity_sync_at TEXT,
+            status TEXT NOT NULL DEFAULT 'active',
+            last_error TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            conflict_rules TEXT,
+            UNIQUE(project_id)
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS zoho_sync_records (
+            id BLOB PRIMARY KEY,
+            integration_id BLOB NOT NULL REFERENCES zoho_integrations(id) ON DELETE CASCADE,
+            entity_type TEXT NOT NULL,
+            local_id BLOB NOT NULL,
+            zoho_id TEXT NOT NULL,
+            zoho_modified_at TEXT,
+            snapshot TEXT,
+            synced_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            UNIQUE(integration_id, entity_type, local_id),
+            UNIQUE(integration_id, entity_type, zoho_id)
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS zoho_sync_conflicts (
+            id BLOB PRIMARY KEY,
+            integration_id BLOB NOT NULL REFERENCES zoho_integrations(id) ON DELETE CASCADE,
+            entity_type TEXT NOT NULL,
+            local_id BLOB NOT NULL,
+            zoho_id TEXT NOT NULL,
+            field TEXT NOT NULL,
+            local_value TEXT,
+            zoho_value TEXT,
+            strategy TEXT NOT NULL,
+            winner TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS call_logs (
+            id BLOB PRIMARY KEY,
+            project_id BLOB NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            call_sid TEXT NOT NULL UNIQUE,
+            parent_call_sid TEXT,
+            account_sid TEXT,
+            from_number TEXT NOT NULL,
+            to_number TEXT NOT NULL,
+            from_formatted TEXT,
+            to_formatted TEXT,
+            caller_name TEXT,
+            direction TEXT NOT NULL,
+            status TEXT NOT NULL,
+            answered_by TEXT,
+            start_time TEXT,
+            end_time TEXT,
+            duration_seconds INTEGER DEFAULT 0,
+            recording_url TEXT,
+            recording_sid TEXT,
+            recording_duration INTEGER,
+            transcription TEXT,
+            transcription_status TEXT,
+            handled_by_agent_id BLOB,
+            conversation_id BLOB,
+            summary TEXT,
+            sentiment TEXT,
+            crm_contact_id BLOB,
+            crm_deal_id BLOB,
+            price REAL,
+            price_unit TEXT DEFAULT 'USD',
+            metadata TEXT,
+            action_items TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS call_turns (
+            id BLOB PRIMARY KEY,
+            call_log_id BLOB NOT NULL REFERENCES call_logs(id) ON DELETE CASCADE,
+            speaker TEXT NOT NULL CHECK (speaker IN ('caller', 'nora')),
+            content TEXT NOT NULL,
+            confidence REAL,
+            spoken_at TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS sms_opt_outs (
+            id BLOB PRIMARY KEY,
+            phone_number TEXT NOT NULL UNIQUE,
+            keyword TEXT NOT NULL,
+            opted_out_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS airtable_bases (
+            id TEXT PRIMARY KEY NOT NULL,
+            project_id TEXT NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+            airtable_base_id TEXT NOT NULL,
+            airtable_base_name TEXT,
+            sync_enabled INTEGER NOT NULL DEFAULT 1,
+            default_table_id TEXT,
+            last_synced_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            UNIQUE(project_id, airtable_base_id)
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS airtable_record_links (
+            id TEXT PRIMARY KEY NOT NULL,
+            task_id TEXT NOT NULL UNIQUE REFERENCES tasks(id) ON DELETE CASCADE,
+            airtable_record_id TEXT NOT NULL,
+            airtable_base_id TEXT NOT NULL,
+            airtable_table_id TEXT,
+            origin TEXT NOT NULL DEFAULT 'airtable',
+            sync_status TEXT NOT NULL DEFAULT 'synced',
+            last_sync_error TEXT,
+            airtable_record_url TEXT,
+            last_synced_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            snapshot TEXT
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS airtable_table_mappings (
+            id BLOB PRIMARY KEY,
+            connection_id BLOB NOT NULL REFERENCES airtable_bases(id) ON DELETE CASCADE,
+            airtable_table_id TEXT NOT NULL,
+            airtable_table_name TEXT,
+            board_id BLOB,
+            field_mappings TEXT NOT NULL DEFAULT '[]',
+            modified_field TEXT,
+            import_new_records INTEGER NOT NULL DEFAULT 1,
+            push_new_tasks INTEGER NOT NULL DEFAULT 0,
+            delete_remote_records INTEGER NOT NULL DEFAULT 1,
+            delete_local_tasks INTEGER NOT NULL DEFAULT 0,
+            sync_enabled INTEGER NOT NULL DEFAULT 1,
+            last_pulled_at TEXT,
+            last_sync_error TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            UNIQUE(connection_id, airtable_table_id)
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS airtable_deleted_records (
+            id BLOB PRIMARY KEY,
+            airtable_base_id TEXT NOT NULL,
+            airtable_table_id TEXT,
+            airtable_record_id TEXT NOT NULL,
+            deleted_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TRIGGER IF NOT EXISTS airtable_record_links_task_deleted
+        AFTER DELETE ON airtable_record_links
+        WHEN NOT EXISTS (SELECT 1 FROM tasks WHERE id = OLD.task_id)
+        BEGIN
+            INSERT INTO airtable_deleted_records (id, airtable_base_id, airtable_table_id, airtable_record_id)
+            VALUES (randomblob(16), OLD.airtable_base_id, OLD.airtable_table_id, OLD.airtable_record_id);
+        END;
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS users (
+            id BLOB PRIMARY KEY NOT NULL,
+            username TEXT NOT NULL UNIQUE,
+            email TEXT NOT NULL UNIQUE,
+            password_hash TEXT NOT NULL,
+            full_name TEXT NOT NULL,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            is_admin INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS mcp_access_tokens (
+            id BLOB PRIMARY KEY,
+            user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            scopes TEXT NOT NULL DEFAULT '["tasks"]',
+            expires_at TEXT,
+            last_used_at TEXT,
+            revoked_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now', 'subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS cms_sites (
+            id BLOB PRIMARY KEY,
+            slug TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            domain TEXT NOT NULL UNIQUE,
+            theme_config TEXT DEFAULT '{}',
+            is_active INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS cms_products (
+            id BLOB PRIMARY KEY,
+            site_id BLOB NOT NULL REFERENCES cms_sites(id) ON DELETE CASCADE,
+            slug TEXT NOT NULL,
+            name TEXT NOT NULL,
+            short_description TEXT,
+            long_description TEXT,
+            price_cents INTEGER NOT NULL,
+            currency TEXT NOT NULL DEFAULT 'USD',
+            stripe_price_id TEXT,
+            image_url TEXT,
+            gallery_images TEXT DEFAULT '[]',
+            specs TEXT DEFAULT '{}',
+            features TEXT DEFAULT '[]',
+            is_active INTEGER NOT NULL DEFAULT 1,
+            is_featured INTEGER NOT NULL DEFAULT 0,
+            stock_status TEXT DEFAULT 'in_stock',
+            sort_order INTEGER DEFAULT 0,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            UNIQUE(site_id, slug)
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS cms_faq_items (
+            id BLOB PRIMARY KEY,
+            site_id BLOB NOT NULL REFERENCES cms_sites(id) ON DELETE CASCADE,
+            category TEXT,
+            question TEXT NOT NULL,
+            answer TEXT NOT NULL,
+            sort_order INTEGER DEFAULT 0,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now','subsec'))
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS cms_published_entities (
+            site_id BLOB NOT NULL REFERENCES cms_sites(id) ON DELETE CASCADE,
+            entity_type TEXT NOT NULL,
+            entity_id BLOB NOT NULL,
+            data TEXT NOT NULL,
+            revision_id BLOB,
+            published_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            PRIMARY KEY (entity_type, entity_id)
+        );
+        "#,
+        r#"
+        CREATE TABLE IF NOT EXISTS cms_revisions (
+            id BLOB PRIMARY KEY,
+            site_id BLOB NOT NULL REFERENCES cms_sites(id) ON DELETE CASCADE,
+            entity_type TEXT NOT NULL,
+            entity_id BLOB NOT NULL,
+            version INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            data TEXT NOT NULL,
+            user_id BLOB,
+            created_at TEXT NOT NULL DEFAULT (datetime('now','subsec')),
+            UNIQUE(entity_type, entity_id, version)
+        );
+        "#,
     ];
 
     for statement in statements {
@@ -194,7 +588,7 @@ async fn bootstrap_schema(pool: &SqlitePool) {
     }
 }
 
-pub(crate) async fn create_test_project(pool: &SqlitePool) -> Uuid {
+pub async fn create_test_project(pool: &SqlitePool) -> Uuid {
     let project_id = Uuid::new_v4();
     let data = CreateProject {
         name: format!("Test Project {}", project_id),
@@ -213,7 +607,22 @@ pub(crate) async fn create_test_project(pool: &SqlitePool) -> Uuid {
     project_id
 }
 
-pub(crate) async fn create_test_social_account(
+pub async fn create_test_user(pool: &SqlitePool, is_admin: bool) -> Uuid {
+    let user_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password_hash, full_name, is_admin) VALUES (?, ?, ?, '', 'Test User', ?)",
+    )
+    .bind(user_id)
+    .bind(format!("user-{}", user_id))
+    .bind(format!("{}@example.com", user_id))
+    .bind(is_admin)
+    .execute(pool)
+    .await
+    .expect("failed to create test user");
+    user_id
+}
+
+pub async fn create_test_social_account(
     pool: &SqlitePool,
     project_id: Uuid,
 ) -> SocialAccount {
@@ -237,3 +646,18 @@ pub(crate) async fn create_test_social_account(
     .await
     .expect("failed to create test social account")
 }
+
+pub async fn create_test_cms_site(pool: &SqlitePool) -> CmsSite {
+    let slug = format!("site-{}", Uuid::new_v4());
+    CmsSite::create(
+        pool,
+        &CreateCmsSite {
+            name: "Test Site".into(),
+            domain: format!("{}.example", slug),
+            slug,
+            theme_config: None,
+        },
+    )
+    .await
+    .expect("failed to create test cms site")
+}
@@ -0,0 +1,894 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, SqlitePool};
+use thiserror::Error;
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::{crm_activity::CrmActivity, crm_contact::CrmContact, crm_deal::CrmDeal};
+
+#[derive(Debug, Error)]
+pub enum ZohoIntegrationError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error("Zoho integration not found")]
+    NotFound,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ZohoSyncDirection {
+    ToZoho,
+    FromZoho,
+    Bidirectional,
+}
+
+impl ZohoSyncDirection {
+    pub fn pulls(self) -> bool {
+        self != ZohoSyncDirection::ToZoho
+    }
+
+    pub fn pushes(self) -> bool {
+        self != ZohoSyncDirection::FromZoho
+    }
+}
+
+impl std::fmt::Display for ZohoSyncDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ZohoSyncDirection::ToZoho => "to_zoho",
+            ZohoSyncDirection::FromZoho => "from_zoho",
+            ZohoSyncDirection::Bidirectional => "bidirectional",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl std::str::FromStr for ZohoSyncDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "to_zoho" => Ok(ZohoSyncDirection::ToZoho),
+            "from_zoho" => Ok(ZohoSyncDirection::FromZoho),
+            "bidirectional" => Ok(ZohoSyncDirection::Bidirectional),
+            _ => Err(format!("Unknown sync direction: {}", s)),
+        }
+    }
+}
+
+/// How a field that changed on both sides since the last sync is resolved
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// The side whose record was modified last wins
+    #[default]
+    NewestWins,
+    /// Zoho, as the system of record, wins
+    SourceWins,
+}
+
+impl std::fmt::Display for ConflictStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ConflictStrategy::NewestWins => "newest_wins",
+            ConflictStrategy::SourceWins => "source_wins",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Conflict strategies keyed by local field name, e.g. `email` or
+/// `custom_fields.region`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+pub struct ConflictRules {
+    #[serde(default)]
+    pub default: ConflictStrategy,
+    #[serde(default)]
+    pub fields: BTreeMap<String, ConflictStrategy>,
+}
+
+impl ConflictRules {
+    pub fn strategy_for(&self, field: &str) -> ConflictStrategy {
+        self.fields.get(field).copied().unwrap_or(self.default)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq, Hash)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ZohoEntity {
+    Contact,
+    Deal,
+    Activity,
+}
+
+impl std::fmt::Display for ZohoEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ZohoEntity::Contact => "contact",
+            ZohoEntity::Deal => "deal",
+            ZohoEntity::Activity => "activity",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ZohoIntegration {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub zoho_org_id: Option<String>,
+    pub zoho_domain: Option<String>,
+    #[serde(skip_serializing)]
+    #[ts(skip)]
+    pub access_token: Option<String>,
+    #[serde(skip_serializing)]
+    #[ts(skip)]
+    pub refresh_token: Option<String>,
+    pub token_expires_at: Option<DateTime<Utc>>,
+    pub granted_scopes: Option<String>,
+    pub sync_contacts: Option<i32>,
+    pub sync_deals: Option<i32>,
+    pub sync_activities: Option<i32>,
+    pub sync_direction: Option<String>,
+    /// JSON object of local field -> Zoho field, replacing the defaults
+    pub contact_field_mapping: Option<String>,
+    pub deal_field_mapping: Option<String>,
+    pub last_contact_sync_at: Option<DateTime<Utc>>,
+    pub last_deal_sync_at: Option<DateTime<Utc>>,
+    pub last_activity_sync_at: Option<DateTime<Utc>>,
+    pub status: String,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub conflict_rules: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, TS)]
+#[ts(export)]
+pub struct UpdateZohoIntegration {
+    pub zoho_domain: Option<String>,
+    pub sync_contacts: Option<bool>,
+    pub sync_deals: Option<bool>,
+    pub sync_activities: Option<bool>,
+    pub sync_direction: Option<ZohoSyncDirection>,
+    pub contact_field_mapping: Option<BTreeMap<String, String>>,
+    pub deal_field_mapping: Option<BTreeMap<String, String>>,
+    pub conflict_rules: Option<ConflictRules>,
+}
+
+/// OAuth grant for connecting a project to Zoho
+#[derive(Debug)]
+pub struct ZohoConnection {
+    pub zoho_domain: String,
+    pub access_token: Option<String>,
+    pub refresh_token: String,
+    pub token_expires_at: Option<DateTime<Utc>>,
+    pub granted_scopes: Option<Vec<String>>,
+}
+
+impl ZohoIntegration {
+    pub fn direction(&self) -> ZohoSyncDirection {
+        self.sync_direction
+            .as_deref()
+            .and_then(|d| d.parse().ok())
+            .unwrap_or(ZohoSyncDirection::Bidirectional)
+    }
+
+    pub fn conflict_rules(&self) -> ConflictRules {
+        self.conflict_rules
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn contact_mapping(&self) -> Option<BTreeMap<String, String>> {
+        self.contact_field_mapping
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+    }
+
+    pub fn deal_mapping(&self) -> Option<BTreeMap<String, String>> {
+        self.deal_field_mapping
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+    }
+
+    pub fn syncs(&self, entity: ZohoEntity) -> bool {
+        let flag = match entity {
+            ZohoEntity::Contact => self.sync_contacts,
+            ZohoEntity::Deal => self.sync_deals,
+            ZohoEntity::Activity => self.sync_activities,
+        };
+        flag.unwrap_or(1) == 1
+    }
+
+    pub fn last_synced_at(&self, entity: ZohoEntity) -> Option<DateTime<Utc>> {
+        match entity {
+            ZohoEntity::Contact => self.last_contact_sync_at,
+            ZohoEntity::Deal => self.last_deal_sync_at,
+            ZohoEntity::Activity => self.last_activity_sync_at,
+        }
+    }
+
+    /// Check if the token needs refreshing (missing, expired or expiring within 5 minutes)
+    pub fn needs_token_refresh(&self) -> bool {
+        match (&self.access_token, &self.token_expires_at) {
+            (None, _) => true,
+            (Some(_), Some(expires)) => *expires <= Utc::now() + chrono::Duration::minutes(5),
+            (Some(_), None) => false,
+        }
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: Uuid) -> Result<Self, ZohoIntegrationError> {
+        sqlx::query_as::<_, ZohoIntegration>(r#"SELECT * FROM zoho_integrations WHERE id = ?1"#)
+            .bind(id)
+            .fetch_optional(pool)
+            .await?
+            .ok_or(ZohoIntegrationError::NotFound)
+    }
+
+    pub async fn find_by_project(
+        pool: &SqlitePool,
+        project_id: Uuid,
+    ) -> Result<Option<Self>, ZohoIntegrationError> {
+        let integration = sqlx::query_as::<_, ZohoIntegration>(
+            r#"SELECT * FROM zoho_integrations WHERE project_id = ?1"#,
+        )
+        .bind(project_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(integration)
+    }
+
+    /// Connected integrations that the scheduler should sync
+    pub async fn find_active(pool: &SqlitePool) -> Result<Vec<Self>, ZohoIntegrationError> {
+        let integrations = sqlx::query_as::<_, ZohoIntegration>(
+            r#"
+            SELECT * FROM zoho_integrations
+            WHERE status IN ('active', 'error') AND refresh_token IS NOT NULL
+            ORDER BY created_at
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(integrations)
+    }
+
+    /// Create or update the project's sync settings
+    pub async fn upsert_settings(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        data: UpdateZohoIntegration,
+    ) -> Result<Self, ZohoIntegrationError> {
+        let contact_mapping = data
+            .contact_field_mapping
+            .map(|m| serde_json::to_string(&m).unwrap_or_default());
+        let deal_mapping = data
+            .deal_field_mapping
+            .map(|m| serde_json::to_string(&m).unwrap_or_default());
+        let conflict_rules = data
+            .conflict_rules
+            .map(|r| serde_json::to_string(&r).unwrap_or_default());
+
+        let integration = sqlx::query_as::<_, ZohoIntegration>(
+            r#"
+            INSERT INTO zoho_integrations (
+                id, project_id, zoho_domain, sync_contacts, sync_deals, sync_activities,
+                sync_direction, contact_field_mapping, deal_field_mapping, conflict_rules,
+                status
+            )
+            VALUES (
+                ?1, ?2, COALESCE(?3, 'com'), COALESCE(?4, 1), COALESCE(?5, 1), COALESCE(?6, 1),
+                COALESCE(?7, 'bidirectional'), ?8, ?9, ?10, 'pending_auth'
+            )
+            ON CONFLICT(project_id) DO UPDATE SET
+                zoho_domain = COALESCE(?3, zoho_domain),
+                sync_contacts = COALESCE(?4, sync_contacts),
+                sync_deals = COALESCE(?5, sync_deals),
+                sync_activities = COALESCE(?6, sync_activities),
+                sync_direction = COALESCE(?7, sync_direction),
+                contact_field_mapping = COALESCE(?8, contact_field_mapping),
+                deal_field_mapping = COALESCE(?9, deal_field_mapping),
+                conflict_rules = COALESCE(?10, conflict_rules),
+                updated_at = datetime('now', 'subsec')
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(project_id)
+        .bind(&data.zoho_domain)
+        .bind(data.sync_contacts)
+        .bind(data.sync_deals)
+        .bind(data.sync_activities)
+        .bind(data.sync_direction.map(|d| d.to_string()))
+        .bind(contact_mapping)
+        .bind(deal_mapping)
+        .bind(conflict_rules)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(integration)
+    }
+
+    /// Store an OAuth grant and activate the integration
+    pub async fn connect(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        connection: ZohoConnection,
+    ) -> Result<Self, ZohoIntegrationError> {
+        let scopes = connection
+            .granted_scopes
+            .map(|s| serde_json::to_string(&s).unwrap_or_default());
+
+        let integration = sqlx::query_as::<_, ZohoIntegration>(
+            r#"
+            INSERT INTO zoho_integrations (
+                id, project_id, zoho_domain, access_token, refresh_token, token_expires_at,
+                granted_scopes, status
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'active')
+            ON CONFLICT(project_id) DO UPDATE SET
+                zoho_domain = ?3,
+                access_token = ?4,
+                refresh_token = ?5,
+                token_expires_at = ?6,
+                granted_scopes = COALESCE(?7, granted_scopes),
+                status = 'active',
+                last_error = NULL,
+                updated_at = datetime('now', 'subsec')
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(project_id)
+        .bind(&connection.zoho_domain)
+        .bind(&connection.access_token)
+        .bind(&connection.refresh_token)
+        .bind(connection.token_expires_at)
+        .bind(scopes)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(integration)
+    }
+
+    pub async fn update_tokens(
+        pool: &SqlitePool,
+        id: Uuid,
+        access_token: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), ZohoIntegrationError> {
+        sqlx::query(
+            r#"
+            UPDATE zoho_integrations SET
+                access_token = ?2,
+                token_expires_at = ?3,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(access_token)
+        .bind(expires_at)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remember how far the pull of a module got
+    pub async fn mark_synced(
+        pool: &SqlitePool,
+        id: Uuid,
+        entity: ZohoEntity,
+        at: DateTime<Utc>,
+    ) -> Result<(), ZohoIntegrationError> {
+        let column = match entity {
+            ZohoEntity::Contact => "last_contact_sync_at",
+            ZohoEntity::Deal => "last_deal_sync_at",
+            ZohoEntity::Activity => "last_activity_sync_at",
+        };
+        let query = format!(
+            r#"
+            UPDATE zoho_integrations SET
+                {} = datetime(?2, 'subsec'),
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+            column
+        );
+
+        sqlx::query(&query).bind(id).bind(at).execute(pool).await?;
+        Ok(())
+    }
+
+    /// Record the outcome of a sync run; `None` clears a previous error
+    pub async fn set_result(
+        pool: &SqlitePool,
+        id: Uuid,
+        error: Option<&str>,
+    ) -> Result<(), ZohoIntegrationError> {
+        sqlx::query(
+            r#"
+            UPDATE zoho_integrations SET
+                status = CASE WHEN ?2 IS NULL THEN 'active' ELSE 'error' END,
+                last_error = ?2,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The refresh token was rejected; stop syncing until reconnected
+    pub async fn revoke(
+        pool: &SqlitePool,
+        id: Uuid,
+        error: &str,
+    ) -> Result<(), ZohoIntegrationError> {
+        sqlx::query(
+            r#"
+            UPDATE zoho_integrations SET
+                status = 'revoked',
+                access_token = NULL,
+                refresh_token = NULL,
+                last_error = ?2,
+                updated_at = datetime('now', 'subsec')
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, id: Uuid) -> Result<(), ZohoIntegrationError> {
+        let result = sqlx::query(r#"DELETE FROM zoho_integrations WHERE id = ?1"#)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(ZohoIntegrationError::NotFound);
+        }
+        Ok(())
+    }
+}
+
+/// Link between a local record and its Zoho counterpart
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ZohoSyncRecord {
+    pub id: Uuid,
+    pub integration_id: Uuid,
+    pub entity_type: String,
+    pub local_id: Uuid,
+    pub zoho_id: String,
+    pub zoho_modified_at: Option<DateTime<Utc>>,
+    pub snapshot: Option<String>,
+    pub synced_at: DateTime<Utc>,
+}
+
+impl ZohoSyncRecord {
+    /// Zoho field values both sides held after the last sync
+    pub fn snapshot(&self) -> BTreeMap<String, String> {
+        self.snapshot
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default()
+    }
+
+    pub async fn find_by_local(
+        pool: &SqlitePool,
+        integration_id: Uuid,
+        entity: ZohoEntity,
+        local_id: Uuid,
+    ) -> Result<Option<Self>, ZohoIntegrationError> {
+        let record = sqlx::query_as::<_, ZohoSyncRecord>(
+            r#"
+            SELECT * FROM zoho_sync_records
+            WHERE integration_id = ?1 AND entity_type = ?2 AND local_id = ?3
+            "#,
+        )
+        .bind(integration_id)
+        .bind(entity.to_string())
+        .bind(local_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    pub async fn find_by_zoho_id(
+        pool: &SqlitePool,
+        integration_id: Uuid,
+        entity: ZohoEntity,
+        zoho_id: &str,
+    ) -> Result<Option<Self>, ZohoIntegrationError> {
+        let record = sqlx::query_as::<_, ZohoSyncRecord>(
+            r#"
+            SELECT * FROM zoho_sync_records
+            WHERE integration_id = ?1 AND entity_type = ?2 AND zoho_id = ?3
+            "#,
+        )
+        .bind(integration_id)
+        .bind(entity.to_string())
+        .bind(zoho_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    /// Link a local record to a Zoho record after syncing it. Also stamps the
+    /// Zoho id on contacts and deals so the rest of the CRM can see it.
+    pub async fn upsert(
+        pool: &SqlitePool,
+        integration_id: Uuid,
+        entity: ZohoEntity,
+        local_id: Uuid,
+        zoho_id: &str,
+        zoho_modified_at: Option<DateTime<Utc>>,
+        snapshot: &BTreeMap<String, String>,
+    ) -> Result<Self, ZohoIntegrationError> {
+        let mut tx = pool.begin().await?;
+
+        let record = sqlx::query_as::<_, ZohoSyncRecord>(
+            r#"
+            INSERT INTO zoho_sync_records (
+                id, integration_id, entity_type, local_id, zoho_id, zoho_modified_at, snapshot
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, datetime(?6, 'subsec'), ?7)
+            ON CONFLICT(integration_id, entity_type, local_id) DO UPDATE SET
+                zoho_id = ?5,
+                zoho_modified_at = COALESCE(datetime(?6, 'subsec'), zoho_modified_at),
+                snapshot = ?7,
+                synced_at = datetime('now', 'subsec')
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(integration_id)
+        .bind(entity.to_string())
+        .bind(local_id)
+        .bind(zoho_id)
+        .bind(zoho_modified_at)
+        .bind(serde_json::to_string(snapshot).unwrap_or_default())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let stamp = match entity {
+            ZohoEntity::Contact => {
+                Some(r#"UPDATE crm_contacts SET zoho_contact_id = ?2 WHERE id = ?1"#)
+            }
+            ZohoEntity::Deal => Some(r#"UPDATE crm_deals SET zoho_deal_id = ?2 WHERE id = ?1"#),
+            ZohoEntity::Activity => None,
+        };
+        if let Some(stamp) = stamp {
+            sqlx::query(stamp)
+                .bind(local_id)
+                .bind(zoho_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(record)
+    }
+
+    /// Contacts of the project edited locally since their last sync, or never synced
+    pub async fn changed_contacts(
+        pool: &SqlitePool,
+        integration: &ZohoIntegration,
+    ) -> Result<Vec<CrmContact>, ZohoIntegrationError> {
+        let contacts = sqlx::query_as::<_, CrmContact>(
+            r#"
+            SELECT c.* FROM crm_contacts c
+            LEFT JOIN zoho_sync_records r
+              ON r.integration_id = ?2 AND r.entity_type = 'contact' AND r.local_id = c.id
+            WHERE c.project_id = ?1
+              AND (r.id IS NULL OR c.updated_at > r.synced_at)
+            ORDER BY c.updated_at
+            "#,
+        )
+        .bind(integration.project_id)
+        .bind(integration.id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(contacts)
+    }
+
+    /// Deals of the project edited locally since their last sync, or never synced
+    pub async fn changed_deals(
+        pool: &SqlitePool,
+        integration: &ZohoIntegration,
+    ) -> Result<Vec<CrmDeal>, ZohoIntegrationError> {
+        let deals = sqlx::query_as::<_, CrmDeal>(
+            r#"
+            SELECT d.* FROM crm_deals d
+            LEFT JOIN zoho_sync_records r
+              ON r.integration_id = ?2 AND r.entity_type = 'deal' AND r.local_id = d.id
+            WHERE d.project_id = ?1
+              AND (r.id IS NULL OR d.updated_at > r.synced_at)
+            ORDER BY d.updated_at
+            "#,
+        )
+        .bind(integration.project_id)
+        .bind(integration.id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(deals)
+    }
+
+    /// Activities of the given types logged since `since` that Zoho has not seen
+    pub async fn unsynced_activities(
+        pool: &SqlitePool,
+        integration: &ZohoIntegration,
+        activity_types: &[&str],
+        since: DateTime<Utc>,
+    ) -> Result<Vec<CrmActivity>, ZohoIntegrationError> {
+        let types = serde_json::to_string(activity_types).unwrap_or_default();
+
+        let activities = sqlx::query_as::<_, CrmActivity>(
+            r#"
+            SELECT a.* FROM crm_activities a
+            LEFT JOIN zoho_sync_records r
+              ON r.integration_id = ?2 AND r.entity_type = 'activity' AND r.local_id = a.id
+            WHERE a.project_id = ?1
+              AND r.id IS NULL
+              AND a.activity_type IN (SELECT value FROM json_each(?3))
+              AND a.created_at >= datetime(?4, 'subsec')
+            ORDER BY a.activity_at
+            "#,
+        )
+        .bind(integration.project_id)
+        .bind(integration.id)
+        .bind(types)
+        .bind(since)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(activities)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictWinner {
+    Local,
+    Zoho,
+}
+
+impl std::fmt::Display for ConflictWinner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ConflictWinner::Local => "local",
+            ConflictWinner::Zoho => "zoho",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A field that changed on both sides, and which value was kept
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ZohoSyncConflict {
+    pub id: Uuid,
+    pub integration_id: Uuid,
+    pub entity_type: String,
+    pub local_id: Uuid,
+    pub zoho_id: String,
+    pub field: String,
+    pub local_value: Option<String>,
+    pub zoho_value: Option<String>,
+    pub strategy: String,
+    pub winner: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateZohoSyncConflict {
+    pub integration_id: Uuid,
+    pub entity: ZohoEntity,
+    pub local_id: Uuid,
+    pub zoho_id: String,
+    pub field: String,
+    pub local_value: Option<String>,
+    pub zoho_value: Option<String>,
+    pub strategy: ConflictStrategy,
+    pub winner: ConflictWinner,
+}
+
+impl ZohoSyncConflict {
+    pub async fn create(
+        pool: &SqlitePool,
+        data: CreateZohoSyncConflict,
+    ) -> Result<Self, ZohoIntegrationError> {
+        let conflict = sqlx::query_as::<_, ZohoSyncConflict>(
+            r#"
+            INSERT INTO zoho_sync_conflicts (
+                id, integration_id, entity_type, local_id, zoho_id, field,
+                local_value, zoho_value, strategy, winner
+            )
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+            RETURNING *
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(data.integration_id)
+        .bind(data.entity.to_string())
+        .bind(data.local_id)
+        .bind(&data.zoho_id)
+        .bind(&data.field)
+        .bind(&data.local_value)
+        .bind(&data.zoho_value)
+        .bind(data.strategy.to_string())
+        .bind(data.winner.to_string())
+        .fetch_one(pool)
+        .await?;
+
+        Ok(conflict)
+    }
+
+    /// Most recent conflicts first
+    pub async fn find_by_integration(
+        pool: &SqlitePool,
+        integration_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Self>, ZohoIntegrationError> {
+        let conflicts = sqlx::query_as::<_, ZohoSyncConflict>(
+            r#"
+            SELECT * FROM zoho_sync_conflicts
+            WHERE integration_id = ?1
+            ORDER BY created_at DESC
+            LIMIT ?2
+            "#,
+        )
+        .bind(integration_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(conflicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::test_utils::{create_test_project, setup_test_pool};
+
+    fn connection() -> ZohoConnection {
+        ZohoConnection {
+            zoho_domain: "eu".into(),
+            access_token: Some("access".into()),
+            refresh_token: "refresh".into(),
+            token_expires_at: None,
+            granted_scopes: Some(vec!["ZohoCRM.modules.ALL".into()]),
+        }
+    }
+
+    #[tokio::test]
+    async fn settings_survive_reconnect() {
+        let pool = setup_test_pool().await;
+        let project_id = create_test_project(&pool).await;
+
+        let pending = ZohoIntegration::upsert_settings(
+            &pool,
+            project_id,
+            UpdateZohoIntegration {
+                sync_direction: Some(ZohoSyncDirection::FromZoho),
+                conflict_rules: Some(ConflictRules {
+                    default: ConflictStrategy::NewestWins,
+                    fields: [("email".to_string(), ConflictStrategy::SourceWins)].into(),
+                }),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(pending.status, "pending_auth");
+        assert!(
+            ZohoIntegration::find_active(&pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let connected = ZohoIntegration::connect(&pool, project_id, connection())
+            .await
+            .unwrap();
+        assert_eq!(connected.id, pending.id);
+        assert_eq!(connected.status, "active");
+        assert_eq!(connected.direction(), ZohoSyncDirection::FromZoho);
+        let rules = connected.conflict_rules();
+        assert_eq!(rules.strategy_for("email"), ConflictStrategy::SourceWins);
+        assert_eq!(rules.strategy_for("phone"), ConflictStrategy::NewestWins);
+        assert_eq!(ZohoIntegration::find_active(&pool).await.unwrap().len(), 1);
+
+        ZohoIntegration::revoke(&pool, connected.id, "invalid_code")
+            .await
+            .unwrap();
+        assert!(
+            ZohoIntegration::find_active(&pool)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn links_records_and_logs_conflicts() {
+        let pool = setup_test_pool().await;
+        let project_id = create_test_project(&pool).await;
+        let integration = ZohoIntegration::connect(&pool, project_id, connection())
+            .await
+            .unwrap();
+        let local_id = Uuid::new_v4();
+
+        let snapshot: BTreeMap<String, String> =
+            [("Email".to_string(), "ada@example.com".to_string())].into();
+        ZohoSyncRecord::upsert(
+            &pool,
+            integration.id,
+            ZohoEntity::Activity,
+            local_id,
+            "z-1",
+            None,
+            &snapshot,
+        )
+        .await
+        .unwrap();
+        let record =
+            ZohoSyncRecord::find_by_zoho_id(&pool, integration.id, ZohoEntity::Activity, "z-1")
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(record.local_id, local_id);
+        assert_eq!(record.snapshot(), snapshot);
+
+        ZohoSyncConflict::create(
+            &pool,
+            CreateZohoSyncConflict {
+                integration_id: integration.id,
+                entity: ZohoEntity::Activity,
+                local_id,
+                zoho_id: "z-1".into(),
+                field: "subject".into(),
+                local_value: Some("Call".into()),
+                zoho_value: Some("Intro call".into()),
+                strategy: ConflictStrategy::SourceWins,
+                winner: ConflictWinner::Zoho,
+            },
+        )
+        .await
+        .unwrap();
+        let conflicts = ZohoSyncConflict::find_by_integration(&pool, integration.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].winner, "zoho");
+        assert_eq!(conflicts[0].strategy, "source_wins");
+    }
+}
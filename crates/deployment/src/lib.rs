@@ -19,22 +19,31 @@ use futures::{StreamExt, TryStreamExt};
 use git2::Error as Git2Error;
 use serde_json::Value;
 use services::services::{
+    airtable_sync::AirtableSyncService,
     analytics::AnalyticsService,
     approvals::Approvals,
     auth::{AuthError, AuthService},
+    cms_publishing::CmsPublishingService,
     config::{Config, ConfigError},
     container::{ContainerError, ContainerService},
+    crm_enrichment::CrmEnrichmentService,
+    editron::SegmentHost,
+    email_sequence::EmailSequenceService,
+    email_sync::EmailSyncService,
     events::{EventError, EventService},
     file_search_cache::FileSearchCache,
     filesystem::{FilesystemError, FilesystemService},
     filesystem_watcher::FilesystemWatcherError,
     git::{GitService, GitServiceError},
     image::{ImageError, ImageService},
+    lead_scoring::LeadScoringService,
+    log_archive::LogArchiveService,
     media_pipeline::{MediaPipelineError, MediaPipelineService},
     pr_monitor::PrMonitorService,
     sentry::SentryService,
     topos_scanner::{ToposScannerService, DiscoveredProject},
     worktree_manager::WorktreeError,
+    zoho::ZohoSyncService,
 };
 use sqlx::{Error as SqlxError, types::Uuid};
 #[cfg(feature = "postgres")]
@@ -100,7 +109,7 @@ pub trait Deployment: Clone + Send + Sync + 'static {
 
     fn analytics(&self) -> &Option<AnalyticsService>;
 
-    fn container(&self) -> &impl ContainerService;
+    fn container(&self) -> &(impl ContainerService + Clone + Send + Sync + 'static);
 
     fn auth(&self) -> &AuthService;
 
@@ -120,6 +129,9 @@ pub trait Deployment: Clone + Send + Sync + 'static {
 
     fn media_pipeline(&self) -> &MediaPipelineService;
 
+    /// Serves segment renders to mesh peers; `None` when ffmpeg is not installed
+    fn segment_host(&self) -> Option<&Arc<SegmentHost>>;
+
     async fn update_sentry_scope(&self) -> Result<(), DeploymentError> {
         let user_id = self.user_id();
         let config = self.config().read().await;
@@ -134,7 +146,40 @@ pub trait Deployment: Clone + Send + Sync + 'static {
     async fn spawn_pr_monitor_service(&self) -> tokio::task::JoinHandle<()> {
         let db = self.db().clone();
         let config = self.config().clone();
-        PrMonitorService::spawn(db, config).await
+        let container = self.container().clone();
+        PrMonitorService::spawn(db, config, container).await
+    }
+
+    async fn spawn_log_retention_service(&self) -> tokio::task::JoinHandle<()> {
+        LogArchiveService::spawn(self.db().pool.clone()).await
+    }
+
+    async fn spawn_email_sync_service(&self) -> tokio::task::JoinHandle<()> {
+        EmailSyncService::spawn(self.db().pool.clone()).await
+    }
+
+    async fn spawn_crm_enrichment_service(&self) -> tokio::task::JoinHandle<()> {
+        CrmEnrichmentService::spawn(self.db().pool.clone()).await
+    }
+
+    async fn spawn_lead_scoring_service(&self) -> tokio::task::JoinHandle<()> {
+        LeadScoringService::spawn(self.db().pool.clone()).await
+    }
+
+    async fn spawn_email_sequence_service(&self) -> tokio::task::JoinHandle<()> {
+        EmailSequenceService::spawn(self.db().pool.clone()).await
+    }
+
+    async fn spawn_zoho_sync_service(&self) -> tokio::task::JoinHandle<()> {
+        ZohoSyncService::spawn(self.db().pool.clone()).await
+    }
+
+    async fn spawn_airtable_sync_service(&self) -> tokio::task::JoinHandle<()> {
+        AirtableSyncService::spawn(self.db().pool.clone(), self.config().clone()).await
+    }
+
+    async fn spawn_cms_publishing_service(&self) -> tokio::task::JoinHandle<()> {
+        CmsPublishingService::spawn(self.db().pool.clone()).await
     }
 
     async fn track_if_analytics_allowed(&self, event_name: &str, properties: Value) {
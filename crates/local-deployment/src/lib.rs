@@ -12,6 +12,7 @@ use services::services::{
     auth::AuthService,
     config::{Config, load_config_from_file, save_config_to_file},
     container::ContainerService,
+    editron::{FFmpegClient, SegmentHost},
     events::EventService,
     file_search_cache::FileSearchCache,
     filesystem::FilesystemService,
@@ -57,6 +58,7 @@ pub struct LocalDeployment {
     file_search_cache: Arc<FileSearchCache>,
     approvals: Approvals,
     media_pipeline: MediaPipelineService,
+    segment_host: Option<Arc<SegmentHost>>,
 }
 
 #[async_trait]
@@ -147,6 +149,12 @@ impl Deployment for LocalDeployment {
             db.pool.clone(),
         )?;
         DropboxMonitor::spawn(db.pool.clone(), media_pipeline.clone());
+        let segment_host = FFmpegClient::new().ok().map(|ffmpeg| {
+            Arc::new(SegmentHost::new(
+                Arc::new(ffmpeg),
+                asset_dir().join("editron").join("segments"),
+            ))
+        });
 
         // Try to initialize PostgreSQL connection if DATABASE_URL is set
         #[cfg(feature = "postgres")]
@@ -193,6 +201,7 @@ impl Deployment for LocalDeployment {
             file_search_cache,
             approvals,
             media_pipeline,
+            segment_host,
         })
     }
 
@@ -225,7 +234,7 @@ impl Deployment for LocalDeployment {
         &self.analytics
     }
 
-    fn container(&self) -> &impl ContainerService {
+    fn container(&self) -> &(impl ContainerService + Clone + Send + Sync + 'static) {
         &self.container
     }
     fn auth(&self) -> &AuthService {
@@ -263,4 +272,8 @@ impl Deployment for LocalDeployment {
     fn media_pipeline(&self) -> &MediaPipelineService {
         &self.media_pipeline
     }
+
+    fn segment_host(&self) -> Option<&Arc<SegmentHost>> {
+        self.segment_host.as_ref()
+    }
 }
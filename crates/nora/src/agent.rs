@@ -16,7 +16,11 @@ use db::models::{
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use services::services::media_pipeline::MediaPipelineService;
+use services::services::{
+    agent_tools::{AgentId, AgentToolRegistry},
+    mcp_client::{McpClientManager, MCP_TOOL_PREFIX},
+    media_pipeline::MediaPipelineService,
+};
 use sqlx::SqlitePool;
 use tokio::sync::RwLock;
 use ts_rs::TS;
@@ -61,6 +65,10 @@ pub struct NoraAgent {
     /// Conversation history per session for true conversational mode
     pub conversation_histories:
         Arc<RwLock<HashMap<String, Vec<crate::brain::ConversationMessage>>>>,
+    /// Connections to the MCP servers configured in `mcp_servers.json`
+    pub mcp_client: McpClientManager,
+    /// Schemas and access rules for the MCP tools Nora may call
+    pub tool_registry: Arc<AgentToolRegistry>,
 }
 
 /// Request to Nora for processing
@@ -255,6 +263,15 @@ impl NoraAgent {
                 }
             });
 
+        // Connect configured MCP servers and register their tools for Nora
+        let mcp_client = McpClientManager::from_config_file().await;
+        let tool_registry = Arc::new(AgentToolRegistry::new());
+        tool_registry.initialize_defaults().await;
+        let mcp_tool_count = tool_registry.register_mcp_tools(&mcp_client).await;
+        if mcp_tool_count > 0 {
+            tracing::info!("Registered {} MCP tools", mcp_tool_count);
+        }
+
         // Start as active
         let is_active = Arc::new(RwLock::new(true));
 
@@ -275,9 +292,50 @@ impl NoraAgent {
             executor: None,
             execution_engine,
             conversation_histories: Arc::new(RwLock::new(HashMap::new())),
+            mcp_client,
+            tool_registry,
         })
     }
 
+    /// Reconnect the MCP servers after `mcp_servers.json` changed
+    pub async fn reload_mcp_servers(&self) -> Result<usize> {
+        self.mcp_client
+            .reload()
+            .await
+            .map_err(|e| NoraError::ConfigError(e.to_string()))?;
+        Ok(self.tool_registry.register_mcp_tools(&self.mcp_client).await)
+    }
+
+    /// Route a tool call addressed to an MCP server through the tool registry,
+    /// which checks Nora's access to that tool
+    async fn execute_mcp_tool_call(&self, tc: &crate::brain::ToolCall) -> crate::brain::ToolResult {
+        let result = self
+            .tool_registry
+            .execute_mcp_tool(&self.mcp_client, AgentId::Nora, &tc.name, &tc.arguments)
+            .await;
+        tracing::info!(
+            "[TOOL_FLOW] MCP tool {} completed - success: {} ({}ms)",
+            tc.name,
+            result.success,
+            result.execution_time_ms
+        );
+        let text = if result.success {
+            match result.data {
+                serde_json::Value::String(text) => text,
+                data => data.to_string(),
+            }
+        } else {
+            result
+                .error
+                .unwrap_or_else(|| "Unknown error".to_string())
+        };
+        crate::brain::ToolResult {
+            tool_call_id: tc.id.clone(),
+            success: result.success,
+            result: text,
+        }
+    }
+
     /// Set the database pool and initialize the task executor
     pub fn with_database(mut self, pool: SqlitePool) -> Self {
         let executor = Arc::new(TaskExecutor::new(pool.clone()));
@@ -565,7 +623,19 @@ impl NoraAgent {
                 "[TOOL_FLOW] System prompt length: {} chars",
                 system_prompt.len()
             );
-            let tools = crate::tools::ExecutiveTools::get_openai_tool_schemas();
+            let mut tools = crate::tools::ExecutiveTools::get_openai_tool_schemas();
+            // Executive tools are dispatched natively; only MCP tools come from the registry
+            tools.extend(
+                self.tool_registry
+                    .get_openai_tools(AgentId::Nora)
+                    .await
+                    .into_iter()
+                    .filter(|tool| {
+                        tool["function"]["name"]
+                            .as_str()
+                            .is_some_and(|name| name.starts_with(MCP_TOOL_PREFIX))
+                    }),
+            );
             tracing::debug!("[TOOL_FLOW] Loaded {} tool schemas", tools.len());
             tracing::info!("[TOOL_FLOW] Sending request to LLM with function calling enabled (history: {} msgs)", conversation_history.len());
 
@@ -690,6 +760,8 @@ impl NoraAgent {
                                     });
                                 }
                             }
+                        } else if tc.name.starts_with(MCP_TOOL_PREFIX) {
+                            tool_results.push(self.execute_mcp_tool_call(tc).await);
                         } else {
                             tracing::warn!(
                                 "[TOOL_FLOW] Failed to parse tool call - unknown tool: {}",
@@ -817,6 +889,8 @@ impl NoraAgent {
                                                 });
                                             }
                                         }
+                                    } else if tc.name.starts_with(MCP_TOOL_PREFIX) {
+                                        new_results.push(self.execute_mcp_tool_call(tc).await);
                                     } else {
                                         tracing::warn!("[TOOL_FLOW] Failed to parse chained tool: {}", tc.name);
                                         new_results.push(crate::brain::ToolResult {
@@ -1,17 +1,28 @@
 //! External API integrations for Nora tools
 
-use chrono::{DateTime, Utc};
+pub mod caldav;
+pub mod ics;
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
 use serde::Serialize;
 use thiserror::Error;
 
+use self::{
+    caldav::{CalDavClient, CalDavConfig},
+    ics::{Attendee, IcsEvent, RecurrenceRule},
+};
+
 #[derive(Debug, Error)]
 pub enum IntegrationError {
     #[error("SMTP error: {0}")]
     Smtp(String),
     #[error("Discord webhook error: {0}")]
     Discord(String),
-    #[error("Google Calendar error: {0}")]
+    #[error("Calendar error: {0}")]
     Calendar(String),
+    #[error("CalDAV error: {0}")]
+    CalDav(String),
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
     #[error("Configuration error: {0}")]
@@ -93,13 +104,69 @@ impl EmailService {
         body: &str,
         is_html: bool,
     ) -> Result<String> {
-        use lettre::{
-            message::{header::ContentType, Mailbox},
-            transport::smtp::authentication::Credentials,
-            Message, SmtpTransport, Transport,
-        };
+        use lettre::message::header::ContentType;
+
+        let message_builder = self.message_builder(recipients, subject)?;
+
+        // Set content type and body
+        let message = if is_html {
+            message_builder
+                .header(ContentType::TEXT_HTML)
+                .body(body.to_string())
+        } else {
+            message_builder.body(body.to_string())
+        }
+        .map_err(|e| IntegrationError::Smtp(format!("Failed to build message: {}", e)))?;
+
+        self.deliver(&message)?;
+
+        let message_id = uuid::Uuid::new_v4().to_string();
+        tracing::info!("Email sent successfully to {:?}", recipients);
+
+        Ok(message_id)
+    }
+
+    /// Email a calendar invite. The event travels as a `text/calendar`
+    /// alternative so mail clients show accept and decline buttons.
+    pub async fn send_invite(
+        &self,
+        recipients: &[String],
+        subject: &str,
+        body: &str,
+        ics: &str,
+    ) -> Result<String> {
+        use lettre::message::{header::ContentType, MultiPart, SinglePart};
+
+        let calendar = ContentType::parse("text/calendar; charset=utf-8; method=REQUEST")
+            .map_err(|e| IntegrationError::Smtp(format!("Invalid content type: {}", e)))?;
+        let message = self
+            .message_builder(recipients, subject)?
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(
+                        SinglePart::builder()
+                            .header(ContentType::TEXT_PLAIN)
+                            .body(body.to_string()),
+                    )
+                    .singlepart(SinglePart::builder().header(calendar).body(ics.to_string())),
+            )
+            .map_err(|e| IntegrationError::Smtp(format!("Failed to build invite: {}", e)))?;
+
+        self.deliver(&message)?;
+
+        let message_id = uuid::Uuid::new_v4().to_string();
+        tracing::info!("Calendar invite sent to {:?}", recipients);
+
+        Ok(message_id)
+    }
+
+    fn message_builder(
+        &self,
+        recipients: &[String],
+        subject: &str,
+    ) -> Result<lettre::message::MessageBuilder> {
+        use lettre::{message::Mailbox, Message};
 
-        // Build message
         let from_mailbox: Mailbox =
             format!("{} <{}>", self.config.from_name, self.config.from_email)
                 .parse()
@@ -114,15 +181,11 @@ impl EmailService {
                 .map_err(|e| IntegrationError::Smtp(format!("Invalid recipient: {}", e)))?);
         }
 
-        // Set content type and body
-        let message = if is_html {
-            message_builder
-                .header(ContentType::TEXT_HTML)
-                .body(body.to_string())
-        } else {
-            message_builder.body(body.to_string())
-        }
-        .map_err(|e| IntegrationError::Smtp(format!("Failed to build message: {}", e)))?;
+        Ok(message_builder)
+    }
+
+    fn deliver(&self, message: &lettre::Message) -> Result<()> {
+        use lettre::{transport::smtp::authentication::Credentials, SmtpTransport, Transport};
 
         // Create SMTP transport
         let credentials =
@@ -143,13 +206,10 @@ impl EmailService {
 
         // Send email
         mailer
-            .send(&message)
+            .send(message)
             .map_err(|e| IntegrationError::Smtp(format!("Failed to send email: {}", e)))?;
 
-        let message_id = uuid::Uuid::new_v4().to_string();
-        tracing::info!("Email sent successfully to {:?}", recipients);
-
-        Ok(message_id)
+        Ok(())
     }
 }
 
@@ -300,13 +360,21 @@ impl DiscordService {
 }
 
 // ============================================================================
-// Google Calendar Integration
+// Calendar Integration (Google Calendar or CalDAV)
 // ============================================================================
 
+/// Working hours offered by `find_available_slots`, in the calendar's zone
+const WORKDAY_START_HOUR: u32 = 9;
+const WORKDAY_END_HOUR: u32 = 17;
+const SLOT_STEP_MINUTES: i64 = 30;
+const MAX_SLOTS_PER_DAY: usize = 3;
+const MAX_SUGGESTED_SLOTS: usize = 10;
+
 #[derive(Debug, Clone)]
 pub struct CalendarConfig {
     pub credentials_path: String,
     pub calendar_id: String,
+    pub timezone: Tz,
 }
 
 impl Default for CalendarConfig {
@@ -316,33 +384,132 @@ impl Default for CalendarConfig {
                 .unwrap_or_else(|_| "credentials.json".to_string()),
             calendar_id: std::env::var("GOOGLE_CALENDAR_ID")
                 .unwrap_or_else(|_| "primary".to_string()),
+            timezone: std::env::var("GOOGLE_CALENDAR_TIMEZONE")
+                .ok()
+                .and_then(|tz| tz.parse().ok())
+                .unwrap_or(Tz::UTC),
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct NewCalendarEvent {
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub attendees: Vec<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    /// IANA zone the event recurs in; defaults to the calendar's zone
+    pub timezone: Option<String>,
+    /// RFC 5545 rule such as `FREQ=WEEKLY;BYDAY=MO;COUNT=6`
+    pub recurrence: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatedEvent {
+    pub event_id: String,
+    /// Attendees sent an invite, by us or by the calendar provider
+    pub invites_sent: usize,
+}
+
+#[derive(Debug)]
+enum CalendarBackend {
+    Google(CalendarConfig),
+    CalDav {
+        client: CalDavClient,
+        invites: Option<EmailService>,
+    },
+}
+
 #[derive(Debug)]
 pub struct CalendarService {
-    config: CalendarConfig,
+    backend: CalendarBackend,
 }
 
 impl CalendarService {
     pub fn new(config: CalendarConfig) -> Self {
-        Self { config }
+        Self {
+            backend: CalendarBackend::Google(config),
+        }
+    }
+
+    /// A CalDAV calendar; invites are emailed through `invites` when given
+    pub fn caldav(client: CalDavClient, invites: Option<EmailService>) -> Self {
+        Self {
+            backend: CalendarBackend::CalDav { client, invites },
+        }
     }
 
+    /// CalDAV when `CALDAV_URL` is set, otherwise Google Calendar
     pub fn from_env() -> Result<Self> {
+        if std::env::var("CALDAV_URL").is_ok() {
+            let config = CalDavConfig::from_env()?;
+            let invites = if config.send_invites {
+                EmailService::from_env().ok()
+            } else {
+                None
+            };
+            return Ok(Self::caldav(CalDavClient::new(config), invites));
+        }
+
         let config = CalendarConfig::default();
-        Ok(Self { config })
+        Ok(Self::new(config))
     }
 
-    pub async fn create_event(
-        &self,
-        title: &str,
-        start_time: DateTime<Utc>,
-        end_time: DateTime<Utc>,
-        attendees: &[String],
-        location: Option<&str>,
-    ) -> Result<String> {
+    pub fn provider_name(&self) -> &'static str {
+        match self.backend {
+            CalendarBackend::Google(_) => "Google Calendar",
+            CalendarBackend::CalDav { .. } => "CalDAV",
+        }
+    }
+
+    pub fn timezone(&self) -> Tz {
+        match &self.backend {
+            CalendarBackend::Google(config) => config.timezone,
+            CalendarBackend::CalDav { client, .. } => client.config().timezone,
+        }
+    }
+
+    pub async fn create_event(&self, event: &NewCalendarEvent) -> Result<CreatedEvent> {
+        if event.end <= event.start {
+            return Err(IntegrationError::Calendar(
+                "Event must end after it starts".to_string(),
+            ));
+        }
+        let timezone = match &event.timezone {
+            Some(name) => name
+                .parse::<Tz>()
+                .map_err(|_| IntegrationError::Calendar(format!("Unknown timezone: {}", name)))?,
+            None => self.timezone(),
+        };
+        let recurrence = event
+            .recurrence
+            .as_deref()
+            .map(str::parse::<RecurrenceRule>)
+            .transpose()
+            .map_err(IntegrationError::Calendar)?;
+
+        let created = match &self.backend {
+            CalendarBackend::Google(config) => {
+                Self::create_google_event(config, event, timezone, recurrence.as_ref()).await?
+            }
+            CalendarBackend::CalDav { client, invites } => {
+                Self::create_caldav_event(client, invites.as_ref(), event, timezone, recurrence)
+                    .await?
+            }
+        };
+        tracing::info!("Calendar event created: {}", created.event_id);
+
+        Ok(created)
+    }
+
+    async fn create_google_event(
+        config: &CalendarConfig,
+        event: &NewCalendarEvent,
+        timezone: Tz,
+        recurrence: Option<&RecurrenceRule>,
+    ) -> Result<CreatedEvent> {
         use google_calendar3::{
             api::{Event, EventAttendee, EventDateTime},
             hyper::{self},
@@ -352,7 +519,7 @@ impl CalendarService {
         };
 
         // Read service account credentials
-        let service_account_key = oauth2::read_service_account_key(&self.config.credentials_path)
+        let service_account_key = oauth2::read_service_account_key(&config.credentials_path)
             .await
             .map_err(|e| {
                 IntegrationError::Calendar(format!("Failed to read credentials: {}", e))
@@ -378,25 +545,31 @@ impl CalendarService {
         // Create Calendar hub
         let hub = CalendarHub::new(client, auth);
 
-        // Build event
-        let mut event = Event {
-            summary: Some(title.to_string()),
+        // Build event. Recurring events need a zone on both ends so Google
+        // expands them in local time.
+        let mut google_event = Event {
+            summary: Some(event.title.clone()),
+            description: event.description.clone(),
             start: Some(EventDateTime {
-                date_time: Some(start_time),
+                date_time: Some(event.start),
+                time_zone: Some(timezone.name().to_string()),
                 ..Default::default()
             }),
             end: Some(EventDateTime {
-                date_time: Some(end_time),
+                date_time: Some(event.end),
+                time_zone: Some(timezone.name().to_string()),
                 ..Default::default()
             }),
-            location: location.map(|l| l.to_string()),
+            location: event.location.clone(),
+            recurrence: recurrence.map(|rule| vec![format!("RRULE:{}", rule)]),
             ..Default::default()
         };
 
         // Add attendees
-        if !attendees.is_empty() {
-            event.attendees = Some(
-                attendees
+        if !event.attendees.is_empty() {
+            google_event.attendees = Some(
+                event
+                    .attendees
                     .iter()
                     .map(|email| EventAttendee {
                         email: Some(email.clone()),
@@ -406,10 +579,11 @@ impl CalendarService {
             );
         }
 
-        // Insert event
+        // Insert event; Google emails the invites itself
         let result = hub
             .events()
-            .insert(event, &self.config.calendar_id)
+            .insert(google_event, &config.calendar_id)
+            .send_updates("all")
             .doit()
             .await
             .map_err(|e| IntegrationError::Calendar(format!("Failed to create event: {}", e)))?;
@@ -418,17 +592,79 @@ impl CalendarService {
             .1
             .id
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-        tracing::info!("Calendar event created: {}", event_id);
 
-        Ok(event_id)
+        Ok(CreatedEvent {
+            event_id,
+            invites_sent: event.attendees.len(),
+        })
     }
 
-    pub async fn check_availability(
+    async fn create_caldav_event(
+        client: &CalDavClient,
+        invites: Option<&EmailService>,
+        event: &NewCalendarEvent,
+        timezone: Tz,
+        recurrence: Option<RecurrenceRule>,
+    ) -> Result<CreatedEvent> {
+        let mut ics_event = IcsEvent::new(&event.title, event.start, event.end, timezone);
+        ics_event.description = event.description.clone();
+        ics_event.location = event.location.clone();
+        ics_event.organizer = client.config().organizer_email.clone().map(Attendee::new);
+        ics_event.attendees = event.attendees.iter().map(Attendee::new).collect();
+        ics_event.rrule = recurrence;
+
+        client.put_event(&ics_event).await?;
+
+        // The event is stored either way; a failed invite is only logged
+        let mut invites_sent = 0;
+        if let (Some(email_service), false) = (invites, event.attendees.is_empty()) {
+            match email_service
+                .send_invite(
+                    &event.attendees,
+                    &format!("Invitation: {}", event.title),
+                    &invite_body(event, timezone),
+                    &ics_event.to_ics(Some("REQUEST")),
+                )
+                .await
+            {
+                Ok(_) => invites_sent = event.attendees.len(),
+                Err(e) => {
+                    tracing::warn!("Failed to send invites for {}: {}", ics_event.uid, e);
+                }
+            }
+        }
+
+        Ok(CreatedEvent {
+            event_id: ics_event.uid,
+            invites_sent,
+        })
+    }
+
+    /// Merged busy periods between `start_time` and `end_time`. Google looks
+    /// up each calendar id or address in `calendars`; a CalDAV account has
+    /// one calendar, so they all share its busy time.
+    pub async fn busy_periods(
         &self,
-        user_email: &str,
+        calendars: &[String],
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
-    ) -> Result<bool> {
+    ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+        match &self.backend {
+            CalendarBackend::Google(config) => {
+                Self::google_busy_periods(config, calendars, start_time, end_time).await
+            }
+            CalendarBackend::CalDav { client, .. } => {
+                client.busy_periods(start_time, end_time).await
+            }
+        }
+    }
+
+    async fn google_busy_periods(
+        config: &CalendarConfig,
+        calendars: &[String],
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
         use google_calendar3::{
             api::FreeBusyRequest,
             hyper::{self},
@@ -438,7 +674,7 @@ impl CalendarService {
         };
 
         // Read service account credentials
-        let service_account_key = oauth2::read_service_account_key(&self.config.credentials_path)
+        let service_account_key = oauth2::read_service_account_key(&config.credentials_path)
             .await
             .map_err(|e| {
                 IntegrationError::Calendar(format!("Failed to read credentials: {}", e))
@@ -464,13 +700,21 @@ impl CalendarService {
         // Create Calendar hub
         let hub = CalendarHub::new(client, auth);
 
+        let ids = if calendars.is_empty() {
+            vec![config.calendar_id.clone()]
+        } else {
+            calendars.to_vec()
+        };
+
         // Query freebusy
         let request = FreeBusyRequest {
             time_min: Some(start_time),
             time_max: Some(end_time),
-            items: Some(vec![google_calendar3::api::FreeBusyRequestItem {
-                id: Some(user_email.to_string()),
-            }]),
+            items: Some(
+                ids.into_iter()
+                    .map(|id| google_calendar3::api::FreeBusyRequestItem { id: Some(id) })
+                    .collect(),
+            ),
             ..Default::default()
         };
 
@@ -478,40 +722,50 @@ impl CalendarService {
             IntegrationError::Calendar(format!("Failed to query availability: {}", e))
         })?;
 
-        // Check if user has any busy periods in the time range
-        let is_available = if let Some(ref calendars) = result.1.calendars {
-            if let Some(calendar) = calendars.get(user_email) {
-                if let Some(ref busy) = calendar.busy {
-                    busy.is_empty()
-                } else {
-                    true
-                }
-            } else {
-                true
-            }
-        } else {
-            true
-        };
+        let periods = result
+            .1
+            .calendars
+            .unwrap_or_default()
+            .into_values()
+            .flat_map(|calendar| calendar.busy.unwrap_or_default())
+            .filter_map(|period| Some((period.start?, period.end?)))
+            .collect();
+
+        Ok(ics::merge_periods(periods))
+    }
 
-        Ok(is_available)
+    pub async fn check_availability(
+        &self,
+        user_email: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<bool> {
+        let busy = self
+            .busy_periods(&[user_email.to_string()], start_time, end_time)
+            .await?;
+        Ok(busy.is_empty())
     }
 
+    /// Meeting times free for every participant over the next `days_ahead`
+    /// days, limited to `preferred_days` when any are given
     pub async fn find_available_slots(
         &self,
         participants: &[String],
         duration_minutes: u32,
         days_ahead: u32,
+        preferred_days: &[Weekday],
     ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
-        // For now, return mock data
-        // Full implementation would query all participants' calendars
-        let mut slots = Vec::new();
         let now = Utc::now();
-
-        for day in 1..=days_ahead {
-            let start = now + chrono::Duration::days(day as i64);
-            let end = start + chrono::Duration::minutes(duration_minutes as i64);
-            slots.push((start, end));
-        }
+        let horizon = now + Duration::days(days_ahead as i64 + 1);
+        let busy = self.busy_periods(participants, now, horizon).await?;
+        let slots = free_slots(
+            &busy,
+            now,
+            days_ahead,
+            Duration::minutes(duration_minutes as i64),
+            self.timezone(),
+            preferred_days,
+        );
 
         tracing::info!(
             "Found {} available slots for {} participants",
@@ -522,3 +776,125 @@ impl CalendarService {
         Ok(slots)
     }
 }
+
+fn invite_body(event: &NewCalendarEvent, timezone: Tz) -> String {
+    let start = event.start.with_timezone(&timezone);
+    let end = event.end.with_timezone(&timezone);
+    let mut body = format!(
+        "{}\n\nWhen: {} - {} ({})",
+        event.title,
+        start.format("%A %-d %B %Y, %H:%M"),
+        end.format("%H:%M"),
+        timezone.name()
+    );
+    if let Some(location) = &event.location {
+        body.push_str(&format!("\nWhere: {}", location));
+    }
+    if let Some(description) = &event.description {
+        body.push_str(&format!("\n\n{}", description));
+    }
+    body
+}
+
+/// Non-overlapping slots of `duration` inside working hours that avoid
+/// `busy`, from `from` to `days_ahead` days later. Weekdays are offered
+/// unless `days` narrows them down.
+pub fn free_slots(
+    busy: &[(DateTime<Utc>, DateTime<Utc>)],
+    from: DateTime<Utc>,
+    days_ahead: u32,
+    duration: Duration,
+    timezone: Tz,
+    days: &[Weekday],
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut slots = Vec::new();
+    if duration <= Duration::zero() {
+        return slots;
+    }
+
+    let today = from.with_timezone(&timezone).date_naive();
+    let day_start = NaiveTime::from_hms_opt(WORKDAY_START_HOUR, 0, 0).expect("valid hour");
+    let day_end = NaiveTime::from_hms_opt(WORKDAY_END_HOUR, 0, 0).expect("valid hour");
+
+    for offset in 0..=days_ahead as i64 {
+        let date = today + Duration::days(offset);
+        let wanted = if days.is_empty() {
+            !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+        } else {
+            days.contains(&date.weekday())
+        };
+        if !wanted {
+            continue;
+        }
+
+        let mut found_today = 0;
+        let mut local = date.and_time(day_start);
+        while local + duration <= date.and_time(day_end) && found_today < MAX_SLOTS_PER_DAY {
+            let start = ics::to_utc(timezone, local);
+            let end = start + duration;
+            let clashes = busy
+                .iter()
+                .any(|(busy_start, busy_end)| *busy_start < end && *busy_end > start);
+            if start >= from && !clashes {
+                slots.push((start, end));
+                if slots.len() == MAX_SUGGESTED_SLOTS {
+                    return slots;
+                }
+                found_today += 1;
+                local += duration;
+            } else {
+                local += Duration::minutes(SLOT_STEP_MINUTES);
+            }
+        }
+    }
+
+    slots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn free_slots_skip_busy_time_and_weekends() {
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        // Friday 16 October 2026, 10:15 in New York
+        let now = utc("2026-10-16T14:15:00Z");
+        let busy = vec![(utc("2026-10-16T14:00:00Z"), utc("2026-10-16T17:00:00Z"))];
+
+        let slots = free_slots(&busy, now, 3, Duration::hours(1), new_york, &[]);
+        let starts: Vec<_> = slots.iter().map(|(start, _)| *start).collect();
+        assert_eq!(
+            starts,
+            vec![
+                // Friday after the 10:00-13:00 meeting
+                utc("2026-10-16T17:00:00Z"),
+                utc("2026-10-16T18:00:00Z"),
+                utc("2026-10-16T19:00:00Z"),
+                // Monday from 9:00
+                utc("2026-10-19T13:00:00Z"),
+                utc("2026-10-19T14:00:00Z"),
+                utc("2026-10-19T15:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn free_slots_follow_preferred_days_in_local_time() {
+        let london: Tz = "Europe/London".parse().unwrap();
+        let now = utc("2026-10-19T00:00:00Z");
+        let slots = free_slots(&[], now, 7, Duration::minutes(30), london, &[Weekday::Mon]);
+
+        assert_eq!(slots.len(), 6);
+        // 9:00 BST, then 9:00 GMT after the clocks go back on 25 October
+        assert_eq!(slots[0].0, utc("2026-10-19T08:00:00Z"));
+        assert_eq!(slots[3].0, utc("2026-10-26T09:00:00Z"));
+        assert_eq!(slots[5].0, utc("2026-10-26T10:00:00Z"));
+    }
+}
@@ -0,0 +1,436 @@
+//! CalDAV (RFC 4791) calendar backend
+//!
+//! Talks to any standards-compliant server (Nextcloud, Fastmail, Radicale,
+//! iCloud) with basic auth. Events are stored as one `.ics` resource each and
+//! free/busy time is worked out from a `calendar-query` REPORT, expanding
+//! recurrences locally so servers without `expand` support still answer
+//! correctly.
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use reqwest::{header, Method, StatusCode};
+
+use super::{
+    ics::{self, IcsEvent},
+    IntegrationError, Result,
+};
+
+const CALDAV_NS: &str = "urn:ietf:params:xml:ns:caldav";
+
+#[derive(Debug, Clone)]
+pub struct CalDavConfig {
+    /// Calendar collection URL, e.g.
+    /// `https://cloud.example.com/remote.php/dav/calendars/nora/personal/`
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// Zone events are created in and working hours are measured in
+    pub timezone: Tz,
+    /// Organizer address written into events and used to send invites
+    pub organizer_email: Option<String>,
+    /// Email ICS invites to attendees; turn off for servers that send their
+    /// own scheduling messages (RFC 6638)
+    pub send_invites: bool,
+}
+
+impl CalDavConfig {
+    pub fn from_env() -> Result<Self> {
+        let url = std::env::var("CALDAV_URL").map_err(|_| {
+            IntegrationError::Config("CalDAV not configured. Set CALDAV_URL env var".to_string())
+        })?;
+        let timezone = match std::env::var("CALDAV_TIMEZONE") {
+            Ok(name) => name.parse::<Tz>().map_err(|_| {
+                IntegrationError::Config(format!("Unknown CALDAV_TIMEZONE: {}", name))
+            })?,
+            Err(_) => Tz::UTC,
+        };
+
+        Ok(Self {
+            url,
+            username: std::env::var("CALDAV_USERNAME").unwrap_or_default(),
+            password: std::env::var("CALDAV_PASSWORD").unwrap_or_default(),
+            timezone,
+            organizer_email: std::env::var("CALDAV_EMAIL")
+                .or_else(|_| std::env::var("SMTP_FROM_EMAIL"))
+                .ok()
+                .filter(|email| !email.is_empty()),
+            send_invites: std::env::var("CALDAV_SEND_INVITES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CalDavClient {
+    config: CalDavConfig,
+    http: reqwest::Client,
+}
+
+impl CalDavClient {
+    pub fn new(config: CalDavConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn config(&self) -> &CalDavConfig {
+        &self.config
+    }
+
+    fn collection_url(&self) -> String {
+        let url = self.config.url.trim_end_matches('/');
+        format!("{}/", url)
+    }
+
+    fn event_url(&self, uid: &str) -> String {
+        // UIDs we generate are URL-safe apart from the `@`
+        format!(
+            "{}{}.ics",
+            self.collection_url(),
+            uid.replace(['@', '/'], "-")
+        )
+    }
+
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        let request = self.http.request(method, url);
+        if self.config.username.is_empty() {
+            request
+        } else {
+            request.basic_auth(&self.config.username, Some(&self.config.password))
+        }
+    }
+
+    async fn check(response: reqwest::Response, action: &str) -> Result<reqwest::Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response.text().await.unwrap_or_default();
+        Err(IntegrationError::CalDav(format!(
+            "Failed to {} ({}): {}",
+            action,
+            status,
+            body.trim()
+        )))
+    }
+
+    /// Store a new event, returning its resource URL
+    pub async fn put_event(&self, event: &IcsEvent) -> Result<String> {
+        let url = self.event_url(&event.uid);
+        let response = self
+            .request(Method::PUT, &url)
+            .header(header::CONTENT_TYPE, "text/calendar; charset=utf-8")
+            // Never overwrite an existing resource with the same name
+            .header(header::IF_NONE_MATCH, "*")
+            .body(event.to_ics(None))
+            .send()
+            .await?;
+        Self::check(response, "create event").await?;
+        tracing::info!("CalDAV event created: {}", url);
+        Ok(url)
+    }
+
+    pub async fn delete_event(&self, uid: &str) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, &self.event_url(uid))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        Self::check(response, "delete event").await?;
+        Ok(())
+    }
+
+    /// Events with an instance overlapping `start..end`, recurring masters
+    /// included
+    pub async fn events_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<IcsEvent>> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="{ns}">
+  <d:prop>
+    <d:getetag/>
+    <c:calendar-data/>
+  </d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT">
+        <c:time-range start="{start}" end="{end}"/>
+      </c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#,
+            ns = CALDAV_NS,
+            start = start.format("%Y%m%dT%H%M%SZ"),
+            end = end.format("%Y%m%dT%H%M%SZ"),
+        );
+
+        let method = Method::from_bytes(b"REPORT").expect("REPORT is a valid method");
+        let response = self
+            .request(method, &self.collection_url())
+            .header("Depth", "1")
+            .header(header::CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await?;
+        let response = Self::check(response, "query calendar").await?;
+        let xml = response.text().await?;
+
+        Ok(calendar_data(&xml)?
+            .iter()
+            .flat_map(|data| ics::parse_events(data, self.config.timezone))
+            .collect())
+    }
+
+    /// Merged periods in `start..end` when the calendar owner is busy
+    pub async fn busy_periods(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+        let events = self.events_between(start, end).await?;
+        let periods = events
+            .iter()
+            .filter(|event| event.blocks_time())
+            .flat_map(|event| event.occurrences(start, end))
+            .map(|(from, to)| (from.max(start), to.min(end)))
+            .collect();
+        Ok(ics::merge_periods(periods))
+    }
+}
+
+/// Every `calendar-data` payload in a multistatus response
+fn calendar_data(xml: &str) -> Result<Vec<String>> {
+    let document = roxmltree::Document::parse(xml)
+        .map_err(|e| IntegrationError::CalDav(format!("Invalid multistatus response: {}", e)))?;
+    Ok(document
+        .descendants()
+        .filter(|node| {
+            node.tag_name().name() == "calendar-data"
+                && node.tag_name().namespace() == Some(CALDAV_NS)
+        })
+        .filter_map(|node| node.text())
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{extract::State, http, Router};
+
+    use super::*;
+    use crate::integrations::{CalendarService, NewCalendarEvent};
+
+    /// Stored `.ics` resources by path
+    type Resources = Arc<Mutex<BTreeMap<String, String>>>;
+
+    /// Just enough of a CalDAV server for the client: PUT, DELETE and a
+    /// REPORT that returns every stored event, leaving the time-range
+    /// filtering to the client like servers without `expand` support
+    async fn fake_caldav(
+        State(resources): State<Resources>,
+        method: http::Method,
+        uri: http::Uri,
+        headers: http::HeaderMap,
+        body: String,
+    ) -> (http::StatusCode, String) {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        // nora:secret
+        if header("authorization") != Some("Basic bm9yYTpzZWNyZXQ=") {
+            return (http::StatusCode::UNAUTHORIZED, String::new());
+        }
+        let mut resources = resources.lock().unwrap();
+        let path = uri.path().to_string();
+        match method.as_str() {
+            "PUT" => {
+                if header("if-none-match") == Some("*") && resources.contains_key(&path) {
+                    return (http::StatusCode::PRECONDITION_FAILED, String::new());
+                }
+                resources.insert(path, body);
+                (http::StatusCode::CREATED, String::new())
+            }
+            "DELETE" => match resources.remove(&path) {
+                Some(_) => (http::StatusCode::NO_CONTENT, String::new()),
+                None => (http::StatusCode::NOT_FOUND, String::new()),
+            },
+            "REPORT" => {
+                assert_eq!(header("depth"), Some("1"));
+                assert!(body.contains("<c:time-range"));
+                let responses: String = resources
+                    .iter()
+                    .filter(|(href, _)| href.starts_with(&path))
+                    .map(|(href, data)| {
+                        let data = data
+                            .replace('&', "&amp;")
+                            .replace('<', "&lt;")
+                            .replace('>', "&gt;")
+                            .replace('\r', "&#13;");
+                        format!(
+                            "<d:response><d:href>{href}</d:href><d:propstat><d:prop>\
+                             <c:calendar-data>{data}</c:calendar-data></d:prop>\
+                             <d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>"
+                        )
+                    })
+                    .collect();
+                let xml = format!(
+                    r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:" xmlns:c="{}">{}</d:multistatus>"#,
+                    CALDAV_NS, responses
+                );
+                (http::StatusCode::MULTI_STATUS, xml)
+            }
+            _ => (http::StatusCode::METHOD_NOT_ALLOWED, String::new()),
+        }
+    }
+
+    async fn fake_calendar(password: &str) -> CalDavClient {
+        let router = Router::new()
+            .fallback(fake_caldav)
+            .with_state(Resources::default());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        CalDavClient::new(CalDavConfig {
+            url: format!("http://{}/nora/calendar", addr),
+            username: "nora".into(),
+            password: password.into(),
+            timezone: "Europe/London".parse::<Tz>().unwrap(),
+            organizer_email: Some("nora@localhost".into()),
+            send_invites: false,
+        })
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[tokio::test]
+    async fn books_recurring_meetings_and_reports_busy_time() {
+        let client = fake_calendar("secret").await;
+        let calendar = CalendarService::caldav(client.clone(), None);
+
+        // Mondays at 9:00 London time, either side of the clocks going back
+        let created = calendar
+            .create_event(&NewCalendarEvent {
+                title: "Pipeline review".into(),
+                start: utc("2026-10-19T08:00:00Z"),
+                end: utc("2026-10-19T08:30:00Z"),
+                attendees: vec!["ada@localhost".into()],
+                location: Some("Boardroom".into()),
+                description: None,
+                timezone: None,
+                recurrence: Some("FREQ=WEEKLY;COUNT=3".into()),
+            })
+            .await
+            .unwrap();
+        assert_eq!(created.invites_sent, 0);
+
+        let from = utc("2026-10-18T00:00:00Z");
+        let to = utc("2026-11-09T00:00:00Z");
+        let events = client.events_between(from, to).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].uid, created.event_id);
+        assert_eq!(events[0].attendees[0].email, "ada@localhost");
+        assert_eq!(
+            client.busy_periods(from, to).await.unwrap(),
+            vec![
+                (utc("2026-10-19T08:00:00Z"), utc("2026-10-19T08:30:00Z")),
+                (utc("2026-10-26T09:00:00Z"), utc("2026-10-26T09:30:00Z")),
+                (utc("2026-11-02T09:00:00Z"), utc("2026-11-02T09:30:00Z")),
+            ]
+        );
+
+        let free = calendar
+            .check_availability(
+                "nora@localhost",
+                utc("2026-10-26T08:00:00Z"),
+                utc("2026-10-26T09:00:00Z"),
+            )
+            .await
+            .unwrap();
+        assert!(free);
+        let free = calendar
+            .check_availability(
+                "nora@localhost",
+                utc("2026-10-26T09:15:00Z"),
+                utc("2026-10-26T10:00:00Z"),
+            )
+            .await
+            .unwrap();
+        assert!(!free);
+
+        client.delete_event(&created.event_id).await.unwrap();
+        assert!(client.busy_periods(from, to).await.unwrap().is_empty());
+        // Already gone
+        client.delete_event(&created.event_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn surfaces_server_errors() {
+        let client = fake_calendar("secret").await;
+        let event = IcsEvent::new(
+            "Standup",
+            utc("2026-10-20T09:00:00Z"),
+            utc("2026-10-20T09:15:00Z"),
+            Tz::UTC,
+        );
+        client.put_event(&event).await.unwrap();
+        let err = client.put_event(&event).await.unwrap_err();
+        assert!(err.to_string().contains("412"), "{}", err);
+
+        let client = fake_calendar("wrong").await;
+        let err = client
+            .events_between(utc("2026-10-20T00:00:00Z"), utc("2026-10-21T00:00:00Z"))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("401"), "{}", err);
+    }
+
+    #[test]
+    fn reads_calendar_data_from_multistatus() {
+        let xml = r#"<?xml version="1.0"?>
+<multistatus xmlns="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <response>
+    <href>/cal/a.ics</href>
+    <propstat>
+      <prop>
+        <getetag>"1"</getetag>
+        <C:calendar-data>BEGIN:VCALENDAR&#13;
+BEGIN:VEVENT&#13;
+UID:a&#13;
+DTSTART:20261020T090000Z&#13;
+DTEND:20261020T100000Z&#13;
+END:VEVENT&#13;
+END:VCALENDAR&#13;
+</C:calendar-data>
+      </prop>
+      <status>HTTP/1.1 200 OK</status>
+    </propstat>
+  </response>
+  <response>
+    <href>/cal/missing.ics</href>
+    <status>HTTP/1.1 404 Not Found</status>
+  </response>
+</multistatus>"#;
+
+        let data = calendar_data(xml).unwrap();
+        assert_eq!(data.len(), 1);
+        let events = ics::parse_events(&data[0], Tz::UTC);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].uid, "a");
+    }
+}
@@ -0,0 +1,1181 @@
+//! iCalendar (RFC 5545) events
+//!
+//! Builds the `VCALENDAR` documents stored on CalDAV servers and emailed to
+//! attendees as invites, and reads events back to work out busy time.
+//! Recurrences are expanded in the event's own timezone so a weekly 09:00
+//! meeting stays at 09:00 local time across daylight saving changes.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::{OffsetComponents, Tz};
+
+const PRODID: &str = "-//PowerClub Global//Nora//EN";
+
+/// Upper bound on recurrence periods walked for one event, so a rule with
+/// no end cannot run away
+const MAX_PERIODS: u32 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attendee {
+    pub email: String,
+    pub name: Option<String>,
+}
+
+impl Attendee {
+    pub fn new(email: impl Into<String>) -> Self {
+        Self {
+            email: email.into(),
+            name: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// The subset of `RRULE` Nora writes and understands when reading:
+/// FREQ, INTERVAL, COUNT, UNTIL, BYDAY, BYMONTHDAY and BYMONTH
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub freq: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    /// Last possible start, as written: UTC when `until_utc`, otherwise
+    /// wall-clock time in the event's timezone
+    pub until: Option<NaiveDateTime>,
+    pub until_utc: bool,
+    /// Weekdays, with an optional position in the month or year (`-1FR`)
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+        let mut freq = None;
+        let mut rule = RecurrenceRule {
+            freq: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            until_utc: false,
+            by_day: Vec::new(),
+            by_month_day: Vec::new(),
+            by_month: Vec::new(),
+        };
+
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid RRULE part: {}", part))?;
+            let invalid = || format!("Invalid {} in RRULE: {}", key, value);
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    freq = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("Unsupported recurrence frequency: {}", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value.parse().map_err(|_| invalid())?;
+                    if rule.interval == 0 {
+                        return Err(invalid());
+                    }
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid())?),
+                "UNTIL" => {
+                    let (until, utc) = parse_until(value).ok_or_else(invalid)?;
+                    rule.until = Some(until);
+                    rule.until_utc = utc;
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        rule.by_day.push(parse_by_day(day).ok_or_else(invalid)?);
+                    }
+                }
+                "BYMONTHDAY" => {
+                    for day in value.split(',') {
+                        rule.by_month_day
+                            .push(day.trim().parse().map_err(|_| invalid())?);
+                    }
+                }
+                "BYMONTH" => {
+                    for month in value.split(',') {
+                        rule.by_month
+                            .push(month.trim().parse().map_err(|_| invalid())?);
+                    }
+                }
+                // Week start and the finer BY* parts are not needed for the
+                // meetings Nora books
+                _ => {}
+            }
+        }
+
+        rule.freq = freq.ok_or_else(|| "RRULE without FREQ".to_string())?;
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let freq = match self.freq {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={}", freq)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            let suffix = if self.until_utc { "Z" } else { "" };
+            write!(f, ";UNTIL={}{}", until.format("%Y%m%dT%H%M%S"), suffix)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|(pos, day)| match pos {
+                    Some(pos) => format!("{}{}", pos, weekday_code(*day)),
+                    None => weekday_code(*day).to_string(),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if !self.by_month_day.is_empty() {
+            let days: Vec<String> = self.by_month_day.iter().map(i32::to_string).collect();
+            write!(f, ";BYMONTHDAY={}", days.join(","))?;
+        }
+        if !self.by_month.is_empty() {
+            let months: Vec<String> = self.by_month.iter().map(u32::to_string).collect();
+            write!(f, ";BYMONTH={}", months.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IcsEvent {
+    pub uid: String,
+    pub summary: String,
+    pub description: Option<String>,
+    pub location: Option<String>,
+    /// Wall-clock start and end in `timezone`
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub timezone: Tz,
+    pub all_day: bool,
+    pub organizer: Option<Attendee>,
+    pub attendees: Vec<Attendee>,
+    pub rrule: Option<RecurrenceRule>,
+    /// Excluded occurrence starts, wall-clock in `timezone`
+    pub exdates: Vec<NaiveDateTime>,
+    /// Set on a moved or edited occurrence of a recurring event
+    pub recurrence_id: Option<NaiveDateTime>,
+    /// `TRANSP:TRANSPARENT` events do not block time
+    pub transparent: bool,
+    pub cancelled: bool,
+    pub sequence: u32,
+}
+
+impl IcsEvent {
+    /// A new timed event; `start` and `end` are stored as wall-clock time in
+    /// `timezone`
+    pub fn new(
+        summary: impl Into<String>,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        timezone: Tz,
+    ) -> Self {
+        Self {
+            uid: format!("{}@nora", uuid::Uuid::new_v4()),
+            summary: summary.into(),
+            description: None,
+            location: None,
+            start: start.with_timezone(&timezone).naive_local(),
+            end: end.with_timezone(&timezone).naive_local(),
+            timezone,
+            all_day: false,
+            organizer: None,
+            attendees: Vec::new(),
+            rrule: None,
+            exdates: Vec::new(),
+            recurrence_id: None,
+            transparent: false,
+            cancelled: false,
+            sequence: 0,
+        }
+    }
+
+    pub fn start_utc(&self) -> DateTime<Utc> {
+        to_utc(self.timezone, self.start)
+    }
+
+    pub fn end_utc(&self) -> DateTime<Utc> {
+        to_utc(self.timezone, self.end)
+    }
+
+    /// Whether the event makes its attendees unavailable
+    pub fn blocks_time(&self) -> bool {
+        !self.transparent && !self.cancelled
+    }
+
+    /// A `VCALENDAR` holding this event. Pass `Some("REQUEST")` for an
+    /// emailed invite; CalDAV servers want no method.
+    pub fn to_ics(&self, method: Option<&str>) -> String {
+        let mut lines = vec![
+            "BEGIN:VCALENDAR".to_string(),
+            "VERSION:2.0".to_string(),
+            format!("PRODID:{}", PRODID),
+            "CALSCALE:GREGORIAN".to_string(),
+        ];
+        if let Some(method) = method {
+            lines.push(format!("METHOD:{}", method));
+        }
+        let zoned = !self.all_day && self.timezone != Tz::UTC;
+        if zoned {
+            lines.extend(vtimezone(self.timezone, self.start.year()));
+        }
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}", self.uid));
+        lines.push(format!("DTSTAMP:{}", Utc::now().format("%Y%m%dT%H%M%SZ")));
+        lines.push(self.time_property("DTSTART", self.start));
+        lines.push(self.time_property("DTEND", self.end));
+        if let Some(recurrence_id) = self.recurrence_id {
+            lines.push(self.time_property("RECURRENCE-ID", recurrence_id));
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&self.summary)));
+        if let Some(description) = &self.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        if let Some(location) = &self.location {
+            lines.push(format!("LOCATION:{}", escape_text(location)));
+        }
+        if let Some(organizer) = &self.organizer {
+            lines.push(format!(
+                "ORGANIZER{}:mailto:{}",
+                cn_param(organizer),
+                organizer.email
+            ));
+        }
+        for attendee in &self.attendees {
+            lines.push(format!(
+                "ATTENDEE{};CUTYPE=INDIVIDUAL;ROLE=REQ-PARTICIPANT;PARTSTAT=NEEDS-ACTION;RSVP=TRUE:mailto:{}",
+                cn_param(attendee),
+                attendee.email
+            ));
+        }
+        if let Some(rrule) = &self.rrule {
+            lines.push(format!("RRULE:{}", rrule));
+        }
+        for exdate in &self.exdates {
+            lines.push(self.time_property("EXDATE", *exdate));
+        }
+        lines.push(format!("SEQUENCE:{}", self.sequence));
+        let status = if self.cancelled {
+            "CANCELLED"
+        } else {
+            "CONFIRMED"
+        };
+        lines.push(format!("STATUS:{}", status));
+        let transp = if self.transparent {
+            "TRANSPARENT"
+        } else {
+            "OPAQUE"
+        };
+        lines.push(format!("TRANSP:{}", transp));
+        lines.push("END:VEVENT".to_string());
+        lines.push("END:VCALENDAR".to_string());
+
+        let mut ics = String::new();
+        for line in lines {
+            ics.push_str(&fold_line(&line));
+        }
+        ics
+    }
+
+    fn time_property(&self, name: &str, value: NaiveDateTime) -> String {
+        if self.all_day {
+            format!("{};VALUE=DATE:{}", name, value.format("%Y%m%d"))
+        } else if self.timezone == Tz::UTC {
+            format!("{}:{}", name, value.format("%Y%m%dT%H%M%SZ"))
+        } else {
+            format!(
+                "{};TZID={}:{}",
+                name,
+                self.timezone.name(),
+                value.format("%Y%m%dT%H%M%S")
+            )
+        }
+    }
+
+    /// Start and end of every occurrence overlapping `from..to`
+    pub fn occurrences(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let first_start = self.start_utc();
+        let duration = self.end_utc() - first_start;
+        let overlaps = |start: DateTime<Utc>| start < to && start + duration > from;
+
+        let Some(rule) = &self.rrule else {
+            return if overlaps(first_start) {
+                vec![(first_start, first_start + duration)]
+            } else {
+                Vec::new()
+            };
+        };
+
+        // Walk far enough in local time to cover `to` whatever the offset
+        let horizon = to.with_timezone(&self.timezone).naive_local() + Duration::days(1);
+        let mut occurrences = Vec::new();
+        for local in expand(rule, self.start, self.timezone, horizon) {
+            if self.exdates.contains(&local) {
+                continue;
+            }
+            let start = to_utc(self.timezone, local);
+            if overlaps(start) {
+                occurrences.push((start, start + duration));
+            }
+        }
+        occurrences
+    }
+}
+
+/// Local start times of a recurring event, from `dtstart` up to `horizon`
+fn expand(
+    rule: &RecurrenceRule,
+    dtstart: NaiveDateTime,
+    tz: Tz,
+    horizon: NaiveDateTime,
+) -> Vec<NaiveDateTime> {
+    let time = dtstart.time();
+    let until = rule.until.map(|until| {
+        if rule.until_utc {
+            Utc.from_utc_datetime(&until)
+                .with_timezone(&tz)
+                .naive_local()
+        } else {
+            until
+        }
+    });
+    let within_until = |at: NaiveDateTime| until.is_none_or(|until| at <= until);
+
+    // DTSTART is always the first occurrence
+    let mut starts = vec![dtstart];
+    let mut count = 1;
+    let done = |count: u32| rule.count.is_some_and(|max| count >= max);
+
+    for period in 0..MAX_PERIODS {
+        if done(count) {
+            break;
+        }
+        let step = period as i64 * rule.interval as i64;
+        let dates = match rule.freq {
+            Frequency::Daily => {
+                let date = dtstart.date() + Duration::days(step);
+                let weekday_ok = rule.by_day.is_empty()
+                    || rule.by_day.iter().any(|(_, day)| *day == date.weekday());
+                let month_ok = rule.by_month.is_empty() || rule.by_month.contains(&date.month());
+                if weekday_ok && month_ok {
+                    vec![date]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let week_start = dtstart.date()
+                    - Duration::days(dtstart.weekday().num_days_from_monday() as i64)
+                    + Duration::weeks(step);
+                let mut days: Vec<Weekday> = if rule.by_day.is_empty() {
+                    vec![dtstart.weekday()]
+                } else {
+                    rule.by_day.iter().map(|(_, day)| *day).collect()
+                };
+                days.sort_by_key(|day| day.num_days_from_monday());
+                days.dedup();
+                days.into_iter()
+                    .map(|day| week_start + Duration::days(day.num_days_from_monday() as i64))
+                    .collect()
+            }
+            Frequency::Monthly => {
+                let months = dtstart.year() * 12 + dtstart.month0() as i32 + step as i32;
+                let (year, month) = (months.div_euclid(12), months.rem_euclid(12) as u32 + 1);
+                if !rule.by_month.is_empty() && !rule.by_month.contains(&month) {
+                    Vec::new()
+                } else {
+                    days_in_month_matching(rule, year, month, dtstart.day())
+                }
+            }
+            Frequency::Yearly => {
+                let year = dtstart.year() + step as i32;
+                let months = if rule.by_month.is_empty() {
+                    vec![dtstart.month()]
+                } else {
+                    rule.by_month.clone()
+                };
+                months
+                    .into_iter()
+                    .flat_map(|month| days_in_month_matching(rule, year, month, dtstart.day()))
+                    .collect()
+            }
+        };
+
+        let mut past_horizon = false;
+        for date in dates {
+            let at = date.and_time(time);
+            if at <= dtstart {
+                continue;
+            }
+            if at > horizon || !within_until(at) {
+                past_horizon = true;
+                break;
+            }
+            starts.push(at);
+            count += 1;
+            if done(count) {
+                break;
+            }
+        }
+        if past_horizon {
+            break;
+        }
+    }
+
+    starts
+}
+
+/// Days of a month selected by BYDAY/BYMONTHDAY, or `default_day`
+fn days_in_month_matching(
+    rule: &RecurrenceRule,
+    year: i32,
+    month: u32,
+    default_day: u32,
+) -> Vec<NaiveDate> {
+    let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+        return Vec::new();
+    };
+    let length = days_in_month(year, month);
+    let mut dates: Vec<NaiveDate> = if !rule.by_day.is_empty() {
+        rule.by_day
+            .iter()
+            .flat_map(|(pos, weekday)| {
+                let matching: Vec<NaiveDate> = (0..length)
+                    .map(|offset| first + Duration::days(offset as i64))
+                    .filter(|date| date.weekday() == *weekday)
+                    .collect();
+                match pos {
+                    None => matching,
+                    Some(pos) if *pos > 0 => matching
+                        .get(*pos as usize - 1)
+                        .copied()
+                        .into_iter()
+                        .collect(),
+                    Some(pos) => matching
+                        .len()
+                        .checked_sub(pos.unsigned_abs() as usize)
+                        .and_then(|i| matching.get(i).copied())
+                        .into_iter()
+                        .collect(),
+                }
+            })
+            .collect()
+    } else if !rule.by_month_day.is_empty() {
+        rule.by_month_day
+            .iter()
+            .filter_map(|day| {
+                let day = if *day < 0 {
+                    length as i32 + day + 1
+                } else {
+                    *day
+                };
+                (day >= 1)
+                    .then(|| NaiveDate::from_ymd_opt(year, month, day as u32))
+                    .flatten()
+            })
+            .collect()
+    } else {
+        // Months without the start's day (the 31st, say) are skipped
+        NaiveDate::from_ymd_opt(year, month, default_day)
+            .into_iter()
+            .collect()
+    };
+    dates.sort();
+    dates.dedup();
+    dates
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    };
+    next.map(|d| d.pred_opt().map_or(28, |last| last.day()))
+        .unwrap_or(28)
+}
+
+/// Resolve wall-clock time in `tz`. Ambiguous times take the earlier
+/// instant; times skipped by a DST change use the offset from before the
+/// gap, as RFC 5545 requires.
+pub fn to_utc(tz: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local).earliest() {
+        Some(dt) => dt.with_timezone(&Utc),
+        None => {
+            let before = tz.offset_from_utc_datetime(&(local - Duration::hours(24)));
+            Utc.from_utc_datetime(
+                &(local - Duration::seconds(before.fix().local_minus_utc() as i64)),
+            )
+        }
+    }
+}
+
+/// Look up a `TZID`, including the path-style ids some clients write
+/// (`/mozilla.org/20050126_1/Europe/London`)
+pub fn parse_tzid(tzid: &str) -> Option<Tz> {
+    let tzid = tzid.trim().trim_matches('"');
+    if let Ok(tz) = tzid.parse::<Tz>() {
+        return Some(tz);
+    }
+    let parts: Vec<&str> = tzid.split('/').filter(|p| !p.is_empty()).collect();
+    (1..parts.len())
+        .rev()
+        .find_map(|n| parts[parts.len() - n..].join("/").parse::<Tz>().ok())
+}
+
+/// Years whose transitions are used for `VTIMEZONE` rules; events outside
+/// this range reuse the nearest year's rules
+const TZ_RULE_YEARS: std::ops::RangeInclusive<i32> = 1970..=9998;
+
+/// `VTIMEZONE` for `tz`, with yearly rules taken from its transitions in `year`
+fn vtimezone(tz: Tz, year: i32) -> Vec<String> {
+    let mut lines = vec!["BEGIN:VTIMEZONE".to_string(), format!("TZID:{}", tz.name())];
+
+    let year = year.clamp(*TZ_RULE_YEARS.start(), *TZ_RULE_YEARS.end());
+    let Some(start) = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).single() else {
+        lines.push("END:VTIMEZONE".to_string());
+        return lines;
+    };
+    let offset_at = |at: DateTime<Utc>| tz.offset_from_utc_datetime(&at.naive_utc());
+    let mut transitions = Vec::new();
+    let mut previous = start;
+    for day in 1..=366 {
+        let at = start + Duration::days(day);
+        if offset_at(at).fix() != offset_at(previous).fix() {
+            // Narrow the change down to the hour
+            let mut hour = previous;
+            while offset_at(hour + Duration::hours(1)).fix() == offset_at(previous).fix() {
+                hour += Duration::hours(1);
+            }
+            transitions.push(hour + Duration::hours(1));
+        }
+        previous = at;
+    }
+
+    if transitions.is_empty() {
+        let offset = offset_at(start).fix();
+        lines.push("BEGIN:STANDARD".to_string());
+        lines.push("DTSTART:19700101T000000".to_string());
+        lines.push(format!(
+            "TZOFFSETFROM:{}",
+            format_offset(offset.local_minus_utc())
+        ));
+        lines.push(format!(
+            "TZOFFSETTO:{}",
+            format_offset(offset.local_minus_utc())
+        ));
+        lines.push("END:STANDARD".to_string());
+    }
+
+    for at in transitions {
+        let before = offset_at(at - Duration::hours(1));
+        let after = offset_at(at);
+        let kind = if after.dst_offset().num_seconds() != 0 {
+            "DAYLIGHT"
+        } else {
+            "STANDARD"
+        };
+        // DTSTART is the wall-clock time the change happens at, before it
+        let local = at.naive_utc() + Duration::seconds(before.fix().local_minus_utc() as i64);
+        let length = days_in_month(local.year(), local.month());
+        let position = if local.day() + 7 > length {
+            -1
+        } else {
+            ((local.day() - 1) / 7 + 1) as i32
+        };
+        lines.push(format!("BEGIN:{}", kind));
+        lines.push(format!("DTSTART:{}", local.format("%Y%m%dT%H%M%S")));
+        lines.push(format!(
+            "TZOFFSETFROM:{}",
+            format_offset(before.fix().local_minus_utc())
+        ));
+        lines.push(format!(
+            "TZOFFSETTO:{}",
+            format_offset(after.fix().local_minus_utc())
+        ));
+        lines.push(format!(
+            "RRULE:FREQ=YEARLY;BYMONTH={};BYDAY={}{}",
+            local.month(),
+            position,
+            weekday_code(local.weekday())
+        ));
+        lines.push(format!("END:{}", kind));
+    }
+
+    lines.push("END:VTIMEZONE".to_string());
+    lines
+}
+
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    format!("{}{:02}{:02}", sign, seconds / 3600, (seconds % 3600) / 60)
+}
+
+fn weekday_code(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_by_day(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let value = value.trim().to_ascii_uppercase();
+    if value.len() < 2 {
+        return None;
+    }
+    let (pos, code) = value.split_at(value.len() - 2);
+    let day = match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let pos = if pos.is_empty() {
+        None
+    } else {
+        Some(pos.trim_start_matches('+').parse().ok()?)
+    };
+    Some((pos, day))
+}
+
+fn parse_until(value: &str) -> Option<(NaiveDateTime, bool)> {
+    let value = value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        return NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|dt| (dt, true));
+    }
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_hms_opt(23, 59, 59)?, false));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(|dt| (dt, false))
+}
+
+fn cn_param(person: &Attendee) -> String {
+    match &person.name {
+        Some(name) => format!(";CN=\"{}\"", name.replace('"', "'")),
+        None => String::new(),
+    }
+}
+
+fn escape_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// Fold a content line at 75 octets, without splitting a UTF-8 character
+fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// A content line split into name, parameters and value
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Wall-clock time in `tz`, and whether the value was a bare date.
+    /// Times in UTC or another zone are converted into `tz`.
+    fn time_in(&self, tz: Tz, value: &str) -> Option<(NaiveDateTime, bool)> {
+        let value = value.trim();
+        if self.param("VALUE") == Some("DATE") || value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+            return Some((date.and_time(NaiveTime::MIN), true));
+        }
+        if let Some(utc) = value.strip_suffix('Z') {
+            let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+            return Some((
+                Utc.from_utc_datetime(&utc).with_timezone(&tz).naive_local(),
+                false,
+            ));
+        }
+        let local = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        match self.param("TZID").and_then(parse_tzid) {
+            Some(zone) if zone != tz => {
+                Some((to_utc(zone, local).with_timezone(&tz).naive_local(), false))
+            }
+            _ => Some((local, false)),
+        }
+    }
+
+    /// The zone an event's times are kept in, going by its `DTSTART`
+    fn zone(&self, default_tz: Tz) -> Tz {
+        if self.value.trim().ends_with('Z') {
+            return Tz::UTC;
+        }
+        self.param("TZID")
+            .and_then(parse_tzid)
+            .unwrap_or(default_tz)
+    }
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside a quoted parameter
+    let mut in_quotes = false;
+    let split = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..split], &line[split + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_ascii_uppercase();
+    let params = parts
+        .filter_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            Some((
+                key.trim().to_ascii_uppercase(),
+                value.trim().trim_matches('"').to_string(),
+            ))
+        })
+        .collect();
+
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+/// Parse an ISO 8601 duration such as `PT1H30M` or `P1D`
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.trim_start_matches('+')),
+    };
+    let value = value.strip_prefix('P')?;
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                total += match (unit, in_time) {
+                    ('W', false) => Duration::weeks(n),
+                    ('D', false) => Duration::days(n),
+                    ('H', true) => Duration::hours(n),
+                    ('M', true) => Duration::minutes(n),
+                    ('S', true) => Duration::seconds(n),
+                    _ => return None,
+                };
+            }
+        }
+    }
+    Some(if negative { -total } else { total })
+}
+
+/// Events in an iCalendar document. Times without a zone are read in
+/// `default_tz`; edited occurrences of a recurring event come back as their
+/// own events and are excluded from the series.
+pub fn parse_events(ics: &str, default_tz: Tz) -> Vec<IcsEvent> {
+    // Unfold continuation lines first
+    let unfolded = ics.replace("\r\n ", "").replace("\r\n\t", "");
+    let unfolded = unfolded.replace("\n ", "").replace("\n\t", "");
+
+    let mut events = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+    let mut depth = 0;
+    for line in unfolded.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.eq_ignore_ascii_case("BEGIN:VEVENT") {
+            current = Some(Vec::new());
+            depth = 0;
+            continue;
+        }
+        let Some(properties) = current.as_mut() else {
+            continue;
+        };
+        if line.eq_ignore_ascii_case("END:VEVENT") {
+            if let Some(event) = build_event(properties, default_tz) {
+                events.push(event);
+            }
+            current = None;
+            continue;
+        }
+        // Skip nested components such as VALARM
+        if line.to_ascii_uppercase().starts_with("BEGIN:") {
+            depth += 1;
+        } else if line.to_ascii_uppercase().starts_with("END:") {
+            depth -= 1;
+        } else if depth == 0 {
+            if let Some(property) = parse_property(line) {
+                properties.push(property);
+            }
+        }
+    }
+
+    // Exclude overridden occurrences from their series
+    let overrides: Vec<(String, NaiveDateTime, Tz)> = events
+        .iter()
+        .filter_map(|e| e.recurrence_id.map(|id| (e.uid.clone(), id, e.timezone)))
+        .collect();
+    for event in events.iter_mut().filter(|e| e.rrule.is_some()) {
+        for (uid, id, tz) in &overrides {
+            if *uid == event.uid {
+                let local = to_utc(*tz, *id)
+                    .with_timezone(&event.timezone)
+                    .naive_local();
+                event.exdates.push(local);
+            }
+        }
+    }
+
+    events
+}
+
+fn build_event(properties: &[Property], default_tz: Tz) -> Option<IcsEvent> {
+    let get = |name: &str| properties.iter().find(|p| p.name == name);
+    let text = |name: &str| get(name).map(|p| unescape_text(&p.value));
+
+    let dtstart = get("DTSTART")?;
+    let tz = dtstart.zone(default_tz);
+    let (start, all_day) = dtstart.time_in(tz, &dtstart.value)?;
+    let end = match get("DTEND") {
+        Some(dtend) => dtend.time_in(tz, &dtend.value)?.0,
+        None => match get("DURATION").and_then(|p| parse_duration(&p.value)) {
+            Some(duration) => start + duration,
+            None if all_day => start + Duration::days(1),
+            None => start,
+        },
+    };
+
+    let person = |p: &Property| Attendee {
+        email: p
+            .value
+            .trim()
+            .trim_start_matches("mailto:")
+            .trim_start_matches("MAILTO:")
+            .to_string(),
+        name: p.param("CN").map(str::to_string),
+    };
+
+    let mut exdates = Vec::new();
+    for exdate in properties.iter().filter(|p| p.name == "EXDATE") {
+        for value in exdate.value.split(',') {
+            if let Some((at, date_only)) = exdate.time_in(tz, value) {
+                // Date-only exclusions apply to the start time of that day
+                exdates.push(if date_only {
+                    at.date().and_time(start.time())
+                } else {
+                    at
+                });
+            }
+        }
+    }
+
+    Some(IcsEvent {
+        uid: text("UID").unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        summary: text("SUMMARY").unwrap_or_default(),
+        description: text("DESCRIPTION"),
+        location: text("LOCATION"),
+        start,
+        end,
+        timezone: tz,
+        all_day,
+        organizer: get("ORGANIZER").map(person),
+        attendees: properties
+            .iter()
+            .filter(|p| p.name == "ATTENDEE")
+            .map(person)
+            .collect(),
+        rrule: get("RRULE").and_then(|p| p.value.parse().ok()),
+        exdates,
+        recurrence_id: get("RECURRENCE-ID").and_then(|p| p.time_in(tz, &p.value).map(|t| t.0)),
+        transparent: get("TRANSP")
+            .is_some_and(|p| p.value.trim().eq_ignore_ascii_case("TRANSPARENT")),
+        cancelled: get("STATUS").is_some_and(|p| p.value.trim().eq_ignore_ascii_case("CANCELLED")),
+        sequence: get("SEQUENCE")
+            .and_then(|p| p.value.trim().parse().ok())
+            .unwrap_or(0),
+    })
+}
+
+/// Merge overlapping or touching periods, sorted by start
+pub fn merge_periods(
+    mut periods: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    periods.sort();
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for (start, end) in periods {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn weekly_meetings_keep_local_time_across_dst() {
+        let london: Tz = "Europe/London".parse().unwrap();
+        // Monday 9:00 GMT, a week before the clocks go forward
+        let mut event = IcsEvent::new(
+            "Standup",
+            utc("2026-03-23T09:00:00Z"),
+            utc("2026-03-23T09:30:00Z"),
+            london,
+        );
+        event.rrule = Some("FREQ=WEEKLY;COUNT=3".parse().unwrap());
+
+        let starts: Vec<_> = event
+            .occurrences(utc("2026-03-01T00:00:00Z"), utc("2026-05-01T00:00:00Z"))
+            .into_iter()
+            .map(|(start, _)| start)
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                utc("2026-03-23T09:00:00Z"),
+                // 9:00 BST
+                utc("2026-03-30T08:00:00Z"),
+                utc("2026-04-06T08:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trips_invites_with_timezone_and_exclusions() {
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        let mut event = IcsEvent::new(
+            "Board review; Q3, draft",
+            utc("2026-10-20T14:00:00Z"),
+            utc("2026-10-20T15:00:00Z"),
+            new_york,
+        );
+        event.organizer = Some(Attendee::new("nora@example.com"));
+        event.attendees = vec![Attendee {
+            email: "ada@example.com".into(),
+            name: Some("Ada Lovelace".into()),
+        }];
+        event.rrule = Some(
+            "FREQ=WEEKLY;BYDAY=TU,TH;UNTIL=20261130T000000Z"
+                .parse()
+                .unwrap(),
+        );
+        event.exdates = vec![event.start + Duration::days(2)];
+
+        let ics = event.to_ics(Some("REQUEST"));
+        assert!(ics.contains("METHOD:REQUEST\r\n"));
+        assert!(ics.contains("BEGIN:VTIMEZONE\r\nTZID:America/New_York\r\n"));
+        assert!(ics.contains("DTSTART;TZID=America/New_York:20261020T100000\r\n"));
+        assert!(ics.contains("RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\n"));
+        assert!(ics.contains("SUMMARY:Board review\\; Q3\\, draft\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+
+        let parsed = parse_events(&ics, Tz::UTC);
+        assert_eq!(parsed.len(), 1);
+        let parsed = &parsed[0];
+        assert_eq!(parsed.summary, "Board review; Q3, draft");
+        assert_eq!(parsed.timezone, new_york);
+        assert_eq!(parsed.attendees, event.attendees);
+        assert_eq!(parsed.rrule, event.rrule);
+
+        let starts: Vec<_> = parsed
+            .occurrences(utc("2026-10-19T00:00:00Z"), utc("2026-11-06T00:00:00Z"))
+            .into_iter()
+            .map(|(start, _)| start)
+            .collect();
+        assert_eq!(
+            starts,
+            vec![
+                utc("2026-10-20T14:00:00Z"),
+                // Thursday the 22nd is excluded
+                utc("2026-10-27T14:00:00Z"),
+                utc("2026-10-29T14:00:00Z"),
+                // 10:00 EST after the clocks go back on 1 November
+                utc("2026-11-03T15:00:00Z"),
+                utc("2026-11-05T15:00:00Z"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_server_events_with_overrides_and_durations() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:series\r\n\
+            DTSTART;TZID=/mozilla.org/20050126_1/Europe/Berlin:20261005T100000\r\n\
+            DURATION:PT1H\r\n\
+            RRULE:FREQ=MONTHLY;BYDAY=1MO;COUNT=3\r\n\
+            SUMMARY:Monthly\r\n  \
+            planning\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-PT15M\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:series\r\n\
+            RECURRENCE-ID;TZID=Europe/Berlin:20261102T100000\r\n\
+            DTSTART;TZID=Europe/Berlin:20261103T100000\r\n\
+            DTEND;TZID=Europe/Berlin:20261103T110000\r\n\
+            SUMMARY:Monthly planning (moved)\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            UID:holiday\r\n\
+            DTSTART;VALUE=DATE:20261012\r\n\
+            TRANSP:TRANSPARENT\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let events = parse_events(ics, Tz::UTC);
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].summary, "Monthly planning");
+        assert!(events[2].all_day && !events[2].blocks_time());
+
+        let busy = merge_periods(
+            events
+                .iter()
+                .filter(|e| e.blocks_time())
+                .flat_map(|e| {
+                    e.occurrences(utc("2026-10-01T00:00:00Z"), utc("2027-01-01T00:00:00Z"))
+                })
+                .collect(),
+        );
+        assert_eq!(
+            busy,
+            vec![
+                (utc("2026-10-05T08:00:00Z"), utc("2026-10-05T09:00:00Z")),
+                (utc("2026-11-03T09:00:00Z"), utc("2026-11-03T10:00:00Z")),
+                (utc("2026-12-07T09:00:00Z"), utc("2026-12-07T10:00:00Z")),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_times_in_dst_gaps() {
+        let london: Tz = "Europe/London".parse().unwrap();
+        let gap = NaiveDate::from_ymd_opt(2026, 3, 29)
+            .unwrap()
+            .and_hms_opt(1, 30, 0)
+            .unwrap();
+        assert_eq!(to_utc(london, gap), utc("2026-03-29T01:30:00Z"));
+        assert_eq!(parse_duration("-PT15M"), Some(Duration::minutes(-15)));
+        assert_eq!(parse_duration("P1DT2H"), Some(Duration::hours(26)));
+    }
+
+    #[test]
+    fn vtimezone_handles_extreme_years() {
+        let london: Tz = "Europe/London".parse().unwrap();
+        for year in [NaiveDate::MIN.year(), -1, 1200, NaiveDate::MAX.year()] {
+            let lines = vtimezone(london, year);
+            assert_eq!(lines.first().unwrap(), "BEGIN:VTIMEZONE");
+            assert_eq!(lines.last().unwrap(), "END:VTIMEZONE");
+            assert!(lines.iter().any(|l| l.starts_with("TZOFFSETTO:")));
+        }
+    }
+}
@@ -17,7 +17,8 @@ use uuid::Uuid;
 
 use crate::{
     executor::{TaskDefinition, TaskExecutor},
-    integrations::{CalendarService, DiscordService, EmailService},
+    integrations::{CalendarService, DiscordService, EmailService, NewCalendarEvent},
+    twilio::TwilioSmsClient,
     NoraError,
 };
 
@@ -28,6 +29,7 @@ pub struct ExecutiveTools {
     email_service: Option<EmailService>,
     discord_service: Option<DiscordService>,
     calendar_service: Option<CalendarService>,
+    sms_service: Option<TwilioSmsClient>,
     // Task execution
     task_executor: Option<Arc<TaskExecutor>>,
     media_pipeline: Option<MediaPipelineService>,
@@ -356,6 +358,10 @@ pub enum NoraExecutiveTool {
         message: String,
         mention_users: Vec<String>,
     },
+    SendSms {
+        to: String,
+        message: String,
+    },
     CreateNotification {
         title: String,
         message: String,
@@ -370,6 +376,9 @@ pub enum NoraExecutiveTool {
         end_time: DateTime<Utc>,
         attendees: Vec<String>,
         location: Option<String>,
+        description: Option<String>,
+        timezone: Option<String>,
+        recurrence: Option<String>,
     },
     FindAvailableSlots {
         participants: Vec<String>,
@@ -794,6 +803,7 @@ impl ExecutiveTools {
             email_service: EmailService::from_env().ok(),
             discord_service: DiscordService::from_env().ok(),
             calendar_service: CalendarService::from_env().ok(),
+            sms_service: TwilioSmsClient::from_env(),
             task_executor: None,
             media_pipeline: None,
             workflow_orchestrator: None,
@@ -1079,6 +1089,27 @@ impl ExecutiveTools {
                     }
                 }
             }),
+            serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": "send_sms",
+                    "description": "Send a text message to a phone number. Use this when the user wants to text, SMS or message someone's phone.",
+                    "parameters": {
+                        "type": "object",
+                        "properties": {
+                            "to": {
+                                "type": "string",
+                                "description": "Phone number in international format, e.g. +447700900123"
+                            },
+                            "message": {
+                                "type": "string",
+                                "description": "Text to send; keep it short"
+                            }
+                        },
+                        "required": ["to", "message"]
+                    }
+                }
+            }),
             serde_json::json!({
                 "type": "function",
                 "function": {
@@ -1103,16 +1134,80 @@ impl ExecutiveTools {
                                 "type": "string",
                                 "description": "Optional event description"
                             },
+                            "location": {
+                                "type": "string",
+                                "description": "Optional location or meeting link"
+                            },
                             "attendees": {
                                 "type": "array",
                                 "items": { "type": "string" },
-                                "description": "Optional list of attendee email addresses"
+                                "description": "Optional list of attendee email addresses. They are sent a calendar invite."
+                            },
+                            "timezone": {
+                                "type": "string",
+                                "description": "Optional IANA timezone the meeting is held in (e.g., Europe/London). Recurring meetings keep their local time in this zone across daylight saving changes."
+                            },
+                            "recurrence": {
+                                "type": "string",
+                                "description": "Optional iCalendar RRULE for repeating meetings (e.g., FREQ=WEEKLY;BYDAY=MO;COUNT=6)"
                             }
                         },
                         "required": ["title", "start_time", "end_time"]
                     }
                 }
             }),
+            serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": "find_available_slots",
+                    "description": "Find free meeting times in working hours over the next week. Use this before booking a meeting when the user has not given an exact time.",
+                    "parameters": {
+                        "type": "object",
+                        "properties": {
+                            "participants": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "Email addresses of the people who need to attend"
+                            },
+                            "duration_minutes": {
+                                "type": "integer",
+                                "description": "Length of the meeting in minutes"
+                            },
+                            "preferred_days": {
+                                "type": "array",
+                                "items": { "type": "string" },
+                                "description": "Optional weekdays to limit the search to (e.g., [\"Tuesday\", \"Thursday\"])"
+                            }
+                        },
+                        "required": ["duration_minutes"]
+                    }
+                }
+            }),
+            serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": "check_calendar_availability",
+                    "description": "Check whether someone is free between two times and list anything that clashes.",
+                    "parameters": {
+                        "type": "object",
+                        "properties": {
+                            "user": {
+                                "type": "string",
+                                "description": "Email address or calendar ID to check"
+                            },
+                            "start_time": {
+                                "type": "string",
+                                "description": "Start time in ISO 8601 format (e.g., 2024-12-05T14:00:00Z)"
+                            },
+                            "end_time": {
+                                "type": "string",
+                                "description": "End time in ISO 8601 format"
+                            }
+                        },
+                        "required": ["user", "start_time", "end_time"]
+                    }
+                }
+            }),
             // Editron / Media Pipeline Tools
             serde_json::json!({
                 "type": "function",
@@ -1448,6 +1543,11 @@ impl ExecutiveTools {
                     mention_users,
                 })
             }
+            "send_sms" => {
+                let to = arguments.get("to")?.as_str()?.to_string();
+                let message = arguments.get("message")?.as_str()?.to_string();
+                Some(NoraExecutiveTool::SendSms { to, message })
+            }
             "ingest_media_batch" => {
                 let source_url = arguments.get("source_url")?.as_str()?.to_string();
                 let reference_name = arguments
@@ -1677,6 +1777,18 @@ impl ExecutiveTools {
                     .get("location")
                     .and_then(|v| v.as_str())
                     .map(String::from);
+                let description = arguments
+                    .get("description")
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+                let timezone = arguments
+                    .get("timezone")
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+                let recurrence = arguments
+                    .get("recurrence")
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
 
                 Some(NoraExecutiveTool::CreateCalendarEvent {
                     title,
@@ -1684,6 +1796,54 @@ impl ExecutiveTools {
                     end_time,
                     attendees,
                     location,
+                    description,
+                    timezone,
+                    recurrence,
+                })
+            }
+            "find_available_slots" => {
+                let duration_minutes = arguments.get("duration_minutes")?.as_u64()? as u32;
+                let participants = arguments
+                    .get("participants")
+                    .and_then(|v| v.as_array())
+                    .map(|arr| {
+                        arr.iter()
+                            .filter_map(|v| v.as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let preferred_days = arguments
+                    .get("preferred_days")
+                    .and_then(|v| v.as_array())
+                    .map(|arr| {
+                        arr.iter()
+                            .filter_map(|v| v.as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                Some(NoraExecutiveTool::FindAvailableSlots {
+                    participants,
+                    duration_minutes,
+                    preferred_days,
+                })
+            }
+            "check_calendar_availability" => {
+                let user = arguments.get("user")?.as_str()?.to_string();
+                let start_time_str = arguments.get("start_time")?.as_str()?;
+                let end_time_str = arguments.get("end_time")?.as_str()?;
+
+                let start_time = chrono::DateTime::parse_from_rfc3339(start_time_str)
+                    .ok()?
+                    .with_timezone(&chrono::Utc);
+                let end_time = chrono::DateTime::parse_from_rfc3339(end_time_str)
+                    .ok()?
+                    .with_timezone(&chrono::Utc);
+
+                Some(NoraExecutiveTool::CheckCalendarAvailability {
+                    user,
+                    start_time,
+                    end_time,
                 })
             }
             _ => None,
@@ -3874,7 +4034,7 @@ impl ExecutiveTools {
                         }
                     }
                     let total_usable = clip_analyses.iter().filter(|c| c.usable).count() as u32;
-                    let scene_result = services::services::scene_analysis::SceneAnalysisResult {
+                    let mut scene_result = services::services::scene_analysis::SceneAnalysisResult {
                         batch_id: batch_id.clone(),
                         total_clips: clip_analyses.len() as u32,
                         total_usable,
@@ -3882,6 +4042,12 @@ impl ExecutiveTools {
                         processing_time_ms: 0,
                     };
 
+                    // Multi-cam and re-ingested copies of the same moment are placed once
+                    match pipeline.duplicate_clusters(batch_uuid).await {
+                        Ok(clusters) => services::services::media_fingerprint::mark_duplicates(&mut scene_result, batch_uuid, &clusters),
+                        Err(e) => tracing::warn!("[TOOL] Duplicate detection skipped: {}", e),
+                    }
+
                     // Step 2: Beat analysis
                     tracing::info!("[TOOL] Step 2/4: Beat grid analysis...");
                     let beat_engine = services::services::beat_analysis::BeatAnalysisEngine::new();
@@ -4285,6 +4451,9 @@ impl ExecutiveTools {
                 self.execute_send_discord_message(&channel, &message, &mention_users)
                     .await
             }
+            NoraExecutiveTool::SendSms { to, message } => {
+                self.execute_send_sms(&to, &message).await
+            }
             NoraExecutiveTool::CreateNotification {
                 title,
                 message,
@@ -4302,14 +4471,20 @@ impl ExecutiveTools {
                 end_time,
                 attendees,
                 location,
+                description,
+                timezone,
+                recurrence,
             } => {
-                self.execute_create_calendar_event(
-                    &title,
-                    start_time,
-                    end_time,
-                    &attendees,
-                    location.as_deref(),
-                )
+                self.execute_create_calendar_event(NewCalendarEvent {
+                    title,
+                    start: start_time,
+                    end: end_time,
+                    attendees,
+                    location,
+                    description,
+                    timezone,
+                    recurrence,
+                })
                 .await
             }
             NoraExecutiveTool::FindAvailableSlots {
@@ -4732,6 +4907,49 @@ impl ExecutiveTools {
         }))
     }
 
+    async fn execute_send_sms(&self, to: &str, message: &str) -> crate::Result<serde_json::Value> {
+        if let Some(ref sms_service) = self.sms_service {
+            // Opt-outs live in the database, so texts can't be sent without one
+            let Some(ref executor) = self.task_executor else {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "to": to,
+                    "error": "SMS is unavailable: no database to check opt-outs against"
+                }));
+            };
+            let sms_service = sms_service.clone().with_pool(executor.pool().clone());
+            return match sms_service.send(to, message).await {
+                Ok(sent) => {
+                    tracing::info!("SMS sent to {} with SID: {}", sent.to, sent.message_sid);
+                    Ok(serde_json::json!({
+                        "success": true,
+                        "to": sent.to,
+                        "message": message,
+                        "message_sid": sent.message_sid,
+                        "status": sent.status,
+                        "sent_via": "Twilio SMS"
+                    }))
+                }
+                Err(e) => {
+                    // Opt-outs and rate limits must not look like a delivered text
+                    tracing::warn!("SMS to {} not sent: {}", to, e);
+                    Ok(serde_json::json!({
+                        "success": false,
+                        "to": to,
+                        "error": e.to_string()
+                    }))
+                }
+            };
+        }
+
+        tracing::info!("SMS not sent to {}: Twilio is not configured", to);
+        Ok(serde_json::json!({
+            "success": false,
+            "to": to,
+            "error": "Twilio not configured - SMS was not sent. Set TWILIO_ACCOUNT_SID, TWILIO_AUTH_TOKEN, TWILIO_PHONE_NUMBER and TWILIO_WEBHOOK_BASE_URL env vars to enable."
+        }))
+    }
+
     async fn execute_create_notification(
         &self,
         title: &str,
@@ -4753,33 +4971,37 @@ impl ExecutiveTools {
     // Calendar & Scheduling Implementations
     async fn execute_create_calendar_event(
         &self,
-        title: &str,
-        start_time: DateTime<Utc>,
-        end_time: DateTime<Utc>,
-        attendees: &[String],
-        location: Option<&str>,
+        event: NewCalendarEvent,
     ) -> crate::Result<serde_json::Value> {
-        // Try to use real Google Calendar API if configured
+        // Try to use the configured calendar (CalDAV or Google)
         if let Some(ref calendar_service) = self.calendar_service {
-            match calendar_service
-                .create_event(title, start_time, end_time, attendees, location)
-                .await
-            {
-                Ok(event_id) => {
-                    tracing::info!("Calendar event created: {} (ID: {})", title, event_id);
+            match calendar_service.create_event(&event).await {
+                Ok(created) => {
+                    tracing::info!(
+                        "Calendar event created: {} (ID: {})",
+                        event.title,
+                        created.event_id
+                    );
                     return Ok(serde_json::json!({
                         "success": true,
-                        "event_id": event_id,
-                        "title": title,
-                        "start_time": start_time.to_rfc3339(),
-                        "end_time": end_time.to_rfc3339(),
-                        "attendees": attendees,
-                        "location": location,
-                        "calendar_provider": "Google Calendar"
+                        "event_id": created.event_id,
+                        "title": event.title,
+                        "start_time": event.start.to_rfc3339(),
+                        "end_time": event.end.to_rfc3339(),
+                        "attendees": event.attendees,
+                        "location": event.location,
+                        "timezone": event.timezone,
+                        "recurrence": event.recurrence,
+                        "invites_sent": created.invites_sent,
+                        "calendar_provider": calendar_service.provider_name()
                     }));
                 }
                 Err(e) => {
-                    tracing::warn!("Google Calendar API failed, returning mock data: {}", e);
+                    tracing::warn!(
+                        "{} failed, returning mock data: {}",
+                        calendar_service.provider_name(),
+                        e
+                    );
                 }
             }
         }
@@ -4788,12 +5010,12 @@ impl ExecutiveTools {
         Ok(serde_json::json!({
             "success": true,
             "event_id": uuid::Uuid::new_v4().to_string(),
-            "title": title,
-            "start_time": start_time.to_rfc3339(),
-            "end_time": end_time.to_rfc3339(),
-            "attendees": attendees,
-            "location": location,
-            "note": "Google Calendar not configured - returning mock data. Set GOOGLE_CALENDAR_CREDENTIALS and GOOGLE_CALENDAR_ID env vars to enable."
+            "title": event.title,
+            "start_time": event.start.to_rfc3339(),
+            "end_time": event.end.to_rfc3339(),
+            "attendees": event.attendees,
+            "location": event.location,
+            "note": "Calendar not configured - returning mock data. Set CALDAV_URL, CALDAV_USERNAME and CALDAV_PASSWORD, or GOOGLE_CALENDAR_CREDENTIALS and GOOGLE_CALENDAR_ID, env vars to enable."
         }))
     }
 
@@ -4803,10 +5025,14 @@ impl ExecutiveTools {
         duration_minutes: u32,
         preferred_days: &[String],
     ) -> crate::Result<serde_json::Value> {
-        // Try to use real Google Calendar API if configured
+        // Try to use the configured calendar (CalDAV or Google)
         if let Some(ref calendar_service) = self.calendar_service {
+            let days: Vec<chrono::Weekday> = preferred_days
+                .iter()
+                .filter_map(|day| day.trim().parse().ok())
+                .collect();
             match calendar_service
-                .find_available_slots(participants, duration_minutes, 7)
+                .find_available_slots(participants, duration_minutes, 7, &days)
                 .await
             {
                 Ok(slots) => {
@@ -4826,12 +5052,17 @@ impl ExecutiveTools {
                         "participants": participants,
                         "duration_minutes": duration_minutes,
                         "preferred_days": preferred_days,
+                        "timezone": calendar_service.timezone().name(),
                         "available_slots": formatted_slots,
-                        "calendar_provider": "Google Calendar"
+                        "calendar_provider": calendar_service.provider_name()
                     }));
                 }
                 Err(e) => {
-                    tracing::warn!("Google Calendar API failed, returning mock data: {}", e);
+                    tracing::warn!(
+                        "{} failed, returning mock data: {}",
+                        calendar_service.provider_name(),
+                        e
+                    );
                 }
             }
         }
@@ -4849,7 +5080,7 @@ impl ExecutiveTools {
             "duration_minutes": duration_minutes,
             "preferred_days": preferred_days,
             "available_slots": slots,
-            "note": "Google Calendar not configured - showing mock slots"
+            "note": "Calendar not configured - showing mock slots"
         }))
     }
 
@@ -4859,26 +5090,40 @@ impl ExecutiveTools {
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
     ) -> crate::Result<serde_json::Value> {
-        // Try to use real Google Calendar API if configured
+        // Try to use the configured calendar (CalDAV or Google)
         if let Some(ref calendar_service) = self.calendar_service {
             match calendar_service
-                .check_availability(user, start_time, end_time)
+                .busy_periods(&[user.to_string()], start_time, end_time)
                 .await
             {
-                Ok(is_available) => {
+                Ok(busy) => {
+                    let is_available = busy.is_empty();
                     tracing::info!("Checked availability for {}: {}", user, is_available);
+                    let conflicts: Vec<_> = busy
+                        .iter()
+                        .map(|(start, end)| {
+                            serde_json::json!({
+                                "start": start.to_rfc3339(),
+                                "end": end.to_rfc3339()
+                            })
+                        })
+                        .collect();
                     return Ok(serde_json::json!({
                         "success": true,
                         "user": user,
                         "start_time": start_time.to_rfc3339(),
                         "end_time": end_time.to_rfc3339(),
                         "is_available": is_available,
-                        "conflicts": if is_available { vec![] as Vec<String> } else { vec!["Busy".to_string()] },
-                        "calendar_provider": "Google Calendar"
+                        "conflicts": conflicts,
+                        "calendar_provider": calendar_service.provider_name()
                     }));
                 }
                 Err(e) => {
-                    tracing::warn!("Google Calendar API failed, assuming available: {}", e);
+                    tracing::warn!(
+                        "{} failed, assuming available: {}",
+                        calendar_service.provider_name(),
+                        e
+                    );
                 }
             }
         }
@@ -4891,7 +5136,7 @@ impl ExecutiveTools {
             "end_time": end_time.to_rfc3339(),
             "is_available": true,
             "conflicts": [],
-            "note": "Google Calendar not configured - assuming available"
+            "note": "Calendar not configured - assuming available"
         }))
     }
 
@@ -5474,4 +5719,68 @@ fn hello_world() {
             "run_visual_qc should be in tool definitions"
         );
     }
+
+    #[test]
+    fn test_send_sms_parse() {
+        let args = serde_json::json!({
+            "to": "+447700900123",
+            "message": "Running 10 minutes late"
+        });
+
+        match ExecutiveTools::parse_tool_call("send_sms", &args) {
+            Some(NoraExecutiveTool::SendSms { to, message }) => {
+                assert_eq!(to, "+447700900123");
+                assert_eq!(message, "Running 10 minutes late");
+            }
+            other => panic!("Expected SendSms, got {:?}", other),
+        }
+
+        let missing_number = serde_json::json!({ "message": "Hello" });
+        assert!(ExecutiveTools::parse_tool_call("send_sms", &missing_number).is_none());
+    }
+
+    #[test]
+    fn test_calendar_tools_parse() {
+        let args = serde_json::json!({
+            "title": "Weekly sync",
+            "start_time": "2026-10-19T09:00:00+01:00",
+            "end_time": "2026-10-19T09:30:00+01:00",
+            "attendees": ["ada@example.com"],
+            "timezone": "Europe/London",
+            "recurrence": "FREQ=WEEKLY;COUNT=6"
+        });
+
+        match ExecutiveTools::parse_tool_call("create_calendar_event", &args) {
+            Some(NoraExecutiveTool::CreateCalendarEvent {
+                start_time,
+                attendees,
+                timezone,
+                recurrence,
+                ..
+            }) => {
+                assert_eq!(start_time.to_rfc3339(), "2026-10-19T08:00:00+00:00");
+                assert_eq!(attendees, vec!["ada@example.com".to_string()]);
+                assert_eq!(timezone.as_deref(), Some("Europe/London"));
+                assert_eq!(recurrence.as_deref(), Some("FREQ=WEEKLY;COUNT=6"));
+            }
+            other => panic!("Expected CreateCalendarEvent, got {:?}", other),
+        }
+
+        let args = serde_json::json!({
+            "duration_minutes": 45,
+            "preferred_days": ["Tuesday"]
+        });
+        match ExecutiveTools::parse_tool_call("find_available_slots", &args) {
+            Some(NoraExecutiveTool::FindAvailableSlots {
+                participants,
+                duration_minutes,
+                preferred_days,
+            }) => {
+                assert!(participants.is_empty());
+                assert_eq!(duration_minutes, 45);
+                assert_eq!(preferred_days, vec!["Tuesday".to_string()]);
+            }
+            other => panic!("Expected FindAvailableSlots, got {:?}", other),
+        }
+    }
 }
@@ -1,24 +1,35 @@
 //! Twilio call handler for managing phone conversations with NORA
 //!
 //! Handles the lifecycle of phone calls and integrates with NORA's
-//! conversation capabilities.
+//! conversation capabilities. When a database pool is attached, every call is
+//! written to `call_logs` with its turns in `call_turns`, ready for post-call
+//! processing once it ends.
 
 use std::{collections::HashMap, sync::Arc};
 
 use chrono::{DateTime, Utc};
+use db::models::{
+    call_log::{
+        CallDirection, CallLog, CallLogError, CallStatus as LogStatus, CreateCallLog, UpdateCallLog,
+    },
+    call_turn::{CallTurn, CallTurnSpeaker, CreateCallTurn},
+};
+use sqlx::SqlitePool;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use super::{
-    twiml::TwimlBuilder, TwilioCallRequest, TwilioConfig, TwilioError, TwilioResult,
-    TwilioSpeechResult,
+    twiml::TwimlBuilder, TwilioCallRequest, TwilioConfig, TwilioError, TwilioRecordingCallback,
+    TwilioResult, TwilioSpeechResult,
 };
 
 /// Manages active Twilio phone calls
 pub struct TwilioCallHandler {
     config: TwilioConfig,
     active_calls: Arc<RwLock<HashMap<String, TwilioCallState>>>,
+    pool: Option<SqlitePool>,
+    http: reqwest::Client,
 }
 
 /// State for an active phone call
@@ -42,6 +53,10 @@ pub struct TwilioCallState {
     pub last_activity: DateTime<Utc>,
     /// Caller info (if available)
     pub caller_info: Option<CallerInfo>,
+    /// Persisted call log row (when a database is attached)
+    pub call_log_id: Option<Uuid>,
+    /// Whether a recording has been requested for this call
+    pub recording_started: bool,
 }
 
 /// Call status
@@ -87,9 +102,22 @@ impl TwilioCallHandler {
         Self {
             config,
             active_calls: Arc::new(RwLock::new(HashMap::new())),
+            pool: None,
+            http: reqwest::Client::new(),
         }
     }
 
+    /// Persist calls and their turns to the database
+    pub fn with_pool(mut self, pool: SqlitePool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Database pool calls are persisted to, if any
+    pub fn pool(&self) -> Option<&SqlitePool> {
+        self.pool.as_ref()
+    }
+
     /// Check if Twilio is properly configured
     pub fn is_configured(&self) -> bool {
         self.config.is_configured()
@@ -120,6 +148,15 @@ impl TwilioCallHandler {
             country: request.from_country,
         };
 
+        let call_log_id = match self.log_call(&request).await {
+            Ok(id) => id,
+            Err(e) => {
+                // Never drop a live call because the log could not be written
+                warn!("Failed to log call {}: {}", request.call_sid, e);
+                None
+            }
+        };
+
         let call_state = TwilioCallState {
             call_sid: request.call_sid.clone(),
             session_id: session_id.clone(),
@@ -130,6 +167,8 @@ impl TwilioCallHandler {
             started_at: Utc::now(),
             last_activity: Utc::now(),
             caller_info: Some(caller_info),
+            call_log_id,
+            recording_started: false,
         };
 
         // Store call state
@@ -167,6 +206,7 @@ impl TwilioCallHandler {
         let call_state = calls
             .get_mut(call_sid)
             .ok_or_else(|| TwilioError::CallNotFound(call_sid.clone()))?;
+        let first_new_turn = call_state.conversation.len();
 
         // Record user's speech
         if let Some(ref text) = speech_result.speech_result {
@@ -193,6 +233,16 @@ impl TwilioCallHandler {
 
         call_state.last_activity = Utc::now();
 
+        let new_turns = call_state.conversation[first_new_turn..].to_vec();
+        let call_log_id = call_state.call_log_id;
+
+        // Record once the caller has been heard: Twilio only accepts a
+        // recording request for a call that is already in progress
+        let start_recording = self.config.recording_enabled && !call_state.recording_started;
+        if start_recording {
+            call_state.recording_started = true;
+        }
+
         // Check for goodbye phrases
         let caller_text = speech_result
             .speech_result
@@ -223,6 +273,29 @@ impl TwilioCallHandler {
                 &self.config.speech_language,
             )
         };
+        drop(calls);
+
+        if let (Some(pool), Some(call_log_id)) = (&self.pool, call_log_id) {
+            for turn in new_turns {
+                let data = CreateCallTurn {
+                    call_log_id,
+                    speaker: match turn.speaker {
+                        Speaker::Caller => CallTurnSpeaker::Caller,
+                        Speaker::Nora => CallTurnSpeaker::Nora,
+                    },
+                    content: turn.content,
+                    confidence: turn.confidence,
+                    spoken_at: turn.timestamp,
+                };
+                if let Err(e) = CallTurn::create(pool, data).await {
+                    warn!("Failed to save turn for call {}: {}", call_sid, e);
+                }
+            }
+        }
+
+        if start_recording {
+            self.spawn_recording(call_sid.clone());
+        }
 
         Ok(twiml)
     }
@@ -258,8 +331,6 @@ impl TwilioCallHandler {
                 || call_state.status == CallStatus::Busy
                 || call_state.status == CallStatus::NoAnswer
             {
-                // In production, you might want to persist this to a database
-                // before removing from memory
                 info!(
                     "Call {} ended with {} turns",
                     call_sid,
@@ -269,10 +340,153 @@ impl TwilioCallHandler {
         } else {
             warn!("Status update for unknown call: {}", call_sid);
         }
+        let state = calls.get(call_sid).cloned();
+        drop(calls);
+
+        let Some(pool) = &self.pool else {
+            return Ok(());
+        };
+        let call_log_id = match state.as_ref().and_then(|s| s.call_log_id) {
+            Some(id) => id,
+            None => match CallLog::find_by_call_sid(pool, call_sid).await {
+                Ok(call) => call.id,
+                Err(CallLogError::NotFound) => return Ok(()),
+                Err(e) => return Err(e.into()),
+            },
+        };
+
+        let log_status = parse_log_status(status);
+        let ended = matches!(
+            log_status,
+            Some(
+                LogStatus::Completed
+                    | LogStatus::Failed
+                    | LogStatus::Busy
+                    | LogStatus::NoAnswer
+                    | LogStatus::Canceled
+            )
+        );
+        // Answered calls wait for their transcript and summary before the CRM
+        // picks them up; missed calls are filed straight away
+        let had_conversation = state
+            .as_ref()
+            .is_some_and(|s| !s.conversation.is_empty() || s.recording_started);
+        let transcription_status = (log_status == Some(LogStatus::Completed) && had_conversation)
+            .then(|| "pending".to_string());
+
+        CallLog::update(
+            pool,
+            call_log_id,
+            UpdateCallLog {
+                status: log_status,
+                end_time: ended.then(Utc::now),
+                duration_seconds: duration.map(|d| d as i32),
+                transcription_status,
+                ..Default::default()
+            },
+        )
+        .await?;
 
         Ok(())
     }
 
+    /// Store a finished recording against its call. Returns the updated log
+    /// when the recording is ready to transcribe.
+    pub async fn handle_recording(
+        &self,
+        callback: &TwilioRecordingCallback,
+    ) -> TwilioResult<Option<CallLog>> {
+        info!(
+            "Recording {} for call {}: {}",
+            callback.recording_sid, callback.call_sid, callback.recording_status
+        );
+
+        let Some(pool) = &self.pool else {
+            return Ok(None);
+        };
+        if callback.recording_status != "completed" {
+            warn!(
+                "Recording for call {} finished as {}",
+                callback.call_sid, callback.recording_status
+            );
+            return Ok(None);
+        }
+
+        let call = CallLog::find_by_call_sid(pool, &callback.call_sid).await?;
+        let call = CallLog::update(
+            pool,
+            call.id,
+            UpdateCallLog {
+                recording_url: Some(callback.recording_url.clone()),
+                recording_sid: Some(callback.recording_sid.clone()),
+                recording_duration: callback.recording_duration.map(|d| d as i32),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(Some(call))
+    }
+
+    /// Ask Twilio to start recording a live call, in the background
+    fn spawn_recording(&self, call_sid: String) {
+        let http = self.http.clone();
+        let config = self.config.clone();
+        let active_calls = self.active_calls.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = start_recording(&http, &config, &call_sid).await {
+                warn!("Failed to start recording call {}: {}", call_sid, e);
+                // Nothing will arrive on the recording callback, so let the
+                // call be processed from its turns as soon as it ends
+                if let Some(state) = active_calls.write().await.get_mut(&call_sid) {
+                    state.recording_started = false;
+                }
+            }
+        });
+    }
+
+    /// Write the call log row for a new inbound call
+    async fn log_call(&self, request: &TwilioCallRequest) -> TwilioResult<Option<Uuid>> {
+        let Some(pool) = &self.pool else {
+            return Ok(None);
+        };
+
+        // Twilio retries the voice webhook, so the call may already be logged
+        match CallLog::find_by_call_sid(pool, &request.call_sid).await {
+            Ok(call) => return Ok(Some(call.id)),
+            Err(CallLogError::NotFound) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let Some(project_id) = self.config.resolve_project_id(pool).await? else {
+            warn!("No project to log call {} against", request.call_sid);
+            return Ok(None);
+        };
+
+        let call = CallLog::create(
+            pool,
+            CreateCallLog {
+                project_id,
+                call_sid: request.call_sid.clone(),
+                parent_call_sid: None,
+                account_sid: Some(request.account_sid.clone()),
+                from_number: request.from.clone(),
+                to_number: request.to.clone(),
+                from_formatted: None,
+                to_formatted: None,
+                caller_name: request.caller_name.clone(),
+                direction: CallDirection::Inbound,
+                status: LogStatus::InProgress,
+                answered_by: None,
+                start_time: Some(Utc::now()),
+            },
+        )
+        .await?;
+
+        Ok(Some(call.id))
+    }
+
     /// Get the NORA session ID for a call
     pub async fn get_session_id(&self, call_sid: &str) -> Option<String> {
         let calls = self.active_calls.read().await;
@@ -324,6 +538,11 @@ impl TwilioCallHandler {
         url: &str,
         params: &HashMap<String, String>,
     ) -> bool {
+        signature == self.request_signature(url, params)
+    }
+
+    /// The `X-Twilio-Signature` Twilio sends with a webhook to `url`
+    pub fn request_signature(&self, url: &str, params: &HashMap<String, String>) -> String {
         use ring::hmac;
 
         // Build the validation string
@@ -342,13 +561,62 @@ impl TwilioCallHandler {
             self.config.auth_token.as_bytes(),
         );
         let computed_signature = hmac::sign(&key, validation_string.as_bytes());
-        let computed_b64 = base64::Engine::encode(
+        base64::Engine::encode(
             &base64::engine::general_purpose::STANDARD,
             computed_signature.as_ref(),
-        );
+        )
+    }
+}
+
+/// Start a recording through the Twilio REST API. Twilio reports back on
+/// `/api/twilio/recording` once the recording is available.
+async fn start_recording(
+    http: &reqwest::Client,
+    config: &TwilioConfig,
+    call_sid: &str,
+) -> TwilioResult<()> {
+    let url = format!(
+        "https://api.twilio.com/2010-04-01/Accounts/{}/Calls/{}/Recordings.json",
+        config.account_sid, call_sid
+    );
+    let callback_url = format!("{}/api/twilio/recording", config.webhook_base_url);
+
+    let response = http
+        .post(&url)
+        .basic_auth(&config.account_sid, Some(&config.auth_token))
+        .form(&[
+            ("RecordingStatusCallback", callback_url.as_str()),
+            ("RecordingStatusCallbackEvent", "completed absent"),
+        ])
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(TwilioError::RecordingFailed(format!(
+            "Twilio refused to record ({}): {}",
+            status,
+            body.trim()
+        )));
+    }
+
+    info!("Recording started for call {}", call_sid);
+    Ok(())
+}
 
-        // Constant-time comparison
-        signature == computed_b64
+/// Map a Twilio call status onto the persisted call log status
+fn parse_log_status(status: &str) -> Option<LogStatus> {
+    match status {
+        "queued" => Some(LogStatus::Queued),
+        "ringing" => Some(LogStatus::Ringing),
+        "in-progress" => Some(LogStatus::InProgress),
+        "completed" => Some(LogStatus::Completed),
+        "busy" => Some(LogStatus::Busy),
+        "failed" => Some(LogStatus::Failed),
+        "no-answer" => Some(LogStatus::NoAnswer),
+        "canceled" => Some(LogStatus::Canceled),
+        _ => None,
     }
 }
 
@@ -2,16 +2,23 @@
 //!
 //! Enables users to call a Twilio virtual number and interact with NORA via voice.
 //! Supports inbound calls, speech recognition, and TTS responses using NORA's voice engine.
+//! The same number handles two-way SMS conversations with NORA.
 
 pub mod audio_cache;
 pub mod call_handler;
+pub mod post_call;
+pub mod sms;
 pub mod twiml;
 
 pub use audio_cache::{get_audio_cache, CachedAudio, TwilioAudioCache};
 pub use call_handler::{TwilioCallHandler, TwilioCallState};
+pub use post_call::{CallDigest, PostCallProcessor};
 use serde::{Deserialize, Serialize};
+pub use sms::{InboundSms, SentSms, SmsKeyword, TwilioSmsClient};
+use sqlx::SqlitePool;
 use ts_rs::TS;
 pub use twiml::TwimlBuilder;
+use uuid::Uuid;
 
 /// Configuration for Twilio integration
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -40,6 +47,15 @@ pub struct TwilioConfig {
     /// Greeting message for incoming calls
     #[serde(default = "default_greeting")]
     pub greeting_message: String,
+    /// Project calls and texts are logged against (default: the oldest project)
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    /// Maximum texts Nora sends to one number per hour
+    #[serde(default = "default_sms_rate_limit")]
+    pub sms_rate_limit: u32,
+    /// Reply to the HELP keyword
+    #[serde(default = "default_sms_help_message")]
+    pub sms_help_message: String,
 }
 
 fn default_max_call_duration() -> u32 {
@@ -54,6 +70,14 @@ fn default_tts_voice() -> String {
     "Polly.Amy".to_string() // British female voice (Amy)
 }
 
+fn default_sms_rate_limit() -> u32 {
+    10
+}
+
+fn default_sms_help_message() -> String {
+    "Nora, your Executive AI Assistant. Text any question and Nora will reply. Reply STOP to opt out. Msg & data rates may apply.".to_string()
+}
+
 fn default_greeting() -> String {
     "Hello, this is Nora, your Executive AI Assistant. How may I assist you today?".to_string()
 }
@@ -70,6 +94,9 @@ impl Default for TwilioConfig {
             tts_voice: default_tts_voice(),
             recording_enabled: false,
             greeting_message: default_greeting(),
+            project_id: None,
+            sms_rate_limit: default_sms_rate_limit(),
+            sms_help_message: default_sms_help_message(),
         }
     }
 }
@@ -113,8 +140,28 @@ impl TwilioConfig {
                 .map(|v| v == "true" || v == "1")
                 .unwrap_or(false),
             greeting_message: get_env_or_default("TWILIO_GREETING_MESSAGE", default_greeting()),
+            project_id: std::env::var("TWILIO_PROJECT_ID")
+                .ok()
+                .and_then(|s| Uuid::parse_str(s.trim()).ok()),
+            sms_rate_limit: std::env::var("TWILIO_SMS_RATE_LIMIT")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_else(default_sms_rate_limit),
+            sms_help_message: get_env_or_default("TWILIO_SMS_HELP_MESSAGE", default_sms_help_message()),
         })
     }
+
+    /// Project to log calls and texts against: `project_id` when set,
+    /// otherwise the oldest project
+    pub async fn resolve_project_id(&self, pool: &SqlitePool) -> Result<Option<Uuid>, sqlx::Error> {
+        if let Some(id) = self.project_id {
+            return Ok(Some(id));
+        }
+        Ok(db::models::project::Project::find_all(pool)
+            .await?
+            .last()
+            .map(|project| project.id))
+    }
 }
 
 /// Twilio webhook request for incoming calls
@@ -176,6 +223,45 @@ pub struct TwilioStatusCallback {
     pub recording_sid: Option<String>,
 }
 
+/// Twilio webhook request for an incoming text message
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TwilioSmsRequest {
+    /// Unique identifier for the message
+    pub message_sid: String,
+    /// The Twilio account SID
+    pub account_sid: Option<String>,
+    /// Messaging service the number belongs to, if any
+    pub messaging_service_sid: Option<String>,
+    /// The phone number that sent the message
+    pub from: String,
+    /// The Twilio number that received it
+    pub to: String,
+    /// Message text
+    #[serde(default)]
+    pub body: String,
+    /// Number of SMS segments
+    pub num_segments: Option<String>,
+    /// Number of attached media items (MMS)
+    pub num_media: Option<String>,
+}
+
+/// Twilio recording status callback
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TwilioRecordingCallback {
+    /// The call SID
+    pub call_sid: String,
+    /// Recording SID
+    pub recording_sid: String,
+    /// Recording URL (without a media extension)
+    pub recording_url: String,
+    /// Recording status (in-progress, completed, absent, failed)
+    pub recording_status: String,
+    /// Recording duration in seconds
+    pub recording_duration: Option<u32>,
+}
+
 /// Twilio error types
 #[derive(Debug, thiserror::Error)]
 pub enum TwilioError {
@@ -194,11 +280,44 @@ pub enum TwilioError {
     #[error("TTS generation failed: {0}")]
     TtsGenerationFailed(String),
 
+    #[error("Recording failed: {0}")]
+    RecordingFailed(String),
+
+    #[error("Call summary failed: {0}")]
+    SummaryFailed(String),
+
+    #[error("Invalid phone number: {0}")]
+    InvalidNumber(String),
+
+    #[error("{0} has opted out of text messages")]
+    OptedOut(String),
+
+    #[error("Text message rate limit reached for {0}")]
+    RateLimited(String),
+
+    #[error("SMS sending failed: {0}")]
+    SmsFailed(String),
+
     #[error("Network error: {0}")]
     NetworkError(#[from] reqwest::Error),
 
     #[error("Configuration error: {0}")]
     ConfigError(String),
+
+    #[error(transparent)]
+    CallLog(#[from] db::models::call_log::CallLogError),
+
+    #[error(transparent)]
+    CallTurn(#[from] db::models::call_turn::CallTurnError),
+
+    #[error(transparent)]
+    SmsMessage(#[from] db::models::sms_message::SmsMessageError),
+
+    #[error(transparent)]
+    SmsOptOut(#[from] db::models::sms_opt_out::SmsOptOutError),
+
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
 }
 
 pub type TwilioResult<T> = Result<T, TwilioError>;
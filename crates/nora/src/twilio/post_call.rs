@@ -0,0 +1,544 @@
+//! Post-call processing for Twilio phone calls
+//!
+//! Once a call has ended, Nora transcribes the recording with the voice
+//! engine's speech-to-text (falling back to the turns captured live), asks the
+//! LLM for a summary, sentiment and action items, turns each action item into
+//! a task and files the call against the caller's CRM contact.
+
+use std::{collections::HashSet, sync::Arc};
+
+use base64::Engine;
+use bytes::Bytes;
+use chrono::{NaiveDate, Utc};
+use db::models::{
+    call_log::{CallLog, UpdateCallLog},
+    call_turn::{CallTurn, CallTurnSpeaker},
+    task::{CreateTask, Priority, Task},
+};
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use services::services::crm_enrichment::CrmEnrichmentService;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::{TwilioConfig, TwilioError, TwilioResult};
+use crate::{brain::LLMClient, voice::VoiceEngine};
+
+/// Whisper rejects uploads above 25 MB; longer recordings fall back to the
+/// live transcript
+const MAX_RECORDING_BYTES: usize = 25 * 1024 * 1024;
+
+/// Calls currently being processed, so a recording callback racing the
+/// fallback timer never produces duplicate tasks
+static IN_FLIGHT: Lazy<std::sync::Mutex<HashSet<String>>> =
+    Lazy::new(|| std::sync::Mutex::new(HashSet::new()));
+
+/// A call's place in `IN_FLIGHT`, given back on drop so a panic or a dropped
+/// request can't leave the call claimed forever
+struct InFlightClaim(String);
+
+impl InFlightClaim {
+    fn acquire(call_sid: &str) -> Option<Self> {
+        IN_FLIGHT
+            .lock()
+            .unwrap()
+            .insert(call_sid.to_string())
+            .then(|| Self(call_sid.to_string()))
+    }
+}
+
+impl Drop for InFlightClaim {
+    fn drop(&mut self) {
+        IN_FLIGHT
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&self.0);
+    }
+}
+
+const SUMMARY_PROMPT: &str = r#"You summarise phone calls handled by Nora, an executive assistant. Reply with JSON only, in this shape:
+{"summary": "2-4 sentences covering who called, what they wanted and what was agreed",
+ "sentiment": "positive" | "neutral" | "negative",
+ "action_items": [{"title": "short imperative", "description": "detail needed to act on it", "due_date": "YYYY-MM-DD or null"}]}
+Only include action items someone actually committed to or that clearly need doing. Use British English."#;
+
+/// What the LLM made of a call
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CallDigest {
+    pub summary: String,
+    pub sentiment: String,
+    #[serde(default)]
+    pub action_items: Vec<ActionItem>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionItem {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub due_date: Option<String>,
+}
+
+/// Turns finished calls into transcripts, summaries and follow-up tasks
+pub struct PostCallProcessor {
+    pool: SqlitePool,
+    config: TwilioConfig,
+    voice_engine: Option<Arc<VoiceEngine>>,
+    llm: Option<Arc<LLMClient>>,
+    http: reqwest::Client,
+}
+
+impl PostCallProcessor {
+    pub fn new(pool: SqlitePool, config: TwilioConfig) -> Self {
+        Self {
+            pool,
+            config,
+            voice_engine: None,
+            llm: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Transcribe recordings with this engine's speech-to-text
+    pub fn with_voice_engine(mut self, voice_engine: Arc<VoiceEngine>) -> Self {
+        self.voice_engine = Some(voice_engine);
+        self
+    }
+
+    /// Summarise calls and extract action items with this model
+    pub fn with_llm(mut self, llm: Arc<LLMClient>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    /// Process a call that is waiting on its transcript. Calls that are
+    /// already done, or being processed elsewhere, are skipped.
+    pub async fn process(&self, call_sid: &str) -> TwilioResult<Option<CallLog>> {
+        let Some(_claim) = InFlightClaim::acquire(call_sid) else {
+            return Ok(None);
+        };
+        self.run(call_sid).await
+    }
+
+    /// Pick up calls left pending for longer than `older_than`, e.g. when the
+    /// recording callback never arrived or the server restarted mid-way
+    pub async fn process_stale(&self, older_than: chrono::Duration) -> TwilioResult<usize> {
+        let calls =
+            CallLog::find_pending_transcription(&self.pool, Utc::now() - older_than, 50).await?;
+        let mut processed = 0;
+        for call in calls {
+            match self.process(&call.call_sid).await {
+                Ok(Some(_)) => processed += 1,
+                Ok(None) => {}
+                Err(e) => warn!("Post-call processing failed for {}: {}", call.call_sid, e),
+            }
+        }
+        Ok(processed)
+    }
+
+    async fn run(&self, call_sid: &str) -> TwilioResult<Option<CallLog>> {
+        let call = CallLog::find_by_call_sid(&self.pool, call_sid).await?;
+        if call.transcription_status.as_deref() != Some("pending") {
+            return Ok(None);
+        }
+
+        let turns = CallTurn::find_by_call(&self.pool, call.id).await?;
+        let live_transcript = transcript_from_turns(&turns);
+        let transcript = match self.transcribe_recording(&call).await {
+            Ok(Some(text)) if !text.trim().is_empty() => text,
+            Ok(_) => live_transcript.clone(),
+            Err(e) => {
+                warn!(
+                    "Falling back to live transcript for call {}: {}",
+                    call_sid, e
+                );
+                live_transcript.clone()
+            }
+        };
+
+        if transcript.trim().is_empty() {
+            let call = CallLog::update(
+                &self.pool,
+                call.id,
+                UpdateCallLog {
+                    transcription_status: Some("failed".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            self.link_to_crm(&call).await;
+            return Ok(Some(call));
+        }
+
+        let digest = match self.summarise(&transcript, &live_transcript).await {
+            Ok(digest) => digest,
+            Err(e) => {
+                warn!("Could not summarise call {}: {}", call_sid, e);
+                None
+            }
+        };
+
+        let mut action_items = Vec::new();
+        if let Some(digest) = &digest {
+            for item in &digest.action_items {
+                let task_id = match self.create_task(&call, item).await {
+                    Ok(task) => Some(task.id),
+                    Err(e) => {
+                        warn!("Failed to create task '{}': {}", item.title, e);
+                        None
+                    }
+                };
+                action_items.push(json!({
+                    "title": item.title,
+                    "description": item.description,
+                    "due_date": item.due_date,
+                    "task_id": task_id,
+                }));
+            }
+        }
+
+        let call = CallLog::update(
+            &self.pool,
+            call.id,
+            UpdateCallLog {
+                transcription: Some(transcript),
+                transcription_status: Some("completed".to_string()),
+                summary: digest.as_ref().map(|d| d.summary.clone()),
+                sentiment: digest.as_ref().map(|d| d.sentiment.clone()),
+                action_items: digest
+                    .as_ref()
+                    .map(|_| serde_json::Value::Array(action_items).to_string()),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        info!(
+            "Processed call {} ({} action items)",
+            call_sid,
+            digest.as_ref().map_or(0, |d| d.action_items.len())
+        );
+
+        self.link_to_crm(&call).await;
+        Ok(Some(call))
+    }
+
+    /// Download the call recording and run it through speech-to-text
+    async fn transcribe_recording(&self, call: &CallLog) -> TwilioResult<Option<String>> {
+        let (Some(voice_engine), Some(recording_sid)) = (&self.voice_engine, &call.recording_sid)
+        else {
+            return Ok(None);
+        };
+        // The request carries our credentials, so it only ever goes to Twilio
+        let url =
+            recording_media_url(&self.config.account_sid, recording_sid).ok_or_else(|| {
+                TwilioError::SpeechRecognitionFailed(format!(
+                    "Invalid recording SID {recording_sid}"
+                ))
+            })?;
+
+        let response = self
+            .http
+            .get(url)
+            .basic_auth(&self.config.account_sid, Some(&self.config.auth_token))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(TwilioError::SpeechRecognitionFailed(format!(
+                "Recording download failed ({})",
+                response.status()
+            )));
+        }
+        if let Some(length) = response.content_length() {
+            if length > MAX_RECORDING_BYTES as u64 {
+                return Err(recording_too_large(length));
+            }
+        }
+
+        let audio = read_capped(response.bytes_stream(), MAX_RECORDING_BYTES).await?;
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&audio);
+        voice_engine
+            .transcribe_speech(&encoded)
+            .await
+            .map(Some)
+            .map_err(|e| TwilioError::SpeechRecognitionFailed(e.to_string()))
+    }
+
+    async fn summarise(
+        &self,
+        transcript: &str,
+        live_transcript: &str,
+    ) -> TwilioResult<Option<CallDigest>> {
+        let Some(llm) = &self.llm else {
+            return Ok(None);
+        };
+
+        let context = format!(
+            "Today is {}. Speaker-labelled notes taken during the call:\n{}",
+            Utc::now().format("%Y-%m-%d"),
+            live_transcript
+        );
+        let response = llm
+            .generate(SUMMARY_PROMPT, transcript, &context)
+            .await
+            .map_err(|e| TwilioError::SummaryFailed(e.to_string()))?;
+
+        Ok(parse_digest(&response))
+    }
+
+    async fn create_task(&self, call: &CallLog, item: &ActionItem) -> Result<Task, sqlx::Error> {
+        let caller = call.caller_name.as_deref().unwrap_or(&call.from_number);
+        let mut description = item.description.clone().unwrap_or_default();
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str(&format!(
+            "From a call with {} on {}.",
+            caller,
+            call.start_time
+                .unwrap_or(call.created_at)
+                .format("%d %B %Y")
+        ));
+
+        let create = CreateTask {
+            project_id: call.project_id,
+            pod_id: None,
+            board_id: None,
+            title: item.title.clone(),
+            description: Some(description),
+            parent_task_attempt: None,
+            image_ids: None,
+            priority: Some(Priority::Medium),
+            assignee_id: None,
+            assigned_agent: None,
+            agent_id: None,
+            assigned_mcps: None,
+            created_by: "nora".to_string(),
+            requires_approval: Some(false),
+            parent_task_id: None,
+            tags: Some(vec!["call".to_string(), "action-item".to_string()]),
+            due_date: item
+                .due_date
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+                .and_then(|d| d.and_hms_opt(17, 0, 0))
+                .map(|d| d.and_utc()),
+            custom_properties: Some(json!({
+                "call_log_id": call.id,
+                "call_sid": call.call_sid,
+                "caller_number": call.from_number,
+            })),
+            scheduled_start: None,
+            scheduled_end: None,
+        };
+
+        Task::create(&self.pool, &create, Uuid::new_v4()).await
+    }
+
+    async fn link_to_crm(&self, call: &CallLog) {
+        if let Err(e) = CrmEnrichmentService::new(self.pool.clone())
+            .ingest_call(call)
+            .await
+        {
+            warn!("Failed to link call {} to CRM: {}", call.call_sid, e);
+        }
+    }
+}
+
+/// The conversation as captured live, one speaker-labelled line per turn
+pub fn transcript_from_turns(turns: &[CallTurn]) -> String {
+    turns
+        .iter()
+        .map(|turn| {
+            let speaker = if turn.speaker == CallTurnSpeaker::Caller.to_string() {
+                "Caller"
+            } else {
+                "Nora"
+            };
+            format!("{}: {}", speaker, turn.content.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Read the LLM's JSON reply, tolerating code fences and stray prose
+pub fn parse_digest(response: &str) -> Option<CallDigest> {
+    let mut digest: CallDigest = serde_json::from_str(extract_json_from_response(response)).ok()?;
+
+    digest.summary = digest.summary.trim().to_string();
+    digest.sentiment = match digest.sentiment.trim().to_lowercase().as_str() {
+        sentiment @ ("positive" | "neutral" | "negative") => sentiment.to_string(),
+        _ => "unknown".to_string(),
+    };
+    digest
+        .action_items
+        .retain(|item| !item.title.trim().is_empty());
+
+    Some(digest)
+}
+
+fn extract_json_from_response(response: &str) -> &str {
+    let trimmed = response.trim();
+
+    if trimmed.starts_with("```") {
+        if let Some(start) = trimmed.find('\n') {
+            let after_fence = &trimmed[start + 1..];
+            if let Some(end) = after_fence.rfind("```") {
+                return after_fence[..end].trim();
+            }
+        }
+    }
+
+    if let (Some(start), Some(end)) = (trimmed.find('{'), trimmed.rfind('}')) {
+        if start < end {
+            return &trimmed[start..=end];
+        }
+    }
+
+    trimmed
+}
+
+/// Media URL of a recording in Twilio's REST API
+fn recording_media_url(account_sid: &str, recording_sid: &str) -> Option<String> {
+    let is_sid = |sid: &str| !sid.is_empty() && sid.chars().all(|c| c.is_ascii_alphanumeric());
+    (is_sid(account_sid) && is_sid(recording_sid)).then(|| {
+        format!(
+            "https://api.twilio.com/2010-04-01/Accounts/{}/Recordings/{}.wav",
+            account_sid, recording_sid
+        )
+    })
+}
+
+fn recording_too_large(bytes: u64) -> TwilioError {
+    TwilioError::SpeechRecognitionFailed(format!(
+        "Recording is too large to transcribe ({} bytes)",
+        bytes
+    ))
+}
+
+/// Read a download, giving up as soon as it grows past `cap` bytes
+async fn read_capped<S, E>(stream: S, cap: usize) -> TwilioResult<Vec<u8>>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+    TwilioError: From<E>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut audio = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        if audio.len() + chunk.len() > cap {
+            return Err(recording_too_large((audio.len() + chunk.len()) as u64));
+        }
+        audio.extend_from_slice(&chunk);
+    }
+    Ok(audio)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(speaker: CallTurnSpeaker, content: &str) -> CallTurn {
+        CallTurn {
+            id: Uuid::new_v4(),
+            call_log_id: Uuid::nil(),
+            speaker: speaker.to_string(),
+            content: content.to_string(),
+            confidence: None,
+            spoken_at: Utc::now(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn recordings_are_only_fetched_from_twilio() {
+        assert_eq!(
+            recording_media_url("AC123", "RE456").as_deref(),
+            Some("https://api.twilio.com/2010-04-01/Accounts/AC123/Recordings/RE456.wav")
+        );
+        assert_eq!(recording_media_url("AC123", "../../evil.example/x"), None);
+        assert_eq!(recording_media_url("AC123", ""), None);
+    }
+
+    #[tokio::test]
+    async fn downloads_stop_at_the_size_cap() {
+        let chunks = || {
+            futures::stream::iter(
+                vec![Bytes::from_static(b"abcd"); 3]
+                    .into_iter()
+                    .map(Ok::<_, TwilioError>),
+            )
+        };
+        assert_eq!(read_capped(chunks(), 12).await.unwrap().len(), 12);
+        assert!(matches!(
+            read_capped(chunks(), 10).await,
+            Err(TwilioError::SpeechRecognitionFailed(_))
+        ));
+    }
+
+    #[test]
+    fn in_flight_claims_are_released_on_drop_and_panic() {
+        let call_sid = format!("CA{}", Uuid::new_v4().simple());
+        let claim = InFlightClaim::acquire(&call_sid).unwrap();
+        assert!(InFlightClaim::acquire(&call_sid).is_none());
+        drop(claim);
+
+        let sid = call_sid.clone();
+        let panicked = std::panic::catch_unwind(move || {
+            let _claim = InFlightClaim::acquire(&sid).unwrap();
+            panic!("processing failed");
+        });
+        assert!(panicked.is_err());
+        assert!(InFlightClaim::acquire(&call_sid).is_some());
+    }
+
+    #[test]
+    fn parses_fenced_digest() {
+        let response = r#"Here you go:
+```json
+{
+  "summary": " Sam from Acme wants a retrofit quote. ",
+  "sentiment": "Positive",
+  "action_items": [
+    {"title": "Send retrofit quote", "description": "Two sites", "due_date": "2026-10-21"},
+    {"title": "  "}
+  ]
+}
+```"#;
+
+        let digest = parse_digest(response).unwrap();
+        assert_eq!(digest.summary, "Sam from Acme wants a retrofit quote.");
+        assert_eq!(digest.sentiment, "positive");
+        assert_eq!(digest.action_items.len(), 1);
+        assert_eq!(
+            digest.action_items[0].due_date.as_deref(),
+            Some("2026-10-21")
+        );
+    }
+
+    #[test]
+    fn unknown_sentiment_and_missing_items() {
+        let digest = parse_digest(r#"{"summary": "Wrong number.", "sentiment": "mixed"}"#).unwrap();
+        assert_eq!(digest.sentiment, "unknown");
+        assert!(digest.action_items.is_empty());
+
+        assert!(parse_digest("I couldn't summarise that call.").is_none());
+    }
+
+    #[test]
+    fn labels_live_turns() {
+        let transcript = transcript_from_turns(&[
+            turn(CallTurnSpeaker::Caller, "Can you book me in for Tuesday? "),
+            turn(CallTurnSpeaker::Nora, "Of course, 10am is free."),
+        ]);
+        assert_eq!(
+            transcript,
+            "Caller: Can you book me in for Tuesday?\nNora: Of course, 10am is free."
+        );
+    }
+}
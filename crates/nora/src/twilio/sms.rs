@@ -0,0 +1,522 @@
+//! Two-way SMS conversations with NORA
+//!
+//! Inbound texts arrive on `/api/twilio/sms`. Compliance keywords (STOP,
+//! START, HELP) are answered here; anything else is handed to NORA in a
+//! session per phone number and the reply goes back through the Twilio REST
+//! API. Every message is stored in `sms_messages`, numbers that texted STOP
+//! are never messaged again, and each number gets at most
+//! `sms_rate_limit` texts an hour.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use db::models::{
+    sms_message::{
+        CreateSmsMessage, SmsDirection, SmsMessage, SmsMessageError, SmsStatus, UpdateSmsMessage,
+    },
+    sms_opt_out::SmsOptOut,
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use services::services::crm_enrichment::normalize_phone;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use super::{TwilioConfig, TwilioError, TwilioResult, TwilioSmsRequest};
+
+/// Messages of earlier conversation given to NORA with each text
+const HISTORY_MESSAGES: i64 = 10;
+
+const RATE_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+/// Texts sent per number, shared by every client so the webhook and the
+/// executive tool count against the same limit
+static OUTBOUND_LIMITER: Lazy<SmsRateLimiter> = Lazy::new(SmsRateLimiter::default);
+
+/// Carrier compliance keywords, only honoured when sent on their own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmsKeyword {
+    OptOut,
+    OptIn,
+    Help,
+}
+
+impl SmsKeyword {
+    pub fn parse(body: &str) -> Option<Self> {
+        let word = body
+            .trim()
+            .trim_end_matches(['.', '!'])
+            .to_ascii_uppercase();
+        match word.as_str() {
+            "STOP" | "STOPALL" | "UNSUBSCRIBE" | "CANCEL" | "END" | "QUIT" | "OPTOUT"
+            | "REVOKE" => Some(SmsKeyword::OptOut),
+            "START" | "YES" | "UNSTOP" => Some(SmsKeyword::OptIn),
+            "HELP" | "INFO" => Some(SmsKeyword::Help),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with an incoming text
+#[derive(Debug)]
+pub enum InboundSms {
+    /// Twilio retried a webhook for a message already stored
+    Duplicate,
+    /// A compliance keyword was handled; answer with this text
+    Keyword { keyword: SmsKeyword, reply: String },
+    /// The sender has opted out; the message is stored but not answered
+    OptedOut,
+    /// The number has had its hourly share of replies; left for a person
+    RateLimited(SmsMessage),
+    /// Hand the text to NORA and send the answer with
+    /// [`TwilioSmsClient::reply`]
+    Conversation {
+        message: SmsMessage,
+        session_id: String,
+        history: String,
+    },
+}
+
+/// A text accepted by Twilio for delivery
+#[derive(Debug, Clone)]
+pub struct SentSms {
+    pub message_sid: String,
+    pub to: String,
+    pub status: String,
+    /// Stored copy, when a database is attached
+    pub stored: Option<SmsMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MessageResource {
+    sid: String,
+    status: String,
+    num_segments: Option<String>,
+}
+
+/// Sends and receives text messages on the configured Twilio number
+#[derive(Debug, Clone)]
+pub struct TwilioSmsClient {
+    config: TwilioConfig,
+    http: reqwest::Client,
+    pool: Option<SqlitePool>,
+}
+
+impl TwilioSmsClient {
+    pub fn new(config: TwilioConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+            pool: None,
+        }
+    }
+
+    /// Create a client from the `TWILIO_*` environment variables
+    pub fn from_env() -> Option<Self> {
+        TwilioConfig::from_env()
+            .filter(|config| config.is_configured())
+            .map(Self::new)
+    }
+
+    /// Store messages and honour opt-outs using this database
+    pub fn with_pool(mut self, pool: SqlitePool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    pub fn config(&self) -> &TwilioConfig {
+        &self.config
+    }
+
+    /// Send a text on NORA's behalf
+    pub async fn send(&self, to: &str, body: &str) -> TwilioResult<SentSms> {
+        self.deliver(to, body, SmsDirection::OutboundApi).await
+    }
+
+    /// Answer an incoming text and mark it as responded to
+    pub async fn reply(&self, inbound: &SmsMessage, body: &str) -> TwilioResult<SentSms> {
+        let sent = self
+            .deliver(&inbound.from_number, body, SmsDirection::OutboundReply)
+            .await?;
+
+        if let Some(pool) = &self.pool {
+            SmsMessage::update(
+                pool,
+                inbound.id,
+                UpdateSmsMessage {
+                    auto_response: Some(body.to_string()),
+                    needs_response: Some(false),
+                    responded_at: Some(Utc::now()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+
+        Ok(sent)
+    }
+
+    /// Store an incoming text and decide how to answer it
+    pub async fn receive(&self, request: &TwilioSmsRequest) -> TwilioResult<InboundSms> {
+        let pool = self.pool.as_ref().ok_or_else(|| {
+            TwilioError::ConfigError("SMS conversations need a database".to_string())
+        })?;
+
+        match SmsMessage::find_by_message_sid(pool, &request.message_sid).await {
+            Ok(_) => return Ok(InboundSms::Duplicate),
+            Err(SmsMessageError::NotFound) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let sender = normalize_phone(&request.from)
+            .ok_or_else(|| TwilioError::InvalidNumber(request.from.clone()))?;
+        let project_id = self.config.resolve_project_id(pool).await?.ok_or_else(|| {
+            TwilioError::ConfigError("No project to log text messages against".to_string())
+        })?;
+
+        let keyword = SmsKeyword::parse(&request.body);
+        let message = SmsMessage::create(
+            pool,
+            CreateSmsMessage {
+                project_id,
+                message_sid: request.message_sid.clone(),
+                account_sid: request.account_sid.clone(),
+                messaging_service_sid: request.messaging_service_sid.clone(),
+                from_number: request.from.clone(),
+                to_number: request.to.clone(),
+                body: request.body.clone(),
+                num_segments: request.num_segments.as_deref().and_then(|n| n.parse().ok()),
+                num_media: request.num_media.as_deref().and_then(|n| n.parse().ok()),
+                media_urls: None,
+                direction: SmsDirection::Inbound,
+                status: SmsStatus::Received,
+                date_sent: Some(Utc::now()),
+            },
+        )
+        .await?;
+        info!("Text {} from {}", message.message_sid, sender);
+
+        match keyword {
+            Some(SmsKeyword::OptOut) => {
+                SmsOptOut::opt_out(pool, &sender, request.body.trim()).await?;
+                info!("{} opted out of text messages", sender);
+                return Ok(InboundSms::Keyword {
+                    keyword: SmsKeyword::OptOut,
+                    reply: "You have been unsubscribed and will receive no further messages. \
+                            Reply START to resubscribe."
+                        .to_string(),
+                });
+            }
+            // "Yes" is an everyday answer, so opting in only applies to
+            // numbers that actually opted out
+            Some(SmsKeyword::OptIn) if SmsOptOut::opt_in(pool, &sender).await? => {
+                info!("{} opted back in to text messages", sender);
+                return Ok(InboundSms::Keyword {
+                    keyword: SmsKeyword::OptIn,
+                    reply: "You have been resubscribed. Text any time and Nora will reply. \
+                            Reply STOP to opt out."
+                        .to_string(),
+                });
+            }
+            Some(SmsKeyword::Help) => {
+                return Ok(InboundSms::Keyword {
+                    keyword: SmsKeyword::Help,
+                    reply: self.config.sms_help_message.clone(),
+                });
+            }
+            Some(SmsKeyword::OptIn) | None => {}
+        }
+
+        if SmsOptOut::is_opted_out(pool, &sender).await? {
+            return Ok(InboundSms::OptedOut);
+        }
+
+        if !OUTBOUND_LIMITER.has_capacity(&sender, self.limit(), RATE_WINDOW, Instant::now()) {
+            warn!("Not answering {}: hourly text limit reached", sender);
+            let message = SmsMessage::update(
+                pool,
+                message.id,
+                UpdateSmsMessage {
+                    needs_response: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            return Ok(InboundSms::RateLimited(message));
+        }
+
+        let history = conversation_history(pool, &message).await?;
+        Ok(InboundSms::Conversation {
+            session_id: session_id(&sender),
+            message,
+            history,
+        })
+    }
+
+    fn limit(&self) -> usize {
+        self.config.sms_rate_limit as usize
+    }
+
+    async fn deliver(
+        &self,
+        to: &str,
+        body: &str,
+        direction: SmsDirection,
+    ) -> TwilioResult<SentSms> {
+        let to = normalize_phone(to).ok_or_else(|| TwilioError::InvalidNumber(to.to_string()))?;
+        if body.trim().is_empty() {
+            return Err(TwilioError::SmsFailed("Message is empty".to_string()));
+        }
+        // Without a database there is no way to honour STOP, so don't send
+        let pool = self.pool.as_ref().ok_or_else(|| {
+            TwilioError::ConfigError("Sending texts needs a database to check opt-outs".to_string())
+        })?;
+        if SmsOptOut::is_opted_out(pool, &to).await? {
+            return Err(TwilioError::OptedOut(to));
+        }
+        if !OUTBOUND_LIMITER.try_acquire(&to, self.limit(), RATE_WINDOW, Instant::now()) {
+            return Err(TwilioError::RateLimited(to));
+        }
+
+        let url = format!(
+            "https://api.twilio.com/2010-04-01/Accounts/{}/Messages.json",
+            self.config.account_sid
+        );
+        let response = self
+            .http
+            .post(&url)
+            .basic_auth(&self.config.account_sid, Some(&self.config.auth_token))
+            .form(&[
+                ("To", to.as_str()),
+                ("From", self.config.phone_number.as_str()),
+                ("Body", body),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(TwilioError::SmsFailed(format!(
+                "Twilio rejected the message ({}): {}",
+                status,
+                body.trim()
+            )));
+        }
+
+        let resource: MessageResource = response.json().await?;
+        info!("Text {} sent to {}", resource.sid, to);
+
+        let stored = self
+            .store_outbound(pool, &resource, &to, body, direction)
+            .await;
+
+        Ok(SentSms {
+            message_sid: resource.sid,
+            to,
+            status: resource.status,
+            stored,
+        })
+    }
+
+    /// Keep a copy of a sent text. Delivery already happened, so failures
+    /// here are only logged.
+    async fn store_outbound(
+        &self,
+        pool: &SqlitePool,
+        resource: &MessageResource,
+        to: &str,
+        body: &str,
+        direction: SmsDirection,
+    ) -> Option<SmsMessage> {
+        let project_id = match self.config.resolve_project_id(pool).await {
+            Ok(Some(id)) => id,
+            Ok(None) => return None,
+            Err(e) => {
+                warn!("Failed to store text {}: {}", resource.sid, e);
+                return None;
+            }
+        };
+
+        let data = CreateSmsMessage {
+            project_id,
+            message_sid: resource.sid.clone(),
+            account_sid: Some(self.config.account_sid.clone()),
+            messaging_service_sid: None,
+            from_number: self.config.phone_number.clone(),
+            to_number: to.to_string(),
+            body: body.to_string(),
+            num_segments: resource
+                .num_segments
+                .as_deref()
+                .and_then(|n| n.parse().ok()),
+            num_media: None,
+            media_urls: None,
+            direction,
+            status: parse_status(&resource.status),
+            date_sent: Some(Utc::now()),
+        };
+        match SmsMessage::create(pool, data).await {
+            Ok(message) => Some(message),
+            Err(e) => {
+                warn!("Failed to store text {}: {}", resource.sid, e);
+                None
+            }
+        }
+    }
+}
+
+/// NORA session for a phone number, so each texter keeps one conversation
+pub fn session_id(phone_number: &str) -> String {
+    format!("sms-{}", phone_number.trim_start_matches('+'))
+}
+
+/// Earlier messages with the sender, oldest first
+async fn conversation_history(pool: &SqlitePool, message: &SmsMessage) -> TwilioResult<String> {
+    let mut messages = SmsMessage::find_conversation(
+        pool,
+        message.project_id,
+        &message.from_number,
+        HISTORY_MESSAGES + 1,
+    )
+    .await?;
+    messages.retain(|m| m.id != message.id);
+    messages.reverse();
+
+    Ok(format_history(&messages))
+}
+
+fn format_history(messages: &[SmsMessage]) -> String {
+    messages
+        .iter()
+        .map(|m| {
+            let speaker = if m.direction == SmsDirection::Inbound.to_string() {
+                "Them"
+            } else {
+                "Nora"
+            };
+            format!("{}: {}", speaker, m.body.trim())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn parse_status(status: &str) -> SmsStatus {
+    match status {
+        "accepted" => SmsStatus::Accepted,
+        "sending" => SmsStatus::Sending,
+        "sent" => SmsStatus::Sent,
+        "delivered" => SmsStatus::Delivered,
+        "undelivered" => SmsStatus::Undelivered,
+        "failed" => SmsStatus::Failed,
+        _ => SmsStatus::Queued,
+    }
+}
+
+/// Sliding-window count of texts sent to each number
+#[derive(Default)]
+struct SmsRateLimiter {
+    sent: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl SmsRateLimiter {
+    /// Whether another text to `key` would stay within the limit
+    fn has_capacity(&self, key: &str, limit: usize, window: Duration, now: Instant) -> bool {
+        let mut sent = self.sent.lock().unwrap();
+        let times = sent.entry(key.to_string()).or_default();
+        prune(times, window, now);
+        times.len() < limit
+    }
+
+    /// Count a text to `key` if the limit allows it. The check and the count
+    /// happen under one lock so concurrent sends can't both squeeze in.
+    fn try_acquire(&self, key: &str, limit: usize, window: Duration, now: Instant) -> bool {
+        let mut sent = self.sent.lock().unwrap();
+        let times = sent.entry(key.to_string()).or_default();
+        prune(times, window, now);
+        if times.len() >= limit {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+/// Drop send times that have left the window
+fn prune(times: &mut VecDeque<Instant>, window: Duration, now: Instant) {
+    while times
+        .front()
+        .is_some_and(|t| now.duration_since(*t) >= window)
+    {
+        times.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_keywords_only_on_their_own() {
+        assert_eq!(SmsKeyword::parse(" stop "), Some(SmsKeyword::OptOut));
+        assert_eq!(SmsKeyword::parse("Unsubscribe."), Some(SmsKeyword::OptOut));
+        assert_eq!(SmsKeyword::parse("START"), Some(SmsKeyword::OptIn));
+        assert_eq!(SmsKeyword::parse("help!"), Some(SmsKeyword::Help));
+        assert_eq!(SmsKeyword::parse("Please stop the meeting at 4"), None);
+        assert_eq!(SmsKeyword::parse(""), None);
+    }
+
+    #[tokio::test]
+    async fn refuses_to_send_without_a_database() {
+        let client = TwilioSmsClient::new(TwilioConfig::default());
+        assert!(matches!(
+            client.send("+447700900123", "Hello").await,
+            Err(TwilioError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn rate_limit_slides() {
+        let limiter = SmsRateLimiter::default();
+        let window = Duration::from_secs(60);
+        let start = Instant::now();
+
+        assert!(limiter.try_acquire("+447700900123", 2, window, start));
+        assert!(limiter.try_acquire("+447700900123", 2, window, start));
+        assert!(!limiter.try_acquire("+447700900123", 2, window, start));
+        assert!(limiter.try_acquire("+447700900999", 2, window, start));
+
+        let later = start + window;
+        assert!(limiter.has_capacity("+447700900123", 2, window, later));
+        assert!(limiter.try_acquire("+447700900123", 2, window, later));
+    }
+
+    #[test]
+    fn concurrent_sends_respect_the_limit() {
+        let limiter = std::sync::Arc::new(SmsRateLimiter::default());
+        let window = Duration::from_secs(60);
+        let now = Instant::now();
+
+        let handles: Vec<_> = (0..16)
+            .map(|_| {
+                let limiter = limiter.clone();
+                std::thread::spawn(move || limiter.try_acquire("+447700900123", 3, window, now))
+            })
+            .collect();
+        let sent = handles
+            .into_iter()
+            .map(|h| h.join().unwrap())
+            .filter(|acquired| *acquired)
+            .count();
+        assert_eq!(sent, 3);
+    }
+
+    #[test]
+    fn sessions_are_keyed_by_number() {
+        assert_eq!(session_id("+447700900123"), "sms-447700900123");
+    }
+}
@@ -1,6 +1,7 @@
 //! TwiML (Twilio Markup Language) builder for generating voice responses
 //!
-//! Creates XML responses that Twilio uses to control phone calls.
+//! Creates XML responses that Twilio uses to control phone calls and answer
+//! text messages.
 
 use std::fmt::Write;
 
@@ -53,6 +54,9 @@ enum TwimlElement {
     Reject {
         reason: String,
     },
+    Message {
+        body: String,
+    },
 }
 
 /// Input types for Gather
@@ -251,6 +255,14 @@ impl TwimlBuilder {
         self
     }
 
+    /// Add a Message element (reply to an incoming text)
+    pub fn message(mut self, body: &str) -> Self {
+        self.elements.push(TwimlElement::Message {
+            body: xml_escape(body),
+        });
+        self
+    }
+
     /// Build the TwiML XML string
     pub fn build(self) -> String {
         let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Response>\n");
@@ -442,6 +454,9 @@ fn render_element(xml: &mut String, element: &TwimlElement, indent: usize) {
         TwimlElement::Reject { reason } => {
             let _ = writeln!(xml, "{}<Reject reason=\"{}\"/>", indent_str, reason);
         }
+        TwimlElement::Message { body } => {
+            let _ = writeln!(xml, "{}<Message>{}</Message>", indent_str, body);
+        }
     }
 }
 
@@ -478,6 +493,12 @@ mod tests {
         assert!(twiml.contains("Goodbye"));
     }
 
+    #[test]
+    fn test_message_twiml() {
+        let twiml = TwimlBuilder::new().message("Reply STOP & relax").build();
+        assert!(twiml.contains("<Message>Reply STOP &amp; relax</Message>"));
+    }
+
     #[test]
     fn test_xml_escape() {
         let escaped = xml_escape("Hello <world> & \"friends\"");
@@ -63,7 +63,7 @@ pub use engine::VoiceEngine;
 //     ControlAction, IntentMatch, StatusTarget, VoiceCommandRouter, VoiceIntent,
 // };
 use serde::{Deserialize, Serialize};
-pub use stt::{SpeechToText, TranscriptionResult};
+pub use stt::{CaptionTranscriber, SpeechToText, TranscriptionResult};
 use ts_rs::TS;
 pub use tts::{TextToSpeech, VoiceProfile};
 
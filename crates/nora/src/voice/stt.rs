@@ -1,10 +1,11 @@
 //! Speech-to-Text implementations adapted from voice-agent-v2
 
-use std::time::Instant;
+use std::{path::Path, time::Instant};
 
 use async_trait::async_trait;
 use base64::engine::Engine;
 use serde::{Deserialize, Serialize};
+use services::services::editron::{CaptionWord, EditronError, EditronResult, Transcriber};
 use tracing::{info, warn};
 use ts_rs::TS;
 
@@ -92,7 +93,8 @@ impl WhisperSTT {
                     self.config.language.clone()
                 },
             )
-            .text("response_format", "verbose_json");
+            .text("response_format", "verbose_json")
+            .text("timestamp_granularities[]", "word");
 
         let response = self
             .client
@@ -641,3 +643,40 @@ impl SpeechToText for LocalWhisperSTT {
         ])
     }
 }
+
+/// Adapts any [`SpeechToText`] engine to Editron's caption [`Transcriber`].
+/// The engine must return word timestamps (hosted Whisper does; the local
+/// Whisper server does not yet).
+pub struct CaptionTranscriber<S>(pub S);
+
+#[async_trait]
+impl<S> Transcriber for CaptionTranscriber<S>
+where
+    S: SpeechToText + Send + Sync,
+{
+    async fn transcribe(&self, wav_path: &Path) -> EditronResult<Vec<CaptionWord>> {
+        let audio = tokio::fs::read(wav_path).await?;
+        let encoded = base64::engine::general_purpose::STANDARD.encode(audio);
+        let result = self
+            .0
+            .transcribe_audio(&encoded)
+            .await
+            .map_err(|e| EditronError::Process(format!("Transcription failed: {}", e)))?;
+
+        if result.word_timestamps.is_empty() && !result.text.trim().is_empty() {
+            return Err(EditronError::InvalidFormat(
+                "Speech-to-text engine returned no word timestamps".to_string(),
+            ));
+        }
+
+        Ok(result
+            .word_timestamps
+            .into_iter()
+            .map(|w| CaptionWord {
+                text: w.word.trim().to_string(),
+                start_ms: w.start_time_ms,
+                end_ms: w.end_time_ms.max(w.start_time_ms),
+            })
+            .collect())
+    }
+}
@@ -0,0 +1,131 @@
+//! Round trip through a real CalDAV server.
+//!
+//! Start a throwaway Radicale server that accepts any login and run the
+//! ignored tests against it:
+//!
+//! ```sh
+//! python3 -m pip install radicale
+//! python3 -m radicale --auth-type none --storage-filesystem-folder /tmp/radicale \
+//!   --server-hosts 127.0.0.1:5232
+//! CALDAV_TEST_URL=http://127.0.0.1:5232/nora/ cargo test -p nora --test caldav -- --ignored
+//! ```
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use nora::integrations::{
+    caldav::{CalDavClient, CalDavConfig},
+    CalendarService, NewCalendarEvent,
+};
+use reqwest::Method;
+use uuid::Uuid;
+
+fn test_url() -> String {
+    std::env::var("CALDAV_TEST_URL").expect("CALDAV_TEST_URL must point at a CalDAV server")
+}
+
+fn utc(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+/// A fresh calendar per run so reruns against the same server stay independent
+async fn create_calendar(base: &str) -> CalDavClient {
+    let url = format!("{}/{}/", base.trim_end_matches('/'), Uuid::new_v4());
+    let response = reqwest::Client::new()
+        .request(Method::from_bytes(b"MKCALENDAR").unwrap(), &url)
+        .basic_auth("nora", Some("secret"))
+        .send()
+        .await
+        .expect("MKCALENDAR failed");
+    assert!(
+        response.status().is_success(),
+        "MKCALENDAR returned {}",
+        response.status()
+    );
+
+    CalDavClient::new(CalDavConfig {
+        url,
+        username: "nora".into(),
+        password: "secret".into(),
+        timezone: "Europe/London".parse::<Tz>().unwrap(),
+        organizer_email: Some("nora@localhost".into()),
+        send_invites: false,
+    })
+}
+
+#[ignore = "needs a CalDAV server at CALDAV_TEST_URL"]
+#[tokio::test]
+async fn books_recurring_meetings_and_reports_busy_time() {
+    let client = create_calendar(&test_url()).await;
+    let calendar = CalendarService::caldav(client.clone(), None);
+    assert_eq!(calendar.provider_name(), "CalDAV");
+
+    // Mondays at 9:00 London time, either side of the clocks going back
+    let created = calendar
+        .create_event(&NewCalendarEvent {
+            title: "Pipeline review".into(),
+            start: utc("2026-10-19T08:00:00Z"),
+            end: utc("2026-10-19T08:30:00Z"),
+            attendees: vec!["ada@localhost".into()],
+            location: Some("Boardroom".into()),
+            description: None,
+            timezone: None,
+            recurrence: Some("FREQ=WEEKLY;COUNT=3".into()),
+        })
+        .await
+        .expect("failed to create event");
+    assert_eq!(created.invites_sent, 0);
+
+    let from = utc("2026-10-18T00:00:00Z");
+    let to = utc("2026-11-09T00:00:00Z");
+    let events = client
+        .events_between(from, to)
+        .await
+        .expect("calendar query failed");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].uid, created.event_id);
+    assert_eq!(events[0].attendees[0].email, "ada@localhost");
+
+    let busy = client
+        .busy_periods(from, to)
+        .await
+        .expect("busy lookup failed");
+    assert_eq!(
+        busy,
+        vec![
+            (utc("2026-10-19T08:00:00Z"), utc("2026-10-19T08:30:00Z")),
+            (utc("2026-10-26T09:00:00Z"), utc("2026-10-26T09:30:00Z")),
+            (utc("2026-11-02T09:00:00Z"), utc("2026-11-02T09:30:00Z")),
+        ]
+    );
+
+    let free = calendar
+        .check_availability(
+            "nora@localhost",
+            utc("2026-10-26T08:00:00Z"),
+            utc("2026-10-26T09:00:00Z"),
+        )
+        .await
+        .expect("availability check failed");
+    assert!(free);
+    let free = calendar
+        .check_availability(
+            "nora@localhost",
+            utc("2026-10-26T09:15:00Z"),
+            utc("2026-10-26T10:00:00Z"),
+        )
+        .await
+        .expect("availability check failed");
+    assert!(!free);
+
+    client
+        .delete_event(&created.event_id)
+        .await
+        .expect("failed to delete event");
+    let busy = client
+        .busy_periods(from, to)
+        .await
+        .expect("busy lookup failed");
+    assert!(busy.is_empty());
+}
@@ -87,6 +87,8 @@ fn generate_types_content() -> String {
         services::services::config::EditorConfig::decl(),
         services::services::config::EditorType::decl(),
         services::services::config::GitHubConfig::decl(),
+        services::services::config::ForgeInstanceConfig::decl(),
+        server::routes::forges::ForgeInstanceSummary::decl(),
         services::services::config::AptosWalletConfig::decl(),
         services::services::config::SoundFile::decl(),
         services::services::config::UiLanguage::decl(),
@@ -123,6 +125,7 @@ fn generate_types_content() -> String {
         server::routes::task_attempts::CommitInfo::decl(),
         server::routes::task_attempts::BranchStatus::decl(),
         services::services::git::ConflictOp::decl(),
+        services::services::session_export::ExportFormat::decl(),
         db::models::task_attempt::TaskAttempt::decl(),
         db::models::execution_process::ExecutionProcess::decl(),
         db::models::execution_process::ExecutionProcessStatus::decl(),
@@ -149,6 +152,7 @@ fn generate_types_content() -> String {
         db::models::merge::PrMerge::decl(),
         db::models::merge::MergeStatus::decl(),
         db::models::merge::PullRequestInfo::decl(),
+        db::models::merge::ForgeType::decl(),
         db::models::follow_up_draft::FollowUpDraft::decl(),
         executors::logs::CommandExitStatus::decl(),
         executors::logs::CommandRunResult::decl(),
@@ -190,6 +194,10 @@ fn generate_types_content() -> String {
         db::models::airtable_record_link::CreateAirtableRecordLink::decl(),
         db::models::airtable_record_link::AirtableOrigin::decl(),
         db::models::airtable_record_link::AirtableSyncStatus::decl(),
+        db::models::airtable_table_mapping::AirtableTableMapping::decl(),
+        db::models::airtable_table_mapping::CreateAirtableTableMapping::decl(),
+        db::models::airtable_table_mapping::UpdateAirtableTableMapping::decl(),
+        db::models::airtable_table_mapping::AirtableFieldMapping::decl(),
         services::services::airtable_service::AirtableBaseInfo::decl(),
         services::services::airtable_service::AirtableTable::decl(),
         services::services::airtable_service::AirtableField::decl(),
@@ -200,6 +208,7 @@ fn generate_types_content() -> String {
         services::services::airtable_service::AirtableComment::decl(),
         services::services::airtable_service::AirtableCommentAuthor::decl(),
         services::services::airtable_service::AirtableServiceError::decl(),
+        services::services::airtable_sync::AirtableSyncReport::decl(),
         services::services::config::TrelloConfig::decl(),
         services::services::config::AirtableConfig::decl(),
         server::routes::airtable::AirtableVerifyRequest::decl(),
@@ -252,6 +261,36 @@ fn generate_types_content() -> String {
         server::routes::agent_chat::AgentChatRequest::decl(),
         server::routes::agent_chat::AgentChatResponse::decl(),
         server::routes::agent_chat::ConversationSummary::decl(),
+        // CMS publishing types
+        db::models::cms_site::CmsSite::decl(),
+        db::models::cms_product::CmsProduct::decl(),
+        db::models::cms_faq_item::CmsFaqItem::decl(),
+        db::models::cms_page_section::CmsPageSection::decl(),
+        db::models::cms_site_setting::CmsSiteSetting::decl(),
+        db::models::cms_entity::CmsEntityType::decl(),
+        db::models::cms_revision::CmsRevisionAction::decl(),
+        db::models::cms_revision::CmsRevision::decl(),
+        db::models::cms_revision::CmsFieldChange::decl(),
+        db::models::cms_published_entity::CmsPublishedContent::decl(),
+        db::models::cms_scheduled_publication::CmsScheduleStatus::decl(),
+        db::models::cms_scheduled_publication::CmsScheduledPublication::decl(),
+        db::models::cms_scheduled_publication::CreateCmsScheduledPublication::decl(),
+        db::models::cms_webhook::CmsWebhook::decl(),
+        db::models::cms_webhook::CreateCmsWebhook::decl(),
+        db::models::cms_webhook::UpdateCmsWebhook::decl(),
+        services::services::cms_publishing::CmsPublishState::decl(),
+        services::services::cms_publishing::CmsEntityStatus::decl(),
+        services::services::cms_publishing::CmsPublishChange::decl(),
+        services::services::cms_publishing::CmsPublishReport::decl(),
+        services::services::cms_publishing::CmsDiffTarget::decl(),
+        services::services::cms_publishing::CmsRevisionDiff::decl(),
+        services::services::cms_publishing::CmsRestoreResult::decl(),
+        services::services::cms_export::CmsExportResult::decl(),
+        server::routes::cms::publishing::CmsPublishRequest::decl(),
+        server::routes::cms::publishing::CmsUnpublishRequest::decl(),
+        server::routes::cms::revisions::CmsRevisionQuery::decl(),
+        server::routes::cms::revisions::CmsRevisionDiffQuery::decl(),
+        server::routes::cms::revisions::CmsRestoreRequest::decl(),
     ];
 
     let body = decls
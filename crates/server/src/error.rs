@@ -10,8 +10,12 @@ use db::models::{
     artifact_review::ArtifactReviewError,
     crm_contact::CrmContactError,
     email_account::EmailAccountError,
+    email_message::EmailMessageError,
+    email_sequence::EmailSequenceError,
     execution_artifact::ExecutionArtifactError,
     execution_process::ExecutionProcessError,
+    lead_scoring::LeadScoringError,
+    log_search::LogSearchError,
     project::ProjectError,
     social_account::SocialAccountError,
     social_mention::SocialMentionError,
@@ -20,13 +24,17 @@ use db::models::{
     task_attempt::TaskAttemptError,
     token_usage::TokenUsageError,
     wide_research::WideResearchError,
+    zoho_integration::ZohoIntegrationError,
 };
 use deployment::DeploymentError;
 use executors::executors::ExecutorError;
 use git2::Error as Git2Error;
 use services::services::{
-    auth::AuthError, config::ConfigError, container::ContainerError, git::GitServiceError,
-    github_service::GitHubServiceError, image::ImageError, worktree_manager::WorktreeError,
+    auth::AuthError, cms_export::CmsExportError, cms_publishing::CmsPublishingError,
+    config::ConfigError, container::ContainerError, email_sequence::EmailSequenceServiceError,
+    forge::ForgeError, git::GitServiceError, github_service::GitHubServiceError, image::ImageError,
+    lead_scoring::LeadScoringServiceError, log_archive::LogArchiveError,
+    session_export::SessionExportError, worktree_manager::WorktreeError, zoho::ZohoError,
 };
 use thiserror::Error;
 use utils::response::ApiResponse;
@@ -63,6 +71,8 @@ pub enum ApiError {
     #[error(transparent)]
     EmailAccount(#[from] EmailAccountError),
     #[error(transparent)]
+    EmailMessage(#[from] EmailMessageError),
+    #[error(transparent)]
     CrmContact(#[from] CrmContactError),
     #[error("Multipart error: {0}")]
     Multipart(#[from] MultipartError),
@@ -187,6 +197,153 @@ impl From<SocialMentionError> for ApiError {
     }
 }
 
+impl From<LogSearchError> for ApiError {
+    fn from(err: LogSearchError) -> Self {
+        match err {
+            LogSearchError::Database(e) => ApiError::Database(e),
+            LogSearchError::EmptyQuery => ApiError::BadRequest("Search query is empty".into()),
+        }
+    }
+}
+
+impl From<LogArchiveError> for ApiError {
+    fn from(err: LogArchiveError) -> Self {
+        match err {
+            LogArchiveError::Database(e) => ApiError::Database(e),
+            LogArchiveError::Io(e) => ApiError::Io(e),
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<ForgeError> for ApiError {
+    fn from(err: ForgeError) -> Self {
+        match err {
+            ForgeError::GitHub(e) => ApiError::GitHubService(e),
+            ForgeError::TokenInvalid(_) | ForgeError::NotConfigured(..) => {
+                ApiError::Unauthorized(err.to_string())
+            }
+            ForgeError::InsufficientPermissions => ApiError::Forbidden(err.to_string()),
+            ForgeError::RepoNotFoundOrNoAccess => ApiError::NotFound(err.to_string()),
+            ForgeError::UnknownRemote(_) => ApiError::BadRequest(err.to_string()),
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<LeadScoringError> for ApiError {
+    fn from(err: LeadScoringError) -> Self {
+        match err {
+            LeadScoringError::Database(e) => ApiError::Database(e),
+            LeadScoringError::NotFound => ApiError::NotFound(err.to_string()),
+            LeadScoringError::InvalidRules(_) => ApiError::BadRequest(err.to_string()),
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<LeadScoringServiceError> for ApiError {
+    fn from(err: LeadScoringServiceError) -> Self {
+        match err {
+            LeadScoringServiceError::Scoring(e) => e.into(),
+            LeadScoringServiceError::Contact(e) => ApiError::CrmContact(e),
+            LeadScoringServiceError::EmailMessage(e) => ApiError::EmailMessage(e),
+            LeadScoringServiceError::SocialMention(e) => e.into(),
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<EmailSequenceError> for ApiError {
+    fn from(err: EmailSequenceError) -> Self {
+        match err {
+            EmailSequenceError::Database(e) => ApiError::Database(e),
+            EmailSequenceError::NotFound | EmailSequenceError::EnrollmentNotFound => {
+                ApiError::NotFound(err.to_string())
+            }
+            EmailSequenceError::Invalid(_) => ApiError::BadRequest(err.to_string()),
+        }
+    }
+}
+
+impl From<EmailSequenceServiceError> for ApiError {
+    fn from(err: EmailSequenceServiceError) -> Self {
+        match err {
+            EmailSequenceServiceError::Database(e) => ApiError::Database(e),
+            EmailSequenceServiceError::Sequence(e) => e.into(),
+            EmailSequenceServiceError::Contact(e) => ApiError::CrmContact(e),
+            EmailSequenceServiceError::Account(e) => ApiError::EmailAccount(e),
+            EmailSequenceServiceError::Message(e) => ApiError::EmailMessage(e),
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<ZohoIntegrationError> for ApiError {
+    fn from(err: ZohoIntegrationError) -> Self {
+        match err {
+            ZohoIntegrationError::Database(e) => ApiError::Database(e),
+            ZohoIntegrationError::NotFound => ApiError::NotFound(err.to_string()),
+        }
+    }
+}
+
+impl From<ZohoError> for ApiError {
+    fn from(err: ZohoError) -> Self {
+        match err {
+            ZohoError::Database(e) => ApiError::Database(e),
+            ZohoError::Integration(e) => e.into(),
+            ZohoError::Contact(e) => ApiError::CrmContact(e),
+            ZohoError::NotConnected => ApiError::NotFound(err.to_string()),
+            ZohoError::Config(_) => ApiError::BadRequest(err.to_string()),
+            // Not our session: the project needs reconnecting to Zoho
+            ZohoError::Revoked(_) | ZohoError::Auth(_) => ApiError::BadRequest(err.to_string()),
+            ZohoError::RateLimited => ApiError::TooManyRequests(err.to_string()),
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<SessionExportError> for ApiError {
+    fn from(err: SessionExportError) -> Self {
+        match err {
+            SessionExportError::Database(e) => ApiError::Database(e),
+            SessionExportError::UnknownFormat(_) => ApiError::BadRequest(err.to_string()),
+            SessionExportError::TaskNotFound(_) | SessionExportError::ProjectNotFound(_) => {
+                ApiError::NotFound(err.to_string())
+            }
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<CmsPublishingError> for ApiError {
+    fn from(err: CmsPublishingError) -> Self {
+        match err {
+            CmsPublishingError::Database(e) => ApiError::Database(e),
+            CmsPublishingError::SiteNotFound
+            | CmsPublishingError::EntityNotFound
+            | CmsPublishingError::RevisionNotFound => ApiError::NotFound(err.to_string()),
+            CmsPublishingError::Conflict(_) => ApiError::Conflict(err.to_string()),
+            CmsPublishingError::InvalidSchedule(_) => ApiError::BadRequest(err.to_string()),
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
+impl From<CmsExportError> for ApiError {
+    fn from(err: CmsExportError) -> Self {
+        match err {
+            CmsExportError::Database(e) => ApiError::Database(e),
+            CmsExportError::Io(e) => ApiError::Io(e),
+            CmsExportError::NotPublished | CmsExportError::UnsafeSlug(_) => {
+                ApiError::BadRequest(err.to_string())
+            }
+            other => ApiError::InternalError(other.to_string()),
+        }
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status_code, error_type) = match &self {
@@ -236,6 +393,10 @@ impl IntoResponse for ApiError {
                 EmailAccountError::NotFound => (StatusCode::NOT_FOUND, "EmailAccountNotFound"),
                 _ => (StatusCode::INTERNAL_SERVER_ERROR, "EmailAccountError"),
             },
+            ApiError::EmailMessage(e) => match e {
+                EmailMessageError::NotFound => (StatusCode::NOT_FOUND, "EmailMessageNotFound"),
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, "EmailMessageError"),
+            },
             ApiError::CrmContact(e) => match e {
                 CrmContactError::NotFound => (StatusCode::NOT_FOUND, "CrmContactNotFound"),
                 _ => (StatusCode::INTERNAL_SERVER_ERROR, "CrmContactError"),
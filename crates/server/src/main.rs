@@ -74,6 +74,14 @@ async fn main() -> Result<(), VibeKanbanError> {
     deployment.cleanup_orphan_executions().await?;
     deployment.backfill_before_head_commits().await?;
     deployment.spawn_pr_monitor_service().await;
+    deployment.spawn_log_retention_service().await;
+    deployment.spawn_email_sync_service().await;
+    deployment.spawn_crm_enrichment_service().await;
+    deployment.spawn_lead_scoring_service().await;
+    deployment.spawn_email_sequence_service().await;
+    deployment.spawn_zoho_sync_service().await;
+    deployment.spawn_airtable_sync_service().await;
+    deployment.spawn_cms_publishing_service().await;
 
     // Sync projects from topos directory (if TOPOS_DIR is configured)
     deployment.sync_from_topos().await;
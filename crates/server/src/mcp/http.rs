@@ -0,0 +1,273 @@
+//! MCP over streamable HTTP
+//!
+//! Serves [`TaskServer`] and [`NoraServer`] from the main axum server so remote
+//! agents and IDEs can reach them. Clients authenticate with a per-user MCP
+//! token (`Authorization: Bearer pcg_mcp_...`). Each token gets its own server
+//! instance and session manager, built with [`TaskServer::new_for_user`] so the
+//! token carries exactly its owner's project permissions, and MCP sessions
+//! can't be picked up with another user's token. The task server gets the
+//! running deployment so its attempt, diff, approval and merge tools work.
+//!
+//! Every tool call is written to `mcp_audit_log` by [`AuditedServer`].
+
+use std::{sync::Arc, time::Instant};
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use db::models::mcp_access::{
+    CreateMcpAuditEntry, McpAccessToken, McpAuditEntry, McpScope, McpTokenAuth, McpTokenIdentity,
+};
+use deployment::Deployment;
+use once_cell::sync::Lazy;
+use rmcp::{
+    ErrorData, RoleServer, ServerHandler,
+    model::{
+        CallToolRequestParam, CallToolResult, ListToolsResult, PaginatedRequestParam, ServerInfo,
+    },
+    service::RequestContext,
+    transport::streamable_http_server::{
+        StreamableHttpServerConfig, StreamableHttpService, session::local::LocalSessionManager,
+    },
+};
+use sqlx::SqlitePool;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+use super::{nora_server::NoraServer, task_server::TaskServer};
+use crate::{DeploymentImpl, routes::nora::NoraManager};
+
+/// Wraps an MCP server and records each tool call against the token that made it
+#[derive(Clone)]
+pub struct AuditedServer<S> {
+    inner: S,
+    pool: SqlitePool,
+    server: McpScope,
+    token_id: Uuid,
+    user_id: Uuid,
+}
+
+impl<S> AuditedServer<S> {
+    pub fn new(inner: S, pool: SqlitePool, server: McpScope, identity: &McpTokenIdentity) -> Self {
+        Self {
+            inner,
+            pool,
+            server,
+            token_id: identity.token_id,
+            user_id: identity.user_id,
+        }
+    }
+}
+
+impl<S: ServerHandler> ServerHandler for AuditedServer<S> {
+    fn get_info(&self) -> ServerInfo {
+        self.inner.get_info()
+    }
+
+    async fn list_tools(
+        &self,
+        request: Option<PaginatedRequestParam>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        self.inner.list_tools(request, context).await
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let tool = request.name.to_string();
+        let arguments = request.arguments.clone().map(serde_json::Value::Object);
+        let started = Instant::now();
+
+        let result = self.inner.call_tool(request, context).await;
+
+        let (success, error) = match &result {
+            Ok(output) if output.is_error == Some(true) => {
+                (false, Some("Tool reported an error".to_string()))
+            }
+            Ok(_) => (true, None),
+            Err(e) => (false, Some(e.message.to_string())),
+        };
+        let entry = CreateMcpAuditEntry {
+            token_id: Some(self.token_id),
+            user_id: self.user_id,
+            server: self.server,
+            tool,
+            arguments,
+            success,
+            error,
+            duration_ms: started.elapsed().as_millis() as i64,
+        };
+        if let Err(e) = McpAuditEntry::record(&self.pool, &entry).await {
+            tracing::warn!(
+                "[MCP] Failed to write audit entry for {}: {}",
+                entry.tool,
+                e
+            );
+        }
+
+        result
+    }
+}
+
+type TaskHttpService = StreamableHttpService<AuditedServer<TaskServer>, LocalSessionManager>;
+type NoraHttpService = StreamableHttpService<AuditedServer<NoraServer>, LocalSessionManager>;
+
+/// Per-token HTTP services. The admin flag is cached alongside so a change to
+/// the user's role rebuilds the server with the new permissions.
+#[derive(Default)]
+struct McpHttpServices {
+    tasks: DashMap<Uuid, (bool, TaskHttpService)>,
+    nora: DashMap<Uuid, NoraHttpService>,
+}
+
+static SERVICES: Lazy<McpHttpServices> = Lazy::new(McpHttpServices::default);
+
+impl McpHttpServices {
+    fn tasks(
+        &self,
+        identity: &McpTokenIdentity,
+        pool: &SqlitePool,
+        deployment: &DeploymentImpl,
+    ) -> TaskHttpService {
+        match self.tasks.get(&identity.token_id) {
+            Some(entry) if entry.0 == identity.is_admin => return entry.1.clone(),
+            _ => {}
+        }
+
+        let (pool, identity_for_factory) = (pool.clone(), identity.clone());
+        let deployment = deployment.clone();
+        let service = StreamableHttpService::new(
+            move || {
+                let server = TaskServer::new_for_user(
+                    pool.clone(),
+                    identity_for_factory.user_id,
+                    identity_for_factory.is_admin,
+                )
+                .with_deployment(deployment.clone());
+                Ok(AuditedServer::new(
+                    server,
+                    pool.clone(),
+                    McpScope::Tasks,
+                    &identity_for_factory,
+                ))
+            },
+            Arc::new(LocalSessionManager::default()),
+            StreamableHttpServerConfig::default(),
+        );
+        self.tasks
+            .insert(identity.token_id, (identity.is_admin, service.clone()));
+        service
+    }
+
+    fn nora(
+        &self,
+        identity: &McpTokenIdentity,
+        pool: &SqlitePool,
+        manager: Arc<NoraManager>,
+    ) -> NoraHttpService {
+        if let Some(entry) = self.nora.get(&identity.token_id) {
+            return entry.clone();
+        }
+
+        let (pool, identity_for_factory) = (pool.clone(), identity.clone());
+        let service = StreamableHttpService::new(
+            move || {
+                let server = NoraServer::new(pool.clone(), manager.clone());
+                Ok(AuditedServer::new(
+                    server,
+                    pool.clone(),
+                    McpScope::Nora,
+                    &identity_for_factory,
+                ))
+            },
+            Arc::new(LocalSessionManager::default()),
+            StreamableHttpServerConfig::default(),
+        );
+        self.nora.insert(identity.token_id, service.clone());
+        service
+    }
+}
+
+/// Resolve the bearer token on an MCP request
+pub async fn authenticate(
+    pool: &SqlitePool,
+    headers: &HeaderMap,
+) -> Result<McpTokenIdentity, StatusCode> {
+    let token = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    match McpAccessToken::authenticate(pool, token.trim()).await {
+        Ok(McpTokenAuth::Valid(identity)) => Ok(identity),
+        Ok(McpTokenAuth::Rejected { token_id }) => {
+            // Expired or revoked elsewhere; don't keep its servers around
+            forget_token(token_id);
+            Err(StatusCode::UNAUTHORIZED)
+        }
+        Ok(McpTokenAuth::Unknown) => Err(StatusCode::UNAUTHORIZED),
+        Err(e) => {
+            tracing::error!("[MCP] Token lookup failed: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Serve one streamable HTTP request (POST, GET for the SSE stream, or
+/// DELETE to end a session) on the given MCP server
+pub async fn serve(deployment: DeploymentImpl, scope: McpScope, request: Request) -> Response {
+    let pool = deployment.db().pool.clone();
+    let identity = match authenticate(&pool, request.headers()).await {
+        Ok(identity) => identity,
+        Err(status) => return status.into_response(),
+    };
+    if !identity.allows(scope) {
+        return (
+            StatusCode::FORBIDDEN,
+            format!(
+                "Token is not allowed to use the {} MCP server",
+                scope.as_str()
+            ),
+        )
+            .into_response();
+    }
+
+    let response = match scope {
+        McpScope::Tasks => {
+            SERVICES
+                .tasks(&identity, &pool, &deployment)
+                .oneshot(request)
+                .await
+        }
+        McpScope::Nora => {
+            // Nora acts with organisation-wide context, so only admins get it
+            if !identity.is_admin {
+                return StatusCode::FORBIDDEN.into_response();
+            }
+            let manager = Arc::new(NoraManager::new().await);
+            SERVICES
+                .nora(&identity, &pool, manager)
+                .oneshot(request)
+                .await
+        }
+    };
+
+    match response {
+        Ok(response) => response.map(Body::new),
+        Err(never) => match never {},
+    }
+}
+
+/// Drop cached servers for a revoked or expired token so its sessions end
+pub fn forget_token(token_id: Uuid) {
+    SERVICES.tasks.remove(&token_id);
+    SERVICES.nora.remove(&token_id);
+}
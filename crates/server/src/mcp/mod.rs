@@ -1,3 +1,4 @@
+pub mod http;
 pub mod nora_server;
 pub mod nora_tools;
 pub mod task_server;
@@ -1,8 +1,20 @@
 use std::{future::Future, path::PathBuf};
 
+use axum::{
+    Extension, Json,
+    extract::{Path, State},
+};
+use chrono::{DateTime, Utc};
 use db::models::{
+    execution_process::{ExecutionProcess, ExecutionProcessRunReason, ExecutionProcessStatus},
     project::Project,
     task::{CreateTask, Task, TaskStatus},
+    task_attempt::TaskAttempt,
+};
+use deployment::Deployment;
+use executors::{
+    actions::ExecutorActionType, executors::BaseCodingAgent, logs::NormalizedEntry,
+    profile::ExecutorProfileId,
 };
 use rmcp::{
     ErrorData, ServerHandler,
@@ -14,10 +26,23 @@ use rmcp::{
 };
 use serde::{Deserialize, Serialize};
 use serde_json;
-use services::services::pcg_policy::{self, PolicyAction, PolicyCheckContext};
+use services::services::{
+    pcg_policy::{self, PolicyAction, PolicyCheckContext},
+    session_export,
+};
 use sqlx::{SqlitePool, types::Json as SqlxJson};
+use utils::approvals::{ApprovalPendingInfo, ApprovalResponse, ToolApprovalStatus};
 use uuid::Uuid;
 
+use crate::{
+    DeploymentImpl,
+    middleware::{AccessContext, ProjectRole},
+    routes::{
+        approvals,
+        task_attempts::{self, CreateFollowUpAttempt, CreateTaskAttemptBody},
+    },
+};
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct CreateTaskRequest {
     #[schemars(description = "The ID of the project to create the task in. This is required!")]
@@ -213,16 +238,175 @@ pub struct GetTaskResponse {
     pub project_name: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListTaskAttemptsRequest {
+    #[schemars(description = "The ID of the task whose attempts to list")]
+    pub task_id: String,
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct TaskAttemptSummary {
+    #[schemars(description = "The unique identifier of the attempt")]
+    pub id: String,
+    #[schemars(description = "The task this attempt belongs to")]
+    pub task_id: String,
+    #[schemars(description = "Coding agent running the attempt (e.g. CLAUDE_CODE)")]
+    pub executor: String,
+    #[schemars(description = "Git branch the attempt works on")]
+    pub branch: Option<String>,
+    #[schemars(description = "Branch the attempt was started from and merges into")]
+    pub base_branch: String,
+    #[schemars(description = "Whether the attempt's worktree has been cleaned up")]
+    pub worktree_deleted: bool,
+    #[schemars(description = "When the attempt was created")]
+    pub created_at: String,
+    #[schemars(description = "When the attempt was last updated")]
+    pub updated_at: String,
+}
+
+impl From<TaskAttempt> for TaskAttemptSummary {
+    fn from(attempt: TaskAttempt) -> Self {
+        Self {
+            id: attempt.id.to_string(),
+            task_id: attempt.task_id.to_string(),
+            executor: attempt.executor,
+            branch: attempt.branch,
+            base_branch: attempt.base_branch,
+            worktree_deleted: attempt.worktree_deleted,
+            created_at: attempt.created_at.to_rfc3339(),
+            updated_at: attempt.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, schemars::JsonSchema)]
+pub struct ListTaskAttemptsResponse {
+    pub success: bool,
+    pub task_id: String,
+    pub attempts: Vec<TaskAttemptSummary>,
+    pub count: usize,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct StartTaskAttemptRequest {
+    #[schemars(description = "The ID of the task to start an attempt for")]
+    pub task_id: String,
+    #[schemars(
+        description = "Coding agent to run: 'CLAUDE_CODE', 'CODEX', 'GEMINI', 'AMP', 'CURSOR', 'OPENCODE', 'QWEN_CODE' or 'DUCK'"
+    )]
+    pub executor: String,
+    #[schemars(description = "Optional executor profile variant (e.g. 'PLAN')")]
+    pub variant: Option<String>,
+    #[schemars(
+        description = "Branch to start from and merge back into (default: the project's current branch)"
+    )]
+    pub base_branch: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct TaskAttemptRequest {
+    #[schemars(description = "The ID of the task attempt")]
+    pub attempt_id: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetAttemptConversationRequest {
+    #[schemars(description = "The ID of the task attempt")]
+    pub attempt_id: String,
+    #[schemars(
+        description = "Only return entries of the latest run from this index on. Pass the previous `total_entries` to poll for new output."
+    )]
+    pub from_entry: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConversationRun {
+    pub execution_id: String,
+    pub status: ExecutionProcessStatus,
+    pub exit_code: Option<i64>,
+    pub prompt: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Number of entries in the run, regardless of `from_entry`
+    pub total_entries: usize,
+    pub entries: Vec<NormalizedEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AttemptConversationResponse {
+    pub success: bool,
+    pub attempt_id: String,
+    /// True while a coding agent process is still running for the attempt
+    pub running: bool,
+    pub runs: Vec<ConversationRun>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct GetAttemptDiffRequest {
+    #[schemars(description = "The ID of the task attempt")]
+    pub attempt_id: String,
+    #[schemars(
+        description = "Include full old/new file contents (default: false, only paths and line stats)"
+    )]
+    pub include_contents: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct SendFollowUpRequest {
+    #[schemars(description = "The ID of the task attempt to continue")]
+    pub attempt_id: String,
+    #[schemars(description = "Follow-up instructions for the coding agent")]
+    pub prompt: String,
+    #[schemars(description = "Optional executor profile variant override")]
+    pub variant: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ListPendingApprovalsRequest {
+    #[schemars(description = "Optional task attempt ID to filter approvals by")]
+    pub attempt_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PendingApprovalSummary {
+    pub attempt_id: String,
+    #[serde(flatten)]
+    pub approval: ApprovalPendingInfo,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct RespondToApprovalRequest {
+    #[schemars(description = "The approval ID from `list_pending_approvals`")]
+    pub approval_id: String,
+    #[schemars(description = "true to approve the tool call, false to deny it")]
+    pub approve: bool,
+    #[schemars(description = "Optional reason passed back to the agent when denying")]
+    pub reason: Option<String>,
+}
+
+#[derive(Clone)]
 pub struct TaskServer {
     pub pool: SqlitePool,
     /// User ID for scoping operations (None = admin/unscoped for backward compat)
     pub user_id: Option<Uuid>,
     /// Whether this user is admin (bypasses project membership checks)
     pub is_admin: bool,
+    /// Running deployment; attempt tools are only available when served from
+    /// the main server, not from the standalone stdio binary
+    deployment: Option<DeploymentImpl>,
     tool_router: ToolRouter<TaskServer>,
 }
 
+impl std::fmt::Debug for TaskServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskServer")
+            .field("user_id", &self.user_id)
+            .field("is_admin", &self.is_admin)
+            .field("has_deployment", &self.deployment.is_some())
+            .finish()
+    }
+}
+
 impl TaskServer {
     #[allow(dead_code)]
     pub fn new(pool: SqlitePool) -> Self {
@@ -230,6 +414,7 @@ impl TaskServer {
             pool,
             user_id: None,
             is_admin: true, // Default: backward-compatible admin access
+            deployment: None,
             tool_router: Self::tool_router(),
         }
     }
@@ -240,9 +425,122 @@ impl TaskServer {
             pool,
             user_id: Some(user_id),
             is_admin,
+            deployment: None,
             tool_router: Self::tool_router(),
         }
     }
+
+    /// Enable the attempt, diff, follow-up, approval and merge tools
+    pub fn with_deployment(mut self, deployment: DeploymentImpl) -> Self {
+        self.deployment = Some(deployment);
+        self
+    }
+
+    fn access_context(&self) -> AccessContext {
+        AccessContext {
+            user_id: self.user_id.unwrap_or_else(Uuid::nil),
+            is_admin: self.is_admin || self.user_id.is_none(),
+            is_active: true,
+        }
+    }
+
+    fn require_deployment(&self) -> Result<DeploymentImpl, CallToolResult> {
+        self.deployment.clone().ok_or_else(|| {
+            tool_error(
+                "Attempt tools are only available over the server's HTTP MCP endpoint (/api/mcp/tasks)",
+                None,
+            )
+        })
+    }
+
+    /// Check the caller's role on the project that owns `task_id`, the same
+    /// membership check the REST project routes apply
+    async fn authorize_task(
+        &self,
+        task_id: Uuid,
+        required_role: ProjectRole,
+    ) -> Result<Task, CallToolResult> {
+        let task = match Task::find_by_id(&self.pool, task_id).await {
+            Ok(Some(task)) => task,
+            Ok(None) => return Err(tool_error("Task not found", None)),
+            Err(e) => return Err(tool_error("Failed to retrieve task", Some(e.to_string()))),
+        };
+        self.access_context()
+            .check_project_access(&self.pool, &task.project_id.to_string(), required_role)
+            .await
+            .map_err(|e| tool_error("Access denied", Some(e.to_string())))?;
+        Ok(task)
+    }
+
+    async fn authorize_attempt(
+        &self,
+        attempt_id: &str,
+        required_role: ProjectRole,
+    ) -> Result<TaskAttempt, CallToolResult> {
+        let attempt_uuid = Uuid::parse_str(attempt_id)
+            .map_err(|_| tool_error("Invalid attempt ID format. Must be a valid UUID.", None))?;
+        let attempt = match TaskAttempt::find_by_id(&self.pool, attempt_uuid).await {
+            Ok(Some(attempt)) => attempt,
+            Ok(None) => return Err(tool_error("Task attempt not found", None)),
+            Err(e) => {
+                return Err(tool_error(
+                    "Failed to retrieve task attempt",
+                    Some(e.to_string()),
+                ));
+            }
+        };
+        self.authorize_task(attempt.task_id, required_role).await?;
+        Ok(attempt)
+    }
+
+    /// Check the caller's role on the attempt that ran `execution_process_id`
+    async fn authorize_execution(
+        &self,
+        execution_process_id: Uuid,
+        required_role: ProjectRole,
+    ) -> Result<TaskAttempt, CallToolResult> {
+        let process = match ExecutionProcess::find_by_id(&self.pool, execution_process_id).await {
+            Ok(Some(process)) => process,
+            Ok(None) => return Err(tool_error("Execution process not found", None)),
+            Err(e) => {
+                return Err(tool_error(
+                    "Failed to retrieve execution process",
+                    Some(e.to_string()),
+                ));
+            }
+        };
+        self.authorize_attempt(&process.task_attempt_id.to_string(), required_role)
+            .await
+    }
+}
+
+fn tool_error(error: &str, details: Option<String>) -> CallToolResult {
+    let error_response = serde_json::json!({
+        "success": false,
+        "error": error,
+        "details": details
+    });
+    CallToolResult::error(vec![Content::text(
+        serde_json::to_string_pretty(&error_response).unwrap_or_else(|_| error.to_string()),
+    )])
+}
+
+fn tool_success<T: Serialize>(response: &T) -> CallToolResult {
+    CallToolResult::success(vec![Content::text(
+        serde_json::to_string_pretty(response)
+            .unwrap_or_else(|_| "Failed to serialize response".to_string()),
+    )])
+}
+
+/// Unwrap a `Result<_, CallToolResult>` from the access helpers, returning the
+/// error result from the tool
+macro_rules! try_tool {
+    ($expr:expr) => {
+        match $expr {
+            Ok(value) => value,
+            Err(result) => return Ok(result),
+        }
+    };
 }
 
 #[tool_router]
@@ -977,6 +1275,430 @@ impl TaskServer {
             }
         }
     }
+
+    #[tool(
+        description = "List the execution attempts of a task, newest first. `task_id` is required!"
+    )]
+    async fn list_task_attempts(
+        &self,
+        Parameters(ListTaskAttemptsRequest { task_id }): Parameters<ListTaskAttemptsRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let task_uuid = match Uuid::parse_str(&task_id) {
+            Ok(uuid) => uuid,
+            Err(_) => {
+                return Ok(tool_error(
+                    "Invalid task ID format. Must be a valid UUID.",
+                    None,
+                ));
+            }
+        };
+        try_tool!(self.authorize_task(task_uuid, ProjectRole::Viewer).await);
+
+        match TaskAttempt::fetch_all(&self.pool, Some(task_uuid)).await {
+            Ok(attempts) => {
+                let attempts: Vec<TaskAttemptSummary> =
+                    attempts.into_iter().map(TaskAttemptSummary::from).collect();
+                let response = ListTaskAttemptsResponse {
+                    success: true,
+                    task_id,
+                    count: attempts.len(),
+                    attempts,
+                };
+                Ok(tool_success(&response))
+            }
+            Err(e) => Ok(tool_error(
+                "Failed to retrieve task attempts",
+                Some(e.to_string()),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Start a coding agent attempt on a task in a fresh worktree. `task_id` and `executor` are required! Poll `get_attempt_conversation` to follow its progress."
+    )]
+    async fn start_task_attempt(
+        &self,
+        Parameters(StartTaskAttemptRequest {
+            task_id,
+            executor,
+            variant,
+            base_branch,
+        }): Parameters<StartTaskAttemptRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let task_uuid = match Uuid::parse_str(&task_id) {
+            Ok(uuid) => uuid,
+            Err(_) => {
+                return Ok(tool_error(
+                    "Invalid task ID format. Must be a valid UUID.",
+                    None,
+                ));
+            }
+        };
+        let executor = match executor
+            .trim()
+            .to_uppercase()
+            .replace('-', "_")
+            .parse::<BaseCodingAgent>()
+        {
+            Ok(executor) => executor,
+            Err(_) => {
+                return Ok(tool_error(
+                    "Unknown executor",
+                    Some(format!("'{executor}' is not a supported coding agent")),
+                ));
+            }
+        };
+        let task = try_tool!(self.authorize_task(task_uuid, ProjectRole::Editor).await);
+        let deployment = try_tool!(self.require_deployment());
+
+        let base_branch = match base_branch {
+            Some(branch) if !branch.trim().is_empty() => branch,
+            _ => {
+                let project = match Project::find_by_id(&self.pool, task.project_id).await {
+                    Ok(Some(project)) => project,
+                    Ok(None) => return Ok(tool_error("Project not found", None)),
+                    Err(e) => {
+                        return Ok(tool_error(
+                            "Failed to retrieve project",
+                            Some(e.to_string()),
+                        ));
+                    }
+                };
+                match deployment.git().get_current_branch(&project.git_repo_path) {
+                    Ok(branch) => branch,
+                    Err(e) => {
+                        return Ok(tool_error(
+                            "Could not determine the project's current branch; pass `base_branch`",
+                            Some(e.to_string()),
+                        ));
+                    }
+                }
+            }
+        };
+
+        let payload = CreateTaskAttemptBody {
+            task_id: task.id,
+            executor_profile_id: ExecutorProfileId { executor, variant },
+            base_branch,
+        };
+        match task_attempts::create_task_attempt(State(deployment), Json(payload)).await {
+            Ok(Json(attempt)) => Ok(tool_success(&attempt)),
+            Err(e) => Ok(tool_error(
+                "Failed to start task attempt",
+                Some(e.to_string()),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Get the normalized agent conversation of a task attempt: messages, tool calls and their results, per coding agent run. `attempt_id` is required!"
+    )]
+    async fn get_attempt_conversation(
+        &self,
+        Parameters(GetAttemptConversationRequest {
+            attempt_id,
+            from_entry,
+        }): Parameters<GetAttemptConversationRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let attempt = try_tool!(
+            self.authorize_attempt(&attempt_id, ProjectRole::Viewer)
+                .await
+        );
+        let deployment = try_tool!(self.require_deployment());
+
+        let processes =
+            match ExecutionProcess::find_by_task_attempt_id(&self.pool, attempt.id, false).await {
+                Ok(processes) => processes
+                    .into_iter()
+                    .filter(|p| p.run_reason == ExecutionProcessRunReason::CodingAgent)
+                    .collect::<Vec<_>>(),
+                Err(e) => {
+                    return Ok(tool_error(
+                        "Failed to retrieve execution processes",
+                        Some(e.to_string()),
+                    ));
+                }
+            };
+        let running = processes
+            .iter()
+            .any(|p| p.status == ExecutionProcessStatus::Running);
+
+        // With `from_entry`, only the latest run is returned so agents can poll
+        let processes = match (from_entry, processes.len()) {
+            (Some(_), len) if len > 0 => processes.into_iter().skip(len - 1).collect(),
+            _ => processes,
+        };
+
+        let mut runs = Vec::with_capacity(processes.len());
+        for process in processes {
+            let prompt = match process.executor_action().map(|a| a.typ()) {
+                Ok(ExecutorActionType::CodingAgentInitialRequest(req)) => Some(req.prompt.clone()),
+                Ok(ExecutorActionType::CodingAgentFollowUpRequest(req)) => Some(req.prompt.clone()),
+                _ => None,
+            };
+            let entries =
+                session_export::collect_normalized_entries(deployment.container(), process.id)
+                    .await;
+            let total_entries = entries.len();
+            runs.push(ConversationRun {
+                execution_id: process.id.to_string(),
+                status: process.status,
+                exit_code: process.exit_code,
+                prompt,
+                started_at: process.started_at,
+                completed_at: process.completed_at,
+                total_entries,
+                entries: entries.into_iter().skip(from_entry.unwrap_or(0)).collect(),
+            });
+        }
+
+        Ok(tool_success(&AttemptConversationResponse {
+            success: true,
+            attempt_id,
+            running,
+            runs,
+        }))
+    }
+
+    #[tool(
+        description = "Get the file changes a task attempt made against its base branch. `attempt_id` is required!"
+    )]
+    async fn get_attempt_diff(
+        &self,
+        Parameters(GetAttemptDiffRequest {
+            attempt_id,
+            include_contents,
+        }): Parameters<GetAttemptDiffRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let attempt = try_tool!(
+            self.authorize_attempt(&attempt_id, ProjectRole::Viewer)
+                .await
+        );
+        let deployment = try_tool!(self.require_deployment());
+
+        let project = match attempt.parent_task(&self.pool).await {
+            Ok(Some(task)) => task.parent_project(&self.pool).await,
+            Ok(None) => Ok(None),
+            Err(e) => Err(e),
+        };
+        let project = match project {
+            Ok(Some(project)) => project,
+            Ok(None) => return Ok(tool_error("Task or project not found", None)),
+            Err(e) => {
+                return Ok(tool_error(
+                    "Failed to retrieve task or project",
+                    Some(e.to_string()),
+                ));
+            }
+        };
+
+        match session_export::collect_diffs(
+            deployment.container(),
+            &project.git_repo_path,
+            &attempt,
+        )
+        .await
+        {
+            Ok(mut diffs) => {
+                if !include_contents.unwrap_or(false) {
+                    for diff in &mut diffs {
+                        diff.old_content = None;
+                        diff.new_content = None;
+                    }
+                }
+                let response = serde_json::json!({
+                    "success": true,
+                    "attempt_id": attempt_id,
+                    "branch": attempt.branch,
+                    "base_branch": attempt.base_branch,
+                    "count": diffs.len(),
+                    "diffs": diffs,
+                });
+                Ok(tool_success(&response))
+            }
+            Err(e) => Ok(tool_error("Failed to compute diff", Some(e.to_string()))),
+        }
+    }
+
+    #[tool(
+        description = "Send follow-up instructions to the coding agent of a task attempt, continuing its session. `attempt_id` and `prompt` are required!"
+    )]
+    async fn send_follow_up(
+        &self,
+        Parameters(SendFollowUpRequest {
+            attempt_id,
+            prompt,
+            variant,
+        }): Parameters<SendFollowUpRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let attempt = try_tool!(
+            self.authorize_attempt(&attempt_id, ProjectRole::Editor)
+                .await
+        );
+        let deployment = try_tool!(self.require_deployment());
+        if prompt.trim().is_empty() {
+            return Ok(tool_error("Follow-up prompt must not be empty", None));
+        }
+
+        let payload = CreateFollowUpAttempt {
+            prompt,
+            variant,
+            image_ids: None,
+        };
+        match task_attempts::follow_up(Extension(attempt), State(deployment), Json(payload)).await {
+            Ok(Json(process)) => Ok(tool_success(&process)),
+            Err(e) => Ok(tool_error("Failed to send follow-up", Some(e.to_string()))),
+        }
+    }
+
+    #[tool(description = "Stop all running processes of a task attempt. `attempt_id` is required!")]
+    async fn stop_task_attempt(
+        &self,
+        Parameters(TaskAttemptRequest { attempt_id }): Parameters<TaskAttemptRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let attempt = try_tool!(
+            self.authorize_attempt(&attempt_id, ProjectRole::Editor)
+                .await
+        );
+        let deployment = try_tool!(self.require_deployment());
+
+        match task_attempts::stop_task_attempt_execution(Extension(attempt), State(deployment))
+            .await
+        {
+            Ok(Json(response)) => Ok(tool_success(&response)),
+            Err(e) => Ok(tool_error(
+                "Failed to stop task attempt",
+                Some(e.to_string()),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "List tool calls waiting for approval from the agents you can access, optionally for one `attempt_id`"
+    )]
+    async fn list_pending_approvals(
+        &self,
+        Parameters(ListPendingApprovalsRequest { attempt_id }): Parameters<
+            ListPendingApprovalsRequest,
+        >,
+    ) -> Result<CallToolResult, ErrorData> {
+        let deployment = try_tool!(self.require_deployment());
+        let attempt_filter = match attempt_id.as_deref().map(Uuid::parse_str) {
+            Some(Ok(uuid)) => Some(uuid),
+            Some(Err(_)) => {
+                return Ok(tool_error(
+                    "Invalid attempt ID format. Must be a valid UUID.",
+                    None,
+                ));
+            }
+            None => None,
+        };
+
+        let mut approvals = Vec::new();
+        for approval in deployment.approvals().pending().await {
+            // Skip approvals on projects the caller cannot see
+            let Ok(attempt) = self
+                .authorize_execution(approval.execution_process_id, ProjectRole::Viewer)
+                .await
+            else {
+                continue;
+            };
+            if attempt_filter.is_some_and(|id| id != attempt.id) {
+                continue;
+            }
+            approvals.push(PendingApprovalSummary {
+                attempt_id: attempt.id.to_string(),
+                approval,
+            });
+        }
+
+        let response = serde_json::json!({
+            "success": true,
+            "count": approvals.len(),
+            "approvals": approvals,
+        });
+        Ok(tool_success(&response))
+    }
+
+    #[tool(
+        description = "Approve or deny a pending tool call of a coding agent. `approval_id` and `approve` are required!"
+    )]
+    async fn respond_to_approval(
+        &self,
+        Parameters(RespondToApprovalRequest {
+            approval_id,
+            approve,
+            reason,
+        }): Parameters<RespondToApprovalRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let deployment = try_tool!(self.require_deployment());
+        let Some(pending) = deployment
+            .approvals()
+            .pending()
+            .await
+            .into_iter()
+            .find(|p| p.approval_id == approval_id)
+        else {
+            return Ok(tool_error(
+                "Approval not found or already answered",
+                Some(approval_id),
+            ));
+        };
+
+        try_tool!(
+            self.authorize_execution(pending.execution_process_id, ProjectRole::Editor)
+                .await
+        );
+
+        let status = if approve {
+            ToolApprovalStatus::Approved
+        } else {
+            ToolApprovalStatus::Denied { reason }
+        };
+        let request = ApprovalResponse {
+            execution_process_id: pending.execution_process_id,
+            status,
+        };
+        match approvals::respond_to_approval(
+            State(deployment),
+            Path(approval_id.clone()),
+            Json(request),
+        )
+        .await
+        {
+            Ok(Json(status)) => Ok(tool_success(&serde_json::json!({
+                "success": true,
+                "approval_id": approval_id,
+                "status": status,
+            }))),
+            Err(code) => Ok(tool_error(
+                "Failed to respond to approval",
+                Some(code.to_string()),
+            )),
+        }
+    }
+
+    #[tool(
+        description = "Merge a task attempt's branch into its base branch and mark the task done. `attempt_id` is required!"
+    )]
+    async fn merge_task_attempt(
+        &self,
+        Parameters(TaskAttemptRequest { attempt_id }): Parameters<TaskAttemptRequest>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let attempt = try_tool!(
+            self.authorize_attempt(&attempt_id, ProjectRole::Editor)
+                .await
+        );
+        let deployment = try_tool!(self.require_deployment());
+
+        match task_attempts::merge_task_attempt(Extension(attempt), State(deployment)).await {
+            Ok(Json(response)) => Ok(tool_success(&response)),
+            Err(e) => Ok(tool_error(
+                "Failed to merge task attempt",
+                Some(e.to_string()),
+            )),
+        }
+    }
 }
 
 #[tool_handler]
@@ -991,7 +1713,483 @@ impl ServerHandler for TaskServer {
                 name: "pcg-dashboard-mcp".to_string(),
                 version: "1.0.0".to_string(),
             },
-            instructions: Some("PCG Dashboard MCP exposes task governance tools. Use these tools to inspect or manage tasks and always pass the `project_id` you are working with. Call `list_tasks` to discover task IDs. TOOLS: 'list_projects', 'list_tasks', 'create_task', 'get_task', 'update_task', 'delete_task', 'evaluate_policy'. Provide `project_id`/`task_id` as required.".to_string()),
+            instructions: Some("PCG Dashboard MCP exposes task governance tools. Use these tools to inspect or manage tasks and always pass the `project_id` you are working with. Call `list_tasks` to discover task IDs. TOOLS: 'list_projects', 'list_tasks', 'create_task', 'get_task', 'update_task', 'delete_task', 'evaluate_policy', 'list_task_attempts', 'start_task_attempt', 'get_attempt_conversation', 'get_attempt_diff', 'send_follow_up', 'stop_task_attempt', 'list_pending_approvals', 'respond_to_approval', 'merge_task_attempt'. Provide `project_id`/`task_id`/`attempt_id` as required.".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::{Path, PathBuf},
+        str::FromStr,
+        sync::{Arc, OnceLock},
+        time::Duration,
+    };
+
+    use db::models::{
+        execution_process::CreateExecutionProcess,
+        executor_session::{CreateExecutorSession, ExecutorSession},
+        project::CreateProject,
+        task_attempt::CreateTaskAttempt,
+    };
+    use executors::{
+        actions::{ExecutorAction, coding_agent_initial::CodingAgentInitialRequest},
+        logs::{ActionType, NormalizedEntryType, ToolStatus, utils::patch::ConversationPatch},
+        profile::ExecutorConfigs,
+    };
+    use services::services::container::ContainerService;
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use tokio::sync::Mutex;
+    use utils::{
+        approvals::{ApprovalRequest, CreateApprovalRequest},
+        msg_store::MsgStore,
+    };
+
+    use super::*;
+
+    /// The deployment tests share one asset dir, and with it one database
+    static DEPLOYMENT_LOCK: Mutex<()> = Mutex::const_new(());
+
+    async fn setup_pool() -> SqlitePool {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .expect("invalid sqlite config")
+            .foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .expect("failed to open sqlite memory db");
+        sqlx::migrate!("../db/migrations")
+            .run(&pool)
+            .await
+            .expect("failed to run migrations");
+        pool
+    }
+
+    async fn test_deployment() -> DeploymentImpl {
+        static ASSET_DIR: OnceLock<PathBuf> = OnceLock::new();
+        ASSET_DIR.get_or_init(|| {
+            let dir = std::env::temp_dir().join(format!("pcg-task-server-{}", Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).expect("failed to create asset dir");
+            // Stand in for Claude Code: drain the prompt, print the arguments and exit
+            std::fs::write(
+                dir.join("profiles.json"),
+                r#"{"executors":{"CLAUDE_CODE":{"DEFAULT":{"CLAUDE_CODE":{"base_command_override":"cat > /dev/null; echo"}}}}}"#,
+            )
+            .expect("failed to write profiles.json");
+            // SAFETY: set once, before any deployment reads them
+            unsafe {
+                std::env::set_var("PCG_ASSET_DIR", &dir);
+                std::env::set_var("DISABLE_WORKTREE_ORPHAN_CLEANUP", "1");
+            }
+            ExecutorConfigs::reload();
+            dir
+        });
+        let deployment = DeploymentImpl::new()
+            .await
+            .expect("failed to start deployment");
+        deployment.config().write().await.analytics_enabled = Some(false);
+        deployment
+    }
+
+    fn init_repo(deployment: &DeploymentImpl, root: &Path) -> PathBuf {
+        let path = root.join("repo");
+        let git = deployment.git();
+        git.initialize_repo_with_main_branch(&path).unwrap();
+        git.configure_user(&path, "Test User", "test@example.com")
+            .unwrap();
+        git.checkout_branch(&path, "main").unwrap();
+        path
+    }
+
+    async fn create_attempt(pool: &SqlitePool, repo_path: &Path) -> TaskAttempt {
+        let project = Project::create(
+            pool,
+            &CreateProject {
+                name: "MCP project".to_string(),
+                git_repo_path: repo_path.to_string_lossy().to_string(),
+                use_existing_repo: true,
+                setup_script: None,
+                dev_script: None,
+                cleanup_script: None,
+                copy_files: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+        let task = Task::create(
+            pool,
+            &CreateTask {
+                project_id: project.id,
+                pod_id: None,
+                board_id: None,
+                title: "Add a readme".to_string(),
+                description: None,
+                parent_task_attempt: None,
+                image_ids: None,
+                priority: None,
+                assignee_id: None,
+                assigned_agent: None,
+                agent_id: None,
+                assigned_mcps: None,
+                created_by: "test".to_string(),
+                requires_approval: None,
+                parent_task_id: None,
+                tags: None,
+                due_date: None,
+                custom_properties: None,
+                scheduled_start: None,
+                scheduled_end: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+        TaskAttempt::create(
+            pool,
+            &CreateTaskAttempt {
+                executor: BaseCodingAgent::ClaudeCode,
+                base_branch: "main".to_string(),
+            },
+            task.id,
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Record a finished Claude Code run with an external session id, as the
+    /// executor would after its first turn
+    async fn create_coding_agent_run(
+        pool: &SqlitePool,
+        attempt: &TaskAttempt,
+        session_id: &str,
+    ) -> ExecutionProcess {
+        let action = ExecutorAction::new(
+            ExecutorActionType::CodingAgentInitialRequest(CodingAgentInitialRequest {
+                prompt: "Add a readme".to_string(),
+                executor_profile_id: ExecutorProfileId {
+                    executor: BaseCodingAgent::ClaudeCode,
+                    variant: None,
+                },
+            }),
+            None,
+        );
+        let process = ExecutionProcess::create(
+            pool,
+            &CreateExecutionProcess {
+                task_attempt_id: attempt.id,
+                executor_action: action,
+                run_reason: ExecutionProcessRunReason::CodingAgent,
+            },
+            Uuid::new_v4(),
+            None,
+        )
+        .await
+        .unwrap();
+        ExecutionProcess::update_completion(
+            pool,
+            process.id,
+            ExecutionProcessStatus::Completed,
+            Some(0),
+        )
+        .await
+        .unwrap();
+        ExecutorSession::create(
+            pool,
+            &CreateExecutorSession {
+                task_attempt_id: attempt.id,
+                execution_process_id: process.id,
+                prompt: Some("Add a readme".to_string()),
+            },
+            Uuid::new_v4(),
+        )
+        .await
+        .unwrap();
+        ExecutorSession::update_session_id(pool, process.id, session_id)
+            .await
+            .unwrap();
+        process
+    }
+
+    fn tool_json(result: CallToolResult) -> (bool, serde_json::Value) {
+        let text = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text().map(|t| t.text.clone()))
+            .collect::<String>();
+        let body = serde_json::from_str(&text).expect("tool output is not JSON");
+        (result.is_error == Some(true), body)
+    }
+
+    fn assert_tool_error(result: CallToolResult, error: &str) {
+        let (is_error, body) = tool_json(result);
+        assert!(is_error, "expected an error, got {body}");
+        assert!(
+            body["error"].as_str().is_some_and(|e| e.contains(error)),
+            "expected {error:?}, got {body}"
+        );
+    }
+
+    fn attempt_request(attempt: &TaskAttempt) -> Parameters<TaskAttemptRequest> {
+        Parameters(TaskAttemptRequest {
+            attempt_id: attempt.id.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn attempt_tools_check_access_before_anything_else() {
+        let pool = setup_pool().await;
+        let attempt = create_attempt(&pool, Path::new("/tmp/pcg-mcp-outsider")).await;
+        let outsider = TaskServer::new_for_user(pool.clone(), Uuid::new_v4(), false);
+
+        let result = outsider
+            .merge_task_attempt(attempt_request(&attempt))
+            .await
+            .unwrap();
+        assert_tool_error(result, "Access denied");
+        let result = outsider
+            .stop_task_attempt(attempt_request(&attempt))
+            .await
+            .unwrap();
+        assert_tool_error(result, "Access denied");
+        let result = outsider
+            .send_follow_up(Parameters(SendFollowUpRequest {
+                attempt_id: attempt.id.to_string(),
+                prompt: "Also add a licence".to_string(),
+                variant: None,
+            }))
+            .await
+            .unwrap();
+        assert_tool_error(result, "Access denied");
+        let result = outsider
+            .start_task_attempt(Parameters(StartTaskAttemptRequest {
+                task_id: attempt.task_id.to_string(),
+                executor: "claude-code".to_string(),
+                variant: None,
+                base_branch: None,
+            }))
+            .await
+            .unwrap();
+        assert_tool_error(result, "Access denied");
+        let result = outsider
+            .get_attempt_conversation(Parameters(GetAttemptConversationRequest {
+                attempt_id: attempt.id.to_string(),
+                from_entry: None,
+            }))
+            .await
+            .unwrap();
+        assert_tool_error(result, "Access denied");
+        let result = outsider
+            .get_attempt_diff(Parameters(GetAttemptDiffRequest {
+                attempt_id: attempt.id.to_string(),
+                include_contents: None,
+            }))
+            .await
+            .unwrap();
+        assert_tool_error(result, "Access denied");
+
+        // Callers who can see the attempt learn the stdio server can't act on it
+        let admin = TaskServer::new_for_user(pool, Uuid::new_v4(), true);
+        let result = admin
+            .merge_task_attempt(attempt_request(&attempt))
+            .await
+            .unwrap();
+        assert_tool_error(result, "only available over the server's HTTP MCP endpoint");
+    }
+
+    #[tokio::test]
+    async fn authorize_execution_checks_the_owning_project() {
+        let pool = setup_pool().await;
+        let attempt = create_attempt(&pool, Path::new("/tmp/pcg-mcp-execution")).await;
+        let process = create_coding_agent_run(&pool, &attempt, "session-1").await;
+
+        let outsider = TaskServer::new_for_user(pool.clone(), Uuid::new_v4(), false);
+        let denied = outsider
+            .authorize_execution(process.id, ProjectRole::Editor)
+            .await
+            .unwrap_err();
+        assert_tool_error(denied, "Access denied");
+
+        let admin = TaskServer::new_for_user(pool, Uuid::new_v4(), true);
+        let authorized = admin
+            .authorize_execution(process.id, ProjectRole::Editor)
+            .await
+            .unwrap();
+        assert_eq!(authorized.id, attempt.id);
+        let missing = admin
+            .authorize_execution(Uuid::new_v4(), ProjectRole::Editor)
+            .await
+            .unwrap_err();
+        assert_tool_error(missing, "Execution process not found");
+    }
+
+    #[tokio::test]
+    async fn merge_task_attempt_merges_the_branch_and_completes_the_task() {
+        let _serial = DEPLOYMENT_LOCK.lock().await;
+        let deployment = test_deployment().await;
+        let pool = deployment.db().pool.clone();
+        let root = tempfile::tempdir().unwrap();
+        let repo_path = init_repo(&deployment, root.path());
+        let attempt = create_attempt(&pool, &repo_path).await;
+        let worktree = deployment.container().create(&attempt).await.unwrap();
+        std::fs::write(Path::new(&worktree).join("README.md"), "hello\n").unwrap();
+        deployment
+            .git()
+            .commit(Path::new(&worktree), "Add a readme")
+            .unwrap();
+        let attempt = TaskAttempt::find_by_id(&pool, attempt.id)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let server = TaskServer::new_for_user(pool.clone(), Uuid::new_v4(), true)
+            .with_deployment(deployment.clone());
+        let (is_error, body) = tool_json(
+            server
+                .merge_task_attempt(attempt_request(&attempt))
+                .await
+                .unwrap(),
+        );
+        assert!(!is_error, "merge failed: {body}");
+
+        let task = Task::find_by_id(&pool, attempt.task_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.status, TaskStatus::Done);
+        assert_eq!(
+            std::fs::read_to_string(repo_path.join("README.md")).unwrap(),
+            "hello\n"
+        );
+        deployment.container().delete(&attempt).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn respond_to_approval_answers_only_for_project_editors() {
+        let _serial = DEPLOYMENT_LOCK.lock().await;
+        let deployment = test_deployment().await;
+        let pool = deployment.db().pool.clone();
+        let attempt = create_attempt(&pool, Path::new("/tmp/pcg-mcp-approval")).await;
+        let session_id = format!("session-{}", Uuid::new_v4());
+        let process = create_coding_agent_run(&pool, &attempt, &session_id).await;
+
+        // The agent's pending tool call, as its log normaliser would have pushed it
+        let store = Arc::new(MsgStore::new());
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            0,
+            NormalizedEntry {
+                timestamp: None,
+                entry_type: NormalizedEntryType::ToolUse {
+                    tool_name: "Bash".to_string(),
+                    action_type: ActionType::CommandRun {
+                        command: "rm -rf build".to_string(),
+                        result: None,
+                    },
+                    status: ToolStatus::Created,
+                },
+                content: "rm -rf build".to_string(),
+                metadata: None,
+            },
+        ));
+        deployment
+            .msg_stores()
+            .write()
+            .await
+            .insert(process.id, store);
+        let approval = deployment
+            .approvals()
+            .create(ApprovalRequest::from_create(CreateApprovalRequest {
+                tool_name: "Bash".to_string(),
+                tool_input: serde_json::json!({ "command": "rm -rf build" }),
+                session_id,
+            }))
+            .await
+            .unwrap();
+        let request = || {
+            Parameters(RespondToApprovalRequest {
+                approval_id: approval.id.clone(),
+                approve: true,
+                reason: None,
+            })
+        };
+
+        let outsider = TaskServer::new_for_user(pool.clone(), Uuid::new_v4(), false)
+            .with_deployment(deployment.clone());
+        assert_tool_error(
+            outsider.respond_to_approval(request()).await.unwrap(),
+            "Access denied",
+        );
+        assert!(matches!(
+            deployment.approvals().status(&approval.id).await,
+            Some(ToolApprovalStatus::Pending)
+        ));
+
+        let admin = TaskServer::new_for_user(pool, Uuid::new_v4(), true)
+            .with_deployment(deployment.clone());
+        let (is_error, body) = tool_json(admin.respond_to_approval(request()).await.unwrap());
+        assert!(!is_error, "approval failed: {body}");
+        assert_eq!(body["status"]["status"], "approved");
+        assert!(matches!(
+            deployment.approvals().status(&approval.id).await,
+            Some(ToolApprovalStatus::Approved)
+        ));
+    }
+
+    #[tokio::test]
+    async fn send_follow_up_resumes_the_agent_session() {
+        let _serial = DEPLOYMENT_LOCK.lock().await;
+        let deployment = test_deployment().await;
+        let pool = deployment.db().pool.clone();
+        let root = tempfile::tempdir().unwrap();
+        let repo_path = init_repo(&deployment, root.path());
+        let attempt = create_attempt(&pool, &repo_path).await;
+        deployment.container().create(&attempt).await.unwrap();
+        let attempt = TaskAttempt::find_by_id(&pool, attempt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        create_coding_agent_run(&pool, &attempt, "session-1").await;
+
+        let server = TaskServer::new_for_user(pool.clone(), Uuid::new_v4(), true)
+            .with_deployment(deployment.clone());
+        let (is_error, body) = tool_json(
+            server
+                .send_follow_up(Parameters(SendFollowUpRequest {
+                    attempt_id: attempt.id.to_string(),
+                    prompt: "Also add a licence".to_string(),
+                    variant: None,
+                }))
+                .await
+                .unwrap(),
+        );
+        assert!(!is_error, "follow-up failed: {body}");
+
+        let process_id = Uuid::parse_str(body["data"]["id"].as_str().unwrap()).unwrap();
+        let process = ExecutionProcess::find_by_id(&pool, process_id)
+            .await
+            .unwrap()
+            .unwrap();
+        match process.executor_action().unwrap().typ() {
+            ExecutorActionType::CodingAgentFollowUpRequest(request) => {
+                assert_eq!(request.session_id, "session-1");
+                assert_eq!(request.prompt, "Also add a licence");
+            }
+            other => panic!("expected a follow-up request, got {other:?}"),
+        }
+
+        // Let the stand-in agent exit before removing its worktree
+        for _ in 0..100 {
+            let process = ExecutionProcess::find_by_id(&pool, process_id)
+                .await
+                .unwrap()
+                .unwrap();
+            if process.status != ExecutionProcessStatus::Running {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
         }
+        deployment.container().delete(&attempt).await.unwrap();
     }
 }
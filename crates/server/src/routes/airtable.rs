@@ -2,7 +2,7 @@ use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
-    routing::{get, post},
+    routing::{get, patch, post},
     Router,
 };
 use db::models::{
@@ -10,6 +10,11 @@ use db::models::{
     airtable_record_link::{
         AirtableOrigin, AirtableRecordLink, AirtableSyncStatus, CreateAirtableRecordLink,
     },
+    airtable_table_mapping::{
+        AirtableFieldMapping, AirtableTableMapping, AirtableTaskField,
+        CreateAirtableTableMapping, UpdateAirtableTableMapping,
+    },
+    custom_field_definition::CustomFieldDefinition,
     task::{CreateTask, Priority, Task},
 };
 use deployment::Deployment;
@@ -19,6 +24,7 @@ use services::services::{
         build_record_url, get_record_name, AirtableBaseInfo, AirtableRecord, AirtableService,
         AirtableTable,
     },
+    airtable_sync::{AirtableSyncReport, AirtableSyncService},
     config::save_config_to_file,
 };
 use tracing::{error, info};
@@ -51,6 +57,16 @@ pub fn router() -> Router<DeploymentImpl> {
             "/airtable/connections/{id}/import",
             post(import_records_from_table),
         )
+        // Scheduled two-way sync, configured per table
+        .route(
+            "/airtable/connections/{id}/mappings",
+            get(list_table_mappings).post(create_table_mapping),
+        )
+        .route(
+            "/airtable/mappings/{id}",
+            patch(update_table_mapping).delete(delete_table_mapping),
+        )
+        .route("/airtable/mappings/{id}/sync", post(sync_table_mapping))
         // Task-level operations
         .route("/airtable/tasks/{task_id}/link", get(get_task_link))
         .route("/airtable/tasks/{task_id}/push", post(push_task_to_airtable))
@@ -621,6 +637,196 @@ async fn import_records_from_table(
     })))
 }
 
+/// Check that every mapping targets a task property or one of the
+/// project's custom fields
+async fn validate_field_mappings(
+    pool: &sqlx::SqlitePool,
+    project_id: Uuid,
+    mappings: &[AirtableFieldMapping],
+) -> Result<Option<String>, sqlx::Error> {
+    let mut custom_fields = None;
+    for mapping in mappings {
+        match mapping.target() {
+            None => {
+                return Ok(Some(format!(
+                    "Unknown task field '{}' for Airtable field '{}'",
+                    mapping.task_field, mapping.airtable_field
+                )));
+            }
+            Some(AirtableTaskField::Custom(name)) => {
+                if custom_fields.is_none() {
+                    custom_fields =
+                        Some(CustomFieldDefinition::list_by_project(pool, project_id).await?);
+                }
+                if !custom_fields
+                    .iter()
+                    .flatten()
+                    .any(|definition| definition.name == name)
+                {
+                    return Ok(Some(format!(
+                        "Project has no custom field named '{}'",
+                        name
+                    )));
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(None)
+}
+
+/// List the table mappings of a base connection
+async fn list_table_mappings(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<AirtableTableMapping>>>, StatusCode> {
+    let pool = &deployment.db().pool;
+
+    match AirtableTableMapping::find_by_connection_id(pool, id).await {
+        Ok(mappings) => Ok(Json(ApiResponse::success(mappings))),
+        Err(e) => {
+            error!("Failed to list Airtable table mappings: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Start syncing a table of a connected base with the project's tasks
+async fn create_table_mapping(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<CreateAirtableTableMapping>,
+) -> Result<Json<ApiResponse<AirtableTableMapping>>, StatusCode> {
+    let pool = &deployment.db().pool;
+
+    let connection = match AirtableBase::find_by_id(pool, id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return Ok(Json(ApiResponse::error("Connection not found"))),
+        Err(e) => {
+            error!("Failed to get connection: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    match validate_field_mappings(pool, connection.project_id, &payload.field_mappings).await {
+        Ok(None) => {}
+        Ok(Some(message)) => return Ok(Json(ApiResponse::error(&message))),
+        Err(e) => {
+            error!("Failed to load custom fields: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    match AirtableTableMapping::create(pool, connection.id, payload).await {
+        Ok(mapping) => {
+            info!(
+                "Mapped Airtable table {} for connection {}",
+                mapping.airtable_table_id, connection.id
+            );
+            Ok(Json(ApiResponse::success(mapping)))
+        }
+        Err(e) => {
+            error!("Failed to create Airtable table mapping: {}", e);
+            Ok(Json(ApiResponse::error(&format!(
+                "Failed to create table mapping: {}",
+                e
+            ))))
+        }
+    }
+}
+
+/// Update how a table is synced
+async fn update_table_mapping(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateAirtableTableMapping>,
+) -> Result<Json<ApiResponse<AirtableTableMapping>>, StatusCode> {
+    let pool = &deployment.db().pool;
+
+    if let Some(field_mappings) = &payload.field_mappings {
+        let connection = match AirtableTableMapping::find_by_id(pool, id).await {
+            Ok(Some(mapping)) => AirtableBase::find_by_id(pool, mapping.connection_id).await,
+            Ok(None) => return Ok(Json(ApiResponse::error("Table mapping not found"))),
+            Err(e) => Err(e),
+        };
+        let project_id = match connection {
+            Ok(Some(c)) => c.project_id,
+            Ok(None) => return Ok(Json(ApiResponse::error("Connection not found"))),
+            Err(e) => {
+                error!("Failed to get connection: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+        match validate_field_mappings(pool, project_id, field_mappings).await {
+            Ok(None) => {}
+            Ok(Some(message)) => return Ok(Json(ApiResponse::error(&message))),
+            Err(e) => {
+                error!("Failed to load custom fields: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+
+    match AirtableTableMapping::update(pool, id, payload).await {
+        Ok(mapping) => {
+            info!("Updated Airtable table mapping {}", id);
+            Ok(Json(ApiResponse::success(mapping)))
+        }
+        Err(e) => {
+            error!("Failed to update Airtable table mapping: {}", e);
+            Ok(Json(ApiResponse::error(&format!(
+                "Failed to update table mapping: {}",
+                e
+            ))))
+        }
+    }
+}
+
+/// Stop syncing a table. Tasks and their links are kept.
+async fn delete_table_mapping(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, StatusCode> {
+    let pool = &deployment.db().pool;
+
+    match AirtableTableMapping::delete(pool, id).await {
+        Ok(()) => {
+            info!("Deleted Airtable table mapping {}", id);
+            Ok(Json(ApiResponse::success(())))
+        }
+        Err(e) => {
+            error!("Failed to delete Airtable table mapping: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Sync a table now instead of waiting for the next scheduled run
+async fn sync_table_mapping(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<AirtableSyncReport>>, StatusCode> {
+    let pool = &deployment.db().pool;
+
+    let mapping = match AirtableTableMapping::find_by_id(pool, id).await {
+        Ok(Some(m)) => m,
+        Ok(None) => return Ok(Json(ApiResponse::error("Table mapping not found"))),
+        Err(e) => {
+            error!("Failed to get table mapping: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let service = AirtableSyncService::new(pool.clone(), deployment.config().clone());
+    match service.sync_mapping(&mapping).await {
+        Ok(report) => Ok(Json(ApiResponse::success(report))),
+        Err(e) => Ok(Json(ApiResponse::error(&format!(
+            "Failed to sync with Airtable: {}",
+            e
+        )))),
+    }
+}
+
 /// Get the Airtable link for a task
 async fn get_task_link(
     State(deployment): State<DeploymentImpl>,
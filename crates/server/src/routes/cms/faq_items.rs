@@ -5,13 +5,20 @@ use axum::{
     response::Json as ResponseJson,
     routing::{get, post, put},
 };
+use db::models::cms_entity::CmsEntity;
 use db::models::cms_faq_item::{CmsFaqItem, CreateCmsFaqItem, UpdateCmsFaqItem, ReorderFaqItems};
+use db::models::cms_revision::CmsRevisionAction;
 use db::models::cms_site::CmsSite;
 use utils::response::ApiResponse;
 use uuid::Uuid;
 
 use deployment::Deployment;
-use crate::{DeploymentImpl, error::ApiError, middleware::require_auth};
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, require_auth},
+};
+use super::record_revision;
 
 pub async fn list_faq_items(
     Extension(site): Extension<CmsSite>,
@@ -39,15 +46,24 @@ pub async fn get_faq_item(
 
 pub async fn create_faq_item(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     State(deployment): State<DeploymentImpl>,
     Json(payload): Json<CreateCmsFaqItem>,
 ) -> Result<ResponseJson<ApiResponse<CmsFaqItem>>, ApiError> {
     let item = CmsFaqItem::create(&deployment.db().pool, site.id, &payload).await?;
+    record_revision(
+        &deployment,
+        CmsEntity::FaqItem(item.clone()),
+        CmsRevisionAction::Created,
+        &access,
+    )
+    .await?;
     Ok(ResponseJson(ApiResponse::success(item)))
 }
 
 pub async fn update_faq_item(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     Path(item_id): Path<Uuid>,
     State(deployment): State<DeploymentImpl>,
     Json(payload): Json<UpdateCmsFaqItem>,
@@ -61,11 +77,19 @@ pub async fn update_faq_item(
     }
 
     let item = CmsFaqItem::update(&deployment.db().pool, item_id, &payload).await?;
+    record_revision(
+        &deployment,
+        CmsEntity::FaqItem(item.clone()),
+        CmsRevisionAction::Updated,
+        &access,
+    )
+    .await?;
     Ok(ResponseJson(ApiResponse::success(item)))
 }
 
 pub async fn reorder_faq_items(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     State(deployment): State<DeploymentImpl>,
     Json(payload): Json<ReorderFaqItems>,
 ) -> Result<ResponseJson<ApiResponse<Vec<CmsFaqItem>>>, ApiError> {
@@ -83,11 +107,21 @@ pub async fn reorder_faq_items(
     CmsFaqItem::reorder(&deployment.db().pool, &payload.item_ids).await?;
 
     let items = CmsFaqItem::find_by_site(&deployment.db().pool, site.id).await?;
+    for item in items.iter().filter(|item| payload.item_ids.contains(&item.id)) {
+        record_revision(
+            &deployment,
+            CmsEntity::FaqItem(item.clone()),
+            CmsRevisionAction::Updated,
+            &access,
+        )
+        .await?;
+    }
     Ok(ResponseJson(ApiResponse::success(items)))
 }
 
 pub async fn delete_faq_item(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     Path(item_id): Path<Uuid>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
@@ -100,6 +134,13 @@ pub async fn delete_faq_item(
     }
 
     CmsFaqItem::delete(&deployment.db().pool, item_id).await?;
+    record_revision(
+        &deployment,
+        CmsEntity::FaqItem(existing),
+        CmsRevisionAction::Deleted,
+        &access,
+    )
+    .await?;
     Ok(ResponseJson(ApiResponse::success(())))
 }
 
@@ -4,17 +4,26 @@ use axum::{
     middleware::{self, Next, from_fn_with_state},
     response::Response,
 };
+use db::models::cms_entity::CmsEntity;
+use db::models::cms_revision::{CmsRevision, CmsRevisionAction};
 use db::models::cms_site::CmsSite;
 use uuid::Uuid;
 
 use deployment::Deployment;
-use crate::{DeploymentImpl, error::ApiError, middleware::require_auth};
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, require_auth},
+};
 
 pub mod faq_items;
 pub mod page_sections;
 pub mod products;
 pub mod public;
+pub mod publishing;
+pub mod revisions;
 pub mod settings;
+pub mod webhooks;
 
 /// Middleware to load CMS site from path parameter
 pub async fn load_site_middleware(
@@ -31,6 +40,17 @@ pub async fn load_site_middleware(
     Ok(next.run(req).await)
 }
 
+/// Record a draft change in the revision history
+pub async fn record_revision(
+    deployment: &DeploymentImpl,
+    entity: CmsEntity,
+    action: CmsRevisionAction,
+    access: &AccessContext,
+) -> Result<(), ApiError> {
+    CmsRevision::record(&deployment.db().pool, &entity, action, Some(access.user_id)).await?;
+    Ok(())
+}
+
 /// CMS admin router (authenticated, requires site access)
 pub fn admin_router(deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
     let site_routes = Router::new()
@@ -38,6 +58,9 @@ pub fn admin_router(deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
         .nest("/faq-items", faq_items::router(deployment))
         .nest("/page-sections", page_sections::router(deployment))
         .nest("/settings", settings::router(deployment))
+        .nest("/publishing", publishing::router(deployment))
+        .nest("/revisions", revisions::router(deployment))
+        .nest("/webhooks", webhooks::router(deployment))
         .layer(from_fn_with_state(
             deployment.clone(),
             load_site_middleware,
@@ -5,13 +5,20 @@ use axum::{
     response::Json as ResponseJson,
     routing::{get, post},
 };
+use db::models::cms_entity::CmsEntity;
 use db::models::cms_page_section::{CmsPageSection, CreateCmsPageSection, UpdateCmsPageSection};
+use db::models::cms_revision::CmsRevisionAction;
 use db::models::cms_site::CmsSite;
 use utils::response::ApiResponse;
 use uuid::Uuid;
 
 use deployment::Deployment;
-use crate::{DeploymentImpl, error::ApiError, middleware::require_auth};
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, require_auth},
+};
+use super::record_revision;
 
 pub async fn list_page_sections(
     Extension(site): Extension<CmsSite>,
@@ -48,15 +55,30 @@ pub async fn get_section(
 
 pub async fn create_or_update_section(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     State(deployment): State<DeploymentImpl>,
     Json(payload): Json<CreateCmsPageSection>,
 ) -> Result<ResponseJson<ApiResponse<CmsPageSection>>, ApiError> {
+    let existing = CmsPageSection::find_by_key(
+        &deployment.db().pool,
+        site.id,
+        &payload.page_slug,
+        &payload.section_key,
+    )
+    .await?;
     let section = CmsPageSection::upsert(&deployment.db().pool, site.id, &payload).await?;
+    let action = if existing.is_some() {
+        CmsRevisionAction::Updated
+    } else {
+        CmsRevisionAction::Created
+    };
+    record_revision(&deployment, CmsEntity::PageSection(section.clone()), action, &access).await?;
     Ok(ResponseJson(ApiResponse::success(section)))
 }
 
 pub async fn update_section(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     Path(section_id): Path<Uuid>,
     State(deployment): State<DeploymentImpl>,
     Json(payload): Json<UpdateCmsPageSection>,
@@ -70,11 +92,19 @@ pub async fn update_section(
     }
 
     let section = CmsPageSection::update(&deployment.db().pool, section_id, &payload).await?;
+    record_revision(
+        &deployment,
+        CmsEntity::PageSection(section.clone()),
+        CmsRevisionAction::Updated,
+        &access,
+    )
+    .await?;
     Ok(ResponseJson(ApiResponse::success(section)))
 }
 
 pub async fn delete_section(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     Path(section_id): Path<Uuid>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
@@ -87,6 +117,13 @@ pub async fn delete_section(
     }
 
     CmsPageSection::delete(&deployment.db().pool, section_id).await?;
+    record_revision(
+        &deployment,
+        CmsEntity::PageSection(existing),
+        CmsRevisionAction::Deleted,
+        &access,
+    )
+    .await?;
     Ok(ResponseJson(ApiResponse::success(())))
 }
 
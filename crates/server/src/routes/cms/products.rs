@@ -5,13 +5,20 @@ use axum::{
     response::Json as ResponseJson,
     routing::{get, post},
 };
+use db::models::cms_entity::CmsEntity;
 use db::models::cms_product::{CmsProduct, CreateCmsProduct, UpdateCmsProduct};
+use db::models::cms_revision::CmsRevisionAction;
 use db::models::cms_site::CmsSite;
 use utils::response::ApiResponse;
 use uuid::Uuid;
 
 use deployment::Deployment;
-use crate::{DeploymentImpl, error::ApiError, middleware::require_auth};
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, require_auth},
+};
+use super::record_revision;
 
 pub async fn list_products(
     Extension(site): Extension<CmsSite>,
@@ -40,15 +47,24 @@ pub async fn get_product(
 
 pub async fn create_product(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     State(deployment): State<DeploymentImpl>,
     Json(payload): Json<CreateCmsProduct>,
 ) -> Result<ResponseJson<ApiResponse<CmsProduct>>, ApiError> {
     let product = CmsProduct::create(&deployment.db().pool, site.id, &payload).await?;
+    record_revision(
+        &deployment,
+        CmsEntity::Product(product.clone()),
+        CmsRevisionAction::Created,
+        &access,
+    )
+    .await?;
     Ok(ResponseJson(ApiResponse::success(product)))
 }
 
 pub async fn update_product(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     Path(product_id): Path<Uuid>,
     State(deployment): State<DeploymentImpl>,
     Json(payload): Json<UpdateCmsProduct>,
@@ -63,11 +79,19 @@ pub async fn update_product(
     }
 
     let product = CmsProduct::update(&deployment.db().pool, product_id, &payload).await?;
+    record_revision(
+        &deployment,
+        CmsEntity::Product(product.clone()),
+        CmsRevisionAction::Updated,
+        &access,
+    )
+    .await?;
     Ok(ResponseJson(ApiResponse::success(product)))
 }
 
 pub async fn delete_product(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     Path(product_id): Path<Uuid>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
@@ -80,7 +104,15 @@ pub async fn delete_product(
         return Err(ApiError::NotFound("Product not found".to_string()));
     }
 
+    // Stays on the public site until the deletion is published
     CmsProduct::delete(&deployment.db().pool, product_id).await?;
+    record_revision(
+        &deployment,
+        CmsEntity::Product(existing),
+        CmsRevisionAction::Deleted,
+        &access,
+    )
+    .await?;
     Ok(ResponseJson(ApiResponse::success(())))
 }
 
@@ -7,8 +7,8 @@ use axum::{
 use db::models::cms_faq_item::CmsFaqItem;
 use db::models::cms_page_section::CmsPageSection;
 use db::models::cms_product::CmsProduct;
+use db::models::cms_published_entity::CmsPublishedContent;
 use db::models::cms_site::CmsSite;
-use db::models::cms_site_setting::CmsSiteSetting;
 use serde::Serialize;
 use ts_rs::TS;
 use utils::response::ApiResponse;
@@ -23,26 +23,37 @@ pub struct PublicSiteData {
     pub settings: HashMap<String, String>,
 }
 
-// Get site config and theme
-pub async fn get_site(
-    Path(slug): Path<String>,
-    State(deployment): State<DeploymentImpl>,
-) -> Result<ResponseJson<ApiResponse<PublicSiteData>>, ApiError> {
-    let site = CmsSite::find_by_slug(&deployment.db().pool, &slug)
+/// Load what a site has published. Drafts are never served.
+async fn load_published(
+    deployment: &DeploymentImpl,
+    slug: &str,
+) -> Result<CmsPublishedContent, ApiError> {
+    let site = CmsSite::find_by_slug(&deployment.db().pool, slug)
         .await?
         .ok_or(ApiError::NotFound("Site not found".to_string()))?;
 
-    if !site.is_active {
+    let content = CmsPublishedContent::load(&deployment.db().pool, site.id)
+        .await?
+        .ok_or(ApiError::NotFound("Site not found".to_string()))?;
+
+    if !content.site.is_active {
         return Err(ApiError::NotFound("Site not found".to_string()));
     }
 
-    let settings_list = CmsSiteSetting::find_by_site(&deployment.db().pool, site.id).await?;
-    let settings: HashMap<String, String> = settings_list
-        .into_iter()
-        .map(|s| (s.setting_key, s.setting_value))
-        .collect();
+    Ok(content)
+}
 
-    Ok(ResponseJson(ApiResponse::success(PublicSiteData { site, settings })))
+// Get site config and theme
+pub async fn get_site(
+    Path(slug): Path<String>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<PublicSiteData>>, ApiError> {
+    let content = load_published(&deployment, &slug).await?;
+
+    Ok(ResponseJson(ApiResponse::success(PublicSiteData {
+        site: content.site,
+        settings: content.settings,
+    })))
 }
 
 // Get active products for a site
@@ -50,16 +61,8 @@ pub async fn get_products(
     Path(slug): Path<String>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<Vec<CmsProduct>>>, ApiError> {
-    let site = CmsSite::find_by_slug(&deployment.db().pool, &slug)
-        .await?
-        .ok_or(ApiError::NotFound("Site not found".to_string()))?;
-
-    if !site.is_active {
-        return Err(ApiError::NotFound("Site not found".to_string()));
-    }
-
-    let products = CmsProduct::find_active_by_site(&deployment.db().pool, site.id).await?;
-    Ok(ResponseJson(ApiResponse::success(products)))
+    let content = load_published(&deployment, &slug).await?;
+    Ok(ResponseJson(ApiResponse::success(content.products)))
 }
 
 // Get single product by slug
@@ -67,22 +70,14 @@ pub async fn get_product(
     Path((site_slug, product_slug)): Path<(String, String)>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<CmsProduct>>, ApiError> {
-    let site = CmsSite::find_by_slug(&deployment.db().pool, &site_slug)
-        .await?
-        .ok_or(ApiError::NotFound("Site not found".to_string()))?;
-
-    if !site.is_active {
-        return Err(ApiError::NotFound("Site not found".to_string()));
-    }
+    let content = load_published(&deployment, &site_slug).await?;
 
-    let product = CmsProduct::find_by_slug(&deployment.db().pool, site.id, &product_slug)
-        .await?
+    // Inactive products are left out when the content loads
+    let product = content
+        .product(&product_slug)
+        .cloned()
         .ok_or(ApiError::NotFound("Product not found".to_string()))?;
 
-    if !product.is_active {
-        return Err(ApiError::NotFound("Product not found".to_string()));
-    }
-
     Ok(ResponseJson(ApiResponse::success(product)))
 }
 
@@ -91,16 +86,8 @@ pub async fn get_faq(
     Path(slug): Path<String>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<Vec<CmsFaqItem>>>, ApiError> {
-    let site = CmsSite::find_by_slug(&deployment.db().pool, &slug)
-        .await?
-        .ok_or(ApiError::NotFound("Site not found".to_string()))?;
-
-    if !site.is_active {
-        return Err(ApiError::NotFound("Site not found".to_string()));
-    }
-
-    let items = CmsFaqItem::find_active_by_site(&deployment.db().pool, site.id).await?;
-    Ok(ResponseJson(ApiResponse::success(items)))
+    let content = load_published(&deployment, &slug).await?;
+    Ok(ResponseJson(ApiResponse::success(content.faq_items)))
 }
 
 // Get active page sections for a specific page
@@ -108,16 +95,8 @@ pub async fn get_page_sections(
     Path((site_slug, page_slug)): Path<(String, String)>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<Vec<CmsPageSection>>>, ApiError> {
-    let site = CmsSite::find_by_slug(&deployment.db().pool, &site_slug)
-        .await?
-        .ok_or(ApiError::NotFound("Site not found".to_string()))?;
-
-    if !site.is_active {
-        return Err(ApiError::NotFound("Site not found".to_string()));
-    }
-
-    let sections = CmsPageSection::find_active_by_page(&deployment.db().pool, site.id, &page_slug).await?;
-    Ok(ResponseJson(ApiResponse::success(sections)))
+    let content = load_published(&deployment, &site_slug).await?;
+    Ok(ResponseJson(ApiResponse::success(content.page_sections(&page_slug))))
 }
 
 pub fn router() -> Router<DeploymentImpl> {
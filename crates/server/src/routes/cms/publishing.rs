@@ -0,0 +1,154 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    middleware::from_fn_with_state,
+    response::Json as ResponseJson,
+    routing::{delete, get, post},
+};
+use db::models::cms_entity::CmsEntityType;
+use db::models::cms_scheduled_publication::{
+    CmsScheduledPublication, CreateCmsScheduledPublication,
+};
+use db::models::cms_site::CmsSite;
+use serde::Deserialize;
+use services::services::{
+    cms_export::{self, CmsExportResult},
+    cms_publishing::{CmsEntityStatus, CmsPublishReport, CmsPublishingService},
+};
+use ts_rs::TS;
+use utils::response::ApiResponse;
+use uuid::Uuid;
+
+use deployment::Deployment;
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, require_auth},
+};
+
+/// Publish one entity, or the whole site when none is given
+#[derive(Debug, Deserialize, TS)]
+pub struct CmsPublishRequest {
+    pub entity_type: Option<CmsEntityType>,
+    pub entity_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+pub struct CmsUnpublishRequest {
+    pub entity_type: CmsEntityType,
+    pub entity_id: Uuid,
+}
+
+fn publishing_service(deployment: &DeploymentImpl) -> CmsPublishingService {
+    CmsPublishingService::new(deployment.db().pool.clone())
+}
+
+pub async fn get_status(
+    Extension(site): Extension<CmsSite>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<Vec<CmsEntityStatus>>>, ApiError> {
+    let status = publishing_service(&deployment).status(site.id).await?;
+    Ok(ResponseJson(ApiResponse::success(status)))
+}
+
+pub async fn publish(
+    Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Json(payload): Json<CmsPublishRequest>,
+) -> Result<ResponseJson<ApiResponse<CmsPublishReport>>, ApiError> {
+    let service = publishing_service(&deployment);
+    let report = match (payload.entity_type, payload.entity_id) {
+        (Some(entity_type), Some(entity_id)) => {
+            service
+                .publish_entity(site.id, entity_type, entity_id, Some(access.user_id))
+                .await?
+        }
+        (None, None) => service.publish_site(site.id, Some(access.user_id)).await?,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "entity_type and entity_id must be given together".to_string(),
+            ));
+        }
+    };
+    Ok(ResponseJson(ApiResponse::success(report)))
+}
+
+pub async fn unpublish(
+    Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Json(payload): Json<CmsUnpublishRequest>,
+) -> Result<ResponseJson<ApiResponse<CmsPublishReport>>, ApiError> {
+    let report = publishing_service(&deployment)
+        .unpublish_entity(
+            site.id,
+            payload.entity_type,
+            payload.entity_id,
+            Some(access.user_id),
+        )
+        .await?;
+    Ok(ResponseJson(ApiResponse::success(report)))
+}
+
+pub async fn list_schedules(
+    Extension(site): Extension<CmsSite>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<Vec<CmsScheduledPublication>>>, ApiError> {
+    let schedules = CmsScheduledPublication::find_by_site(&deployment.db().pool, site.id).await?;
+    Ok(ResponseJson(ApiResponse::success(schedules)))
+}
+
+pub async fn create_schedule(
+    Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Json(payload): Json<CreateCmsScheduledPublication>,
+) -> Result<ResponseJson<ApiResponse<CmsScheduledPublication>>, ApiError> {
+    let schedule = publishing_service(&deployment)
+        .schedule(site.id, &payload, Some(access.user_id))
+        .await?;
+    Ok(ResponseJson(ApiResponse::success(schedule)))
+}
+
+pub async fn cancel_schedule(
+    Extension(site): Extension<CmsSite>,
+    Path(schedule_id): Path<Uuid>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
+    let pool = &deployment.db().pool;
+    let schedule = CmsScheduledPublication::find_by_id(pool, schedule_id)
+        .await?
+        .filter(|schedule| schedule.site_id == site.id)
+        .ok_or(ApiError::NotFound("Schedule not found".to_string()))?;
+
+    if CmsScheduledPublication::cancel(pool, schedule.id).await? == 0 {
+        return Err(ApiError::Conflict(
+            "Schedule has already run or been cancelled".to_string(),
+        ));
+    }
+    Ok(ResponseJson(ApiResponse::success(())))
+}
+
+/// Write the published site out as static JSON and HTML files
+pub async fn export(
+    Extension(site): Extension<CmsSite>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<CmsExportResult>>, ApiError> {
+    let result = cms_export::export_site(&deployment.db().pool, site.id).await?;
+    Ok(ResponseJson(ApiResponse::success(result)))
+}
+
+pub fn router(deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route("/", get(get_status))
+        .route("/publish", post(publish))
+        .route("/unpublish", post(unpublish))
+        .route("/schedules", get(list_schedules).post(create_schedule))
+        .route("/schedules/{schedule_id}", delete(cancel_schedule))
+        .route("/export", post(export))
+        .layer(from_fn_with_state(
+            deployment.clone(),
+            require_auth,
+        ))
+}
@@ -0,0 +1,120 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query, State},
+    middleware::from_fn_with_state,
+    response::Json as ResponseJson,
+    routing::{get, post},
+};
+use db::models::cms_entity::CmsEntityType;
+use db::models::cms_revision::CmsRevision;
+use db::models::cms_site::CmsSite;
+use serde::Deserialize;
+use services::services::cms_publishing::{
+    CmsDiffTarget, CmsPublishingService, CmsRestoreResult, CmsRevisionDiff,
+};
+use ts_rs::TS;
+use utils::response::ApiResponse;
+use uuid::Uuid;
+
+use deployment::Deployment;
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, require_auth},
+};
+
+const DEFAULT_LIMIT: i64 = 50;
+
+/// Filter the history down to one entity
+#[derive(Debug, Deserialize, TS)]
+pub struct CmsRevisionQuery {
+    pub entity_type: Option<CmsEntityType>,
+    pub entity_id: Option<Uuid>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, TS)]
+pub struct CmsRevisionDiffQuery {
+    #[serde(default)]
+    pub against: CmsDiffTarget,
+}
+
+#[derive(Debug, Deserialize, TS)]
+pub struct CmsRestoreRequest {
+    /// Also make the restored version live
+    #[serde(default)]
+    pub publish: bool,
+}
+
+pub async fn list_revisions(
+    Extension(site): Extension<CmsSite>,
+    State(deployment): State<DeploymentImpl>,
+    Query(query): Query<CmsRevisionQuery>,
+) -> Result<ResponseJson<ApiResponse<Vec<CmsRevision>>>, ApiError> {
+    let pool = &deployment.db().pool;
+    let revisions = match (query.entity_type, query.entity_id) {
+        (Some(entity_type), Some(entity_id)) => {
+            let mut revisions = CmsRevision::find_by_entity(pool, entity_type, entity_id).await?;
+            revisions.retain(|revision| revision.site_id == site.id);
+            revisions
+        }
+        (None, None) => {
+            CmsRevision::find_by_site(pool, site.id, query.limit.unwrap_or(DEFAULT_LIMIT)).await?
+        }
+        _ => {
+            return Err(ApiError::BadRequest(
+                "entity_type and entity_id must be given together".to_string(),
+            ));
+        }
+    };
+    Ok(ResponseJson(ApiResponse::success(revisions)))
+}
+
+pub async fn get_revision(
+    Extension(site): Extension<CmsSite>,
+    Path(revision_id): Path<Uuid>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<CmsRevision>>, ApiError> {
+    let revision = CmsRevision::find_by_id(&deployment.db().pool, revision_id)
+        .await?
+        .filter(|revision| revision.site_id == site.id)
+        .ok_or(ApiError::NotFound("Revision not found".to_string()))?;
+    Ok(ResponseJson(ApiResponse::success(revision)))
+}
+
+pub async fn diff_revision(
+    Extension(site): Extension<CmsSite>,
+    Path(revision_id): Path<Uuid>,
+    State(deployment): State<DeploymentImpl>,
+    Query(query): Query<CmsRevisionDiffQuery>,
+) -> Result<ResponseJson<ApiResponse<CmsRevisionDiff>>, ApiError> {
+    let diff = CmsPublishingService::new(deployment.db().pool.clone())
+        .diff(site.id, revision_id, query.against)
+        .await?;
+    Ok(ResponseJson(ApiResponse::success(diff)))
+}
+
+pub async fn restore_revision(
+    Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
+    Path(revision_id): Path<Uuid>,
+    State(deployment): State<DeploymentImpl>,
+    Json(payload): Json<CmsRestoreRequest>,
+) -> Result<ResponseJson<ApiResponse<CmsRestoreResult>>, ApiError> {
+    let result = CmsPublishingService::new(deployment.db().pool.clone())
+        .restore(site.id, revision_id, payload.publish, Some(access.user_id))
+        .await?;
+    Ok(ResponseJson(ApiResponse::success(result)))
+}
+
+pub fn router(deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route("/", get(list_revisions))
+        .route("/{revision_id}", get(get_revision))
+        .route("/{revision_id}/diff", get(diff_revision))
+        .route("/{revision_id}/restore", post(restore_revision))
+        .layer(from_fn_with_state(
+            deployment.clone(),
+            require_auth,
+        ))
+}
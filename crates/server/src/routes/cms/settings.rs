@@ -5,6 +5,8 @@ use axum::{
     response::Json as ResponseJson,
     routing::{get, put, delete},
 };
+use db::models::cms_entity::CmsEntity;
+use db::models::cms_revision::CmsRevisionAction;
 use db::models::cms_site_setting::{CmsSiteSetting, SetCmsSiteSetting};
 use db::models::cms_site::CmsSite;
 use serde::Serialize;
@@ -13,7 +15,12 @@ use utils::response::ApiResponse;
 use std::collections::HashMap;
 
 use deployment::Deployment;
-use crate::{DeploymentImpl, error::ApiError, middleware::require_auth};
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, require_auth},
+};
+use super::record_revision;
 
 #[derive(Debug, Serialize, TS)]
 pub struct SettingsMap {
@@ -51,20 +58,39 @@ pub async fn get_setting(
 
 pub async fn set_setting(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     Path(key): Path<String>,
     State(deployment): State<DeploymentImpl>,
     Json(payload): Json<SetCmsSiteSetting>,
 ) -> Result<ResponseJson<ApiResponse<CmsSiteSetting>>, ApiError> {
+    let existing = CmsSiteSetting::find_by_key(&deployment.db().pool, site.id, &key).await?;
     let setting = CmsSiteSetting::set(&deployment.db().pool, site.id, &key, &payload.value).await?;
+    let action = if existing.is_some() {
+        CmsRevisionAction::Updated
+    } else {
+        CmsRevisionAction::Created
+    };
+    record_revision(&deployment, CmsEntity::Setting(setting.clone()), action, &access).await?;
     Ok(ResponseJson(ApiResponse::success(setting)))
 }
 
 pub async fn delete_setting(
     Extension(site): Extension<CmsSite>,
+    Extension(access): Extension<AccessContext>,
     Path(key): Path<String>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
+    let existing = CmsSiteSetting::find_by_key(&deployment.db().pool, site.id, &key).await?;
     CmsSiteSetting::delete(&deployment.db().pool, site.id, &key).await?;
+    if let Some(setting) = existing {
+        record_revision(
+            &deployment,
+            CmsEntity::Setting(setting),
+            CmsRevisionAction::Deleted,
+            &access,
+        )
+        .await?;
+    }
     Ok(ResponseJson(ApiResponse::success(())))
 }
 
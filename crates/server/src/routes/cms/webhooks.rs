@@ -0,0 +1,90 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, State},
+    middleware::from_fn_with_state,
+    response::Json as ResponseJson,
+    routing::{get, patch},
+};
+use db::models::cms_site::CmsSite;
+use db::models::cms_webhook::{CmsWebhook, CreateCmsWebhook, UpdateCmsWebhook};
+use utils::response::ApiResponse;
+use uuid::Uuid;
+
+use deployment::Deployment;
+use crate::{DeploymentImpl, error::ApiError, middleware::require_auth};
+
+fn validate_url(url: &str) -> Result<(), ApiError> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err(ApiError::BadRequest(
+            "Webhook URL must start with http:// or https://".to_string(),
+        ))
+    }
+}
+
+async fn find_site_webhook(
+    deployment: &DeploymentImpl,
+    site: &CmsSite,
+    webhook_id: Uuid,
+) -> Result<CmsWebhook, ApiError> {
+    CmsWebhook::find_by_id(&deployment.db().pool, webhook_id)
+        .await?
+        .filter(|webhook| webhook.site_id == site.id)
+        .ok_or(ApiError::NotFound("Webhook not found".to_string()))
+}
+
+pub async fn list_webhooks(
+    Extension(site): Extension<CmsSite>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<Vec<CmsWebhook>>>, ApiError> {
+    let webhooks = CmsWebhook::find_by_site(&deployment.db().pool, site.id).await?;
+    Ok(ResponseJson(ApiResponse::success(webhooks)))
+}
+
+pub async fn create_webhook(
+    Extension(site): Extension<CmsSite>,
+    State(deployment): State<DeploymentImpl>,
+    Json(payload): Json<CreateCmsWebhook>,
+) -> Result<ResponseJson<ApiResponse<CmsWebhook>>, ApiError> {
+    validate_url(&payload.url)?;
+    let webhook = CmsWebhook::create(&deployment.db().pool, site.id, &payload).await?;
+    Ok(ResponseJson(ApiResponse::success(webhook)))
+}
+
+pub async fn update_webhook(
+    Extension(site): Extension<CmsSite>,
+    Path(webhook_id): Path<Uuid>,
+    State(deployment): State<DeploymentImpl>,
+    Json(payload): Json<UpdateCmsWebhook>,
+) -> Result<ResponseJson<ApiResponse<CmsWebhook>>, ApiError> {
+    find_site_webhook(&deployment, &site, webhook_id).await?;
+    if let Some(url) = &payload.url {
+        validate_url(url)?;
+    }
+    let webhook = CmsWebhook::update(&deployment.db().pool, webhook_id, &payload).await?;
+    Ok(ResponseJson(ApiResponse::success(webhook)))
+}
+
+pub async fn delete_webhook(
+    Extension(site): Extension<CmsSite>,
+    Path(webhook_id): Path<Uuid>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
+    find_site_webhook(&deployment, &site, webhook_id).await?;
+    CmsWebhook::delete(&deployment.db().pool, webhook_id).await?;
+    Ok(ResponseJson(ApiResponse::success(())))
+}
+
+pub fn router(deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route("/", get(list_webhooks).post(create_webhook))
+        .route(
+            "/{webhook_id}",
+            patch(update_webhook).delete(delete_webhook),
+        )
+        .layer(from_fn_with_state(
+            deployment.clone(),
+            require_auth,
+        ))
+}
@@ -5,11 +5,16 @@
 use axum::{
     Router,
     extract::{Path, Query, State},
-    routing::{get, post, delete, patch},
+    routing::{get, post, put, delete, patch},
     Json,
 };
 use deployment::Deployment;
 use serde::{Deserialize, Serialize};
+use services::services::{
+    lead_scoring::LeadScoringService,
+    zoho::{ZohoSyncReport, ZohoSyncService},
+};
+use tracing::warn;
 use utils::response::ApiResponse;
 use uuid::Uuid;
 
@@ -17,6 +22,14 @@ use crate::{DeploymentImpl, error::ApiError};
 use db::models::crm_contact::{
     CrmContact, CreateCrmContact, UpdateCrmContact, ContactSearchParams, LifecycleStage
 };
+use db::models::lead_scoring::{
+    LeadScoreBreakdown, LeadScoreHistory, LeadScoreTrigger, LeadScoringConfig,
+    UpsertLeadScoringConfig,
+};
+use db::models::email_account::{EmailAccount, EmailProvider};
+use db::models::zoho_integration::{
+    UpdateZohoIntegration, ZohoConnection, ZohoIntegration, ZohoSyncConflict,
+};
 
 #[derive(Debug, Deserialize)]
 pub struct ListContactsQuery {
@@ -55,6 +68,42 @@ pub struct UpdateLeadScoreRequest {
     pub score_delta: i32,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct LeadScoreHistoryQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecalculateResult {
+    pub changed: usize,
+}
+
+/// Connect with the CRM grant of a Zoho mail account, or a refresh token
+#[derive(Debug, Deserialize)]
+pub struct ConnectZohoRequest {
+    pub email_account_id: Option<Uuid>,
+    pub refresh_token: Option<String>,
+    pub zoho_domain: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ZohoConflictsQuery {
+    pub limit: Option<i64>,
+}
+
+/// Rescore a contact after an edit; the edit itself has already succeeded
+async fn rescore_after_update(deployment: &DeploymentImpl, contact: CrmContact) -> CrmContact {
+    let pool = &deployment.db().pool;
+    if let Err(e) = LeadScoringService::new(pool.clone())
+        .rescore_contact(contact.id, LeadScoreTrigger::ContactUpdated)
+        .await
+    {
+        warn!("Failed to rescore contact {}: {}", contact.id, e);
+        return contact;
+    }
+    CrmContact::find_by_id(pool, contact.id).await.unwrap_or(contact)
+}
+
 /// GET /crm/contacts - List contacts
 async fn list_contacts(
     State(deployment): State<DeploymentImpl>,
@@ -91,6 +140,7 @@ async fn create_contact(
     }
 
     let contact = CrmContact::create(pool, data).await?;
+    let contact = rescore_after_update(&deployment, contact).await;
     Ok(Json(ApiResponse::success(contact)))
 }
 
@@ -207,6 +257,7 @@ async fn update_contact(
 ) -> Result<Json<ApiResponse<CrmContact>>, ApiError> {
     let pool = &deployment.db().pool;
     let contact = CrmContact::update(pool, id, update).await?;
+    let contact = rescore_after_update(&deployment, contact).await;
     Ok(Json(ApiResponse::success(contact)))
 }
 
@@ -240,18 +291,210 @@ async fn record_replied(
     Ok(Json(ApiResponse::success(())))
 }
 
-/// POST /crm/contacts/:id/lead-score - Update lead score
+/// POST /crm/contacts/:id/lead-score - Manually adjust lead score
 async fn update_lead_score(
     State(deployment): State<DeploymentImpl>,
     Path(id): Path<Uuid>,
     Json(request): Json<UpdateLeadScoreRequest>,
 ) -> Result<Json<ApiResponse<CrmContact>>, ApiError> {
     let pool = &deployment.db().pool;
-    CrmContact::update_lead_score(pool, id, request.score_delta).await?;
+    LeadScoringService::new(pool.clone())
+        .adjust_contact(id, request.score_delta)
+        .await?;
     let contact = CrmContact::find_by_id(pool, id).await?;
     Ok(Json(ApiResponse::success(contact)))
 }
 
+/// GET /crm/contacts/:id/lead-score - Explain how the lead score is made up
+async fn get_lead_score(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<LeadScoreBreakdown>>, ApiError> {
+    let breakdown = LeadScoringService::new(deployment.db().pool.clone())
+        .explain(id)
+        .await?;
+    Ok(Json(ApiResponse::success(breakdown)))
+}
+
+/// POST /crm/contacts/:id/lead-score/recalculate - Recalculate lead score now
+async fn recalculate_lead_score(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<LeadScoreBreakdown>>, ApiError> {
+    let breakdown = LeadScoringService::new(deployment.db().pool.clone())
+        .rescore_contact(id, LeadScoreTrigger::Manual)
+        .await?;
+    Ok(Json(ApiResponse::success(breakdown)))
+}
+
+/// GET /crm/contacts/:id/lead-score/history - Lead score changes, newest first
+async fn get_lead_score_history(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<LeadScoreHistoryQuery>,
+) -> Result<Json<ApiResponse<Vec<LeadScoreHistory>>>, ApiError> {
+    let pool = &deployment.db().pool;
+    // 404 for unknown contacts rather than an empty history
+    CrmContact::find_by_id(pool, id).await?;
+    let history = LeadScoreHistory::find_by_contact(pool, id, query.limit.unwrap_or(50)).await?;
+    Ok(Json(ApiResponse::success(history)))
+}
+
+/// GET /crm/lead-scoring/:project_id - Get the project's scoring rules
+async fn get_lead_scoring_config(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<LeadScoringConfig>>, ApiError> {
+    let config = LeadScoringConfig::find_or_create(&deployment.db().pool, project_id).await?;
+    Ok(Json(ApiResponse::success(config)))
+}
+
+/// PUT /crm/lead-scoring/:project_id - Update scoring rules and rescore all contacts
+async fn update_lead_scoring_config(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+    Json(data): Json<UpsertLeadScoringConfig>,
+) -> Result<Json<ApiResponse<LeadScoringConfig>>, ApiError> {
+    let pool = &deployment.db().pool;
+    let config = LeadScoringConfig::upsert(pool, project_id, data).await?;
+    LeadScoringService::new(pool.clone())
+        .rescore_project(project_id, LeadScoreTrigger::ConfigChanged)
+        .await?;
+    Ok(Json(ApiResponse::success(config)))
+}
+
+/// POST /crm/lead-scoring/:project_id/recalculate - Rescore all contacts now
+async fn recalculate_project_lead_scores(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<RecalculateResult>>, ApiError> {
+    let changed = LeadScoringService::new(deployment.db().pool.clone())
+        .rescore_project(project_id, LeadScoreTrigger::Manual)
+        .await?;
+    Ok(Json(ApiResponse::success(RecalculateResult { changed })))
+}
+
+/// GET /crm/zoho/:project_id - Zoho sync settings and status
+async fn get_zoho_integration(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<ZohoIntegration>>, ApiError> {
+    let integration = ZohoIntegration::find_by_project(&deployment.db().pool, project_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Zoho integration not found".into()))?;
+    Ok(Json(ApiResponse::success(integration)))
+}
+
+/// PUT /crm/zoho/:project_id - Update what syncs, field mappings and conflict rules
+async fn update_zoho_integration(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+    Json(data): Json<UpdateZohoIntegration>,
+) -> Result<Json<ApiResponse<ZohoIntegration>>, ApiError> {
+    let integration =
+        ZohoIntegration::upsert_settings(&deployment.db().pool, project_id, data).await?;
+    Ok(Json(ApiResponse::success(integration)))
+}
+
+/// POST /crm/zoho/:project_id/connect - Connect the project to Zoho CRM
+async fn connect_zoho(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+    Json(request): Json<ConnectZohoRequest>,
+) -> Result<Json<ApiResponse<ZohoIntegration>>, ApiError> {
+    let pool = &deployment.db().pool;
+
+    let connection = if let Some(account_id) = request.email_account_id {
+        let account = EmailAccount::find_by_id(pool, account_id).await?;
+        if account.project_id != project_id || account.provider != EmailProvider::Zoho.to_string() {
+            return Err(ApiError::BadRequest("Not a Zoho account of this project".into()));
+        }
+        let scopes: Vec<String> = account
+            .granted_scopes
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default();
+        if !scopes.iter().any(|s| s.starts_with("ZohoCRM.")) {
+            return Err(ApiError::BadRequest(
+                "The account was connected without Zoho CRM access; reconnect it".into(),
+            ));
+        }
+        let refresh_token = account.refresh_token.clone().ok_or_else(|| {
+            ApiError::BadRequest("The account has no refresh token; reconnect it".into())
+        })?;
+        let account_domain = account
+            .metadata
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+            .and_then(|m| m.get("zoho_domain").and_then(|d| d.as_str()).map(str::to_string));
+        ZohoConnection {
+            zoho_domain: request
+                .zoho_domain
+                .or(account_domain)
+                .unwrap_or_else(|| "com".to_string()),
+            access_token: account.access_token.clone(),
+            refresh_token,
+            token_expires_at: account.token_expires_at,
+            granted_scopes: Some(scopes),
+        }
+    } else if let Some(refresh_token) = request.refresh_token.filter(|t| !t.trim().is_empty()) {
+        ZohoConnection {
+            zoho_domain: request.zoho_domain.unwrap_or_else(|| "com".to_string()),
+            access_token: None,
+            refresh_token,
+            token_expires_at: None,
+            granted_scopes: None,
+        }
+    } else {
+        return Err(ApiError::BadRequest(
+            "Provide email_account_id or refresh_token".into(),
+        ));
+    };
+
+    let integration = ZohoIntegration::connect(pool, project_id, connection).await?;
+    Ok(Json(ApiResponse::success(integration)))
+}
+
+/// POST /crm/zoho/:project_id/sync - Sync with Zoho now
+async fn sync_zoho(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<ZohoSyncReport>>, ApiError> {
+    let report = ZohoSyncService::new(deployment.db().pool.clone())
+        .sync_project(project_id)
+        .await?;
+    Ok(Json(ApiResponse::success(report)))
+}
+
+/// GET /crm/zoho/:project_id/conflicts - Fields changed on both sides, newest first
+async fn get_zoho_conflicts(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+    Query(query): Query<ZohoConflictsQuery>,
+) -> Result<Json<ApiResponse<Vec<ZohoSyncConflict>>>, ApiError> {
+    let pool = &deployment.db().pool;
+    let integration = ZohoIntegration::find_by_project(pool, project_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Zoho integration not found".into()))?;
+    let conflicts =
+        ZohoSyncConflict::find_by_integration(pool, integration.id, query.limit.unwrap_or(50))
+            .await?;
+    Ok(Json(ApiResponse::success(conflicts)))
+}
+
+/// DELETE /crm/zoho/:project_id - Disconnect Zoho and forget sync state
+async fn disconnect_zoho(
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, ApiError> {
+    let pool = &deployment.db().pool;
+    let integration = ZohoIntegration::find_by_project(pool, project_id)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("Zoho integration not found".into()))?;
+    ZohoIntegration::delete(pool, integration.id).await?;
+    Ok(Json(ApiResponse::success(())))
+}
+
 /// DELETE /crm/contacts/:id - Delete contact
 async fn delete_contact(
     State(deployment): State<DeploymentImpl>,
@@ -274,6 +517,18 @@ pub fn router(_deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
         .route("/crm/contacts/{id}/activity", post(record_activity))
         .route("/crm/contacts/{id}/contacted", post(record_contacted))
         .route("/crm/contacts/{id}/replied", post(record_replied))
+        .route("/crm/contacts/{id}/lead-score", get(get_lead_score))
         .route("/crm/contacts/{id}/lead-score", post(update_lead_score))
+        .route("/crm/contacts/{id}/lead-score/recalculate", post(recalculate_lead_score))
+        .route("/crm/contacts/{id}/lead-score/history", get(get_lead_score_history))
+        .route("/crm/lead-scoring/{project_id}", get(get_lead_scoring_config))
+        .route("/crm/lead-scoring/{project_id}", put(update_lead_scoring_config))
+        .route("/crm/lead-scoring/{project_id}/recalculate", post(recalculate_project_lead_scores))
         .route("/crm/contacts/by-email/{project_id}/{email}", get(get_contact_by_email))
+        .route("/crm/zoho/{project_id}", get(get_zoho_integration))
+        .route("/crm/zoho/{project_id}", put(update_zoho_integration))
+        .route("/crm/zoho/{project_id}", delete(disconnect_zoho))
+        .route("/crm/zoho/{project_id}/connect", post(connect_zoho))
+        .route("/crm/zoho/{project_id}/sync", post(sync_zoho))
+        .route("/crm/zoho/{project_id}/conflicts", get(get_zoho_conflicts))
 }
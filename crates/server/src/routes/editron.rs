@@ -0,0 +1,90 @@
+//! Editron peer protocol: mesh peers post segment jobs here and download the
+//! rendered files, and this node's own distributed exports serve their
+//! sources to peers from here
+
+use std::{path::Path as FsPath, sync::Arc};
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+    routing::{get, post},
+};
+use deployment::Deployment;
+use services::services::editron::{EditronError, SegmentDelivery, SegmentHost, SegmentJob};
+use tokio::fs::File;
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::{DeploymentImpl, error::ApiError};
+
+pub fn router(deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route("/editron/segments", post(render_segment))
+        .route("/editron/segments/{file_name}", get(download_segment))
+        .route("/editron/sources/{id}", get(download_source))
+        .with_state(deployment.clone())
+}
+
+fn segment_host(deployment: &DeploymentImpl) -> Result<&Arc<SegmentHost>, ApiError> {
+    deployment
+        .segment_host()
+        .ok_or_else(|| ApiError::InternalError("ffmpeg is not installed on this node".into()))
+}
+
+/// POST /api/editron/segments - Render a segment for another node
+async fn render_segment(
+    State(deployment): State<DeploymentImpl>,
+    headers: HeaderMap,
+    Json(job): Json<SegmentJob>,
+) -> Result<Json<SegmentDelivery>, ApiError> {
+    let host = segment_host(&deployment)?;
+    // The download URL must use the address the requesting node reached us on
+    let public_url = headers
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(|authority| format!("http://{authority}"))
+        .ok_or_else(|| ApiError::BadRequest("Missing Host header".into()))?;
+
+    let delivery = host.render(&job, &public_url).await.map_err(|e| match e {
+        EditronError::InvalidFormat(message) => ApiError::BadRequest(message),
+        e => ApiError::InternalError(e.to_string()),
+    })?;
+    Ok(Json(delivery))
+}
+
+/// GET /api/editron/segments/{file_name} - Download a rendered segment
+async fn download_segment(
+    State(deployment): State<DeploymentImpl>,
+    Path(file_name): Path<String>,
+) -> Result<Response, ApiError> {
+    let path = segment_host(&deployment)?
+        .segment_path(&file_name)
+        .ok_or_else(|| ApiError::NotFound("Segment not found".into()))?;
+    serve_file(&path).await
+}
+
+/// GET /api/editron/sources/{id} - Download the source of a running export
+async fn download_source(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let path = segment_host(&deployment)?
+        .source_path(id)
+        .await
+        .ok_or_else(|| ApiError::NotFound("Source not found".into()))?;
+    serve_file(&path).await
+}
+
+async fn serve_file(path: &FsPath) -> Result<Response, ApiError> {
+    let file = File::open(path).await?;
+    let length = file.metadata().await?.len();
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/octet-stream")
+        .header(header::CONTENT_LENGTH, length)
+        .body(Body::from_stream(ReaderStream::new(file)))
+        .map_err(|e| ApiError::InternalError(e.to_string()))
+}
@@ -1,6 +1,6 @@
 //! Email Account Management Routes
 //!
-//! Handles Gmail and Zoho Mail connections, OAuth flows, and email account operations.
+//! Handles Gmail and Zoho Mail connections, OAuth flows, IMAP/SMTP sync and sending.
 
 use axum::{
     Router,
@@ -14,8 +14,14 @@ use utils::response::ApiResponse;
 use uuid::Uuid;
 
 use crate::{DeploymentImpl, error::ApiError};
-use db::models::email_account::{
-    EmailAccount, CreateEmailAccount, UpdateEmailAccount, EmailProvider
+use db::models::{
+    email_account::{EmailAccount, CreateEmailAccount, UpdateEmailAccount, EmailProvider},
+    email_folder::EmailFolder,
+    email_message::EmailMessage,
+};
+use services::services::{
+    email_sequence::parse_send_time,
+    email_sync::{EmailSyncError, EmailSyncService, OutgoingEmail},
 };
 
 #[derive(Debug, Deserialize)]
@@ -100,10 +106,38 @@ async fn update_account(
     Json(update): Json<UpdateEmailAccount>,
 ) -> Result<Json<ApiResponse<EmailAccount>>, ApiError> {
     let pool = &deployment.db().pool;
+    let current = EmailAccount::find_by_id(pool, id).await?;
+    validate_send_settings(&current, &update)?;
     let account = EmailAccount::update(pool, id, update).await?;
     Ok(Json(ApiResponse::success(account)))
 }
 
+/// Reject send windows that sequences could never send in
+fn validate_send_settings(
+    current: &EmailAccount,
+    update: &UpdateEmailAccount,
+) -> Result<(), ApiError> {
+    let start = update.send_window_start.as_deref().unwrap_or(&current.send_window_start);
+    let end = update.send_window_end.as_deref().unwrap_or(&current.send_window_end);
+    let (Some(start), Some(end)) = (parse_send_time(start), parse_send_time(end)) else {
+        return Err(ApiError::BadRequest("Send window times must be HH:MM".into()));
+    };
+    if start >= end {
+        return Err(ApiError::BadRequest("Send window must end after it starts".into()));
+    }
+    if let Some(offset) = update.send_utc_offset_minutes
+        && !(-12 * 60..=14 * 60).contains(&offset)
+    {
+        return Err(ApiError::BadRequest(
+            "UTC offset must be between -12:00 and +14:00".into(),
+        ));
+    }
+    if update.daily_send_limit.is_some_and(|limit| limit < 0) {
+        return Err(ApiError::BadRequest("Daily send limit cannot be negative".into()));
+    }
+    Ok(())
+}
+
 /// DELETE /email/accounts/:id - Disconnect account
 async fn delete_account(
     State(deployment): State<DeploymentImpl>,
@@ -121,16 +155,64 @@ async fn trigger_sync(
 ) -> Result<Json<ApiResponse<EmailAccount>>, ApiError> {
     let pool = &deployment.db().pool;
 
-    // Update sync status to indicate sync is starting
+    // Reactivate the account in case a previous sync parked it in `error`
     EmailAccount::update_sync_status(pool, id, "active", None).await?;
+    let account = EmailAccount::find_by_id(pool, id).await?;
 
-    // TODO: Trigger actual email sync background job here
-    // For now, just return the updated account
+    // The first sync backfills whole folders, so run it in the background
+    let service = EmailSyncService::new(pool.clone());
+    let email_address = account.email_address.clone();
+    tokio::spawn(async move {
+        match service.sync_account(id).await {
+            Ok(report) => tracing::info!(
+                "Manual sync of {}: {} new, {} updated across {} folders",
+                email_address,
+                report.fetched,
+                report.updated,
+                report.folders
+            ),
+            Err(EmailSyncError::AlreadySyncing) => {}
+            Err(e) => tracing::error!("Manual sync of {} failed: {}", email_address, e),
+        }
+    });
 
-    let account = EmailAccount::find_by_id(pool, id).await?;
     Ok(Json(ApiResponse::success(account)))
 }
 
+/// GET /email/accounts/:id/folders - List synced IMAP folders
+async fn list_folders(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<EmailFolder>>>, ApiError> {
+    let pool = &deployment.db().pool;
+    EmailAccount::find_by_id(pool, id).await?;
+    let folders = EmailFolder::find_by_account(pool, id)
+        .await
+        .map_err(|e| ApiError::InternalError(e.to_string()))?;
+    Ok(Json(ApiResponse::success(folders)))
+}
+
+/// POST /email/accounts/:id/send - Send through the account's SMTP server
+async fn send_email(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+    Json(email): Json<OutgoingEmail>,
+) -> Result<Json<ApiResponse<EmailMessage>>, ApiError> {
+    let pool = &deployment.db().pool;
+    let sent = EmailSyncService::new(pool.clone())
+        .send(id, email)
+        .await
+        .map_err(|e| match e {
+            EmailSyncError::Account(e) => ApiError::EmailAccount(e),
+            EmailSyncError::Message(e) => ApiError::EmailMessage(e),
+            EmailSyncError::InvalidMessage(_)
+            | EmailSyncError::MissingSettings(_)
+            | EmailSyncError::Unsupported(_) => ApiError::BadRequest(e.to_string()),
+            e => ApiError::InternalError(format!("Failed to send email: {}", e)),
+        })?;
+    Ok(Json(ApiResponse::success(sent)))
+}
+
 /// POST /email/oauth/initiate - Start OAuth flow
 async fn initiate_oauth(
     Json(request): Json<InitiateOAuthRequest>,
@@ -293,6 +375,8 @@ async fn gmail_oauth_callback(
         smtp_host: None,
         smtp_port: None,
         use_ssl: None,
+        imap_username: None,
+        imap_password: None,
         granted_scopes: Some(EmailAccount::gmail_scopes().iter().map(|s| s.to_string()).collect()),
         metadata: None,
     }).await?;
@@ -431,6 +515,8 @@ async fn zoho_oauth_callback(
         smtp_host: None,
         smtp_port: None,
         use_ssl: None,
+        imap_username: None,
+        imap_password: None,
         granted_scopes: Some(scopes),
         metadata: Some(serde_json::json!({
             "zoho_account_id": zoho_account.account_id,
@@ -449,6 +535,8 @@ pub fn router(_deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
         .route("/email/accounts/{id}", patch(update_account))
         .route("/email/accounts/{id}", delete(delete_account))
         .route("/email/accounts/{id}/sync", post(trigger_sync))
+        .route("/email/accounts/{id}/folders", get(list_folders))
+        .route("/email/accounts/{id}/send", post(send_email))
         .route("/email/oauth/initiate", post(initiate_oauth))
         .route("/email/oauth/gmail/callback", get(gmail_oauth_callback))
         .route("/email/oauth/zoho/callback", get(zoho_oauth_callback))
@@ -0,0 +1,354 @@
+//! Email Sequence Routes
+//!
+//! Sequence and step editing, contact enrollment, per-step reporting and the
+//! public open-tracking pixel.
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, State},
+    http::header,
+    response::IntoResponse,
+    routing::{get, post, put},
+};
+use db::models::{
+    email_account::EmailAccount,
+    email_sequence::{
+        CreateEmailSequence, EmailSequence, EmailSequenceEnrollment, EmailSequenceEvent,
+        EmailSequenceStep, EnrollmentStatus, SequenceStatus, SequenceStepInput, SequenceStepStats,
+        UpdateEmailSequence,
+    },
+};
+use deployment::Deployment;
+use serde::{Deserialize, Serialize};
+use services::services::email_sequence::{EmailSequenceService, EnrollResult};
+use tracing::warn;
+use utils::response::ApiResponse;
+use uuid::Uuid;
+
+use crate::{DeploymentImpl, error::ApiError};
+
+/// 1x1 transparent GIF
+const TRACKING_PIXEL: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+#[derive(Debug, Deserialize)]
+pub struct ListSequencesQuery {
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListEnrollmentsQuery {
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReplaceStepsRequest {
+    pub steps: Vec<SequenceStepInput>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnrollRequest {
+    pub contact_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SequenceDetail {
+    #[serde(flatten)]
+    pub sequence: EmailSequence,
+    pub steps: Vec<EmailSequenceStep>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SequenceStats {
+    pub enrollments: Vec<EnrollmentCount>,
+    pub steps: Vec<SequenceStepStats>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnrollmentCount {
+    pub status: String,
+    pub count: i64,
+}
+
+async fn sequence_detail(
+    deployment: &DeploymentImpl,
+    sequence: EmailSequence,
+) -> Result<SequenceDetail, ApiError> {
+    let steps = EmailSequenceStep::find_by_sequence(&deployment.db().pool, sequence.id).await?;
+    Ok(SequenceDetail { sequence, steps })
+}
+
+/// Sequences may only send from an account of their own project
+async fn check_account(
+    deployment: &DeploymentImpl,
+    project_id: Uuid,
+    email_account_id: Uuid,
+) -> Result<(), ApiError> {
+    let account = EmailAccount::find_by_id(&deployment.db().pool, email_account_id).await?;
+    if account.project_id != project_id {
+        return Err(ApiError::BadRequest(
+            "Email account belongs to another project".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// GET /email/sequences - List sequences of a project
+async fn list_sequences(
+    State(deployment): State<DeploymentImpl>,
+    Query(query): Query<ListSequencesQuery>,
+) -> Result<Json<ApiResponse<Vec<EmailSequence>>>, ApiError> {
+    let sequences = EmailSequence::find_by_project(&deployment.db().pool, query.project_id).await?;
+    Ok(Json(ApiResponse::success(sequences)))
+}
+
+/// POST /email/sequences - Create a draft sequence
+async fn create_sequence(
+    State(deployment): State<DeploymentImpl>,
+    Json(data): Json<CreateEmailSequence>,
+) -> Result<Json<ApiResponse<SequenceDetail>>, ApiError> {
+    check_account(&deployment, data.project_id, data.email_account_id).await?;
+    let sequence = EmailSequence::create(&deployment.db().pool, data).await?;
+    Ok(Json(ApiResponse::success(
+        sequence_detail(&deployment, sequence).await?,
+    )))
+}
+
+/// GET /email/sequences/:id - Get a sequence with its steps
+async fn get_sequence(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<SequenceDetail>>, ApiError> {
+    let sequence = EmailSequence::find_by_id(&deployment.db().pool, id).await?;
+    Ok(Json(ApiResponse::success(
+        sequence_detail(&deployment, sequence).await?,
+    )))
+}
+
+/// PATCH /email/sequences/:id - Rename, change account or change status
+async fn update_sequence(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<UpdateEmailSequence>,
+) -> Result<Json<ApiResponse<SequenceDetail>>, ApiError> {
+    let pool = &deployment.db().pool;
+    let current = EmailSequence::find_by_id(pool, id).await?;
+    if let Some(account_id) = data.email_account_id {
+        check_account(&deployment, current.project_id, account_id).await?;
+    }
+    if data.status == Some(SequenceStatus::Active)
+        && EmailSequenceStep::find_by_sequence(pool, id)
+            .await?
+            .is_empty()
+    {
+        return Err(ApiError::BadRequest(
+            "Add at least one step before activating the sequence".into(),
+        ));
+    }
+
+    let sequence = EmailSequence::update(pool, id, data).await?;
+    Ok(Json(ApiResponse::success(
+        sequence_detail(&deployment, sequence).await?,
+    )))
+}
+
+/// DELETE /email/sequences/:id - Delete a sequence and its enrollments
+async fn delete_sequence(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, ApiError> {
+    EmailSequence::delete(&deployment.db().pool, id).await?;
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// PUT /email/sequences/:id/steps - Replace all steps, in order
+async fn replace_steps(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<ReplaceStepsRequest>,
+) -> Result<Json<ApiResponse<Vec<EmailSequenceStep>>>, ApiError> {
+    let pool = &deployment.db().pool;
+    EmailSequence::find_by_id(pool, id).await?;
+    let steps = EmailSequenceStep::replace_all(pool, id, data.steps).await?;
+    Ok(Json(ApiResponse::success(steps)))
+}
+
+/// POST /email/sequences/:id/enroll - Enroll one or many contacts
+async fn enroll_contacts(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+    Json(data): Json<EnrollRequest>,
+) -> Result<Json<ApiResponse<EnrollResult>>, ApiError> {
+    if data.contact_ids.is_empty() {
+        return Err(ApiError::BadRequest("No contacts to enroll".into()));
+    }
+    let result = EmailSequenceService::new(deployment.db().pool.clone())
+        .enroll(id, &data.contact_ids)
+        .await?;
+    Ok(Json(ApiResponse::success(result)))
+}
+
+/// GET /email/sequences/:id/enrollments - List enrollments, optionally by status
+async fn list_enrollments(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ListEnrollmentsQuery>,
+) -> Result<Json<ApiResponse<Vec<EmailSequenceEnrollment>>>, ApiError> {
+    let status = query
+        .status
+        .map(|s| {
+            s.parse::<EnrollmentStatus>()
+                .map_err(|_| ApiError::BadRequest(format!("Invalid enrollment status: {}", s)))
+        })
+        .transpose()?;
+    let enrollments =
+        EmailSequenceEnrollment::find_by_sequence(&deployment.db().pool, id, status).await?;
+    Ok(Json(ApiResponse::success(enrollments)))
+}
+
+/// GET /email/sequences/:id/stats - Enrollment counts and per-step results
+async fn get_sequence_stats(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<SequenceStats>>, ApiError> {
+    let pool = &deployment.db().pool;
+    EmailSequence::find_by_id(pool, id).await?;
+
+    let counts: Vec<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT status, COUNT(*) FROM email_sequence_enrollments
+        WHERE sequence_id = ?1
+        GROUP BY status
+        ORDER BY status
+        "#,
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    let steps = EmailSequenceEvent::step_stats(pool, id).await?;
+    Ok(Json(ApiResponse::success(SequenceStats {
+        enrollments: counts
+            .into_iter()
+            .map(|(status, count)| EnrollmentCount { status, count })
+            .collect(),
+        steps,
+    })))
+}
+
+/// GET /email/sequences/enrollments/:id/events - Timeline of an enrollment
+async fn list_enrollment_events(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<EmailSequenceEvent>>>, ApiError> {
+    let pool = &deployment.db().pool;
+    EmailSequenceEnrollment::find_by_id(pool, id).await?;
+    let events = EmailSequenceEvent::find_by_enrollment(pool, id).await?;
+    Ok(Json(ApiResponse::success(events)))
+}
+
+/// POST /email/sequences/enrollments/:id/pause - Pause an enrollment
+async fn pause_enrollment(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<EmailSequenceEnrollment>>, ApiError> {
+    let enrollment = EmailSequenceEnrollment::set_paused(&deployment.db().pool, id, true).await?;
+    Ok(Json(ApiResponse::success(enrollment)))
+}
+
+/// POST /email/sequences/enrollments/:id/resume - Resume a paused enrollment
+async fn resume_enrollment(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<EmailSequenceEnrollment>>, ApiError> {
+    let enrollment = EmailSequenceEnrollment::set_paused(&deployment.db().pool, id, false).await?;
+    Ok(Json(ApiResponse::success(enrollment)))
+}
+
+/// POST /email/sequences/enrollments/:id/remove - Take a contact out of the sequence
+async fn remove_enrollment(
+    State(deployment): State<DeploymentImpl>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<EmailSequenceEnrollment>>, ApiError> {
+    let pool = &deployment.db().pool;
+    if !EmailSequenceEnrollment::finish(
+        pool,
+        id,
+        EnrollmentStatus::Removed,
+        Some("removed manually"),
+    )
+    .await?
+    {
+        // Unknown ids are a 404; finished enrollments simply stay as they are
+        EmailSequenceEnrollment::find_by_id(pool, id).await?;
+    }
+    let enrollment = EmailSequenceEnrollment::find_by_id(pool, id).await?;
+    Ok(Json(ApiResponse::success(enrollment)))
+}
+
+/// GET /email/sequences/opens/:event_id.gif - Open-tracking pixel
+async fn track_open(
+    State(deployment): State<DeploymentImpl>,
+    Path(file): Path<String>,
+) -> impl IntoResponse {
+    // Always answer with the image; a broken pixel shows up in the recipient's client
+    if let Some(event_id) = file
+        .strip_suffix(".gif")
+        .and_then(|id| Uuid::parse_str(id).ok())
+        && let Err(e) = EmailSequenceService::new(deployment.db().pool.clone())
+            .record_open(event_id)
+            .await
+    {
+        warn!("Failed to record sequence open {}: {}", event_id, e);
+    }
+
+    (
+        [
+            (header::CONTENT_TYPE, "image/gif"),
+            (header::CACHE_CONTROL, "no-store, max-age=0"),
+        ],
+        TRACKING_PIXEL,
+    )
+}
+
+pub fn router(_deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route(
+            "/email/sequences",
+            get(list_sequences).post(create_sequence),
+        )
+        .route(
+            "/email/sequences/{id}",
+            get(get_sequence)
+                .patch(update_sequence)
+                .delete(delete_sequence),
+        )
+        .route("/email/sequences/{id}/steps", put(replace_steps))
+        .route("/email/sequences/{id}/enroll", post(enroll_contacts))
+        .route("/email/sequences/{id}/enrollments", get(list_enrollments))
+        .route("/email/sequences/{id}/stats", get(get_sequence_stats))
+        .route(
+            "/email/sequences/enrollments/{id}/events",
+            get(list_enrollment_events),
+        )
+        .route(
+            "/email/sequences/enrollments/{id}/pause",
+            post(pause_enrollment),
+        )
+        .route(
+            "/email/sequences/enrollments/{id}/resume",
+            post(resume_enrollment),
+        )
+        .route(
+            "/email/sequences/enrollments/{id}/remove",
+            post(remove_enrollment),
+        )
+}
+
+/// Routes that mail clients reach without credentials
+pub fn tracking_router() -> Router<DeploymentImpl> {
+    Router::new().route("/email/sequences/opens/{file}", get(track_open))
+}
@@ -14,7 +14,7 @@ use db::models::execution_process_logs::ExecutionProcessLogs;
 use deployment::Deployment;
 use futures_util::{SinkExt, StreamExt, TryStreamExt};
 use serde::Deserialize;
-use services::services::container::ContainerService;
+use services::services::{container::ContainerService, log_archive::load_execution_logs};
 use utils::{log_msg::LogMsg, response::ApiResponse};
 use uuid::Uuid;
 
@@ -55,11 +55,7 @@ pub async fn get_stored_logs(
     Extension(execution_process): Extension<ExecutionProcess>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<Option<ExecutionProcessLogs>>>, ApiError> {
-    let logs = ExecutionProcessLogs::find_by_execution_id(
-        &deployment.db().pool,
-        execution_process.id,
-    )
-    .await?;
+    let logs = load_execution_logs(&deployment.db().pool, execution_process.id).await?;
 
     Ok(ResponseJson(ApiResponse::success(logs)))
 }
@@ -0,0 +1,78 @@
+use axum::{
+    Router,
+    extract::{Query, State},
+    response::Json as ResponseJson,
+    routing::get,
+};
+use deployment::Deployment;
+use serde::{Deserialize, Serialize};
+use services::services::{
+    forge::{ForgeCredentials, ForgeType},
+    github_service::RepositoryInfo,
+};
+use ts_rs::TS;
+use utils::response::ApiResponse;
+
+use crate::{DeploymentImpl, error::ApiError};
+
+/// A configured forge instance, without its token
+#[derive(Debug, Serialize, TS)]
+pub struct ForgeInstanceSummary {
+    pub forge: ForgeType,
+    pub host: String,
+    pub base_url: String,
+    pub has_token: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ForgeRepositoriesQuery {
+    pub forge: ForgeType,
+    /// Defaults to the public host for the forge (github.com, gitlab.com, codeberg.org)
+    pub host: Option<String>,
+    pub page: Option<u32>,
+}
+
+/// GET /api/forges
+pub async fn list_forges(
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<Vec<ForgeInstanceSummary>>>, ApiError> {
+    let config = deployment.config().read().await;
+    let mut forges = vec![ForgeInstanceSummary {
+        forge: ForgeType::Github,
+        host: "github.com".to_string(),
+        base_url: "https://github.com".to_string(),
+        has_token: config.github.token().is_some(),
+    }];
+    forges.extend(config.forges.iter().map(|instance| ForgeInstanceSummary {
+        forge: instance.forge,
+        host: instance.host.clone(),
+        base_url: instance.base_url(),
+        has_token: instance.token.as_deref().is_some_and(|t| !t.is_empty()),
+    }));
+    Ok(ResponseJson(ApiResponse::success(forges)))
+}
+
+/// GET /api/forges/repositories?forge=gitlab&host=gitlab.example.com&page=1
+pub async fn list_forge_repositories(
+    State(deployment): State<DeploymentImpl>,
+    Query(query): Query<ForgeRepositoriesQuery>,
+) -> Result<ResponseJson<ApiResponse<Vec<RepositoryInfo>>>, ApiError> {
+    let config = deployment.config().read().await.clone();
+    let host = query.host.unwrap_or_else(|| {
+        match query.forge {
+            ForgeType::Github => "github.com",
+            ForgeType::Gitlab => "gitlab.com",
+            ForgeType::Gitea => "codeberg.org",
+        }
+        .to_string()
+    });
+    let provider = ForgeCredentials::for_host(query.forge, &host, &config)?.provider()?;
+    let repositories = provider.list_repositories(query.page.unwrap_or(1)).await?;
+    Ok(ResponseJson(ApiResponse::success(repositories)))
+}
+
+pub fn router(_deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route("/forges", get(list_forges))
+        .route("/forges/repositories", get(list_forge_repositories))
+}
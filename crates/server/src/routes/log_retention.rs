@@ -0,0 +1,168 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query, State},
+    response::Json as ResponseJson,
+    routing::{get, post},
+};
+use db::models::execution_log_archive::{
+    DEFAULT_ARCHIVE_AFTER_HOURS, DEFAULT_DELETE_AFTER_DAYS, LogRetentionPolicy, LogVacuumRun,
+    UpsertLogRetentionPolicy,
+};
+use deployment::Deployment;
+use serde::{Deserialize, Serialize};
+use services::services::log_archive::LogArchiveService;
+use ts_rs::TS;
+use utils::response::ApiResponse;
+use uuid::Uuid;
+
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, ProjectRole},
+};
+
+#[derive(Debug, Serialize, TS)]
+pub struct EffectiveLogRetentionPolicy {
+    pub project_id: Uuid,
+    pub archive_after_hours: i64,
+    pub delete_after_days: Option<i64>,
+    pub enabled: bool,
+    /// False when the project has no policy row and built-in defaults apply
+    pub is_custom: bool,
+}
+
+#[derive(Debug, Serialize, TS)]
+pub struct VacuumReport {
+    #[serde(flatten)]
+    pub run: LogVacuumRun,
+    pub db_bytes_reclaimed: i64,
+}
+
+impl From<LogVacuumRun> for VacuumReport {
+    fn from(run: LogVacuumRun) -> Self {
+        Self {
+            db_bytes_reclaimed: run.db_bytes_reclaimed(),
+            run,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VacuumRunsQuery {
+    pub limit: Option<i64>,
+}
+
+/// GET /api/projects/:project_id/log-retention
+pub async fn get_project_policy(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+) -> Result<ResponseJson<ApiResponse<EffectiveLogRetentionPolicy>>, ApiError> {
+    let pool = &deployment.db().pool;
+    access_context
+        .check_project_access(pool, &project_id.to_string(), ProjectRole::Viewer)
+        .await?;
+
+    let effective = match LogRetentionPolicy::find_by_project(pool, project_id).await? {
+        Some(policy) => EffectiveLogRetentionPolicy {
+            project_id,
+            archive_after_hours: policy.archive_after_hours,
+            delete_after_days: policy.delete_after_days,
+            enabled: policy.enabled,
+            is_custom: true,
+        },
+        None => EffectiveLogRetentionPolicy {
+            project_id,
+            archive_after_hours: DEFAULT_ARCHIVE_AFTER_HOURS,
+            delete_after_days: DEFAULT_DELETE_AFTER_DAYS,
+            enabled: true,
+            is_custom: false,
+        },
+    };
+
+    Ok(ResponseJson(ApiResponse::success(effective)))
+}
+
+/// PUT /api/projects/:project_id/log-retention
+pub async fn upsert_project_policy(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+    Json(payload): Json<UpsertLogRetentionPolicy>,
+) -> Result<ResponseJson<ApiResponse<LogRetentionPolicy>>, ApiError> {
+    let pool = &deployment.db().pool;
+    access_context
+        .check_project_access(pool, &project_id.to_string(), ProjectRole::Admin)
+        .await?;
+
+    if payload.archive_after_hours < 0 {
+        return Err(ApiError::BadRequest(
+            "archive_after_hours must not be negative".to_string(),
+        ));
+    }
+    if matches!(payload.delete_after_days, Some(days) if days <= 0) {
+        return Err(ApiError::BadRequest(
+            "delete_after_days must be positive".to_string(),
+        ));
+    }
+
+    let policy = LogRetentionPolicy::upsert(pool, project_id, &payload).await?;
+    Ok(ResponseJson(ApiResponse::success(policy)))
+}
+
+/// DELETE /api/projects/:project_id/log-retention - Revert to the defaults
+pub async fn delete_project_policy(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Path(project_id): Path<Uuid>,
+) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
+    let pool = &deployment.db().pool;
+    access_context
+        .check_project_access(pool, &project_id.to_string(), ProjectRole::Admin)
+        .await?;
+
+    LogRetentionPolicy::delete(pool, project_id).await?;
+    Ok(ResponseJson(ApiResponse::success(())))
+}
+
+/// POST /api/log-retention/vacuum - Run retention now and VACUUM the database
+pub async fn run_vacuum(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<VacuumReport>>, ApiError> {
+    access_context.require_admin()?;
+
+    let run = LogArchiveService::new(deployment.db().pool.clone())
+        .run_retention(true)
+        .await?;
+    Ok(ResponseJson(ApiResponse::success(run.into())))
+}
+
+/// GET /api/log-retention/runs - Recent retention/vacuum runs
+pub async fn list_vacuum_runs(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Query(query): Query<VacuumRunsQuery>,
+) -> Result<ResponseJson<ApiResponse<Vec<VacuumReport>>>, ApiError> {
+    access_context.require_admin()?;
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 200);
+    let runs = LogVacuumRun::find_recent(&deployment.db().pool, limit)
+        .await?
+        .into_iter()
+        .map(VacuumReport::from)
+        .collect();
+    Ok(ResponseJson(ApiResponse::success(runs)))
+}
+
+pub fn router(_deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route(
+            "/projects/{project_id}/log-retention",
+            get(get_project_policy)
+                .put(upsert_project_policy)
+                .delete(delete_project_policy),
+        )
+        .route("/log-retention/vacuum", post(run_vacuum))
+        .route("/log-retention/runs", get(list_vacuum_runs))
+}
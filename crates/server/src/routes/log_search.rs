@@ -0,0 +1,101 @@
+use axum::{
+    Extension, Router,
+    extract::{Path, Query, State},
+    response::Json as ResponseJson,
+    routing::{get, post},
+};
+use db::models::{
+    execution_process::ExecutionProcessError,
+    log_search::{LogSearchContext, LogSearchEntry, LogSearchHit, LogSearchQuery},
+};
+use deployment::Deployment;
+use executors::logs::utils::patch::extract_normalized_entry_from_patch;
+use futures_util::StreamExt;
+use serde::Serialize;
+use services::services::container::ContainerService;
+use ts_rs::TS;
+use utils::{log_msg::LogMsg, response::ApiResponse};
+use uuid::Uuid;
+
+use crate::{
+    DeploymentImpl,
+    error::ApiError,
+    middleware::{AccessContext, ProjectRole},
+};
+
+#[derive(Debug, Serialize, TS)]
+pub struct ReindexResult {
+    pub execution_id: Uuid,
+    pub entries_indexed: usize,
+}
+
+/// GET /api/search/logs - Full-text search over execution logs and agent conversations
+pub async fn search_logs(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Query(query): Query<LogSearchQuery>,
+) -> Result<ResponseJson<ApiResponse<Vec<LogSearchHit>>>, ApiError> {
+    let pool = &deployment.db().pool;
+
+    // Non-admins may only search inside a project they can read
+    match query.project_id {
+        Some(project_id) => {
+            access_context
+                .check_project_access(pool, &project_id.to_string(), ProjectRole::Viewer)
+                .await?;
+        }
+        None => access_context.require_admin().map_err(|_| {
+            ApiError::BadRequest("project_id is required to search logs".to_string())
+        })?,
+    }
+
+    let hits = LogSearchEntry::search(pool, &query).await?;
+    Ok(ResponseJson(ApiResponse::success(hits)))
+}
+
+/// POST /api/search/logs/reindex/:execution_id - Rebuild the index for one execution
+/// from its stored logs (used to backfill executions that predate the index)
+pub async fn reindex_execution(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Path(execution_id): Path<Uuid>,
+) -> Result<ResponseJson<ApiResponse<ReindexResult>>, ApiError> {
+    let pool = &deployment.db().pool;
+
+    let ctx = LogSearchContext::for_execution(pool, execution_id)
+        .await?
+        .ok_or(ExecutionProcessError::ExecutionProcessNotFound)?;
+    access_context
+        .check_project_access(pool, &ctx.project_id.to_string(), ProjectRole::Editor)
+        .await?;
+
+    let mut stream = deployment
+        .container()
+        .stream_normalized_logs(&execution_id)
+        .await
+        .ok_or_else(|| ApiError::NotFound("No logs stored for execution".to_string()))?;
+
+    let mut entries = Vec::new();
+    while let Some(Ok(msg)) = stream.next().await {
+        match msg {
+            LogMsg::JsonPatch(patch) => {
+                entries.extend(extract_normalized_entry_from_patch(&patch));
+            }
+            LogMsg::Finished => break,
+            _ => {}
+        }
+    }
+    let entries_indexed = LogSearchEntry::reindex_execution(pool, &ctx, &entries).await?;
+
+    Ok(ResponseJson(ApiResponse::success(ReindexResult {
+        execution_id,
+        entries_indexed,
+    })))
+}
+
+pub fn router(_deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new().route("/search/logs", get(search_logs)).route(
+        "/search/logs/reindex/{execution_id}",
+        post(reindex_execution),
+    )
+}
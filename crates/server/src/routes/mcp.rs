@@ -0,0 +1,275 @@
+use axum::{
+    Extension, Json, Router,
+    extract::{Path, Query, Request, State},
+    response::{Json as ResponseJson, Response},
+    routing::{any, delete, get},
+};
+use chrono::{DateTime, Utc};
+use db::models::mcp_access::{CreateMcpAccessToken, McpAccessToken, McpAuditEntry, McpScope};
+use deployment::Deployment;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use ts_rs::TS;
+use utils::response::ApiResponse;
+use uuid::Uuid;
+
+use crate::{DeploymentImpl, error::ApiError, mcp::http, middleware::AccessContext};
+
+#[derive(Debug, Serialize, TS)]
+pub struct CreatedMcpAccessToken {
+    #[serde(flatten)]
+    pub token: McpAccessToken,
+    /// Plaintext bearer token; shown only in this response
+    pub secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub limit: Option<i64>,
+    /// Admins may look at another user's calls; everyone else sees their own
+    pub user_id: Option<Uuid>,
+    pub all_users: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTokenBody {
+    pub name: String,
+    pub scopes: Option<Vec<McpScope>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// GET /api/mcp/tokens
+pub async fn list_tokens(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+) -> Result<ResponseJson<ApiResponse<Vec<McpAccessToken>>>, ApiError> {
+    let tokens =
+        McpAccessToken::find_by_user(&deployment.db().pool, access_context.user_id).await?;
+    Ok(ResponseJson(ApiResponse::success(tokens)))
+}
+
+/// Check a token request against the caller's role
+fn token_request(
+    access_context: &AccessContext,
+    body: CreateTokenBody,
+) -> Result<CreateMcpAccessToken, ApiError> {
+    access_context.require_active()?;
+    if body.name.trim().is_empty() {
+        return Err(ApiError::BadRequest("Token name is required".to_string()));
+    }
+    let scopes = body.scopes.unwrap_or_else(|| vec![McpScope::Tasks]);
+    if scopes.is_empty() {
+        return Err(ApiError::BadRequest(
+            "At least one scope is required".to_string(),
+        ));
+    }
+    if scopes.contains(&McpScope::Nora) && !access_context.is_admin {
+        return Err(ApiError::Forbidden(
+            "Only admins can create tokens for the Nora MCP server".to_string(),
+        ));
+    }
+    if body.expires_at.is_some_and(|at| at <= Utc::now()) {
+        return Err(ApiError::BadRequest(
+            "Expiry must be in the future".to_string(),
+        ));
+    }
+
+    Ok(CreateMcpAccessToken {
+        name: body.name.trim().to_string(),
+        scopes,
+        expires_at: body.expires_at,
+    })
+}
+
+/// POST /api/mcp/tokens
+pub async fn create_token(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Json(body): Json<CreateTokenBody>,
+) -> Result<ResponseJson<ApiResponse<CreatedMcpAccessToken>>, ApiError> {
+    let data = token_request(&access_context, body)?;
+    let (token, secret) =
+        McpAccessToken::create(&deployment.db().pool, access_context.user_id, &data).await?;
+    tracing::info!(
+        "[MCP] User {} created access token {} ({})",
+        access_context.user_id,
+        token.id,
+        token.name
+    );
+
+    Ok(ResponseJson(ApiResponse::success(CreatedMcpAccessToken {
+        token,
+        secret,
+    })))
+}
+
+/// DELETE /api/mcp/tokens/:token_id
+pub async fn revoke_token(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Path(token_id): Path<Uuid>,
+) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
+    revoke_own_token(&deployment.db().pool, &access_context, token_id).await?;
+    Ok(ResponseJson(ApiResponse::success(())))
+}
+
+/// Revoke one of the caller's tokens; anyone else's reads as not found
+async fn revoke_own_token(
+    pool: &SqlitePool,
+    access_context: &AccessContext,
+    token_id: Uuid,
+) -> Result<(), ApiError> {
+    if !McpAccessToken::revoke(pool, token_id, access_context.user_id).await? {
+        return Err(ApiError::NotFound("MCP token not found".to_string()));
+    }
+    http::forget_token(token_id);
+    Ok(())
+}
+
+/// GET /api/mcp/audit
+pub async fn list_audit(
+    Extension(access_context): Extension<AccessContext>,
+    State(deployment): State<DeploymentImpl>,
+    Query(query): Query<AuditQuery>,
+) -> Result<ResponseJson<ApiResponse<Vec<McpAuditEntry>>>, ApiError> {
+    let user_id = if query.all_users.unwrap_or(false) {
+        access_context.require_admin()?;
+        None
+    } else {
+        match query.user_id {
+            Some(user_id) if user_id != access_context.user_id => {
+                access_context.require_admin()?;
+                Some(user_id)
+            }
+            _ => Some(access_context.user_id),
+        }
+    };
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+    let entries = McpAuditEntry::find_recent(&deployment.db().pool, user_id, limit).await?;
+    Ok(ResponseJson(ApiResponse::success(entries)))
+}
+
+/// /api/mcp/tasks — streamable HTTP transport for the task server
+async fn serve_tasks(State(deployment): State<DeploymentImpl>, request: Request) -> Response {
+    http::serve(deployment, McpScope::Tasks, request).await
+}
+
+/// /api/mcp/nora — streamable HTTP transport for the Nora server
+async fn serve_nora(State(deployment): State<DeploymentImpl>, request: Request) -> Response {
+    http::serve(deployment, McpScope::Nora, request).await
+}
+
+/// Token management, behind the session auth middleware
+pub fn router(_deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route("/mcp/tokens", get(list_tokens).post(create_token))
+        .route("/mcp/tokens/{token_id}", delete(revoke_token))
+        .route("/mcp/audit", get(list_audit))
+}
+
+/// MCP endpoints; these authenticate with MCP tokens rather than sessions
+pub fn transport_router(_deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
+    Router::new()
+        .route("/mcp/tasks", any(serve_tasks))
+        .route("/mcp/nora", any(serve_nora))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use db::models::mcp_access::McpTokenAuth;
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+    use super::*;
+
+    async fn setup_pool() -> SqlitePool {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .expect("invalid sqlite config")
+            .foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .expect("failed to open sqlite memory db");
+        sqlx::migrate!("../db/migrations")
+            .run(&pool)
+            .await
+            .expect("failed to run migrations");
+        pool
+    }
+
+    async fn create_user(pool: &SqlitePool, is_admin: bool) -> AccessContext {
+        let user_id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO users (id, username, email, password_hash, full_name, is_admin) VALUES (?, ?, ?, '', 'Test User', ?)",
+        )
+        .bind(user_id)
+        .bind(format!("user-{}", user_id))
+        .bind(format!("{}@example.com", user_id))
+        .bind(is_admin)
+        .execute(pool)
+        .await
+        .expect("failed to create user");
+        AccessContext {
+            user_id,
+            is_admin,
+            is_active: true,
+        }
+    }
+
+    fn body(scopes: Vec<McpScope>) -> CreateTokenBody {
+        CreateTokenBody {
+            name: "ide".to_string(),
+            scopes: Some(scopes),
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn only_admins_get_nora_tokens() {
+        let member = AccessContext {
+            user_id: Uuid::new_v4(),
+            is_admin: false,
+            is_active: true,
+        };
+        assert!(matches!(
+            token_request(&member, body(vec![McpScope::Tasks, McpScope::Nora])),
+            Err(ApiError::Forbidden(_))
+        ));
+        assert!(token_request(&member, body(vec![McpScope::Tasks])).is_ok());
+
+        let admin = AccessContext {
+            is_admin: true,
+            ..member
+        };
+        let data = token_request(&admin, body(vec![McpScope::Nora])).unwrap();
+        assert_eq!(data.scopes, vec![McpScope::Nora]);
+    }
+
+    #[tokio::test]
+    async fn users_cannot_revoke_each_others_tokens() {
+        let pool = setup_pool().await;
+        let owner = create_user(&pool, false).await;
+        let admin = create_user(&pool, true).await;
+        let data = token_request(&owner, body(vec![McpScope::Tasks])).unwrap();
+        let (token, secret) = McpAccessToken::create(&pool, owner.user_id, &data)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            revoke_own_token(&pool, &admin, token.id).await,
+            Err(ApiError::NotFound(_))
+        ));
+        assert!(matches!(
+            McpAccessToken::authenticate(&pool, &secret).await.unwrap(),
+            McpTokenAuth::Valid(_)
+        ));
+
+        revoke_own_token(&pool, &owner, token.id).await.unwrap();
+        assert!(matches!(
+            McpAccessToken::authenticate(&pool, &secret).await.unwrap(),
+            McpTokenAuth::Rejected { .. }
+        ));
+    }
+}
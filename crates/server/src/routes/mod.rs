@@ -23,7 +23,9 @@ pub mod collaboration;
 pub mod comments;
 pub mod config;
 pub mod containers;
+pub mod editron;
 pub mod filesystem;
+pub mod forges;
 // pub mod github;
 pub mod agent_chat;
 pub mod agent_wallets;
@@ -34,6 +36,9 @@ pub mod execution_summaries;
 pub mod frontend;
 pub mod health;
 pub mod images;
+pub mod log_retention;
+pub mod log_search;
+pub mod mcp;
 pub mod mission_control;
 pub mod nora;
 pub mod permissions;
@@ -58,6 +63,8 @@ pub mod social_accounts;
 pub mod social_posts;
 pub mod social_inbox;
 pub mod email_accounts;
+pub mod email_messages;
+pub mod email_sequences;
 pub mod crm_contacts;
 pub mod onboarding;
 pub mod multiplayer;
@@ -97,6 +104,8 @@ pub fn router(deployment: DeploymentImpl) -> IntoMakeService<Router> {
         .merge(social_posts::router(&deployment))
         .merge(social_inbox::router(&deployment))
         .merge(email_accounts::router(&deployment))
+        .merge(email_messages::router(&deployment))
+        .merge(email_sequences::router(&deployment))
         .merge(crm_contacts::router(&deployment))
         .merge(dropbox::router())
         .merge(agents::routes())
@@ -106,7 +115,11 @@ pub fn router(deployment: DeploymentImpl) -> IntoMakeService<Router> {
         .merge(projects::router(&deployment))
         .merge(tasks::router(&deployment))
         .merge(task_attempts::router(&deployment))
+        .merge(forges::router(&deployment))
         .merge(task_templates::router(&deployment))
+        .merge(log_search::router(&deployment))
+        .merge(log_retention::router(&deployment))
+        .merge(mcp::router(&deployment))
         .merge(approvals::router())
         .merge(agent_wallets::router(&deployment))
         .nest("/permissions", permissions::router(&deployment))
@@ -132,6 +145,7 @@ pub fn router(deployment: DeploymentImpl) -> IntoMakeService<Router> {
         .merge(nora::nora_routes())
         .merge(cinematics::router(&deployment))
         .merge(twilio::twilio_routes())
+        .merge(email_sequences::tracking_router())
         .merge(activity::router())
         .merge(aptos::router(&deployment))
         .merge(webhooks::router())
@@ -154,8 +168,10 @@ pub fn router(deployment: DeploymentImpl) -> IntoMakeService<Router> {
         .merge(model_pricing::router(&deployment))
         .merge(topsi::topsi_routes())
         .merge(mesh::router(&deployment))
+        .merge(editron::router(&deployment))
         .merge(peer_rewards::router(&deployment))
         .merge(pythia::router(&deployment))
+        .merge(mcp::transport_router(&deployment))
         .merge(protected_routes)
         .merge(admin_routes)
         .with_state(deployment);
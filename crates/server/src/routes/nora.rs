@@ -31,6 +31,7 @@ use nora::{
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use services::services::mcp_client::{McpServerStatus, McpToolInfo};
 use tokio::sync::{broadcast, RwLock};
 use ts_rs::TS;
 use uuid::Uuid;
@@ -324,6 +325,8 @@ pub fn nora_routes() -> Router<DeploymentImpl> {
         .route("/nora/voice/conversations/{session_id}", get(get_session_conversation))
         .route("/nora/tools/execute", post(execute_executive_tool))
         .route("/nora/tools/available", get(get_available_tools))
+        .route("/nora/mcp/servers", get(get_mcp_servers))
+        .route("/nora/mcp/reload", post(reload_mcp_servers))
         .route("/nora/context/sync", post(sync_live_context_handler))
         .route("/nora/modes", get(list_modes_handler))
         .route("/nora/modes/apply", post(apply_mode_handler))
@@ -498,6 +501,14 @@ pub struct AvailableToolsResponse {
     pub categories: Vec<String>,
 }
 
+/// External MCP servers and the tools discovered on them
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServersResponse {
+    pub servers: Vec<McpServerStatus>,
+    pub tools: Vec<McpToolInfo>,
+}
+
 /// Directives sent to specific agents from the global console
 #[derive(Debug, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
@@ -1619,6 +1630,42 @@ pub async fn get_available_tools(
     Ok(Json(AvailableToolsResponse { tools, categories }))
 }
 
+/// List configured MCP servers with their connection state and tools
+pub async fn get_mcp_servers(
+    State(_state): State<DeploymentImpl>,
+) -> Result<Json<McpServersResponse>, ApiError> {
+    let nora_instance = get_nora_instance().await?;
+    let instance = nora_instance.read().await;
+    let nora = instance
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Nora not initialized".to_string()))?;
+
+    Ok(Json(McpServersResponse {
+        servers: nora.mcp_client.statuses().await,
+        tools: nora.mcp_client.tools().await,
+    }))
+}
+
+/// Re-read `mcp_servers.json`, reconnect and re-register the discovered tools
+pub async fn reload_mcp_servers(
+    State(_state): State<DeploymentImpl>,
+) -> Result<Json<McpServersResponse>, ApiError> {
+    let nora_instance = get_nora_instance().await?;
+    let instance = nora_instance.read().await;
+    let nora = instance
+        .as_ref()
+        .ok_or_else(|| ApiError::NotFound("Nora not initialized".to_string()))?;
+
+    nora.reload_mcp_servers()
+        .await
+        .map_err(|e| ApiError::BadRequest(format!("Failed to reload MCP servers: {}", e)))?;
+
+    Ok(Json(McpServersResponse {
+        servers: nora.mcp_client.statuses().await,
+        tools: nora.mcp_client.tools().await,
+    }))
+}
+
 /// Get coordination statistics
 pub async fn get_coordination_stats(
     State(_state): State<DeploymentImpl>,
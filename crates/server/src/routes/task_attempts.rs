@@ -6,7 +6,7 @@ use axum::{
         Query, State,
         ws::{WebSocket, WebSocketUpgrade},
     },
-    http::StatusCode,
+    http::{StatusCode, header},
     middleware::from_fn_with_state,
     response::{
         IntoResponse, Json as ResponseJson, Sse,
@@ -40,9 +40,11 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use services::services::{
     container::ContainerService,
+    forge::{ForgeCredentials, ForgeError, ForgeType},
     git::ConflictOp,
-    github_service::{CreatePrRequest, GitHubService, GitHubServiceError},
+    github_service::{CreatePrRequest, GitHubServiceError},
     image::ImageService,
+    session_export::{ExportFormat, SessionTranscript},
 };
 use sqlx::Error as SqlxError;
 use ts_rs::TS;
@@ -194,114 +196,23 @@ pub async fn follow_up(
 ) -> Result<ResponseJson<ApiResponse<ExecutionProcess>>, ApiError> {
     tracing::info!("{:?}", task_attempt);
 
-    // Ensure worktree exists (recreate if needed for cold task support)
-    deployment
-        .container()
-        .ensure_container_exists(&task_attempt)
-        .await?;
-
-    // Get latest session id (ignoring dropped)
-    let session_id = ExecutionProcess::find_latest_session_id_by_task_attempt(
-        &deployment.db().pool,
-        task_attempt.id,
-    )
-    .await?
-    .ok_or(ApiError::TaskAttempt(TaskAttemptError::ValidationError(
-        "Couldn't find a prior session_id, please create a new task attempt".to_string(),
-    )))?;
-
-    // Get ExecutionProcess for profile data
-    let latest_execution_process = ExecutionProcess::find_latest_by_task_attempt_and_run_reason(
-        &deployment.db().pool,
-        task_attempt.id,
-        &ExecutionProcessRunReason::CodingAgent,
+    let prompt = follow_up_prompt(
+        &deployment,
+        &task_attempt,
+        payload.prompt,
+        payload.image_ids.as_deref(),
     )
-    .await?
-    .ok_or(ApiError::TaskAttempt(TaskAttemptError::ValidationError(
-        "Couldn't find initial coding agent process, has it run yet?".to_string(),
-    )))?;
-    let initial_executor_profile_id = match &latest_execution_process
-        .executor_action()
-        .map_err(|e| ApiError::TaskAttempt(TaskAttemptError::ValidationError(e.to_string())))?
-        .typ
-    {
-        ExecutorActionType::CodingAgentInitialRequest(request) => {
-            Ok(request.executor_profile_id.clone())
-        }
-        ExecutorActionType::CodingAgentFollowUpRequest(request) => {
-            Ok(request.executor_profile_id.clone())
-        }
-        _ => Err(ApiError::TaskAttempt(TaskAttemptError::ValidationError(
-            "Couldn't find profile from initial request".to_string(),
-        ))),
-    }?;
-
-    let executor_profile_id = ExecutorProfileId {
-        executor: initial_executor_profile_id.executor,
-        variant: payload.variant,
-    };
-
-    // Get parent task
-    let task = task_attempt
-        .parent_task(&deployment.db().pool)
-        .await?
-        .ok_or(SqlxError::RowNotFound)?;
-
-    // Get parent project
-    let project = task
-        .parent_project(&deployment.db().pool)
-        .await?
-        .ok_or(SqlxError::RowNotFound)?;
-
-    let mut prompt = payload.prompt;
-    if let Some(image_ids) = &payload.image_ids {
-        TaskImage::associate_many_dedup(&deployment.db().pool, task.id, image_ids).await?;
-
-        // Copy new images from the image cache to the worktree
-        if let Some(container_ref) = &task_attempt.container_ref {
-            let worktree_path = std::path::PathBuf::from(container_ref);
-            deployment
-                .image()
-                .copy_images_by_ids_to_worktree(&worktree_path, image_ids)
-                .await?;
-
-            // Update image paths in prompt with full worktree path
-            prompt = ImageService::canonicalise_image_paths(&prompt, &worktree_path);
-        }
-    }
-
-    let cleanup_action = project.cleanup_script.map(|script| {
-        Box::new(ExecutorAction::new(
-            ExecutorActionType::ScriptRequest(ScriptRequest {
-                script,
-                language: ScriptRequestLanguage::Bash,
-                context: ScriptContext::CleanupScript,
-            }),
-            None,
-        ))
-    });
-
-    let follow_up_request = CodingAgentFollowUpRequest {
-        prompt,
-        session_id,
-        executor_profile_id: executor_profile_id.clone(),
-    };
-
-    let follow_up_action = ExecutorAction::new(
-        ExecutorActionType::CodingAgentFollowUpRequest(follow_up_request),
-        cleanup_action,
-    );
-
+    .await?;
     let execution_process = deployment
         .container()
-        .start_execution(
-            &task_attempt,
-            &follow_up_action,
-            &ExecutionProcessRunReason::CodingAgent,
-        )
+        .start_follow_up(&task_attempt, prompt, payload.variant)
         .await?;
 
-    publish_execution_events(&executor_profile_id, &task_attempt).await;
+    if let Ok(ExecutorActionType::CodingAgentFollowUpRequest(request)) =
+        execution_process.executor_action().map(|action| action.typ())
+    {
+        publish_execution_events(&request.executor_profile_id, &task_attempt).await;
+    }
 
     // Clear any persisted follow-up draft for this attempt to avoid stale UI after manual send
     let _ = FollowUpDraft::clear_after_send(&deployment.db().pool, task_attempt.id).await;
@@ -700,108 +611,16 @@ async fn start_follow_up_from_draft(
     task_attempt: &TaskAttempt,
     draft: &FollowUpDraft,
 ) -> Result<ExecutionProcess, ApiError> {
-    // Ensure worktree exists
-    deployment
-        .container()
-        .ensure_container_exists(task_attempt)
-        .await?;
-
-    // Get latest session id (ignoring dropped)
-    let session_id = ExecutionProcess::find_latest_session_id_by_task_attempt(
-        &deployment.db().pool,
-        task_attempt.id,
-    )
-    .await?
-    .ok_or(ApiError::TaskAttempt(TaskAttemptError::ValidationError(
-        "Couldn't find a prior session_id, please create a new task attempt".to_string(),
-    )))?;
-
-    // Get latest coding agent process to inherit executor profile
-    let latest_execution_process = ExecutionProcess::find_latest_by_task_attempt_and_run_reason(
-        &deployment.db().pool,
-        task_attempt.id,
-        &ExecutionProcessRunReason::CodingAgent,
+    let prompt = follow_up_prompt(
+        deployment,
+        task_attempt,
+        draft.prompt.clone(),
+        draft.image_ids.as_deref(),
     )
-    .await?
-    .ok_or(ApiError::TaskAttempt(TaskAttemptError::ValidationError(
-        "Couldn't find initial coding agent process, has it run yet?".to_string(),
-    )))?;
-    let initial_executor_profile_id = match &latest_execution_process
-        .executor_action()
-        .map_err(|e| ApiError::TaskAttempt(TaskAttemptError::ValidationError(e.to_string())))?
-        .typ
-    {
-        ExecutorActionType::CodingAgentInitialRequest(request) => {
-            Ok(request.executor_profile_id.clone())
-        }
-        ExecutorActionType::CodingAgentFollowUpRequest(request) => {
-            Ok(request.executor_profile_id.clone())
-        }
-        _ => Err(ApiError::TaskAttempt(TaskAttemptError::ValidationError(
-            "Couldn't find profile from initial request".to_string(),
-        ))),
-    }?;
-
-    // Inherit executor profile; override variant if provided in draft
-    let executor_profile_id = ExecutorProfileId {
-        executor: initial_executor_profile_id.executor,
-        variant: draft.variant.clone(),
-    };
-
-    // Get parent task -> project and cleanup action
-    let task = task_attempt
-        .parent_task(&deployment.db().pool)
-        .await?
-        .ok_or(SqlxError::RowNotFound)?;
-    let project = task
-        .parent_project(&deployment.db().pool)
-        .await?
-        .ok_or(SqlxError::RowNotFound)?;
-
-    let cleanup_action = project.cleanup_script.map(|script| {
-        Box::new(ExecutorAction::new(
-            ExecutorActionType::ScriptRequest(ScriptRequest {
-                script,
-                language: ScriptRequestLanguage::Bash,
-                context: ScriptContext::CleanupScript,
-            }),
-            None,
-        ))
-    });
-
-    // Handle images: associate to task, copy to worktree, and canonicalize paths in prompt
-    let mut prompt = draft.prompt.clone();
-    if let Some(image_ids) = &draft.image_ids {
-        TaskImage::associate_many_dedup(&deployment.db().pool, task_attempt.task_id, image_ids)
-            .await?;
-        if let Some(container_ref) = &task_attempt.container_ref {
-            let worktree_path = std::path::PathBuf::from(container_ref);
-            deployment
-                .image()
-                .copy_images_by_ids_to_worktree(&worktree_path, image_ids)
-                .await?;
-            prompt = ImageService::canonicalise_image_paths(&prompt, &worktree_path);
-        }
-    }
-
-    let follow_up_request = CodingAgentFollowUpRequest {
-        prompt,
-        session_id,
-        executor_profile_id,
-    };
-
-    let follow_up_action = ExecutorAction::new(
-        ExecutorActionType::CodingAgentFollowUpRequest(follow_up_request),
-        cleanup_action,
-    );
-
+    .await?;
     let execution_process = deployment
         .container()
-        .start_execution(
-            task_attempt,
-            &follow_up_action,
-            &ExecutionProcessRunReason::CodingAgent,
-        )
+        .start_follow_up(task_attempt, prompt, draft.variant.clone())
         .await?;
 
     // Best-effort: clear the draft after scheduling the execution
@@ -810,6 +629,33 @@ async fn start_follow_up_from_draft(
     Ok(execution_process)
 }
 
+/// Attach `image_ids` to the attempt's task and copy them into its worktree,
+/// pointing the prompt's image paths at the copies
+async fn follow_up_prompt(
+    deployment: &DeploymentImpl,
+    task_attempt: &TaskAttempt,
+    prompt: String,
+    image_ids: Option<&[Uuid]>,
+) -> Result<String, ApiError> {
+    let Some(image_ids) = image_ids else {
+        return Ok(prompt);
+    };
+    TaskImage::associate_many_dedup(&deployment.db().pool, task_attempt.task_id, image_ids)
+        .await?;
+
+    // Ensure worktree exists (recreate if needed for cold task support)
+    let container_ref = deployment
+        .container()
+        .ensure_container_exists(task_attempt)
+        .await?;
+    let worktree_path = std::path::PathBuf::from(container_ref);
+    deployment
+        .image()
+        .copy_images_by_ids_to_worktree(&worktree_path, image_ids)
+        .await?;
+    Ok(ImageService::canonicalise_image_paths(&prompt, &worktree_path))
+}
+
 #[axum::debug_handler]
 pub async fn replace_process(
     Extension(task_attempt): Extension<TaskAttempt>,
@@ -981,6 +827,39 @@ pub async fn get_task_attempt_diff(
     Ok(Sse::new(stream.map_err(|e| -> BoxError { e.into() })).keep_alive(KeepAlive::default()))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// markdown (default), html or jsonl
+    pub format: Option<String>,
+}
+
+/// GET /api/task-attempts/:id/export - Download the attempt's transcript and diff
+pub async fn export_task_attempt(
+    Extension(task_attempt): Extension<TaskAttempt>,
+    State(deployment): State<DeploymentImpl>,
+    Query(query): Query<ExportQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    let format = match query.format.as_deref() {
+        Some(format) => format.parse::<ExportFormat>()?,
+        None => ExportFormat::Markdown,
+    };
+
+    let transcript = SessionTranscript::collect(deployment.container(), &task_attempt).await?;
+    let body = transcript.render(format)?;
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        transcript.file_name(format)
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    ))
+}
+
 #[derive(Debug, Serialize, TS)]
 pub struct CommitInfo {
     pub sha: String,
@@ -1123,13 +1002,27 @@ pub async fn push_task_attempt_branch(
     Extension(task_attempt): Extension<TaskAttempt>,
     State(deployment): State<DeploymentImpl>,
 ) -> Result<ResponseJson<ApiResponse<()>>, ApiError> {
-    let github_config = deployment.config().read().await.github.clone();
-    let Some(github_token) = github_config.token() else {
-        return Err(GitHubServiceError::TokenInvalid.into());
-    };
+    let config = deployment.config().read().await.clone();
+    let pool = &deployment.db().pool;
+    let task = task_attempt
+        .parent_task(pool)
+        .await?
+        .ok_or(ApiError::TaskAttempt(TaskAttemptError::TaskNotFound))?;
+    let project = Project::find_by_id(pool, task.project_id)
+        .await?
+        .ok_or(ApiError::Project(ProjectError::ProjectNotFound))?;
 
-    let github_service = GitHubService::new(&github_token)?;
-    github_service.check_token().await?;
+    let repo_info = deployment
+        .git()
+        .get_forge_repo_info(&project.git_repo_path, &config.forges)?;
+    let credentials = match ForgeCredentials::resolve(&repo_info, &config) {
+        Ok(credentials) => credentials,
+        Err(_) if repo_info.forge == ForgeType::Github => {
+            return Err(GitHubServiceError::TokenInvalid.into());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    credentials.provider()?.check_token().await?;
 
     let branch_name = task_attempt.branch.as_ref().ok_or_else(|| {
         ApiError::TaskAttempt(TaskAttemptError::ValidationError(
@@ -1145,7 +1038,7 @@ pub async fn push_task_attempt_branch(
 
     deployment
         .git()
-        .push_to_github(&ws_path, branch_name, &github_token)?;
+        .push_to_github(&ws_path, branch_name, &credentials.token)?;
     Ok(ResponseJson(ApiResponse::success(())))
 }
 
@@ -1154,14 +1047,32 @@ pub async fn create_github_pr(
     State(deployment): State<DeploymentImpl>,
     Json(request): Json<CreateGitHubPrRequest>,
 ) -> Result<ResponseJson<ApiResponse<String, GitHubServiceError>>, ApiError> {
-    let github_config = deployment.config().read().await.github.clone();
-    let Some(github_token) = github_config.token() else {
-        return Ok(ResponseJson(ApiResponse::error_with_data(
-            GitHubServiceError::TokenInvalid,
-        )));
+    let config = deployment.config().read().await.clone();
+
+    let pool = &deployment.db().pool;
+    let task = task_attempt
+        .parent_task(pool)
+        .await?
+        .ok_or(ApiError::TaskAttempt(TaskAttemptError::TaskNotFound))?;
+    let project = Project::find_by_id(pool, task.project_id)
+        .await?
+        .ok_or(ApiError::Project(ProjectError::ProjectNotFound))?;
+
+    // Work out which forge the project's remote lives on (GitHub, GitLab, Gitea)
+    let repo_info = deployment
+        .git()
+        .get_forge_repo_info(&project.git_repo_path, &config.forges)?;
+    let credentials = match ForgeCredentials::resolve(&repo_info, &config) {
+        Ok(credentials) => credentials,
+        Err(_) if repo_info.forge == ForgeType::Github => {
+            return Ok(ResponseJson(ApiResponse::error_with_data(
+                GitHubServiceError::TokenInvalid,
+            )));
+        }
+        Err(e) => return Ok(ResponseJson(ApiResponse::error(&e.to_string()))),
     };
-    // Create GitHub service instance
-    let github_service = GitHubService::new(&github_token)?;
+    let forge = credentials.provider()?;
+
     // Get the task attempt to access the stored base branch
     let base_branch = request.base_branch.unwrap_or_else(|| {
         // Use the stored base branch from the task attempt as the default
@@ -1169,22 +1080,14 @@ pub async fn create_github_pr(
         if !task_attempt.base_branch.trim().is_empty() {
             task_attempt.base_branch.clone()
         } else {
-            github_config
+            config
+                .github
                 .default_pr_base
                 .as_ref()
                 .map_or_else(|| "main".to_string(), |b| b.to_string())
         }
     });
 
-    let pool = &deployment.db().pool;
-    let task = task_attempt
-        .parent_task(pool)
-        .await?
-        .ok_or(ApiError::TaskAttempt(TaskAttemptError::TaskNotFound))?;
-    let project = Project::find_by_id(pool, task.project_id)
-        .await?
-        .ok_or(ApiError::Project(ProjectError::ProjectNotFound))?;
-
     // Get branch name from task attempt
     let branch_name = task_attempt.branch.as_ref().ok_or_else(|| {
         ApiError::TaskAttempt(TaskAttemptError::ValidationError(
@@ -1198,18 +1101,18 @@ pub async fn create_github_pr(
             .await?,
     );
 
-    // Push the branch to GitHub first
+    // Push the branch to the forge first
     if let Err(e) = deployment
         .git()
-        .push_to_github(&workspace_path, branch_name, &github_token)
+        .push_to_github(&workspace_path, branch_name, &credentials.token)
     {
-        tracing::error!("Failed to push branch to GitHub: {}", e);
+        tracing::error!("Failed to push branch to {}: {}", repo_info.forge, e);
         let gh_e = GitHubServiceError::from(e);
-        if gh_e.is_api_data() {
+        if gh_e.is_api_data() && repo_info.forge == ForgeType::Github {
             return Ok(ResponseJson(ApiResponse::error_with_data(gh_e)));
         } else {
             return Ok(ResponseJson(ApiResponse::error(
-                format!("Failed to push branch to GitHub: {}", gh_e).as_str(),
+                format!("Failed to push branch to {}: {}", repo_info.forge, gh_e).as_str(),
             )));
         }
     }
@@ -1233,19 +1136,15 @@ pub async fn create_github_pr(
     } else {
         base_branch
     };
-    // Create the PR using GitHub service
+    // Create the PR/MR on the forge
     let pr_request = CreatePrRequest {
         title: request.title.clone(),
         body: request.body.clone(),
         head_branch: branch_name.clone(),
         base_branch: norm_base_branch_name.clone(),
     };
-    // Use GitService to get the remote URL, then create GitHubRepoInfo
-    let repo_info = deployment
-        .git()
-        .get_github_repo_info(&project.git_repo_path)?;
 
-    match github_service.create_pr(&repo_info, &pr_request).await {
+    match forge.create_pr(&repo_info, &pr_request).await {
         Ok(pr_info) => {
             // Update the task attempt with PR information
             if let Err(e) = Merge::create_pr(
@@ -1254,6 +1153,7 @@ pub async fn create_github_pr(
                 &norm_base_branch_name,
                 pr_info.number,
                 &pr_info.url,
+                repo_info.forge,
             )
             .await
             {
@@ -1271,6 +1171,7 @@ pub async fn create_github_pr(
                         "task_id": task.id.to_string(),
                         "project_id": project.id.to_string(),
                         "attempt_id": task_attempt.id.to_string(),
+                        "forge": repo_info.forge.as_str(),
                     }),
                 )
                 .await;
@@ -1279,16 +1180,18 @@ pub async fn create_github_pr(
         }
         Err(e) => {
             tracing::error!(
-                "Failed to create GitHub PR for attempt {}: {}",
+                "Failed to create {} PR for attempt {}: {}",
+                repo_info.forge,
                 task_attempt.id,
                 e
             );
-            if e.is_api_data() {
-                Ok(ResponseJson(ApiResponse::error_with_data(e)))
-            } else {
-                Ok(ResponseJson(ApiResponse::error(
+            match e {
+                ForgeError::GitHub(gh_e) if gh_e.is_api_data() => {
+                    Ok(ResponseJson(ApiResponse::error_with_data(gh_e)))
+                }
+                e => Ok(ResponseJson(ApiResponse::error(
                     format!("Failed to create PR: {}", e).as_str(),
-                )))
+                ))),
             }
         }
     }
@@ -1740,6 +1643,7 @@ pub fn router(deployment: &DeploymentImpl) -> Router<DeploymentImpl> {
         .route("/start-dev-server", post(start_dev_server))
         .route("/branch-status", get(get_task_attempt_branch_status))
         .route("/diff", get(get_task_attempt_diff))
+        .route("/export", get(export_task_attempt))
         .route("/merge", post(merge_task_attempt))
         .route("/push", post(push_task_attempt_branch))
         .route("/rebase", post(rebase_task_attempt))
@@ -4,26 +4,30 @@
 //! enabling users to interact with NORA by calling a phone number.
 //! Now uses NORA's voice engine for TTS instead of Twilio's Polly.
 
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
 use axum::{
     Form, Router,
+    body::Bytes,
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, post},
 };
 use chrono::Utc;
+use deployment::Deployment;
 use nora::{
     agent::{NoraRequest, NoraRequestType, RequestPriority},
     twilio::{
-        TwilioCallHandler, TwilioCallRequest, TwilioConfig, TwilioSpeechResult,
+        InboundSms, PostCallProcessor, TwilioCallHandler, TwilioCallRequest, TwilioConfig,
+        TwilioRecordingCallback, TwilioSmsClient, TwilioSmsRequest, TwilioSpeechResult,
         TwilioStatusCallback, TwimlBuilder, get_audio_cache,
     },
     voice::AudioFormat,
 };
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use serde_json::json;
 use tokio::time::timeout;
 use tracing::{error, info, warn};
@@ -35,8 +39,15 @@ use crate::{DeploymentImpl, routes::nora::get_nora_instance};
 static TWILIO_HANDLER: tokio::sync::OnceCell<Arc<TwilioCallHandler>> =
     tokio::sync::OnceCell::const_new();
 
+/// Global Twilio SMS client
+static SMS_CLIENT: tokio::sync::OnceCell<Arc<TwilioSmsClient>> = tokio::sync::OnceCell::const_new();
+
+/// How long to wait for Twilio's recording callback before summarising a
+/// call from its live transcript instead
+const RECORDING_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
 /// Get or initialize the Twilio call handler
-async fn get_twilio_handler() -> Option<Arc<TwilioCallHandler>> {
+async fn get_twilio_handler(deployment: &DeploymentImpl) -> Option<Arc<TwilioCallHandler>> {
     if let Some(handler) = TWILIO_HANDLER.get() {
         return Some(handler.clone());
     }
@@ -44,9 +55,24 @@ async fn get_twilio_handler() -> Option<Arc<TwilioCallHandler>> {
     // Try to initialize from environment
     if let Some(config) = TwilioConfig::from_env() {
         if config.is_configured() {
-            let handler = Arc::new(TwilioCallHandler::new(config));
+            let handler =
+                Arc::new(TwilioCallHandler::new(config).with_pool(deployment.db().pool.clone()));
             if TWILIO_HANDLER.set(handler.clone()).is_ok() {
                 info!("Twilio handler initialized successfully with NORA voice engine");
+
+                // Finish off calls left pending by a previous run
+                let catch_up = handler.clone();
+                tokio::spawn(async move {
+                    if let Some(processor) = post_call_processor(&catch_up).await {
+                        let grace = chrono::Duration::from_std(RECORDING_GRACE_PERIOD)
+                            .unwrap_or_else(|_| chrono::Duration::minutes(10));
+                        match processor.process_stale(grace).await {
+                            Ok(0) => {}
+                            Ok(count) => info!("Processed {} pending calls", count),
+                            Err(e) => warn!("Failed to process pending calls: {}", e),
+                        }
+                    }
+                });
                 return Some(handler);
             }
         }
@@ -56,6 +82,17 @@ async fn get_twilio_handler() -> Option<Arc<TwilioCallHandler>> {
     None
 }
 
+/// Get or initialize the Twilio SMS client
+async fn get_sms_client(deployment: &DeploymentImpl) -> Option<Arc<TwilioSmsClient>> {
+    if let Some(client) = SMS_CLIENT.get() {
+        return Some(client.clone());
+    }
+
+    let client = TwilioSmsClient::from_env()?.with_pool(deployment.db().pool.clone());
+    let client = SMS_CLIENT.get_or_init(|| async { Arc::new(client) }).await;
+    Some(client.clone())
+}
+
 /// Initialize Twilio routes
 pub fn twilio_routes() -> Router<DeploymentImpl> {
     Router::new()
@@ -63,6 +100,8 @@ pub fn twilio_routes() -> Router<DeploymentImpl> {
         .route("/twilio/speech", post(handle_speech_input))
         .route("/twilio/audio/{audio_id}", get(serve_audio))
         .route("/twilio/status", post(handle_status_callback))
+        .route("/twilio/recording", post(handle_recording_callback))
+        .route("/twilio/sms", post(handle_incoming_sms))
         .route("/twilio/fallback", post(handle_fallback))
         .route("/twilio/health", get(twilio_health))
 }
@@ -176,7 +215,7 @@ pub async fn serve_audio(
 /// Twilio calls this endpoint when someone calls the configured phone number.
 /// Returns TwiML that greets the caller using NORA's voice and starts listening for speech.
 pub async fn handle_incoming_call(
-    State(_state): State<DeploymentImpl>,
+    State(state): State<DeploymentImpl>,
     Form(request): Form<TwilioCallRequest>,
 ) -> impl IntoResponse {
     info!(
@@ -184,7 +223,7 @@ pub async fn handle_incoming_call(
         request.call_sid, request.from
     );
 
-    let handler = match get_twilio_handler().await {
+    let handler = match get_twilio_handler(&state).await {
         Some(h) => h,
         None => {
             error!("Twilio not configured - rejecting call");
@@ -244,7 +283,7 @@ pub async fn handle_incoming_call(
 /// Twilio calls this endpoint after the caller speaks.
 /// Processes the speech through NORA and returns a TwiML response with NORA's voice.
 pub async fn handle_speech_input(
-    State(_state): State<DeploymentImpl>,
+    State(state): State<DeploymentImpl>,
     Query(params): Query<SpeechQueryParams>,
     Form(speech_result): Form<TwilioSpeechResult>,
 ) -> impl IntoResponse {
@@ -258,7 +297,7 @@ pub async fn handle_speech_input(
         call_sid, speech_result.speech_result
     );
 
-    let handler = match get_twilio_handler().await {
+    let handler = match get_twilio_handler(&state).await {
         Some(h) => h,
         None => {
             let twiml = TwimlBuilder::new()
@@ -412,21 +451,39 @@ pub async fn handle_speech_input(
 ///
 /// Twilio calls this endpoint when the call status changes.
 pub async fn handle_status_callback(
-    State(_state): State<DeploymentImpl>,
-    Form(status): Form<TwilioStatusCallback>,
+    State(state): State<DeploymentImpl>,
+    headers: HeaderMap,
+    body: Bytes,
 ) -> impl IntoResponse {
+    let Some(handler) = get_twilio_handler(&state).await else {
+        return StatusCode::OK;
+    };
+    // A completed call starts post-call processing, so only Twilio may say so
+    let status: TwilioStatusCallback =
+        match verified_webhook(&handler, &headers, "/api/twilio/status", &body) {
+            Ok(status) => status,
+            Err(code) => return code,
+        };
     info!(
         "Call status update: {} -> {}",
         status.call_sid, status.call_status
     );
 
-    if let Some(handler) = get_twilio_handler().await {
-        if let Err(e) = handler
-            .handle_status_update(&status.call_sid, &status.call_status, status.call_duration)
-            .await
-        {
-            warn!("Error handling status update: {}", e);
-        }
+    if let Err(e) = handler
+        .handle_status_update(&status.call_sid, &status.call_status, status.call_duration)
+        .await
+    {
+        warn!("Error handling status update: {}", e);
+    }
+
+    if status.call_status == "completed" {
+        // A recorded call is summarised when its recording arrives; the
+        // delayed run only does anything if that callback never comes
+        let delay = match handler.get_call_state(&status.call_sid).await {
+            Some(call) if call.recording_started => RECORDING_GRACE_PERIOD,
+            _ => Duration::ZERO,
+        };
+        spawn_post_call(handler, status.call_sid.clone(), delay);
     }
 
     // Clean up cached audio for completed calls
@@ -443,11 +500,162 @@ pub async fn handle_status_callback(
     StatusCode::OK
 }
 
+/// Handle recording status callback from Twilio
+///
+/// POST /api/twilio/recording
+///
+/// Twilio calls this endpoint once a call recording is ready, which kicks off
+/// transcription, the summary and follow-up tasks.
+pub async fn handle_recording_callback(
+    State(state): State<DeploymentImpl>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Some(handler) = get_twilio_handler(&state).await else {
+        return StatusCode::OK;
+    };
+    let recording: TwilioRecordingCallback =
+        match verified_webhook(&handler, &headers, "/api/twilio/recording", &body) {
+            Ok(recording) => recording,
+            Err(code) => return code,
+        };
+
+    match handler.handle_recording(&recording).await {
+        Ok(_) => spawn_post_call(handler, recording.call_sid, Duration::ZERO),
+        Err(e) => warn!(
+            "Error storing recording for call {}: {}",
+            recording.call_sid, e
+        ),
+    }
+
+    StatusCode::OK
+}
+
+/// Parse a Twilio webhook, accepting it only when `X-Twilio-Signature` shows
+/// it was signed with our auth token for `path`
+fn verified_webhook<T: DeserializeOwned>(
+    handler: &TwilioCallHandler,
+    headers: &HeaderMap,
+    path: &str,
+    body: &[u8],
+) -> Result<T, StatusCode> {
+    let params: HashMap<String, String> =
+        serde_urlencoded::from_bytes(body).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let signature = headers
+        .get("X-Twilio-Signature")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let url = format!("{}{}", handler.config().webhook_base_url, path);
+    if !handler.validate_request_signature(signature, &url, &params) {
+        warn!(
+            "Rejected Twilio webhook to {} with an invalid signature",
+            path
+        );
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    serde_urlencoded::from_bytes(body).map_err(|e| {
+        warn!("Malformed Twilio webhook to {}: {}", path, e);
+        StatusCode::BAD_REQUEST
+    })
+}
+
+/// Build a post-call processor using NORA's speech-to-text and LLM when they
+/// are available
+async fn post_call_processor(handler: &TwilioCallHandler) -> Option<PostCallProcessor> {
+    let pool = handler.pool()?.clone();
+    let mut processor = PostCallProcessor::new(pool, handler.config().clone());
+
+    if let Ok(nora_instance) = get_nora_instance().await {
+        if let Some(nora) = nora_instance.read().await.as_ref() {
+            processor = processor.with_voice_engine(nora.voice_engine.clone());
+            if let Some(llm) = &nora.llm {
+                processor = processor.with_llm(llm.clone());
+            }
+        }
+    }
+
+    Some(processor)
+}
+
+/// Transcribe and summarise a finished call in the background
+fn spawn_post_call(handler: Arc<TwilioCallHandler>, call_sid: String, delay: Duration) {
+    tokio::spawn(async move {
+        tokio::time::sleep(delay).await;
+        let Some(processor) = post_call_processor(&handler).await else {
+            return;
+        };
+        if let Err(e) = processor.process(&call_sid).await {
+            error!("Post-call processing failed for {}: {}", call_sid, e);
+        }
+    });
+}
+
+/// Handle incoming text message webhook from Twilio
+///
+/// POST /api/twilio/sms
+///
+/// Twilio calls this endpoint when someone texts the configured number.
+/// Compliance keywords are answered straight away; other texts go to NORA in
+/// a session per phone number and the reply is sent once NORA has answered.
+pub async fn handle_incoming_sms(
+    State(state): State<DeploymentImpl>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let empty = TwimlBuilder::new().build();
+
+    let (Some(handler), Some(client)) = (
+        get_twilio_handler(&state).await,
+        get_sms_client(&state).await,
+    ) else {
+        warn!("Text received but Twilio is not configured");
+        return (StatusCode::OK, [("Content-Type", "application/xml")], empty);
+    };
+
+    // Only accept requests signed with our auth token
+    let request: TwilioSmsRequest =
+        match verified_webhook(&handler, &headers, "/api/twilio/sms", &body) {
+            Ok(request) => request,
+            Err(code) => return (code, [("Content-Type", "application/xml")], empty),
+        };
+
+    let twiml = match client.receive(&request).await {
+        Ok(InboundSms::Keyword { reply, .. }) => TwimlBuilder::new().message(&reply).build(),
+        Ok(InboundSms::Conversation {
+            message,
+            session_id,
+            history,
+        }) => {
+            tokio::spawn(async move {
+                let reply = match process_sms_with_nora(&message.body, &session_id, history).await {
+                    Ok(reply) => reply,
+                    Err(e) => {
+                        error!("Error processing text with NORA: {}", e);
+                        return;
+                    }
+                };
+                if let Err(e) = client.reply(&message, &reply).await {
+                    warn!("Failed to reply to {}: {}", message.from_number, e);
+                }
+            });
+            empty
+        }
+        Ok(InboundSms::Duplicate | InboundSms::OptedOut | InboundSms::RateLimited(_)) => empty,
+        Err(e) => {
+            error!("Error handling text {}: {}", request.message_sid, e);
+            empty
+        }
+    };
+
+    (StatusCode::OK, [("Content-Type", "application/xml")], twiml)
+}
+
 /// Handle fallback webhook (called on errors)
 ///
 /// POST /api/twilio/fallback
 pub async fn handle_fallback(
-    State(_state): State<DeploymentImpl>,
+    State(state): State<DeploymentImpl>,
     Form(request): Form<TwilioCallRequest>,
 ) -> impl IntoResponse {
     error!("Twilio fallback triggered for call: {}", request.call_sid);
@@ -458,7 +666,7 @@ pub async fn handle_fallback(
     let twiml = match generate_and_cache_audio(error_message, Some(request.call_sid.clone())).await
     {
         Ok(audio_id) => {
-            if let Some(handler) = get_twilio_handler().await {
+            if let Some(handler) = get_twilio_handler(&state).await {
                 let audio_url = build_audio_url(&handler.config().webhook_base_url, &audio_id);
                 TwimlBuilder::new()
                     .play(&audio_url, 1)
@@ -486,9 +694,9 @@ pub async fn handle_fallback(
 /// Twilio health check endpoint
 ///
 /// GET /api/twilio/health
-pub async fn twilio_health(State(_state): State<DeploymentImpl>) -> impl IntoResponse {
+pub async fn twilio_health(State(state): State<DeploymentImpl>) -> impl IntoResponse {
     let (configured, active_calls, phone_number, using_nora_voice) =
-        if let Some(handler) = get_twilio_handler().await {
+        if let Some(handler) = get_twilio_handler(&state).await {
             let calls = handler.get_active_calls().await;
             let phone = if handler.is_configured() {
                 Some(handler.config().phone_number.clone())
@@ -570,6 +778,49 @@ async fn process_with_nora(
     Ok(response.content)
 }
 
+/// Maximum time for NORA to answer a text; texts have no caller waiting on
+/// the line, so this is far more generous than for calls
+const SMS_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Process an incoming text with NORA
+async fn process_sms_with_nora(
+    text: &str,
+    session_id: &str,
+    history: String,
+) -> Result<String, String> {
+    let nora_instance = get_nora_instance()
+        .await
+        .map_err(|e| format!("NORA not available: {}", e))?;
+
+    let instance = nora_instance.read().await;
+    let nora = instance
+        .as_ref()
+        .ok_or_else(|| "NORA not initialized".to_string())?;
+
+    let sms_context = json!({
+        "source": "sms",
+        "instruction": "This is a text message conversation. Reply in plain text with no markdown, ideally under 320 characters. Be warm and concise. Use British English.",
+        "conversation_history": history
+    });
+
+    let request = NoraRequest {
+        request_id: Uuid::new_v4().to_string(),
+        session_id: session_id.to_string(),
+        request_type: NoraRequestType::TextInteraction,
+        content: text.to_string(),
+        context: Some(sms_context),
+        voice_enabled: false,
+        priority: RequestPriority::Normal,
+        timestamp: Utc::now(),
+    };
+
+    match timeout(SMS_TIMEOUT, nora.process_request(request)).await {
+        Ok(Ok(response)) => Ok(response.content),
+        Ok(Err(e)) => Err(format!("NORA processing error: {}", e)),
+        Err(_) => Err(format!("NORA timeout after {:?}", SMS_TIMEOUT)),
+    }
+}
+
 /// Safely truncate a string for logging (UTF-8 aware)
 fn truncate_for_log(text: &str, max_chars: usize) -> String {
     let char_count = text.chars().count();
@@ -597,6 +848,41 @@ mod tests {
         assert!(twiml.contains("<Hangup/>"));
     }
 
+    #[test]
+    fn webhooks_must_be_signed_for_their_path() {
+        let handler = TwilioCallHandler::new(TwilioConfig {
+            account_sid: "AC123".to_string(),
+            auth_token: "secret".to_string(),
+            webhook_base_url: "https://nora.example.com".to_string(),
+            ..Default::default()
+        });
+        let body = b"CallSid=CA1&CallStatus=completed&CallDuration=42";
+        let params: HashMap<String, String> = serde_urlencoded::from_bytes(body).unwrap();
+        let signed = |url: &str| {
+            let mut headers = HeaderMap::new();
+            let signature = handler.request_signature(url, &params);
+            headers.insert("X-Twilio-Signature", signature.parse().unwrap());
+            headers
+        };
+
+        let headers = signed("https://nora.example.com/api/twilio/status");
+        let status: TwilioStatusCallback =
+            verified_webhook(&handler, &headers, "/api/twilio/status", body).unwrap();
+        assert_eq!(status.call_sid, "CA1");
+        assert_eq!(status.call_duration, Some(42));
+
+        let forbidden = |headers: &HeaderMap, path: &str| {
+            matches!(
+                verified_webhook::<TwilioStatusCallback>(&handler, headers, path, body),
+                Err(StatusCode::FORBIDDEN)
+            )
+        };
+        assert!(forbidden(&HeaderMap::new(), "/api/twilio/status"));
+        assert!(forbidden(&headers, "/api/twilio/recording"));
+        let forged = signed("https://attacker.example.com/api/twilio/status");
+        assert!(forbidden(&forged, "/api/twilio/status"));
+    }
+
     #[test]
     fn test_audio_url_generation() {
         let url = build_audio_url("https://example.com", "abc123");
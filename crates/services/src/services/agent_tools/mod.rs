@@ -86,6 +86,10 @@ pub enum ToolCategory {
     BrowserAutomation,
     FileManagement,
     Communication,
+
+    // External
+    /// Tools discovered on configured MCP servers; granted per tool, never by category
+    McpServer,
 }
 
 /// Agent identifier
@@ -2,10 +2,12 @@
 //!
 //! Central registry for managing agent tool access, permissions, and execution.
 
-use super::{AgentId, ToolCategory, ToolConfig};
+use super::{AgentId, ToolCategory, ToolConfig, ToolResult};
+use crate::services::mcp_client::{McpClientManager, MCP_TOOL_PREFIX};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::RwLock;
 
 /// Tool access permission
@@ -225,6 +227,86 @@ impl AgentToolRegistry {
         let configs = self.tool_configs.read().await;
         configs.get(tool_name).cloned()
     }
+
+    /// Register the tools of every connected MCP server, replacing any
+    /// previously registered MCP tools, and grant the configured agents access
+    pub async fn register_mcp_tools(&self, client: &McpClientManager) -> usize {
+        let tools = client.tools().await;
+        let mut definitions = self.tool_definitions.write().await;
+        let mut agent_tools = self.agent_tools.write().await;
+
+        definitions.retain(|_, def| def.category != ToolCategory::McpServer);
+        for tool_set in agent_tools.values_mut() {
+            tool_set
+                .tools
+                .retain(|name, _| !name.starts_with(MCP_TOOL_PREFIX));
+        }
+
+        for tool in &tools {
+            definitions.insert(
+                tool.qualified_name.clone(),
+                ToolDefinition {
+                    name: tool.qualified_name.clone(),
+                    description: tool.description.clone(),
+                    category: ToolCategory::McpServer,
+                    agent_owner: AgentId::Nora,
+                    parameters: tool.input_schema.clone(),
+                    returns: serde_json::json!({"type": "object"}),
+                    examples: vec![],
+                    estimated_duration_ms: None,
+                    rate_limit: None,
+                },
+            );
+            for agent_id in &tool.agents {
+                agent_tools
+                    .entry(*agent_id)
+                    .or_insert_with(|| AgentToolSet::new(*agent_id))
+                    .tools
+                    .insert(tool.qualified_name.clone(), tool.access);
+            }
+        }
+
+        tools.len()
+    }
+
+    /// Execute an MCP tool on behalf of an agent, enforcing its tool access
+    pub async fn execute_mcp_tool(
+        &self,
+        client: &McpClientManager,
+        agent_id: AgentId,
+        tool_name: &str,
+        arguments: &serde_json::Value,
+    ) -> ToolResult {
+        let start = Instant::now();
+        let result = |success: bool, data: serde_json::Value, error: Option<String>| ToolResult {
+            success,
+            tool_name: tool_name.to_string(),
+            agent_name: format!("{:?}", agent_id),
+            execution_time_ms: start.elapsed().as_millis() as u64,
+            data,
+            error,
+        };
+
+        if !self.can_execute(agent_id, tool_name).await {
+            return result(
+                false,
+                serde_json::Value::Null,
+                Some(format!("{:?} is not allowed to use {}", agent_id, tool_name)),
+            );
+        }
+
+        match client.call_tool(tool_name, arguments).await {
+            Ok(output) => {
+                let data = output
+                    .structured
+                    .clone()
+                    .unwrap_or_else(|| serde_json::Value::String(output.text.clone()));
+                let error = output.is_error.then(|| output.text.clone());
+                result(!output.is_error, data, error)
+            }
+            Err(e) => result(false, serde_json::Value::Null, Some(e.to_string())),
+        }
+    }
 }
 
 impl Default for AgentToolRegistry {
@@ -1377,3 +1459,242 @@ impl AgentToolRegistry {
         self.register_marketing_tools().await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rmcp::{
+        ErrorData, ServerHandler, ServiceExt,
+        handler::server::tool::{Parameters, ToolRouter},
+        model::{
+            CallToolResult, Content, Implementation, ProtocolVersion, ServerCapabilities,
+            ServerInfo,
+        },
+        tool, tool_handler, tool_router,
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::services::mcp_client::McpServerConfig;
+
+    #[derive(Debug, Deserialize, schemars::JsonSchema)]
+    struct EchoRequest {
+        #[schemars(description = "The text to send back")]
+        text: String,
+    }
+
+    /// In-process MCP server with two tools
+    #[derive(Clone)]
+    struct EchoServer {
+        tool_router: ToolRouter<EchoServer>,
+    }
+
+    impl EchoServer {
+        fn new() -> Self {
+            Self {
+                tool_router: Self::tool_router(),
+            }
+        }
+    }
+
+    #[tool_router]
+    impl EchoServer {
+        #[tool(description = "Send the text back")]
+        async fn echo(
+            &self,
+            Parameters(EchoRequest { text }): Parameters<EchoRequest>,
+        ) -> Result<CallToolResult, ErrorData> {
+            Ok(CallToolResult::success(vec![Content::text(text)]))
+        }
+
+        #[tool(description = "Send the text back in capitals")]
+        async fn shout(
+            &self,
+            Parameters(EchoRequest { text }): Parameters<EchoRequest>,
+        ) -> Result<CallToolResult, ErrorData> {
+            Ok(CallToolResult::success(vec![Content::text(
+                text.to_uppercase(),
+            )]))
+        }
+    }
+
+    #[tool_handler]
+    impl ServerHandler for EchoServer {
+        fn get_info(&self) -> ServerInfo {
+            ServerInfo {
+                protocol_version: ProtocolVersion::V_2025_03_26,
+                capabilities: ServerCapabilities::builder().enable_tools().build(),
+                server_info: Implementation {
+                    name: "echo".to_string(),
+                    version: "1.0.0".to_string(),
+                },
+                instructions: None,
+            }
+        }
+    }
+
+    /// Connect `client` to a fresh `EchoServer` under the given server config
+    async fn connect(client: &McpClientManager, mut config: serde_json::Value) {
+        config["transport"] = json!({ "type": "stdio", "command": "in-process" });
+        let config: McpServerConfig = serde_json::from_value(config).unwrap();
+        let (client_io, server_io) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            if let Ok(server) = EchoServer::new().serve(server_io).await {
+                let _ = server.waiting().await;
+            }
+        });
+        client.connect_transport(config, client_io).await.unwrap();
+    }
+
+    async fn registry() -> AgentToolRegistry {
+        let registry = AgentToolRegistry::new();
+        registry.initialize_defaults().await;
+        registry
+    }
+
+    async fn openai_names(registry: &AgentToolRegistry, agent_id: AgentId) -> Vec<String> {
+        let mut names: Vec<String> = registry
+            .get_openai_tools(agent_id)
+            .await
+            .iter()
+            .map(|tool| tool["function"]["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    async fn anthropic_names(registry: &AgentToolRegistry, agent_id: AgentId) -> Vec<String> {
+        let mut names: Vec<String> = registry
+            .get_anthropic_tools(agent_id)
+            .await
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn mcp_tools_are_listed_only_for_agents_that_may_execute_them() {
+        let client = McpClientManager::default();
+        connect(
+            &client,
+            json!({ "name": "echo", "agents": ["Nora"], "access": "Full" }),
+        )
+        .await;
+        connect(
+            &client,
+            json!({ "name": "notes", "agents": ["Scribe"], "access": "Execute" }),
+        )
+        .await;
+        connect(
+            &client,
+            json!({ "name": "vault", "agents": ["Scribe"], "access": "ReadOnly" }),
+        )
+        .await;
+        let registry = registry().await;
+
+        assert_eq!(registry.register_mcp_tools(&client).await, 6);
+
+        let nora = ["mcp__echo__echo", "mcp__echo__shout"];
+        assert_eq!(openai_names(&registry, AgentId::Nora).await, nora);
+        assert_eq!(anthropic_names(&registry, AgentId::Nora).await, nora);
+        let scribe = ["mcp__notes__echo", "mcp__notes__shout"];
+        assert_eq!(openai_names(&registry, AgentId::Scribe).await, scribe);
+        assert_eq!(anthropic_names(&registry, AgentId::Scribe).await, scribe);
+        assert!(openai_names(&registry, AgentId::Compass).await.is_empty());
+        assert!(
+            anthropic_names(&registry, AgentId::Compass)
+                .await
+                .is_empty()
+        );
+        let tool = registry.get_anthropic_tools(AgentId::Nora).await;
+        assert_eq!(
+            tool[0]["input_schema"]["properties"]["text"]["type"],
+            "string"
+        );
+    }
+
+    #[tokio::test]
+    async fn mcp_tools_round_trip_and_refuse_denied_agents() {
+        let client = McpClientManager::default();
+        connect(
+            &client,
+            json!({ "name": "echo", "agents": ["Nora"], "access": "Execute" }),
+        )
+        .await;
+        connect(
+            &client,
+            json!({ "name": "vault", "agents": ["Scribe"], "access": "Denied" }),
+        )
+        .await;
+        let registry = registry().await;
+        registry.register_mcp_tools(&client).await;
+        let arguments = json!({ "text": "hello" });
+
+        let result = registry
+            .execute_mcp_tool(&client, AgentId::Nora, "mcp__echo__shout", &arguments)
+            .await;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.data, json!("HELLO"));
+        assert_eq!(result.tool_name, "mcp__echo__shout");
+
+        for (agent_id, tool_name) in [
+            (AgentId::Scribe, "mcp__vault__echo"),
+            (AgentId::Compass, "mcp__echo__echo"),
+        ] {
+            let result = registry
+                .execute_mcp_tool(&client, agent_id, tool_name, &arguments)
+                .await;
+            assert!(!result.success);
+            assert_eq!(result.data, serde_json::Value::Null);
+            assert_eq!(
+                result.error,
+                Some(format!(
+                    "{:?} is not allowed to use {}",
+                    agent_id, tool_name
+                ))
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn re_registering_drops_stale_mcp_tools() {
+        let registry = registry().await;
+        let client = McpClientManager::default();
+        connect(&client, json!({ "name": "echo", "agents": ["Nora"] })).await;
+        connect(&client, json!({ "name": "notes", "agents": ["Scribe"] })).await;
+        assert_eq!(registry.register_mcp_tools(&client).await, 4);
+
+        // The notes server is gone and echo now only exposes one tool
+        let client = McpClientManager::default();
+        connect(
+            &client,
+            json!({ "name": "echo", "agents": ["Nora"], "tools": ["echo"] }),
+        )
+        .await;
+        assert_eq!(registry.register_mcp_tools(&client).await, 1);
+
+        assert_eq!(
+            openai_names(&registry, AgentId::Nora).await,
+            ["mcp__echo__echo"]
+        );
+        assert!(openai_names(&registry, AgentId::Scribe).await.is_empty());
+        assert!(
+            !registry
+                .can_execute(AgentId::Nora, "mcp__echo__shout")
+                .await
+        );
+        assert!(
+            !registry
+                .can_execute(AgentId::Scribe, "mcp__notes__echo")
+                .await
+        );
+        let agent_tools = registry.agent_tools.read().await;
+        assert!(
+            agent_tools
+                .values()
+                .flat_map(|tool_set| tool_set.tools.keys())
+                .all(|name| name == "mcp__echo__echo")
+        );
+    }
+}
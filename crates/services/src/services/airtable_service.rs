@@ -136,6 +136,15 @@ struct RecordsResponse {
     offset: Option<String>,
 }
 
+/// Filters for listing records
+#[derive(Debug, Clone, Default)]
+pub struct AirtableListQuery {
+    /// Airtable formula a record must satisfy, e.g. `IS_AFTER(LAST_MODIFIED_TIME(), '...')`
+    pub filter_by_formula: Option<String>,
+    /// Only return these fields (all fields when empty)
+    pub fields: Vec<String>,
+}
+
 /// Response for creating/updating a record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
@@ -204,6 +213,25 @@ impl AirtableService {
         format!("Bearer {}", self.token)
     }
 
+    /// Backoff for calls made by the scheduled sync. Airtable asks clients
+    /// that hit the limit to wait 30 seconds, so this waits longer than the
+    /// interactive calls do.
+    fn sync_backoff() -> ExponentialBuilder {
+        ExponentialBuilder::default()
+            .with_min_delay(Duration::from_secs(2))
+            .with_max_delay(Duration::from_secs(30))
+            .with_max_times(5)
+            .with_jitter()
+    }
+
+    fn log_retry(err: &AirtableServiceError, dur: Duration) {
+        tracing::warn!(
+            "Airtable API call failed, retrying after {:.2}s: {}",
+            dur.as_secs_f64(),
+            err
+        );
+    }
+
     /// Verify the API token by fetching user info
     pub async fn verify_credentials(&self) -> Result<AirtableUserInfo, AirtableServiceError> {
         let url = format!("{}/whoami", AIRTABLE_META_API_BASE);
@@ -401,12 +429,104 @@ impl AirtableService {
         Ok(records_response.records)
     }
 
+    /// List every record in a table matching the query, following pagination
+    pub async fn list_records(
+        &self,
+        base_id: &str,
+        table_id: &str,
+        query: &AirtableListQuery,
+    ) -> Result<Vec<AirtableRecord>, AirtableServiceError> {
+        let mut records = Vec::new();
+        let mut offset: Option<String> = None;
+
+        loop {
+            let page = (|| async {
+                self.list_records_page(base_id, table_id, query, offset.as_deref())
+                    .await
+            })
+            .retry(&Self::sync_backoff())
+            .when(|e| e.should_retry())
+            .notify(Self::log_retry)
+            .await?;
+
+            records.extend(page.records);
+            match page.offset {
+                Some(next) => offset = Some(next),
+                None => return Ok(records),
+            }
+        }
+    }
+
+    async fn list_records_page(
+        &self,
+        base_id: &str,
+        table_id: &str,
+        query: &AirtableListQuery,
+        offset: Option<&str>,
+    ) -> Result<RecordsResponse, AirtableServiceError> {
+        let url = format!("{}/{}/{}", AIRTABLE_API_BASE, base_id, table_id);
+
+        let mut params = vec![("pageSize", "100".to_string())];
+        if let Some(formula) = &query.filter_by_formula {
+            params.push(("filterByFormula", formula.clone()));
+        }
+        for field in &query.fields {
+            params.push(("fields[]", field.clone()));
+        }
+        if let Some(offset) = offset {
+            params.push(("offset", offset.to_string()));
+        }
+
+        let response = self
+            .client
+            .get(&url)
+            .header("Authorization", self.auth_header())
+            .query(&params)
+            .send()
+            .await?;
+
+        if response.status() == 404 {
+            return Err(AirtableServiceError::TableNotFound(table_id.to_string()));
+        }
+
+        if response.status() == 401 || response.status() == 403 {
+            return Err(AirtableServiceError::AuthFailed);
+        }
+
+        if response.status() == 429 {
+            return Err(AirtableServiceError::RateLimited);
+        }
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AirtableServiceError::ApiError(error_text));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| AirtableServiceError::ApiError(e.to_string()))
+    }
+
     /// Get a single record by ID
     pub async fn get_record(
         &self,
         base_id: &str,
         table_id: &str,
         record_id: &str,
+    ) -> Result<AirtableRecord, AirtableServiceError> {
+        (|| async { self.get_record_internal(base_id, table_id, record_id).await })
+            .retry(&Self::sync_backoff())
+            .when(|e| e.should_retry())
+            .notify(Self::log_retry)
+            .await
+    }
+
+    async fn get_record_internal(
+        &self,
+        base_id: &str,
+        table_id: &str,
+        record_id: &str,
     ) -> Result<AirtableRecord, AirtableServiceError> {
         let url = format!(
             "{}/{}/{}/{}",
@@ -445,6 +565,24 @@ impl AirtableService {
         base_id: &str,
         table_id: &str,
         fields: serde_json::Value,
+    ) -> Result<AirtableRecord, AirtableServiceError> {
+        // A dropped connection may still have created the record, so only
+        // retry when Airtable turned the request away
+        (|| async {
+            self.create_record_internal(base_id, table_id, fields.clone())
+                .await
+        })
+        .retry(&Self::sync_backoff())
+        .when(|e| matches!(e, AirtableServiceError::RateLimited))
+        .notify(Self::log_retry)
+        .await
+    }
+
+    async fn create_record_internal(
+        &self,
+        base_id: &str,
+        table_id: &str,
+        fields: serde_json::Value,
     ) -> Result<AirtableRecord, AirtableServiceError> {
         let url = format!("{}/{}/{}", AIRTABLE_API_BASE, base_id, table_id);
 
@@ -487,6 +625,23 @@ impl AirtableService {
         table_id: &str,
         record_id: &str,
         fields: serde_json::Value,
+    ) -> Result<AirtableRecord, AirtableServiceError> {
+        (|| async {
+            self.update_record_internal(base_id, table_id, record_id, fields.clone())
+                .await
+        })
+        .retry(&Self::sync_backoff())
+        .when(|e| e.should_retry())
+        .notify(Self::log_retry)
+        .await
+    }
+
+    async fn update_record_internal(
+        &self,
+        base_id: &str,
+        table_id: &str,
+        record_id: &str,
+        fields: serde_json::Value,
     ) -> Result<AirtableRecord, AirtableServiceError> {
         let url = format!(
             "{}/{}/{}/{}",
@@ -577,6 +732,22 @@ impl AirtableService {
         base_id: &str,
         table_id: &str,
         record_id: &str,
+    ) -> Result<(), AirtableServiceError> {
+        (|| async {
+            self.delete_record_internal(base_id, table_id, record_id)
+                .await
+        })
+        .retry(&Self::sync_backoff())
+        .when(|e| e.should_retry())
+        .notify(Self::log_retry)
+        .await
+    }
+
+    async fn delete_record_internal(
+        &self,
+        base_id: &str,
+        table_id: &str,
+        record_id: &str,
     ) -> Result<(), AirtableServiceError> {
         let url = format!(
             "{}/{}/{}/{}",
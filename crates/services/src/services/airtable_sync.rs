@@ -0,0 +1,1204 @@
+//! Scheduled two-way sync between tasks and Airtable tables
+//!
+//! Each run goes through the enabled table mappings:
+//! - Deletes records whose linked tasks were deleted locally
+//! - Pulls records Airtable changed since the table's last pull (by
+//!   last-modified time), merges them into their linked tasks and imports
+//!   records that are not linked yet
+//! - Pushes tasks edited locally since their last sync, and new tasks when
+//!   the mapping asks for it
+//! - Cancels (or deletes) tasks whose records were deleted in Airtable
+//!
+//! Every link keeps the mapped values both sides agreed on at the last sync,
+//! so a field changed on one side is copied to the other. When both sides
+//! changed the same field, the newer edit wins.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use db::models::{
+    airtable_base::AirtableBase,
+    airtable_record_link::{
+        AirtableLinkState, AirtableOrigin, AirtableRecordLink, CreateAirtableRecordLink,
+    },
+    airtable_table_mapping::{AirtableFieldMapping, AirtableTableMapping, AirtableTaskField},
+    task::{CreateTask, Priority, Task, TaskStatus},
+};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use sqlx::{SqlitePool, types::Json};
+use thiserror::Error;
+use tokio::{
+    sync::RwLock,
+    task::JoinHandle,
+    time::{interval, sleep},
+};
+use tracing::{error, info, warn};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::services::{
+    airtable_service::{
+        AirtableListQuery, AirtableRecord, AirtableService, AirtableServiceError, build_record_url,
+    },
+    config::Config,
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Airtable allows five requests per second per base
+const WRITE_SPACING: Duration = Duration::from_millis(250);
+
+/// Re-read a little before the last pull, in case our clock and Airtable's
+/// disagree; records that did not change are left alone
+const PULL_OVERLAP: chrono::Duration = chrono::Duration::seconds(60);
+
+#[derive(Debug, Error)]
+pub enum AirtableSyncError {
+    #[error("Airtable is not configured")]
+    NotConfigured,
+    #[error("Airtable connection not found")]
+    ConnectionNotFound,
+    #[error(transparent)]
+    Airtable(#[from] AirtableServiceError),
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+}
+
+impl AirtableSyncError {
+    /// Failures that will hit every other record of the table too
+    fn aborts_run(&self) -> bool {
+        matches!(
+            self,
+            AirtableSyncError::Database(_)
+                | AirtableSyncError::Airtable(
+                    AirtableServiceError::AuthFailed
+                        | AirtableServiceError::RateLimited
+                        | AirtableServiceError::BaseNotFound(_)
+                        | AirtableServiceError::TableNotFound(_)
+                )
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+pub struct AirtableSyncReport {
+    pub pulled: u32,
+    pub imported: u32,
+    pub pushed: u32,
+    pub created_remote: u32,
+    pub deleted_remote: u32,
+    pub deleted_local: u32,
+    pub cancelled_local: u32,
+    pub failed: u32,
+}
+
+/// Mapped values of one record or task, by Airtable field name. Select
+/// options are held as the local value they map to, so both sides compare
+/// equal when they mean the same thing.
+type Values = Map<String, Value>;
+
+/// What to copy where after comparing a record, its task and the values both
+/// agreed on last time
+#[derive(Debug, Default, PartialEq)]
+struct Reconciliation {
+    to_local: Values,
+    to_remote: Values,
+}
+
+/// Everything a run needs about the table it is syncing
+struct TableSync<'a> {
+    service: AirtableService,
+    connection: &'a AirtableBase,
+    mapping: &'a AirtableTableMapping,
+    fields: Vec<(&'a AirtableFieldMapping, AirtableTaskField)>,
+}
+
+#[derive(Clone)]
+pub struct AirtableSyncService {
+    pool: SqlitePool,
+    config: Arc<RwLock<Config>>,
+}
+
+impl AirtableSyncService {
+    pub fn new(pool: SqlitePool, config: Arc<RwLock<Config>>) -> Self {
+        Self { pool, config }
+    }
+
+    /// Sync every enabled table mapping in the background
+    pub async fn spawn(pool: SqlitePool, config: Arc<RwLock<Config>>) -> JoinHandle<()> {
+        let service = Self::new(pool, config);
+        tokio::spawn(async move {
+            service.start().await;
+        })
+    }
+
+    async fn start(&self) {
+        info!(
+            "Starting Airtable sync service with interval {:?}",
+            SYNC_INTERVAL
+        );
+
+        let mut interval = interval(SYNC_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if !self.config.read().await.airtable.is_configured() {
+                continue;
+            }
+
+            let mappings = match AirtableTableMapping::find_enabled(&self.pool).await {
+                Ok(mappings) => mappings,
+                Err(e) => {
+                    error!("Failed to load Airtable table mappings: {}", e);
+                    continue;
+                }
+            };
+            for mapping in mappings {
+                match self.sync_mapping(&mapping).await {
+                    Ok(report) => info!(
+                        "Airtable sync for table {}: {:?}",
+                        mapping.airtable_table_id, report
+                    ),
+                    Err(e) => warn!(
+                        "Airtable sync for table {} failed: {}",
+                        mapping.airtable_table_id, e
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Sync one table now, recording the outcome on the mapping
+    pub async fn sync_mapping(
+        &self,
+        mapping: &AirtableTableMapping,
+    ) -> Result<AirtableSyncReport, AirtableSyncError> {
+        let connection = AirtableBase::find_by_id(&self.pool, mapping.connection_id)
+            .await?
+            .ok_or(AirtableSyncError::ConnectionNotFound)?;
+        let token = self
+            .config
+            .read()
+            .await
+            .airtable
+            .token()
+            .map(str::to_string)
+            .ok_or(AirtableSyncError::NotConfigured)?;
+
+        let table = TableSync {
+            service: AirtableService::new(&token)?,
+            connection: &connection,
+            mapping,
+            fields: mapping
+                .field_mappings
+                .0
+                .iter()
+                .filter_map(|field| field.target().map(|target| (field, target)))
+                .collect(),
+        };
+
+        let started = Utc::now();
+        match self.run(&table).await {
+            Ok(report) => {
+                AirtableTableMapping::record_sync(&self.pool, mapping.id, Some(started), None)
+                    .await?;
+                AirtableBase::mark_synced(&self.pool, connection.id).await?;
+                Ok(report)
+            }
+            Err(e) => {
+                AirtableTableMapping::record_sync(
+                    &self.pool,
+                    mapping.id,
+                    None,
+                    Some(&e.to_string()),
+                )
+                .await?;
+                Err(e)
+            }
+        }
+    }
+
+    async fn run(&self, table: &TableSync<'_>) -> Result<AirtableSyncReport, AirtableSyncError> {
+        let mut report = AirtableSyncReport::default();
+        let base_id = &table.connection.airtable_base_id;
+        let table_id = &table.mapping.airtable_table_id;
+
+        self.push_local_deletes(table, &mut report).await?;
+
+        let mut links: HashMap<String, AirtableLinkState> =
+            AirtableRecordLink::find_states_by_table(&self.pool, base_id, table_id)
+                .await?
+                .into_iter()
+                .map(|state| (state.link.airtable_record_id.clone(), state))
+                .collect();
+
+        // Pull what changed in Airtable
+        let mut field_names: Vec<String> = table
+            .fields
+            .iter()
+            .map(|(field, _)| field.airtable_field.clone())
+            .collect();
+        field_names.extend(table.mapping.modified_field.clone());
+        let changed = table
+            .service
+            .list_records(
+                base_id,
+                table_id,
+                &AirtableListQuery {
+                    filter_by_formula: table.mapping.last_pulled_at.map(|since| {
+                        changed_since_formula(table.mapping.modified_field.as_deref(), since)
+                    }),
+                    fields: field_names,
+                },
+            )
+            .await?;
+
+        let mut handled = HashSet::new();
+        for record in &changed {
+            let outcome = match links.get(&record.id) {
+                Some(state) => self.merge(table, state, Some(record)).await.map(|pushed| {
+                    report.pulled += 1;
+                    report.pushed += u32::from(pushed);
+                }),
+                None if table.mapping.import_new_records => self
+                    .import(table, record)
+                    .await
+                    .map(|()| report.imported += 1),
+                None => Ok(()),
+            };
+            handled.insert(record.id.clone());
+            self.settle(outcome, links.get(&record.id), &mut report)
+                .await?;
+        }
+
+        // Push tasks edited since their last sync that Airtable left alone
+        for state in links.values() {
+            if handled.contains(&state.link.airtable_record_id)
+                || state
+                    .link
+                    .last_synced_at
+                    .is_some_and(|synced| state.task_updated_at <= synced)
+            {
+                continue;
+            }
+            let outcome = self
+                .merge(table, state, None)
+                .await
+                .map(|pushed| report.pushed += u32::from(pushed));
+            self.settle(outcome, Some(state), &mut report).await?;
+        }
+
+        if table.mapping.push_new_tasks {
+            for task in table.mapping.find_unlinked_tasks(&self.pool).await? {
+                let outcome = self
+                    .create_remote(table, &task)
+                    .await
+                    .map(|()| report.created_remote += 1);
+                self.settle(outcome, None, &mut report).await?;
+            }
+        }
+
+        // Records deleted in Airtable no longer show up in a full listing
+        let remaining: HashSet<String> = table
+            .service
+            .list_records(
+                base_id,
+                table_id,
+                &AirtableListQuery {
+                    filter_by_formula: None,
+                    fields: table
+                        .fields
+                        .first()
+                        .map(|(field, _)| vec![field.airtable_field.clone()])
+                        .unwrap_or_default(),
+                },
+            )
+            .await?
+            .into_iter()
+            .map(|record| record.id)
+            .collect();
+        links.retain(|record_id, _| !remaining.contains(record_id));
+        if remaining.is_empty() && !links.is_empty() {
+            warn!(
+                "Airtable table {} came back empty; not treating {} linked records as deleted",
+                table_id,
+                links.len()
+            );
+        } else {
+            for state in links.values() {
+                self.apply_remote_delete(table, state, &mut report).await?;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Count a record's outcome; failures are kept on the link so the next
+    /// run retries it, unless they would fail the rest of the table too
+    async fn settle(
+        &self,
+        outcome: Result<(), AirtableSyncError>,
+        state: Option<&AirtableLinkState>,
+        report: &mut AirtableSyncReport,
+    ) -> Result<(), AirtableSyncError> {
+        let Err(e) = outcome else {
+            return Ok(());
+        };
+        if e.aborts_run() {
+            return Err(e);
+        }
+        report.failed += 1;
+        match state {
+            Some(state) => {
+                warn!(
+                    "Airtable sync of record {} failed: {}",
+                    state.link.airtable_record_id, e
+                );
+                AirtableRecordLink::record_field_sync(
+                    &self.pool,
+                    state.link.id,
+                    None,
+                    Some(&e.to_string()),
+                )
+                .await?;
+            }
+            None => warn!("Airtable sync failed: {}", e),
+        }
+        Ok(())
+    }
+
+    async fn push_local_deletes(
+        &self,
+        table: &TableSync<'_>,
+        report: &mut AirtableSyncReport,
+    ) -> Result<(), AirtableSyncError> {
+        let deleted = AirtableRecordLink::find_deleted_records(
+            &self.pool,
+            &table.connection.airtable_base_id,
+            &table.mapping.airtable_table_id,
+        )
+        .await?;
+
+        for record in deleted {
+            if table.mapping.delete_remote_records {
+                match table
+                    .service
+                    .delete_record(
+                        &record.airtable_base_id,
+                        &table.mapping.airtable_table_id,
+                        &record.airtable_record_id,
+                    )
+                    .await
+                {
+                    Ok(()) => report.deleted_remote += 1,
+                    Err(AirtableServiceError::RecordNotFound(_)) => {}
+                    Err(e) => {
+                        let e = AirtableSyncError::from(e);
+                        self.settle(Err(e), None, report).await?;
+                        continue;
+                    }
+                }
+                sleep(WRITE_SPACING).await;
+            }
+            AirtableRecordLink::clear_deleted_record(&self.pool, record.id).await?;
+        }
+        Ok(())
+    }
+
+    /// Merge a linked record with its task. Without a record, Airtable is
+    /// taken to still hold the values of the last sync. Returns whether
+    /// anything was pushed to Airtable.
+    async fn merge(
+        &self,
+        table: &TableSync<'_>,
+        state: &AirtableLinkState,
+        record: Option<&AirtableRecord>,
+    ) -> Result<bool, AirtableSyncError> {
+        let task = Task::find_by_id(&self.pool, state.link.task_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        let snapshot: Option<Values> = state
+            .snapshot
+            .as_deref()
+            .and_then(|snapshot| serde_json::from_str(snapshot).ok());
+
+        let fetched;
+        let record = match (record, &snapshot) {
+            (Some(record), _) => Some(record),
+            (None, Some(_)) => None,
+            // Linked before field sync existed: start from Airtable's values
+            (None, None) => {
+                fetched = table
+                    .service
+                    .get_record(
+                        &table.connection.airtable_base_id,
+                        &table.mapping.airtable_table_id,
+                        &state.link.airtable_record_id,
+                    )
+                    .await?;
+                Some(&fetched)
+            }
+        };
+
+        let local = task_values(&task, &table.fields);
+        let (remote, remote_wins) = match record {
+            Some(record) => {
+                let modified_at = table
+                    .mapping
+                    .modified_field
+                    .as_deref()
+                    .and_then(|field| record.fields.get(field))
+                    .and_then(Value::as_str)
+                    .and_then(parse_datetime);
+                // Until both sides have agreed once, Airtable is the source
+                (
+                    record_values(record, &table.fields),
+                    snapshot.is_none() || modified_at.is_none_or(|at| at >= task.updated_at),
+                )
+            }
+            None => (snapshot.clone().unwrap_or_default(), false),
+        };
+
+        let fields: Vec<&str> = table
+            .fields
+            .iter()
+            .map(|(field, _)| field.airtable_field.as_str())
+            .collect();
+        let reconciliation = reconcile(&fields, snapshot.as_ref(), &remote, &local, remote_wins);
+
+        let applied = if reconciliation.to_local.is_empty() {
+            local.clone()
+        } else {
+            let updated = self
+                .apply_to_task(&task, &reconciliation.to_local, &table.fields)
+                .await?;
+            task_values(&updated, &table.fields)
+        };
+
+        let pushed = !reconciliation.to_remote.is_empty();
+        if pushed {
+            table
+                .service
+                .update_record(
+                    &table.connection.airtable_base_id,
+                    &table.mapping.airtable_table_id,
+                    &state.link.airtable_record_id,
+                    airtable_fields(&reconciliation.to_remote, &table.fields),
+                )
+                .await?;
+            sleep(WRITE_SPACING).await;
+        }
+
+        // Fields that could not be applied locally keep the local value, so
+        // the task is not pushed over an Airtable value it could not read
+        let agreed: Values = fields
+            .iter()
+            .map(|field| {
+                let value = if reconciliation.to_local.contains_key(*field) {
+                    applied.get(*field)
+                } else if reconciliation.to_remote.contains_key(*field) {
+                    local.get(*field)
+                } else {
+                    remote.get(*field)
+                };
+                (field.to_string(), value.cloned().unwrap_or(Value::Null))
+            })
+            .collect();
+        AirtableRecordLink::record_field_sync(
+            &self.pool,
+            state.link.id,
+            Some(&Value::Object(agreed).to_string()),
+            None,
+        )
+        .await?;
+
+        Ok(pushed)
+    }
+
+    /// Create a task for a record that is not linked yet
+    async fn import(
+        &self,
+        table: &TableSync<'_>,
+        record: &AirtableRecord,
+    ) -> Result<(), AirtableSyncError> {
+        let remote = record_values(record, &table.fields);
+        let title = table
+            .fields
+            .iter()
+            .find(|(_, target)| *target == AirtableTaskField::Title)
+            .and_then(|(field, _)| remote.get(&field.airtable_field))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Record {}", record.id.chars().take(8).collect::<String>()));
+
+        let task = Task::create(
+            &self.pool,
+            &CreateTask {
+                project_id: table.connection.project_id,
+                pod_id: None,
+                board_id: table.mapping.board_id,
+                title,
+                description: None,
+                parent_task_attempt: None,
+                image_ids: None,
+                priority: Some(Priority::Medium),
+                assignee_id: None,
+                assigned_agent: None,
+                agent_id: None,
+                assigned_mcps: None,
+                created_by: "airtable_import".to_string(),
+                requires_approval: Some(false),
+                parent_task_id: None,
+                tags: None,
+                due_date: None,
+                custom_properties: None,
+                scheduled_start: None,
+                scheduled_end: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await?;
+
+        let link = match AirtableRecordLink::create(
+            &self.pool,
+            CreateAirtableRecordLink {
+                task_id: task.id,
+                airtable_record_id: record.id.clone(),
+                airtable_base_id: table.connection.airtable_base_id.clone(),
+                airtable_table_id: Some(table.mapping.airtable_table_id.clone()),
+                origin: AirtableOrigin::Airtable,
+                airtable_record_url: Some(build_record_url(
+                    &table.connection.airtable_base_id,
+                    &table.mapping.airtable_table_id,
+                    &record.id,
+                )),
+            },
+        )
+        .await
+        {
+            Ok(link) => link,
+            Err(e) => {
+                Task::delete(&self.pool, task.id).await?;
+                return Err(e.into());
+            }
+        };
+
+        // Copy the rest of the record across; Airtable wins every field
+        let state = AirtableLinkState {
+            task_updated_at: task.updated_at,
+            link,
+            snapshot: None,
+        };
+        self.merge(table, &state, Some(record)).await.map(|_| ())
+    }
+
+    /// Create a record for a local task and link the two
+    async fn create_remote(
+        &self,
+        table: &TableSync<'_>,
+        task: &Task,
+    ) -> Result<(), AirtableSyncError> {
+        let local = task_values(task, &table.fields);
+        let record = table
+            .service
+            .create_record(
+                &table.connection.airtable_base_id,
+                &table.mapping.airtable_table_id,
+                airtable_fields(&local, &table.fields),
+            )
+            .await?;
+        sleep(WRITE_SPACING).await;
+
+        let link = AirtableRecordLink::create(
+            &self.pool,
+            CreateAirtableRecordLink {
+                task_id: task.id,
+                airtable_record_id: record.id.clone(),
+                airtable_base_id: table.connection.airtable_base_id.clone(),
+                airtable_table_id: Some(table.mapping.airtable_table_id.clone()),
+                origin: AirtableOrigin::Pcg,
+                airtable_record_url: Some(build_record_url(
+                    &table.connection.airtable_base_id,
+                    &table.mapping.airtable_table_id,
+                    &record.id,
+                )),
+            },
+        )
+        .await?;
+        AirtableRecordLink::record_field_sync(
+            &self.pool,
+            link.id,
+            Some(&Value::Object(local).to_string()),
+            None,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn apply_remote_delete(
+        &self,
+        table: &TableSync<'_>,
+        state: &AirtableLinkState,
+        report: &mut AirtableSyncReport,
+    ) -> Result<(), AirtableSyncError> {
+        // Unlink first, so deleting the task is not pushed back to Airtable
+        AirtableRecordLink::delete(&self.pool, state.link.id).await?;
+        if table.mapping.delete_local_tasks {
+            Task::delete(&self.pool, state.link.task_id).await?;
+            report.deleted_local += 1;
+        } else {
+            Task::update_status(&self.pool, state.link.task_id, TaskStatus::Cancelled).await?;
+            report.cancelled_local += 1;
+        }
+        info!(
+            "Airtable record {} was deleted; {} task {}",
+            state.link.airtable_record_id,
+            if table.mapping.delete_local_tasks {
+                "deleted"
+            } else {
+                "cancelled"
+            },
+            state.link.task_id
+        );
+        Ok(())
+    }
+
+    /// Write mapped values into a task. Values the task cannot hold, such as
+    /// a status with no matching local status, are skipped.
+    async fn apply_to_task(
+        &self,
+        task: &Task,
+        changes: &Values,
+        fields: &[(&AirtableFieldMapping, AirtableTaskField)],
+    ) -> Result<Task, AirtableSyncError> {
+        let mut title = task.title.clone();
+        let mut description = task.description.clone();
+        let mut status = task.status.clone();
+        let mut priority = task.priority.clone();
+        let mut due_date = task.due_date;
+        let mut tags = task.tags.clone();
+        let mut custom = match &task.custom_properties {
+            Some(Json(Value::Object(properties))) => properties.clone(),
+            _ => Map::new(),
+        };
+
+        for (field, target) in fields {
+            let Some(value) = changes.get(&field.airtable_field) else {
+                continue;
+            };
+            match target {
+                AirtableTaskField::Title => {
+                    if let Some(value) = value.as_str() {
+                        title = value.to_string();
+                    }
+                }
+                AirtableTaskField::Description => {
+                    description = value.as_str().map(str::to_string);
+                }
+                AirtableTaskField::Status => {
+                    if let Ok(value) = serde_json::from_value(value.clone()) {
+                        status = value;
+                    }
+                }
+                AirtableTaskField::Priority => {
+                    if let Ok(value) = serde_json::from_value(value.clone()) {
+                        priority = value;
+                    }
+                }
+                AirtableTaskField::DueDate => match value {
+                    Value::Null => due_date = None,
+                    Value::String(value) => {
+                        if let Some(value) = parse_datetime(value) {
+                            due_date = Some(value);
+                        }
+                    }
+                    _ => {}
+                },
+                AirtableTaskField::Tags => {
+                    tags = (!value.is_null()).then(|| value.to_string());
+                }
+                AirtableTaskField::Custom(name) => {
+                    if value.is_null() {
+                        custom.remove(name);
+                    } else {
+                        custom.insert(name.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        let custom_properties = if custom.is_empty() && task.custom_properties.is_none() {
+            None
+        } else {
+            Some(Json(Value::Object(custom)))
+        };
+
+        Ok(Task::update(
+            &self.pool,
+            task.id,
+            task.project_id,
+            title,
+            description,
+            status,
+            task.parent_task_attempt,
+            task.pod_id,
+            task.board_id,
+            priority,
+            task.assignee_id.clone(),
+            task.assigned_agent.clone(),
+            task.assigned_mcps.clone(),
+            task.requires_approval,
+            task.approval_status.clone(),
+            task.parent_task_id,
+            tags,
+            due_date,
+            custom_properties,
+            task.scheduled_start,
+            task.scheduled_end,
+        )
+        .await?)
+    }
+}
+
+/// Airtable formula matching records modified after `since`
+fn changed_since_formula(modified_field: Option<&str>, since: DateTime<Utc>) -> String {
+    let modified = match modified_field {
+        Some(field) => format!("{{{}}}", field),
+        None => "LAST_MODIFIED_TIME()".to_string(),
+    };
+    format!(
+        "IS_AFTER({}, DATETIME_PARSE('{}'))",
+        modified,
+        (since - PULL_OVERLAP).format("%Y-%m-%dT%H:%M:%SZ")
+    )
+}
+
+/// Decide, field by field, which side's value to keep
+fn reconcile(
+    fields: &[&str],
+    snapshot: Option<&Values>,
+    remote: &Values,
+    local: &Values,
+    remote_wins: bool,
+) -> Reconciliation {
+    let mut reconciliation = Reconciliation::default();
+
+    for field in fields {
+        let remote_value = remote.get(*field).unwrap_or(&Value::Null);
+        let local_value = local.get(*field).unwrap_or(&Value::Null);
+        if remote_value == local_value {
+            continue;
+        }
+
+        let agreed = snapshot.map(|snapshot| snapshot.get(*field).unwrap_or(&Value::Null));
+        let take_remote = match agreed {
+            Some(agreed) if agreed == remote_value => false,
+            Some(agreed) if agreed == local_value => true,
+            // Both changed, or never synced
+            _ => remote_wins,
+        };
+
+        if take_remote {
+            reconciliation
+                .to_local
+                .insert(field.to_string(), remote_value.clone());
+        } else {
+            reconciliation
+                .to_remote
+                .insert(field.to_string(), local_value.clone());
+        }
+    }
+
+    reconciliation
+}
+
+/// Mapped values of an Airtable record
+fn record_values(
+    record: &AirtableRecord,
+    fields: &[(&AirtableFieldMapping, AirtableTaskField)],
+) -> Values {
+    fields
+        .iter()
+        .map(|(field, target)| {
+            let value = record
+                .fields
+                .get(&field.airtable_field)
+                .map(|value| from_airtable(field, target, value))
+                .unwrap_or(Value::Null);
+            (field.airtable_field.clone(), value)
+        })
+        .collect()
+}
+
+/// Mapped values of a task
+fn task_values(task: &Task, fields: &[(&AirtableFieldMapping, AirtableTaskField)]) -> Values {
+    fields
+        .iter()
+        .map(|(field, target)| {
+            let value = match target {
+                AirtableTaskField::Title => normalize_text(&task.title),
+                AirtableTaskField::Description => task
+                    .description
+                    .as_deref()
+                    .map(normalize_text)
+                    .unwrap_or(Value::Null),
+                AirtableTaskField::Status => {
+                    serde_json::to_value(&task.status).unwrap_or(Value::Null)
+                }
+                AirtableTaskField::Priority => {
+                    serde_json::to_value(&task.priority).unwrap_or(Value::Null)
+                }
+                AirtableTaskField::DueDate => task
+                    .due_date
+                    .map(|due| Value::String(format_datetime(due)))
+                    .unwrap_or(Value::Null),
+                AirtableTaskField::Tags => task
+                    .tags
+                    .as_deref()
+                    .and_then(|tags| serde_json::from_str::<Value>(tags).ok())
+                    .map(|tags| normalize_tags(&tags))
+                    .unwrap_or(Value::Null),
+                AirtableTaskField::Custom(name) => match &task.custom_properties {
+                    Some(Json(properties)) => properties
+                        .get(name)
+                        .map(|value| match value {
+                            Value::String(text) => normalize_text(text),
+                            other => other.clone(),
+                        })
+                        .unwrap_or(Value::Null),
+                    None => Value::Null,
+                },
+            };
+            (field.airtable_field.clone(), value)
+        })
+        .collect()
+}
+
+/// Bring an Airtable value into the shape task values are compared in
+fn from_airtable(field: &AirtableFieldMapping, target: &AirtableTaskField, value: &Value) -> Value {
+    match target {
+        AirtableTaskField::Status | AirtableTaskField::Priority => {
+            let Some(option) = value.as_str() else {
+                return Value::Null;
+            };
+            let mapped = field
+                .values
+                .as_ref()
+                .and_then(|values| values.get(option))
+                .map(String::as_str);
+            let local = mapped.map(select_key).or_else(|| match target {
+                AirtableTaskField::Status => status_key(option),
+                _ => priority_key(option),
+            });
+            // Options with no local equivalent stay as they are, so they never
+            // compare equal to a local value and are not applied
+            local
+                .map(Value::String)
+                .unwrap_or_else(|| normalize_text(option))
+        }
+        AirtableTaskField::DueDate => match value.as_str().and_then(parse_datetime) {
+            Some(due) => Value::String(format_datetime(due)),
+            None => Value::Null,
+        },
+        AirtableTaskField::Tags => normalize_tags(value),
+        AirtableTaskField::Title | AirtableTaskField::Description => match value {
+            Value::String(text) => normalize_text(text),
+            Value::Null => Value::Null,
+            other => Value::String(other.to_string()),
+        },
+        AirtableTaskField::Custom(_) => match value {
+            Value::String(text) => {
+                let text = field
+                    .values
+                    .as_ref()
+                    .and_then(|values| values.get(text.as_str()))
+                    .unwrap_or(text);
+                normalize_text(text)
+            }
+            other => other.clone(),
+        },
+    }
+}
+
+/// Turn compared values back into Airtable field values
+fn airtable_fields(
+    values: &Values,
+    fields: &[(&AirtableFieldMapping, AirtableTaskField)],
+) -> Value {
+    let mut out = Map::new();
+    for (field, target) in fields {
+        let Some(value) = values.get(&field.airtable_field) else {
+            continue;
+        };
+        let value = match (target, value) {
+            (
+                AirtableTaskField::Status
+                | AirtableTaskField::Priority
+                | AirtableTaskField::Custom(_),
+                Value::String(local),
+            ) => {
+                // The first Airtable option mapped to this value, else a label
+                let option = field.values.as_ref().and_then(|values| {
+                    values
+                        .iter()
+                        .find(|(_, mapped)| select_key(mapped) == select_key(local))
+                        .map(|(option, _)| option.clone())
+                });
+                Value::String(option.unwrap_or_else(|| match target {
+                    AirtableTaskField::Status => status_label(local),
+                    AirtableTaskField::Priority => capitalise(local),
+                    _ => local.clone(),
+                }))
+            }
+            (_, value) => value.clone(),
+        };
+        out.insert(field.airtable_field.clone(), value);
+    }
+    Value::Object(out)
+}
+
+fn normalize_text(text: &str) -> Value {
+    let text = text.trim();
+    if text.is_empty() {
+        Value::Null
+    } else {
+        Value::String(text.to_string())
+    }
+}
+
+/// Tags as a list of names, from a multiple select or comma-separated text
+fn normalize_tags(value: &Value) -> Value {
+    let tags: Vec<Value> = match value {
+        Value::Array(items) => items
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| json!(tag))
+            .collect(),
+        Value::String(text) => text
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| json!(tag))
+            .collect(),
+        _ => Vec::new(),
+    };
+    if tags.is_empty() {
+        Value::Null
+    } else {
+        Value::Array(tags)
+    }
+}
+
+/// Lowercase letters and digits only, so `In Progress` matches `inprogress`
+fn select_key(value: &str) -> String {
+    value
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn status_key(option: &str) -> Option<String> {
+    let key = match select_key(option).as_str() {
+        "todo" | "notstarted" | "backlog" => "todo",
+        "inprogress" | "doing" => "inprogress",
+        "inreview" | "review" => "inreview",
+        "done" | "complete" | "completed" => "done",
+        "cancelled" | "canceled" => "cancelled",
+        _ => return None,
+    };
+    Some(key.to_string())
+}
+
+fn priority_key(option: &str) -> Option<String> {
+    let key = select_key(option);
+    matches!(key.as_str(), "critical" | "high" | "medium" | "low").then_some(key)
+}
+
+fn status_label(status: &str) -> String {
+    match status {
+        "todo" => "Todo",
+        "inprogress" => "In progress",
+        "inreview" => "In review",
+        "done" => "Done",
+        "cancelled" => "Cancelled",
+        other => other,
+    }
+    .to_string()
+}
+
+fn capitalise(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Airtable dates are `2026-03-01`; date-times are RFC 3339
+fn parse_datetime(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+        })
+}
+
+/// Dates at midnight are written as plain dates, so date fields round-trip
+fn format_datetime(at: DateTime<Utc>) -> String {
+    if at.time() == NaiveTime::MIN {
+        at.format("%Y-%m-%d").to_string()
+    } else {
+        at.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn mapping(airtable_field: &str, task_field: &str) -> AirtableFieldMapping {
+        AirtableFieldMapping {
+            airtable_field: airtable_field.into(),
+            task_field: task_field.into(),
+            values: None,
+        }
+    }
+
+    fn values(pairs: &[(&str, Value)]) -> Values {
+        pairs
+            .iter()
+            .map(|(field, value)| (field.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn one_sided_changes_flow_to_the_other_side() {
+        let fields = ["Shot", "Status", "Due"];
+        let snapshot = values(&[
+            ("Shot", json!("Shot 010")),
+            ("Status", json!("todo")),
+            ("Due", json!("2026-03-01")),
+        ]);
+        let remote = values(&[
+            ("Shot", json!("Shot 010")),
+            ("Status", json!("inprogress")),
+            ("Due", json!("2026-03-01")),
+        ]);
+        let local = values(&[
+            ("Shot", json!("Shot 010 v2")),
+            ("Status", json!("todo")),
+            ("Due", json!("2026-03-01")),
+        ]);
+
+        let reconciliation = reconcile(&fields, Some(&snapshot), &remote, &local, false);
+        assert_eq!(
+            reconciliation.to_local,
+            values(&[("Status", json!("inprogress"))])
+        );
+        assert_eq!(
+            reconciliation.to_remote,
+            values(&[("Shot", json!("Shot 010 v2"))])
+        );
+    }
+
+    #[test]
+    fn conflicting_edits_go_to_the_newer_side() {
+        let fields = ["Status"];
+        let snapshot = values(&[("Status", json!("todo"))]);
+        let remote = values(&[("Status", json!("done"))]);
+        let local = values(&[("Status", json!("inreview"))]);
+
+        let remote_newer = reconcile(&fields, Some(&snapshot), &remote, &local, true);
+        assert_eq!(remote_newer.to_local, remote);
+        assert!(remote_newer.to_remote.is_empty());
+
+        let local_newer = reconcile(&fields, Some(&snapshot), &remote, &local, false);
+        assert_eq!(local_newer.to_remote, local);
+        assert!(local_newer.to_local.is_empty());
+    }
+
+    #[test]
+    fn select_options_map_both_ways() {
+        let mut status = mapping("Stage", "status");
+        status.values = Some(BTreeMap::from([
+            ("Awaiting notes".to_string(), "inreview".to_string()),
+            ("Final".to_string(), "done".to_string()),
+        ]));
+        let target = status.target().unwrap();
+
+        assert_eq!(
+            from_airtable(&status, &target, &json!("Final")),
+            json!("done")
+        );
+        assert_eq!(
+            from_airtable(&status, &target, &json!("In Progress")),
+            json!("inprogress")
+        );
+        // Unknown options never match a local status
+        assert_eq!(
+            from_airtable(&status, &target, &json!("On hold")),
+            json!("On hold")
+        );
+
+        let fields = vec![(&status, target)];
+        assert_eq!(
+            airtable_fields(&values(&[("Stage", json!("inreview"))]), &fields),
+            json!({ "Stage": "Awaiting notes" })
+        );
+        assert_eq!(
+            airtable_fields(&values(&[("Stage", json!("inprogress"))]), &fields),
+            json!({ "Stage": "In progress" })
+        );
+    }
+
+    #[test]
+    fn dates_and_tags_compare_in_one_shape() {
+        let due = mapping("Due", "due_date");
+        let tags = mapping("Tags", "tags");
+        let due_target = due.target().unwrap();
+        let tags_target = tags.target().unwrap();
+
+        assert_eq!(
+            from_airtable(&due, &due_target, &json!("2026-03-01")),
+            json!("2026-03-01")
+        );
+        assert_eq!(
+            from_airtable(&due, &due_target, &json!("2026-03-01T17:30:00.000Z")),
+            json!("2026-03-01T17:30:00Z")
+        );
+        assert_eq!(
+            from_airtable(&tags, &tags_target, &json!("vfx, grade ,")),
+            json!(["vfx", "grade"])
+        );
+        assert_eq!(from_airtable(&tags, &tags_target, &json!([])), Value::Null);
+    }
+
+    #[test]
+    fn changed_since_uses_the_configured_field() {
+        let since = DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            changed_since_formula(Some("Last Modified"), since),
+            "IS_AFTER({Last Modified}, DATETIME_PARSE('2026-03-01T11:59:00Z'))"
+        );
+        assert_eq!(
+            changed_since_formula(None, since),
+            "IS_AFTER(LAST_MODIFIED_TIME(), DATETIME_PARSE('2026-03-01T11:59:00Z'))"
+        );
+    }
+}
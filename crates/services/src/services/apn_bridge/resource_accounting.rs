@@ -128,6 +128,39 @@ impl ResourceAccounting {
         Ok(())
     }
 
+    /// Pay a peer for work it completed, debiting our balance
+    pub async fn pay_peer(
+        &self,
+        peer_node: &str,
+        task_id: Uuid,
+        amount: f64,
+        description: String,
+    ) -> anyhow::Result<()> {
+        {
+            let mut balance = self.balance.write().await;
+            if *balance < amount {
+                return Err(anyhow::anyhow!(
+                    "Insufficient Vibe balance to pay {} {:.2} VIBE",
+                    peer_node,
+                    amount
+                ));
+            }
+            *balance -= amount;
+        }
+
+        self.record_transaction(TransactionLog {
+            id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            tx_type: TransactionType::VibeSpent,
+            description,
+            vibe_amount: Some(-amount),
+            peer_node: Some(peer_node.to_string()),
+            task_id: Some(task_id),
+        }).await;
+
+        Ok(())
+    }
+
     /// Record a task distribution
     pub async fn record_task_distributed(
         &self,
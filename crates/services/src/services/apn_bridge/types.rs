@@ -229,4 +229,6 @@ pub mod capabilities {
     pub const STORAGE: &str = "storage";
     pub const GPU: &str = "gpu";
     pub const HIGH_MEMORY: &str = "high_memory";
+    /// Renders video segments with ffmpeg (see `editron::distributed_render`)
+    pub const FFMPEG: &str = "ffmpeg";
 }
@@ -0,0 +1,522 @@
+//! Static export of a CMS site's published content, for hosting on a CDN
+//!
+//! The export holds the same JSON the public API serves (`site.json`,
+//! `products.json`, `products/<slug>.json`, `faq.json`, `pages/<page>.json`)
+//! plus plain HTML pages: `index.html` (the `home` page), `products.html`,
+//! `products/<slug>.html`, `faq.html` and `<page>.html` for other pages.
+//! Drafts never appear in an export.
+
+use std::{fmt::Write as _, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use db::models::{
+    cms_page_section::CmsPageSection, cms_product::CmsProduct,
+    cms_published_entity::CmsPublishedContent,
+};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use tokio::fs;
+use tracing::{info, warn};
+use ts_rs::TS;
+use utils::assets::asset_dir;
+use uuid::Uuid;
+
+use super::session_export::escape_html;
+
+/// Page whose sections make up `index.html`
+const HOME_PAGE: &str = "home";
+
+#[derive(Debug, Error)]
+pub enum CmsExportError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Site has not been published")]
+    NotPublished,
+    #[error("Site slug {0:?} can't be used as a directory name")]
+    UnsafeSlug(String),
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+pub struct CmsExportResult {
+    pub output_dir: String,
+    /// Paths relative to `output_dir`
+    pub files: Vec<String>,
+    #[ts(type = "Date")]
+    pub exported_at: DateTime<Utc>,
+}
+
+/// Directory holding one export folder per site slug
+pub fn export_root() -> PathBuf {
+    asset_dir().join("cms_exports")
+}
+
+/// Render a site's published content into `export_root()/<slug>`. The new
+/// export replaces the previous one only once it is fully written.
+pub async fn export_site(
+    pool: &SqlitePool,
+    site_id: Uuid,
+) -> Result<CmsExportResult, CmsExportError> {
+    let content = CmsPublishedContent::load(pool, site_id)
+        .await?
+        .ok_or(CmsExportError::NotPublished)?;
+    // The output directory is removed before the new export moves in, so the
+    // slug must not be able to point at the export root or outside it
+    let slug = file_slug(&content.site.slug)
+        .ok_or_else(|| CmsExportError::UnsafeSlug(content.site.slug.clone()))?;
+    let files = render_site(&content)?;
+
+    let root = export_root();
+    let output_dir = root.join(slug);
+    let staging_dir = root.join(format!(".{}-{}", slug, Uuid::new_v4()));
+    for (path, body) in &files {
+        let path = staging_dir.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, body).await?;
+    }
+
+    if fs::try_exists(&output_dir).await? {
+        fs::remove_dir_all(&output_dir).await?;
+    }
+    fs::rename(&staging_dir, &output_dir).await?;
+
+    info!(
+        "Exported CMS site {} ({} files) to {}",
+        content.site.slug,
+        files.len(),
+        output_dir.display()
+    );
+
+    Ok(CmsExportResult {
+        output_dir: output_dir.to_string_lossy().to_string(),
+        files: files.into_iter().map(|(path, _)| path).collect(),
+        exported_at: Utc::now(),
+    })
+}
+
+/// Every file of an export, as (relative path, contents)
+pub fn render_site(content: &CmsPublishedContent) -> Result<Vec<(String, String)>, CmsExportError> {
+    let mut files = Vec::new();
+    let layout = Layout::new(content);
+
+    files.push((
+        "site.json".to_string(),
+        serde_json::to_string_pretty(&json!({
+            "site": content.site,
+            "settings": content.settings,
+        }))?,
+    ));
+    files.push((
+        "products.json".to_string(),
+        serde_json::to_string_pretty(&content.products)?,
+    ));
+    files.push((
+        "faq.json".to_string(),
+        serde_json::to_string_pretty(&content.faq_items)?,
+    ));
+
+    files.push((
+        "products.html".to_string(),
+        layout.page("", "Products", &render_product_list(&content.products, "")),
+    ));
+    for product in &content.products {
+        let Some(slug) = file_slug(&product.slug) else {
+            warn!("Skipping product with unsafe slug {:?}", product.slug);
+            continue;
+        };
+        files.push((
+            format!("products/{slug}.json"),
+            serde_json::to_string_pretty(product)?,
+        ));
+        files.push((
+            format!("products/{slug}.html"),
+            layout.page("../", &product.name, &render_product(product)),
+        ));
+    }
+
+    let mut faq = String::from("<h1>FAQ</h1>\n");
+    for item in &content.faq_items {
+        let _ = write!(
+            faq,
+            "<details>\n<summary>{}</summary>\n<p>{}</p>\n</details>\n",
+            escape_html(&item.question),
+            escape_html(&item.answer)
+        );
+    }
+    files.push(("faq.html".to_string(), layout.page("", "FAQ", &faq)));
+
+    let mut pages: Vec<&str> = content
+        .page_sections
+        .iter()
+        .map(|section| section.page_slug.as_str())
+        .collect();
+    pages.dedup();
+    if !pages.contains(&HOME_PAGE) {
+        pages.insert(0, HOME_PAGE);
+    }
+    for page in pages {
+        let Some(slug) = file_slug(page) else {
+            warn!("Skipping page with unsafe slug {:?}", page);
+            continue;
+        };
+        let sections = content.page_sections(page);
+        files.push((
+            format!("pages/{slug}.json"),
+            serde_json::to_string_pretty(&sections)?,
+        ));
+
+        let mut body: String = sections.iter().map(render_section).collect();
+        if page == HOME_PAGE {
+            let featured: Vec<CmsProduct> = content
+                .products
+                .iter()
+                .filter(|product| product.is_featured)
+                .cloned()
+                .collect();
+            if !featured.is_empty() {
+                body.push_str(&render_product_list(&featured, ""));
+            }
+            files.push((
+                "index.html".to_string(),
+                layout.page("", &content.site.name, &body),
+            ));
+        } else {
+            files.push((format!("{slug}.html"), layout.page("", page, &body)));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Header, navigation and theme shared by every HTML page
+struct Layout<'a> {
+    site_name: &'a str,
+    style: String,
+    nav_pages: Vec<&'a str>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(content: &'a CmsPublishedContent) -> Self {
+        let theme = parse_json_column(content.site.theme_config.as_deref());
+        let theme_value = |key: &str, default: &str| {
+            theme
+                .get(key)
+                .and_then(Value::as_str)
+                .map(css_value)
+                .unwrap_or_else(|| default.to_string())
+        };
+        let style = format!(
+            "body{{font-family:{};background:{};color:{};max-width:960px;margin:0 auto;\
+             padding:0 1rem;line-height:1.5}}a{{color:{}}}nav a{{margin-right:1rem}}\
+             img{{max-width:100%}}",
+            theme_value("fontFamily", "sans-serif"),
+            theme_value("backgroundColor", "#ffffff"),
+            theme_value("textColor", "inherit"),
+            theme_value("primaryColor", "inherit"),
+        );
+
+        let mut nav_pages: Vec<&str> = content
+            .page_sections
+            .iter()
+            .map(|section| section.page_slug.as_str())
+            .filter(|page| *page != HOME_PAGE && file_slug(page).is_some())
+            .collect();
+        nav_pages.dedup();
+
+        Self {
+            site_name: &content.site.name,
+            style,
+            nav_pages,
+        }
+    }
+
+    /// A full HTML document. `root` leads back to the export root from the
+    /// page's folder.
+    fn page(&self, root: &str, title: &str, body: &str) -> String {
+        let mut nav = format!(
+            "<a href=\"{root}index.html\">Home</a><a href=\"{root}products.html\">Products</a>"
+        );
+        for page in &self.nav_pages {
+            let _ = write!(
+                nav,
+                "<a href=\"{root}{page}.html\">{}</a>",
+                escape_html(page)
+            );
+        }
+        let _ = write!(nav, "<a href=\"{root}faq.html\">FAQ</a>");
+
+        let title = if title == self.site_name {
+            escape_html(title)
+        } else {
+            format!("{} | {}", escape_html(title), escape_html(self.site_name))
+        };
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{title}</title>\n<style>{}</style>\n</head>\n<body>\n\
+             <header><nav>{nav}</nav></header>\n<main>\n{body}</main>\n</body>\n</html>\n",
+            self.style
+        )
+    }
+}
+
+fn render_product_list(products: &[CmsProduct], root: &str) -> String {
+    let mut html = String::from("<section class=\"products\">\n");
+    for product in products {
+        let Some(slug) = file_slug(&product.slug) else {
+            continue;
+        };
+        let _ = write!(
+            html,
+            "<article>\n<h2><a href=\"{root}products/{slug}.html\">{}</a></h2>\n",
+            escape_html(&product.name)
+        );
+        if let Some(description) = &product.short_description {
+            let _ = writeln!(html, "<p>{}</p>", escape_html(description));
+        }
+        let _ = write!(
+            html,
+            "<p class=\"price\">{}</p>\n</article>\n",
+            format_price(product)
+        );
+    }
+    html.push_str("</section>\n");
+    html
+}
+
+fn render_product(product: &CmsProduct) -> String {
+    let mut html = format!("<h1>{}</h1>\n", escape_html(&product.name));
+    if let Some(image_url) = &product.image_url {
+        let _ = writeln!(
+            html,
+            "<img src=\"{}\" alt=\"{}\">",
+            escape_html(image_url),
+            escape_html(&product.name)
+        );
+    }
+    if let Some(description) = &product.short_description {
+        let _ = writeln!(html, "<p>{}</p>", escape_html(description));
+    }
+    let _ = writeln!(html, "<p class=\"price\">{}</p>", format_price(product));
+    if let Some(description) = &product.long_description {
+        let _ = writeln!(html, "<p>{}</p>", escape_html(description));
+    }
+
+    if let Value::Array(features) = parse_json_column(product.features.as_deref())
+        && !features.is_empty()
+    {
+        html.push_str("<ul class=\"features\">\n");
+        for feature in &features {
+            let _ = writeln!(html, "<li>{}</li>", escape_html(&display_value(feature)));
+        }
+        html.push_str("</ul>\n");
+    }
+    if let Value::Object(specs) = parse_json_column(product.specs.as_deref())
+        && !specs.is_empty()
+    {
+        html.push_str("<table class=\"specs\">\n");
+        for (name, value) in &specs {
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape_html(name),
+                escape_html(&display_value(value))
+            );
+        }
+        html.push_str("</table>\n");
+    }
+    html
+}
+
+/// Section content is free-form JSON. Title-like fields become headings,
+/// other text becomes paragraphs and lists become lists.
+fn render_section(section: &CmsPageSection) -> String {
+    let content = parse_json_column(Some(&section.content));
+    format!(
+        "<section id=\"{}\">\n{}</section>\n",
+        escape_html(&section.section_key),
+        render_content(&content)
+    )
+}
+
+fn render_content(content: &Value) -> String {
+    const HEADINGS: [&str; 4] = ["headline", "title", "heading", "subheadline"];
+
+    match content {
+        Value::Object(fields) => {
+            let mut html = String::new();
+            for key in HEADINGS {
+                if let Some(text) = fields.get(key).and_then(Value::as_str) {
+                    let tag = if key == "subheadline" { "h3" } else { "h2" };
+                    let _ = writeln!(html, "<{tag}>{}</{tag}>", escape_html(text));
+                }
+            }
+            for (key, value) in fields {
+                if HEADINGS.contains(&key.as_str()) && value.is_string() {
+                    continue;
+                }
+                let _ = write!(
+                    html,
+                    "<div class=\"{}\">\n{}</div>\n",
+                    escape_html(key),
+                    render_content(value)
+                );
+            }
+            html
+        }
+        Value::Array(items) => {
+            let mut html = String::from("<ul>\n");
+            for item in items {
+                let _ = writeln!(html, "<li>{}</li>", render_content(item).trim_end());
+            }
+            html.push_str("</ul>\n");
+            html
+        }
+        Value::Null => String::new(),
+        other => format!("<p>{}</p>\n", escape_html(&display_value(other))),
+    }
+}
+
+fn format_price(product: &CmsProduct) -> String {
+    format!(
+        "{} {}.{:02}",
+        escape_html(&product.currency),
+        product.price_cents / 100,
+        product.price_cents.rem_euclid(100)
+    )
+}
+
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Parse a JSON text column (theme, specs, features, section content),
+/// `Null` when it is missing or invalid
+fn parse_json_column(value: Option<&str>) -> Value {
+    value
+        .and_then(|value| serde_json::from_str(value).ok())
+        .unwrap_or(Value::Null)
+}
+
+/// Keep theme values from breaking out of the stylesheet
+fn css_value(value: &str) -> String {
+    value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || " #,.%()-'".contains(*c))
+        .collect()
+}
+
+/// Slugs used as file names must not leave the export folder
+fn file_slug(slug: &str) -> Option<&str> {
+    let safe = !slug.is_empty()
+        && !slug.starts_with('.')
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.');
+    safe.then_some(slug)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use db::models::cms_site::CmsSite;
+
+    use super::*;
+
+    fn content() -> CmsPublishedContent {
+        let site_id = Uuid::new_v4();
+        let now = Utc::now();
+        let product = |slug: &str, name: &str| CmsProduct {
+            id: Uuid::new_v4(),
+            site_id,
+            slug: slug.to_string(),
+            name: name.to_string(),
+            short_description: None,
+            long_description: None,
+            price_cents: 100050,
+            currency: "USD".to_string(),
+            stripe_price_id: None,
+            image_url: None,
+            gallery_images: None,
+            specs: Some(r#"{"radio": "LoRa"}"#.to_string()),
+            features: Some(r#"["Solar ready"]"#.to_string()),
+            is_active: true,
+            is_featured: true,
+            stock_status: None,
+            sort_order: 0,
+            created_at: now,
+            updated_at: now,
+        };
+
+        CmsPublishedContent {
+            site: CmsSite {
+                id: site_id,
+                slug: "omega".to_string(),
+                name: "Omega".to_string(),
+                domain: "omega.example".to_string(),
+                theme_config: Some(r#"{"primaryColor": "red;}</style><script>"}"#.to_string()),
+                is_active: true,
+                created_at: now,
+                updated_at: now,
+            },
+            settings: HashMap::new(),
+            products: vec![
+                product("founders-edition", "Founders' <Edition>"),
+                product("../escape", "Escape"),
+            ],
+            faq_items: vec![],
+            page_sections: vec![CmsPageSection {
+                id: Uuid::new_v4(),
+                site_id,
+                page_slug: "home".to_string(),
+                section_key: "hero".to_string(),
+                content: r#"{"cta": "Pre-order", "headline": "Host your own network"}"#.to_string(),
+                sort_order: 0,
+                is_active: true,
+                created_at: now,
+                updated_at: now,
+            }],
+        }
+    }
+
+    #[test]
+    fn unsafe_slugs_are_rejected() {
+        assert_eq!(file_slug("acme-store_2"), Some("acme-store_2"));
+        for slug in ["", ".", "..", "../other", "/etc", "a/b", ".hidden"] {
+            assert_eq!(file_slug(slug), None, "{slug:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn renders_published_content_as_json_and_html() {
+        let files: HashMap<String, String> = render_site(&content()).unwrap().into_iter().collect();
+
+        let product = &files["products/founders-edition.html"];
+        assert!(product.contains("<h1>Founders&#39; &lt;Edition&gt;</h1>"));
+        assert!(product.contains("USD 1000.50"));
+        assert!(product.contains("<li>Solar ready</li>"));
+        assert!(product.contains("<a href=\"../faq.html\">FAQ</a>"));
+
+        let index = &files["index.html"];
+        let headline = index.find("<h2>Host your own network</h2>").unwrap();
+        assert!(headline < index.find("Pre-order").unwrap());
+        assert!(index.contains("href=\"products/founders-edition.html\""));
+        assert!(!index.contains("<script>"));
+
+        let exported: Vec<CmsProduct> = serde_json::from_str(&files["products.json"]).unwrap();
+        assert_eq!(exported.len(), 2);
+        assert!(files.contains_key("pages/home.json"));
+        assert!(!files.keys().any(|path| path.contains("escape")));
+    }
+}
@@ -25,6 +25,7 @@ pub type UiLanguage = versions::v10::UiLanguage;
 pub type AptosWalletConfig = versions::v10::AptosWalletConfig;
 pub type TrelloConfig = versions::v10::TrelloConfig;
 pub type AirtableConfig = versions::v10::AirtableConfig;
+pub type ForgeInstanceConfig = versions::v10::ForgeInstanceConfig;
 
 /// Will always return config, trying old schemas or eventually returning default
 pub async fn load_config_from_file(config_path: &PathBuf) -> Config {
@@ -1,4 +1,5 @@
 use anyhow::Error;
+use db::models::merge::ForgeType;
 use executors::{executors::BaseCodingAgent, profile::ExecutorProfileId};
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
@@ -41,6 +42,29 @@ impl AirtableConfig {
     }
 }
 
+/// A GitLab or Gitea/Forgejo instance PRs can be opened against. GitHub keeps
+/// using `GitHubConfig`; gitlab.com and codeberg.org are recognised without an
+/// entry and can take their token from `GITLAB_TOKEN` / `GITEA_TOKEN` instead.
+#[derive(Clone, Debug, Serialize, Deserialize, TS)]
+pub struct ForgeInstanceConfig {
+    pub forge: ForgeType,
+    /// Host as it appears in git remotes, e.g. `gitlab.example.com`
+    pub host: String,
+    /// Base URL of the web/API server when it differs from `https://{host}`
+    pub api_url: Option<String>,
+    /// Personal access token used for the API and for pushing branches
+    pub token: Option<String>,
+}
+
+impl ForgeInstanceConfig {
+    pub fn base_url(&self) -> String {
+        self.api_url
+            .as_deref()
+            .map(|url| url.trim_end_matches('/').to_string())
+            .unwrap_or_else(|| format!("https://{}", self.host))
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, TS)]
 pub struct Config {
     pub config_version: String,
@@ -65,6 +89,8 @@ pub struct Config {
     pub trello: TrelloConfig,
     #[serde(default)]
     pub airtable: AirtableConfig,
+    #[serde(default)]
+    pub forges: Vec<ForgeInstanceConfig>,
 }
 
 impl Config {
@@ -97,6 +123,7 @@ impl Config {
             aptos_wallet: old_config.aptos_wallet,
             trello: old_config.trello,
             airtable: AirtableConfig::default(),
+            forges: Vec::new(),
         })
     }
 }
@@ -143,6 +170,7 @@ impl Default for Config {
             aptos_wallet: AptosWalletConfig::default(),
             trello: TrelloConfig::default(),
             airtable: AirtableConfig::default(),
+            forges: Vec::new(),
         }
     }
 }
@@ -17,6 +17,7 @@ use db::{
         execution_process_logs::ExecutionProcessLogs,
         execution_slot::{ExecutionSlot, ProjectCapacity, SlotType},
         executor_session::{CreateExecutorSession, ExecutorSession},
+        log_search::{LogSearchContext, LogSearchEntry},
         task::{Task, TaskStatus},
         task_attempt::{TaskAttempt, TaskAttemptError},
     },
@@ -29,6 +30,7 @@ use executors::{
         script::{ScriptContext, ScriptRequest, ScriptRequestLanguage},
     },
     executors::{ExecutorError, StandardCodingAgentExecutor},
+    logs::utils::patch::extract_normalized_entry_from_patch,
     profile::{ExecutorConfigs, ExecutorProfileId, to_default_variant},
 };
 use futures::{StreamExt, future};
@@ -41,6 +43,7 @@ use uuid::Uuid;
 use crate::services::{
     git::{GitService, GitServiceError},
     image::ImageService,
+    log_archive::load_execution_logs,
     worktree_manager::{WorktreeError, WorktreeManager},
 };
 pub type ContainerRef = String;
@@ -228,16 +231,15 @@ pub trait ContainerService {
                     .boxed(),
             );
         } else {
-            // Fallback: load from DB and create direct stream
-            let logs_record =
-                match ExecutionProcessLogs::find_by_execution_id(&self.db().pool, *id).await {
-                    Ok(Some(record)) => record,
-                    Ok(None) => return None, // No logs exist
-                    Err(e) => {
-                        tracing::error!("Failed to fetch logs for execution {}: {}", id, e);
-                        return None;
-                    }
-                };
+            // Fallback: load from DB (or cold storage) and create direct stream
+            let logs_record = match load_execution_logs(&self.db().pool, *id).await {
+                Ok(Some(record)) => record,
+                Ok(None) => return None, // No logs exist
+                Err(e) => {
+                    tracing::error!("Failed to fetch logs for execution {}: {}", id, e);
+                    return None;
+                }
+            };
 
             let messages = match logs_record.parse_logs() {
                 Ok(msgs) => msgs,
@@ -277,16 +279,15 @@ pub trait ContainerService {
                     .boxed(),
             )
         } else {
-            // Fallback: load from DB and normalize
-            let logs_record =
-                match ExecutionProcessLogs::find_by_execution_id(&self.db().pool, *id).await {
-                    Ok(Some(record)) => record,
-                    Ok(None) => return None, // No logs exist
-                    Err(e) => {
-                        tracing::error!("Failed to fetch logs for execution {}: {}", id, e);
-                        return None;
-                    }
-                };
+            // Fallback: load from DB (or cold storage) and normalize
+            let logs_record = match load_execution_logs(&self.db().pool, *id).await {
+                Ok(Some(record)) => record,
+                Ok(None) => return None, // No logs exist
+                Err(e) => {
+                    tracing::error!("Failed to fetch logs for execution {}: {}", id, e);
+                    return None;
+                }
+            };
 
             let raw_messages = match logs_record.parse_logs() {
                 Ok(msgs) => msgs,
@@ -400,6 +401,18 @@ pub trait ContainerService {
 
             if let Some(store) = store {
                 let mut stream = store.history_plus_stream();
+                let search_ctx = match LogSearchContext::for_execution(&db.pool, execution_id).await
+                {
+                    Ok(ctx) => ctx,
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to resolve search context for execution {}: {}",
+                            execution_id,
+                            e
+                        );
+                        None
+                    }
+                };
 
                 while let Some(Ok(msg)) = stream.next().await {
                     match &msg {
@@ -453,7 +466,28 @@ pub trait ContainerService {
                         LogMsg::Finished => {
                             break;
                         }
-                        LogMsg::JsonPatch(_) | LogMsg::TokenCount { .. } => continue,
+                        LogMsg::JsonPatch(patch) => {
+                            // Keep the full-text index in step with the normalized log
+                            let Some(ctx) = &search_ctx else { continue };
+                            if let Some((entry_index, entry)) =
+                                extract_normalized_entry_from_patch(patch)
+                                && let Err(e) = LogSearchEntry::index_normalized_entry(
+                                    &db.pool,
+                                    ctx,
+                                    entry_index,
+                                    &entry,
+                                )
+                                .await
+                            {
+                                tracing::warn!(
+                                    "Failed to index log entry {} for execution {}: {}",
+                                    entry_index,
+                                    execution_id,
+                                    e
+                                );
+                            }
+                        }
+                        LogMsg::TokenCount { .. } => continue,
                     }
                 }
             }
@@ -660,6 +694,90 @@ pub trait ContainerService {
         Ok(execution_process)
     }
 
+    /// Continue the attempt's latest coding agent session with `prompt`,
+    /// inheriting its executor profile (optionally with another `variant`)
+    async fn start_follow_up(
+        &self,
+        task_attempt: &TaskAttempt,
+        prompt: String,
+        variant: Option<String>,
+    ) -> Result<ExecutionProcess, ContainerError> {
+        self.ensure_container_exists(task_attempt).await?;
+
+        let pool = &self.db().pool;
+        let session_id =
+            ExecutionProcess::find_latest_session_id_by_task_attempt(pool, task_attempt.id)
+                .await?
+                .ok_or_else(|| {
+                    TaskAttemptError::ValidationError(
+                        "Couldn't find a prior session_id, please create a new task attempt"
+                            .to_string(),
+                    )
+                })?;
+        let latest = ExecutionProcess::find_latest_by_task_attempt_and_run_reason(
+            pool,
+            task_attempt.id,
+            &ExecutionProcessRunReason::CodingAgent,
+        )
+        .await?
+        .ok_or_else(|| {
+            TaskAttemptError::ValidationError(
+                "Couldn't find initial coding agent process, has it run yet?".to_string(),
+            )
+        })?;
+        let executor = match latest
+            .executor_action()
+            .map_err(|e| TaskAttemptError::ValidationError(e.to_string()))?
+            .typ()
+        {
+            ExecutorActionType::CodingAgentInitialRequest(req) => {
+                req.executor_profile_id.executor.clone()
+            }
+            ExecutorActionType::CodingAgentFollowUpRequest(req) => {
+                req.executor_profile_id.executor.clone()
+            }
+            _ => {
+                return Err(TaskAttemptError::ValidationError(
+                    "Couldn't find profile from initial request".to_string(),
+                )
+                .into());
+            }
+        };
+
+        let cleanup_action = task_attempt
+            .parent_task(pool)
+            .await?
+            .ok_or(SqlxError::RowNotFound)?
+            .parent_project(pool)
+            .await?
+            .and_then(|project| project.cleanup_script)
+            .map(|script| {
+                Box::new(ExecutorAction::new(
+                    ExecutorActionType::ScriptRequest(ScriptRequest {
+                        script,
+                        language: ScriptRequestLanguage::Bash,
+                        context: ScriptContext::CleanupScript,
+                    }),
+                    None,
+                ))
+            });
+
+        let follow_up_action = ExecutorAction::new(
+            ExecutorActionType::CodingAgentFollowUpRequest(CodingAgentFollowUpRequest {
+                prompt,
+                session_id,
+                executor_profile_id: ExecutorProfileId { executor, variant },
+            }),
+            cleanup_action,
+        );
+        self.start_execution(
+            task_attempt,
+            &follow_up_action,
+            &ExecutionProcessRunReason::CodingAgent,
+        )
+        .await
+    }
+
     async fn try_start_next_action(&self, ctx: &ExecutionContext) -> Result<(), ContainerError> {
         let action = ctx.execution_process.executor_action()?;
         let next_action = if let Some(next_action) = action.next_action() {
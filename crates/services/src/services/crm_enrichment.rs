@@ -0,0 +1,630 @@
+//! Automatic CRM enrichment from email, calls and SMS
+//!
+//! Every message and call is resolved to a `crm_contact` by email address or
+//! E.164 phone number, creating the contact when it is new. The interaction is
+//! appended to the contact's `crm_activity` timeline, moves the contact's
+//! last-contacted or last-replied timestamp and is filed under the contact's
+//! most recently active open deal, while every open deal of the contact has
+//! its `last_activity_at` bumped.
+//!
+//! Email sync calls [`CrmEnrichmentService::ingest_email`] as messages arrive
+//! or are sent. A background sweep links anything stored without going
+//! through a hook, which covers history and Twilio call and SMS rows.
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use db::models::{
+    call_log::{CallLog, CallLogError, UpdateCallLog},
+    crm_activity::{CreateCrmActivity, CrmActivity, CrmActivityError, CrmActivityType},
+    crm_contact::{ContactSource, CrmContact, CrmContactError},
+    crm_deal::{CrmDeal, CrmDealError},
+    email_account::{EmailAccount, EmailAccountError, EmailProvider},
+    email_message::{EmailMessage, EmailMessageError, UpdateEmailMessage},
+    lead_scoring::LeadScoreTrigger,
+    sms_message::{SmsMessage, SmsMessageError, UpdateSmsMessage},
+};
+use serde_json::json;
+use sqlx::SqlitePool;
+use thiserror::Error;
+use tokio::{task::JoinHandle, time::interval};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::services::lead_scoring::LeadScoringService;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+/// Rows loaded per query while sweeping
+const SWEEP_BATCH: i64 = 200;
+/// E.164 allows at most 15 digits; anything under 7 is a short code
+const E164_MIN_DIGITS: usize = 7;
+const E164_MAX_DIGITS: usize = 15;
+/// Mailboxes that never belong to a person, so they never become contacts
+const AUTOMATED_LOCAL_PARTS: &[&str] = &[
+    "noreply",
+    "no-reply",
+    "donotreply",
+    "do-not-reply",
+    "mailer-daemon",
+    "postmaster",
+    "bounce",
+    "bounces",
+];
+
+#[derive(Debug, Error)]
+pub enum CrmEnrichmentError {
+    #[error(transparent)]
+    Contact(#[from] CrmContactError),
+    #[error(transparent)]
+    Activity(#[from] CrmActivityError),
+    #[error(transparent)]
+    Deal(#[from] CrmDealError),
+    #[error(transparent)]
+    EmailAccount(#[from] EmailAccountError),
+    #[error(transparent)]
+    EmailMessage(#[from] EmailMessageError),
+    #[error(transparent)]
+    CallLog(#[from] CallLogError),
+    #[error(transparent)]
+    SmsMessage(#[from] SmsMessageError),
+}
+
+/// Which way an interaction went, seen from our side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+impl Direction {
+    /// Twilio directions: `inbound`, `outbound`, `outbound-api`, `outbound-dial`, ...
+    fn from_twilio(direction: &str) -> Self {
+        if direction.starts_with("outbound") {
+            Direction::Outbound
+        } else {
+            Direction::Inbound
+        }
+    }
+}
+
+/// The contact an interaction was filed under
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnrichedContact {
+    pub contact_id: Uuid,
+    /// Most recently active open deal of the contact, if any
+    pub deal_id: Option<Uuid>,
+}
+
+/// Normalize a phone number to E.164 (`+` followed by digits).
+///
+/// Spaces, dashes, dots and parentheses are dropped and a leading `00`
+/// international prefix becomes `+`. Numbers without a country code are
+/// rejected rather than guessed.
+pub fn normalize_phone(raw: &str) -> Option<String> {
+    let compact: String = raw
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')' | '\u{a0}'))
+        .collect();
+    let digits = compact
+        .strip_prefix('+')
+        .or_else(|| compact.strip_prefix("00"))?;
+
+    let valid = (E164_MIN_DIGITS..=E164_MAX_DIGITS).contains(&digits.len())
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && !digits.starts_with('0');
+    valid.then(|| format!("+{}", digits))
+}
+
+/// Bare, lowercased address of a person, or `None` for malformed and
+/// automated addresses. Accepts `Name <address>` as well.
+pub fn normalize_email(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    let address = match (raw.rfind('<'), raw.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &raw[start + 1..end],
+        _ => raw,
+    };
+    let address = address.trim().to_lowercase();
+
+    let (local, domain) = address.split_once('@')?;
+    if local.is_empty() || !domain.contains('.') || address.contains(char::is_whitespace) {
+        return None;
+    }
+    let local = local.split('+').next().unwrap_or(local);
+    if AUTOMATED_LOCAL_PARTS.contains(&local) {
+        return None;
+    }
+    Some(address)
+}
+
+fn parse_address_list(json: Option<&str>) -> Vec<String> {
+    json.and_then(|value| serde_json::from_str(value).ok())
+        .unwrap_or_default()
+}
+
+/// How far each sweep got, so unlinkable rows are not retried every pass
+#[derive(Debug, Default)]
+struct SweepCursor {
+    emails: Option<DateTime<Utc>>,
+    calls: Option<DateTime<Utc>>,
+    sms: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone)]
+pub struct CrmEnrichmentService {
+    pool: SqlitePool,
+}
+
+impl CrmEnrichmentService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Periodically link emails, calls and SMS that bypassed the hooks
+    pub async fn spawn(pool: SqlitePool) -> JoinHandle<()> {
+        let service = Self::new(pool);
+        tokio::spawn(async move {
+            service.start().await;
+        })
+    }
+
+    async fn start(&self) {
+        info!(
+            "Starting CRM enrichment service with interval {:?}",
+            SWEEP_INTERVAL
+        );
+
+        let mut cursor = SweepCursor::default();
+        let mut interval = interval(SWEEP_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match self.sweep(&mut cursor).await {
+                Ok(0) => {}
+                Ok(linked) => info!("Linked {} communications to CRM contacts", linked),
+                Err(e) => error!("CRM enrichment sweep failed: {}", e),
+            }
+        }
+    }
+
+    /// Link everything stored since the last pass; returns how many rows were linked
+    async fn sweep(&self, cursor: &mut SweepCursor) -> Result<usize, CrmEnrichmentError> {
+        let mut linked = 0;
+
+        let mut accounts: HashMap<Uuid, EmailAccount> = HashMap::new();
+        loop {
+            let messages =
+                EmailMessage::find_unlinked(&self.pool, cursor.emails, SWEEP_BATCH).await?;
+            let done = (messages.len() as i64) < SWEEP_BATCH;
+            for message in messages {
+                cursor.emails = Some(message.created_at);
+                let account = match accounts.entry(message.email_account_id) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(
+                        EmailAccount::find_by_id(&self.pool, message.email_account_id).await?,
+                    ),
+                };
+                match self.ingest_email(&message, account).await {
+                    Ok(contacts) if !contacts.is_empty() => linked += 1,
+                    Ok(_) => {}
+                    Err(e) => warn!("Failed to enrich email {}: {}", message.id, e),
+                }
+            }
+            if done {
+                break;
+            }
+        }
+
+        loop {
+            let calls = CallLog::find_unlinked(&self.pool, cursor.calls, SWEEP_BATCH).await?;
+            let done = (calls.len() as i64) < SWEEP_BATCH;
+            for call in calls {
+                cursor.calls = Some(call.created_at);
+                match self.ingest_call(&call).await {
+                    Ok(Some(_)) => linked += 1,
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to enrich call {}: {}", call.call_sid, e),
+                }
+            }
+            if done {
+                break;
+            }
+        }
+
+        loop {
+            let messages = SmsMessage::find_unlinked(&self.pool, cursor.sms, SWEEP_BATCH).await?;
+            let done = (messages.len() as i64) < SWEEP_BATCH;
+            for sms in messages {
+                cursor.sms = Some(sms.created_at);
+                match self.ingest_sms(&sms).await {
+                    Ok(Some(_)) => linked += 1,
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to enrich SMS {}: {}", sms.message_sid, e),
+                }
+            }
+            if done {
+                break;
+            }
+        }
+
+        Ok(linked)
+    }
+
+    /// File an email under the sender (inbound) or every To/Cc recipient
+    /// (sent). Drafts, spam, trash and already filed messages are skipped.
+    pub async fn ingest_email(
+        &self,
+        message: &EmailMessage,
+        account: &EmailAccount,
+    ) -> Result<Vec<EnrichedContact>, CrmEnrichmentError> {
+        if message.is_draft != 0 || message.is_spam != 0 || message.is_trash != 0 {
+            return Ok(Vec::new());
+        }
+        if !CrmActivity::find_by_email_message(&self.pool, message.id)
+            .await?
+            .is_empty()
+        {
+            return Ok(Vec::new());
+        }
+
+        let own_address = normalize_email(&account.email_address);
+        let direction = if message.is_sent != 0 {
+            Direction::Outbound
+        } else {
+            Direction::Inbound
+        };
+
+        let mut counterparts: Vec<(String, Option<&str>)> = Vec::new();
+        match direction {
+            Direction::Inbound => {
+                if let Some(address) = normalize_email(&message.from_address) {
+                    counterparts.push((address, message.from_name.as_deref()));
+                }
+            }
+            Direction::Outbound => {
+                let recipients = parse_address_list(Some(&message.to_addresses))
+                    .into_iter()
+                    .chain(parse_address_list(message.cc_addresses.as_deref()));
+                for address in recipients.filter_map(|r| normalize_email(&r)) {
+                    if !counterparts.iter().any(|(known, _)| *known == address) {
+                        counterparts.push((address, None));
+                    }
+                }
+            }
+        }
+        counterparts.retain(|(address, _)| Some(address) != own_address.as_ref());
+
+        let source = match account.provider.parse::<EmailProvider>() {
+            Ok(EmailProvider::Gmail) => ContactSource::GmailSync,
+            _ => ContactSource::Email,
+        };
+        let (activity_type, at) = match direction {
+            Direction::Inbound => (CrmActivityType::EmailReceived, message.received_at),
+            Direction::Outbound => (
+                CrmActivityType::EmailSent,
+                message.sent_at.unwrap_or(message.received_at),
+            ),
+        };
+
+        let mut enriched = Vec::with_capacity(counterparts.len());
+        for (address, name) in counterparts {
+            let contact = CrmContact::find_or_create_from_email(
+                &self.pool,
+                message.project_id,
+                &address,
+                name,
+                source,
+            )
+            .await?;
+            enriched.push(
+                self.file_interaction(
+                    contact.id,
+                    direction,
+                    true,
+                    CreateCrmActivity {
+                        project_id: message.project_id,
+                        crm_contact_id: None,
+                        crm_deal_id: None,
+                        activity_type,
+                        subject: message.subject.clone(),
+                        description: message.snippet.clone(),
+                        outcome: None,
+                        email_message_id: Some(message.id),
+                        social_mention_id: None,
+                        task_id: None,
+                        performed_by_user: None,
+                        performed_by_agent_id: None,
+                        metadata: None,
+                        duration_minutes: None,
+                        activity_at: Some(at),
+                    },
+                )
+                .await?,
+            );
+        }
+
+        if let Some(primary) = enriched.first()
+            && message.crm_contact_id.is_none()
+        {
+            EmailMessage::update(
+                &self.pool,
+                message.id,
+                UpdateEmailMessage {
+                    crm_contact_id: Some(primary.contact_id),
+                    crm_deal_id: primary.deal_id,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+
+        Ok(enriched)
+    }
+
+    /// File a call under the caller (inbound) or the number dialled
+    /// (outbound). Calls already linked to a contact are left alone.
+    pub async fn ingest_call(
+        &self,
+        call: &CallLog,
+    ) -> Result<Option<EnrichedContact>, CrmEnrichmentError> {
+        if call.crm_contact_id.is_some() {
+            return Ok(None);
+        }
+
+        let direction = Direction::from_twilio(&call.direction);
+        let number = match direction {
+            Direction::Inbound => &call.from_number,
+            Direction::Outbound => &call.to_number,
+        };
+        let Some(phone) = normalize_phone(number) else {
+            debug!("Call {} has no usable number ({})", call.call_sid, number);
+            return Ok(None);
+        };
+
+        let caller_name = match direction {
+            Direction::Inbound => call.caller_name.as_deref(),
+            Direction::Outbound => None,
+        };
+        let contact = CrmContact::find_or_create_from_phone(
+            &self.pool,
+            call.project_id,
+            &phone,
+            caller_name,
+            ContactSource::Api,
+        )
+        .await?;
+
+        let (activity_type, subject) = match direction {
+            Direction::Inbound => (CrmActivityType::CallReceived, "Inbound call"),
+            Direction::Outbound => (CrmActivityType::CallMade, "Outbound call"),
+        };
+        let enriched = self
+            .file_interaction(
+                contact.id,
+                direction,
+                false,
+                CreateCrmActivity {
+                    project_id: call.project_id,
+                    crm_contact_id: None,
+                    crm_deal_id: None,
+                    activity_type,
+                    subject: Some(subject.to_string()),
+                    description: call.summary.clone(),
+                    outcome: Some(call.status.clone()),
+                    email_message_id: None,
+                    social_mention_id: None,
+                    task_id: None,
+                    performed_by_user: None,
+                    performed_by_agent_id: call.handled_by_agent_id,
+                    metadata: Some(json!({
+                        "channel": "call",
+                        "call_log_id": call.id,
+                        "call_sid": call.call_sid,
+                    })),
+                    duration_minutes: call
+                        .duration_seconds
+                        .filter(|seconds| *seconds > 0)
+                        .map(|seconds| (seconds + 59) / 60),
+                    activity_at: Some(call.start_time.unwrap_or(call.created_at)),
+                },
+            )
+            .await?;
+
+        CallLog::update(
+            &self.pool,
+            call.id,
+            UpdateCallLog {
+                crm_contact_id: Some(enriched.contact_id),
+                crm_deal_id: enriched.deal_id,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(Some(enriched))
+    }
+
+    /// File a text message under the other party's number. Messages already
+    /// linked to a contact are left alone.
+    pub async fn ingest_sms(
+        &self,
+        sms: &SmsMessage,
+    ) -> Result<Option<EnrichedContact>, CrmEnrichmentError> {
+        if sms.crm_contact_id.is_some() {
+            return Ok(None);
+        }
+
+        let direction = Direction::from_twilio(&sms.direction);
+        let number = match direction {
+            Direction::Inbound => &sms.from_number,
+            Direction::Outbound => &sms.to_number,
+        };
+        let Some(phone) = normalize_phone(number) else {
+            debug!("SMS {} has no usable number ({})", sms.message_sid, number);
+            return Ok(None);
+        };
+
+        let contact = CrmContact::find_or_create_from_phone(
+            &self.pool,
+            sms.project_id,
+            &phone,
+            None,
+            ContactSource::Api,
+        )
+        .await?;
+
+        let subject = match direction {
+            Direction::Inbound => "SMS received",
+            Direction::Outbound => "SMS sent",
+        };
+        // crm_activities has no SMS type, so texts are custom activities
+        // tagged with their channel
+        let enriched = self
+            .file_interaction(
+                contact.id,
+                direction,
+                false,
+                CreateCrmActivity {
+                    project_id: sms.project_id,
+                    crm_contact_id: None,
+                    crm_deal_id: None,
+                    activity_type: CrmActivityType::Custom,
+                    subject: Some(subject.to_string()),
+                    description: Some(sms.body.clone()),
+                    outcome: Some(sms.status.clone()),
+                    email_message_id: None,
+                    social_mention_id: None,
+                    task_id: None,
+                    performed_by_user: None,
+                    performed_by_agent_id: sms.handled_by_agent_id,
+                    metadata: Some(json!({
+                        "channel": "sms",
+                        "sms_message_id": sms.id,
+                        "message_sid": sms.message_sid,
+                    })),
+                    duration_minutes: None,
+                    activity_at: Some(sms.date_sent.unwrap_or(sms.created_at)),
+                },
+            )
+            .await?;
+
+        SmsMessage::update(
+            &self.pool,
+            sms.id,
+            UpdateSmsMessage {
+                crm_contact_id: Some(enriched.contact_id),
+                crm_deal_id: enriched.deal_id,
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(Some(enriched))
+    }
+
+    /// Append the activity to the contact's timeline, touch its open deals
+    /// and move the contact's last-contacted or last-replied timestamp
+    async fn file_interaction(
+        &self,
+        contact_id: Uuid,
+        direction: Direction,
+        is_email: bool,
+        mut activity: CreateCrmActivity,
+    ) -> Result<EnrichedContact, CrmEnrichmentError> {
+        let at = *activity.activity_at.get_or_insert_with(Utc::now);
+
+        let open_deals = CrmDeal::find_open_by_contact(&self.pool, contact_id).await?;
+        for deal in &open_deals {
+            CrmDeal::record_activity(&self.pool, deal.id, at).await?;
+        }
+        let deal_id = open_deals.first().map(|deal| deal.id);
+
+        activity.crm_contact_id = Some(contact_id);
+        activity.crm_deal_id = deal_id;
+        CrmActivity::create(&self.pool, activity).await?;
+
+        match direction {
+            Direction::Outbound => {
+                CrmContact::record_contact_made_at(&self.pool, contact_id, at, is_email).await?
+            }
+            Direction::Inbound => {
+                CrmContact::record_reply_received_at(&self.pool, contact_id, at).await?
+            }
+        }
+
+        if let Err(e) = LeadScoringService::new(self.pool.clone())
+            .rescore_contact(contact_id, LeadScoreTrigger::Activity)
+            .await
+        {
+            warn!("Failed to rescore contact {}: {}", contact_id, e);
+        }
+
+        Ok(EnrichedContact {
+            contact_id,
+            deal_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_phone_numbers_to_e164() {
+        assert_eq!(
+            normalize_phone("+1 (415) 555-0100").as_deref(),
+            Some("+14155550100")
+        );
+        assert_eq!(
+            normalize_phone("0044 20 7946 0958").as_deref(),
+            Some("+442079460958")
+        );
+        assert_eq!(
+            normalize_phone("+44.20.7946.0958").as_deref(),
+            Some("+442079460958")
+        );
+    }
+
+    #[test]
+    fn rejects_numbers_without_country_code() {
+        assert_eq!(normalize_phone("(415) 555-0100"), None);
+        assert_eq!(normalize_phone("anonymous"), None);
+        assert_eq!(normalize_phone("+12345"), None);
+        assert_eq!(normalize_phone("+1234567890123456"), None);
+        assert_eq!(normalize_phone("+0441234567"), None);
+    }
+
+    #[test]
+    fn normalizes_email_addresses() {
+        assert_eq!(
+            normalize_email("Ada Lovelace <Ada@Example.com>").as_deref(),
+            Some("ada@example.com")
+        );
+        assert_eq!(
+            normalize_email(" bob@example.org ").as_deref(),
+            Some("bob@example.org")
+        );
+        assert_eq!(normalize_email("not an address"), None);
+        assert_eq!(normalize_email("root@localhost"), None);
+    }
+
+    #[test]
+    fn skips_automated_senders() {
+        assert_eq!(normalize_email("no-reply@example.com"), None);
+        assert_eq!(normalize_email("noreply+billing@example.com"), None);
+        assert_eq!(normalize_email("MAILER-DAEMON@mx.example.com"), None);
+        assert!(normalize_email("reply@example.com").is_some());
+    }
+
+    #[test]
+    fn reads_twilio_directions() {
+        assert_eq!(Direction::from_twilio("inbound"), Direction::Inbound);
+        assert_eq!(Direction::from_twilio("outbound-api"), Direction::Outbound);
+        assert_eq!(Direction::from_twilio("outbound-dial"), Direction::Outbound);
+    }
+}
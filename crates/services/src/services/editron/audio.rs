@@ -8,6 +8,7 @@
 //! - Beat detection and sync
 //! - Audio effects and filters
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use super::{EditronError, EditronResult};
@@ -48,8 +49,74 @@ impl LoudnessStandard {
             LoudnessStandard::Custom { true_peak, .. } => *true_peak,
         }
     }
+
+    /// Allowed deviation from the integrated target when verifying a render
+    pub fn tolerance_lu(&self) -> f32 {
+        match self {
+            // EBU R128 allows +/-0.5 LU for file-based programmes
+            LoudnessStandard::EbuR128Broadcast => 0.5,
+            LoudnessStandard::Custom { .. } => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    /// First pass: measure with `ebur128` (momentary/short-term maxima) and
+    /// `loudnorm` (integrated, LRA, true peak and gating threshold as JSON)
+    pub fn measurement_filter(&self) -> String {
+        format!(
+            "ebur128=framelog=info,loudnorm=I={}:TP={}:LRA=11:print_format=json",
+            self.target_lufs(),
+            self.true_peak_limit()
+        )
+    }
+
+    /// Second pass: linear normalisation using the first pass measurement.
+    ///
+    /// loudnorm only stays linear when the target LRA covers the measured one,
+    /// so the target is raised to the programme's own range. It still falls
+    /// back to dynamic mode if the gain would push true peak over the limit.
+    pub fn normalization_filter(&self, measured: &LoudnessMeasurement) -> String {
+        let lra = measured.loudness_range_lu.ceil().clamp(11.0, 50.0);
+        format!(
+            "loudnorm=I={}:TP={}:LRA={}:measured_I={:.2}:measured_TP={:.2}:measured_LRA={:.2}:measured_thresh={:.2}:offset={:.2}:linear=true:print_format=json",
+            self.target_lufs(),
+            self.true_peak_limit(),
+            lra,
+            measured.integrated_lufs,
+            measured.true_peak_db,
+            measured.loudness_range_lu,
+            measured.threshold_lufs,
+            measured.target_offset_lu
+        )
+    }
+
+    /// Ways a measurement breaks this standard (empty when compliant)
+    pub fn violations(&self, measured: &LoudnessMeasurement) -> Vec<String> {
+        let mut violations = Vec::new();
+        let deviation = measured.integrated_lufs - self.target_lufs();
+        if deviation.abs() > self.tolerance_lu() {
+            violations.push(format!(
+                "Integrated loudness {:.1} LUFS is {:+.1} LU from the {:.1} LUFS target (tolerance {:.1} LU)",
+                measured.integrated_lufs,
+                deviation,
+                self.target_lufs(),
+                self.tolerance_lu()
+            ));
+        }
+        if measured.true_peak_db > self.true_peak_limit() {
+            violations.push(format!(
+                "True peak {:.1} dBTP exceeds the {:.1} dBTP limit",
+                measured.true_peak_db,
+                self.true_peak_limit()
+            ));
+        }
+        violations
+    }
 }
 
+/// ebur128 reports silence (and frames before the first full window) at this level
+const EBUR128_FLOOR_LUFS: f32 = -120.7;
+
 /// Audio loudness measurement
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoudnessMeasurement {
@@ -58,6 +125,131 @@ pub struct LoudnessMeasurement {
     pub loudness_range_lu: f32,
     pub short_term_max_lufs: f32,
     pub momentary_max_lufs: f32,
+    /// Relative gating threshold, fed back into the second loudnorm pass
+    #[serde(default)]
+    pub threshold_lufs: f32,
+    /// Gain offset loudnorm computed for the target
+    #[serde(default)]
+    pub target_offset_lu: f32,
+}
+
+impl LoudnessMeasurement {
+    /// Parse the log of a [`LoudnessStandard::measurement_filter`] pass.
+    ///
+    /// The loudnorm JSON is required; ebur128 frame lines are optional and
+    /// only provide the momentary and short-term maxima.
+    pub fn from_ffmpeg_output(log: &str) -> EditronResult<Self> {
+        let json = loudnorm_json(log)?;
+        let field = |name: &str| -> EditronResult<f32> {
+            let value = json[name]
+                .as_str()
+                .ok_or_else(|| EditronError::FFmpeg(format!("loudnorm output is missing {}", name)))?;
+            let value: f32 = value
+                .trim()
+                .parse()
+                .map_err(|_| EditronError::FFmpeg(format!("Invalid loudnorm {}: {}", name, value)))?;
+            if !value.is_finite() {
+                return Err(EditronError::InvalidFormat(
+                    "No measurable programme loudness (is the audio silent?)".to_string(),
+                ));
+            }
+            Ok(value)
+        };
+
+        let mut momentary_max = EBUR128_FLOOR_LUFS;
+        let mut short_term_max = EBUR128_FLOOR_LUFS;
+        for line in log.lines().filter(|l| l.contains("Parsed_ebur128")) {
+            if let Some(m) = ebur128_value(line, "M:") {
+                momentary_max = momentary_max.max(m);
+            }
+            if let Some(s) = ebur128_value(line, "S:") {
+                short_term_max = short_term_max.max(s);
+            }
+        }
+
+        Ok(Self {
+            integrated_lufs: field("input_i")?,
+            true_peak_db: field("input_tp")?,
+            loudness_range_lu: field("input_lra")?,
+            short_term_max_lufs: short_term_max,
+            momentary_max_lufs: momentary_max,
+            threshold_lufs: field("input_thresh")?,
+            target_offset_lu: field("target_offset")?,
+        })
+    }
+}
+
+/// `linear` or `dynamic`, as reported by a [`LoudnessStandard::normalization_filter`] pass
+pub fn normalization_type(log: &str) -> Option<String> {
+    loudnorm_json(log)
+        .ok()?
+        .get("normalization_type")?
+        .as_str()
+        .map(|s| s.to_ascii_lowercase())
+}
+
+/// The JSON block loudnorm prints at the end of a pass
+fn loudnorm_json(log: &str) -> EditronResult<serde_json::Value> {
+    let missing = || EditronError::FFmpeg("No loudnorm statistics in ffmpeg output".to_string());
+    let key = log.rfind("\"input_i\"").ok_or_else(missing)?;
+    let start = log[..key].rfind('{').ok_or_else(missing)?;
+    let end = log[key..].find('}').map(|i| key + i + 1).ok_or_else(missing)?;
+    serde_json::from_str(&log[start..end])
+        .map_err(|e| EditronError::FFmpeg(format!("Invalid loudnorm statistics: {}", e)))
+}
+
+/// Value following `label` on an ebur128 frame line (`M: -23.1 S:-24.0`)
+fn ebur128_value(line: &str, label: &str) -> Option<f32> {
+    let rest = &line[line.find(label)? + label.len()..];
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+/// Loudness compliance record kept for each normalised render
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoudnessReport {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub standard: LoudnessStandard,
+    pub target_lufs: f32,
+    pub true_peak_limit: f32,
+    pub tolerance_lu: f32,
+    /// Measurement of the processed signal before normalisation
+    pub before: LoudnessMeasurement,
+    /// Measurement of the rendered output
+    pub after: LoudnessMeasurement,
+    /// `linear` when the measured gain was applied as is, `dynamic` when
+    /// loudnorm had to compress to respect the true peak limit
+    pub normalization_type: String,
+    pub violations: Vec<String>,
+    pub compliant: bool,
+    pub measured_at: DateTime<Utc>,
+}
+
+impl LoudnessReport {
+    pub fn new(
+        input: PathBuf,
+        output: PathBuf,
+        standard: LoudnessStandard,
+        before: LoudnessMeasurement,
+        after: LoudnessMeasurement,
+        normalization_type: String,
+    ) -> Self {
+        let violations = standard.violations(&after);
+        Self {
+            input,
+            output,
+            target_lufs: standard.target_lufs(),
+            true_peak_limit: standard.true_peak_limit(),
+            tolerance_lu: standard.tolerance_lu(),
+            standard,
+            before,
+            after,
+            normalization_type,
+            compliant: violations.is_empty(),
+            violations,
+            measured_at: Utc::now(),
+        }
+    }
 }
 
 /// Compression settings
@@ -485,8 +677,32 @@ impl AudioProcessingEngine {
         ]
     }
 
-    /// Generate FFmpeg audio filter string
+    /// Generate FFmpeg audio filter string.
+    ///
+    /// Loudness is normalised in a single `loudnorm` pass here, which is fine
+    /// for previews; deliverables go through the two-pass
+    /// [`super::EditronService::apply_audio_processing`].
     pub fn to_ffmpeg_filter(&self, preset: &AudioProcessingPreset) -> String {
+        let mut filters = self.processing_filters(preset);
+
+        // Loudness normalization
+        if let Some(loudness) = &preset.loudness {
+            filters.push(format!(
+                "loudnorm=I={}:TP={}:LRA=11",
+                loudness.target_lufs(),
+                loudness.true_peak_limit()
+            ));
+        }
+
+        if filters.is_empty() {
+            "anull".to_string()
+        } else {
+            filters.join(",")
+        }
+    }
+
+    /// Preset filters that run ahead of loudness normalisation (EQ, dynamics, gate)
+    pub fn processing_filters(&self, preset: &AudioProcessingPreset) -> Vec<String> {
         let mut filters = Vec::new();
 
         // High-pass filter
@@ -551,27 +767,16 @@ impl AudioProcessingEngine {
             ));
         }
 
-        // Loudness normalization
-        if let Some(loudness) = &preset.loudness {
-            filters.push(format!(
-                "loudnorm=I={}:TP={}:LRA=11",
-                loudness.target_lufs(),
-                loudness.true_peak_limit()
-            ));
-        }
-
-        if filters.is_empty() {
-            "anull".to_string()
-        } else {
-            filters.join(",")
-        }
+        filters
     }
 
-    /// Generate FFmpeg command for loudness measurement
+    /// Generate FFmpeg command for loudness measurement; its output parses
+    /// with [`LoudnessMeasurement::from_ffmpeg_output`]
     pub fn loudness_measure_command<P: AsRef<Path>>(input: P) -> String {
         format!(
-            "ffmpeg -i \"{}\" -af loudnorm=I=-16:TP=-1.5:LRA=11:print_format=json -f null -",
-            input.as_ref().display()
+            "ffmpeg -hide_banner -nostats -i \"{}\" -vn -af {} -f null -",
+            input.as_ref().display(),
+            LoudnessStandard::EbuR128Broadcast.measurement_filter()
         )
     }
 
@@ -626,4 +831,62 @@ mod tests {
         let filter = engine.to_ffmpeg_filter(preset);
         assert!(filter.contains("loudnorm"));
     }
+
+    const MEASURE_LOG: &str = r#"[Parsed_ebur128_0 @ 0x55d0c8c0a1c0] t: 0.4        TARGET:-23 LUFS    M:-120.7 S:-120.7     I: -70.0 LUFS       LRA:   0.0 LU
+[Parsed_ebur128_0 @ 0x55d0c8c0a1c0] t: 3.0        TARGET:-23 LUFS    M: -17.9 S: -21.3     I: -20.1 LUFS       LRA:   3.2 LU
+[Parsed_ebur128_0 @ 0x55d0c8c0a1c0] t: 3.1        TARGET:-23 LUFS    M: -18.4 S: -20.8     I: -20.1 LUFS       LRA:   3.2 LU
+[Parsed_loudnorm_1 @ 0x55d0c8c0b040] 
+{
+	"input_i" : "-20.14",
+	"input_tp" : "-2.31",
+	"input_lra" : "14.60",
+	"input_thresh" : "-30.52",
+	"output_i" : "-24.02",
+	"output_tp" : "-6.10",
+	"output_lra" : "10.80",
+	"output_thresh" : "-34.35",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.02"
+}
+"#;
+
+    #[test]
+    fn test_parse_loudness_measurement() {
+        let m = LoudnessMeasurement::from_ffmpeg_output(MEASURE_LOG).unwrap();
+        assert_eq!(m.integrated_lufs, -20.14);
+        assert_eq!(m.true_peak_db, -2.31);
+        assert_eq!(m.loudness_range_lu, 14.6);
+        assert_eq!(m.threshold_lufs, -30.52);
+        assert_eq!(m.target_offset_lu, 0.02);
+        assert_eq!(m.momentary_max_lufs, -17.9);
+        assert_eq!(m.short_term_max_lufs, -20.8);
+        assert_eq!(normalization_type(MEASURE_LOG).as_deref(), Some("dynamic"));
+
+        let silent = MEASURE_LOG.replace("\"-20.14\"", "\"-inf\"");
+        assert!(LoudnessMeasurement::from_ffmpeg_output(&silent).is_err());
+        assert!(LoudnessMeasurement::from_ffmpeg_output("no stats").is_err());
+    }
+
+    #[test]
+    fn test_two_pass_filter_uses_measurement() {
+        let m = LoudnessMeasurement::from_ffmpeg_output(MEASURE_LOG).unwrap();
+        let filter = LoudnessStandard::EbuR128Broadcast.normalization_filter(&m);
+        assert!(filter.starts_with("loudnorm=I=-24:TP=-1:LRA=15:"));
+        assert!(filter.contains("measured_I=-20.14:measured_TP=-2.31:measured_LRA=14.60:measured_thresh=-30.52:offset=0.02"));
+        assert!(filter.contains("linear=true"));
+    }
+
+    #[test]
+    fn test_loudness_violations() {
+        let mut m = LoudnessMeasurement::from_ffmpeg_output(MEASURE_LOG).unwrap();
+        m.integrated_lufs = -23.7;
+        m.true_peak_db = -1.2;
+        assert!(LoudnessStandard::EbuR128Broadcast.violations(&m).is_empty());
+
+        m.integrated_lufs = -22.9;
+        m.true_peak_db = -0.4;
+        let violations = LoudnessStandard::EbuR128Broadcast.violations(&m);
+        assert_eq!(violations.len(), 2);
+        assert!(violations[1].contains("True peak"));
+    }
 }
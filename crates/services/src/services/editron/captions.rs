@@ -0,0 +1,697 @@
+//! Captions and Subtitles for Editron
+//!
+//! Turns the dialogue of a clip or an assembled edit into caption files:
+//! - Word-timed transcription through a pluggable [`Transcriber`]
+//! - Cue segmentation honouring line length, line count and reading speed
+//! - SRT and WebVTT output (and parsing, for hand-made caption files)
+//! - Styled burn-in via the `subtitles` filter or soft subtitle tracks
+
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{EditronError, EditronResult, edit_assembly::AssembledEdit};
+
+/// Longest stretch of audio sent to a [`Transcriber`] at once; 10 minutes of
+/// 16 kHz mono PCM stays under the 25 MB upload limit of hosted Whisper
+pub const TRANSCRIPTION_CHUNK_SECONDS: f64 = 600.0;
+
+/// A transcribed word, timed relative to the start of the transcribed audio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionWord {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+/// Speech-to-text backend used for captioning
+#[async_trait]
+pub trait Transcriber: Send + Sync {
+    /// Transcribe a 16 kHz mono WAV file into word-timed text
+    async fn transcribe(&self, wav_path: &Path) -> EditronResult<Vec<CaptionWord>>;
+}
+
+/// Segmentation and timing rules for caption cues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionRules {
+    /// Maximum characters on one caption line
+    pub max_chars_per_line: usize,
+    /// Maximum lines shown at once
+    pub max_lines: usize,
+    /// Reading speed limit; short cues are held longer to stay under it
+    pub max_chars_per_second: f32,
+    pub min_duration_ms: u64,
+    pub max_duration_ms: u64,
+    /// Minimum blank time between consecutive cues
+    pub min_gap_ms: u64,
+    /// A pause in speech at least this long always starts a new cue
+    pub pause_break_ms: u64,
+}
+
+impl CaptionRules {
+    /// Broadcast/streaming guidelines: 42 characters, 2 lines, 20 cps
+    pub fn broadcast() -> Self {
+        Self {
+            max_chars_per_line: 42,
+            max_lines: 2,
+            max_chars_per_second: 20.0,
+            min_duration_ms: 833,
+            max_duration_ms: 7000,
+            min_gap_ms: 83,
+            pause_break_ms: 700,
+        }
+    }
+
+    /// Short, punchy lines for vertical social video
+    pub fn social() -> Self {
+        Self {
+            max_chars_per_line: 24,
+            max_lines: 2,
+            max_chars_per_second: 17.0,
+            min_duration_ms: 700,
+            max_duration_ms: 4000,
+            min_gap_ms: 83,
+            pause_break_ms: 500,
+        }
+    }
+}
+
+impl Default for CaptionRules {
+    fn default() -> Self {
+        Self::broadcast()
+    }
+}
+
+/// One caption shown on screen
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptionCue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub lines: Vec<String>,
+}
+
+impl CaptionCue {
+    pub fn text(&self) -> String {
+        self.lines.join(" ")
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.end_ms.saturating_sub(self.start_ms)
+    }
+
+    pub fn chars_per_second(&self) -> f32 {
+        let chars = self.text().chars().count() as f32;
+        chars / (self.duration_ms().max(1) as f32 / 1000.0)
+    }
+}
+
+/// A timed caption track in one language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionTrack {
+    /// ISO 639-2 code used for the soft subtitle track, e.g. `eng`
+    pub language: String,
+    pub cues: Vec<CaptionCue>,
+}
+
+/// Sidecar caption files written for a track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionFiles {
+    pub srt: PathBuf,
+    pub vtt: PathBuf,
+}
+
+impl CaptionTrack {
+    /// Build cues from word timings following `rules`
+    pub fn from_words(words: &[CaptionWord], rules: &CaptionRules, language: &str) -> Self {
+        let mut cues = Vec::new();
+        let mut current: Vec<&CaptionWord> = Vec::new();
+
+        for word in words.iter().filter(|w| !w.text.trim().is_empty()) {
+            if let (Some(first), Some(last)) = (current.first(), current.last()) {
+                let gap = word.start_ms.saturating_sub(last.end_ms);
+                let duration = word.end_ms.saturating_sub(first.start_ms);
+                let current_len = joined_len(&current);
+                let sentence_done =
+                    ends_sentence(&last.text) && current_len >= rules.max_chars_per_line / 2;
+
+                let mut candidate: Vec<&str> = current.iter().map(|w| w.text.trim()).collect();
+                candidate.push(word.text.trim());
+                let fits =
+                    wrap_lines(&candidate, rules.max_chars_per_line, rules.max_lines).is_some();
+
+                if !fits
+                    || duration > rules.max_duration_ms
+                    || gap >= rules.pause_break_ms
+                    || sentence_done
+                {
+                    cues.push(build_cue(&current, rules));
+                    current.clear();
+                }
+            }
+            current.push(word);
+        }
+        if !current.is_empty() {
+            cues.push(build_cue(&current, rules));
+        }
+
+        apply_timing_rules(&mut cues, rules);
+        Self {
+            language: language.to_string(),
+            cues,
+        }
+    }
+
+    /// Shift every cue by `offset_ms` (clamped at zero)
+    pub fn shifted(mut self, offset_ms: i64) -> Self {
+        for cue in &mut self.cues {
+            cue.start_ms = (cue.start_ms as i64 + offset_ms).max(0) as u64;
+            cue.end_ms = (cue.end_ms as i64 + offset_ms).max(0) as u64;
+        }
+        self
+    }
+
+    pub fn to_srt(&self) -> String {
+        let mut out = String::new();
+        for (i, cue) in self.cues.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                format_timestamp(cue.start_ms, ','),
+                format_timestamp(cue.end_ms, ','),
+                cue.lines.join("\n")
+            );
+        }
+        out
+    }
+
+    pub fn to_webvtt(&self) -> String {
+        let mut out = String::from("WEBVTT\n\n");
+        for cue in &self.cues {
+            let text = cue
+                .lines
+                .iter()
+                .map(|line| escape_vtt(line))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let _ = writeln!(
+                out,
+                "{} --> {}\n{}\n",
+                format_timestamp(cue.start_ms, '.'),
+                format_timestamp(cue.end_ms, '.'),
+                text
+            );
+        }
+        out
+    }
+
+    /// Parse an SRT or WebVTT document
+    pub fn parse(content: &str, language: &str) -> EditronResult<Self> {
+        let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+        let mut cues = Vec::new();
+
+        for block in content.split("\n\n") {
+            let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+            let Some(timing) = lines.next() else {
+                continue;
+            };
+            let mut parts = timing.split("-->");
+            let (Some(start), Some(end)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            // WebVTT cue settings follow the end time
+            let end = end.split_whitespace().next().unwrap_or_default();
+            let start_ms = parse_timestamp(start.trim())
+                .ok_or_else(|| EditronError::InvalidFormat(format!("Bad cue time: {timing}")))?;
+            let end_ms = parse_timestamp(end)
+                .ok_or_else(|| EditronError::InvalidFormat(format!("Bad cue time: {timing}")))?;
+            let text: Vec<String> = lines
+                .map(|l| unescape_vtt(l.trim()))
+                .filter(|l| !l.is_empty())
+                .collect();
+            if !text.is_empty() {
+                cues.push(CaptionCue {
+                    start_ms,
+                    end_ms,
+                    lines: text,
+                });
+            }
+        }
+
+        if cues.is_empty() {
+            return Err(EditronError::InvalidFormat(
+                "No caption cues found".to_string(),
+            ));
+        }
+        Ok(Self {
+            language: language.to_string(),
+            cues,
+        })
+    }
+
+    /// Write `<stem>.srt` and `<stem>.vtt` into `dir`
+    pub async fn write_files(&self, dir: &Path, stem: &str) -> EditronResult<CaptionFiles> {
+        tokio::fs::create_dir_all(dir).await?;
+        let srt = dir.join(format!("{stem}.srt"));
+        let vtt = dir.join(format!("{stem}.vtt"));
+        tokio::fs::write(&srt, self.to_srt()).await?;
+        tokio::fs::write(&vtt, self.to_webvtt()).await?;
+        Ok(CaptionFiles { srt, vtt })
+    }
+}
+
+/// Result of delivering captions with a render
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionedRender {
+    /// The captioned video; the untouched input for sidecar-only delivery
+    pub video: PathBuf,
+    pub sidecars: CaptionFiles,
+}
+
+/// How an export preset delivers captions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionDelivery {
+    /// Only write SRT/WebVTT files next to the render
+    #[default]
+    Sidecar,
+    /// Render styled captions into the picture
+    BurnIn,
+    /// Mux a selectable subtitle track into the container
+    SoftTrack,
+}
+
+/// Vertical placement of burned-in captions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptionPlacement {
+    Bottom,
+    /// Lower third, clear of platform UI on vertical video
+    LowerMiddle,
+    Top,
+}
+
+/// Look of burned-in captions, rendered by libass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptionStyle {
+    pub font_name: String,
+    /// In libass script units (the subtitle canvas is 288 units tall)
+    pub font_size: u32,
+    pub bold: bool,
+    /// Text colour as `RRGGBB`
+    pub primary_color: String,
+    /// Outline colour as `RRGGBB`
+    pub outline_color: String,
+    pub outline: f32,
+    pub shadow: f32,
+    /// Draw an opaque box in this colour (`RRGGBB`) instead of an outline
+    pub box_color: Option<String>,
+    pub placement: CaptionPlacement,
+    pub margin_v: u32,
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            font_name: "Arial".to_string(),
+            font_size: 16,
+            bold: false,
+            primary_color: "FFFFFF".to_string(),
+            outline_color: "000000".to_string(),
+            outline: 1.5,
+            shadow: 0.0,
+            box_color: None,
+            placement: CaptionPlacement::Bottom,
+            margin_v: 20,
+        }
+    }
+}
+
+impl CaptionStyle {
+    /// Large bold captions for Reels/TikTok/Shorts
+    pub fn social() -> Self {
+        Self {
+            font_name: "Montserrat".to_string(),
+            font_size: 20,
+            bold: true,
+            outline: 2.5,
+            shadow: 1.0,
+            placement: CaptionPlacement::LowerMiddle,
+            margin_v: 70,
+            ..Self::default()
+        }
+    }
+
+    /// `force_style` value for FFmpeg's `subtitles` filter
+    pub fn to_force_style(&self) -> String {
+        let alignment = match self.placement {
+            CaptionPlacement::Bottom | CaptionPlacement::LowerMiddle => 2,
+            CaptionPlacement::Top => 8,
+        };
+        let mut style = format!(
+            "FontName={},FontSize={},Bold={},PrimaryColour={},OutlineColour={},Outline={},Shadow={},Alignment={},MarginV={}",
+            self.font_name,
+            self.font_size,
+            if self.bold { -1 } else { 0 },
+            ass_color(&self.primary_color),
+            ass_color(&self.outline_color),
+            self.outline,
+            self.shadow,
+            alignment,
+            self.margin_v,
+        );
+        match &self.box_color {
+            Some(color) => {
+                let _ = write!(style, ",BorderStyle=3,BackColour={}", ass_color(color));
+            }
+            None => style.push_str(",BorderStyle=1"),
+        }
+        style
+    }
+
+    /// Complete `subtitles` filter burning `subtitle_path` in with this style
+    pub fn to_ffmpeg_filter(&self, subtitle_path: &Path) -> String {
+        format!(
+            "subtitles=filename='{}':force_style='{}'",
+            escape_filter_path(subtitle_path),
+            self.to_force_style()
+        )
+    }
+}
+
+/// A stretch of dialogue on an edit's timeline, read from a source file
+#[derive(Debug, Clone)]
+pub struct DialogueSegment {
+    pub source: PathBuf,
+    pub source_in: f64,
+    pub source_out: f64,
+    pub timeline_in: f64,
+    pub speed: f32,
+}
+
+impl DialogueSegment {
+    /// Map a time in the extracted segment audio onto the edit timeline
+    pub fn to_timeline_ms(&self, segment_ms: u64) -> u64 {
+        let speed = if self.speed > 0.0 {
+            self.speed as f64
+        } else {
+            1.0
+        };
+        ((self.timeline_in + segment_ms as f64 / 1000.0 / speed) * 1000.0).round() as u64
+    }
+}
+
+/// Dialogue sources of an edit: its non-music audio clips, or the footage's own
+/// sound on the primary video track when there are none
+pub fn dialogue_segments(edit: &AssembledEdit) -> Vec<DialogueSegment> {
+    let mut segments: Vec<DialogueSegment> = edit
+        .audio_clips
+        .iter()
+        .filter(|clip| !clip.is_music)
+        .map(|clip| DialogueSegment {
+            source: clip.source.clone(),
+            source_in: clip.source_in,
+            source_out: clip.source_out,
+            timeline_in: clip.timeline_in,
+            speed: 1.0,
+        })
+        .collect();
+
+    if segments.is_empty() {
+        let primary_track = edit.video_clips.iter().map(|c| c.track).min().unwrap_or(1);
+        segments = edit
+            .video_clips
+            .iter()
+            .filter(|clip| clip.track == primary_track)
+            .map(|clip| DialogueSegment {
+                source: clip.source.clone(),
+                source_in: clip.source_in,
+                source_out: clip.source_out,
+                timeline_in: clip.timeline_in,
+                speed: clip.speed,
+            })
+            .collect();
+    }
+
+    segments.sort_by(|a, b| a.timeline_in.total_cmp(&b.timeline_in));
+    segments
+}
+
+fn joined_len(words: &[&CaptionWord]) -> usize {
+    words
+        .iter()
+        .map(|w| w.text.trim().chars().count())
+        .sum::<usize>()
+        + words.len().saturating_sub(1)
+}
+
+fn ends_sentence(word: &str) -> bool {
+    word.trim_end_matches(['"', '\'', ')', '”', '’'])
+        .ends_with(['.', '?', '!', '…'])
+}
+
+/// Break words into at most `max_lines` lines of `max_chars` characters,
+/// balancing two-line cues. `None` when the words do not fit.
+fn wrap_lines(words: &[&str], max_chars: usize, max_lines: usize) -> Option<Vec<String>> {
+    let total =
+        words.iter().map(|w| w.chars().count()).sum::<usize>() + words.len().saturating_sub(1);
+    if total <= max_chars {
+        return Some(vec![words.join(" ")]);
+    }
+
+    if max_lines == 2 {
+        // Pick the split whose lines are closest in length, preferring a
+        // shorter top line (bottom-heavy pyramid)
+        let best = (1..words.len())
+            .filter_map(|split| {
+                let top = words[..split].join(" ");
+                let bottom = words[split..].join(" ");
+                let (t, b) = (top.chars().count(), bottom.chars().count());
+                (t <= max_chars && b <= max_chars).then_some((t.abs_diff(b), t > b, top, bottom))
+            })
+            .min_by_key(|(diff, top_longer, _, _)| (*diff, *top_longer))?;
+        return Some(vec![best.2, best.3]);
+    }
+
+    let mut lines: Vec<String> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= max_chars => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => {
+                if word.chars().count() > max_chars {
+                    return None;
+                }
+                lines.push(word.to_string());
+            }
+        }
+    }
+    (lines.len() <= max_lines).then_some(lines)
+}
+
+fn build_cue(words: &[&CaptionWord], rules: &CaptionRules) -> CaptionCue {
+    let texts: Vec<&str> = words.iter().map(|w| w.text.trim()).collect();
+    // A single over-long word still gets a cue of its own
+    let lines = wrap_lines(&texts, rules.max_chars_per_line, rules.max_lines)
+        .unwrap_or_else(|| vec![texts.join(" ")]);
+    CaptionCue {
+        start_ms: words.first().map(|w| w.start_ms).unwrap_or(0),
+        end_ms: words.last().map(|w| w.end_ms).unwrap_or(0),
+        lines,
+    }
+}
+
+/// Hold cues long enough to read (minimum duration and reading speed) without
+/// running into the next cue, and cap overly long ones
+fn apply_timing_rules(cues: &mut [CaptionCue], rules: &CaptionRules) {
+    for i in 0..cues.len() {
+        let next_start = cues.get(i + 1).map(|c| c.start_ms);
+        let cue = &mut cues[i];
+
+        let chars = cue.text().chars().count() as f32;
+        let reading_ms = (chars / rules.max_chars_per_second * 1000.0).ceil() as u64;
+        let wanted = reading_ms
+            .max(rules.min_duration_ms)
+            .min(rules.max_duration_ms);
+        if cue.duration_ms() < wanted {
+            cue.end_ms = cue.start_ms + wanted;
+        }
+        if cue.duration_ms() > rules.max_duration_ms {
+            cue.end_ms = cue.start_ms + rules.max_duration_ms;
+        }
+        if let Some(next_start) = next_start {
+            let limit = next_start.saturating_sub(rules.min_gap_ms);
+            if cue.end_ms > limit {
+                cue.end_ms = limit.max(cue.start_ms + 1);
+            }
+        }
+    }
+}
+
+fn format_timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+/// `HH:MM:SS,mmm`, `HH:MM:SS.mmm` or WebVTT's short `MM:SS.mmm`
+fn parse_timestamp(value: &str) -> Option<u64> {
+    let (clock, millis) = value.rsplit_once([',', '.'])?;
+    let millis: u64 = millis.parse().ok()?;
+    let parts: Vec<u64> = clock
+        .split(':')
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let (h, m, s) = match parts.as_slice() {
+        [h, m, s] => (*h, *m, *s),
+        [m, s] => (0, *m, *s),
+        _ => return None,
+    };
+    Some(((h * 60 + m) * 60 + s) * 1000 + millis)
+}
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn unescape_vtt(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// `RRGGBB` to libass `&HAABBGGRR`
+fn ass_color(hex: &str) -> String {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return "&H00FFFFFF".to_string();
+    }
+    format!("&H00{}{}{}", &hex[4..6], &hex[2..4], &hex[0..2]).to_uppercase()
+}
+
+/// Escape a path for use inside a quoted filtergraph option value
+pub(super) fn escape_filter_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "/")
+        .replace(':', "\\:")
+        .replace('\'', "'\\''")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(spec: &[(&str, u64, u64)]) -> Vec<CaptionWord> {
+        spec.iter()
+            .map(|(text, start, end)| CaptionWord {
+                text: text.to_string(),
+                start_ms: *start,
+                end_ms: *end,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_segmentation_respects_line_rules() {
+        let input = words(&[
+            ("Welcome", 0, 400),
+            ("back", 400, 700),
+            ("to", 700, 800),
+            ("the", 800, 900),
+            ("channel,", 900, 1300),
+            ("today", 1300, 1700),
+            ("we", 1700, 1800),
+            ("are", 1800, 1900),
+            ("building", 1900, 2400),
+            ("a", 2400, 2450),
+            ("camera", 2450, 2900),
+            ("rig.", 2900, 3300),
+        ]);
+        let rules = CaptionRules::social();
+        let track = CaptionTrack::from_words(&input, &rules, "eng");
+
+        assert!(track.cues.len() >= 2);
+        for cue in &track.cues {
+            assert!(cue.lines.len() <= rules.max_lines);
+            assert!(
+                cue.lines
+                    .iter()
+                    .all(|l| l.chars().count() <= rules.max_chars_per_line)
+            );
+        }
+        let all: Vec<String> = track.cues.iter().map(|c| c.text()).collect();
+        assert_eq!(
+            all.join(" "),
+            "Welcome back to the channel, today we are building a camera rig."
+        );
+    }
+
+    #[test]
+    fn test_pause_starts_new_cue_and_gaps_are_kept() {
+        let input = words(&[("Hi.", 0, 300), ("Later", 2000, 2400), ("on", 2400, 2600)]);
+        let track = CaptionTrack::from_words(&input, &CaptionRules::broadcast(), "eng");
+        assert_eq!(track.cues.len(), 2);
+        // Held for the minimum duration but never overlapping the next cue
+        assert_eq!(track.cues[0].end_ms, 833);
+        assert!(track.cues[0].end_ms + 83 <= track.cues[1].start_ms);
+    }
+
+    #[test]
+    fn test_reading_speed_extends_fast_cues() {
+        let input = words(&[
+            ("Unbelievably", 0, 200),
+            ("quick", 200, 300),
+            ("speech", 300, 400),
+        ]);
+        let rules = CaptionRules::broadcast();
+        let track = CaptionTrack::from_words(&input, &rules, "eng");
+        assert!(track.cues[0].chars_per_second() <= rules.max_chars_per_second);
+    }
+
+    #[test]
+    fn test_srt_and_vtt_round_trip() {
+        let track = CaptionTrack {
+            language: "eng".to_string(),
+            cues: vec![CaptionCue {
+                start_ms: 3_723_004,
+                end_ms: 3_725_500,
+                lines: vec!["Fish & chips".to_string(), "<loud>".to_string()],
+            }],
+        };
+        let srt = track.to_srt();
+        assert!(srt.starts_with("1\n01:02:03,004 --> 01:02:05,500\nFish & chips\n<loud>\n"));
+        let vtt = track.to_webvtt();
+        assert!(vtt.starts_with(
+            "WEBVTT\n\n01:02:03.004 --> 01:02:05.500\nFish &amp; chips\n&lt;loud&gt;"
+        ));
+
+        assert_eq!(CaptionTrack::parse(&srt, "eng").unwrap().cues, track.cues);
+        assert_eq!(CaptionTrack::parse(&vtt, "eng").unwrap().cues, track.cues);
+    }
+
+    #[test]
+    fn test_burn_in_filter() {
+        let style = CaptionStyle {
+            box_color: Some("101010".to_string()),
+            ..CaptionStyle::social()
+        };
+        let filter = style.to_ffmpeg_filter(Path::new("C:\\work\\it's.srt"));
+        assert!(filter.starts_with("subtitles=filename='C\\:/work/it'\\''s.srt'"));
+        assert!(filter.contains("PrimaryColour=&H00FFFFFF"));
+        assert!(filter.contains("BorderStyle=3,BackColour=&H00101010"));
+        assert!(filter.contains("Bold=-1"));
+    }
+}
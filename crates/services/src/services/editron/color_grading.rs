@@ -7,10 +7,15 @@
 //! - HSL secondary corrections
 //! - Color matching between clips
 //! - Lumetri-style color presets
+//! - Baking a preset and its LUT into a single `.cube` for export and preview
 
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 use super::{EditronError, EditronResult};
+use super::lut::{CubeLut, LutInterpolation};
+
+/// Lattice size for baked LUTs; 33 is the common delivery size
+pub const BAKED_LUT_SIZE: usize = 33;
 
 /// LUT (Look-Up Table) for color grading
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -139,6 +144,210 @@ pub struct BasicColorCorrection {
     pub saturation: f32,     // -100 to 100
 }
 
+impl ColorGradePreset {
+    /// Grade one display-referred RGB value (0.0-1.0), in Lumetri order: the
+    /// preset's LUT as the input transform, then basic correction, wheels,
+    /// curves and HSL secondaries. Vignettes are spatial and are left to the
+    /// ffmpeg filter chain.
+    pub fn grade_rgb(&self, lut: Option<&CubeLut>, rgb: [f32; 3]) -> [f32; 3] {
+        let mut c = rgb;
+
+        if let (Some(lut), Some(settings)) = (lut, &self.lut) {
+            let looked_up = lut.apply(c, LutInterpolation::Tetrahedral);
+            let t = settings.intensity.clamp(0.0, 1.0);
+            for i in 0..3 {
+                c[i] += (looked_up[i] - c[i]) * t;
+            }
+        }
+
+        c = self.basic.apply(c);
+        c = self.wheels.apply(c);
+        c = self.curves.apply(c);
+        for secondary in self.secondaries.iter().filter(|s| s.enabled) {
+            c = secondary.apply(c);
+        }
+
+        c.map(|v| v.clamp(0.0, 1.0))
+    }
+}
+
+impl BasicColorCorrection {
+    fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let exposure = 2f32.powf(self.exposure);
+        // Warm pushes red up and blue down; tint trades green against magenta
+        let warmth = self.temperature / 100.0 * 0.1;
+        let tint = self.tint / 100.0 * 0.1;
+        let balance = [1.0 + warmth, 1.0 - tint, 1.0 - warmth];
+        let contrast = 1.0 + self.contrast / 100.0;
+
+        let mut c = [0.0f32; 3];
+        for i in 0..3 {
+            let mut v = rgb[i] * exposure * balance[i];
+            v = (v - 0.5) * contrast + 0.5;
+            v = v * (1.0 + self.whites / 100.0 * 0.1) + self.blacks / 100.0 * 0.1 * (1.0 - v);
+            c[i] = v;
+        }
+
+        // Tonal range adjustments weighted by luma
+        let l = luma(c).clamp(0.0, 1.0);
+        let tonal = self.shadows / 100.0 * 0.2 * (1.0 - l).powi(2) + self.highlights / 100.0 * 0.2 * l.powi(2);
+        for v in &mut c {
+            *v += tonal;
+        }
+
+        // Vibrance boosts muted colours more than saturated ones
+        let l = luma(c);
+        let max = c.iter().cloned().fold(f32::MIN, f32::max);
+        let min = c.iter().cloned().fold(f32::MAX, f32::min);
+        let chroma = (max - min).clamp(0.0, 1.0);
+        let saturation = (1.0 + self.saturation / 100.0) * (1.0 + self.vibrance / 100.0 * (1.0 - chroma));
+        c.map(|v| l + (v - l) * saturation)
+    }
+}
+
+impl ColorWheels {
+    fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let lift = [self.lift.red, self.lift.green, self.lift.blue].map(|v| v + self.lift.luminance);
+        let gamma = [self.gamma.red, self.gamma.green, self.gamma.blue].map(|v| v + self.gamma.luminance);
+        let gain = [self.gain.red, self.gain.green, self.gain.blue].map(|v| v + self.gain.luminance);
+
+        let mut c = rgb;
+        for i in 0..3 {
+            let v = (1.0 + gain[i]) * (c[i] + lift[i] * (1.0 - c[i]));
+            c[i] = v.max(0.0).powf(1.0 / (1.0 + gamma[i]).max(0.1));
+        }
+        c
+    }
+}
+
+impl ColorCurves {
+    /// Master then per-channel curves. Hue curves need a hue-keyed model
+    /// and aren't baked.
+    fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let channels = [&self.red, &self.green, &self.blue];
+        let mut c = rgb;
+        for i in 0..3 {
+            c[i] = evaluate_curve(channels[i], evaluate_curve(&self.master, c[i]));
+        }
+        c
+    }
+}
+
+impl HSLSecondary {
+    fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let (h, s, l) = rgb_to_hsl(rgb);
+        let softness = self.softness.max(0.001);
+        let weight = range_weight(hue_distance(h, self.hue_range), softness * 60.0)
+            * range_weight(outside(s, self.sat_range), softness)
+            * range_weight(outside(l, self.lum_range), softness);
+        if weight <= 0.0 {
+            return rgb;
+        }
+
+        let adj = &self.adjustments;
+        let graded = hsl_to_rgb(
+            (h + adj.hue_shift).rem_euclid(360.0),
+            (s * (1.0 + adj.saturation)).clamp(0.0, 1.0),
+            (l + adj.luminance * 0.5).clamp(0.0, 1.0),
+        );
+        let mut c = rgb;
+        for i in 0..3 {
+            c[i] += (graded[i] - c[i]) * weight;
+        }
+        c
+    }
+}
+
+/// Rec.709 luma
+fn luma([r, g, b]: [f32; 3]) -> f32 {
+    0.2126 * r + 0.7152 * g + 0.0722 * b
+}
+
+/// Piecewise-linear curve lookup; empty curves are the identity
+fn evaluate_curve(points: &[CurvePoint], x: f32) -> f32 {
+    if points.is_empty() {
+        return x;
+    }
+    let mut sorted: Vec<&CurvePoint> = points.iter().collect();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x));
+    let first = sorted[0];
+    let last = sorted[sorted.len() - 1];
+    if x <= first.x {
+        return first.y;
+    }
+    if x >= last.x {
+        return last.y;
+    }
+    for pair in sorted.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if x <= b.x {
+            let span = b.x - a.x;
+            if span <= f32::EPSILON {
+                return b.y;
+            }
+            return a.y + (b.y - a.y) * (x - a.x) / span;
+        }
+    }
+    last.y
+}
+
+/// How far `value` lies outside `range`; 0 inside
+fn outside(value: f32, (lo, hi): (f32, f32)) -> f32 {
+    (lo - value).max(value - hi).max(0.0)
+}
+
+/// Degrees outside a hue range, which may wrap through 0° (e.g. 340-20)
+fn hue_distance(hue: f32, (lo, hi): (f32, f32)) -> f32 {
+    let inside = if lo <= hi { hue >= lo && hue <= hi } else { hue >= lo || hue <= hi };
+    if inside {
+        return 0.0;
+    }
+    let to = |edge: f32| {
+        let d = (hue - edge).rem_euclid(360.0);
+        d.min(360.0 - d)
+    };
+    to(lo).min(to(hi))
+}
+
+/// Full weight inside the qualifier, falling off linearly over `softness`
+fn range_weight(distance: f32, softness: f32) -> f32 {
+    (1.0 - distance / softness).clamp(0.0, 1.0)
+}
+
+fn rgb_to_hsl([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d <= f32::EPSILON {
+        return (0.0, 0.0, l);
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs()).max(f32::EPSILON);
+    let h = if max == r {
+        60.0 * ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / d + 2.0)
+    } else {
+        60.0 * ((r - g) / d + 4.0)
+    };
+    (h, s.min(1.0), l)
+}
+
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> [f32; 3] {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    [r + m, g + m, b + m]
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Vignette {
     pub amount: f32,    // -100 to 100
@@ -147,6 +356,16 @@ pub struct Vignette {
     pub feather: f32,   // 0 to 100
 }
 
+/// Before/after stills of a grade on one frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradePreview {
+    pub time_seconds: f64,
+    pub before: PathBuf,
+    pub after: PathBuf,
+    /// The baked LUT used for the after still, reusable for the export
+    pub baked_lut: PathBuf,
+}
+
 /// Color Grading Engine
 pub struct ColorGradingEngine {
     lut_directory: PathBuf,
@@ -494,6 +713,42 @@ if (lumetriEffect) {{
         Ok(luts)
     }
 
+    /// Parse a LUT file. Only `.cube` files can be evaluated natively.
+    pub async fn load_lut(&self, lut: &LUT) -> EditronResult<CubeLut> {
+        match lut.format {
+            LUTFormat::Cube => CubeLut::load(&lut.path).await,
+            _ => Err(EditronError::InvalidFormat(format!(
+                "{} is not a .cube LUT",
+                lut.path.display()
+            ))),
+        }
+    }
+
+    /// Compose the preset's LUT and adjustments into one 3D LUT
+    pub async fn bake(&self, preset: &ColorGradePreset, size: usize) -> EditronResult<CubeLut> {
+        let lut = match &preset.lut {
+            Some(lut) => Some(self.load_lut(lut).await?),
+            None => None,
+        };
+        let mut baked = CubeLut::from_fn(size, |rgb| preset.grade_rgb(lut.as_ref(), rgb));
+        baked.title = Some(preset.name.clone());
+        Ok(baked)
+    }
+
+    /// ffmpeg filter chain for a baked export: the LUT plus the spatial
+    /// vignette it can't carry
+    pub fn baked_ffmpeg_filter(&self, preset: &ColorGradePreset, baked_lut: &Path) -> String {
+        let mut filters = vec![format!(
+            "lut3d=file='{}':interp={}",
+            super::captions::escape_filter_path(baked_lut),
+            LutInterpolation::Tetrahedral.ffmpeg_name()
+        )];
+        if preset.vignette.as_ref().is_some_and(|v| v.amount != 0.0) {
+            filters.push(format!("vignette=angle={}:mode=backward", std::f32::consts::PI / 5.0));
+        }
+        filters.join(",")
+    }
+
     /// Get all presets
     pub fn presets(&self) -> &[ColorGradePreset] {
         &self.presets
@@ -522,4 +777,92 @@ mod tests {
         let filter = engine.to_ffmpeg_filter(&preset);
         assert!(filter.contains("eq="));
     }
+
+    fn neutral_preset() -> ColorGradePreset {
+        ColorGradePreset {
+            name: "Neutral".to_string(),
+            description: String::new(),
+            basic: BasicColorCorrection::default(),
+            wheels: ColorWheels::default(),
+            curves: ColorCurves::default(),
+            lut: None,
+            secondaries: vec![],
+            vignette: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_neutral_preset_bakes_to_identity() {
+        let engine = ColorGradingEngine::new("/tmp/luts");
+        let baked = engine.bake(&neutral_preset(), 9).await.unwrap();
+        let identity = CubeLut::identity(9);
+        for (a, b) in baked.table.iter().zip(&identity.table) {
+            for c in 0..3 {
+                assert!((a[c] - b[c]).abs() < 1e-5);
+            }
+        }
+        assert_eq!(baked.title.as_deref(), Some("Neutral"));
+    }
+
+    #[tokio::test]
+    async fn test_bake_blends_lut_by_intensity() {
+        let dir = std::env::temp_dir().join(format!("editron_lut_{}", std::process::id()));
+        let path = dir.join("invert.cube");
+        CubeLut::from_fn(2, |rgb| rgb.map(|v| 1.0 - v)).write(&path).await.unwrap();
+
+        let engine = ColorGradingEngine::new(&dir);
+        let mut preset = neutral_preset();
+        preset.lut = Some(LUT {
+            name: "invert".to_string(),
+            path: path.clone(),
+            format: LUTFormat::Cube,
+            intensity: 0.25,
+        });
+        let baked = engine.bake(&preset, 5).await.unwrap();
+        let out = baked.apply([1.0, 0.0, 0.5], LutInterpolation::Tetrahedral);
+        assert!((out[0] - 0.75).abs() < 1e-4);
+        assert!((out[1] - 0.25).abs() < 1e-4);
+        assert!((out[2] - 0.5).abs() < 1e-4);
+
+        preset.lut.as_mut().unwrap().format = LUTFormat::Look;
+        assert!(engine.bake(&preset, 5).await.is_err());
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
+
+    #[test]
+    fn test_grade_adjustments_move_the_right_way() {
+        let mut preset = neutral_preset();
+        preset.basic.exposure = 1.0;
+        assert!((preset.grade_rgb(None, [0.25; 3])[0] - 0.5).abs() < 1e-4);
+
+        let mut preset = neutral_preset();
+        preset.basic.temperature = 50.0;
+        let warm = preset.grade_rgb(None, [0.5; 3]);
+        assert!(warm[0] > 0.5 && warm[2] < 0.5);
+
+        let mut preset = neutral_preset();
+        preset.basic.saturation = -100.0;
+        let grey = preset.grade_rgb(None, [0.8, 0.2, 0.4]);
+        assert!((grey[0] - grey[1]).abs() < 1e-4 && (grey[1] - grey[2]).abs() < 1e-4);
+
+        let film = ColorGradingEngine::preset_vintage_film();
+        assert!(film.grade_rgb(None, [0.0; 3]).iter().all(|&v| v > 0.0), "lifted blacks");
+    }
+
+    #[test]
+    fn test_secondary_only_touches_its_hue_range() {
+        let mut preset = neutral_preset();
+        preset.secondaries.push(HSLSecondary {
+            enabled: true,
+            hue_range: (340.0, 20.0),
+            sat_range: (0.2, 1.0),
+            lum_range: (0.1, 0.9),
+            adjustments: HSLAdjustments { hue_shift: 0.0, saturation: -1.0, luminance: 0.0 },
+            softness: 0.1,
+        });
+        let red = preset.grade_rgb(None, [0.8, 0.1, 0.1]);
+        assert!((red[0] - red[1]).abs() < 1e-3, "reds desaturated: {:?}", red);
+        let blue = preset.grade_rgb(None, [0.1, 0.2, 0.8]);
+        assert!((blue[0] - 0.1).abs() < 1e-4 && (blue[2] - 0.8).abs() < 1e-4);
+    }
 }
@@ -0,0 +1,829 @@
+//! Distributed Rendering
+//!
+//! Splits long exports into GOP-aligned segments and renders them on APN mesh
+//! peers advertising the `ffmpeg` capability:
+//! - Segment boundaries sit on source keyframes so every segment seeks exactly
+//! - Segments are video-only; audio is encoded once locally to avoid priming
+//!   gaps at the joins
+//! - Each returned segment is sha256-verified before use, failed segments are
+//!   retried on another peer and finally rendered locally
+//! - Verified segments are joined with a stream-copy concat, then muxed with
+//!   the audio
+//!
+//! ## Peer protocol
+//!
+//! A peer accepts `POST {address}/api/editron/segments` with a [`SegmentJob`],
+//! renders it with [`render_segment_job`] and answers with a
+//! [`SegmentDelivery`] whose `download_url` serves the rendered file.
+//! [`SegmentHost`] implements that side, and also serves the sources of this
+//! node's own distributed exports to its peers.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use futures_util::stream;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use super::ffmpeg::FFmpegClient;
+use super::{EditronError, EditronResult, ExportPreset};
+use crate::services::apn_bridge::{
+    PeerInfo, ResourceAccounting, ResourceRequirements, TaskDistributor, capabilities,
+};
+
+/// Node name recorded for segments rendered on this machine
+pub const LOCAL_NODE: &str = "local";
+
+/// A GOP-aligned slice of the source timeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderSegment {
+    pub index: usize,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl RenderSegment {
+    pub fn duration(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+/// Work sent to a peer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentJob {
+    /// Id of this attempt, also used for the payment record
+    pub task_id: Uuid,
+    pub render_id: Uuid,
+    pub segment: RenderSegment,
+    /// URL the peer reads the source from
+    pub source_url: String,
+    pub preset: ExportPreset,
+    /// Container extension for the segment (`mp4`, `mov`, ...)
+    pub extension: String,
+}
+
+/// A peer's answer to a [`SegmentJob`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentDelivery {
+    pub task_id: Uuid,
+    pub download_url: String,
+    pub sha256: String,
+    pub bytes: u64,
+}
+
+/// Moves segment jobs to peers and their results back
+#[async_trait]
+pub trait SegmentTransport: Send + Sync {
+    async fn dispatch(&self, peer: &PeerInfo, job: &SegmentJob) -> EditronResult<SegmentDelivery>;
+
+    async fn fetch(&self, delivery: &SegmentDelivery, dest: &Path) -> EditronResult<()>;
+}
+
+/// [`SegmentTransport`] over the peers' HTTP API
+pub struct HttpSegmentTransport {
+    client: reqwest::Client,
+}
+
+impl HttpSegmentTransport {
+    /// `timeout` bounds a single segment render plus its download
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(timeout)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    fn base_url(peer: &PeerInfo) -> String {
+        let address = peer.address.trim_end_matches('/');
+        if address.starts_with("http://") || address.starts_with("https://") {
+            address.to_string()
+        } else {
+            format!("http://{}", address)
+        }
+    }
+}
+
+#[async_trait]
+impl SegmentTransport for HttpSegmentTransport {
+    async fn dispatch(&self, peer: &PeerInfo, job: &SegmentJob) -> EditronResult<SegmentDelivery> {
+        let url = format!("{}/api/editron/segments", Self::base_url(peer));
+        let response = self.client
+            .post(&url)
+            .json(job)
+            .send()
+            .await
+            .map_err(|e| EditronError::Process(format!("{}: {}", peer.node_id, e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(EditronError::Process(format!(
+                "{} rejected segment {}: {} {}",
+                peer.node_id, job.segment.index, status, body
+            )));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| EditronError::Process(format!("{}: invalid delivery: {}", peer.node_id, e)))
+    }
+
+    async fn fetch(&self, delivery: &SegmentDelivery, dest: &Path) -> EditronResult<()> {
+        let mut response = self.client
+            .get(&delivery.download_url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| EditronError::Process(format!("Segment download failed: {}", e)))?;
+
+        let mut file = tokio::fs::File::create(dest).await?;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| EditronError::Process(format!("Segment download failed: {}", e)))?
+        {
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Peer side: render a received job into `output_dir` and return the file
+/// with its sha256
+pub async fn render_segment_job(
+    ffmpeg: &FFmpegClient,
+    job: &SegmentJob,
+    output_dir: &Path,
+) -> EditronResult<(PathBuf, String)> {
+    tokio::fs::create_dir_all(output_dir).await?;
+    let output = output_dir.join(segment_file_name(job.render_id, job.segment.index, &job.extension));
+    ffmpeg
+        .render_segment(&job.source_url, job.segment.start, job.segment.duration(), &job.preset, &output)
+        .await?;
+    let checksum = sha256_file(&output).await?;
+    Ok((output, checksum))
+}
+
+/// Rendered segments older than this are removed from a peer's segment directory
+const SEGMENT_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// This node's side of the peer protocol: renders segments other nodes send
+/// it, hands out the files behind their download URLs, and serves the sources
+/// of its own distributed exports
+pub struct SegmentHost {
+    ffmpeg: Arc<FFmpegClient>,
+    dir: PathBuf,
+    sources: Mutex<HashMap<Uuid, PathBuf>>,
+}
+
+impl SegmentHost {
+    pub fn new(ffmpeg: Arc<FFmpegClient>, dir: PathBuf) -> Self {
+        Self {
+            ffmpeg,
+            dir,
+            sources: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Render a job received from another node. `public_url` is this node's
+    /// address as that node reaches it, and prefixes the download URL.
+    pub async fn render(&self, job: &SegmentJob, public_url: &str) -> EditronResult<SegmentDelivery> {
+        // ffmpeg would otherwise happily read local files or exotic protocols
+        if !(job.source_url.starts_with("http://") || job.source_url.starts_with("https://")) {
+            return Err(EditronError::InvalidFormat(format!(
+                "Segment sources must be fetched over HTTP, got {}",
+                job.source_url
+            )));
+        }
+        if job.extension.is_empty() || !job.extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(EditronError::InvalidFormat(format!("Invalid segment extension: {}", job.extension)));
+        }
+
+        self.prune().await;
+        let (output, sha256) = render_segment_job(&self.ffmpeg, job, &self.dir).await?;
+        let bytes = tokio::fs::metadata(&output).await?.len();
+        Ok(SegmentDelivery {
+            task_id: job.task_id,
+            download_url: format!(
+                "{}/api/editron/segments/{}",
+                public_url.trim_end_matches('/'),
+                segment_file_name(job.render_id, job.segment.index, &job.extension)
+            ),
+            sha256,
+            bytes,
+        })
+    }
+
+    /// A rendered segment by the file name in its download URL. Names that
+    /// could leave the segment directory are refused.
+    pub fn segment_path(&self, file_name: &str) -> Option<PathBuf> {
+        let valid = !file_name.starts_with('.')
+            && file_name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        let path = self.dir.join(file_name);
+        (valid && !file_name.is_empty() && path.is_file()).then_some(path)
+    }
+
+    /// Let peers download `path` until [`Self::unshare_source`] is called
+    pub async fn share_source(&self, path: &Path) -> Uuid {
+        let id = Uuid::new_v4();
+        self.sources.lock().await.insert(id, path.to_path_buf());
+        id
+    }
+
+    pub async fn unshare_source(&self, id: Uuid) {
+        self.sources.lock().await.remove(&id);
+    }
+
+    pub async fn source_path(&self, id: Uuid) -> Option<PathBuf> {
+        self.sources.lock().await.get(&id).cloned()
+    }
+
+    async fn prune(&self) {
+        let Ok(mut entries) = tokio::fs::read_dir(&self.dir).await else {
+            return;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let stale = entry
+                .metadata()
+                .await
+                .ok()
+                .and_then(|m| m.modified().ok())
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age > SEGMENT_TTL);
+            if stale {
+                let _ = tokio::fs::remove_file(entry.path()).await;
+            }
+        }
+    }
+}
+
+/// Distributed render settings
+#[derive(Debug, Clone)]
+pub struct DistributedRenderSettings {
+    /// Preferred segment length; actual cuts land on the next keyframe
+    pub segment_seconds: f64,
+    /// Sources shorter than this render locally in one pass
+    pub min_distributed_seconds: f64,
+    /// Attempts per segment across different peers before rendering locally
+    pub max_attempts: usize,
+    /// Segments in flight per peer
+    pub segments_per_peer: usize,
+    /// Render segments locally when every peer attempt failed
+    pub local_fallback: bool,
+    /// Payment per minute of rendered segment
+    pub vibe_per_minute: f64,
+}
+
+impl Default for DistributedRenderSettings {
+    fn default() -> Self {
+        Self {
+            segment_seconds: 60.0,
+            min_distributed_seconds: 300.0,
+            max_attempts: 3,
+            segments_per_peer: 2,
+            local_fallback: true,
+            vibe_per_minute: 0.5,
+        }
+    }
+}
+
+/// Where a segment was rendered and how it got there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentOutcome {
+    pub segment: RenderSegment,
+    pub node: String,
+    pub attempts: usize,
+    pub sha256: String,
+    /// Failed attempts as `node: reason`
+    pub failures: Vec<String>,
+    pub vibe_paid: f64,
+}
+
+/// Result of a distributed render
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistributedRender {
+    pub render_id: Uuid,
+    pub output: PathBuf,
+    pub segments: Vec<SegmentOutcome>,
+    pub vibe_spent: f64,
+}
+
+impl DistributedRender {
+    pub fn remote_segments(&self) -> usize {
+        self.segments.iter().filter(|s| s.node != LOCAL_NODE).count()
+    }
+}
+
+/// Cut `duration` seconds into segments of roughly `target` seconds, each
+/// starting on a keyframe. A short tail is merged into the last segment.
+pub fn plan_segments(keyframes: &[f64], duration: f64, target: f64) -> Vec<RenderSegment> {
+    let mut cuts = vec![0.0];
+    for &time in keyframes {
+        let last = *cuts.last().unwrap_or(&0.0);
+        if time - last >= target && duration - time >= target / 4.0 {
+            cuts.push(time);
+        }
+    }
+
+    cuts.iter()
+        .enumerate()
+        .map(|(index, &start)| RenderSegment {
+            index,
+            start,
+            end: cuts.get(index + 1).copied().unwrap_or(duration),
+        })
+        .collect()
+}
+
+/// Hands segments to peers: fewest segments in flight first, then reputation
+/// and latency, never a peer that already failed the segment
+struct PeerScheduler {
+    peers: Vec<PeerInfo>,
+    in_flight: Mutex<HashMap<String, usize>>,
+    limit: usize,
+}
+
+impl PeerScheduler {
+    fn new(peers: Vec<PeerInfo>, limit: usize) -> Self {
+        Self {
+            peers,
+            in_flight: Mutex::new(HashMap::new()),
+            limit: limit.max(1),
+        }
+    }
+
+    async fn acquire(&self, excluded: &HashSet<String>) -> Option<PeerInfo> {
+        let mut in_flight = self.in_flight.lock().await;
+        let peer = self.peers
+            .iter()
+            .filter(|p| !excluded.contains(&p.node_id))
+            .filter(|p| in_flight.get(&p.node_id).copied().unwrap_or(0) < self.limit)
+            .min_by(|a, b| {
+                let load = |p: &PeerInfo| in_flight.get(&p.node_id).copied().unwrap_or(0);
+                load(a)
+                    .cmp(&load(b))
+                    .then(b.reputation.total_cmp(&a.reputation))
+                    .then(a.latency_ms.unwrap_or(u64::MAX).cmp(&b.latency_ms.unwrap_or(u64::MAX)))
+            })?
+            .clone();
+        *in_flight.entry(peer.node_id.clone()).or_default() += 1;
+        Some(peer)
+    }
+
+    async fn release(&self, peer: &PeerInfo) {
+        if let Some(count) = self.in_flight.lock().await.get_mut(&peer.node_id) {
+            *count = count.saturating_sub(1);
+        }
+    }
+
+    fn remaining(&self, excluded: &HashSet<String>) -> bool {
+        self.peers.iter().any(|p| !excluded.contains(&p.node_id))
+    }
+}
+
+/// Renders exports across the APN mesh
+pub struct DistributedRenderer {
+    ffmpeg: Arc<FFmpegClient>,
+    distributor: Arc<TaskDistributor>,
+    accounting: Arc<ResourceAccounting>,
+    transport: Arc<dyn SegmentTransport>,
+    settings: DistributedRenderSettings,
+}
+
+impl DistributedRenderer {
+    pub fn new(
+        ffmpeg: Arc<FFmpegClient>,
+        distributor: Arc<TaskDistributor>,
+        accounting: Arc<ResourceAccounting>,
+        transport: Arc<dyn SegmentTransport>,
+    ) -> Self {
+        Self {
+            ffmpeg,
+            distributor,
+            accounting,
+            transport,
+            settings: DistributedRenderSettings::default(),
+        }
+    }
+
+    pub fn with_settings(mut self, settings: DistributedRenderSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Render `input` to `output`. Peers read the source from `source_url`;
+    /// without one (or without capable peers, or for short sources) the
+    /// export runs locally in a single pass.
+    pub async fn render(
+        &self,
+        input: &Path,
+        source_url: Option<&str>,
+        output: &Path,
+        preset: &ExportPreset,
+        work_dir: &Path,
+    ) -> EditronResult<DistributedRender> {
+        let render_id = Uuid::new_v4();
+        let metadata = self.ffmpeg.probe(input).await?;
+        let peers = self.distributor
+            .find_capable_peers(&ResourceRequirements {
+                required_capabilities: vec![capabilities::FFMPEG.to_string()],
+                ..Default::default()
+            })
+            .await;
+
+        let distribute = source_url.is_some()
+            && !peers.is_empty()
+            && metadata.duration_seconds >= self.settings.min_distributed_seconds;
+        if !distribute {
+            self.ffmpeg.export(input, output, preset.clone()).await?;
+            let sha256 = sha256_file(output).await?;
+            return Ok(DistributedRender {
+                render_id,
+                output: output.to_path_buf(),
+                segments: vec![SegmentOutcome {
+                    segment: RenderSegment { index: 0, start: 0.0, end: metadata.duration_seconds },
+                    node: LOCAL_NODE.to_string(),
+                    attempts: 1,
+                    sha256,
+                    failures: Vec::new(),
+                    vibe_paid: 0.0,
+                }],
+                vibe_spent: 0.0,
+            });
+        }
+
+        let keyframes = self.ffmpeg.keyframe_times(input).await?;
+        let segments = plan_segments(&keyframes, metadata.duration_seconds, self.settings.segment_seconds);
+        let render_dir = work_dir.join("distributed").join(render_id.to_string());
+        tokio::fs::create_dir_all(&render_dir).await?;
+
+        let extension = output
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "mp4".to_string());
+        let source_url = source_url.unwrap_or_default();
+        let scheduler = PeerScheduler::new(peers, self.settings.segments_per_peer);
+        let parallelism = (scheduler.peers.len() * scheduler.limit).max(1);
+
+        tracing::info!(
+            "Distributing render {} as {} segments across {} peers",
+            render_id,
+            segments.len(),
+            scheduler.peers.len()
+        );
+
+        let (dir, scheduler_ref, segment_extension) = (render_dir.as_path(), &scheduler, extension.clone());
+        let mut outcomes: Vec<SegmentOutcome> = stream::iter(segments.into_iter().map(move |segment| {
+            let job = SegmentJob {
+                task_id: Uuid::new_v4(),
+                render_id,
+                segment,
+                source_url: source_url.to_string(),
+                preset: preset.clone(),
+                extension: segment_extension.clone(),
+            };
+            self.render_segment(job, input, dir, scheduler_ref)
+        }))
+        .buffer_unordered(parallelism)
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<EditronResult<_>>()?;
+        outcomes.sort_by_key(|o| o.segment.index);
+
+        // Join the verified segments, then lay the audio back on
+        let segment_paths: Vec<PathBuf> = outcomes
+            .iter()
+            .map(|o| render_dir.join(segment_file_name(render_id, o.segment.index, &extension)))
+            .collect();
+        let video = render_dir.join(format!("video.{}", extension));
+        self.ffmpeg.concat(segment_paths, video.clone(), None).await?;
+        if metadata.audio_codec.is_some() {
+            let audio = render_dir.join(format!("audio.{}", extension));
+            self.ffmpeg.encode_audio(input, preset, audio.as_path()).await?;
+            self.ffmpeg.mux_audio(video.as_path(), audio.as_path(), output).await?;
+        } else {
+            tokio::fs::rename(&video, output).await?;
+        }
+        let _ = tokio::fs::remove_dir_all(&render_dir).await;
+
+        let vibe_spent = outcomes.iter().map(|o| o.vibe_paid).sum();
+        Ok(DistributedRender {
+            render_id,
+            output: output.to_path_buf(),
+            segments: outcomes,
+            vibe_spent,
+        })
+    }
+
+    /// Try peers until one returns a segment whose checksum matches, then
+    /// fall back to rendering it here
+    async fn render_segment(
+        &self,
+        mut job: SegmentJob,
+        input: &Path,
+        render_dir: &Path,
+        scheduler: &PeerScheduler,
+    ) -> EditronResult<SegmentOutcome> {
+        let dest = render_dir.join(segment_file_name(job.render_id, job.segment.index, &job.extension));
+        let mut tried = HashSet::new();
+        let mut failures = Vec::new();
+
+        while tried.len() < self.settings.max_attempts && scheduler.remaining(&tried) {
+            let Some(peer) = scheduler.acquire(&tried).await else {
+                // Every untried peer is busy
+                tokio::time::sleep(Duration::from_millis(250)).await;
+                continue;
+            };
+            tried.insert(peer.node_id.clone());
+            job.task_id = Uuid::new_v4();
+
+            let result = self.fetch_verified(&peer, &job, &dest).await;
+            scheduler.release(&peer).await;
+            match result {
+                Ok(sha256) => {
+                    self.accounting
+                        .record_task_completed(job.task_id, &peer.node_id, true, 0.0)
+                        .await;
+                    let vibe_paid = self.pay(&peer, &job).await;
+                    return Ok(SegmentOutcome {
+                        segment: job.segment,
+                        node: peer.node_id,
+                        attempts: tried.len(),
+                        sha256,
+                        failures,
+                        vibe_paid,
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Segment {} of render {} failed on {}: {}",
+                        job.segment.index,
+                        job.render_id,
+                        peer.node_id,
+                        e
+                    );
+                    self.accounting
+                        .record_task_completed(job.task_id, &peer.node_id, false, 0.0)
+                        .await;
+                    failures.push(format!("{}: {}", peer.node_id, e));
+                    let _ = tokio::fs::remove_file(&dest).await;
+                }
+            }
+        }
+
+        if !self.settings.local_fallback {
+            return Err(EditronError::Process(format!(
+                "Segment {} failed on every peer: {}",
+                job.segment.index,
+                failures.join("; ")
+            )));
+        }
+
+        self.ffmpeg
+            .render_segment(&input.to_string_lossy(), job.segment.start, job.segment.duration(), &job.preset, &dest)
+            .await?;
+        let sha256 = sha256_file(&dest).await?;
+        Ok(SegmentOutcome {
+            segment: job.segment,
+            node: LOCAL_NODE.to_string(),
+            attempts: tried.len() + 1,
+            sha256,
+            failures,
+            vibe_paid: 0.0,
+        })
+    }
+
+    async fn fetch_verified(&self, peer: &PeerInfo, job: &SegmentJob, dest: &Path) -> EditronResult<String> {
+        let delivery = self.transport.dispatch(peer, job).await?;
+        if delivery.task_id != job.task_id {
+            return Err(EditronError::Process(format!(
+                "Delivery for task {} does not match job {}",
+                delivery.task_id, job.task_id
+            )));
+        }
+        self.transport.fetch(&delivery, dest).await?;
+
+        let sha256 = sha256_file(dest).await?;
+        if !sha256.eq_ignore_ascii_case(&delivery.sha256) {
+            return Err(EditronError::InvalidFormat(format!(
+                "Checksum mismatch: peer reported {}, received {}",
+                delivery.sha256, sha256
+            )));
+        }
+        Ok(sha256)
+    }
+
+    /// Pay the peer for a verified segment; an unpaid segment is still used
+    async fn pay(&self, peer: &PeerInfo, job: &SegmentJob) -> f64 {
+        let amount = self.settings.vibe_per_minute * job.segment.duration() / 60.0;
+        if amount <= 0.0 {
+            return 0.0;
+        }
+        let description = format!(
+            "Rendered segment {} ({:.1}s) of render {}",
+            job.segment.index,
+            job.segment.duration(),
+            job.render_id
+        );
+        match self.accounting.pay_peer(&peer.node_id, job.task_id, amount, description).await {
+            Ok(()) => amount,
+            Err(e) => {
+                tracing::warn!("Could not pay {} for segment {}: {}", peer.node_id, job.segment.index, e);
+                0.0
+            }
+        }
+    }
+}
+
+fn segment_file_name(render_id: Uuid, index: usize, extension: &str) -> String {
+    format!("{}_{:05}.{}", render_id, index, extension)
+}
+
+/// Hex sha256 of a file, read in chunks
+pub async fn sha256_file(path: &Path) -> EditronResult<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    const PAYLOAD: &[u8] = b"rendered segment";
+
+    /// Fails every job sent to a peer in `broken` and delivers [`PAYLOAD`]
+    /// from the rest
+    struct FakeTransport {
+        broken: HashSet<String>,
+        dispatched: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl SegmentTransport for FakeTransport {
+        async fn dispatch(&self, peer: &PeerInfo, job: &SegmentJob) -> EditronResult<SegmentDelivery> {
+            self.dispatched.lock().unwrap().push(peer.node_id.clone());
+            if self.broken.contains(&peer.node_id) {
+                return Err(EditronError::Process(format!("{} is unreachable", peer.node_id)));
+            }
+            Ok(SegmentDelivery {
+                task_id: job.task_id,
+                download_url: format!("http://{}/api/editron/segments/{}", peer.address, job.segment.index),
+                sha256: format!("{:x}", Sha256::digest(PAYLOAD)),
+                bytes: PAYLOAD.len() as u64,
+            })
+        }
+
+        async fn fetch(&self, _delivery: &SegmentDelivery, dest: &Path) -> EditronResult<()> {
+            tokio::fs::write(dest, PAYLOAD).await?;
+            Ok(())
+        }
+    }
+
+    fn job(source_url: &str) -> SegmentJob {
+        SegmentJob {
+            task_id: Uuid::new_v4(),
+            render_id: Uuid::new_v4(),
+            segment: RenderSegment { index: 3, start: 180.0, end: 240.0 },
+            source_url: source_url.to_string(),
+            preset: ExportPreset::youtube_1080p(),
+            extension: "mp4".to_string(),
+        }
+    }
+
+    // Nothing in these tests reaches ffmpeg
+    fn ffmpeg() -> Arc<FFmpegClient> {
+        Arc::new(FFmpegClient::with_paths("ffmpeg".into(), "ffprobe".into()))
+    }
+
+    fn peer(id: &str, reputation: f64, latency: u64) -> PeerInfo {
+        PeerInfo {
+            node_id: id.to_string(),
+            address: format!("{}.mesh:8080", id),
+            capabilities: vec![capabilities::FFMPEG.to_string()],
+            reputation,
+            latency_ms: Some(latency),
+            available_bandwidth_mbps: None,
+            last_seen: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_plan_segments_on_keyframes() {
+        // 2s GOPs over 250s
+        let keyframes: Vec<f64> = (0..125).map(|i| i as f64 * 2.0).collect();
+        let segments = plan_segments(&keyframes, 250.0, 60.0);
+
+        let starts: Vec<f64> = segments.iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![0.0, 60.0, 120.0, 180.0]);
+        // The 10s tail is folded into the last segment
+        assert_eq!(segments.last().unwrap().end, 250.0);
+        for pair in segments.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+    }
+
+    #[test]
+    fn test_plan_segments_irregular_gops() {
+        let keyframes = vec![0.0, 7.5, 50.0, 70.2, 71.0, 133.3, 140.0];
+        let segments = plan_segments(&keyframes, 150.0, 60.0);
+        let bounds: Vec<(f64, f64)> = segments.iter().map(|s| (s.start, s.end)).collect();
+        assert_eq!(bounds, vec![(0.0, 70.2), (70.2, 133.3), (133.3, 150.0)]);
+
+        assert_eq!(plan_segments(&[], 42.0, 60.0).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_balances_and_skips_failed_peers() {
+        let scheduler = PeerScheduler::new(
+            vec![peer("slow", 0.9, 200), peer("fast", 0.9, 20), peer("trusted", 1.0, 500)],
+            1,
+        );
+        let none = HashSet::new();
+
+        // Highest reputation first, then lowest latency
+        let first = scheduler.acquire(&none).await.unwrap();
+        assert_eq!(first.node_id, "trusted");
+        let second = scheduler.acquire(&none).await.unwrap();
+        assert_eq!(second.node_id, "fast");
+
+        // A retry never goes back to a peer that failed the segment
+        let excluded: HashSet<String> = ["slow".to_string()].into();
+        assert!(scheduler.acquire(&excluded).await.is_none());
+        scheduler.release(&first).await;
+        assert_eq!(scheduler.acquire(&excluded).await.unwrap().node_id, "trusted");
+
+        let all: HashSet<String> = ["slow", "fast", "trusted"].iter().map(|s| s.to_string()).collect();
+        assert!(!scheduler.remaining(&all));
+    }
+
+    #[tokio::test]
+    async fn test_failed_segment_is_retried_on_another_peer() {
+        let transport = Arc::new(FakeTransport {
+            broken: ["flaky".to_string()].into(),
+            dispatched: Default::default(),
+        });
+        let renderer = DistributedRenderer::new(
+            ffmpeg(),
+            Arc::new(TaskDistributor::new()),
+            Arc::new(ResourceAccounting::new()),
+            transport.clone(),
+        )
+        .with_settings(DistributedRenderSettings { local_fallback: false, ..Default::default() });
+        // The failing peer ranks first on reputation
+        let scheduler = PeerScheduler::new(vec![peer("flaky", 1.0, 10), peer("steady", 0.5, 10)], 1);
+        let dir = tempfile::tempdir().unwrap();
+        let job = job("http://origin.mesh:8080/api/editron/sources/source");
+        let dest = dir.path().join(segment_file_name(job.render_id, job.segment.index, &job.extension));
+
+        let outcome = renderer
+            .render_segment(job, Path::new("source.mov"), dir.path(), &scheduler)
+            .await
+            .unwrap();
+
+        assert_eq!(*transport.dispatched.lock().unwrap(), ["flaky", "steady"]);
+        assert_eq!(outcome.node, "steady");
+        assert_eq!(outcome.attempts, 2);
+        assert_eq!(outcome.failures, ["flaky: Process error: flaky is unreachable"]);
+        assert_eq!(tokio::fs::read(&dest).await.unwrap(), PAYLOAD);
+    }
+
+    #[tokio::test]
+    async fn test_segment_host_refuses_local_sources_and_escaping_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let host = SegmentHost::new(ffmpeg(), dir.path().to_path_buf());
+
+        for source in ["/etc/passwd", "file:///etc/passwd", "concat:a.mp4|b.mp4"] {
+            assert!(host.render(&job(source), "http://peer.mesh:8080").await.is_err());
+        }
+
+        std::fs::write(dir.path().join("segment.mp4"), PAYLOAD).unwrap();
+        assert_eq!(host.segment_path("segment.mp4"), Some(dir.path().join("segment.mp4")));
+        for name in ["../segment.mp4", "..", ".hidden", "a/b.mp4", "", "missing.mp4"] {
+            assert_eq!(host.segment_path(name), None, "{name}");
+        }
+    }
+}
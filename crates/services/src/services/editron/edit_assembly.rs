@@ -8,6 +8,8 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use super::{EditronError, EditronResult, VideoMetadata};
+use super::transitions::{EasingCurve, Transition, WipeDirection};
+use super::reframe::ReframeTrack;
 
 /// Represents a piece of footage with metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -234,6 +236,9 @@ pub struct TimelineClip {
     pub speed: f32,         // 1.0 = normal speed
     pub transition_in: Option<TransitionSpec>,
     pub transition_out: Option<TransitionSpec>,
+    /// Crop path for aspect-changing exports (see `reframe`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reframe: Option<ReframeTrack>,
 }
 
 impl TimelineClip {
@@ -252,6 +257,7 @@ impl TimelineClip {
             speed: 1.0,
             transition_in: None,
             transition_out: None,
+            reframe: None,
         }
     }
 
@@ -285,6 +291,11 @@ impl TimelineClip {
         self.transition_out = Some(transition);
         self
     }
+
+    pub fn with_reframe(mut self, reframe: ReframeTrack) -> Self {
+        self.reframe = Some(reframe);
+        self
+    }
 }
 
 /// Transition specification
@@ -323,6 +334,89 @@ impl TransitionSpec {
     }
 }
 
+impl TransitionSpec {
+    /// The engine [`Transition`] this spec describes at `frame_rate`.
+    /// Unknown types fall back to a dissolve, as in the Premiere XML export.
+    pub fn to_transition(&self, frame_rate: f32) -> Transition {
+        let duration_frames = (self.duration * frame_rate as f64).round().max(0.0) as u32;
+        match self.transition_type.as_str() {
+            "cut" => Transition::Cut,
+            "dip_to_black" => Transition::FadeToColor {
+                duration_frames,
+                color: "#000000".to_string(),
+                curve: EasingCurve::Linear,
+            },
+            "dip_to_white" => Transition::FadeToColor {
+                duration_frames,
+                color: "#FFFFFF".to_string(),
+                curve: EasingCurve::Linear,
+            },
+            "wipe" => Transition::Wipe {
+                duration_frames,
+                direction: wipe_direction(self.params.get("angle").copied().unwrap_or(0.0)),
+                softness: self.params.get("softness").copied().unwrap_or(0.0) as f32,
+            },
+            _ => Transition::Dissolve {
+                duration_frames,
+                curve: EasingCurve::Linear,
+            },
+        }
+    }
+
+    /// Spec for an engine transition, e.g. one read from an interchange file
+    pub fn from_transition(transition: &Transition, frame_rate: f32) -> Self {
+        let duration = transition.duration_frames() as f64 / frame_rate as f64;
+        match transition {
+            Transition::Cut => Self {
+                transition_type: "cut".to_string(),
+                duration: 0.0,
+                params: HashMap::new(),
+            },
+            Transition::FadeToColor { color, .. } if color.eq_ignore_ascii_case("#FFFFFF") => Self {
+                transition_type: "dip_to_white".to_string(),
+                duration,
+                params: HashMap::new(),
+            },
+            Transition::FadeToColor { .. } => Self::dip_to_black(duration),
+            Transition::Wipe { direction, softness, .. } => {
+                let mut spec = Self::wipe(duration, wipe_angle(direction));
+                if *softness > 0.0 {
+                    spec.params.insert("softness".to_string(), *softness as f64);
+                }
+                spec
+            }
+            _ => Self::dissolve(duration),
+        }
+    }
+}
+
+/// Wipe angle in degrees, clockwise from left-to-right
+fn wipe_angle(direction: &WipeDirection) -> f64 {
+    match direction {
+        WipeDirection::Right => 0.0,
+        WipeDirection::DiagonalBottomRight => 45.0,
+        WipeDirection::Down => 90.0,
+        WipeDirection::DiagonalBottomLeft => 135.0,
+        WipeDirection::Left => 180.0,
+        WipeDirection::DiagonalTopLeft => 225.0,
+        WipeDirection::Up => 270.0,
+        WipeDirection::DiagonalTopRight => 315.0,
+    }
+}
+
+fn wipe_direction(angle: f64) -> WipeDirection {
+    match ((angle.rem_euclid(360.0) + 22.5) / 45.0) as u32 % 8 {
+        0 => WipeDirection::Right,
+        1 => WipeDirection::DiagonalBottomRight,
+        2 => WipeDirection::Down,
+        3 => WipeDirection::DiagonalBottomLeft,
+        4 => WipeDirection::Left,
+        5 => WipeDirection::DiagonalTopLeft,
+        6 => WipeDirection::Up,
+        _ => WipeDirection::DiagonalTopRight,
+    }
+}
+
 /// Audio clip on timeline
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioClip {
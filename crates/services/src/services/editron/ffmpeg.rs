@@ -28,6 +28,14 @@ impl FFmpegClient {
         })
     }
 
+    /// Use the given `ffmpeg` and `ffprobe` binaries instead of searching for them
+    pub fn with_paths(ffmpeg_path: PathBuf, ffprobe_path: PathBuf) -> Self {
+        Self {
+            ffmpeg_path,
+            ffprobe_path,
+        }
+    }
+
     fn find_executable(name: &str) -> EditronResult<PathBuf> {
         // Check common paths
         let paths = [
@@ -343,6 +351,168 @@ impl FFmpegClient {
         Ok(output.to_path_buf())
     }
 
+    /// Presentation times of the video keyframes in `input`, read from packet
+    /// flags so nothing is decoded
+    pub async fn keyframe_times<P: AsRef<Path>>(&self, input: P) -> EditronResult<Vec<f64>> {
+        let input = input.as_ref();
+        if !input.exists() {
+            return Err(EditronError::FileNotFound(input.to_path_buf()));
+        }
+
+        let output = Command::new(&self.ffprobe_path)
+            .args([
+                "-v", "error",
+                "-select_streams", "v:0",
+                "-show_entries", "packet=pts_time,flags",
+                "-of", "csv=p=0",
+            ])
+            .arg(input)
+            .output()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        if !output.status.success() {
+            return Err(EditronError::FFmpeg(
+                String::from_utf8_lossy(&output.stderr).to_string(),
+            ));
+        }
+
+        let mut times: Vec<f64> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| {
+                let (pts, flags) = line.split_once(',')?;
+                if !flags.starts_with('K') {
+                    return None;
+                }
+                pts.trim().parse().ok()
+            })
+            .collect();
+        times.sort_by(|a, b| a.total_cmp(b));
+        times.dedup();
+        Ok(times)
+    }
+
+    /// Render `duration` seconds of video from `start` with the preset's video
+    /// settings and no audio. `source` may be a path or a URL ffmpeg can read.
+    pub async fn render_segment<P: AsRef<Path>>(
+        &self,
+        source: &str,
+        start: f64,
+        duration: f64,
+        preset: &ExportPreset,
+        output: P,
+    ) -> EditronResult<PathBuf> {
+        let output = output.as_ref();
+
+        let mut args = vec![
+            "-y".to_string(),
+            "-ss".to_string(),
+            format!("{:.6}", start),
+            "-i".to_string(),
+            source.to_string(),
+            "-t".to_string(),
+            format!("{:.6}", duration),
+            "-map".to_string(),
+            "0:v:0".to_string(),
+            "-an".to_string(),
+        ];
+        self.apply_preset_args(&mut args, preset);
+        args.push("-avoid_negative_ts".to_string());
+        args.push("make_zero".to_string());
+        args.push(output.to_string_lossy().to_string());
+
+        let status = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .status()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        if !status.success() {
+            return Err(EditronError::FFmpeg(format!(
+                "Segment render at {:.3}s failed with status: {}",
+                start, status
+            )));
+        }
+
+        Ok(output.to_path_buf())
+    }
+
+    /// Encode only the audio of `input` with the preset's audio settings
+    pub async fn encode_audio<P: AsRef<Path>>(
+        &self,
+        input: P,
+        preset: &ExportPreset,
+        output: P,
+    ) -> EditronResult<PathBuf> {
+        let input = input.as_ref();
+        let output = output.as_ref();
+
+        let mut args = vec![
+            "-y".to_string(),
+            "-i".to_string(),
+            input.to_string_lossy().to_string(),
+            "-vn".to_string(),
+            "-c:a".to_string(),
+            preset.audio_codec.ffmpeg_codec().to_string(),
+        ];
+        if let Some(ref abr) = preset.audio_bitrate {
+            args.push("-b:a".to_string());
+            args.push(abr.clone());
+        }
+        args.push(output.to_string_lossy().to_string());
+
+        let status = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .status()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        if !status.success() {
+            return Err(EditronError::FFmpeg("Audio encode failed".to_string()));
+        }
+
+        Ok(output.to_path_buf())
+    }
+
+    /// Combine a video-only file with an audio file without re-encoding
+    pub async fn mux_audio<P: AsRef<Path>>(
+        &self,
+        video: P,
+        audio: P,
+        output: P,
+    ) -> EditronResult<PathBuf> {
+        let video = video.as_ref();
+        let audio = audio.as_ref();
+        let output = output.as_ref();
+
+        let status = Command::new(&self.ffmpeg_path)
+            .args([
+                "-y",
+                "-i", &video.to_string_lossy(),
+                "-i", &audio.to_string_lossy(),
+                "-map", "0:v:0",
+                "-map", "1:a:0",
+                "-c", "copy",
+                "-shortest",
+                &output.to_string_lossy(),
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .status()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        if !status.success() {
+            return Err(EditronError::FFmpeg("Audio mux failed".to_string()));
+        }
+
+        Ok(output.to_path_buf())
+    }
+
     /// Export video with preset
     pub async fn export<P: AsRef<Path>>(
         &self,
@@ -384,6 +554,54 @@ impl FFmpegClient {
         Ok(output.to_path_buf())
     }
 
+    /// Export `input` (or the `range` of it, in seconds) with an optional crop
+    /// filter ahead of the preset's scale, e.g. a reframe track for a vertical preset
+    pub async fn export_cropped<P: AsRef<Path>>(
+        &self,
+        input: P,
+        output: P,
+        range: Option<(f64, f64)>,
+        crop_filter: Option<&str>,
+        preset: &ExportPreset,
+    ) -> EditronResult<PathBuf> {
+        let input = input.as_ref();
+        let output = output.as_ref();
+
+        if !input.exists() {
+            return Err(EditronError::FileNotFound(input.to_path_buf()));
+        }
+
+        let mut args = vec!["-y".to_string()];
+        if let Some((start, end)) = range {
+            args.push("-ss".to_string());
+            args.push(format!("{:.6}", start));
+            args.push("-t".to_string());
+            args.push(format!("{:.6}", end - start));
+        }
+        args.push("-i".to_string());
+        args.push(input.to_string_lossy().to_string());
+
+        self.apply_preset_args_with_filter(&mut args, preset, crop_filter);
+        args.push(output.to_string_lossy().to_string());
+
+        let status = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .status()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        if !status.success() {
+            return Err(EditronError::FFmpeg(format!(
+                "FFmpeg cropped export failed with status: {}",
+                status
+            )));
+        }
+
+        Ok(output.to_path_buf())
+    }
+
     /// Extract single frame as image
     pub async fn extract_frame<P: AsRef<Path>>(
         &self,
@@ -460,6 +678,16 @@ impl FFmpegClient {
     }
 
     fn apply_preset_args(&self, args: &mut Vec<String>, preset: &ExportPreset) {
+        self.apply_preset_args_with_filter(args, preset, None);
+    }
+
+    /// Preset arguments with `filter` run ahead of the preset's scale
+    fn apply_preset_args_with_filter(
+        &self,
+        args: &mut Vec<String>,
+        preset: &ExportPreset,
+        filter: Option<&str>,
+    ) {
         // Video codec
         args.push("-c:v".to_string());
         args.push(preset.video_codec.ffmpeg_codec().to_string());
@@ -474,10 +702,15 @@ impl FFmpegClient {
         args.push("-c:a".to_string());
         args.push(preset.audio_codec.ffmpeg_codec().to_string());
 
-        // Scale if specified
-        if let (Some(w), Some(h)) = (preset.width, preset.height) {
+        // Caller's filter, then scale if specified
+        let scale = match (preset.width, preset.height) {
+            (Some(w), Some(h)) => Some(format!("scale={}:{}", w, h)),
+            _ => None,
+        };
+        let chain: Vec<String> = filter.map(str::to_string).into_iter().chain(scale).collect();
+        if !chain.is_empty() {
             args.push("-vf".to_string());
-            args.push(format!("scale={}:{}", w, h));
+            args.push(chain.join(","));
         }
 
         // Frame rate
@@ -577,6 +810,187 @@ impl FFmpegClient {
         Ok(output.to_path_buf())
     }
 
+    /// Run an analysis-only audio filter (`ebur128`, `loudnorm` with
+    /// `print_format`) over `input` and return ffmpeg's log
+    pub async fn analyze_audio<P: AsRef<Path>>(
+        &self,
+        input: P,
+        filter: &str,
+    ) -> EditronResult<String> {
+        let input = input.as_ref();
+        if !input.exists() {
+            return Err(EditronError::FileNotFound(input.to_path_buf()));
+        }
+
+        let output = Command::new(&self.ffmpeg_path)
+            .args([
+                "-hide_banner",
+                "-nostats",
+                "-i", &input.to_string_lossy(),
+                "-vn",
+                "-af", filter,
+                "-f", "null",
+                "-",
+            ])
+            .output()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        let log = String::from_utf8_lossy(&output.stderr).to_string();
+        if !output.status.success() {
+            return Err(EditronError::FFmpeg(format!(
+                "Audio analysis failed: {}",
+                log.lines().last().unwrap_or_default()
+            )));
+        }
+
+        Ok(log)
+    }
+
+    /// Apply an audio filter and resample to `sample_rate` (loudnorm works at
+    /// 192 kHz internally). Returns ffmpeg's log for filters that report stats.
+    pub async fn normalize_audio<P: AsRef<Path>>(
+        &self,
+        input: P,
+        filter: &str,
+        sample_rate: u32,
+        output: P,
+    ) -> EditronResult<String> {
+        let input = input.as_ref();
+        let output = output.as_ref();
+
+        let result = Command::new(&self.ffmpeg_path)
+            .args([
+                "-hide_banner",
+                "-nostats",
+                "-i", &input.to_string_lossy(),
+                "-af", filter,
+                "-ar", &sample_rate.to_string(),
+                "-c:v", "copy",
+                "-y",
+                &output.to_string_lossy(),
+            ])
+            .output()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        let log = String::from_utf8_lossy(&result.stderr).to_string();
+        if !result.status.success() {
+            return Err(EditronError::FFmpeg(format!(
+                "Loudness normalization failed: {}",
+                log.lines().last().unwrap_or_default()
+            )));
+        }
+
+        Ok(log)
+    }
+
+    /// Extract the dialogue band of `input` (optionally only `start..start+duration`
+    /// seconds) as 16 kHz mono WAV for speech recognition
+    pub async fn extract_dialogue_audio<P: AsRef<Path>>(
+        &self,
+        input: P,
+        output: P,
+        range: Option<(f64, f64)>,
+    ) -> EditronResult<PathBuf> {
+        let input = input.as_ref();
+        let output = output.as_ref();
+
+        if !input.exists() {
+            return Err(EditronError::FileNotFound(input.to_path_buf()));
+        }
+
+        let mut args = vec!["-y".to_string()];
+        if let Some((start, duration)) = range {
+            args.extend([
+                "-ss".to_string(), format!("{:.3}", start),
+                "-t".to_string(), format!("{:.3}", duration),
+            ]);
+        }
+        args.extend([
+            "-i".to_string(), input.to_string_lossy().to_string(),
+            "-vn".to_string(),
+            "-af".to_string(), "highpass=f=80,lowpass=f=8000".to_string(),
+            "-ac".to_string(), "1".to_string(),
+            "-ar".to_string(), "16000".to_string(),
+            "-c:a".to_string(), "pcm_s16le".to_string(),
+            output.to_string_lossy().to_string(),
+        ]);
+
+        let status = Command::new(&self.ffmpeg_path)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .status()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        if !status.success() {
+            return Err(EditronError::FFmpeg("Dialogue extraction failed".to_string()));
+        }
+
+        Ok(output.to_path_buf())
+    }
+
+    /// Mux `subtitles` into `input` as a soft subtitle track without re-encoding.
+    /// The subtitle codec follows the output container (mov_text for MP4/MOV,
+    /// WebVTT for WebM, SRT otherwise).
+    pub async fn mux_subtitles<P: AsRef<Path>>(
+        &self,
+        input: P,
+        subtitles: P,
+        language: &str,
+        output: P,
+    ) -> EditronResult<PathBuf> {
+        let input = input.as_ref();
+        let subtitles = subtitles.as_ref();
+        let output = output.as_ref();
+
+        for path in [input, subtitles] {
+            if !path.exists() {
+                return Err(EditronError::FileNotFound(path.to_path_buf()));
+            }
+        }
+
+        let extension = output
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+        let subtitle_codec = match extension.as_str() {
+            "mp4" | "m4v" | "mov" => "mov_text",
+            "webm" => "webvtt",
+            _ => "srt",
+        };
+
+        let status = Command::new(&self.ffmpeg_path)
+            .args([
+                "-i", &input.to_string_lossy(),
+                "-i", &subtitles.to_string_lossy(),
+                "-map", "0:v",
+                "-map", "0:a?",
+                "-map", "1:0",
+                "-c:v", "copy",
+                "-c:a", "copy",
+                "-c:s", subtitle_codec,
+                "-metadata:s:s:0", &format!("language={}", language),
+                "-disposition:s:0", "default",
+                "-y",
+                &output.to_string_lossy(),
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .status()
+            .await
+            .map_err(|e| EditronError::Process(e.to_string()))?;
+
+        if !status.success() {
+            return Err(EditronError::FFmpeg("Subtitle muxing failed".to_string()));
+        }
+
+        Ok(output.to_path_buf())
+    }
+
     /// Process video with both video and audio filters
     pub async fn process_with_filters<P: AsRef<Path>>(
         &self,
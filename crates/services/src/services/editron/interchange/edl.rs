@@ -0,0 +1,562 @@
+//! CMX3600 EDL
+//!
+//! Picture-only edit decision lists for conforming in Resolve or Baselight:
+//! one EDL describes one video track (track 1 by default). Reels are written
+//! as `AX` with `* FROM CLIP NAME` / `* SOURCE FILE` comments, speed changes
+//! as `M2` lines and markers as Avid-style `* LOC` locators. Record timecode
+//! starts at 01:00:00:00, non-drop frame.
+//!
+//! Wipes keep their SMPTE pattern only, so left/up wipes import as right/down.
+
+use std::{fmt::Write as _, path::PathBuf};
+
+use super::{
+    super::{
+        EditronError, EditronResult,
+        edit_assembly::{AssembledEdit, EditMarker, MarkerType, TimelineClip, TransitionSpec},
+        transitions::{EasingCurve, Transition, WipeDirection},
+    },
+    color_hex, color_name, exchange_rate, exports_transition_out, file_name, finish_import,
+    to_frames, to_seconds, video_tracks,
+};
+
+const REEL: &str = "AX";
+const BLACK_REEL: &str = "BL";
+const RECORD_START_HOURS: i64 = 1;
+const LOCATOR_COLORS: &[&str] = &[
+    "RED", "GREEN", "BLUE", "CYAN", "MAGENTA", "YELLOW", "BLACK", "WHITE",
+];
+
+/// EDL of the edit's primary (lowest-numbered) video track
+pub fn export(edit: &AssembledEdit) -> EditronResult<String> {
+    let track = video_tracks(edit).keys().next().copied().unwrap_or(1);
+    export_track(edit, track)
+}
+
+/// EDL of one video track
+pub fn export_track(edit: &AssembledEdit, track: u32) -> EditronResult<String> {
+    let rate = exchange_rate(edit.frame_rate);
+    let timecode = Timecode::new(rate);
+    let record_offset = RECORD_START_HOURS * 3600 * timecode.base;
+    let tracks = video_tracks(edit);
+    let clips = tracks.get(&track).map(Vec::as_slice).unwrap_or_default();
+
+    let mut out = format!("TITLE: {}\nFCM: NON-DROP FRAME\n\n", edit.name);
+    let mut events: Vec<(i64, i64, String)> = Vec::new();
+    let mut number = 0;
+    let mut previous: Option<&TimelineClip> = None;
+
+    for (i, clip) in clips.iter().enumerate() {
+        let rec_in = to_frames(clip.timeline_in, rate);
+        let mut rec_out = to_frames(clip.timeline_out, rate);
+        if previous.is_some_and(|p| to_frames(p.timeline_out, rate) > rec_in) {
+            return Err(EditronError::InvalidFormat(format!(
+                "Overlapping clips on track V{} at {:.3}s",
+                track, clip.timeline_in
+            )));
+        }
+        let speed = clip.speed as f64;
+        let src_in = to_frames(clip.source_in, rate);
+        let fade_out = exports_transition_out(clip, clips.get(i + 1), rate)
+            .then_some(clip.transition_out.as_ref())
+            .flatten()
+            .map(|spec| spec.to_transition(edit.frame_rate));
+        if let Some(transition) = &fade_out {
+            rec_out -= transition.duration_frames() as i64;
+        }
+
+        number += 1;
+        let mut lines = String::new();
+        match clip
+            .transition_in
+            .as_ref()
+            .map(|s| s.to_transition(edit.frame_rate))
+        {
+            Some(transition) => {
+                // The outgoing side is the previous clip when it cuts straight into
+                // this one, black otherwise
+                let from = previous.filter(|p| to_frames(p.timeline_out, rate) == rec_in);
+                let (from_reel, from_src) = match from {
+                    Some(p) => (REEL, to_frames(p.source_out, rate)),
+                    None => (BLACK_REEL, 0),
+                };
+                let _ = writeln!(
+                    lines,
+                    "{}",
+                    event_line(
+                        number,
+                        from_reel,
+                        "C",
+                        None,
+                        &timecode,
+                        [
+                            from_src,
+                            from_src,
+                            rec_in + record_offset,
+                            rec_in + record_offset
+                        ]
+                    )
+                );
+                let (code, frames) = transition_code(&transition);
+                let _ = writeln!(
+                    lines,
+                    "{}",
+                    event_line(
+                        number,
+                        REEL,
+                        &code,
+                        Some(frames),
+                        &timecode,
+                        [
+                            src_in,
+                            src_in + rec_out - rec_in,
+                            rec_in + record_offset,
+                            rec_out + record_offset
+                        ]
+                    )
+                );
+                let _ = writeln!(lines, "* EFFECT NAME: {}", effect_name(&transition));
+                if let Some(p) = from {
+                    let _ = writeln!(lines, "* FROM CLIP NAME: {}", file_name(&p.source));
+                }
+                let _ = writeln!(lines, "* TO CLIP NAME: {}", file_name(&clip.source));
+            }
+            None => {
+                let _ = writeln!(
+                    lines,
+                    "{}",
+                    event_line(
+                        number,
+                        REEL,
+                        "C",
+                        None,
+                        &timecode,
+                        [
+                            src_in,
+                            src_in + rec_out - rec_in,
+                            rec_in + record_offset,
+                            rec_out + record_offset
+                        ]
+                    )
+                );
+                let _ = writeln!(lines, "* FROM CLIP NAME: {}", file_name(&clip.source));
+            }
+        }
+        if speed != 1.0 {
+            let _ = writeln!(
+                lines,
+                "M2   {:<8} {:05.1}                {}",
+                REEL,
+                rate * speed,
+                timecode.format(src_in)
+            );
+        }
+        let _ = writeln!(lines, "* SOURCE FILE: {}", clip.source.display());
+        events.push((rec_in, rec_out, lines));
+
+        if let Some(transition) = fade_out {
+            number += 1;
+            let frames = transition.duration_frames() as i64;
+            let from_src = src_in + ((rec_out - rec_in) as f64 * speed).round() as i64;
+            let end = rec_out + frames;
+            let (code, _) = transition_code(&transition);
+            let mut lines = String::new();
+            let _ = writeln!(
+                lines,
+                "{}",
+                event_line(
+                    number,
+                    REEL,
+                    "C",
+                    None,
+                    &timecode,
+                    [
+                        from_src,
+                        from_src,
+                        rec_out + record_offset,
+                        rec_out + record_offset
+                    ]
+                )
+            );
+            let _ = writeln!(
+                lines,
+                "{}",
+                event_line(
+                    number,
+                    BLACK_REEL,
+                    &code,
+                    Some(frames),
+                    &timecode,
+                    [0, frames, rec_out + record_offset, end + record_offset]
+                )
+            );
+            let _ = writeln!(lines, "* EFFECT NAME: {}", effect_name(&transition));
+            let _ = writeln!(lines, "* FROM CLIP NAME: {}", file_name(&clip.source));
+            events.push((rec_out, end, lines));
+        }
+        previous = Some(clip);
+    }
+
+    // Locators follow the event they fall in (or the last one before them)
+    let mut locators: Vec<Vec<String>> = vec![Vec::new(); events.len().max(1)];
+    for marker in &edit.markers {
+        let frame = to_frames(marker.time, rate);
+        let index = events
+            .iter()
+            .rposition(|(start, _, _)| *start <= frame)
+            .unwrap_or(0);
+        locators[index].push(format!(
+            "* LOC: {} {:<7} {}",
+            timecode.format(frame + record_offset),
+            color_name(&marker.color, LOCATOR_COLORS),
+            marker.name
+        ));
+    }
+
+    for (i, (_, _, lines)) in events.iter().enumerate() {
+        out.push_str(lines);
+        for locator in &locators[i] {
+            let _ = writeln!(out, "{}", locator);
+        }
+        out.push('\n');
+    }
+    if events.is_empty() {
+        for locator in &locators[0] {
+            let _ = writeln!(out, "{}", locator);
+        }
+    }
+
+    Ok(out)
+}
+
+/// Parse an EDL. EDLs carry no frame rate, so `frame_rate` must match the one
+/// the list was written at.
+pub fn import(content: &str, frame_rate: f32) -> EditronResult<AssembledEdit> {
+    let rate = exchange_rate(frame_rate);
+    let timecode = Timecode::new(rate);
+    let mut title = "Imported EDL".to_string();
+    let mut events: Vec<Event> = Vec::new();
+
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("TITLE:") {
+            title = rest.trim().to_string();
+        } else if let Some(comment) = trimmed.strip_prefix('*') {
+            if let Some(event) = events.last_mut() {
+                event.comments.push(comment.trim().to_string());
+            }
+        } else if trimmed.starts_with("M2") {
+            let tokens: Vec<&str> = trimmed.split_whitespace().collect();
+            if let (Some(event), Some(fps)) = (
+                events.last_mut(),
+                tokens.get(2).and_then(|t| t.parse::<f64>().ok()),
+            ) {
+                event.speed = Some(fps / rate);
+            }
+        } else if trimmed.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+            let parsed = EventLine::parse(trimmed, &timecode).ok_or_else(|| {
+                EditronError::InvalidFormat(format!(
+                    "Bad EDL event on line {}: {}",
+                    line_number + 1,
+                    trimmed
+                ))
+            })?;
+            match events.last_mut() {
+                Some(event) if event.number == parsed.number => event.lines.push(parsed),
+                _ => events.push(Event {
+                    number: parsed.number,
+                    lines: vec![parsed],
+                    comments: Vec::new(),
+                    speed: None,
+                }),
+            }
+        }
+    }
+
+    let record_offset = events
+        .iter()
+        .flat_map(|e| e.lines.iter().map(|l| l.rec_in))
+        .min()
+        .filter(|first| *first >= RECORD_START_HOURS * 3600 * timecode.base)
+        .map(|_| RECORD_START_HOURS * 3600 * timecode.base)
+        .unwrap_or(0);
+    let seconds = |frames: i64| to_seconds(frames, rate);
+
+    let mut edit = AssembledEdit::new(&title, 0.0, 1920, 1080, frame_rate);
+    let mut clips: Vec<TimelineClip> = Vec::new();
+
+    for event in events.iter().filter(|e| {
+        e.lines
+            .iter()
+            .any(|l| l.channel.starts_with('V') || l.channel == "B")
+    }) {
+        let speed = event.speed.unwrap_or(1.0);
+        let incoming = event.lines.last().expect("events have at least one line");
+
+        if let Some(frames) = incoming.transition_frames {
+            let transition = parse_transition(&incoming.transition, frames, event.effect_name());
+            let spec = TransitionSpec::from_transition(&transition, frame_rate);
+            if incoming.reel == BLACK_REEL {
+                // Fade to black: the outgoing clip plays through the dissolve
+                if let Some(prev) = clips.last_mut() {
+                    prev.timeline_out = seconds(incoming.rec_out - record_offset);
+                    prev.source_out = prev.source_in + prev.duration() * prev.speed as f64;
+                    prev.transition_out = Some(spec);
+                }
+                continue;
+            }
+            let mut clip = clip_from_line(incoming, event, speed, record_offset, rate);
+            clip.transition_in = Some(spec);
+            clips.push(clip);
+        } else {
+            clips.push(clip_from_line(incoming, event, speed, record_offset, rate));
+        }
+    }
+    edit.video_clips = clips;
+
+    for event in &events {
+        for comment in &event.comments {
+            if let Some(marker) = parse_locator(comment, &timecode, record_offset, rate) {
+                edit.add_marker(marker);
+            }
+        }
+    }
+
+    finish_import(&mut edit);
+    Ok(edit)
+}
+
+struct Event {
+    number: u32,
+    lines: Vec<EventLine>,
+    comments: Vec<String>,
+    speed: Option<f64>,
+}
+
+impl Event {
+    fn comment(&self, key: &str) -> Option<&str> {
+        self.comments
+            .iter()
+            .find_map(|c| c.strip_prefix(key))
+            .map(|v| v.trim_start_matches(':').trim())
+    }
+
+    fn effect_name(&self) -> Option<&str> {
+        self.comment("EFFECT NAME")
+    }
+}
+
+struct EventLine {
+    number: u32,
+    reel: String,
+    channel: String,
+    transition: String,
+    transition_frames: Option<i64>,
+    src_in: i64,
+    rec_in: i64,
+    rec_out: i64,
+}
+
+impl EventLine {
+    fn parse(line: &str, timecode: &Timecode) -> Option<Self> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() < 8 {
+            return None;
+        }
+        let transition = tokens[3].to_string();
+        let (transition_frames, times) = if transition == "C" {
+            (None, &tokens[4..])
+        } else {
+            (Some(tokens.get(4)?.parse().ok()?), &tokens[5..])
+        };
+        if times.len() < 4 {
+            return None;
+        }
+        Some(Self {
+            number: tokens[0].parse().ok()?,
+            reel: tokens[1].to_string(),
+            channel: tokens[2].to_string(),
+            transition,
+            transition_frames,
+            src_in: timecode.parse(times[0])?,
+            rec_in: timecode.parse(times[2])?,
+            rec_out: timecode.parse(times[3])?,
+        })
+    }
+}
+
+fn clip_from_line(
+    line: &EventLine,
+    event: &Event,
+    speed: f64,
+    record_offset: i64,
+    rate: f64,
+) -> TimelineClip {
+    let source = event
+        .comment("SOURCE FILE")
+        .or_else(|| event.comment("TO CLIP NAME"))
+        .or_else(|| event.comment("FROM CLIP NAME"))
+        .unwrap_or(&line.reel);
+    let timeline_in = to_seconds(line.rec_in - record_offset, rate);
+    let duration = to_seconds(line.rec_out - line.rec_in, rate);
+    let source_in = to_seconds(line.src_in, rate);
+    let mut clip = TimelineClip::new(
+        PathBuf::from(source),
+        source_in,
+        source_in + duration * speed,
+        timeline_in,
+    );
+    clip.timeline_out = timeline_in + duration;
+    clip.speed = speed as f32;
+    clip
+}
+
+fn event_line(
+    number: u32,
+    reel: &str,
+    transition: &str,
+    frames: Option<i64>,
+    timecode: &Timecode,
+    times: [i64; 4],
+) -> String {
+    let frames = frames.map(|f| format!("{:03}", f)).unwrap_or_default();
+    format!(
+        "{:03}  {:<8} {:<5} {:<4} {:>3} {} {} {} {}",
+        number,
+        reel,
+        "V",
+        transition,
+        frames,
+        timecode.format(times[0]),
+        timecode.format(times[1]),
+        timecode.format(times[2]),
+        timecode.format(times[3])
+    )
+}
+
+/// CMX transition code (`D`, `Wnnn`) and duration in frames
+fn transition_code(transition: &Transition) -> (String, i64) {
+    let frames = transition.duration_frames() as i64;
+    let code = match transition {
+        Transition::Wipe { direction, .. } => format!("W{:03}", wipe_pattern(direction)),
+        _ => "D".to_string(),
+    };
+    (code, frames)
+}
+
+fn effect_name(transition: &Transition) -> &'static str {
+    match transition {
+        Transition::FadeToColor { color, .. } if color.eq_ignore_ascii_case("#FFFFFF") => {
+            "DIP TO WHITE"
+        }
+        Transition::FadeToColor { .. } => "DIP TO BLACK",
+        Transition::Wipe { .. } => "WIPE",
+        _ => "CROSS DISSOLVE",
+    }
+}
+
+fn parse_transition(code: &str, frames: i64, effect: Option<&str>) -> Transition {
+    let duration_frames = frames.max(0) as u32;
+    if let Some(pattern) = code.strip_prefix('W').and_then(|p| p.parse::<u32>().ok()) {
+        return Transition::Wipe {
+            duration_frames,
+            direction: wipe_direction(pattern),
+            softness: 0.0,
+        };
+    }
+    match effect.map(|e| e.to_ascii_uppercase()) {
+        Some(name) if name.starts_with("DIP TO") => Transition::FadeToColor {
+            duration_frames,
+            color: if name.contains("WHITE") {
+                "#FFFFFF"
+            } else {
+                "#000000"
+            }
+            .to_string(),
+            curve: EasingCurve::Linear,
+        },
+        _ => Transition::Dissolve {
+            duration_frames,
+            curve: EasingCurve::Linear,
+        },
+    }
+}
+
+/// SMPTE wipe pattern numbers: 1 horizontal, 2 vertical, 3-6 corners
+fn wipe_pattern(direction: &WipeDirection) -> u32 {
+    match direction {
+        WipeDirection::Left | WipeDirection::Right => 1,
+        WipeDirection::Up | WipeDirection::Down => 2,
+        WipeDirection::DiagonalTopLeft => 3,
+        WipeDirection::DiagonalTopRight => 4,
+        WipeDirection::DiagonalBottomRight => 5,
+        WipeDirection::DiagonalBottomLeft => 6,
+    }
+}
+
+fn wipe_direction(pattern: u32) -> WipeDirection {
+    match pattern {
+        2 => WipeDirection::Down,
+        3 => WipeDirection::DiagonalTopLeft,
+        4 => WipeDirection::DiagonalTopRight,
+        5 => WipeDirection::DiagonalBottomRight,
+        6 => WipeDirection::DiagonalBottomLeft,
+        _ => WipeDirection::Right,
+    }
+}
+
+/// `LOC: 01:00:05:00 RED     Chorus`
+fn parse_locator(
+    comment: &str,
+    timecode: &Timecode,
+    record_offset: i64,
+    rate: f64,
+) -> Option<EditMarker> {
+    let rest = comment.strip_prefix("LOC:")?.trim_start();
+    let (tc, rest) = rest.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (color, name) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Some(EditMarker {
+        time: to_seconds(timecode.parse(tc)? - record_offset, rate),
+        name: name.trim().to_string(),
+        color: color_hex(color),
+        marker_type: MarkerType::Note,
+    })
+}
+
+/// Non-drop-frame timecode at the nominal (rounded) frame rate
+struct Timecode {
+    base: i64,
+}
+
+impl Timecode {
+    fn new(rate: f64) -> Self {
+        Self {
+            base: (rate.round() as i64).max(1),
+        }
+    }
+
+    fn format(&self, frames: i64) -> String {
+        let frames = frames.max(0);
+        let seconds = frames / self.base;
+        format!(
+            "{:02}:{:02}:{:02}:{:02}",
+            seconds / 3600,
+            (seconds / 60) % 60,
+            seconds % 60,
+            frames % self.base
+        )
+    }
+
+    fn parse(&self, tc: &str) -> Option<i64> {
+        let parts: Vec<i64> = tc
+            .split([':', ';', '.'])
+            .map(|p| p.parse().ok())
+            .collect::<Option<_>>()?;
+        let [h, m, s, f] = parts.as_slice() else {
+            return None;
+        };
+        Some(((h * 60 + m) * 60 + s) * self.base + f)
+    }
+}
@@ -0,0 +1,943 @@
+//! FCPXML 1.9+
+//!
+//! Video track 1 becomes the primary storyline (`spine`); higher video tracks
+//! and all audio are connected clips on positive and negative lanes. Times are
+//! rational frame multiples of the sequence `frameDuration`. Transitions on
+//! connected lanes need secondary storylines and are not exported.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use roxmltree::{Document, Node, ParsingOptions};
+
+use super::{
+    super::{
+        EditronError, EditronResult,
+        edit_assembly::{
+            AssembledEdit, AudioClip, EditMarker, MarkerType, TimelineClip, TransitionSpec,
+        },
+        transitions::{EasingCurve, Transition, WipeDirection},
+    },
+    audio_tracks, exchange_rate, exports_transition_out, file_name, file_url, finish_import,
+    path_from_url, to_frames, video_tracks,
+};
+
+const VERSION: &str = "1.9";
+const CROSS_DISSOLVE_UID: &str = "FxPlug:4731E73A-8DAC-4113-9A30-AE85B1761265";
+const FADE_TO_COLOR_UID: &str =
+    ".../Transitions.localized/Dissolves.localized/Fade To Color.localized/Fade To Color.motr";
+const WIPE_UID: &str = ".../Transitions.localized/Wipes.localized/Wipe.localized/Wipe.motr";
+
+pub fn export(edit: &AssembledEdit) -> EditronResult<String> {
+    let time = RationalClock::new(edit.frame_rate);
+    let rate = exchange_rate(edit.frame_rate);
+    let mut tracks = video_tracks(edit);
+    let primary_number = tracks.keys().next().copied().unwrap_or(1);
+    let primary = tracks.remove(&primary_number).unwrap_or_default();
+    let audio = audio_tracks(edit);
+
+    // Resources: one asset per source file, then the transition effects in use
+    let mut assets: Vec<(PathBuf, f64, bool, bool)> = Vec::new();
+    let mut note_asset = |path: &Path, source_out: f64, video: bool| match assets
+        .iter_mut()
+        .find(|(p, ..)| p == path)
+    {
+        Some(asset) => {
+            asset.1 = asset.1.max(source_out);
+            asset.2 |= video;
+        }
+        None => assets.push((path.to_path_buf(), source_out, video, !video)),
+    };
+    for clip in primary.iter().chain(tracks.values().flatten()) {
+        note_asset(&clip.source, clip.source_out, true);
+    }
+    for clip in audio.values().flatten() {
+        note_asset(&clip.source, clip.source_out, false);
+    }
+    let asset_id = |path: &Path| {
+        assets
+            .iter()
+            .position(|(p, ..)| p == path)
+            .map(|i| format!("r{}", i + 2))
+            .unwrap_or_default()
+    };
+
+    let mut effects: Vec<(&'static str, &'static str)> = Vec::new();
+    for clip in &primary {
+        for spec in [&clip.transition_in, &clip.transition_out]
+            .into_iter()
+            .flatten()
+        {
+            let effect = effect_for(&spec.to_transition(edit.frame_rate));
+            if !effects.contains(&effect) {
+                effects.push(effect);
+            }
+        }
+    }
+    let effect_id = |name: &str| {
+        effects
+            .iter()
+            .position(|(n, _)| *n == name)
+            .map(|i| format!("r{}", assets.len() + 2 + i))
+            .unwrap_or_default()
+    };
+
+    // Primary storyline, with gaps filling holes and running to the end of the edit
+    let mut spine: Vec<SpineItem> = Vec::new();
+    let mut cursor = 0;
+    for clip in &primary {
+        let start = to_frames(clip.timeline_in, rate);
+        if start < cursor {
+            return Err(EditronError::InvalidFormat(format!(
+                "Overlapping clips on track V{} at {:.3}s",
+                primary_number, clip.timeline_in
+            )));
+        }
+        if start > cursor {
+            spine.push(SpineItem::gap(cursor, start));
+        }
+        let end = to_frames(clip.timeline_out, rate);
+        spine.push(SpineItem {
+            clip: Some(clip),
+            offset: start,
+            duration: end - start,
+            local_start: to_frames(clip.source_in, rate),
+        });
+        cursor = end;
+    }
+    let edit_end = to_frames(edit.duration, rate)
+        .max(
+            tracks
+                .values()
+                .flatten()
+                .map(|c| to_frames(c.timeline_out, rate))
+                .max()
+                .unwrap_or(0),
+        )
+        .max(
+            audio
+                .values()
+                .flatten()
+                .map(|c| to_frames(c.timeline_out, rate))
+                .max()
+                .unwrap_or(0),
+        )
+        .max(
+            edit.markers
+                .iter()
+                .map(|m| to_frames(m.time, rate) + 1)
+                .max()
+                .unwrap_or(0),
+        );
+    if edit_end > cursor {
+        spine.push(SpineItem::gap(cursor, edit_end));
+    }
+
+    // Connected clips and markers hang off the spine item they start in
+    let host = |frame: i64| {
+        spine
+            .iter()
+            .rposition(|item| item.offset <= frame)
+            .unwrap_or(0)
+    };
+    let mut attached: Vec<Vec<String>> = vec![Vec::new(); spine.len()];
+    for (number, clips) in &tracks {
+        for clip in clips {
+            let index = host(to_frames(clip.timeline_in, rate));
+            let offset = spine[index].local(to_frames(clip.timeline_in, rate));
+            attached[index].push(video_clip_xml(
+                clip,
+                &asset_id(&clip.source),
+                Some(*number as i64 - primary_number as i64),
+                offset,
+                &time,
+                rate,
+                &[],
+            ));
+        }
+    }
+    for (number, clips) in &audio {
+        for clip in clips {
+            let index = host(to_frames(clip.timeline_in, rate));
+            let offset = spine[index].local(to_frames(clip.timeline_in, rate));
+            attached[index].push(audio_clip_xml(
+                clip,
+                &asset_id(&clip.source),
+                -(*number as i64),
+                offset,
+                &time,
+                rate,
+            ));
+        }
+    }
+    for marker in &edit.markers {
+        let frame = to_frames(marker.time, rate);
+        let index = host(frame);
+        attached[index].push(marker_xml(marker, spine[index].local(frame), &time));
+    }
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<!DOCTYPE fcpxml>\n");
+    let _ = writeln!(xml, "<fcpxml version=\"{}\">", VERSION);
+    xml.push_str("  <resources>\n");
+    let _ = writeln!(
+        xml,
+        "    <format id=\"r1\" frameDuration=\"{}\" width=\"{}\" height=\"{}\"/>",
+        time.format(1),
+        edit.width,
+        edit.height
+    );
+    for (i, (path, duration, has_video, has_audio)) in assets.iter().enumerate() {
+        let format_attr = if *has_video { " format=\"r1\"" } else { "" };
+        let _ = writeln!(
+            xml,
+            "    <asset id=\"r{}\" name=\"{}\" start=\"0s\" duration=\"{}\" hasVideo=\"{}\" hasAudio=\"{}\"{}>",
+            i + 2,
+            escape_xml(&file_stem(path)),
+            time.format(to_frames(*duration, rate)),
+            u8::from(*has_video),
+            u8::from(*has_audio || *has_video),
+            format_attr
+        );
+        let _ = writeln!(
+            xml,
+            "      <media-rep kind=\"original-media\" src=\"{}\"/>",
+            escape_xml(&file_url(path))
+        );
+        xml.push_str("    </asset>\n");
+    }
+    for (name, uid) in &effects {
+        let _ = writeln!(
+            xml,
+            "    <effect id=\"{}\" name=\"{}\" uid=\"{}\"/>",
+            effect_id(name),
+            name,
+            escape_xml(uid)
+        );
+    }
+    xml.push_str("  </resources>\n");
+    xml.push_str("  <library>\n");
+    xml.push_str("    <event name=\"Editron\">\n");
+    let _ = writeln!(xml, "      <project name=\"{}\">", escape_xml(&edit.name));
+    let _ = writeln!(
+        xml,
+        "        <sequence format=\"r1\" duration=\"{}\" tcStart=\"0s\" tcFormat=\"NDF\" audioLayout=\"stereo\" audioRate=\"48k\">",
+        time.format(edit_end)
+    );
+    xml.push_str("          <spine>\n");
+
+    for (i, item) in spine.iter().enumerate() {
+        let next_clip = spine[i + 1..].iter().find_map(|s| s.clip);
+        if let Some(clip) = item.clip
+            && let Some(spec) = &clip.transition_in
+        {
+            let transition = spec.to_transition(edit.frame_rate);
+            xml.push_str(&transition_xml(
+                &transition,
+                item.offset,
+                &effect_id(effect_for(&transition).0),
+                &time,
+            ));
+        }
+
+        let children = &attached[i];
+        let element = match item.clip {
+            Some(clip) => video_clip_xml(
+                clip,
+                &asset_id(&clip.source),
+                None,
+                item.offset,
+                &time,
+                rate,
+                children,
+            ),
+            None => {
+                let mut gap = format!(
+                    "<gap name=\"Gap\" offset=\"{}\" start=\"0s\" duration=\"{}\"",
+                    time.format(item.offset),
+                    time.format(item.duration)
+                );
+                if children.is_empty() {
+                    gap.push_str("/>\n");
+                } else {
+                    gap.push_str(">\n");
+                    for child in children {
+                        gap.push_str(&indent(child, "  "));
+                    }
+                    gap.push_str("</gap>\n");
+                }
+                gap
+            }
+        };
+        xml.push_str(&indent(&element, "            "));
+
+        if let Some(clip) = item.clip
+            && exports_transition_out(clip, next_clip.as_ref(), rate)
+            && let Some(spec) = &clip.transition_out
+        {
+            let transition = spec.to_transition(edit.frame_rate);
+            let frames = transition.duration_frames() as i64;
+            xml.push_str(&transition_xml(
+                &transition,
+                item.offset + item.duration - frames,
+                &effect_id(effect_for(&transition).0),
+                &time,
+            ));
+        }
+    }
+
+    xml.push_str("          </spine>\n");
+    xml.push_str("        </sequence>\n");
+    xml.push_str("      </project>\n");
+    xml.push_str("    </event>\n");
+    xml.push_str("  </library>\n");
+    xml.push_str("</fcpxml>\n");
+    Ok(xml)
+}
+
+pub fn import(content: &str) -> EditronResult<AssembledEdit> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let doc = Document::parse_with_options(content, options)
+        .map_err(|e| EditronError::InvalidFormat(format!("Invalid FCPXML: {}", e)))?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "fcpxml" {
+        return Err(EditronError::InvalidFormat(
+            "Not an FCPXML document".to_string(),
+        ));
+    }
+
+    let mut formats: HashMap<&str, Node> = HashMap::new();
+    let mut assets: HashMap<&str, PathBuf> = HashMap::new();
+    let mut effects: HashMap<&str, &str> = HashMap::new();
+    if let Some(resources) = child(root, "resources") {
+        for resource in resources.children().filter(Node::is_element) {
+            let Some(id) = resource.attribute("id") else {
+                continue;
+            };
+            match resource.tag_name().name() {
+                "format" => {
+                    formats.insert(id, resource);
+                }
+                "asset" => {
+                    let src = resource.attribute("src").or_else(|| {
+                        resource
+                            .children()
+                            .filter(|c| c.has_tag_name("media-rep"))
+                            .find(|c| {
+                                c.attribute("kind").unwrap_or("original-media") == "original-media"
+                            })
+                            .and_then(|c| c.attribute("src"))
+                    });
+                    assets.insert(id, path_from_url(src.unwrap_or_default()));
+                }
+                "effect" => {
+                    effects.insert(id, resource.attribute("name").unwrap_or_default());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let sequence = root
+        .descendants()
+        .find(|n| n.has_tag_name("sequence"))
+        .ok_or_else(|| EditronError::InvalidFormat("FCPXML has no sequence".to_string()))?;
+    let name = sequence
+        .ancestors()
+        .find(|n| n.has_tag_name("project"))
+        .and_then(|p| p.attribute("name"))
+        .unwrap_or("Imported FCPXML");
+    let format = sequence.attribute("format").and_then(|id| formats.get(id));
+    let frame_duration = format
+        .and_then(|f| f.attribute("frameDuration"))
+        .and_then(parse_time)
+        .filter(|d| *d > 0.0)
+        .unwrap_or(1.0 / 24.0);
+    let frame_rate = ((1.0 / frame_duration) * 1000.0).round() / 1000.0;
+    let dimension = |attr: &str, default: u32| {
+        format
+            .and_then(|f| f.attribute(attr))
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    let mut edit = AssembledEdit::new(
+        name,
+        0.0,
+        dimension("width", 1920),
+        dimension("height", 1080),
+        frame_rate as f32,
+    );
+
+    let spine = child(sequence, "spine")
+        .ok_or_else(|| EditronError::InvalidFormat("FCPXML sequence has no spine".to_string()))?;
+    let mut transitions: Vec<(f64, f64, Transition)> = Vec::new();
+    for item in spine.children().filter(Node::is_element) {
+        let offset = time_attr(item, "offset");
+        let start = time_attr(item, "start");
+        match item.tag_name().name() {
+            "transition" => {
+                let duration = time_attr(item, "duration");
+                transitions.push((
+                    offset,
+                    duration,
+                    parse_transition(item, &effects, duration, frame_rate),
+                ));
+                continue;
+            }
+            "asset-clip" | "clip" | "video" => {
+                if let Some(clip) = parse_video_clip(item, &assets, offset, 1) {
+                    edit.add_video_clip(clip);
+                }
+            }
+            _ => {}
+        }
+
+        // Connected clips and markers are timed in the parent's local time
+        let to_timeline = |local: f64| offset + (local - start);
+        for connected in item.children().filter(Node::is_element) {
+            let lane: i64 = connected
+                .attribute("lane")
+                .and_then(|l| l.parse().ok())
+                .unwrap_or(0);
+            match connected.tag_name().name() {
+                "asset-clip" | "clip" | "video" | "audio" if lane != 0 => {
+                    let timeline_in = to_timeline(time_attr(connected, "offset"));
+                    if lane > 0 && connected.tag_name().name() != "audio" {
+                        if let Some(clip) =
+                            parse_video_clip(connected, &assets, timeline_in, lane as u32 + 1)
+                        {
+                            edit.add_video_clip(clip);
+                        }
+                    } else if let Some(clip) = parse_audio_clip(
+                        connected,
+                        &assets,
+                        timeline_in,
+                        lane.unsigned_abs() as u32,
+                    ) {
+                        edit.add_audio_clip(clip);
+                    }
+                }
+                "marker" | "chapter-marker" => {
+                    edit.add_marker(parse_marker(
+                        connected,
+                        to_timeline(time_attr(connected, "start")),
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // A transition starting where a primary clip starts leads into it; any other
+    // ends the clip it overlaps
+    let half_frame = frame_duration / 2.0;
+    for (offset, duration, transition) in transitions {
+        let spec = TransitionSpec::from_transition(&transition, frame_rate as f32);
+        let primary = edit.video_clips.iter_mut().filter(|c| c.track == 1);
+        let mut primary: Vec<&mut TimelineClip> = primary.collect();
+        if let Some(clip) = primary
+            .iter_mut()
+            .find(|c| (c.timeline_in - offset).abs() < half_frame)
+        {
+            clip.transition_in = Some(spec);
+        } else if let Some(clip) = primary
+            .iter_mut()
+            .find(|c| (c.timeline_out - (offset + duration)).abs() < half_frame)
+        {
+            clip.transition_out = Some(spec);
+        }
+    }
+
+    finish_import(&mut edit);
+    Ok(edit)
+}
+
+/// An element of the primary storyline: a clip or a gap
+struct SpineItem<'a> {
+    clip: Option<&'a TimelineClip>,
+    offset: i64,
+    duration: i64,
+    /// Frame of the item's own time at its first timeline frame
+    local_start: i64,
+}
+
+impl SpineItem<'_> {
+    fn gap(start: i64, end: i64) -> Self {
+        Self {
+            clip: None,
+            offset: start,
+            duration: end - start,
+            local_start: 0,
+        }
+    }
+
+    /// Item-local time of a timeline frame
+    fn local(&self, frame: i64) -> i64 {
+        self.local_start + (frame - self.offset)
+    }
+}
+
+/// Formats frame counts as FCPXML rational times (`1001/30000s`)
+struct RationalClock {
+    numerator: i64,
+    denominator: i64,
+}
+
+impl RationalClock {
+    fn new(frame_rate: f32) -> Self {
+        let (numerator, denominator) = match (frame_rate as f64 * 1000.0).round() as i64 {
+            23976 => (1001, 24000),
+            29970 => (1001, 30000),
+            47952 => (1001, 48000),
+            59940 => (1001, 60000),
+            millis => (100, (millis / 10).max(1)),
+        };
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+
+    fn format(&self, frames: i64) -> String {
+        if frames == 0 {
+            return "0s".to_string();
+        }
+        let numerator = frames * self.numerator;
+        let divisor = gcd(numerator.abs(), self.denominator);
+        let (n, d) = (numerator / divisor, self.denominator / divisor);
+        if d == 1 {
+            format!("{}s", n)
+        } else {
+            format!("{}/{}s", n, d)
+        }
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.max(1) } else { gcd(b, a % b) }
+}
+
+/// `1001/30000s`, `5s` or `0s` in seconds
+fn parse_time(value: &str) -> Option<f64> {
+    let value = value.trim().strip_suffix('s')?;
+    match value.split_once('/') {
+        Some((n, d)) => Some(n.parse::<f64>().ok()? / d.parse::<f64>().ok()?),
+        None => value.parse().ok(),
+    }
+}
+
+fn time_attr(node: Node, name: &str) -> f64 {
+    node.attribute(name).and_then(parse_time).unwrap_or(0.0)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|c| c.has_tag_name(name))
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| file_name(path))
+}
+
+fn effect_for(transition: &Transition) -> (&'static str, &'static str) {
+    match transition {
+        Transition::FadeToColor { .. } => ("Fade To Color", FADE_TO_COLOR_UID),
+        Transition::Wipe { .. } => ("Wipe", WIPE_UID),
+        _ => ("Cross Dissolve", CROSS_DISSOLVE_UID),
+    }
+}
+
+fn transition_xml(
+    transition: &Transition,
+    offset: i64,
+    effect_ref: &str,
+    time: &RationalClock,
+) -> String {
+    let (name, _) = effect_for(transition);
+    let frames = transition.duration_frames() as i64;
+    let mut xml = format!(
+        "            <transition name=\"{}\" offset=\"{}\" duration=\"{}\">\n",
+        name,
+        time.format(offset),
+        time.format(frames)
+    );
+    let params = match transition {
+        Transition::FadeToColor { color, .. } => vec![("Color", fcp_color(color))],
+        Transition::Wipe { direction, .. } => vec![("Angle", wipe_angle(direction).to_string())],
+        _ => Vec::new(),
+    };
+    if params.is_empty() {
+        let _ = writeln!(
+            xml,
+            "              <filter-video ref=\"{}\" name=\"{}\"/>",
+            effect_ref, name
+        );
+    } else {
+        let _ = writeln!(
+            xml,
+            "              <filter-video ref=\"{}\" name=\"{}\">",
+            effect_ref, name
+        );
+        for (param, value) in params {
+            let _ = writeln!(
+                xml,
+                "                <param name=\"{}\" value=\"{}\"/>",
+                param, value
+            );
+        }
+        xml.push_str("              </filter-video>\n");
+    }
+    xml.push_str("            </transition>\n");
+    xml
+}
+
+fn parse_transition(
+    node: Node,
+    effects: &HashMap<&str, &str>,
+    duration: f64,
+    frame_rate: f64,
+) -> Transition {
+    let filter = child(node, "filter-video");
+    let name = filter
+        .and_then(|f| f.attribute("ref"))
+        .and_then(|r| effects.get(r).copied())
+        .or_else(|| node.attribute("name"))
+        .unwrap_or("Cross Dissolve");
+    let param = |param: &str| {
+        filter
+            .into_iter()
+            .flat_map(|f| f.children())
+            .find(|c| c.has_tag_name("param") && c.attribute("name") == Some(param))
+            .and_then(|c| c.attribute("value"))
+    };
+    let duration_frames = (duration * frame_rate).round() as u32;
+    if name.contains("Fade To Color") || name.contains("Dip") {
+        let white = param("Color").is_some_and(|c| {
+            c.split_whitespace()
+                .all(|v| v.parse::<f64>().is_ok_and(|v| v >= 0.99))
+        });
+        Transition::FadeToColor {
+            duration_frames,
+            color: if white { "#FFFFFF" } else { "#000000" }.to_string(),
+            curve: EasingCurve::Linear,
+        }
+    } else if name.contains("Wipe") {
+        let angle: f64 = param("Angle").and_then(|a| a.parse().ok()).unwrap_or(0.0);
+        TransitionSpec::wipe(duration_frames as f64 / frame_rate, angle)
+            .to_transition(frame_rate as f32)
+    } else {
+        Transition::Dissolve {
+            duration_frames,
+            curve: EasingCurve::Linear,
+        }
+    }
+}
+
+fn wipe_angle(direction: &WipeDirection) -> i32 {
+    match direction {
+        WipeDirection::Right => 0,
+        WipeDirection::DiagonalBottomRight => 45,
+        WipeDirection::Down => 90,
+        WipeDirection::DiagonalBottomLeft => 135,
+        WipeDirection::Left => 180,
+        WipeDirection::DiagonalTopLeft => 225,
+        WipeDirection::Up => 270,
+        WipeDirection::DiagonalTopRight => 315,
+    }
+}
+
+/// `#RRGGBB` as FCP's normalised `r g b`
+fn fcp_color(hex: &str) -> String {
+    let hex = hex.trim_start_matches('#');
+    (0..3)
+        .map(|i| {
+            let value = hex
+                .get(i * 2..i * 2 + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .unwrap_or(0);
+            format!("{}", (value as f64 / 255.0 * 1000.0).round() / 1000.0)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// An `asset-clip` with its retiming and adjustments, followed by any nested
+/// connected clips and markers
+#[allow(clippy::too_many_arguments)]
+fn video_clip_xml(
+    clip: &TimelineClip,
+    asset_ref: &str,
+    lane: Option<i64>,
+    offset: i64,
+    time: &RationalClock,
+    rate: f64,
+    children: &[String],
+) -> String {
+    let start = to_frames(clip.source_in, rate);
+    let duration = to_frames(clip.timeline_out, rate) - to_frames(clip.timeline_in, rate);
+    let lane_attr = lane.map(|l| format!(" lane=\"{}\"", l)).unwrap_or_default();
+    let mut xml = format!(
+        "<asset-clip ref=\"{}\"{} offset=\"{}\" name=\"{}\" start=\"{}\" duration=\"{}\" tcFormat=\"NDF\"",
+        asset_ref,
+        lane_attr,
+        time.format(offset),
+        escape_xml(&file_stem(&clip.source)),
+        time.format(start),
+        time.format(duration),
+    );
+
+    let mut body = String::new();
+    if clip.speed != 1.0 {
+        body.push_str("  <timeMap>\n");
+        let _ = writeln!(
+            body,
+            "    <timept time=\"{}\" value=\"{}\" interp=\"linear\"/>",
+            time.format(start),
+            time.format(start)
+        );
+        let _ = writeln!(
+            body,
+            "    <timept time=\"{}\" value=\"{}\" interp=\"linear\"/>",
+            time.format(start + duration),
+            time.format(to_frames(clip.source_out, rate))
+        );
+        body.push_str("  </timeMap>\n");
+    }
+    if clip.scale != 1.0 || clip.position != (0.0, 0.0) {
+        let _ = writeln!(
+            body,
+            "  <adjust-transform position=\"{} {}\" scale=\"{} {}\"/>",
+            clip.position.0, clip.position.1, clip.scale, clip.scale
+        );
+    }
+    if clip.opacity != 1.0 {
+        let _ = writeln!(body, "  <adjust-blend amount=\"{}\"/>", clip.opacity);
+    }
+    for child in children {
+        body.push_str(&indent(child, "  "));
+    }
+
+    if body.is_empty() {
+        xml.push_str("/>\n");
+    } else {
+        xml.push_str(">\n");
+        xml.push_str(&body);
+        xml.push_str("</asset-clip>\n");
+    }
+    xml
+}
+
+fn audio_clip_xml(
+    clip: &AudioClip,
+    asset_ref: &str,
+    lane: i64,
+    offset: i64,
+    time: &RationalClock,
+    rate: f64,
+) -> String {
+    let start = to_frames(clip.source_in, rate);
+    let duration = to_frames(clip.timeline_out, rate) - to_frames(clip.timeline_in, rate);
+    let role = if clip.is_music { "music" } else { "dialogue" };
+    let mut xml = format!(
+        "<asset-clip ref=\"{}\" lane=\"{}\" offset=\"{}\" name=\"{}\" start=\"{}\" duration=\"{}\" audioRole=\"{}\">\n",
+        asset_ref,
+        lane,
+        time.format(offset),
+        escape_xml(&file_stem(&clip.source)),
+        time.format(start),
+        time.format(duration),
+        role
+    );
+    let gain_db = 20.0 * (clip.volume.max(0.0001) as f64).log10();
+    let _ = write!(xml, "  <adjust-volume amount=\"{:.4}dB\"", gain_db);
+    if clip.fade_in.is_none() && clip.fade_out.is_none() {
+        xml.push_str("/>\n");
+    } else {
+        xml.push_str(">\n    <param name=\"amount\">\n");
+        if let Some(fade) = clip.fade_in {
+            let _ = writeln!(
+                xml,
+                "      <fadeIn type=\"easeIn\" duration=\"{}\"/>",
+                time.format(to_frames(fade, rate))
+            );
+        }
+        if let Some(fade) = clip.fade_out {
+            let _ = writeln!(
+                xml,
+                "      <fadeOut type=\"easeOut\" duration=\"{}\"/>",
+                time.format(to_frames(fade, rate))
+            );
+        }
+        xml.push_str("    </param>\n  </adjust-volume>\n");
+    }
+    xml.push_str("</asset-clip>\n");
+    xml
+}
+
+/// Chapters become chapter markers; the marker type and colour ride in `note`
+fn marker_xml(marker: &EditMarker, start: i64, time: &RationalClock) -> String {
+    let element = match marker.marker_type {
+        MarkerType::Chapter => "chapter-marker",
+        _ => "marker",
+    };
+    let kind = serde_json::to_value(marker.marker_type)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    format!(
+        "<{} start=\"{}\" duration=\"{}\" value=\"{}\" note=\"{} {}\"/>\n",
+        element,
+        time.format(start),
+        time.format(1),
+        escape_xml(&marker.name),
+        kind,
+        escape_xml(&marker.color)
+    )
+}
+
+fn parse_marker(node: Node, time: f64) -> EditMarker {
+    let mut note = node
+        .attribute("note")
+        .unwrap_or_default()
+        .split_whitespace();
+    let kind = note.next().unwrap_or_default();
+    let color = note.next().filter(|c| c.starts_with('#'));
+    let marker_type = serde_json::from_value(serde_json::Value::String(kind.to_string()))
+        .unwrap_or(if node.has_tag_name("chapter-marker") {
+            MarkerType::Chapter
+        } else {
+            MarkerType::Note
+        });
+    EditMarker {
+        time,
+        name: node.attribute("value").unwrap_or_default().to_string(),
+        color: color.unwrap_or("#D0021B").to_string(),
+        marker_type,
+    }
+}
+
+/// The media reference of a clip element: its own `ref`, or that of the first
+/// nested `video`/`audio` for `clip` elements
+fn media_ref<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.attribute("ref").or_else(|| {
+        node.children()
+            .find(|c| {
+                c.has_tag_name("video") || c.has_tag_name("audio") || c.has_tag_name("asset-clip")
+            })
+            .and_then(|c| c.attribute("ref"))
+    })
+}
+
+/// Source range of a clip, following a linear `timeMap` for retimed clips
+fn source_range(node: Node, start: f64, duration: f64) -> (f64, f64, f64) {
+    let points: Vec<(f64, f64)> = child(node, "timeMap")
+        .into_iter()
+        .flat_map(|m| m.children().filter(|c| c.has_tag_name("timept")))
+        .map(|p| (time_attr(p, "time"), time_attr(p, "value")))
+        .collect();
+    match (points.first(), points.last()) {
+        (Some(first), Some(last)) if last.0 > first.0 => {
+            let speed = (last.1 - first.1) / (last.0 - first.0);
+            let source_in = first.1 + (start - first.0) * speed;
+            (source_in, source_in + duration * speed, speed)
+        }
+        _ => (start, start + duration, 1.0),
+    }
+}
+
+fn parse_video_clip(
+    node: Node,
+    assets: &HashMap<&str, PathBuf>,
+    timeline_in: f64,
+    track: u32,
+) -> Option<TimelineClip> {
+    let source = assets.get(media_ref(node)?)?.clone();
+    let duration = time_attr(node, "duration");
+    let (source_in, source_out, speed) = source_range(node, time_attr(node, "start"), duration);
+    let mut clip = TimelineClip::new(source, source_in, source_out, timeline_in).with_track(track);
+    clip.timeline_out = timeline_in + duration;
+    clip.speed = ((speed * 1000.0).round() / 1000.0) as f32;
+    if let Some(transform) = child(node, "adjust-transform") {
+        let numbers = |attr: &str| -> Vec<f32> {
+            transform
+                .attribute(attr)
+                .unwrap_or_default()
+                .split_whitespace()
+                .filter_map(|v| v.parse().ok())
+                .collect()
+        };
+        if let [x, y] = numbers("position").as_slice() {
+            clip.position = (*x, *y);
+        }
+        if let Some(scale) = numbers("scale").first() {
+            clip.scale = *scale;
+        }
+    }
+    if let Some(amount) = child(node, "adjust-blend")
+        .and_then(|b| b.attribute("amount"))
+        .and_then(|a| a.parse().ok())
+    {
+        clip.opacity = amount;
+    }
+    Some(clip)
+}
+
+fn parse_audio_clip(
+    node: Node,
+    assets: &HashMap<&str, PathBuf>,
+    timeline_in: f64,
+    track: u32,
+) -> Option<AudioClip> {
+    let source = assets.get(media_ref(node)?)?.clone();
+    let duration = time_attr(node, "duration");
+    let (source_in, source_out, _) = source_range(node, time_attr(node, "start"), duration);
+    let volume_node = child(node, "adjust-volume");
+    let volume = volume_node
+        .and_then(|v| v.attribute("amount"))
+        .and_then(|a| a.trim_end_matches("dB").parse::<f64>().ok())
+        .map(|db| (10f64.powf(db / 20.0) * 1000.0).round() / 1000.0)
+        .unwrap_or(1.0);
+    let fade = |name: &str| {
+        volume_node
+            .into_iter()
+            .flat_map(|v| v.descendants())
+            .find(|n| n.has_tag_name(name))
+            .map(|n| time_attr(n, "duration"))
+    };
+    Some(AudioClip {
+        source,
+        source_in,
+        source_out,
+        timeline_in,
+        timeline_out: timeline_in + duration,
+        track,
+        volume: volume as f32,
+        fade_in: fade("fadeIn"),
+        fade_out: fade("fadeOut"),
+        is_music: node
+            .attribute("audioRole")
+            .is_some_and(|r| r.starts_with("music")),
+    })
+}
+
+fn indent(xml: &str, prefix: &str) -> String {
+    xml.lines()
+        .map(|line| format!("{}{}\n", prefix, line))
+        .collect()
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
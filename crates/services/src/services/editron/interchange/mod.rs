@@ -0,0 +1,240 @@
+//! Timeline Interchange
+//!
+//! Import and export of [`AssembledEdit`] for NLEs other than Premiere:
+//! - OpenTimelineIO JSON (`.otio`) - lossless, Editron extras kept in metadata
+//! - CMX3600 EDL (`.edl`) - picture track for colour conform in Resolve
+//! - FCPXML 1.9+ (`.fcpxml`) - Final Cut Pro and Resolve
+//!
+//! Transitions are exchanged through [`super::transitions::Transition`]: a clip's `transition_in`
+//! starts at its first frame and a `transition_out` ends on its last frame,
+//! matching the alignment used by the Premiere XML export.
+
+pub mod edl;
+pub mod fcpxml;
+pub mod otio;
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    EditronError, EditronResult,
+    edit_assembly::{AssembledEdit, AudioClip, TimelineClip},
+};
+
+/// Supported interchange formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InterchangeFormat {
+    Otio,
+    Edl,
+    Fcpxml,
+}
+
+impl InterchangeFormat {
+    pub fn from_path(path: &Path) -> EditronResult<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "otio" => Ok(Self::Otio),
+            "edl" => Ok(Self::Edl),
+            "fcpxml" => Ok(Self::Fcpxml),
+            _ => Err(EditronError::InvalidFormat(format!(
+                "Unsupported timeline file: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Otio => "otio",
+            Self::Edl => "edl",
+            Self::Fcpxml => "fcpxml",
+        }
+    }
+}
+
+/// Serialize an edit in `format`
+pub fn export_edit(edit: &AssembledEdit, format: InterchangeFormat) -> EditronResult<String> {
+    match format {
+        InterchangeFormat::Otio => otio::export(edit),
+        InterchangeFormat::Edl => edl::export(edit),
+        InterchangeFormat::Fcpxml => fcpxml::export(edit),
+    }
+}
+
+/// Parse an edit from `content`. `frame_rate` is only used by formats that do not
+/// carry one (EDL).
+pub fn import_edit(
+    content: &str,
+    format: InterchangeFormat,
+    frame_rate: f32,
+) -> EditronResult<AssembledEdit> {
+    match format {
+        InterchangeFormat::Otio => otio::import(content),
+        InterchangeFormat::Edl => edl::import(content, frame_rate),
+        InterchangeFormat::Fcpxml => fcpxml::import(content),
+    }
+}
+
+/// Frame rate as exchanged in files: NTSC rates keep three decimals (29.97,
+/// 23.976) instead of the f32 noise
+pub(crate) fn exchange_rate(frame_rate: f32) -> f64 {
+    (frame_rate as f64 * 1000.0).round() / 1000.0
+}
+
+pub(crate) fn to_frames(seconds: f64, rate: f64) -> i64 {
+    (seconds * rate).round() as i64
+}
+
+pub(crate) fn to_seconds(frames: i64, rate: f64) -> f64 {
+    frames as f64 / rate
+}
+
+/// Video clips grouped by track number, each track sorted by timeline position
+pub(crate) fn video_tracks(edit: &AssembledEdit) -> BTreeMap<u32, Vec<&TimelineClip>> {
+    let mut tracks: BTreeMap<u32, Vec<&TimelineClip>> = BTreeMap::new();
+    for clip in &edit.video_clips {
+        tracks.entry(clip.track).or_default().push(clip);
+    }
+    for clips in tracks.values_mut() {
+        clips.sort_by(|a, b| a.timeline_in.total_cmp(&b.timeline_in));
+    }
+    tracks
+}
+
+/// Audio clips grouped by track number, each track sorted by timeline position
+pub(crate) fn audio_tracks(edit: &AssembledEdit) -> BTreeMap<u32, Vec<&AudioClip>> {
+    let mut tracks: BTreeMap<u32, Vec<&AudioClip>> = BTreeMap::new();
+    for clip in &edit.audio_clips {
+        tracks.entry(clip.track).or_default().push(clip);
+    }
+    for clips in tracks.values_mut() {
+        clips.sort_by(|a, b| a.timeline_in.total_cmp(&b.timeline_in));
+    }
+    tracks
+}
+
+/// Whether `clip`'s `transition_out` is exchanged: only when no clip follows
+/// directly, otherwise the next clip's `transition_in` covers that cut
+pub(crate) fn exports_transition_out(
+    clip: &TimelineClip,
+    next: Option<&&TimelineClip>,
+    rate: f64,
+) -> bool {
+    clip.transition_out.is_some()
+        && next.is_none_or(|n| to_frames(n.timeline_in, rate) > to_frames(clip.timeline_out, rate))
+}
+
+/// Set the edit's duration to cover every clip and marker
+pub(crate) fn finish_import(edit: &mut AssembledEdit) {
+    edit.sort_clips();
+    let clips_end = edit
+        .video_clips
+        .iter()
+        .map(|c| c.timeline_out)
+        .chain(edit.audio_clips.iter().map(|c| c.timeline_out))
+        .chain(edit.markers.iter().map(|m| m.time))
+        .fold(0.0, f64::max);
+    edit.duration = edit.duration.max(clips_end);
+}
+
+/// Named marker colours shared by OTIO and EDL locators, with the hex value
+/// Editron uses for each
+const NAMED_COLORS: &[(&str, &str)] = &[
+    ("RED", "#D0021B"),
+    ("PINK", "#FF2D55"),
+    ("ORANGE", "#F5A623"),
+    ("YELLOW", "#FFCC00"),
+    ("GREEN", "#7ED321"),
+    ("CYAN", "#50E3C2"),
+    ("BLUE", "#4A90D9"),
+    ("PURPLE", "#BD10E0"),
+    ("MAGENTA", "#9013FE"),
+    ("BLACK", "#000000"),
+    ("WHITE", "#FFFFFF"),
+];
+
+/// Closest of `allowed` named colours to a `#RRGGBB` value
+pub(crate) fn color_name(hex: &str, allowed: &[&str]) -> &'static str {
+    let Some(target) = parse_hex(hex) else {
+        return "RED";
+    };
+    NAMED_COLORS
+        .iter()
+        .filter(|(name, _)| allowed.contains(name))
+        .min_by_key(|(_, value)| {
+            let rgb = parse_hex(value).unwrap_or_default();
+            (0..3)
+                .map(|i| (rgb[i] as i32 - target[i] as i32).pow(2))
+                .sum::<i32>()
+        })
+        .map(|(name, _)| *name)
+        .unwrap_or("RED")
+}
+
+pub(crate) fn color_hex(name: &str) -> String {
+    NAMED_COLORS
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, hex)| hex.to_string())
+        .unwrap_or_else(|| "#D0021B".to_string())
+}
+
+fn parse_hex(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// `file://` URL for a media path
+pub(crate) fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded = path
+        .split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect::<Vec<_>>()
+        .join("/");
+    if encoded.starts_with('/') {
+        format!("file://{}", encoded)
+    } else {
+        format!("file:///{}", encoded)
+    }
+}
+
+/// Media path from a `file://` URL (or a plain path)
+pub(crate) fn path_from_url(url: &str) -> PathBuf {
+    let Some(rest) = url.strip_prefix("file://") else {
+        return PathBuf::from(url);
+    };
+    // Drop an optional host (`file://localhost/...`)
+    let rest = match rest.find('/') {
+        Some(0) | None => rest,
+        Some(i) => &rest[i..],
+    };
+    let decoded = urlencoding::decode(rest)
+        .map(|s| s.into_owned())
+        .unwrap_or_else(|_| rest.to_string());
+    // `/C:/media/clip.mov` on Windows
+    let bytes = decoded.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        return PathBuf::from(&decoded[1..]);
+    }
+    PathBuf::from(decoded)
+}
+
+pub(crate) fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
@@ -0,0 +1,479 @@
+//! OpenTimelineIO JSON
+//!
+//! Writes `Timeline.1` documents with one `Track.1` per Editron video and audio
+//! track. Editron-only properties (clip opacity/scale/position, audio volume
+//! and fades, marker types, full transition parameters) live under the
+//! `editron` metadata key so OTIO round-trips are lossless; files from other
+//! tools import with sensible defaults.
+
+use serde_json::{Value, json};
+
+use super::{
+    super::{
+        EditronError, EditronResult,
+        edit_assembly::{
+            AssembledEdit, AudioClip, EditMarker, MarkerType, TimelineClip, TransitionSpec,
+        },
+        transitions::{EasingCurve, Transition},
+    },
+    audio_tracks, color_hex, color_name, exchange_rate, exports_transition_out, file_name,
+    file_url, finish_import, path_from_url, to_frames, video_tracks,
+};
+
+const METADATA_KEY: &str = "editron";
+
+const OTIO_COLORS: &[&str] = &[
+    "RED", "PINK", "ORANGE", "YELLOW", "GREEN", "CYAN", "BLUE", "PURPLE", "MAGENTA", "BLACK",
+    "WHITE",
+];
+
+pub fn export(edit: &AssembledEdit) -> EditronResult<String> {
+    let rate = exchange_rate(edit.frame_rate);
+    let mut tracks = Vec::new();
+
+    for (number, clips) in video_tracks(edit) {
+        let mut children = Vec::new();
+        let mut cursor = 0;
+        for (i, clip) in clips.iter().enumerate() {
+            let start = to_frames(clip.timeline_in, rate);
+            let end = to_frames(clip.timeline_out, rate);
+            push_gap(
+                &mut children,
+                &mut cursor,
+                start,
+                rate,
+                &format!("V{}", number),
+            )?;
+
+            if let Some(spec) = &clip.transition_in {
+                children.push(transition_json(spec, edit.frame_rate, rate, true));
+            }
+            children.push(video_clip_json(clip, start, end, rate));
+            if exports_transition_out(clip, clips.get(i + 1), rate)
+                && let Some(spec) = &clip.transition_out
+            {
+                children.push(transition_json(spec, edit.frame_rate, rate, false));
+            }
+            cursor = end;
+        }
+        tracks.push(track_json(number, "Video", children));
+    }
+
+    for (number, clips) in audio_tracks(edit) {
+        let mut children = Vec::new();
+        let mut cursor = 0;
+        for clip in clips {
+            let start = to_frames(clip.timeline_in, rate);
+            let end = to_frames(clip.timeline_out, rate);
+            push_gap(
+                &mut children,
+                &mut cursor,
+                start,
+                rate,
+                &format!("A{}", number),
+            )?;
+            children.push(audio_clip_json(clip, start, end, rate));
+            cursor = end;
+        }
+        tracks.push(track_json(number, "Audio", children));
+    }
+
+    let markers: Vec<Value> = edit
+        .markers
+        .iter()
+        .map(|marker| marker_json(marker, rate))
+        .collect();
+
+    let timeline = json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": edit.name,
+        "global_start_time": null,
+        "metadata": {
+            METADATA_KEY: {
+                "width": edit.width,
+                "height": edit.height,
+                "frame_rate": rate,
+            }
+        },
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "name": "tracks",
+            "source_range": null,
+            "effects": [],
+            "markers": markers,
+            "metadata": {},
+            "children": tracks,
+        }
+    });
+
+    serde_json::to_string_pretty(&timeline)
+        .map(|s| s + "\n")
+        .map_err(|e| EditronError::InvalidFormat(format!("OTIO serialization failed: {}", e)))
+}
+
+pub fn import(content: &str) -> EditronResult<AssembledEdit> {
+    let root: Value = serde_json::from_str(content)
+        .map_err(|e| EditronError::InvalidFormat(format!("Invalid OTIO JSON: {}", e)))?;
+    if !schema(&root).starts_with("Timeline.") {
+        return Err(EditronError::InvalidFormat(format!(
+            "Expected an OTIO Timeline, found {}",
+            schema(&root)
+        )));
+    }
+
+    let stack = &root["tracks"];
+    let extras = &root["metadata"][METADATA_KEY];
+    let frame_rate = extras["frame_rate"]
+        .as_f64()
+        .or_else(|| first_rate(stack))
+        .unwrap_or(24.0) as f32;
+    let mut edit = AssembledEdit::new(
+        root["name"].as_str().unwrap_or("Imported Timeline"),
+        0.0,
+        extras["width"].as_u64().unwrap_or(1920) as u32,
+        extras["height"].as_u64().unwrap_or(1080) as u32,
+        frame_rate,
+    );
+
+    let (mut video_number, mut audio_number) = (0, 0);
+    for track in children(stack).filter(|t| schema(t).starts_with("Track.")) {
+        let is_audio = track["kind"].as_str() == Some("Audio");
+        let fallback = if is_audio {
+            audio_number += 1;
+            audio_number
+        } else {
+            video_number += 1;
+            video_number
+        };
+        let number = track["metadata"][METADATA_KEY]["track"]
+            .as_u64()
+            .map(|n| n as u32)
+            .unwrap_or(fallback);
+
+        if is_audio {
+            import_audio_track(&mut edit, track, number);
+        } else {
+            import_video_track(&mut edit, track, number);
+        }
+    }
+
+    for marker in stack["markers"].as_array().into_iter().flatten() {
+        let extra = &marker["metadata"][METADATA_KEY];
+        edit.add_marker(EditMarker {
+            time: time_seconds(&marker["marked_range"]["start_time"]),
+            name: marker["name"].as_str().unwrap_or_default().to_string(),
+            color: extra["color"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| color_hex(marker["color"].as_str().unwrap_or("RED"))),
+            marker_type: serde_json::from_value(extra["marker_type"].clone())
+                .unwrap_or(MarkerType::Note),
+        });
+    }
+
+    finish_import(&mut edit);
+    Ok(edit)
+}
+
+fn import_video_track(edit: &mut AssembledEdit, track: &Value, number: u32) {
+    let items: Vec<&Value> = children(track).collect();
+    let mut cursor = 0.0;
+    let mut clips: Vec<TimelineClip> = Vec::new();
+    let mut pending_in = None;
+
+    for (i, item) in items.iter().enumerate() {
+        let kind = schema(item);
+        if kind.starts_with("Transition.") {
+            let spec = transition_spec(item, edit.frame_rate);
+            match items.get(i + 1) {
+                Some(next) if schema(next).starts_with("Clip.") => pending_in = Some(spec),
+                _ => {
+                    if let Some(prev) = clips.last_mut() {
+                        prev.transition_out = Some(spec);
+                    }
+                }
+            }
+            continue;
+        }
+
+        let duration = time_seconds(&item["source_range"]["duration"]);
+        if kind.starts_with("Clip.") {
+            let extra = &item["metadata"][METADATA_KEY];
+            let speed = time_scalar(item);
+            let source_in = time_seconds(&item["source_range"]["start_time"]);
+            let mut clip = TimelineClip::new(
+                path_from_url(media_url(item)),
+                source_in,
+                source_in + duration * speed as f64,
+                cursor,
+            )
+            .with_track(number);
+            clip.speed = speed;
+            clip.timeline_out = cursor + duration;
+            clip.transition_in = pending_in.take();
+            if let Some(opacity) = extra["opacity"].as_f64() {
+                clip.opacity = opacity as f32;
+            }
+            if let Some(scale) = extra["scale"].as_f64() {
+                clip.scale = scale as f32;
+            }
+            if let Some([x, y]) = extra["position"].as_array().map(Vec::as_slice) {
+                clip.position = (
+                    x.as_f64().unwrap_or_default() as f32,
+                    y.as_f64().unwrap_or_default() as f32,
+                );
+            }
+            clips.push(clip);
+        }
+        cursor += duration;
+    }
+
+    edit.video_clips.extend(clips);
+}
+
+fn import_audio_track(edit: &mut AssembledEdit, track: &Value, number: u32) {
+    let mut cursor = 0.0;
+    for item in children(track) {
+        let kind = schema(item);
+        if kind.starts_with("Transition.") {
+            continue;
+        }
+        let duration = time_seconds(&item["source_range"]["duration"]);
+        if kind.starts_with("Clip.") {
+            let extra = &item["metadata"][METADATA_KEY];
+            let speed = time_scalar(item) as f64;
+            let source_in = time_seconds(&item["source_range"]["start_time"]);
+            edit.add_audio_clip(AudioClip {
+                source: path_from_url(media_url(item)),
+                source_in,
+                source_out: source_in + duration * speed,
+                timeline_in: cursor,
+                timeline_out: cursor + duration,
+                track: number,
+                volume: extra["volume"].as_f64().unwrap_or(1.0) as f32,
+                fade_in: extra["fade_in"].as_f64(),
+                fade_out: extra["fade_out"].as_f64(),
+                is_music: extra["music"].as_bool().unwrap_or(false),
+            });
+        }
+        cursor += duration;
+    }
+}
+
+fn rational_time(frames: i64, rate: f64) -> Value {
+    json!({
+        "OTIO_SCHEMA": "RationalTime.1",
+        "rate": rate,
+        "value": frames as f64,
+    })
+}
+
+fn time_range(start: i64, duration: i64, rate: f64) -> Value {
+    json!({
+        "OTIO_SCHEMA": "TimeRange.1",
+        "start_time": rational_time(start, rate),
+        "duration": rational_time(duration, rate),
+    })
+}
+
+fn track_json(number: u32, kind: &str, children: Vec<Value>) -> Value {
+    json!({
+        "OTIO_SCHEMA": "Track.1",
+        "name": format!("{}{}", &kind[..1], number),
+        "kind": kind,
+        "source_range": null,
+        "effects": [],
+        "markers": [],
+        "metadata": { METADATA_KEY: { "track": number } },
+        "children": children,
+    })
+}
+
+fn push_gap(
+    children: &mut Vec<Value>,
+    cursor: &mut i64,
+    start: i64,
+    rate: f64,
+    track: &str,
+) -> EditronResult<()> {
+    if start < *cursor {
+        return Err(EditronError::InvalidFormat(format!(
+            "Overlapping clips on track {} at frame {}",
+            track, start
+        )));
+    }
+    if start > *cursor {
+        children.push(json!({
+            "OTIO_SCHEMA": "Gap.1",
+            "name": "",
+            "source_range": time_range(0, start - *cursor, rate),
+            "effects": [],
+            "markers": [],
+            "metadata": {},
+        }));
+    }
+    *cursor = start;
+    Ok(())
+}
+
+fn media_json(clip_name: &str, url: String) -> Value {
+    json!({
+        "DEFAULT_MEDIA": {
+            "OTIO_SCHEMA": "ExternalReference.1",
+            "name": clip_name,
+            "target_url": url,
+            "available_range": null,
+            "metadata": {},
+        }
+    })
+}
+
+fn speed_effects(speed: f32) -> Vec<Value> {
+    if speed == 1.0 {
+        return Vec::new();
+    }
+    vec![json!({
+        "OTIO_SCHEMA": "LinearTimeWarp.1",
+        "name": "",
+        "effect_name": "LinearTimeWarp",
+        "time_scalar": speed,
+        "metadata": {},
+    })]
+}
+
+fn video_clip_json(clip: &TimelineClip, start: i64, end: i64, rate: f64) -> Value {
+    let name = file_name(&clip.source);
+    json!({
+        "OTIO_SCHEMA": "Clip.2",
+        "name": name,
+        "source_range": time_range(to_frames(clip.source_in, rate), end - start, rate),
+        "effects": speed_effects(clip.speed),
+        "markers": [],
+        "enabled": true,
+        "media_references": media_json(&name, file_url(&clip.source)),
+        "active_media_reference_key": "DEFAULT_MEDIA",
+        "metadata": {
+            METADATA_KEY: {
+                "opacity": clip.opacity,
+                "scale": clip.scale,
+                "position": [clip.position.0, clip.position.1],
+            }
+        },
+    })
+}
+
+fn audio_clip_json(clip: &AudioClip, start: i64, end: i64, rate: f64) -> Value {
+    let name = file_name(&clip.source);
+    // Audio clips have no speed field; a retimed one shows as differing durations
+    let speed = (clip.source_out - clip.source_in) / clip.duration().max(f64::EPSILON);
+    json!({
+        "OTIO_SCHEMA": "Clip.2",
+        "name": name,
+        "source_range": time_range(to_frames(clip.source_in, rate), end - start, rate),
+        "effects": speed_effects(((speed * 1000.0).round() / 1000.0) as f32),
+        "markers": [],
+        "enabled": true,
+        "media_references": media_json(&name, file_url(&clip.source)),
+        "active_media_reference_key": "DEFAULT_MEDIA",
+        "metadata": {
+            METADATA_KEY: {
+                "volume": clip.volume,
+                "fade_in": clip.fade_in,
+                "fade_out": clip.fade_out,
+                "music": clip.is_music,
+            }
+        },
+    })
+}
+
+/// A transition at the start of the following clip (`incoming`) or the end of
+/// the preceding one
+fn transition_json(spec: &TransitionSpec, frame_rate: f32, rate: f64, incoming: bool) -> Value {
+    let transition = spec.to_transition(frame_rate);
+    let frames = transition.duration_frames() as i64;
+    let (in_offset, out_offset) = if incoming { (0, frames) } else { (frames, 0) };
+    let transition_type = match transition {
+        Transition::Dissolve { .. } => "SMPTE_Dissolve",
+        _ => "Custom_Transition",
+    };
+    json!({
+        "OTIO_SCHEMA": "Transition.1",
+        "name": spec.transition_type,
+        "transition_type": transition_type,
+        "in_offset": rational_time(in_offset, rate),
+        "out_offset": rational_time(out_offset, rate),
+        "metadata": { METADATA_KEY: { "transition": transition } },
+    })
+}
+
+fn transition_spec(item: &Value, frame_rate: f32) -> TransitionSpec {
+    let transition =
+        serde_json::from_value::<Transition>(item["metadata"][METADATA_KEY]["transition"].clone())
+            .unwrap_or_else(|_| {
+                let seconds = time_seconds(&item["in_offset"]) + time_seconds(&item["out_offset"]);
+                Transition::Dissolve {
+                    duration_frames: (seconds * frame_rate as f64).round() as u32,
+                    curve: EasingCurve::Linear,
+                }
+            });
+    TransitionSpec::from_transition(&transition, frame_rate)
+}
+
+fn marker_json(marker: &EditMarker, rate: f64) -> Value {
+    json!({
+        "OTIO_SCHEMA": "Marker.2",
+        "name": marker.name,
+        "color": color_name(&marker.color, OTIO_COLORS),
+        "comment": "",
+        "marked_range": time_range(to_frames(marker.time, rate), 0, rate),
+        "metadata": {
+            METADATA_KEY: {
+                "color": marker.color,
+                "marker_type": marker.marker_type,
+            }
+        },
+    })
+}
+
+fn schema(value: &Value) -> &str {
+    value["OTIO_SCHEMA"].as_str().unwrap_or_default()
+}
+
+fn children(value: &Value) -> impl Iterator<Item = &Value> {
+    value["children"].as_array().into_iter().flatten()
+}
+
+fn time_seconds(time: &Value) -> f64 {
+    let rate = time["rate"].as_f64().filter(|r| *r > 0.0).unwrap_or(1.0);
+    time["value"].as_f64().unwrap_or_default() / rate
+}
+
+/// The rate of the first timed item, for timelines written by other tools
+fn first_rate(stack: &Value) -> Option<f64> {
+    children(stack)
+        .flat_map(children)
+        .find_map(|item| item["source_range"]["duration"]["rate"].as_f64())
+}
+
+/// `Clip.2` keeps references in a map, `Clip.1` has a single `media_reference`
+fn media_url(clip: &Value) -> &str {
+    let key = clip["active_media_reference_key"]
+        .as_str()
+        .unwrap_or("DEFAULT_MEDIA");
+    let reference = if clip["media_references"].is_object() {
+        &clip["media_references"][key]
+    } else {
+        &clip["media_reference"]
+    };
+    reference["target_url"].as_str().unwrap_or_default()
+}
+
+fn time_scalar(clip: &Value) -> f32 {
+    clip["effects"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|e| schema(e).starts_with("LinearTimeWarp."))
+        .filter_map(|e| e["time_scalar"].as_f64())
+        .product::<f64>() as f32
+}
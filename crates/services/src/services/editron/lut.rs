@@ -0,0 +1,458 @@
+//! `.cube` LUT parsing and evaluation
+//!
+//! Reads Adobe/Resolve `.cube` files (1D and 3D) and evaluates them on the
+//! CPU, so a look can be previewed on a still before an export is queued and
+//! combined with [`ColorGradePreset`](super::color_grading::ColorGradePreset)
+//! adjustments into a single baked LUT.
+//!
+//! - 3D tables are stored red-fastest, as they appear in the file
+//! - Inputs are mapped through `DOMAIN_MIN`/`DOMAIN_MAX` and clamped
+//! - 3D lookups use trilinear or tetrahedral interpolation; 1D is linear
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::{EditronError, EditronResult};
+
+/// Largest sizes accepted by Resolve and Premiere
+const MAX_1D_SIZE: usize = 65536;
+const MAX_3D_SIZE: usize = 256;
+
+/// Interpolation between 3D lattice points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LutInterpolation {
+    Trilinear,
+    /// Splits each cube into six tetrahedra; keeps the grey axis neutral
+    #[default]
+    Tetrahedral,
+}
+
+impl LutInterpolation {
+    /// Name of the matching `lut3d` filter `interp` option
+    pub fn ffmpeg_name(&self) -> &'static str {
+        match self {
+            LutInterpolation::Trilinear => "trilinear",
+            LutInterpolation::Tetrahedral => "tetrahedral",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CubeDimension {
+    OneD,
+    ThreeD,
+}
+
+/// A parsed `.cube` LUT
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CubeLut {
+    pub title: Option<String>,
+    pub dimension: CubeDimension,
+    /// Entries per axis
+    pub size: usize,
+    pub domain_min: [f32; 3],
+    pub domain_max: [f32; 3],
+    /// `size` entries for 1D, `size³` (red fastest) for 3D
+    pub table: Vec<[f32; 3]>,
+}
+
+impl CubeLut {
+    /// Identity 3D LUT with `size` points per axis
+    pub fn identity(size: usize) -> Self {
+        Self::from_fn(size, |rgb| rgb)
+    }
+
+    /// 3D LUT over the unit cube, sampling `f` at each lattice point
+    pub fn from_fn(size: usize, mut f: impl FnMut([f32; 3]) -> [f32; 3]) -> Self {
+        let size = size.clamp(2, MAX_3D_SIZE);
+        let step = 1.0 / (size - 1) as f32;
+        let mut table = Vec::with_capacity(size * size * size);
+        for b in 0..size {
+            for g in 0..size {
+                for r in 0..size {
+                    table.push(f([r as f32 * step, g as f32 * step, b as f32 * step]));
+                }
+            }
+        }
+        Self {
+            title: None,
+            dimension: CubeDimension::ThreeD,
+            size,
+            domain_min: [0.0; 3],
+            domain_max: [1.0; 3],
+            table,
+        }
+    }
+
+    pub async fn load(path: &Path) -> EditronResult<Self> {
+        if !path.exists() {
+            return Err(EditronError::FileNotFound(path.to_path_buf()));
+        }
+        let text = tokio::fs::read_to_string(path).await?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> EditronResult<Self> {
+        let invalid = |line: usize, msg: &str| EditronError::InvalidFormat(format!("Cube LUT line {}: {}", line, msg));
+
+        let mut title = None;
+        let mut size_1d = None;
+        let mut size_3d = None;
+        let mut domain_min = [0.0f32; 3];
+        let mut domain_max = [1.0f32; 3];
+        let mut table = Vec::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+
+            if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                if !table.is_empty() {
+                    return Err(invalid(line_no, "keyword after table data"));
+                }
+                match keyword {
+                    "TITLE" => title = Some(rest.trim_matches('"').to_string()),
+                    "LUT_1D_SIZE" => size_1d = Some(parse_size(rest, MAX_1D_SIZE).ok_or_else(|| invalid(line_no, "bad LUT_1D_SIZE"))?),
+                    "LUT_3D_SIZE" => size_3d = Some(parse_size(rest, MAX_3D_SIZE).ok_or_else(|| invalid(line_no, "bad LUT_3D_SIZE"))?),
+                    "DOMAIN_MIN" => domain_min = parse_triplet(rest).ok_or_else(|| invalid(line_no, "bad DOMAIN_MIN"))?,
+                    "DOMAIN_MAX" => domain_max = parse_triplet(rest).ok_or_else(|| invalid(line_no, "bad DOMAIN_MAX"))?,
+                    // Resolve's single-range form of the domain keywords
+                    "LUT_1D_INPUT_RANGE" | "LUT_3D_INPUT_RANGE" => {
+                        let values: Vec<f32> = rest.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+                        let [min, max] = values[..] else {
+                            return Err(invalid(line_no, "bad input range"));
+                        };
+                        domain_min = [min; 3];
+                        domain_max = [max; 3];
+                    }
+                    // Unknown keywords are vendor extensions and safe to skip
+                    _ => {}
+                }
+                continue;
+            }
+
+            table.push(parse_triplet(line).ok_or_else(|| invalid(line_no, "expected three numbers"))?);
+        }
+
+        let (dimension, size, expected) = match (size_1d, size_3d) {
+            (Some(n), None) => (CubeDimension::OneD, n, n),
+            (None, Some(n)) => (CubeDimension::ThreeD, n, n * n * n),
+            (Some(_), Some(_)) => return Err(EditronError::InvalidFormat("Cube LUT declares both 1D and 3D sizes".to_string())),
+            (None, None) => return Err(EditronError::InvalidFormat("Cube LUT has no LUT_1D_SIZE or LUT_3D_SIZE".to_string())),
+        };
+        if table.len() != expected {
+            return Err(EditronError::InvalidFormat(format!(
+                "Cube LUT has {} entries, expected {}",
+                table.len(),
+                expected
+            )));
+        }
+        if (0..3).any(|c| domain_min[c] >= domain_max[c]) {
+            return Err(EditronError::InvalidFormat("Cube LUT domain is empty".to_string()));
+        }
+
+        Ok(Self { title, dimension, size, domain_min, domain_max, table })
+    }
+
+    /// Serialize in `.cube` format
+    pub fn to_cube_string(&self) -> String {
+        let mut out = String::new();
+        if let Some(title) = &self.title {
+            let _ = writeln!(out, "TITLE \"{}\"", title.replace('"', "'"));
+        }
+        let keyword = match self.dimension {
+            CubeDimension::OneD => "LUT_1D_SIZE",
+            CubeDimension::ThreeD => "LUT_3D_SIZE",
+        };
+        let _ = writeln!(out, "{} {}", keyword, self.size);
+        let [r, g, b] = self.domain_min;
+        let _ = writeln!(out, "DOMAIN_MIN {} {} {}", r, g, b);
+        let [r, g, b] = self.domain_max;
+        let _ = writeln!(out, "DOMAIN_MAX {} {} {}", r, g, b);
+        for [r, g, b] in &self.table {
+            let _ = writeln!(out, "{:.6} {:.6} {:.6}", r, g, b);
+        }
+        out
+    }
+
+    pub async fn write(&self, path: &Path) -> EditronResult<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, self.to_cube_string()).await?;
+        Ok(())
+    }
+
+    /// Look up one RGB value
+    pub fn apply(&self, rgb: [f32; 3], interpolation: LutInterpolation) -> [f32; 3] {
+        let mut pos = [0.0f32; 3];
+        for c in 0..3 {
+            let t = (rgb[c] - self.domain_min[c]) / (self.domain_max[c] - self.domain_min[c]);
+            pos[c] = t.clamp(0.0, 1.0) * (self.size - 1) as f32;
+        }
+        match self.dimension {
+            CubeDimension::OneD => self.apply_1d(pos),
+            CubeDimension::ThreeD => match interpolation {
+                LutInterpolation::Trilinear => self.trilinear(pos),
+                LutInterpolation::Tetrahedral => self.tetrahedral(pos),
+            },
+        }
+    }
+
+    fn apply_1d(&self, pos: [f32; 3]) -> [f32; 3] {
+        let mut out = [0.0f32; 3];
+        for c in 0..3 {
+            let (i, f) = split(pos[c], self.size);
+            out[c] = lerp(self.table[i][c], self.table[i + 1][c], f);
+        }
+        out
+    }
+
+    fn at(&self, r: usize, g: usize, b: usize) -> [f32; 3] {
+        self.table[(b * self.size + g) * self.size + r]
+    }
+
+    fn trilinear(&self, pos: [f32; 3]) -> [f32; 3] {
+        let (r, fr) = split(pos[0], self.size);
+        let (g, fg) = split(pos[1], self.size);
+        let (b, fb) = split(pos[2], self.size);
+        let mut out = [0.0f32; 3];
+        for (c, value) in out.iter_mut().enumerate() {
+            let c00 = lerp(self.at(r, g, b)[c], self.at(r + 1, g, b)[c], fr);
+            let c10 = lerp(self.at(r, g + 1, b)[c], self.at(r + 1, g + 1, b)[c], fr);
+            let c01 = lerp(self.at(r, g, b + 1)[c], self.at(r + 1, g, b + 1)[c], fr);
+            let c11 = lerp(self.at(r, g + 1, b + 1)[c], self.at(r + 1, g + 1, b + 1)[c], fr);
+            *value = lerp(lerp(c00, c10, fg), lerp(c01, c11, fg), fb);
+        }
+        out
+    }
+
+    fn tetrahedral(&self, pos: [f32; 3]) -> [f32; 3] {
+        let (r, fr) = split(pos[0], self.size);
+        let (g, fg) = split(pos[1], self.size);
+        let (b, fb) = split(pos[2], self.size);
+        let c000 = self.at(r, g, b);
+        let c111 = self.at(r + 1, g + 1, b + 1);
+
+        // Walk from c000 to c111 along the edges in order of the largest fraction
+        let (w, v1, v2) = if fr > fg {
+            if fg > fb {
+                ([fr, fg, fb], self.at(r + 1, g, b), self.at(r + 1, g + 1, b))
+            } else if fr > fb {
+                ([fr, fb, fg], self.at(r + 1, g, b), self.at(r + 1, g, b + 1))
+            } else {
+                ([fb, fr, fg], self.at(r, g, b + 1), self.at(r + 1, g, b + 1))
+            }
+        } else if fb > fg {
+            ([fb, fg, fr], self.at(r, g, b + 1), self.at(r, g + 1, b + 1))
+        } else if fb > fr {
+            ([fg, fb, fr], self.at(r, g + 1, b), self.at(r, g + 1, b + 1))
+        } else {
+            ([fg, fr, fb], self.at(r, g + 1, b), self.at(r + 1, g + 1, b))
+        };
+
+        let mut out = [0.0f32; 3];
+        for (c, value) in out.iter_mut().enumerate() {
+            *value = (1.0 - w[0]) * c000[c] + (w[0] - w[1]) * v1[c] + (w[1] - w[2]) * v2[c] + w[2] * c111[c];
+        }
+        out
+    }
+}
+
+/// Lattice cell index and fraction for a position in `0..=size-1`
+fn split(pos: f32, size: usize) -> (usize, f32) {
+    let i = (pos.floor() as usize).min(size - 2);
+    (i, pos - i as f32)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+fn parse_size(value: &str, max: usize) -> Option<usize> {
+    value.parse::<usize>().ok().filter(|n| (2..=max).contains(n))
+}
+
+fn parse_triplet(value: &str) -> Option<[f32; 3]> {
+    let mut parts = value.split_whitespace().map(|v| v.parse::<f32>().ok().filter(|f| f.is_finite()));
+    let triplet = [parts.next()??, parts.next()??, parts.next()??];
+    parts.next().is_none().then_some(triplet)
+}
+
+/// RGB still, as read from a binary PPM (P6) written by ffmpeg's `ppm` encoder.
+/// Samples are normalised to 0.0..=1.0 so 8- and 16-bit frames are handled alike.
+#[derive(Debug, Clone)]
+pub struct RgbFrame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[f32; 3]>,
+}
+
+impl RgbFrame {
+    pub fn from_ppm(data: &[u8]) -> EditronResult<Self> {
+        let mut pos = 0;
+        let mut fields = Vec::with_capacity(4);
+        while fields.len() < 4 {
+            while pos < data.len() && data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if pos < data.len() && data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(EditronError::InvalidFormat("Truncated PPM header".to_string()));
+            }
+            fields.push(String::from_utf8_lossy(&data[start..pos]).to_string());
+        }
+        pos += 1;
+
+        if fields[0] != "P6" {
+            return Err(EditronError::InvalidFormat(format!("Expected PPM (P6), got {}", fields[0])));
+        }
+        let parse = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| EditronError::InvalidFormat(format!("Bad PPM header field: {}", s)))
+        };
+        let (width, height, max_value) = (parse(&fields[1])?, parse(&fields[2])?, parse(&fields[3])?);
+        if max_value == 0 || max_value > 65535 {
+            return Err(EditronError::InvalidFormat(format!("Bad PPM max value: {}", max_value)));
+        }
+        let bytes_per_sample = if max_value > 255 { 2 } else { 1 };
+        let raster = data
+            .get(pos..pos + width * height * 3 * bytes_per_sample)
+            .ok_or_else(|| EditronError::InvalidFormat("Truncated PPM raster".to_string()))?;
+
+        let scale = 1.0 / max_value as f32;
+        let sample = |i: usize| {
+            let value = if bytes_per_sample == 2 {
+                u16::from_be_bytes([raster[i * 2], raster[i * 2 + 1]]) as f32
+            } else {
+                raster[i] as f32
+            };
+            value * scale
+        };
+        let pixels = (0..width * height)
+            .map(|p| [sample(p * 3), sample(p * 3 + 1), sample(p * 3 + 2)])
+            .collect();
+
+        Ok(Self { width, height, pixels })
+    }
+
+    /// 8-bit binary PPM
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut out = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        out.reserve(self.pixels.len() * 3);
+        for pixel in &self.pixels {
+            out.extend(pixel.iter().map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8));
+        }
+        out
+    }
+
+    pub fn map(&self, lut: &CubeLut, interpolation: LutInterpolation) -> Self {
+        Self {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|&p| lut.apply(p, interpolation)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        for c in 0..3 {
+            assert!((a[c] - b[c]).abs() < 1e-4, "{:?} != {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_parse_3d_cube_and_round_trip() {
+        let text = "# comment\nTITLE \"Warm\"\nLUT_3D_SIZE 2\n\n\
+            0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+        let lut = CubeLut::parse(text).unwrap();
+        assert_eq!(lut.title.as_deref(), Some("Warm"));
+        assert_eq!(lut.dimension, CubeDimension::ThreeD);
+        assert_eq!(lut.size, 2);
+        assert_eq!(lut.table, CubeLut::identity(2).table);
+
+        let reparsed = CubeLut::parse(&lut.to_cube_string()).unwrap();
+        assert_eq!(reparsed.table, lut.table);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_files() {
+        assert!(CubeLut::parse("LUT_3D_SIZE 2\n0 0 0\n").is_err());
+        assert!(CubeLut::parse("0 0 0\n1 1 1\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\nLUT_3D_SIZE 2\n0 0 0\n1 1 1\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 1\n0 0 0\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\nDOMAIN_MIN 1 0 0\n0 0 0\n1 1 1\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 nan\n").is_err());
+        assert!(CubeLut::parse("LUT_1D_SIZE 2\n0 0 0\n1 1 1\nTITLE \"late\"\n").is_err());
+    }
+
+    #[test]
+    fn test_1d_lut_with_domain() {
+        let lut = CubeLut::parse("LUT_1D_SIZE 3\nLUT_1D_INPUT_RANGE 0 2\n0 0 0\n0.25 0.5 1\n1 1 1\n").unwrap();
+        assert_close(lut.apply([1.0, 1.0, 1.0], LutInterpolation::Trilinear), [0.25, 0.5, 1.0]);
+        assert_close(lut.apply([0.5, 0.5, 0.5], LutInterpolation::Trilinear), [0.125, 0.25, 0.5]);
+        // Inputs outside the domain clamp to the ends of the table
+        assert_close(lut.apply([4.0, -1.0, 2.0], LutInterpolation::Trilinear), [1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_interpolation_reproduces_linear_transforms() {
+        // Any affine map is exact under both schemes
+        let matrix = |[r, g, b]: [f32; 3]| [0.8 * r + 0.1 * g + 0.05, 0.2 * g + 0.7 * b, 0.3 * r + 0.6 * b];
+        let lut = CubeLut::from_fn(5, matrix);
+        for rgb in [[0.13, 0.77, 0.42], [0.9, 0.1, 0.5], [0.33, 0.33, 0.33], [1.0, 0.0, 0.61]] {
+            assert_close(lut.apply(rgb, LutInterpolation::Trilinear), matrix(rgb));
+            assert_close(lut.apply(rgb, LutInterpolation::Tetrahedral), matrix(rgb));
+        }
+    }
+
+    #[test]
+    fn test_tetrahedral_keeps_greys_neutral() {
+        // Only the black and white corners are used along the grey axis
+        let lut = CubeLut::from_fn(2, |[r, g, b]| [r * g, g * b, b * r]);
+        let grey = lut.apply([0.5, 0.5, 0.5], LutInterpolation::Tetrahedral);
+        assert_close(grey, [0.5, 0.5, 0.5]);
+        let trilinear = lut.apply([0.5, 0.5, 0.5], LutInterpolation::Trilinear);
+        assert!((trilinear[0] - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_ppm_round_trip() {
+        let mut data = b"P6\n# ffmpeg\n2 1\n255\n".to_vec();
+        data.extend([255, 0, 0, 0, 128, 255]);
+        let frame = RgbFrame::from_ppm(&data).unwrap();
+        assert_eq!((frame.width, frame.height), (2, 1));
+        assert_close(frame.pixels[0], [1.0, 0.0, 0.0]);
+        let mut expected = b"P6\n2 1\n255\n".to_vec();
+        expected.extend([255, 0, 0, 0, 128, 255]);
+        assert_eq!(frame.map(&CubeLut::identity(2), LutInterpolation::Tetrahedral).to_ppm(), expected);
+
+        let mut deep = b"P6 1 1 65535 ".to_vec();
+        deep.extend([0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+        let frame = RgbFrame::from_ppm(&deep).unwrap();
+        assert_close(frame.pixels[0], [1.0, 32768.0 / 65535.0, 0.0]);
+        assert!(RgbFrame::from_ppm(b"P5\n1 1\n255\n\0").is_err());
+    }
+}
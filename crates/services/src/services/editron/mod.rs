@@ -37,6 +37,11 @@ pub mod edit_assembly;
 pub mod premiere_xml;
 pub mod premiere_prproj;
 pub mod artlist;
+pub mod captions;
+pub mod interchange;
+pub mod distributed_render;
+pub mod reframe;
+pub mod lut;
 // visual_qc lives as a standalone module at services::services::visual_qc
 
 use std::path::{Path, PathBuf};
@@ -46,26 +51,42 @@ use thiserror::Error;
 use tokio::sync::RwLock;
 use uuid::Uuid;
 
+use super::apn_bridge::{ResourceAccounting, TaskDistributor};
+
 pub use ffmpeg::FFmpegClient;
 pub use premiere::PremiereProBridge;
 pub use encoder::MediaEncoderBridge;
-pub use color_grading::{ColorGradingEngine, ColorGradePreset, LUT, ColorWheels, ColorCurves};
+pub use color_grading::{ColorGradingEngine, ColorGradePreset, GradePreview, LUT, ColorWheels, ColorCurves, BAKED_LUT_SIZE};
+pub use lut::{CubeLut, CubeDimension, LutInterpolation, RgbFrame};
 pub use transitions::{TransitionEngine, Transition, TransitionPreset, TransitionCategory, EasingCurve};
-pub use audio::{AudioProcessingEngine, AudioProcessingPreset, LoudnessStandard, CompressionSettings};
+pub use audio::{
+    AudioProcessingEngine, AudioProcessingPreset, LoudnessStandard, LoudnessMeasurement,
+    LoudnessReport, CompressionSettings,
+};
 pub use proxy::{ProxyWorkflowManager, ProxyPreset, ProxySettings, ProxyFile};
 pub use scene_detection::{SceneDetectionEngine, Scene, SceneDetectionResult, DetectionMethod};
 pub use music::{MusicLibrary, MusicTrack, MusicSearchCriteria, MusicMood, MusicGenre, MusicRecommendation, AudioAnalysis, MusicPlatform};
 pub use artlist::{ArtlistClient, ArtlistConfig, ArtlistError};
+pub use captions::{
+    CaptionTrack, CaptionCue, CaptionWord, CaptionRules, CaptionStyle, CaptionDelivery,
+    CaptionPlacement, CaptionFiles, CaptionedRender, Transcriber,
+};
 pub use edit_assembly::{
     EditAssemblyEngine, AssembledEdit, FootageClip, MusicAnalysis as EditMusicAnalysis,
     MusicSection, SectionType, PacingStyle, TimelineClip, AudioClip, AssemblyConfig,
     TransitionStyle, EditMarker, MarkerType,
 };
+pub use interchange::{InterchangeFormat, export_edit, import_edit};
+pub use distributed_render::{
+    DistributedRenderer, DistributedRenderSettings, DistributedRender, HttpSegmentTransport,
+    RenderSegment, SegmentHost, SegmentJob, SegmentDelivery, SegmentOutcome, SegmentTransport,
+};
+pub use reframe::{ReframeEngine, ReframeSettings, ReframeTrack, ReframeSource, CropKeyframe};
 pub use premiere_xml::PremiereXmlExporter;
 pub use premiere_prproj::{PrprojRecutEngine, PrprojClipEntry, PrprojRecutResult};
 pub use super::visual_qc::{
     VisualQcEngine, VisualQcConfig, VisualQcResult, ClipQcResult, AnalyzedFrame,
-    SubjectRegion, CropRegion,
+    SubjectRegion, CropRegion, QcFootageClip, VisualQcError,
 };
 
 #[derive(Debug, Error)]
@@ -90,6 +111,9 @@ pub enum EditronError {
 
     #[error("Process error: {0}")]
     Process(String),
+
+    #[error("Visual QC error: {0}")]
+    VisualQc(#[from] VisualQcError),
 }
 
 pub type EditronResult<T> = Result<T, EditronError>;
@@ -167,6 +191,9 @@ pub struct ExportPreset {
     pub bitrate: Option<String>,
     pub audio_bitrate: Option<String>,
     pub quality: Option<u8>,
+    /// How captions are delivered with renders using this preset
+    #[serde(default)]
+    pub captions: CaptionDelivery,
 }
 
 impl ExportPreset {
@@ -182,6 +209,7 @@ impl ExportPreset {
             bitrate: Some("8M".to_string()),
             audio_bitrate: Some("192k".to_string()),
             quality: None,
+            captions: CaptionDelivery::SoftTrack,
         }
     }
 
@@ -197,6 +225,7 @@ impl ExportPreset {
             bitrate: Some("35M".to_string()),
             audio_bitrate: Some("256k".to_string()),
             quality: None,
+            captions: CaptionDelivery::SoftTrack,
         }
     }
 
@@ -212,6 +241,7 @@ impl ExportPreset {
             bitrate: Some("6M".to_string()),
             audio_bitrate: Some("128k".to_string()),
             quality: None,
+            captions: CaptionDelivery::BurnIn,
         }
     }
 
@@ -227,6 +257,7 @@ impl ExportPreset {
             bitrate: Some("6M".to_string()),
             audio_bitrate: Some("128k".to_string()),
             quality: None,
+            captions: CaptionDelivery::BurnIn,
         }
     }
 
@@ -242,6 +273,27 @@ impl ExportPreset {
             bitrate: None,
             audio_bitrate: None,
             quality: None,
+            captions: CaptionDelivery::Sidecar,
+        }
+    }
+
+    /// Output aspect ratio (width / height), if the preset fixes the frame size
+    pub fn aspect_ratio(&self) -> Option<f64> {
+        match (self.width, self.height) {
+            (Some(w), Some(h)) if h > 0 => Some(w as f64 / h as f64),
+            _ => None,
+        }
+    }
+
+    /// Whether rendering `metadata` with this preset changes the aspect ratio,
+    /// so the picture has to be cropped rather than just scaled
+    pub fn needs_reframe(&self, metadata: &VideoMetadata) -> bool {
+        match self.aspect_ratio() {
+            Some(aspect) if metadata.height > 0 => {
+                let source = metadata.width as f64 / metadata.height as f64;
+                (source / aspect - 1.0).abs() > 0.01
+            }
+            _ => false,
         }
     }
 }
@@ -350,6 +402,19 @@ pub struct EditronService {
     artlist_client: Arc<RwLock<Option<ArtlistClient>>>,
     // Visual QC engine (Spectra)
     visual_qc: Arc<VisualQcEngine>,
+    // Mesh rendering, when connected to APN
+    distributed: Option<RenderMesh>,
+    // Subject tracking for aspect-changing exports
+    reframe: Arc<ReframeEngine>,
+}
+
+/// Mesh rendering: the renderer, and the host that serves export sources to peers
+#[derive(Clone)]
+struct RenderMesh {
+    renderer: Arc<DistributedRenderer>,
+    host: Arc<SegmentHost>,
+    /// This node's address as peers reach it
+    public_url: String,
 }
 
 impl EditronService {
@@ -410,9 +475,33 @@ impl EditronService {
             music_library,
             artlist_client: Arc::new(RwLock::new(None)),
             visual_qc,
+            distributed: None,
+            reframe: Arc::new(ReframeEngine::default()),
         })
     }
 
+    /// Render long exports across APN peers advertising the `ffmpeg` capability.
+    /// Peers download sources from `host` through the server at `public_url`.
+    pub fn with_render_mesh(
+        mut self,
+        distributor: Arc<TaskDistributor>,
+        accounting: Arc<ResourceAccounting>,
+        host: Arc<SegmentHost>,
+        public_url: &str,
+        settings: DistributedRenderSettings,
+    ) -> Self {
+        let transport = Arc::new(HttpSegmentTransport::new(std::time::Duration::from_secs(30 * 60)));
+        self.distributed = Some(RenderMesh {
+            renderer: Arc::new(
+                DistributedRenderer::new(self.ffmpeg.clone(), distributor, accounting, transport)
+                    .with_settings(settings),
+            ),
+            host,
+            public_url: public_url.trim_end_matches('/').to_string(),
+        });
+        self
+    }
+
     /// Probe video file for metadata
     pub async fn probe<P: AsRef<Path>>(&self, path: P) -> EditronResult<VideoMetadata> {
         self.ffmpeg.probe(path).await
@@ -454,14 +543,49 @@ impl EditronService {
         self.ffmpeg.concat(inputs, output, preset).await
     }
 
-    /// Export with preset
+    /// Export with preset, split across mesh peers when a render mesh is configured
     pub async fn export<P: AsRef<Path>>(
         &self,
         input: P,
         output: P,
         preset: ExportPreset,
     ) -> EditronResult<PathBuf> {
-        self.ffmpeg.export(input, output, preset).await
+        if self.distributed.is_none() {
+            return self.ffmpeg.export(input, output, preset).await;
+        }
+        let render = self.export_distributed(input.as_ref(), output.as_ref(), preset).await?;
+        Ok(render.output)
+    }
+
+    /// Like [`Self::export`], reporting where each segment was rendered. Peers
+    /// can download the source from this node only while the render runs.
+    pub async fn export_distributed(
+        &self,
+        input: &Path,
+        output: &Path,
+        preset: ExportPreset,
+    ) -> EditronResult<DistributedRender> {
+        match &self.distributed {
+            Some(mesh) => {
+                let source = mesh.host.share_source(input).await;
+                let source_url = format!("{}/api/editron/sources/{}", mesh.public_url, source);
+                let render = mesh
+                    .renderer
+                    .render(input, Some(&source_url), output, &preset, &self.work_dir)
+                    .await;
+                mesh.host.unshare_source(source).await;
+                render
+            }
+            None => {
+                let renderer = DistributedRenderer::new(
+                    self.ffmpeg.clone(),
+                    Arc::new(TaskDistributor::new()),
+                    Arc::new(ResourceAccounting::new()),
+                    Arc::new(HttpSegmentTransport::new(std::time::Duration::from_secs(60))),
+                );
+                renderer.render(input, None, output, &preset, &self.work_dir).await
+            }
+        }
     }
 
     /// Open project in Premiere Pro
@@ -547,6 +671,54 @@ impl EditronService {
         self.color_grading.list_luts().await
     }
 
+    /// Render before/after stills of `preset` on the frame at `time_seconds`.
+    /// The after still goes through the same baked LUT an export would use.
+    pub async fn preview_color_grade(
+        &self,
+        input: &Path,
+        preset: &ColorGradePreset,
+        time_seconds: f64,
+    ) -> EditronResult<GradePreview> {
+        let dir = self.work_dir.join("color_previews").join(Uuid::new_v4().to_string());
+        tokio::fs::create_dir_all(&dir).await?;
+
+        let source = self.ffmpeg.extract_frame(input, dir.join("source.ppm").as_path(), time_seconds).await?;
+        let frame = RgbFrame::from_ppm(&tokio::fs::read(&source).await?)?;
+
+        let baked = self.color_grading.bake(preset, BAKED_LUT_SIZE).await?;
+        let baked_lut = dir.join("grade.cube");
+        baked.write(&baked_lut).await?;
+
+        let graded = dir.join("graded.ppm");
+        tokio::fs::write(&graded, frame.map(&baked, LutInterpolation::Tetrahedral).to_ppm()).await?;
+
+        let before = self.ffmpeg.extract_frame(source.as_path(), dir.join("before.png").as_path(), 0.0).await?;
+        let after = self.ffmpeg.extract_frame(graded.as_path(), dir.join("after.png").as_path(), 0.0).await?;
+        let _ = tokio::fs::remove_file(&source).await;
+        let _ = tokio::fs::remove_file(&graded).await;
+
+        Ok(GradePreview { time_seconds, before, after, baked_lut })
+    }
+
+    /// Export with `preset` and its LUT baked into a single 3D LUT, so the
+    /// render matches [`preview_color_grade`](Self::preview_color_grade)
+    pub async fn export_baked_grade(
+        &self,
+        input: &Path,
+        preset: &ColorGradePreset,
+        output: &Path,
+    ) -> EditronResult<PathBuf> {
+        let baked = self.color_grading.bake(preset, BAKED_LUT_SIZE).await?;
+        let baked_lut = self.work_dir
+            .join("luts")
+            .join("baked")
+            .join(format!("{}.cube", Uuid::new_v4()));
+        baked.write(&baked_lut).await?;
+
+        let filter = self.color_grading.baked_ffmpeg_filter(preset, &baked_lut);
+        self.ffmpeg.apply_filter(input, &filter, output).await
+    }
+
     // ============ TRANSITIONS ============
 
     /// Get available transition presets
@@ -576,30 +748,120 @@ impl EditronService {
         self.audio.get_preset(name)
     }
 
-    /// Apply audio processing to video/audio
+    /// Apply audio processing to video/audio. Presets with a loudness target
+    /// are normalised in two passes and get a loudness report.
     pub async fn apply_audio_processing<P: AsRef<Path>>(
         &self,
         input: P,
         preset: &AudioProcessingPreset,
         output: P,
     ) -> EditronResult<PathBuf> {
-        let filter = self.audio.to_ffmpeg_filter(preset);
-        self.ffmpeg.apply_audio_filter(input, &filter, output).await
+        let Some(standard) = preset.loudness.clone() else {
+            let filter = self.audio.to_ffmpeg_filter(preset);
+            return self.ffmpeg.apply_audio_filter(input, &filter, output).await;
+        };
+        let chain = self.audio.processing_filters(preset);
+        self.two_pass_loudness(input.as_ref(), &chain, standard, output.as_ref())
+            .await?;
+        Ok(output.as_ref().to_path_buf())
     }
 
-    /// Normalize audio loudness
+    /// Normalize audio loudness to `standard` with a two-pass linear loudnorm,
+    /// verify the output and store the loudness report for the render
     pub async fn normalize_loudness<P: AsRef<Path>>(
         &self,
         input: P,
         standard: LoudnessStandard,
         output: P,
-    ) -> EditronResult<PathBuf> {
-        let filter = format!(
-            "loudnorm=I={}:TP={}:LRA=11",
-            standard.target_lufs(),
-            standard.true_peak_limit()
+    ) -> EditronResult<LoudnessReport> {
+        self.two_pass_loudness(input.as_ref(), &[], standard, output.as_ref())
+            .await
+    }
+
+    /// Measure integrated loudness, loudness range and true peak
+    pub async fn measure_loudness<P: AsRef<Path>>(
+        &self,
+        input: P,
+    ) -> EditronResult<LoudnessMeasurement> {
+        let filter = LoudnessStandard::EbuR128Broadcast.measurement_filter();
+        let log = self.ffmpeg.analyze_audio(input, &filter).await?;
+        LoudnessMeasurement::from_ffmpeg_output(&log)
+    }
+
+    /// Loudness report stored for a normalised render, if any
+    pub async fn loudness_report(&self, render: &Path) -> EditronResult<Option<LoudnessReport>> {
+        let path = Self::loudness_report_path(render);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = tokio::fs::read_to_string(&path).await?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| EditronError::InvalidFormat(e.to_string()))
+    }
+
+    /// Reports live next to their render, so renders that share a file name
+    /// in different directories never read each other's report
+    fn loudness_report_path(render: &Path) -> PathBuf {
+        let mut path = render.as_os_str().to_owned();
+        path.push(".loudness.json");
+        PathBuf::from(path)
+    }
+
+    /// Measure (after `chain`), normalise linearly with the measured values,
+    /// then re-measure the output against `standard`
+    async fn two_pass_loudness(
+        &self,
+        input: &Path,
+        chain: &[String],
+        standard: LoudnessStandard,
+        output: &Path,
+    ) -> EditronResult<LoudnessReport> {
+        let with_chain = |loudness: String| {
+            chain.iter().cloned().chain(std::iter::once(loudness)).collect::<Vec<_>>().join(",")
+        };
+
+        // Pass 1: measure the processed signal
+        let log = self.ffmpeg
+            .analyze_audio(input, &with_chain(standard.measurement_filter()))
+            .await?;
+        let before = LoudnessMeasurement::from_ffmpeg_output(&log)?;
+
+        // Pass 2: apply the measured gain
+        let log = self.ffmpeg
+            .normalize_audio(input, &with_chain(standard.normalization_filter(&before)), 48000, output)
+            .await?;
+        let normalization_type = audio::normalization_type(&log)
+            .unwrap_or_else(|| "unknown".to_string());
+
+        // Verify the render
+        let log = self.ffmpeg
+            .analyze_audio(output, &standard.measurement_filter())
+            .await?;
+        let after = LoudnessMeasurement::from_ffmpeg_output(&log)?;
+
+        let report = LoudnessReport::new(
+            input.to_path_buf(),
+            output.to_path_buf(),
+            standard,
+            before,
+            after,
+            normalization_type,
         );
-        self.ffmpeg.apply_audio_filter(input, &filter, output).await
+        if !report.compliant {
+            tracing::warn!(
+                "Loudness check failed for {}: {}",
+                output.display(),
+                report.violations.join("; ")
+            );
+        }
+
+        let report_path = Self::loudness_report_path(output);
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| EditronError::InvalidFormat(e.to_string()))?;
+        tokio::fs::write(&report_path, json).await?;
+
+        Ok(report)
     }
 
     // ============ PROXY WORKFLOW ============
@@ -692,10 +954,12 @@ impl EditronService {
         }
 
         // Apply combined filters
+        let video_filters = video_filters.join(",");
+        let audio_filters = audio_filters.join(",");
         self.ffmpeg.process_with_filters(
             input,
-            if video_filters.is_empty() { None } else { Some(video_filters.join(",").as_str()) },
-            if audio_filters.is_empty() { None } else { Some(audio_filters.join(",").as_str()) },
+            (!video_filters.is_empty()).then_some(video_filters.as_str()),
+            (!audio_filters.is_empty()).then_some(audio_filters.as_str()),
             output,
             export_preset,
         ).await
@@ -715,6 +979,7 @@ impl EditronService {
             proxies: vec![],
             scene_detection: None,
             scripts: vec![],
+            music_recommendations: None,
         };
 
         // Process each media file
@@ -878,7 +1143,7 @@ impl EditronService {
                 filename,
                 source_in: clip.source_in,
                 duration: clip.timeline_out - clip.timeline_in,
-                label: Some(clip.label.clone().unwrap_or_default()),
+                label: None,
             }
         }).collect();
 
@@ -901,6 +1166,37 @@ impl EditronService {
             .map_err(|e| EditronError::Io(e))
     }
 
+    /// Export an assembled edit as OTIO, CMX3600 EDL or FCPXML, chosen by the
+    /// extension of `output_path`
+    pub async fn export_timeline(
+        &self,
+        edit: &AssembledEdit,
+        output_path: &Path,
+    ) -> EditronResult<PathBuf> {
+        let format = InterchangeFormat::from_path(output_path)?;
+        let content = export_edit(edit, format)?;
+        if let Some(parent) = output_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(output_path, content).await?;
+        Ok(output_path.to_path_buf())
+    }
+
+    /// Import an OTIO, EDL or FCPXML timeline. EDLs carry no frame rate, so
+    /// `frame_rate` is used for them.
+    pub async fn import_timeline(
+        &self,
+        path: &Path,
+        frame_rate: f32,
+    ) -> EditronResult<AssembledEdit> {
+        if !path.exists() {
+            return Err(EditronError::FileNotFound(path.to_path_buf()));
+        }
+        let format = InterchangeFormat::from_path(path)?;
+        let content = tokio::fs::read_to_string(path).await?;
+        import_edit(&content, format, frame_rate)
+    }
+
     /// Create footage clips from video files with metadata
     pub async fn create_footage_clips<P: AsRef<Path>>(
         &self,
@@ -1170,6 +1466,119 @@ impl EditronService {
         Ok(all_tracks)
     }
 
+    // ============ CAPTIONS ============
+
+    /// Transcribe a clip's dialogue into a caption track
+    pub async fn caption_clip<P: AsRef<Path>>(
+        &self,
+        input: P,
+        transcriber: &dyn Transcriber,
+        rules: &CaptionRules,
+        language: &str,
+    ) -> EditronResult<CaptionTrack> {
+        let input = input.as_ref();
+        let duration = self.ffmpeg.probe(input).await?.duration_seconds;
+        let words = self.transcribe_range(input, 0.0, duration, transcriber).await?;
+        Ok(CaptionTrack::from_words(&words, rules, language))
+    }
+
+    /// Transcribe the dialogue of an assembled edit, timed to the edit's timeline
+    pub async fn caption_edit(
+        &self,
+        edit: &AssembledEdit,
+        transcriber: &dyn Transcriber,
+        rules: &CaptionRules,
+        language: &str,
+    ) -> EditronResult<CaptionTrack> {
+        let mut words = Vec::new();
+        for segment in captions::dialogue_segments(edit) {
+            let duration = segment.source_out - segment.source_in;
+            if duration <= 0.0 {
+                continue;
+            }
+            let segment_words = self
+                .transcribe_range(&segment.source, segment.source_in, duration, transcriber)
+                .await?;
+            words.extend(segment_words.into_iter().map(|word| CaptionWord {
+                start_ms: segment.to_timeline_ms(word.start_ms),
+                end_ms: segment.to_timeline_ms(word.end_ms),
+                text: word.text,
+            }));
+        }
+        words.sort_by_key(|w| w.start_ms);
+        Ok(CaptionTrack::from_words(&words, rules, language))
+    }
+
+    /// Extract and transcribe `start..start + duration` seconds of `input`, in
+    /// chunks small enough for hosted speech-to-text APIs
+    async fn transcribe_range(
+        &self,
+        input: &Path,
+        start: f64,
+        duration: f64,
+        transcriber: &dyn Transcriber,
+    ) -> EditronResult<Vec<CaptionWord>> {
+        let caption_dir = self.work_dir.join("captions");
+        tokio::fs::create_dir_all(&caption_dir).await?;
+
+        let mut words = Vec::new();
+        let mut offset = 0.0;
+        while offset < duration {
+            let length = (duration - offset).min(captions::TRANSCRIPTION_CHUNK_SECONDS);
+            let wav = caption_dir.join(format!("{}.wav", Uuid::new_v4()));
+            self.ffmpeg
+                .extract_dialogue_audio(input, wav.as_path(), Some((start + offset, length)))
+                .await?;
+            let result = transcriber.transcribe(&wav).await;
+            let _ = tokio::fs::remove_file(&wav).await;
+
+            let offset_ms = (offset * 1000.0).round() as u64;
+            words.extend(result?.into_iter().map(|mut word| {
+                word.start_ms += offset_ms;
+                word.end_ms += offset_ms;
+                word
+            }));
+            offset += length;
+        }
+        Ok(words)
+    }
+
+    /// Write SRT/WebVTT sidecars next to `output`, then deliver the captions the
+    /// way `preset.captions` asks: burned in with `style`, muxed as a soft
+    /// subtitle track, or as sidecars only
+    pub async fn apply_captions<P: AsRef<Path>>(
+        &self,
+        input: P,
+        track: &CaptionTrack,
+        preset: &ExportPreset,
+        style: &CaptionStyle,
+        output: P,
+    ) -> EditronResult<CaptionedRender> {
+        let input = input.as_ref();
+        let output = output.as_ref();
+        let stem = output
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("captions");
+        let dir = output.parent().unwrap_or_else(|| Path::new("."));
+        let sidecars = track.write_files(dir, stem).await?;
+
+        let video = match preset.captions {
+            CaptionDelivery::BurnIn => {
+                let filter = style.to_ffmpeg_filter(&sidecars.srt);
+                self.ffmpeg.apply_filter(input, &filter, output).await?
+            }
+            CaptionDelivery::SoftTrack => {
+                self.ffmpeg
+                    .mux_subtitles(input, sidecars.srt.as_path(), &track.language, output)
+                    .await?
+            }
+            CaptionDelivery::Sidecar => input.to_path_buf(),
+        };
+
+        Ok(CaptionedRender { video, sidecars })
+    }
+
     // ============ VISUAL QC (SPECTRA) ============
 
     /// Extract candidate frames from a clip for visual QC analysis
@@ -1178,16 +1587,16 @@ impl EditronService {
         clip_path: &Path,
         config: &VisualQcConfig,
     ) -> EditronResult<Vec<(f64, PathBuf)>> {
-        self.visual_qc.extract_candidate_frames(clip_path, config).await
+        Ok(self.visual_qc.extract_candidate_frames(clip_path, config).await?)
     }
 
     /// Read a frame JPEG and return base64 for vision API
     pub async fn get_frame_base64(&self, path: &Path) -> EditronResult<String> {
-        VisualQcEngine::frame_to_base64(path).await
+        Ok(VisualQcEngine::frame_to_base64(path).await?)
     }
 
     /// Apply visual QC results to a set of footage clips
-    pub fn apply_visual_qc(&self, clips: &mut [FootageClip], qc_result: &VisualQcResult) {
+    pub fn apply_visual_qc(&self, clips: &mut [QcFootageClip], qc_result: &VisualQcResult) {
         VisualQcEngine::apply_qc_to_clips(clips, qc_result);
     }
 
@@ -1195,4 +1604,161 @@ impl EditronService {
     pub fn visual_qc_engine(&self) -> &VisualQcEngine {
         &self.visual_qc
     }
+
+    // ============ REFRAMING ============
+
+    /// Track the subject through `clip` and build its crop path for `preset`.
+    /// Returns `None` when the preset keeps the source aspect ratio.
+    pub async fn reframe_clip(
+        &self,
+        clip: &TimelineClip,
+        preset: &ExportPreset,
+    ) -> EditronResult<Option<ReframeTrack>> {
+        let metadata = self.probe(&clip.source).await?;
+        let (Some(width), Some(height)) = (preset.width, preset.height) else {
+            return Ok(None);
+        };
+        if !preset.needs_reframe(&metadata) {
+            return Ok(None);
+        }
+
+        let mut track = ReframeTrack::centered(metadata.width, metadata.height, width, height);
+        let frame_dir = self.reframe_frame_dir();
+        let keyframes = self.reframe.analyze_shot(
+            &self.ffmpeg,
+            &clip.source,
+            (clip.source_in, clip.source_out),
+            track.width,
+            track.height,
+            &frame_dir,
+        ).await;
+        let _ = tokio::fs::remove_dir_all(&frame_dir).await;
+
+        track.keyframes = keyframes?;
+        Ok(Some(track))
+    }
+
+    /// Run the reframing pass over the main picture track (V1) of `edit`.
+    /// Clips whose crop path an editor has overridden are left untouched.
+    /// Returns the number of clips given a track.
+    pub async fn reframe_edit(
+        &self,
+        edit: &mut AssembledEdit,
+        preset: &ExportPreset,
+    ) -> EditronResult<usize> {
+        let mut reframed = 0;
+        for clip in edit.video_clips.iter_mut().filter(|c| c.track == 1) {
+            if clip.reframe.as_ref().is_some_and(|r| r.is_manual()) {
+                continue;
+            }
+            clip.reframe = self.reframe_clip(clip, preset).await?;
+            if clip.reframe.is_some() {
+                reframed += 1;
+            }
+        }
+        Ok(reframed)
+    }
+
+    /// Render a timeline clip's source range with `preset`, cropping along its
+    /// reframe track. Clips without a track get a centred crop when the preset
+    /// changes the aspect ratio, rather than being squashed.
+    pub async fn render_clip(
+        &self,
+        clip: &TimelineClip,
+        preset: &ExportPreset,
+        output: &Path,
+    ) -> EditronResult<PathBuf> {
+        let metadata = self.probe(&clip.source).await?;
+        let track = match (&clip.reframe, preset.width, preset.height) {
+            (Some(track), _, _) => Some(track.clone()),
+            (None, Some(width), Some(height)) if preset.needs_reframe(&metadata) => {
+                Some(ReframeTrack::centered(metadata.width, metadata.height, width, height))
+            }
+            _ => None,
+        };
+        let filter = track.map(|t| t.crop_filter(metadata.width, metadata.height, clip.source_in));
+
+        self.ffmpeg.export_cropped(
+            clip.source.as_path(),
+            output,
+            Some((clip.source_in, clip.source_out)),
+            filter.as_deref(),
+            preset,
+        ).await
+    }
+
+    /// Export a whole file with `preset`, reframing shot by shot when the
+    /// preset changes the aspect ratio. The crop cuts with the picture at
+    /// each detected scene change. Returns the crop path that was applied.
+    pub async fn export_reframed(
+        &self,
+        input: &Path,
+        output: &Path,
+        preset: &ExportPreset,
+    ) -> EditronResult<Option<ReframeTrack>> {
+        let metadata = self.probe(input).await?;
+        let (Some(width), Some(height)) = (preset.width, preset.height) else {
+            self.ffmpeg.export_cropped(input, output, None, None, preset).await?;
+            return Ok(None);
+        };
+        if !preset.needs_reframe(&metadata) {
+            self.ffmpeg.export_cropped(input, output, None, None, preset).await?;
+            return Ok(None);
+        }
+
+        let scenes = self.detect_scenes(input, DetectionMethod::default()).await?;
+        let mut shots: Vec<(f64, f64)> = scenes.scenes.iter().map(|s| (s.start_time, s.end_time)).collect();
+        if shots.is_empty() {
+            shots.push((0.0, metadata.duration_seconds));
+        }
+
+        let mut track = ReframeTrack::centered(metadata.width, metadata.height, width, height);
+        track.keyframes.clear();
+        let frame_dir = self.reframe_frame_dir();
+        for (index, shot) in shots.into_iter().enumerate() {
+            let keyframes = self.reframe.analyze_shot(
+                &self.ffmpeg,
+                input,
+                shot,
+                track.width,
+                track.height,
+                &frame_dir,
+            ).await;
+            let mut keyframes = match keyframes {
+                Ok(keyframes) => keyframes,
+                Err(e) => {
+                    let _ = tokio::fs::remove_dir_all(&frame_dir).await;
+                    return Err(e);
+                }
+            };
+            if index > 0 {
+                if let Some(first) = keyframes.first_mut() {
+                    first.cut = true;
+                }
+            }
+            track.keyframes.extend(keyframes);
+        }
+        let _ = tokio::fs::remove_dir_all(&frame_dir).await;
+
+        let filter = track.crop_filter(metadata.width, metadata.height, 0.0);
+        self.ffmpeg.export_cropped(input, output, None, Some(&filter), preset).await?;
+        Ok(Some(track))
+    }
+
+    fn reframe_frame_dir(&self) -> PathBuf {
+        self.work_dir.join("reframe").join(Uuid::new_v4().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loudness_reports_are_keyed_by_the_full_render_path() {
+        let a = EditronService::loudness_report_path(Path::new("/renders/a/final.mp4"));
+        let b = EditronService::loudness_report_path(Path::new("/renders/b/final.mp4"));
+        assert_eq!(a, PathBuf::from("/renders/a/final.mp4.loudness.json"));
+        assert_ne!(a, b);
+    }
 }
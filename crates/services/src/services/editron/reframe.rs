@@ -0,0 +1,643 @@
+//! Automatic reframing for vertical deliverables
+//!
+//! Landscape footage going to a 9:16 preset is cropped around the subject
+//! instead of the centre. Frames are sampled across each shot with
+//! [`FFmpegClient::extract_frame`] as greyscale PGM, scored on the CPU for
+//! motion and saliency, and the crop window is placed over the busiest part
+//! of the picture. The raw path is then smoothed so the virtual camera pans
+//! like an operator rather than snapping between samples.
+//!
+//! The result is a [`ReframeTrack`] of crop keyframes in source time. It is
+//! stored on the [`TimelineClip`](super::edit_assembly::TimelineClip) so an
+//! editor can override it before the clip is rendered.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::ffmpeg::FFmpegClient;
+use super::{EditronError, EditronResult};
+
+/// Who produced a reframe track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReframeSource {
+    /// Computed by the tracking pass; replaced when the pass runs again
+    Auto,
+    /// Set or adjusted by an editor; never overwritten automatically
+    Manual,
+}
+
+/// Crop centre at a point in source time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CropKeyframe {
+    /// Source time in seconds
+    pub time: f64,
+    /// Normalized 0.0-1.0 crop centre
+    pub x: f64,
+    pub y: f64,
+    /// Jump here instead of easing from the previous keyframe (shot change)
+    #[serde(default)]
+    pub cut: bool,
+}
+
+/// Per-clip crop path for rendering into a different aspect ratio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReframeTrack {
+    /// Target aspect ratio (width / height)
+    pub aspect: f64,
+    /// Crop window size, normalized to the source frame
+    pub width: f64,
+    pub height: f64,
+    pub keyframes: Vec<CropKeyframe>,
+    pub source: ReframeSource,
+}
+
+impl ReframeTrack {
+    /// Centred crop of a `source_width`x`source_height` frame at the target size's aspect
+    pub fn centered(source_width: u32, source_height: u32, target_width: u32, target_height: u32) -> Self {
+        let aspect = target_width as f64 / target_height.max(1) as f64;
+        let source_aspect = source_width as f64 / source_height.max(1) as f64;
+        let (width, height) = if source_aspect > aspect {
+            (aspect / source_aspect, 1.0)
+        } else {
+            (1.0, source_aspect / aspect)
+        };
+
+        Self {
+            aspect,
+            width,
+            height,
+            keyframes: vec![CropKeyframe { time: 0.0, x: 0.5, y: 0.5, cut: false }],
+            source: ReframeSource::Auto,
+        }
+    }
+
+    pub fn is_manual(&self) -> bool {
+        self.source == ReframeSource::Manual
+    }
+
+    /// Editor override: pin the crop centre at `time`, replacing any keyframe
+    /// within a frame of it. The track becomes manual so re-running the
+    /// tracking pass leaves it alone.
+    pub fn set_keyframe(&mut self, time: f64, x: f64, y: f64) {
+        let (x, y) = self.clamp_center(x, y);
+        self.keyframes.retain(|k| (k.time - time).abs() > 0.001);
+        let index = self.keyframes.partition_point(|k| k.time < time);
+        self.keyframes.insert(index, CropKeyframe { time, x, y, cut: false });
+        self.source = ReframeSource::Manual;
+    }
+
+    /// Crop centre at `time`, interpolated between keyframes
+    pub fn center_at(&self, time: f64) -> (f64, f64) {
+        let Some(first) = self.keyframes.first() else {
+            return (0.5, 0.5);
+        };
+        if time <= first.time {
+            return (first.x, first.y);
+        }
+        for pair in self.keyframes.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if time < b.time {
+                if b.cut || b.time <= a.time {
+                    return (a.x, a.y);
+                }
+                let f = (time - a.time) / (b.time - a.time);
+                return (a.x + (b.x - a.x) * f, a.y + (b.y - a.y) * f);
+            }
+        }
+        let last = self.keyframes[self.keyframes.len() - 1];
+        (last.x, last.y)
+    }
+
+    /// FFmpeg `crop` filter following this track. `time_offset` is the source
+    /// time of the first output frame (the trim in point when rendering a clip).
+    pub fn crop_filter(&self, source_width: u32, source_height: u32, time_offset: f64) -> String {
+        let crop_w = even_pixels(self.width, source_width);
+        let crop_h = even_pixels(self.height, source_height);
+        let time = if time_offset.abs() < 1e-9 {
+            "t".to_string()
+        } else {
+            format!("(t+{:.6})", time_offset)
+        };
+
+        let x = self.axis_expression(&time, |k| {
+            ((k.x - self.width / 2.0) * source_width as f64).clamp(0.0, (source_width - crop_w) as f64)
+        });
+        let y = self.axis_expression(&time, |k| {
+            ((k.y - self.height / 2.0) * source_height as f64).clamp(0.0, (source_height - crop_h) as f64)
+        });
+
+        format!("crop=w={}:h={}:x='{}':y='{}'", crop_w, crop_h, x, y)
+    }
+
+    /// Piecewise-linear expression in `time` through the keyframe offsets
+    fn axis_expression(&self, time: &str, offset: impl Fn(&CropKeyframe) -> f64) -> String {
+        let Some(last) = self.keyframes.last() else {
+            return "0".to_string();
+        };
+        if self.keyframes.iter().all(|k| (offset(k) - offset(last)).abs() < 0.05) {
+            return format!("{:.1}", offset(last));
+        }
+        let mut expr = format!("{:.1}", offset(last));
+        for pair in self.keyframes.windows(2).rev() {
+            let (a, b) = (&pair[0], &pair[1]);
+            let (va, vb) = (offset(a), offset(b));
+            let segment = if b.cut || (vb - va).abs() < 0.05 || b.time <= a.time {
+                format!("{:.1}", va)
+            } else {
+                format!(
+                    "{:.1}+{:.3}*({}-{:.3})",
+                    va,
+                    (vb - va) / (b.time - a.time),
+                    time,
+                    a.time
+                )
+            };
+            expr = format!("if(lt({},{:.3}),{},{})", time, b.time, segment, expr);
+        }
+        expr
+    }
+
+    fn clamp_center(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            x.clamp(self.width / 2.0, 1.0 - self.width / 2.0),
+            y.clamp(self.height / 2.0, 1.0 - self.height / 2.0),
+        )
+    }
+}
+
+fn even_pixels(fraction: f64, size: u32) -> u32 {
+    (((fraction * size as f64).round() as u32) & !1).clamp(2, size.max(2))
+}
+
+/// 8-bit greyscale frame
+#[derive(Debug, Clone)]
+pub struct GrayFrame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl GrayFrame {
+    /// Parse a binary PGM (P5) image as written by ffmpeg's `pgm` encoder
+    pub fn from_pgm(data: &[u8]) -> EditronResult<Self> {
+        let mut pos = 0;
+        let mut fields = Vec::with_capacity(4);
+        while fields.len() < 4 {
+            while pos < data.len() && data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if pos < data.len() && data[pos] == b'#' {
+                while pos < data.len() && data[pos] != b'\n' {
+                    pos += 1;
+                }
+                continue;
+            }
+            let start = pos;
+            while pos < data.len() && !data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err(EditronError::InvalidFormat("Truncated PGM header".to_string()));
+            }
+            fields.push(String::from_utf8_lossy(&data[start..pos]).to_string());
+        }
+        // Exactly one whitespace byte separates the header from the raster
+        pos += 1;
+
+        if fields[0] != "P5" {
+            return Err(EditronError::InvalidFormat(format!("Expected PGM (P5), got {}", fields[0])));
+        }
+        let parse = |s: &str| {
+            s.parse::<usize>()
+                .map_err(|_| EditronError::InvalidFormat(format!("Bad PGM header field: {}", s)))
+        };
+        let (width, height, max_value) = (parse(&fields[1])?, parse(&fields[2])?, parse(&fields[3])?);
+        if max_value > 255 {
+            return Err(EditronError::InvalidFormat("16-bit PGM is not supported".to_string()));
+        }
+        let pixels = data
+            .get(pos..pos + width * height)
+            .ok_or_else(|| EditronError::InvalidFormat("Truncated PGM raster".to_string()))?
+            .to_vec();
+
+        Ok(Self { width, height, pixels })
+    }
+
+    /// Box-filter down to at most `max_width` columns, keeping the aspect ratio
+    pub fn downsample(&self, max_width: usize) -> Self {
+        let factor = self.width.div_ceil(max_width.max(1)).max(1);
+        if factor == 1 {
+            return self.clone();
+        }
+        let width = self.width / factor;
+        let height = self.height / factor;
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = 0u32;
+                for dy in 0..factor {
+                    let row = (y * factor + dy) * self.width;
+                    for dx in 0..factor {
+                        sum += self.pixels[row + x * factor + dx] as u32;
+                    }
+                }
+                pixels.push((sum / (factor * factor) as u32) as u8);
+            }
+        }
+        Self { width, height, pixels }
+    }
+
+    fn at(&self, x: usize, y: usize) -> f64 {
+        self.pixels[y * self.width + x] as f64
+    }
+}
+
+/// Tuning for the tracking pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReframeSettings {
+    /// Seconds between analysed frames
+    pub sample_interval: f64,
+    /// Frames are downsampled to this width before scoring
+    pub analysis_width: usize,
+    /// Share of the attention score that comes from motion (rest is saliency)
+    pub motion_weight: f64,
+    /// How strongly the frame centre is preferred (0.0 = not at all)
+    pub center_bias: f64,
+    /// Penalty for jumping to a window far from the previous subject position
+    pub switch_penalty: f64,
+    /// Subject movement (fraction of frame) ignored before the crop follows
+    pub dead_zone: f64,
+    /// Samples in the moving-average window
+    pub smoothing_window: usize,
+    /// Fastest pan, in frame widths per second
+    pub max_pan_speed: f64,
+}
+
+impl Default for ReframeSettings {
+    fn default() -> Self {
+        Self {
+            sample_interval: 0.5,
+            analysis_width: 160,
+            motion_weight: 0.6,
+            center_bias: 0.2,
+            switch_penalty: 0.5,
+            dead_zone: 0.04,
+            smoothing_window: 5,
+            max_pan_speed: 0.35,
+        }
+    }
+}
+
+/// Motion/saliency subject tracker
+pub struct ReframeEngine {
+    settings: ReframeSettings,
+}
+
+impl ReframeEngine {
+    pub fn new(settings: ReframeSettings) -> Self {
+        Self { settings }
+    }
+
+    pub fn settings(&self) -> &ReframeSettings {
+        &self.settings
+    }
+
+    /// Track the subject through the `(start, end)` range of `source` and
+    /// return crop keyframes for a `width`x`height` (normalized) window. Sample
+    /// frames are written to `frame_dir` and removed afterwards.
+    pub async fn analyze_shot(
+        &self,
+        ffmpeg: &FFmpegClient,
+        source: &Path,
+        (start, end): (f64, f64),
+        width: f64,
+        height: f64,
+        frame_dir: &Path,
+    ) -> EditronResult<Vec<CropKeyframe>> {
+        tokio::fs::create_dir_all(frame_dir).await?;
+
+        let interval = self.settings.sample_interval.max(0.05);
+        let count = (((end - start) / interval).ceil() as usize).max(1);
+        let mut frames = Vec::with_capacity(count);
+        for i in 0..count {
+            let time = start + i as f64 * interval;
+            let path = frame_dir.join(format!("reframe_{:05}.pgm", i));
+            ffmpeg.extract_frame(source, path.as_path(), time).await?;
+            let data = tokio::fs::read(&path).await?;
+            let _ = tokio::fs::remove_file(&path).await;
+            frames.push((time, GrayFrame::from_pgm(&data)?.downsample(self.settings.analysis_width)));
+        }
+
+        Ok(self.track(&frames, width, height))
+    }
+
+    /// Crop keyframes for a shot from already-decoded `(time, frame)` samples
+    pub fn track(&self, frames: &[(f64, GrayFrame)], width: f64, height: f64) -> Vec<CropKeyframe> {
+        let mut samples = Vec::with_capacity(frames.len());
+        let mut previous: Option<&GrayFrame> = None;
+        let mut center = None;
+        for (time, frame) in frames {
+            let map = self.attention_map(frame, previous);
+            let found = self.locate_subject(&map, frame.width, frame.height, width, height, center);
+            samples.push((*time, found.0, found.1));
+            center = Some(found);
+            previous = Some(frame);
+        }
+        self.smooth_path(&samples, width, height)
+    }
+
+    /// Per-pixel attention: gradient energy, plus frame difference against the
+    /// previous sample with the mean removed so camera moves don't dominate
+    pub fn attention_map(&self, frame: &GrayFrame, previous: Option<&GrayFrame>) -> Vec<f64> {
+        let (w, h) = (frame.width, frame.height);
+        let mut saliency = vec![0.0; w * h];
+        for y in 1..h.saturating_sub(1) {
+            for x in 1..w.saturating_sub(1) {
+                let dx = frame.at(x + 1, y) - frame.at(x - 1, y);
+                let dy = frame.at(x, y + 1) - frame.at(x, y - 1);
+                saliency[y * w + x] = dx.abs() + dy.abs();
+            }
+        }
+        normalize(&mut saliency);
+
+        let motion = previous
+            .filter(|p| p.width == w && p.height == h)
+            .map(|p| {
+                let mut diff: Vec<f64> = frame
+                    .pixels
+                    .iter()
+                    .zip(&p.pixels)
+                    .map(|(a, b)| (*a as f64 - *b as f64).abs())
+                    .collect();
+                let mean = diff.iter().sum::<f64>() / diff.len().max(1) as f64;
+                diff.iter_mut().for_each(|d| *d = (*d - mean).max(0.0));
+                normalize(&mut diff);
+                diff
+            });
+
+        let motion_weight = if motion.is_some() { self.settings.motion_weight } else { 0.0 };
+        (0..w * h)
+            .map(|i| {
+                let (x, y) = ((i % w) as f64 / w as f64 - 0.5, (i / w) as f64 / h as f64 - 0.5);
+                let bias = 1.0 - self.settings.center_bias * (x * x + y * y) * 2.0;
+                let m = motion.as_ref().map_or(0.0, |m| m[i]);
+                (motion_weight * m + (1.0 - motion_weight) * saliency[i]) * bias
+            })
+            .collect()
+    }
+
+    /// Normalized centre for a `width`x`height` window over the attention map:
+    /// the window holding the most attention along each axis, centred on the
+    /// attention inside it
+    pub fn locate_subject(
+        &self,
+        map: &[f64],
+        map_width: usize,
+        map_height: usize,
+        width: f64,
+        height: f64,
+        previous: Option<(f64, f64)>,
+    ) -> (f64, f64) {
+        let mut columns = vec![0.0; map_width];
+        let mut rows = vec![0.0; map_height];
+        for (i, value) in map.iter().enumerate() {
+            columns[i % map_width] += value;
+            rows[i / map_width] += value;
+        }
+        (
+            self.best_window(&columns, width, previous.map(|p| p.0)),
+            self.best_window(&rows, height, previous.map(|p| p.1)),
+        )
+    }
+
+    fn best_window(&self, profile: &[f64], fraction: f64, previous: Option<f64>) -> f64 {
+        let len = profile.len();
+        let span = ((fraction * len as f64).round() as usize).clamp(1, len.max(1));
+        if len == 0 || span >= len {
+            return 0.5;
+        }
+
+        let mut prefix = vec![0.0; len + 1];
+        for (i, value) in profile.iter().enumerate() {
+            prefix[i + 1] = prefix[i] + value;
+        }
+        let total = prefix[len];
+        if total <= f64::EPSILON {
+            return previous.unwrap_or(0.5);
+        }
+
+        let mut best = (f64::MIN, 0);
+        for start in 0..=len - span {
+            let sum = prefix[start + span] - prefix[start];
+            let center = (start as f64 + span as f64 / 2.0) / len as f64;
+            let distance = previous.map_or(0.0, |p| (center - p).abs());
+            let score = sum / total * (1.0 - self.settings.switch_penalty * distance);
+            if score > best.0 {
+                best = (score, start);
+            }
+        }
+
+        let start = best.1;
+        let window = &profile[start..start + span];
+        let weight: f64 = window.iter().sum();
+        let centroid = if weight > f64::EPSILON {
+            window
+                .iter()
+                .enumerate()
+                .map(|(i, v)| ((start + i) as f64 + 0.5) * v)
+                .sum::<f64>()
+                / weight
+        } else {
+            start as f64 + span as f64 / 2.0
+        };
+        (centroid / len as f64).clamp(fraction / 2.0, 1.0 - fraction / 2.0)
+    }
+
+    /// Turn raw `(time, x, y)` subject positions into keyframes: median filter
+    /// out single-sample glitches, hold still inside the dead zone, average,
+    /// limit pan speed, keep the window inside the frame and drop keyframes
+    /// that lie on a straight line between their neighbours
+    pub fn smooth_path(&self, samples: &[(f64, f64, f64)], width: f64, height: f64) -> Vec<CropKeyframe> {
+        if samples.is_empty() {
+            return Vec::new();
+        }
+        let times: Vec<f64> = samples.iter().map(|s| s.0).collect();
+        let xs = self.smooth_axis(&times, samples.iter().map(|s| s.1).collect(), width);
+        let ys = self.smooth_axis(&times, samples.iter().map(|s| s.2).collect(), height);
+
+        let mut keyframes: Vec<CropKeyframe> = times
+            .iter()
+            .zip(xs.iter().zip(&ys))
+            .map(|(time, (x, y))| CropKeyframe { time: *time, x: *x, y: *y, cut: false })
+            .collect();
+
+        let mut i = 1;
+        while i + 1 < keyframes.len() {
+            let (a, k, b) = (keyframes[i - 1], keyframes[i], keyframes[i + 1]);
+            let f = (k.time - a.time) / (b.time - a.time);
+            let on_line = (a.x + (b.x - a.x) * f - k.x).abs() < 0.005
+                && (a.y + (b.y - a.y) * f - k.y).abs() < 0.005;
+            if on_line {
+                keyframes.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        keyframes
+    }
+
+    fn smooth_axis(&self, times: &[f64], raw: Vec<f64>, fraction: f64) -> Vec<f64> {
+        let (min, max) = (fraction / 2.0, 1.0 - fraction / 2.0);
+        if min >= max {
+            return vec![0.5; raw.len()];
+        }
+        let n = raw.len();
+
+        let median: Vec<f64> = (0..n)
+            .map(|i| {
+                if i == 0 || i + 1 == n {
+                    return raw[i];
+                }
+                let mut window = [raw[i - 1], raw[i], raw[i + 1]];
+                window.sort_by(|a, b| a.total_cmp(b));
+                window[1]
+            })
+            .collect();
+
+        let mut held = Vec::with_capacity(n);
+        let mut position = median[0];
+        for value in &median {
+            let delta = value - position;
+            if delta.abs() > self.settings.dead_zone {
+                position = value - delta.signum() * self.settings.dead_zone;
+            }
+            held.push(position);
+        }
+
+        let radius = self.settings.smoothing_window / 2;
+        let averaged: Vec<f64> = (0..n)
+            .map(|i| {
+                let window = &held[i.saturating_sub(radius)..(i + radius + 1).min(n)];
+                window.iter().sum::<f64>() / window.len() as f64
+            })
+            .collect();
+
+        let mut path = Vec::with_capacity(n);
+        let mut position = averaged[0].clamp(min, max);
+        for (i, value) in averaged.iter().enumerate() {
+            if i > 0 {
+                let limit = self.settings.max_pan_speed * (times[i] - times[i - 1]).max(0.0);
+                position += (value - position).clamp(-limit, limit);
+            }
+            position = position.clamp(min, max);
+            path.push(position);
+        }
+        path
+    }
+}
+
+impl Default for ReframeEngine {
+    fn default() -> Self {
+        Self::new(ReframeSettings::default())
+    }
+}
+
+fn normalize(values: &mut [f64]) {
+    let max = values.iter().cloned().fold(0.0, f64::max);
+    if max > f64::EPSILON {
+        values.iter_mut().for_each(|v| *v /= max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 160x90 grey frame with a bright 12x30 block whose left edge is at `x`
+    fn frame_with_subject(x: usize) -> GrayFrame {
+        let (width, height) = (160, 90);
+        let mut pixels = vec![40u8; width * height];
+        for row in 30..60 {
+            for col in x..x + 12 {
+                pixels[row * width + col] = 230;
+            }
+        }
+        GrayFrame { width, height, pixels }
+    }
+
+    #[test]
+    fn test_pgm_parse_and_downsample() {
+        let mut data = b"P5\n# ffmpeg\n4 2\n255\n".to_vec();
+        data.extend_from_slice(&[0, 10, 20, 30, 40, 50, 60, 70]);
+        let frame = GrayFrame::from_pgm(&data).unwrap();
+        assert_eq!((frame.width, frame.height), (4, 2));
+        assert_eq!(frame.pixels[5], 50);
+
+        let half = frame.downsample(2);
+        assert_eq!((half.width, half.height), (2, 1));
+        assert_eq!(half.pixels, vec![25, 45]);
+
+        assert!(GrayFrame::from_pgm(b"P6\n4 2\n255\n").is_err());
+    }
+
+    #[test]
+    fn test_tracks_off_centre_subject() {
+        let track = ReframeTrack::centered(1920, 1080, 1080, 1920);
+        let engine = ReframeEngine::default();
+        let frames: Vec<(f64, GrayFrame)> = (0..6)
+            .map(|i| (i as f64 * 0.5, frame_with_subject(120 + i % 2)))
+            .collect();
+
+        let keyframes = engine.track(&frames, track.width, track.height);
+        assert!(!keyframes.is_empty());
+        let last = keyframes.last().unwrap();
+        // Subject centre is at ~126/160; the crop follows it right, never past the edge
+        assert!(last.x > 0.7, "crop stayed near centre: {}", last.x);
+        assert!(last.x <= 1.0 - track.width / 2.0 + 1e-9);
+        assert!((last.y - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_smoothing_limits_jitter_and_pan_speed() {
+        let engine = ReframeEngine::default();
+        let samples: Vec<(f64, f64, f64)> = (0..10)
+            .map(|i| {
+                let x = if i == 4 { 0.9 } else if i % 2 == 0 { 0.5 } else { 0.52 };
+                (i as f64 * 0.5, x, 0.5)
+            })
+            .collect();
+
+        let keyframes = engine.smooth_path(&samples, 0.316, 1.0);
+        for k in &keyframes {
+            assert!((k.x - 0.5).abs() < 0.02, "jitter leaked through at {}: {}", k.time, k.x);
+        }
+
+        let jump: Vec<(f64, f64, f64)> = (0..6).map(|i| (i as f64 * 0.5, if i < 2 { 0.2 } else { 0.8 }, 0.5)).collect();
+        let keyframes = engine.smooth_path(&jump, 0.316, 1.0);
+        for pair in keyframes.windows(2) {
+            let speed = (pair[1].x - pair[0].x).abs() / (pair[1].time - pair[0].time);
+            assert!(speed <= engine.settings().max_pan_speed + 0.01);
+        }
+    }
+
+    #[test]
+    fn test_manual_override_and_crop_filter() {
+        let mut track = ReframeTrack::centered(1920, 1080, 1080, 1920);
+        assert!((track.width - 0.31640625).abs() < 1e-6);
+        assert_eq!(track.height, 1.0);
+
+        track.set_keyframe(2.0, 0.95, 0.5);
+        track.set_keyframe(1.0, 0.3, 0.5);
+        assert!(track.is_manual());
+        assert_eq!(track.keyframes.len(), 3);
+        // Clamped so the window stays inside the frame
+        assert!((track.keyframes[2].x - (1.0 - track.width / 2.0)).abs() < 1e-9);
+        assert!((track.center_at(0.5).0 - 0.4).abs() < 1e-9);
+
+        let filter = track.crop_filter(1920, 1080, 10.0);
+        assert!(filter.starts_with("crop=w=608:h=1080:x='if(lt((t+10.000000),1.000),"));
+        assert!(filter.ends_with(":y='0.0'"));
+    }
+}
@@ -128,6 +128,30 @@ pub enum Transition {
     },
 }
 
+impl Transition {
+    /// Length of the transition in frames (zero for a cut)
+    pub fn duration_frames(&self) -> u32 {
+        match self {
+            Transition::Cut => 0,
+            Transition::Dissolve { duration_frames, .. }
+            | Transition::FadeToColor { duration_frames, .. }
+            | Transition::Wipe { duration_frames, .. }
+            | Transition::WhipPan { duration_frames, .. }
+            | Transition::ZoomPush { duration_frames, .. }
+            | Transition::Glitch { duration_frames, .. }
+            | Transition::LightLeak { duration_frames, .. }
+            | Transition::LensDistort { duration_frames, .. }
+            | Transition::Spin { duration_frames, .. }
+            | Transition::Iris { duration_frames, .. }
+            | Transition::FilmFrame { duration_frames, .. }
+            | Transition::LumaWipe { duration_frames, .. }
+            | Transition::ShapeWipe { duration_frames, .. }
+            | Transition::Morph { duration_frames, .. } => *duration_frames,
+            Transition::BeatSync { base_transition, .. } => base_transition.duration_frames(),
+        }
+    }
+}
+
 /// Easing curve for animations
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
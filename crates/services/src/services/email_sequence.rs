@@ -0,0 +1,1036 @@
+//! Outbound email sequences
+//!
+//! Runs enrolled CRM contacts through a sequence's steps: template emails
+//! with `{{field}}` merge fields from the contact, waits counted in business
+//! days, and follow-up tasks. Mail goes out through the sequence's account
+//! only inside the account's send window and under its daily cap.
+//!
+//! Enrollments stop when the contact replies or the address bounces, as seen
+//! on the synced mailbox, and when a meeting with the contact is booked.
+//! Sends, opens, replies, bounces, meetings and tasks are recorded against
+//! the step that led to them for per-step reporting. Opens are tracked with
+//! a pixel when `EMAIL_TRACKING_BASE_URL` points at a reachable server.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::Duration,
+};
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveTime, TimeZone, Utc, Weekday};
+use db::models::{
+    crm_activity::{CrmActivity, CrmActivityError},
+    crm_contact::{CrmContact, CrmContactError},
+    email_account::{EmailAccount, EmailAccountError},
+    email_message::{EmailMessage, EmailMessageError},
+    email_sequence::{
+        CreateSequenceEvent, EmailSequence, EmailSequenceEnrollment, EmailSequenceError,
+        EmailSequenceEvent, EmailSequenceStep, EnrollmentStatus, SequenceEventType, SequenceStatus,
+        SequenceStepType,
+    },
+    task::{CreateTask, Priority, Task},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::json;
+use sqlx::SqlitePool;
+use thiserror::Error;
+use tokio::{task::JoinHandle, time::interval};
+use tracing::{error, info, warn};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::services::{
+    crm_enrichment::normalize_email,
+    email_sync::{EmailSyncError, EmailSyncService, OutgoingEmail},
+};
+
+const RUN_INTERVAL: Duration = Duration::from_secs(60);
+/// Enrollments processed per tick
+const RUN_BATCH: i64 = 100;
+/// Delay before retrying a send that failed for a transient reason
+const RETRY_DELAY_MINUTES: i64 = 30;
+/// How long after a send a booked meeting is credited to the sequence
+const MEETING_ATTRIBUTION_DAYS: i64 = 30;
+const MEETING_ACTIVITY_TYPES: [&str; 2] = ["meeting_scheduled", "meeting_completed"];
+
+static MERGE_FIELD: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{\s*([\w.]+)\s*(?:\|([^}]*))?\}\}").unwrap());
+static EMAIL_ADDRESS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+").unwrap());
+
+#[derive(Debug, Error)]
+pub enum EmailSequenceServiceError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Sequence(#[from] EmailSequenceError),
+    #[error(transparent)]
+    Contact(#[from] CrmContactError),
+    #[error(transparent)]
+    Account(#[from] EmailAccountError),
+    #[error(transparent)]
+    Message(#[from] EmailMessageError),
+    #[error(transparent)]
+    Activity(#[from] CrmActivityError),
+    #[error(transparent)]
+    Sync(#[from] EmailSyncError),
+}
+
+/// Parse an `HH:MM` send window boundary
+pub fn parse_send_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// When an account may send sequence mail
+#[derive(Debug, Clone, Copy)]
+pub struct SendWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub offset: FixedOffset,
+}
+
+impl SendWindow {
+    /// The account's window, or 09:00-17:00 UTC when it is misconfigured
+    pub fn for_account(account: &EmailAccount) -> Self {
+        let start = parse_send_time(&account.send_window_start);
+        let end = parse_send_time(&account.send_window_end);
+        let offset = FixedOffset::east_opt(account.send_utc_offset_minutes * 60);
+        match (start, end, offset) {
+            (Some(start), Some(end), Some(offset)) if start < end => Self { start, end, offset },
+            _ => {
+                warn!(
+                    "Invalid send window on {}, using 09:00-17:00 UTC",
+                    account.email_address
+                );
+                Self {
+                    start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                    end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+                    offset: FixedOffset::east_opt(0).unwrap(),
+                }
+            }
+        }
+    }
+
+    /// The first moment at or after `at` that falls inside the window on a weekday
+    pub fn next_open(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let local = at.with_timezone(&self.offset);
+        let mut date = local.date_naive();
+        if is_business_day(date.weekday()) && local.time() >= self.start && local.time() < self.end
+        {
+            return at;
+        }
+        if local.time() >= self.end || !is_business_day(date.weekday()) {
+            date = date.succ_opt().unwrap_or(date);
+        }
+        while !is_business_day(date.weekday()) {
+            date = date.succ_opt().unwrap_or(date);
+        }
+        self.local_to_utc(date.and_time(self.start))
+    }
+
+    /// Midnight of the local day containing `at`, in UTC
+    pub fn day_start(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let date = at.with_timezone(&self.offset).date_naive();
+        self.local_to_utc(date.and_time(NaiveTime::MIN))
+    }
+
+    fn local_to_utc(&self, local: chrono::NaiveDateTime) -> DateTime<Utc> {
+        self.offset
+            .from_local_datetime(&local)
+            .single()
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|| local.and_utc())
+    }
+}
+
+fn is_business_day(day: Weekday) -> bool {
+    !matches!(day, Weekday::Sat | Weekday::Sun)
+}
+
+/// `days` business days after `from` in the given local time, at the same time of day
+pub fn add_business_days(from: DateTime<Utc>, days: i32, offset: FixedOffset) -> DateTime<Utc> {
+    let mut local = from.with_timezone(&offset);
+    let mut remaining = days.max(0);
+    while remaining > 0 {
+        local += chrono::Duration::days(1);
+        if is_business_day(local.weekday()) {
+            remaining -= 1;
+        }
+    }
+    local.with_timezone(&Utc)
+}
+
+/// Fill `{{field}}` and `{{field|fallback}}` placeholders. Unknown or empty
+/// fields fall back to the fallback, or to nothing.
+pub fn render_template(template: &str, fields: &BTreeMap<String, String>) -> String {
+    MERGE_FIELD
+        .replace_all(template, |caps: &regex::Captures| {
+            fields
+                .get(&caps[1])
+                .cloned()
+                .or_else(|| {
+                    caps.get(2)
+                        .map(|fallback| fallback.as_str().trim().to_string())
+                })
+                .unwrap_or_default()
+        })
+        .into_owned()
+}
+
+/// Delivery status notifications from the receiving server
+pub fn is_bounce(message: &EmailMessage) -> bool {
+    let from = message.from_address.to_lowercase();
+    let local = from.split('@').next().unwrap_or_default();
+    if matches!(local, "mailer-daemon" | "postmaster" | "mail-daemon") {
+        return true;
+    }
+    let subject = message
+        .subject
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+    [
+        "undeliverable",
+        "undelivered mail",
+        "delivery status notification (failure)",
+        "mail delivery failed",
+        "delivery has failed",
+        "returned mail",
+        "failure notice",
+    ]
+    .iter()
+    .any(|marker| subject.contains(marker))
+}
+
+/// Out-of-office and other automatic answers, which do not end a sequence
+pub fn is_auto_reply(message: &EmailMessage) -> bool {
+    let subject = message
+        .subject
+        .as_deref()
+        .unwrap_or_default()
+        .to_lowercase();
+    [
+        "automatic reply",
+        "auto reply",
+        "autoreply",
+        "auto-reply",
+        "out of office",
+        "out of the office",
+        "abwesenheitsnotiz",
+    ]
+    .iter()
+    .any(|marker| subject.contains(marker))
+}
+
+/// Why a contact could not be enrolled
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct SkippedContact {
+    pub crm_contact_id: Uuid,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct EnrollResult {
+    pub enrolled: Vec<EmailSequenceEnrollment>,
+    pub skipped: Vec<SkippedContact>,
+}
+
+/// What happened when a step ran
+enum StepOutcome {
+    /// Move on to the next step at this time
+    Advance(DateTime<Utc>, Option<Uuid>),
+    /// Try the same step again later
+    Retry(DateTime<Utc>),
+    /// The enrollment left the sequence
+    Finished,
+}
+
+/// Per-tick state shared by the enrollments of one run
+#[derive(Default)]
+struct RunState {
+    sequences: HashMap<Uuid, EmailSequence>,
+    accounts: HashMap<Uuid, EmailAccount>,
+    /// Sequence emails sent today, per account
+    sent_today: HashMap<Uuid, i64>,
+}
+
+#[derive(Clone)]
+pub struct EmailSequenceService {
+    pool: SqlitePool,
+}
+
+impl EmailSequenceService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Run due sequence steps in the background
+    pub async fn spawn(pool: SqlitePool) -> JoinHandle<()> {
+        let service = Self::new(pool);
+        tokio::spawn(async move {
+            service.start().await;
+        })
+    }
+
+    async fn start(&self) {
+        info!(
+            "Starting email sequence service with interval {:?}",
+            RUN_INTERVAL
+        );
+
+        let mut interval = interval(RUN_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = self.attribute_meetings().await {
+                error!("Failed to attribute meetings to sequences: {}", e);
+            }
+            match self.run_due().await {
+                Ok(0) => {}
+                Ok(count) => info!("Ran {} sequence steps", count),
+                Err(e) => error!("Failed to run email sequences: {}", e),
+            }
+        }
+    }
+
+    /// Enroll contacts one at a time or in bulk. Contacts that cannot be
+    /// emailed or are already enrolled are skipped with a reason.
+    pub async fn enroll(
+        &self,
+        sequence_id: Uuid,
+        contact_ids: &[Uuid],
+    ) -> Result<EnrollResult, EmailSequenceServiceError> {
+        let sequence = EmailSequence::find_by_id(&self.pool, sequence_id).await?;
+        if sequence.status == SequenceStatus::Archived.to_string() {
+            return Err(EmailSequenceError::Invalid("the sequence is archived".into()).into());
+        }
+
+        let mut result = EnrollResult::default();
+        let mut seen = HashSet::new();
+        for &contact_id in contact_ids {
+            if !seen.insert(contact_id) {
+                continue;
+            }
+            let skip = |reason: &str| SkippedContact {
+                crm_contact_id: contact_id,
+                reason: reason.to_string(),
+            };
+
+            let contact = match CrmContact::find_by_id(&self.pool, contact_id).await {
+                Ok(contact) => contact,
+                Err(CrmContactError::NotFound) => {
+                    result.skipped.push(skip("contact not found"));
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if let Some(reason) = Self::not_enrollable(&sequence, &contact) {
+                result.skipped.push(skip(reason));
+                continue;
+            }
+
+            match EmailSequenceEnrollment::enroll(&self.pool, sequence.id, contact.id).await? {
+                Some(enrollment) => result.enrolled.push(enrollment),
+                None => result.skipped.push(skip("already enrolled")),
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn not_enrollable(sequence: &EmailSequence, contact: &CrmContact) -> Option<&'static str> {
+        if contact.project_id != sequence.project_id {
+            Some("contact belongs to another project")
+        } else if contact.email.as_deref().and_then(normalize_email).is_none() {
+            Some("contact has no usable email address")
+        } else if contact.do_not_contact == Some(1) {
+            Some("contact is marked do not contact")
+        } else if contact.email_opt_in == Some(0) {
+            Some("contact opted out of email")
+        } else {
+            None
+        }
+    }
+
+    /// Run every enrollment whose next step is due; returns how many ran
+    pub async fn run_due(&self) -> Result<usize, EmailSequenceServiceError> {
+        let due = EmailSequenceEnrollment::find_due(&self.pool, RUN_BATCH).await?;
+        let mut state = RunState::default();
+        let mut ran = 0;
+
+        for enrollment in due {
+            match self.run_enrollment(&mut state, &enrollment).await {
+                Ok(()) => ran += 1,
+                Err(e) => {
+                    warn!("Sequence enrollment {} failed: {}", enrollment.id, e);
+                    // Keep one broken enrollment from being retried every tick
+                    let retry = Utc::now() + chrono::Duration::minutes(RETRY_DELAY_MINUTES);
+                    EmailSequenceEnrollment::reschedule(&self.pool, enrollment.id, retry).await?;
+                }
+            }
+        }
+
+        Ok(ran)
+    }
+
+    async fn run_enrollment(
+        &self,
+        state: &mut RunState,
+        enrollment: &EmailSequenceEnrollment,
+    ) -> Result<(), EmailSequenceServiceError> {
+        let sequence = match state.sequences.get(&enrollment.sequence_id) {
+            Some(sequence) => sequence.clone(),
+            None => {
+                let sequence =
+                    EmailSequence::find_by_id(&self.pool, enrollment.sequence_id).await?;
+                state.sequences.insert(sequence.id, sequence.clone());
+                sequence
+            }
+        };
+
+        // Walk through steps that run immediately (tasks, waits) in one go
+        let mut current_step = enrollment.current_step;
+        let mut last_message_id = enrollment.last_message_id;
+        loop {
+            let Some(step) =
+                EmailSequenceStep::find_at_position(&self.pool, sequence.id, current_step).await?
+            else {
+                EmailSequenceEnrollment::finish(
+                    &self.pool,
+                    enrollment.id,
+                    EnrollmentStatus::Completed,
+                    None,
+                )
+                .await?;
+                return Ok(());
+            };
+
+            let outcome = match step.kind() {
+                Some(SequenceStepType::Email) => {
+                    self.run_email_step(state, &sequence, enrollment, &step, last_message_id)
+                        .await?
+                }
+                Some(SequenceStepType::Wait) => {
+                    let account = self.account(state, sequence.email_account_id).await?;
+                    let offset = SendWindow::for_account(&account).offset;
+                    StepOutcome::Advance(
+                        add_business_days(Utc::now(), step.wait_business_days.unwrap_or(1), offset),
+                        None,
+                    )
+                }
+                Some(SequenceStepType::Task) => {
+                    self.run_task_step(&sequence, enrollment, &step).await?;
+                    StepOutcome::Advance(Utc::now(), None)
+                }
+                None => {
+                    self.fail(
+                        enrollment,
+                        &step,
+                        &format!("unknown step type {}", step.step_type),
+                    )
+                    .await?;
+                    StepOutcome::Finished
+                }
+            };
+
+            match outcome {
+                StepOutcome::Advance(at, message_id) => {
+                    current_step += 1;
+                    last_message_id = message_id.or(last_message_id);
+                    EmailSequenceEnrollment::advance(
+                        &self.pool,
+                        enrollment.id,
+                        current_step,
+                        at,
+                        message_id,
+                    )
+                    .await?;
+                    // Emails and waits end the run; the next step is picked up when due
+                    if at > Utc::now() || message_id.is_some() {
+                        return Ok(());
+                    }
+                }
+                StepOutcome::Retry(at) => {
+                    EmailSequenceEnrollment::reschedule(&self.pool, enrollment.id, at).await?;
+                    return Ok(());
+                }
+                StepOutcome::Finished => return Ok(()),
+            }
+        }
+    }
+
+    async fn account(
+        &self,
+        state: &mut RunState,
+        account_id: Uuid,
+    ) -> Result<EmailAccount, EmailSequenceServiceError> {
+        if let Some(account) = state.accounts.get(&account_id) {
+            return Ok(account.clone());
+        }
+        let account = EmailAccount::find_by_id(&self.pool, account_id).await?;
+        state.accounts.insert(account.id, account.clone());
+        Ok(account)
+    }
+
+    async fn run_email_step(
+        &self,
+        state: &mut RunState,
+        sequence: &EmailSequence,
+        enrollment: &EmailSequenceEnrollment,
+        step: &EmailSequenceStep,
+        last_message_id: Option<Uuid>,
+    ) -> Result<StepOutcome, EmailSequenceServiceError> {
+        let contact = CrmContact::find_by_id(&self.pool, enrollment.crm_contact_id).await?;
+        if let Some(reason) = Self::not_enrollable(sequence, &contact) {
+            EmailSequenceEnrollment::finish(
+                &self.pool,
+                enrollment.id,
+                EnrollmentStatus::Removed,
+                Some(reason),
+            )
+            .await?;
+            return Ok(StepOutcome::Finished);
+        }
+
+        // Safety net for replies that arrived while the mailbox hook was not running
+        let replies =
+            EmailMessage::find_replies_from_contact(&self.pool, contact.id, enrollment.enrolled_at)
+                .await?;
+        if let Some(reply) = replies.iter().find(|reply| !is_auto_reply(reply)) {
+            self.exit(enrollment, EnrollmentStatus::Replied, reply.id)
+                .await?;
+            return Ok(StepOutcome::Finished);
+        }
+
+        let account = self.account(state, sequence.email_account_id).await?;
+        let window = SendWindow::for_account(&account);
+        let now = Utc::now();
+        let open = window.next_open(now);
+        if open > now {
+            return Ok(StepOutcome::Retry(open));
+        }
+
+        let sent_today = match state.sent_today.get(&account.id) {
+            Some(count) => *count,
+            None => {
+                EmailSequenceEvent::count_sent_by_account_since(
+                    &self.pool,
+                    account.id,
+                    window.day_start(now),
+                )
+                .await?
+            }
+        };
+        if sent_today >= i64::from(account.daily_send_limit) {
+            let tomorrow = window.day_start(now) + chrono::Duration::days(1);
+            return Ok(StepOutcome::Retry(window.next_open(tomorrow)));
+        }
+
+        let fields = contact.field_values();
+        let previous = match last_message_id {
+            Some(id) => EmailMessage::find_by_id(&self.pool, id).await.ok(),
+            None => None,
+        };
+        let subject = match step.subject.as_deref().filter(|s| !s.trim().is_empty()) {
+            Some(subject) => render_template(subject, &fields),
+            None => {
+                let previous_subject = previous
+                    .as_ref()
+                    .and_then(|p| p.subject.clone())
+                    .unwrap_or_else(|| sequence.name.clone());
+                if previous_subject.to_lowercase().starts_with("re:") {
+                    previous_subject
+                } else {
+                    format!("Re: {}", previous_subject)
+                }
+            }
+        };
+        let in_reply_to_message_id = match step.subject.as_deref() {
+            Some(s) if !s.trim().is_empty() => None,
+            _ => previous.as_ref().map(|p| p.id),
+        };
+
+        let event_id = Uuid::new_v4();
+        let body_text = render_template(step.body_text.as_deref().unwrap_or_default(), &fields);
+        let body_html = step
+            .body_html
+            .as_deref()
+            .map(|html| render_template(html, &fields))
+            .map(|html| with_tracking_pixel(html, event_id));
+        // `send` only knows the address, so normalize the stored one
+        let to = contact
+            .email
+            .as_deref()
+            .and_then(normalize_email)
+            .unwrap_or_default();
+
+        let sent = EmailSyncService::new(self.pool.clone())
+            .send(
+                account.id,
+                OutgoingEmail {
+                    to: vec![to],
+                    cc: Vec::new(),
+                    bcc: Vec::new(),
+                    subject,
+                    body_text,
+                    body_html,
+                    in_reply_to_message_id,
+                },
+            )
+            .await;
+
+        match sent {
+            Ok(message) => {
+                EmailSequenceEvent::create(
+                    &self.pool,
+                    CreateSequenceEvent {
+                        id: Some(event_id),
+                        enrollment_id: enrollment.id,
+                        sequence_id: sequence.id,
+                        step_id: Some(step.id),
+                        event_type: SequenceEventType::Sent,
+                        email_message_id: Some(message.id),
+                        task_id: None,
+                        detail: None,
+                    },
+                )
+                .await?;
+                state.sent_today.insert(account.id, sent_today + 1);
+                Ok(StepOutcome::Advance(Utc::now(), Some(message.id)))
+            }
+            Err(EmailSyncError::InvalidMessage(reason)) => {
+                self.fail(enrollment, step, &reason).await?;
+                Ok(StepOutcome::Finished)
+            }
+            Err(e) => {
+                warn!(
+                    "Sequence email to contact {} failed, retrying later: {}",
+                    contact.id, e
+                );
+                Ok(StepOutcome::Retry(
+                    Utc::now() + chrono::Duration::minutes(RETRY_DELAY_MINUTES),
+                ))
+            }
+        }
+    }
+
+    async fn run_task_step(
+        &self,
+        sequence: &EmailSequence,
+        enrollment: &EmailSequenceEnrollment,
+        step: &EmailSequenceStep,
+    ) -> Result<(), EmailSequenceServiceError> {
+        let contact = CrmContact::find_by_id(&self.pool, enrollment.crm_contact_id).await?;
+        let fields = contact.field_values();
+        let title = render_template(step.task_title.as_deref().unwrap_or_default(), &fields);
+        let description = step
+            .task_description
+            .as_deref()
+            .map(|description| render_template(description, &fields));
+
+        let task = Task::create(
+            &self.pool,
+            &CreateTask {
+                project_id: sequence.project_id,
+                pod_id: None,
+                board_id: None,
+                title,
+                description,
+                parent_task_attempt: None,
+                image_ids: None,
+                priority: Some(Priority::Medium),
+                assignee_id: None,
+                assigned_agent: None,
+                agent_id: None,
+                assigned_mcps: None,
+                created_by: "email-sequence".to_string(),
+                requires_approval: None,
+                parent_task_id: None,
+                tags: Some(vec!["sequence".to_string()]),
+                due_date: None,
+                custom_properties: Some(json!({
+                    "sequence_id": sequence.id,
+                    "sequence_enrollment_id": enrollment.id,
+                    "crm_contact_id": contact.id,
+                })),
+                scheduled_start: None,
+                scheduled_end: None,
+            },
+            Uuid::new_v4(),
+        )
+        .await?;
+
+        EmailSequenceEvent::create(
+            &self.pool,
+            CreateSequenceEvent {
+                id: None,
+                enrollment_id: enrollment.id,
+                sequence_id: sequence.id,
+                step_id: Some(step.id),
+                event_type: SequenceEventType::TaskCreated,
+                email_message_id: None,
+                task_id: Some(task.id),
+                detail: None,
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn fail(
+        &self,
+        enrollment: &EmailSequenceEnrollment,
+        step: &EmailSequenceStep,
+        reason: &str,
+    ) -> Result<(), EmailSequenceServiceError> {
+        if EmailSequenceEnrollment::finish(
+            &self.pool,
+            enrollment.id,
+            EnrollmentStatus::Failed,
+            Some(reason),
+        )
+        .await?
+        {
+            EmailSequenceEvent::create(
+                &self.pool,
+                CreateSequenceEvent {
+                    id: None,
+                    enrollment_id: enrollment.id,
+                    sequence_id: enrollment.sequence_id,
+                    step_id: Some(step.id),
+                    event_type: SequenceEventType::Failed,
+                    email_message_id: None,
+                    task_id: None,
+                    detail: Some(reason.to_string()),
+                },
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// End an enrollment because of a reply or bounce, crediting the last email sent
+    async fn exit(
+        &self,
+        enrollment: &EmailSequenceEnrollment,
+        status: EnrollmentStatus,
+        message_id: Uuid,
+    ) -> Result<bool, EmailSequenceServiceError> {
+        let event_type = match status {
+            EnrollmentStatus::Bounced => SequenceEventType::Bounced,
+            _ => SequenceEventType::Replied,
+        };
+        if !EmailSequenceEnrollment::finish(&self.pool, enrollment.id, status, None).await? {
+            return Ok(false);
+        }
+
+        let last_sent = EmailSequenceEvent::find_last_sent(&self.pool, enrollment.id).await?;
+        EmailSequenceEvent::create(
+            &self.pool,
+            CreateSequenceEvent {
+                id: None,
+                enrollment_id: enrollment.id,
+                sequence_id: enrollment.sequence_id,
+                step_id: last_sent.and_then(|event| event.step_id),
+                event_type,
+                email_message_id: Some(message_id),
+                task_id: None,
+                detail: None,
+            },
+        )
+        .await?;
+        Ok(true)
+    }
+
+    /// Check a message that arrived on a synced mailbox for replies and
+    /// bounces; returns how many enrollments it ended
+    pub async fn handle_inbound(
+        &self,
+        message: &EmailMessage,
+    ) -> Result<usize, EmailSequenceServiceError> {
+        if message.is_sent == 1 || message.is_draft == 1 || is_auto_reply(message) {
+            return Ok(0);
+        }
+
+        let mut thread_ids: Vec<String> = message
+            .references
+            .as_deref()
+            .and_then(|refs| serde_json::from_str(refs).ok())
+            .unwrap_or_default();
+        thread_ids.extend(message.in_reply_to.clone());
+
+        let mut enrollments: HashMap<Uuid, EmailSequenceEnrollment> = HashMap::new();
+        if let Some(enrollment) = EmailSequenceEnrollment::find_by_sent_message_ids(
+            &self.pool,
+            message.email_account_id,
+            &thread_ids,
+        )
+        .await?
+        {
+            enrollments.insert(enrollment.id, enrollment);
+        }
+
+        let bounce = is_bounce(message);
+        let addresses: Vec<String> = if bounce {
+            // The failed recipient is only named in the notification body
+            let body = message.body_text.as_deref().unwrap_or_default();
+            EMAIL_ADDRESS
+                .find_iter(body)
+                .filter_map(|m| normalize_email(m.as_str()))
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect()
+        } else {
+            normalize_email(&message.from_address).into_iter().collect()
+        };
+        for address in addresses {
+            for enrollment in EmailSequenceEnrollment::find_active_by_email(
+                &self.pool,
+                message.project_id,
+                &address,
+            )
+            .await?
+            {
+                enrollments.insert(enrollment.id, enrollment);
+            }
+        }
+
+        let status = if bounce {
+            EnrollmentStatus::Bounced
+        } else {
+            EnrollmentStatus::Replied
+        };
+        let mut ended = 0;
+        for enrollment in enrollments.values() {
+            if self.exit(enrollment, status, message.id).await? {
+                ended += 1;
+            }
+        }
+        Ok(ended)
+    }
+
+    /// Record an open from the tracking pixel; repeat opens are ignored
+    pub async fn record_open(&self, sent_event_id: Uuid) -> Result<(), EmailSequenceServiceError> {
+        let Some(sent) = EmailSequenceEvent::find_by_id(&self.pool, sent_event_id).await? else {
+            return Ok(());
+        };
+        if sent.event_type != SequenceEventType::Sent.to_string()
+            || EmailSequenceEvent::exists(
+                &self.pool,
+                sent.enrollment_id,
+                SequenceEventType::Opened,
+                sent.step_id,
+            )
+            .await?
+        {
+            return Ok(());
+        }
+
+        EmailSequenceEvent::create(
+            &self.pool,
+            CreateSequenceEvent {
+                id: None,
+                enrollment_id: sent.enrollment_id,
+                sequence_id: sent.sequence_id,
+                step_id: sent.step_id,
+                event_type: SequenceEventType::Opened,
+                email_message_id: sent.email_message_id,
+                task_id: None,
+                detail: None,
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Credit meetings booked with contacts after a sequence email, and take
+    /// those contacts out of the sequence
+    async fn attribute_meetings(&self) -> Result<(), EmailSequenceServiceError> {
+        let since = Utc::now() - chrono::Duration::days(MEETING_ATTRIBUTION_DAYS);
+        for enrollment in EmailSequenceEnrollment::find_contacted_since(&self.pool, since).await? {
+            if EmailSequenceEvent::exists(
+                &self.pool,
+                enrollment.id,
+                SequenceEventType::MeetingBooked,
+                None,
+            )
+            .await?
+            {
+                continue;
+            }
+            let Some(last_sent) =
+                EmailSequenceEvent::find_last_sent(&self.pool, enrollment.id).await?
+            else {
+                continue;
+            };
+
+            let meetings = CrmActivity::find_by_contact_since(
+                &self.pool,
+                enrollment.crm_contact_id,
+                enrollment.enrolled_at,
+            )
+            .await?;
+            let Some(meeting) = meetings
+                .iter()
+                .find(|activity| MEETING_ACTIVITY_TYPES.contains(&activity.activity_type.as_str()))
+            else {
+                continue;
+            };
+
+            EmailSequenceEvent::create(
+                &self.pool,
+                CreateSequenceEvent {
+                    id: None,
+                    enrollment_id: enrollment.id,
+                    sequence_id: enrollment.sequence_id,
+                    step_id: last_sent.step_id,
+                    event_type: SequenceEventType::MeetingBooked,
+                    email_message_id: None,
+                    task_id: None,
+                    detail: meeting.subject.clone(),
+                },
+            )
+            .await?;
+            EmailSequenceEnrollment::finish(
+                &self.pool,
+                enrollment.id,
+                EnrollmentStatus::Completed,
+                Some("meeting booked"),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Append an open-tracking pixel when a public base URL is configured
+fn with_tracking_pixel(html: String, sent_event_id: Uuid) -> String {
+    let Some(base_url) = std::env::var("EMAIL_TRACKING_BASE_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+    else {
+        return html;
+    };
+    let pixel = format!(
+        r#"<img src="{}/api/email/sequences/opens/{}.gif" width="1" height="1" alt="" style="display:none">"#,
+        base_url.trim_end_matches('/'),
+        sent_event_id
+    );
+    match html.rfind("</body>") {
+        Some(index) => {
+            let mut html = html;
+            html.insert_str(index, &pixel);
+            html
+        }
+        None => html + &pixel,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(start: &str, end: &str, offset_hours: i32) -> SendWindow {
+        SendWindow {
+            start: parse_send_time(start).unwrap(),
+            end: parse_send_time(end).unwrap(),
+            offset: FixedOffset::east_opt(offset_hours * 3600).unwrap(),
+        }
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn renders_merge_fields_with_fallbacks() {
+        let fields: BTreeMap<String, String> = [
+            ("first_name".to_string(), "Ada".to_string()),
+            ("custom_fields.booth".to_string(), "B12".to_string()),
+        ]
+        .into();
+
+        assert_eq!(
+            render_template(
+                "Hi {{ first_name }}, thanks for visiting {{custom_fields.booth}} at {{company_name|your company}}{{unknown}}.",
+                &fields
+            ),
+            "Hi Ada, thanks for visiting B12 at your company."
+        );
+    }
+
+    #[test]
+    fn send_window_opens_on_next_weekday() {
+        let window = window("09:00", "17:00", 0);
+        // Wednesday inside the window
+        let inside = utc("2026-03-04T10:00:00Z");
+        assert_eq!(window.next_open(inside), inside);
+        // Wednesday before and after the window
+        assert_eq!(
+            window.next_open(utc("2026-03-04T07:30:00Z")),
+            utc("2026-03-04T09:00:00Z")
+        );
+        assert_eq!(
+            window.next_open(utc("2026-03-04T18:00:00Z")),
+            utc("2026-03-05T09:00:00Z")
+        );
+        // Friday evening and Saturday move to Monday
+        assert_eq!(
+            window.next_open(utc("2026-03-06T17:00:00Z")),
+            utc("2026-03-09T09:00:00Z")
+        );
+        assert_eq!(
+            window.next_open(utc("2026-03-07T12:00:00Z")),
+            utc("2026-03-09T09:00:00Z")
+        );
+    }
+
+    #[test]
+    fn send_window_uses_local_time() {
+        let window = window("09:00", "17:00", -5);
+        // 13:00 UTC is 08:00 at UTC-5
+        assert_eq!(
+            window.next_open(utc("2026-03-04T13:00:00Z")),
+            utc("2026-03-04T14:00:00Z")
+        );
+        assert_eq!(
+            window.day_start(utc("2026-03-04T03:00:00Z")),
+            utc("2026-03-03T05:00:00Z")
+        );
+    }
+
+    #[test]
+    fn waits_skip_weekends() {
+        let utc_offset = FixedOffset::east_opt(0).unwrap();
+        // Thursday + 2 business days is Monday
+        assert_eq!(
+            add_business_days(utc("2026-03-05T10:00:00Z"), 2, utc_offset),
+            utc("2026-03-09T10:00:00Z")
+        );
+        // Saturday + 1 business day is Monday
+        assert_eq!(
+            add_business_days(utc("2026-03-07T10:00:00Z"), 1, utc_offset),
+            utc("2026-03-09T10:00:00Z")
+        );
+    }
+
+    #[test]
+    fn adds_tracking_pixel_before_body_end() {
+        let id = Uuid::new_v4();
+        // SAFETY: tests in this module do not read the variable concurrently
+        unsafe { std::env::set_var("EMAIL_TRACKING_BASE_URL", "https://crm.example.com/") };
+        let html = with_tracking_pixel("<html><body><p>Hi</p></body></html>".into(), id);
+        unsafe { std::env::remove_var("EMAIL_TRACKING_BASE_URL") };
+
+        assert!(html.contains(&format!(
+            "https://crm.example.com/api/email/sequences/opens/{}.gif",
+            id
+        )));
+        assert!(html.ends_with("</body></html>"));
+    }
+}
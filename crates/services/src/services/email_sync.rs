@@ -0,0 +1,1826 @@
+//! IMAP/SMTP mailbox sync for email accounts
+//!
+//! Every account that can be reached over IMAP (custom servers with a
+//! password, Gmail through XOAUTH2) is synced into `email_messages`:
+//!
+//! - The first pass backfills the last [`BACKFILL_DAYS`] of every selectable
+//!   folder. Afterwards only UIDs at or above the folder's stored UIDNEXT are
+//!   fetched, and a changed UIDVALIDITY restarts the folder from scratch.
+//! - Flags of already synced messages are refreshed on each pass. A message
+//!   that disappears from its folder loses its location and is claimed by
+//!   whichever folder it shows up in next, which is how moves are tracked.
+//! - Messages are keyed by their `Message-ID`, so a copy that lives in
+//!   several folders is stored once with every folder in `labels`. Threads
+//!   are keyed by the root of `References`, falling back to the parent's
+//!   thread for clients that only send `In-Reply-To`.
+//! - Servers that advertise IDLE get a watcher on INBOX so new mail shows up
+//!   without waiting for the next poll.
+//!
+//! Outgoing mail goes through the account's SMTP server and is stored as a
+//! sent message in the same thread as the message it replies to.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_imap::{
+    Authenticator,
+    extensions::idle::IdleResponse,
+    types::{Fetch, Flag, NameAttribute},
+};
+use chrono::{DateTime, Utc};
+use db::models::{
+    email_account::{EmailAccount, EmailAccountError, EmailProvider, UpdateEmailAccount},
+    email_folder::{EmailFolder, EmailFolderError, EmailFolderRole},
+    email_message::{CreateEmailMessage, EmailMessage, EmailMessageError, UpdateEmailMessage},
+};
+use futures::TryStreamExt;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    message::{Mailbox, MultiPart, header::ContentType},
+    transport::smtp::authentication::{Credentials, Mechanism},
+};
+use mail_parser::{Address, HeaderValue, MessageParser, PartType};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpStream,
+    task::JoinHandle,
+    time::{interval, sleep, timeout},
+};
+use tracing::{debug, error, info, warn};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use crate::services::{crm_enrichment::CrmEnrichmentService, email_sequence::EmailSequenceService};
+
+/// How often accounts are checked against their `sync_frequency_minutes`
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+/// How far back the first sync of a folder reaches
+const BACKFILL_DAYS: i64 = 90;
+/// Messages fetched per UID FETCH round trip
+const FETCH_BATCH: usize = 50;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const SMTP_TIMEOUT: Duration = Duration::from_secs(60);
+/// Servers drop IDLE after 30 minutes, so re-issue it a little earlier
+const IDLE_TIMEOUT: Duration = Duration::from_secs(25 * 60);
+const IDLE_RETRY_DELAY: Duration = Duration::from_secs(60);
+const SNIPPET_LEN: usize = 200;
+const INBOX: &str = "INBOX";
+
+/// Accounts with a sync in progress, shared by the poller, IDLE watchers and
+/// manual syncs triggered through the API
+static SYNCS_IN_FLIGHT: Lazy<Mutex<HashSet<Uuid>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Debug, Error)]
+pub enum EmailSyncError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Account(#[from] EmailAccountError),
+    #[error(transparent)]
+    Message(#[from] EmailMessageError),
+    #[error(transparent)]
+    Folder(#[from] EmailFolderError),
+    #[error("IMAP error: {0}")]
+    Imap(#[from] async_imap::error::Error),
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+    #[error("Could not connect to {host}: {reason}")]
+    Connect { host: String, reason: String },
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+    #[error("Email account is missing {0}")]
+    MissingSettings(&'static str),
+    #[error("{0} accounts cannot be synced over IMAP")]
+    Unsupported(String),
+    #[error("Invalid email: {0}")]
+    InvalidMessage(String),
+    #[error("A sync is already running for this account")]
+    AlreadySyncing,
+}
+
+impl EmailSyncError {
+    /// Errors that will not go away by retrying, so the account is parked in
+    /// the `error` state until its settings are fixed
+    fn is_permanent(&self) -> bool {
+        matches!(
+            self,
+            EmailSyncError::Auth(_)
+                | EmailSyncError::MissingSettings(_)
+                | EmailSyncError::Unsupported(_)
+        )
+    }
+}
+
+/// What one sync pass did
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct EmailSyncReport {
+    pub folders: usize,
+    /// New messages stored
+    pub fetched: usize,
+    /// Existing messages whose flags, folder or labels changed
+    pub updated: usize,
+}
+
+/// A message to send through the account's SMTP server
+#[derive(Debug, Clone, Deserialize, TS)]
+#[ts(export)]
+pub struct OutgoingEmail {
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub cc: Vec<String>,
+    #[serde(default)]
+    pub bcc: Vec<String>,
+    pub subject: String,
+    pub body_text: String,
+    pub body_html: Option<String>,
+    /// Stored message this one replies to; sets `In-Reply-To`/`References`
+    pub in_reply_to_message_id: Option<Uuid>,
+}
+
+/// Whether the sync engine can handle an account
+pub fn is_syncable(account: &EmailAccount) -> bool {
+    account.status == "active"
+        && account.sync_enabled.unwrap_or(1) == 1
+        && matches!(
+            account.provider.parse::<EmailProvider>(),
+            Ok(EmailProvider::ImapCustom | EmailProvider::Gmail)
+        )
+}
+
+#[derive(Clone)]
+pub struct EmailSyncService {
+    pool: SqlitePool,
+    idle_watchers: Arc<tokio::sync::Mutex<HashMap<Uuid, JoinHandle<()>>>>,
+}
+
+impl EmailSyncService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self {
+            pool,
+            idle_watchers: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Poll due accounts in the background and keep IDLE watchers running
+    pub async fn spawn(pool: SqlitePool) -> JoinHandle<()> {
+        let service = Self::new(pool);
+        tokio::spawn(async move {
+            service.start().await;
+        })
+    }
+
+    async fn start(&self) {
+        info!(
+            "Starting email sync service with interval {:?}",
+            POLL_INTERVAL
+        );
+
+        let mut interval = interval(POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match EmailAccount::find_needs_sync(&self.pool).await {
+                Ok(accounts) => {
+                    for account in accounts.iter().filter(|a| is_syncable(a)) {
+                        match self.sync_account(account.id).await {
+                            Ok(report) if report.fetched > 0 || report.updated > 0 => info!(
+                                "Synced {}: {} new, {} updated across {} folders",
+                                account.email_address,
+                                report.fetched,
+                                report.updated,
+                                report.folders
+                            ),
+                            Ok(_) | Err(EmailSyncError::AlreadySyncing) => {}
+                            Err(e) => {
+                                error!("Email sync failed for {}: {}", account.email_address, e)
+                            }
+                        }
+                    }
+                }
+                Err(e) => error!("Failed to load email accounts due for sync: {}", e),
+            }
+
+            self.ensure_idle_watchers().await;
+        }
+    }
+
+    /// Sync every folder of an account
+    pub async fn sync_account(&self, account_id: Uuid) -> Result<EmailSyncReport, EmailSyncError> {
+        self.sync_folders(account_id, None).await
+    }
+
+    async fn sync_folders(
+        &self,
+        account_id: Uuid,
+        only: Option<&str>,
+    ) -> Result<EmailSyncReport, EmailSyncError> {
+        let _guard = SyncGuard::acquire(account_id)?;
+
+        let result = self.run_sync(account_id, only).await;
+        match &result {
+            Ok(_) => {
+                let unread = EmailMessage::count_unread_by_account(&self.pool, account_id).await?;
+                EmailAccount::update_sync_status(
+                    &self.pool,
+                    account_id,
+                    "active",
+                    Some(unread as i32),
+                )
+                .await?;
+            }
+            Err(EmailSyncError::Account(EmailAccountError::NotFound)) => {}
+            Err(e) if e.is_permanent() => {
+                EmailAccount::set_error(&self.pool, account_id, &e.to_string()).await?;
+            }
+            Err(e) => {
+                EmailAccount::update(
+                    &self.pool,
+                    account_id,
+                    UpdateEmailAccount {
+                        last_error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+            }
+        }
+        result
+    }
+
+    async fn run_sync(
+        &self,
+        account_id: Uuid,
+        only: Option<&str>,
+    ) -> Result<EmailSyncReport, EmailSyncError> {
+        let account = EmailAccount::find_by_id(&self.pool, account_id).await?;
+        let settings = MailSettings::resolve(&self.pool, &account).await?;
+        let mut session = connect_imap(&settings).await?;
+
+        let mut report = EmailSyncReport::default();
+        let result = async {
+            let mut folders = list_folders(&mut session).await?;
+            if only.is_none() {
+                let names: Vec<String> = folders.iter().map(|(name, _)| name.clone()).collect();
+                EmailFolder::delete_missing(&self.pool, account.id, &names).await?;
+            }
+            // INBOX goes first so it keeps ownership of messages also filed elsewhere
+            folders.sort_by_key(|(name, role)| (*role != EmailFolderRole::Inbox, name.clone()));
+
+            for (name, role) in folders {
+                if only.is_some_and(|only| only != name) {
+                    continue;
+                }
+                let folder = EmailFolder::upsert(&self.pool, account.id, &name, role).await?;
+                let folder_report = self.sync_folder(&mut session, &account, &folder).await?;
+                report.folders += 1;
+                report.fetched += folder_report.fetched;
+                report.updated += folder_report.updated;
+            }
+            Ok::<_, EmailSyncError>(())
+        }
+        .await;
+
+        let _ = session.logout().await;
+        result.map(|_| report)
+    }
+
+    async fn sync_folder(
+        &self,
+        session: &mut ImapSession,
+        account: &EmailAccount,
+        folder: &EmailFolder,
+    ) -> Result<EmailSyncReport, EmailSyncError> {
+        let mailbox = session.select(&folder.name).await?;
+        let uid_validity = mailbox.uid_validity.map(i64::from);
+        let server_uid_next = mailbox.uid_next.map(i64::from);
+        let mut report = EmailSyncReport::default();
+
+        let mut uid_next = folder.uid_next;
+        if folder.uid_validity.is_some() && folder.uid_validity != uid_validity {
+            warn!(
+                "UIDVALIDITY of {} on {} changed, resyncing the folder",
+                folder.name, account.email_address
+            );
+            EmailMessage::clear_imap_folder(&self.pool, account.id, &folder.name).await?;
+            uid_next = 1;
+        }
+
+        if uid_next > 1 {
+            report.updated += self
+                .refresh_flags(session, account, folder, uid_next)
+                .await?;
+        }
+
+        let mut new_uids: Vec<u32> = if mailbox.exists == 0 {
+            Vec::new()
+        } else if uid_next <= 1 {
+            let since = (Utc::now() - chrono::Duration::days(BACKFILL_DAYS)).format("%d-%b-%Y");
+            session
+                .uid_search(format!("SINCE {}", since))
+                .await?
+                .into_iter()
+                .collect()
+        } else if server_uid_next.is_some_and(|next| next <= uid_next) {
+            Vec::new()
+        } else {
+            // `n:*` always matches the highest UID, even when it is below n
+            session
+                .uid_search(format!("UID {}:*", uid_next))
+                .await?
+                .into_iter()
+                .filter(|uid| i64::from(*uid) >= uid_next)
+                .collect()
+        };
+        new_uids.sort_unstable();
+
+        let mut highest_seen = uid_next - 1;
+        for chunk in new_uids.chunks(FETCH_BATCH) {
+            let fetches: Vec<Fetch> = session
+                .uid_fetch(uid_set(chunk), "(UID FLAGS INTERNALDATE BODY.PEEK[])")
+                .await?
+                .try_collect()
+                .await?;
+
+            for fetch in &fetches {
+                let Some(uid) = fetch.uid else { continue };
+                highest_seen = highest_seen.max(i64::from(uid));
+                match self
+                    .store_fetched(account, folder, uid_validity, fetch)
+                    .await
+                {
+                    Ok(StoreOutcome::Created) => report.fetched += 1,
+                    Ok(StoreOutcome::Updated) => report.updated += 1,
+                    Ok(StoreOutcome::Unchanged) => {}
+                    Err(e) => warn!(
+                        "Skipping UID {} in {} for {}: {}",
+                        uid, folder.name, account.email_address, e
+                    ),
+                }
+            }
+        }
+
+        let next = server_uid_next
+            .unwrap_or(highest_seen + 1)
+            .max(highest_seen + 1);
+        EmailFolder::update_sync_state(
+            &self.pool,
+            folder.id,
+            uid_validity,
+            next,
+            i64::from(mailbox.exists),
+        )
+        .await?;
+
+        debug!(
+            "Synced {} for {}: {} new, {} updated",
+            folder.name, account.email_address, report.fetched, report.updated
+        );
+        Ok(report)
+    }
+
+    /// Pull flags of messages synced earlier and release the ones that left
+    /// the folder
+    async fn refresh_flags(
+        &self,
+        session: &mut ImapSession,
+        account: &EmailAccount,
+        folder: &EmailFolder,
+        uid_next: i64,
+    ) -> Result<usize, EmailSyncError> {
+        let known = EmailMessage::find_by_imap_folder(&self.pool, account.id, &folder.name).await?;
+        if known.is_empty() {
+            return Ok(0);
+        }
+
+        let fetches: Vec<Fetch> = session
+            .uid_fetch(format!("1:{}", uid_next - 1), "(UID FLAGS)")
+            .await?
+            .try_collect()
+            .await?;
+        let on_server: HashMap<i64, MessageFlags> = fetches
+            .iter()
+            .filter_map(|fetch| Some((i64::from(fetch.uid?), MessageFlags::from_fetch(fetch))))
+            .collect();
+
+        let mut updated = 0;
+        for message in known {
+            let Some(uid) = message.imap_uid else {
+                continue;
+            };
+            match on_server.get(&uid) {
+                Some(flags) => {
+                    if (message.is_read == 1) != flags.seen
+                        || (message.is_starred == 1) != flags.flagged
+                    {
+                        EmailMessage::update(
+                            &self.pool,
+                            message.id,
+                            UpdateEmailMessage {
+                                is_read: Some(flags.seen),
+                                is_starred: Some(flags.flagged),
+                                ..Default::default()
+                            },
+                        )
+                        .await?;
+                        updated += 1;
+                    }
+                }
+                None => {
+                    EmailMessage::set_imap_location(&self.pool, message.id, None, None).await?;
+                    let labels: Vec<String> = parse_labels(message.labels.as_deref())
+                        .into_iter()
+                        .filter(|label| *label != folder.name)
+                        .collect();
+                    EmailMessage::update(
+                        &self.pool,
+                        message.id,
+                        UpdateEmailMessage {
+                            labels: Some(labels),
+                            // Leaving the inbox without showing up elsewhere means archived
+                            is_archived: (folder.role() == EmailFolderRole::Inbox).then_some(true),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                    updated += 1;
+                }
+            }
+        }
+
+        Ok(updated)
+    }
+
+    async fn store_fetched(
+        &self,
+        account: &EmailAccount,
+        folder: &EmailFolder,
+        uid_validity: Option<i64>,
+        fetch: &Fetch,
+    ) -> Result<StoreOutcome, EmailSyncError> {
+        let uid = i64::from(fetch.uid.unwrap_or_default());
+        let raw = fetch
+            .body()
+            .ok_or_else(|| EmailSyncError::InvalidMessage("empty body".into()))?;
+        let parsed = ParsedEmail::parse(raw)
+            .ok_or_else(|| EmailSyncError::InvalidMessage("unparseable message".into()))?;
+        let flags = MessageFlags::from_fetch(fetch);
+        let role = folder.role();
+
+        let provider_message_id = parsed.message_id.clone().unwrap_or_else(|| {
+            format!(
+                "{}:{}:{}",
+                folder.name,
+                uid_validity.unwrap_or_default(),
+                uid
+            )
+        });
+
+        if let Some(existing) =
+            EmailMessage::find_by_provider_message_id(&self.pool, account.id, &provider_message_id)
+                .await?
+        {
+            let mut labels = parse_labels(existing.labels.as_deref());
+            let new_label = !labels.contains(&folder.name);
+            if new_label {
+                labels.push(folder.name.clone());
+            }
+
+            if existing.imap_folder.is_some() {
+                if !new_label {
+                    return Ok(StoreOutcome::Unchanged);
+                }
+                EmailMessage::update(
+                    &self.pool,
+                    existing.id,
+                    UpdateEmailMessage {
+                        labels: Some(labels),
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                return Ok(StoreOutcome::Updated);
+            }
+
+            // Moved here from another folder, or the sent copy we stored ourselves
+            EmailMessage::set_imap_location(&self.pool, existing.id, Some(&folder.name), Some(uid))
+                .await?;
+            EmailMessage::update(
+                &self.pool,
+                existing.id,
+                UpdateEmailMessage {
+                    is_read: Some(flags.seen),
+                    is_starred: Some(flags.flagged),
+                    is_archived: Some(matches!(
+                        role,
+                        EmailFolderRole::Archive | EmailFolderRole::Other
+                    )),
+                    is_spam: Some(role == EmailFolderRole::Spam),
+                    is_trash: Some(role == EmailFolderRole::Trash),
+                    labels: Some(labels),
+                    ..Default::default()
+                },
+            )
+            .await?;
+            return Ok(StoreOutcome::Updated);
+        }
+
+        let thread_id = self
+            .resolve_thread_id(account.id, &parsed, &provider_message_id)
+            .await?;
+        let received_at = fetch
+            .internal_date()
+            .map(|date| date.with_timezone(&Utc))
+            .or(parsed.date)
+            .unwrap_or_else(Utc::now);
+        let is_sent = role == EmailFolderRole::Sent;
+
+        let message = EmailMessage::create(
+            &self.pool,
+            CreateEmailMessage {
+                email_account_id: account.id,
+                project_id: account.project_id,
+                provider_message_id,
+                thread_id: Some(thread_id),
+                from_address: parsed.from_address,
+                from_name: parsed.from_name,
+                to_addresses: parsed.to,
+                cc_addresses: (!parsed.cc.is_empty()).then_some(parsed.cc),
+                bcc_addresses: (!parsed.bcc.is_empty()).then_some(parsed.bcc),
+                reply_to: parsed.reply_to,
+                subject: parsed.subject,
+                snippet: parsed.body_text.as_deref().map(snippet),
+                body_text: parsed.body_text,
+                body_html: parsed.body_html,
+                has_attachments: !parsed.attachments.is_empty(),
+                attachments: (!parsed.attachments.is_empty())
+                    .then(|| serde_json::to_value(&parsed.attachments).unwrap_or_default()),
+                labels: Some(vec![folder.name.clone()]),
+                is_read: flags.seen || is_sent,
+                is_starred: flags.flagged,
+                is_draft: flags.draft || role == EmailFolderRole::Drafts,
+                is_sent,
+                is_archived: matches!(role, EmailFolderRole::Archive | EmailFolderRole::Other),
+                is_spam: role == EmailFolderRole::Spam,
+                is_trash: role == EmailFolderRole::Trash,
+                in_reply_to: parsed.in_reply_to,
+                references: (!parsed.references.is_empty()).then_some(parsed.references),
+                message_id_header: parsed.message_id,
+                imap_folder: Some(folder.name.clone()),
+                imap_uid: Some(uid),
+                received_at,
+                sent_at: is_sent.then_some(parsed.date.unwrap_or(received_at)),
+            },
+        )
+        .await?;
+        self.enrich_crm(account, &message).await;
+        if !is_sent {
+            self.check_sequences(&message).await;
+        }
+
+        Ok(StoreOutcome::Created)
+    }
+
+    /// Thread by the root of `References`; replies that only carry
+    /// `In-Reply-To` join their parent's thread when we have the parent
+    async fn resolve_thread_id(
+        &self,
+        account_id: Uuid,
+        parsed: &ParsedEmail,
+        provider_message_id: &str,
+    ) -> Result<String, EmailSyncError> {
+        if parsed.references.is_empty()
+            && let Some(parent_id) = &parsed.in_reply_to
+            && let Some(parent) = EmailMessage::find_by_message_id_headers(
+                &self.pool,
+                account_id,
+                std::slice::from_ref(parent_id),
+            )
+            .await?
+        {
+            if let Some(thread_id) = parent.thread_id.or(parent.message_id_header) {
+                return Ok(thread_id);
+            }
+        }
+        Ok(parsed
+            .thread_root()
+            .unwrap_or_else(|| provider_message_id.to_string()))
+    }
+
+    /// Send a message through the account's SMTP server and store the sent copy
+    pub async fn send(
+        &self,
+        account_id: Uuid,
+        email: OutgoingEmail,
+    ) -> Result<EmailMessage, EmailSyncError> {
+        if email.to.is_empty() {
+            return Err(EmailSyncError::InvalidMessage("no recipients".into()));
+        }
+        let account = EmailAccount::find_by_id(&self.pool, account_id).await?;
+        let settings = MailSettings::resolve(&self.pool, &account).await?;
+
+        let parent = match email.in_reply_to_message_id {
+            Some(id) => Some(EmailMessage::find_by_id(&self.pool, id).await?),
+            None => None,
+        };
+        let in_reply_to = parent.as_ref().and_then(|p| p.message_id_header.clone());
+        let mut references: Vec<String> = parent
+            .as_ref()
+            .and_then(|p| p.references.as_deref())
+            .and_then(|refs| serde_json::from_str(refs).ok())
+            .unwrap_or_default();
+        references.extend(in_reply_to.clone());
+
+        let domain = account
+            .email_address
+            .rsplit_once('@')
+            .map(|(_, domain)| domain.to_string())
+            .unwrap_or_else(|| "localhost".into());
+        let message_id = format!("{}@{}", Uuid::new_v4(), domain);
+
+        let from = Mailbox::new(
+            account.display_name.clone(),
+            parse_address(&account.email_address)?,
+        );
+        let mut builder = Message::builder()
+            .from(from)
+            .subject(email.subject.clone())
+            .message_id(Some(format!("<{}>", message_id)));
+        for to in &email.to {
+            builder = builder.to(parse_mailbox(to)?);
+        }
+        for cc in &email.cc {
+            builder = builder.cc(parse_mailbox(cc)?);
+        }
+        for bcc in &email.bcc {
+            builder = builder.bcc(parse_mailbox(bcc)?);
+        }
+        if let Some(parent_id) = &in_reply_to {
+            builder = builder.in_reply_to(format!("<{}>", parent_id));
+        }
+        if !references.is_empty() {
+            builder = builder.references(
+                references
+                    .iter()
+                    .map(|id| format!("<{}>", id))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            );
+        }
+        let message = match &email.body_html {
+            Some(html) => builder.multipart(MultiPart::alternative_plain_html(
+                email.body_text.clone(),
+                html.clone(),
+            )),
+            None => builder
+                .header(ContentType::TEXT_PLAIN)
+                .body(email.body_text.clone()),
+        }
+        .map_err(|e| EmailSyncError::InvalidMessage(e.to_string()))?;
+
+        smtp_transport(&settings)?.send(message).await?;
+
+        let thread_id = parent
+            .as_ref()
+            .and_then(|p| p.thread_id.clone())
+            .or_else(|| references.first().cloned())
+            .unwrap_or_else(|| message_id.clone());
+        let now = Utc::now();
+
+        let sent = EmailMessage::create(
+            &self.pool,
+            CreateEmailMessage {
+                email_account_id: account.id,
+                project_id: account.project_id,
+                provider_message_id: message_id.clone(),
+                thread_id: Some(thread_id),
+                from_address: account.email_address.clone(),
+                from_name: account.display_name.clone(),
+                to_addresses: email.to,
+                cc_addresses: (!email.cc.is_empty()).then_some(email.cc),
+                bcc_addresses: (!email.bcc.is_empty()).then_some(email.bcc),
+                reply_to: None,
+                subject: Some(email.subject),
+                snippet: Some(snippet(&email.body_text)),
+                body_text: Some(email.body_text),
+                body_html: email.body_html,
+                has_attachments: false,
+                attachments: None,
+                labels: None,
+                is_read: true,
+                is_starred: false,
+                is_draft: false,
+                is_sent: true,
+                is_archived: false,
+                is_spam: false,
+                is_trash: false,
+                in_reply_to,
+                references: (!references.is_empty()).then_some(references),
+                message_id_header: Some(message_id),
+                imap_folder: None,
+                imap_uid: None,
+                received_at: now,
+                sent_at: Some(now),
+            },
+        )
+        .await?;
+
+        if let Some(parent) = parent {
+            EmailMessage::update(
+                &self.pool,
+                parent.id,
+                UpdateEmailMessage {
+                    needs_response: Some(false),
+                    responded_at: Some(now),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        }
+        self.enrich_crm(&account, &sent).await;
+
+        Ok(sent)
+    }
+
+    /// File a new message on the CRM timeline; the message is already stored,
+    /// so a failure here is left to the enrichment sweep
+    async fn enrich_crm(&self, account: &EmailAccount, message: &EmailMessage) {
+        if let Err(e) = CrmEnrichmentService::new(self.pool.clone())
+            .ingest_email(message, account)
+            .await
+        {
+            warn!("Failed to enrich email {}: {}", message.id, e);
+        }
+    }
+
+    /// Stop sequences for contacts that replied or whose address bounced
+    async fn check_sequences(&self, message: &EmailMessage) {
+        if let Err(e) = EmailSequenceService::new(self.pool.clone())
+            .handle_inbound(message)
+            .await
+        {
+            warn!(
+                "Failed to check email {} against sequences: {}",
+                message.id, e
+            );
+        }
+    }
+
+    /// Start an INBOX watcher for every active account that has none
+    async fn ensure_idle_watchers(&self) {
+        let accounts = match EmailAccount::find_active(&self.pool).await {
+            Ok(accounts) => accounts,
+            Err(e) => {
+                error!("Failed to load email accounts for IDLE: {}", e);
+                return;
+            }
+        };
+
+        let mut watchers = self.idle_watchers.lock().await;
+        watchers.retain(|_, handle| !handle.is_finished());
+        for account in accounts.iter().filter(|a| is_syncable(a)) {
+            if watchers.contains_key(&account.id) {
+                continue;
+            }
+            let service = self.clone();
+            let account_id = account.id;
+            watchers.insert(
+                account_id,
+                tokio::spawn(async move { service.watch_inbox(account_id).await }),
+            );
+        }
+    }
+
+    async fn watch_inbox(self, account_id: Uuid) {
+        loop {
+            match self.idle_once(account_id).await {
+                Ok(IdleOutcome::NewMail) => {
+                    match self.sync_folders(account_id, Some(INBOX)).await {
+                        Ok(_) | Err(EmailSyncError::AlreadySyncing) => {}
+                        Err(e) => warn!("INBOX sync after IDLE failed for {}: {}", account_id, e),
+                    }
+                }
+                Ok(IdleOutcome::Timeout) => {}
+                Ok(IdleOutcome::Stop) => return,
+                Err(e) => {
+                    debug!("IDLE on {} failed, retrying: {}", account_id, e);
+                    sleep(IDLE_RETRY_DELAY).await;
+                }
+            }
+        }
+    }
+
+    /// Wait on INBOX until the server reports a change or the IDLE times out
+    async fn idle_once(&self, account_id: Uuid) -> Result<IdleOutcome, EmailSyncError> {
+        let account = match EmailAccount::find_by_id(&self.pool, account_id).await {
+            Ok(account) => account,
+            Err(EmailAccountError::NotFound) => return Ok(IdleOutcome::Stop),
+            Err(e) => return Err(e.into()),
+        };
+        if !is_syncable(&account) {
+            return Ok(IdleOutcome::Stop);
+        }
+
+        let settings = MailSettings::resolve(&self.pool, &account).await?;
+        let mut session = connect_imap(&settings).await?;
+        if !session.capabilities().await?.has_str("IDLE") {
+            debug!(
+                "{} does not support IDLE, relying on polling",
+                settings.imap_host
+            );
+            let _ = session.logout().await;
+            return Ok(IdleOutcome::Stop);
+        }
+        session.select(INBOX).await?;
+
+        let mut idle = session.idle();
+        idle.init().await?;
+        let (wait, _interrupt) = idle.wait_with_timeout(IDLE_TIMEOUT);
+        let response = wait.await?;
+        let mut session = idle.done().await?;
+        let _ = session.logout().await;
+
+        Ok(match response {
+            IdleResponse::NewData(_) => IdleOutcome::NewMail,
+            _ => IdleOutcome::Timeout,
+        })
+    }
+}
+
+enum IdleOutcome {
+    NewMail,
+    Timeout,
+    Stop,
+}
+
+enum StoreOutcome {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+/// Removes the account from [`SYNCS_IN_FLIGHT`] when the sync ends
+struct SyncGuard(Uuid);
+
+impl SyncGuard {
+    fn acquire(account_id: Uuid) -> Result<Self, EmailSyncError> {
+        let mut in_flight = SYNCS_IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+        if !in_flight.insert(account_id) {
+            return Err(EmailSyncError::AlreadySyncing);
+        }
+        Ok(Self(account_id))
+    }
+}
+
+impl Drop for SyncGuard {
+    fn drop(&mut self) {
+        SYNCS_IN_FLIGHT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.0);
+    }
+}
+
+enum MailAuth {
+    Password {
+        username: String,
+        password: String,
+    },
+    OAuth2 {
+        username: String,
+        access_token: String,
+    },
+}
+
+/// Where and how to reach an account's IMAP and SMTP servers
+struct MailSettings {
+    imap_host: String,
+    imap_port: u16,
+    smtp_host: String,
+    smtp_port: u16,
+    use_ssl: bool,
+    auth: MailAuth,
+}
+
+impl MailSettings {
+    async fn resolve(pool: &SqlitePool, account: &EmailAccount) -> Result<Self, EmailSyncError> {
+        let provider: EmailProvider = account
+            .provider
+            .parse()
+            .map_err(|_| EmailSyncError::Unsupported(account.provider.clone()))?;
+        let use_ssl = account.use_ssl.unwrap_or(1) == 1;
+        let username = account
+            .imap_username
+            .clone()
+            .unwrap_or_else(|| account.email_address.clone());
+
+        let (default_imap, default_smtp, auth) = match provider {
+            EmailProvider::ImapCustom => {
+                let password = account
+                    .imap_password
+                    .clone()
+                    .ok_or(EmailSyncError::MissingSettings("an IMAP password"))?;
+                (None, None, MailAuth::Password { username, password })
+            }
+            EmailProvider::Gmail => {
+                let access_token = if account.needs_token_refresh() {
+                    refresh_gmail_token(pool, account).await?
+                } else {
+                    account
+                        .access_token
+                        .clone()
+                        .ok_or(EmailSyncError::MissingSettings("an access token"))?
+                };
+                (
+                    Some("imap.gmail.com"),
+                    Some("smtp.gmail.com"),
+                    MailAuth::OAuth2 {
+                        username,
+                        access_token,
+                    },
+                )
+            }
+            EmailProvider::Zoho => {
+                return Err(EmailSyncError::Unsupported(account.provider.clone()));
+            }
+        };
+
+        let imap_host = account
+            .imap_host
+            .clone()
+            .or(default_imap.map(str::to_string))
+            .ok_or(EmailSyncError::MissingSettings("an IMAP host"))?;
+        let smtp_host = account
+            .smtp_host
+            .clone()
+            .or(default_smtp.map(str::to_string))
+            .unwrap_or_else(|| imap_host.clone());
+
+        Ok(Self {
+            imap_port: port_or(account.imap_port, if use_ssl { 993 } else { 143 }),
+            smtp_port: port_or(account.smtp_port, if use_ssl { 465 } else { 25 }),
+            imap_host,
+            smtp_host,
+            use_ssl,
+            auth,
+        })
+    }
+}
+
+fn port_or(port: Option<i32>, default: u16) -> u16 {
+    port.and_then(|p| u16::try_from(p).ok()).unwrap_or(default)
+}
+
+/// Exchange the stored refresh token for a new Gmail access token
+async fn refresh_gmail_token(
+    pool: &SqlitePool,
+    account: &EmailAccount,
+) -> Result<String, EmailSyncError> {
+    let refresh_token = account
+        .refresh_token
+        .as_deref()
+        .ok_or(EmailSyncError::MissingSettings("a refresh token"))?;
+    let client_id = std::env::var("GOOGLE_CLIENT_ID")
+        .map_err(|_| EmailSyncError::Auth("GOOGLE_CLIENT_ID not configured".into()))?;
+    let client_secret = std::env::var("GOOGLE_CLIENT_SECRET")
+        .map_err(|_| EmailSyncError::Auth("GOOGLE_CLIENT_SECRET not configured".into()))?;
+
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        access_token: String,
+        expires_in: Option<i64>,
+    }
+
+    let response = reqwest::Client::new()
+        .post("https://oauth2.googleapis.com/token")
+        .form(&[
+            ("client_id", client_id.as_str()),
+            ("client_secret", client_secret.as_str()),
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token"),
+        ])
+        .send()
+        .await
+        .map_err(|e| EmailSyncError::Auth(format!("token refresh failed: {}", e)))?;
+    if !response.status().is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(EmailSyncError::Auth(format!(
+            "token refresh rejected: {}",
+            body
+        )));
+    }
+    let tokens: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| EmailSyncError::Auth(format!("invalid token response: {}", e)))?;
+
+    let expires_at = tokens
+        .expires_in
+        .map(|secs| Utc::now() + chrono::Duration::seconds(secs));
+    EmailAccount::update_tokens(pool, account.id, &tokens.access_token, None, expires_at).await?;
+    Ok(tokens.access_token)
+}
+
+trait ImapIo: AsyncRead + AsyncWrite + Unpin + Send + std::fmt::Debug {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + std::fmt::Debug> ImapIo for T {}
+
+type ImapSession = async_imap::Session<Box<dyn ImapIo>>;
+
+struct XOAuth2<'a> {
+    username: &'a str,
+    access_token: &'a str,
+}
+
+impl Authenticator for XOAuth2<'_> {
+    type Response = String;
+
+    fn process(&mut self, _challenge: &[u8]) -> Self::Response {
+        format!(
+            "user={}\x01auth=Bearer {}\x01\x01",
+            self.username, self.access_token
+        )
+    }
+}
+
+async fn connect_imap(settings: &MailSettings) -> Result<ImapSession, EmailSyncError> {
+    let connect_error = |reason: String| EmailSyncError::Connect {
+        host: format!("{}:{}", settings.imap_host, settings.imap_port),
+        reason,
+    };
+
+    let tcp = timeout(
+        CONNECT_TIMEOUT,
+        TcpStream::connect((settings.imap_host.as_str(), settings.imap_port)),
+    )
+    .await
+    .map_err(|_| connect_error("timed out".into()))?
+    .map_err(|e| connect_error(e.to_string()))?;
+
+    let stream: Box<dyn ImapIo> = if settings.use_ssl {
+        let connector = tokio_native_tls::native_tls::TlsConnector::new()
+            .map_err(|e| connect_error(e.to_string()))?;
+        let tls = tokio_native_tls::TlsConnector::from(connector)
+            .connect(&settings.imap_host, tcp)
+            .await
+            .map_err(|e| connect_error(e.to_string()))?;
+        Box::new(tls)
+    } else {
+        Box::new(tcp)
+    };
+
+    let mut client = async_imap::Client::new(stream);
+    let _greeting = client.read_response().await;
+
+    let session = match &settings.auth {
+        MailAuth::Password { username, password } => client.login(username, password).await,
+        MailAuth::OAuth2 {
+            username,
+            access_token,
+        } => {
+            client
+                .authenticate(
+                    "XOAUTH2",
+                    XOAuth2 {
+                        username,
+                        access_token,
+                    },
+                )
+                .await
+        }
+    };
+    session.map_err(|(e, _)| EmailSyncError::Auth(e.to_string()))
+}
+
+fn smtp_transport(
+    settings: &MailSettings,
+) -> Result<AsyncSmtpTransport<Tokio1Executor>, EmailSyncError> {
+    let host = settings.smtp_host.as_str();
+    let builder = if settings.use_ssl && settings.smtp_port == 465 {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(host)?
+    } else if settings.use_ssl {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+    };
+
+    let builder = builder.port(settings.smtp_port).timeout(Some(SMTP_TIMEOUT));
+    let builder = match &settings.auth {
+        MailAuth::Password { username, password } => {
+            builder.credentials(Credentials::new(username.clone(), password.clone()))
+        }
+        MailAuth::OAuth2 {
+            username,
+            access_token,
+        } => builder
+            .credentials(Credentials::new(username.clone(), access_token.clone()))
+            .authentication(vec![Mechanism::Xoauth2]),
+    };
+    Ok(builder.build())
+}
+
+/// Selectable folders with their role; virtual "all mail" and "starred"
+/// views are skipped because their messages already live in real folders
+async fn list_folders(
+    session: &mut ImapSession,
+) -> Result<Vec<(String, EmailFolderRole)>, EmailSyncError> {
+    let names: Vec<_> = session
+        .list(Some(""), Some("*"))
+        .await?
+        .try_collect()
+        .await?;
+
+    Ok(names
+        .iter()
+        .filter(|name| {
+            !name.attributes().iter().any(|attr| {
+                matches!(
+                    attr,
+                    NameAttribute::NoSelect | NameAttribute::All | NameAttribute::Flagged
+                )
+            })
+        })
+        .map(|name| {
+            let special_use = name.attributes().iter().find_map(|attr| match attr {
+                NameAttribute::Sent => Some(EmailFolderRole::Sent),
+                NameAttribute::Drafts => Some(EmailFolderRole::Drafts),
+                NameAttribute::Archive => Some(EmailFolderRole::Archive),
+                NameAttribute::Junk => Some(EmailFolderRole::Spam),
+                NameAttribute::Trash => Some(EmailFolderRole::Trash),
+                _ => None,
+            });
+            let role = special_use.unwrap_or_else(|| folder_role_from_name(name.name()));
+            (name.name().to_string(), role)
+        })
+        .collect())
+}
+
+/// Guess a folder's role from its name for servers without SPECIAL-USE
+fn folder_role_from_name(name: &str) -> EmailFolderRole {
+    if name.eq_ignore_ascii_case(INBOX) {
+        return EmailFolderRole::Inbox;
+    }
+    let leaf = name
+        .rsplit(['/', '.'])
+        .next()
+        .unwrap_or(name)
+        .to_lowercase();
+    match leaf.as_str() {
+        "sent" | "sent mail" | "sent items" | "sent messages" => EmailFolderRole::Sent,
+        "drafts" | "draft" => EmailFolderRole::Drafts,
+        "archive" | "archives" => EmailFolderRole::Archive,
+        "spam" | "junk" | "junk email" | "bulk mail" => EmailFolderRole::Spam,
+        "trash" | "deleted items" | "deleted messages" | "bin" => EmailFolderRole::Trash,
+        _ => EmailFolderRole::Other,
+    }
+}
+
+/// Compact UID set, e.g. `1:3,7,9:10`
+fn uid_set(uids: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &uid in uids {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == uid => *end = uid,
+            _ => ranges.push((uid, uid)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}:{}", start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MessageFlags {
+    seen: bool,
+    flagged: bool,
+    draft: bool,
+}
+
+impl MessageFlags {
+    fn from_fetch(fetch: &Fetch) -> Self {
+        let mut flags = Self::default();
+        for flag in fetch.flags() {
+            match flag {
+                Flag::Seen => flags.seen = true,
+                Flag::Flagged => flags.flagged = true,
+                Flag::Draft => flags.draft = true,
+                _ => {}
+            }
+        }
+        flags
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AttachmentInfo {
+    name: Option<String>,
+    size: usize,
+    content_type: Option<String>,
+}
+
+/// The parts of an RFC 5322 message stored in `email_messages`
+#[derive(Debug, Default)]
+struct ParsedEmail {
+    message_id: Option<String>,
+    in_reply_to: Option<String>,
+    /// Oldest first, as sent in the `References` header
+    references: Vec<String>,
+    from_address: String,
+    from_name: Option<String>,
+    to: Vec<String>,
+    cc: Vec<String>,
+    bcc: Vec<String>,
+    reply_to: Option<String>,
+    subject: Option<String>,
+    body_text: Option<String>,
+    body_html: Option<String>,
+    attachments: Vec<AttachmentInfo>,
+    date: Option<DateTime<Utc>>,
+}
+
+impl ParsedEmail {
+    fn parse(raw: &[u8]) -> Option<Self> {
+        let message = MessageParser::default().parse(raw)?;
+        let from = message.from().and_then(|from| from.first());
+
+        Some(Self {
+            message_id: message
+                .message_id()
+                .and_then(|id| message_ids(id).into_iter().next()),
+            in_reply_to: header_ids(message.in_reply_to()).into_iter().next(),
+            references: header_ids(message.references()),
+            from_address: from
+                .and_then(|addr| addr.address())
+                .unwrap_or_default()
+                .to_string(),
+            from_name: from.and_then(|addr| addr.name()).map(str::to_string),
+            to: addresses(message.to()),
+            cc: addresses(message.cc()),
+            bcc: addresses(message.bcc()),
+            reply_to: addresses(message.reply_to()).into_iter().next(),
+            subject: message.subject().map(str::to_string),
+            body_text: message.body_text(0).map(|text| text.into_owned()),
+            body_html: message.html_part(0).and_then(|part| match &part.body {
+                PartType::Html(html) => Some(html.to_string()),
+                _ => None,
+            }),
+            attachments: message
+                .attachments()
+                .map(|part| AttachmentInfo {
+                    name: part.attachment_name().map(str::to_string),
+                    size: part.len(),
+                    content_type: part.content_type().map(|ct| match ct.subtype() {
+                        Some(subtype) => format!("{}/{}", ct.ctype(), subtype),
+                        None => ct.ctype().to_string(),
+                    }),
+                })
+                .collect(),
+            date: message
+                .date()
+                .and_then(|date| DateTime::from_timestamp(date.to_timestamp(), 0)),
+        })
+    }
+
+    /// `Message-ID` of the message that started the conversation, if known
+    fn thread_root(&self) -> Option<String> {
+        self.references
+            .first()
+            .or(self.in_reply_to.as_ref())
+            .or(self.message_id.as_ref())
+            .cloned()
+    }
+}
+
+fn header_ids(value: &HeaderValue<'_>) -> Vec<String> {
+    match value {
+        HeaderValue::Text(ids) => message_ids(ids),
+        HeaderValue::TextList(list) => list.iter().flat_map(|ids| message_ids(ids)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Split a header holding one or more `<id>` tokens into bare ids
+fn message_ids(value: &str) -> Vec<String> {
+    value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|id| id.trim_matches(|c| c == '<' || c == '>'))
+        .filter(|id| !id.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn addresses(address: Option<&Address<'_>>) -> Vec<String> {
+    address
+        .map(|address| {
+            address
+                .iter()
+                .filter_map(|addr| addr.address())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn parse_labels(labels: Option<&str>) -> Vec<String> {
+    labels
+        .and_then(|labels| serde_json::from_str(labels).ok())
+        .unwrap_or_default()
+}
+
+fn snippet(body: &str) -> String {
+    body.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SNIPPET_LEN)
+        .collect()
+}
+
+fn parse_address(address: &str) -> Result<lettre::Address, EmailSyncError> {
+    address
+        .parse()
+        .map_err(|_| EmailSyncError::InvalidMessage(format!("invalid address {}", address)))
+}
+
+fn parse_mailbox(mailbox: &str) -> Result<Mailbox, EmailSyncError> {
+    mailbox
+        .parse()
+        .map_err(|_| EmailSyncError::InvalidMessage(format!("invalid recipient {}", mailbox)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use db::models::{
+        email_account::CreateEmailAccount,
+        email_message::EmailMessageFilter,
+        project::{CreateProject, Project},
+    };
+    use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// Raw messages delivered to each address; a message's UID is its
+    /// position in the list plus one
+    type Mailboxes = Arc<Mutex<HashMap<String, Vec<Vec<u8>>>>>;
+
+    /// Just enough SMTP for lettre: PLAIN auth and delivery into `mailboxes`
+    async fn fake_smtp(listener: TcpListener, mailboxes: Mailboxes) {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mailboxes = mailboxes.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read);
+                let mut recipients = Vec::new();
+                write.write_all(b"220 fake ESMTP\r\n").await.unwrap();
+                let mut line = String::new();
+                while lines.read_line(&mut line).await.unwrap_or(0) > 0 {
+                    let command = line.trim_end().to_string();
+                    line.clear();
+                    let verb = command
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_ascii_uppercase();
+                    let reply = match verb.as_str() {
+                        "EHLO" => "250-fake\r\n250-AUTH PLAIN\r\n250 8BITMIME\r\n",
+                        "AUTH" => "235 2.7.0 Authenticated\r\n",
+                        "RCPT" => {
+                            let address = command
+                                .split_once('<')
+                                .and_then(|(_, rest)| rest.split_once('>'))
+                                .map(|(address, _)| address.to_string())
+                                .unwrap_or_default();
+                            recipients.push(address);
+                            "250 OK\r\n"
+                        }
+                        "DATA" => {
+                            write.write_all(b"354 Go ahead\r\n").await.unwrap();
+                            let mut raw = Vec::new();
+                            loop {
+                                let mut data = Vec::new();
+                                lines.read_until(b'\n', &mut data).await.unwrap();
+                                if data == b".\r\n" || data.is_empty() {
+                                    break;
+                                }
+                                let data = data.strip_prefix(b".").unwrap_or(&data);
+                                raw.extend_from_slice(data);
+                            }
+                            let mut mailboxes = mailboxes.lock().unwrap();
+                            for recipient in recipients.drain(..) {
+                                mailboxes.entry(recipient).or_default().push(raw.clone());
+                            }
+                            "250 OK\r\n"
+                        }
+                        "QUIT" => {
+                            write.write_all(b"221 Bye\r\n").await.unwrap();
+                            return;
+                        }
+                        _ => "250 OK\r\n",
+                    };
+                    write.write_all(reply.as_bytes()).await.unwrap();
+                }
+            });
+        }
+    }
+
+    /// UIDs in an IMAP sequence set like `1:3,7,9:*`
+    fn uids_in(set: &str, highest: u32) -> Vec<u32> {
+        set.split(',')
+            .flat_map(|range| {
+                let bound = |value: &str| {
+                    if value == "*" {
+                        highest
+                    } else {
+                        value.parse().unwrap()
+                    }
+                };
+                let (from, to) = match range.split_once(':') {
+                    Some((from, to)) => (bound(from), bound(to)),
+                    None => (bound(range), bound(range)),
+                };
+                from.min(to)..=from.max(to)
+            })
+            .collect()
+    }
+
+    /// Just enough IMAP for a sync: LOGIN, LIST, SELECT, UID SEARCH, UID
+    /// FETCH and LOGOUT over the INBOX of the logged-in address
+    async fn fake_imap(listener: TcpListener, mailboxes: Mailboxes) {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mailboxes = mailboxes.clone();
+            tokio::spawn(async move {
+                let (read, mut write) = stream.into_split();
+                let mut lines = BufReader::new(read);
+                let mut user = String::new();
+                write
+                    .write_all(b"* OK [CAPABILITY IMAP4rev1] fake ready\r\n")
+                    .await
+                    .unwrap();
+                let mut line = String::new();
+                while lines.read_line(&mut line).await.unwrap_or(0) > 0 {
+                    let request = line.trim_end().to_string();
+                    line.clear();
+                    let (tag, command) = request.split_once(' ').unwrap_or((&request, ""));
+                    let mut words = command.split_whitespace();
+                    let mut verb = words.next().unwrap_or_default().to_ascii_uppercase();
+                    if verb == "UID" {
+                        verb = format!(
+                            "UID {}",
+                            words.next().unwrap_or_default().to_ascii_uppercase()
+                        );
+                    }
+                    let inbox = mailboxes
+                        .lock()
+                        .unwrap()
+                        .get(&user)
+                        .cloned()
+                        .unwrap_or_default();
+                    let highest = inbox.len() as u32;
+                    let mut out = Vec::new();
+                    match verb.as_str() {
+                        "LOGIN" => {
+                            user = command.split('"').nth(1).unwrap_or_default().to_string();
+                        }
+                        "LIST" => {
+                            out.extend_from_slice(b"* LIST (\\HasNoChildren) \"/\" \"INBOX\"\r\n")
+                        }
+                        "SELECT" | "EXAMINE" => out.extend_from_slice(
+                            format!(
+                                "* {highest} EXISTS\r\n* 0 RECENT\r\n* FLAGS (\\Seen \\Flagged)\r\n\
+                                 * OK [UIDVALIDITY 7] UIDs valid\r\n\
+                                 * OK [UIDNEXT {}] Predicted next UID\r\n",
+                                highest + 1
+                            )
+                            .as_bytes(),
+                        ),
+                        "UID SEARCH" => {
+                            let criteria: Vec<&str> = words.collect();
+                            let uids: Vec<u32> = match criteria.as_slice() {
+                                ["UID", set] if highest > 0 => uids_in(set, highest),
+                                ["SINCE", _] => (1..=highest).collect(),
+                                _ => Vec::new(),
+                            };
+                            let mut response = String::from("* SEARCH");
+                            for uid in uids {
+                                response.push_str(&format!(" {uid}"));
+                            }
+                            out.extend_from_slice(format!("{response}\r\n").as_bytes());
+                        }
+                        "UID FETCH" => {
+                            let set = words.next().unwrap_or_default();
+                            let with_body = command.contains("BODY.PEEK[]");
+                            for uid in uids_in(set, highest) {
+                                let Some(raw) = inbox.get(uid as usize - 1) else {
+                                    continue;
+                                };
+                                out.extend_from_slice(
+                                    format!("* {uid} FETCH (UID {uid} FLAGS ()").as_bytes(),
+                                );
+                                if with_body {
+                                    out.extend_from_slice(
+                                        format!(
+                                            " INTERNALDATE \"17-Feb-2026 10:00:00 +0000\" BODY[] {{{}}}\r\n",
+                                            raw.len()
+                                        )
+                                        .as_bytes(),
+                                    );
+                                    out.extend_from_slice(raw);
+                                }
+                                out.extend_from_slice(b")\r\n");
+                            }
+                        }
+                        "LOGOUT" => out.extend_from_slice(b"* BYE fake closing\r\n"),
+                        _ => {}
+                    }
+                    out.extend_from_slice(format!("{tag} OK {verb} completed\r\n").as_bytes());
+                    write.write_all(&out).await.unwrap();
+                    if verb == "LOGOUT" {
+                        return;
+                    }
+                }
+            });
+        }
+    }
+
+    /// Fake SMTP and IMAP servers sharing one set of mailboxes, returning
+    /// their (SMTP, IMAP) ports
+    async fn fake_mail_server() -> (u16, u16) {
+        let mailboxes = Mailboxes::default();
+        let smtp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let imap = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ports = (
+            smtp.local_addr().unwrap().port(),
+            imap.local_addr().unwrap().port(),
+        );
+        tokio::spawn(fake_smtp(smtp, mailboxes.clone()));
+        tokio::spawn(fake_imap(imap, mailboxes));
+        ports
+    }
+
+    async fn setup_pool() -> SqlitePool {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .expect("invalid sqlite config")
+            .foreign_keys(true);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .expect("failed to open sqlite memory db");
+        sqlx::migrate!("../db/migrations")
+            .run(&pool)
+            .await
+            .expect("failed to run migrations");
+        pool
+    }
+
+    async fn create_account(
+        pool: &SqlitePool,
+        project_id: Uuid,
+        address: &str,
+        (smtp_port, imap_port): (u16, u16),
+    ) -> EmailAccount {
+        EmailAccount::create(
+            pool,
+            CreateEmailAccount {
+                project_id,
+                provider: EmailProvider::ImapCustom,
+                account_type: None,
+                email_address: address.into(),
+                display_name: None,
+                avatar_url: None,
+                access_token: None,
+                refresh_token: None,
+                token_expires_at: None,
+                imap_host: Some("127.0.0.1".into()),
+                imap_port: Some(imap_port.into()),
+                smtp_host: Some("127.0.0.1".into()),
+                smtp_port: Some(smtp_port.into()),
+                use_ssl: Some(false),
+                imap_username: None,
+                imap_password: Some("secret".into()),
+                granted_scopes: None,
+                metadata: None,
+            },
+        )
+        .await
+        .expect("failed to create email account")
+    }
+
+    async fn inbox(pool: &SqlitePool, account_id: Uuid) -> Vec<EmailMessage> {
+        EmailMessage::find_by_filter(
+            pool,
+            EmailMessageFilter {
+                project_id: None,
+                email_account_id: Some(account_id),
+                is_read: None,
+                is_starred: None,
+                is_archived: None,
+                is_spam: None,
+                is_trash: None,
+                needs_response: None,
+                crm_contact_id: None,
+                search: None,
+                limit: None,
+                offset: None,
+            },
+        )
+        .await
+        .expect("failed to list messages")
+        .into_iter()
+        .filter(|m| m.is_sent == 0)
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn sends_syncs_and_threads_replies() {
+        let ports = fake_mail_server().await;
+        let pool = setup_pool().await;
+        let project_id = Uuid::new_v4();
+        Project::create(
+            &pool,
+            &CreateProject {
+                name: "Email sync".into(),
+                git_repo_path: format!("/tmp/{}", project_id),
+                use_existing_repo: true,
+                setup_script: None,
+                dev_script: None,
+                cleanup_script: None,
+                copy_files: None,
+            },
+            project_id,
+        )
+        .await
+        .expect("failed to create project");
+
+        let alice = create_account(&pool, project_id, "alice@localhost", ports).await;
+        let bob = create_account(&pool, project_id, "bob@localhost", ports).await;
+        let service = EmailSyncService::new(pool.clone());
+
+        let sent = service
+            .send(
+                alice.id,
+                OutgoingEmail {
+                    to: vec!["bob@localhost".into()],
+                    cc: vec![],
+                    bcc: vec![],
+                    subject: "Launch plan".into(),
+                    body_text: "Does Friday work?".into(),
+                    body_html: None,
+                    in_reply_to_message_id: None,
+                },
+            )
+            .await
+            .expect("failed to send");
+        assert_eq!(sent.is_sent, 1);
+
+        let report = service.sync_account(bob.id).await.expect("bob sync failed");
+        assert_eq!(report.fetched, 1);
+        let received = inbox(&pool, bob.id).await;
+        assert_eq!(received.len(), 1);
+        let question = &received[0];
+        assert_eq!(question.subject.as_deref(), Some("Launch plan"));
+        assert_eq!(question.from_address, "alice@localhost");
+        assert_eq!(question.message_id_header, sent.message_id_header);
+        assert_eq!(question.thread_id, sent.thread_id);
+        assert_eq!(question.imap_folder.as_deref(), Some("INBOX"));
+        assert_eq!(question.imap_uid, Some(1));
+
+        // Nothing new on the server, so the incremental pass fetches nothing
+        let report = service
+            .sync_account(bob.id)
+            .await
+            .expect("bob resync failed");
+        assert_eq!(report.fetched, 0);
+        let folder = EmailFolder::find_by_name(&pool, bob.id, "INBOX")
+            .await
+            .expect("INBOX state missing");
+        assert_eq!(folder.uid_validity, Some(7));
+        assert_eq!(folder.uid_next, 2);
+
+        service
+            .send(
+                bob.id,
+                OutgoingEmail {
+                    to: vec!["alice@localhost".into()],
+                    cc: vec![],
+                    bcc: vec![],
+                    subject: "Re: Launch plan".into(),
+                    body_text: "Friday works.".into(),
+                    body_html: None,
+                    in_reply_to_message_id: Some(question.id),
+                },
+            )
+            .await
+            .expect("failed to reply");
+
+        service
+            .sync_account(alice.id)
+            .await
+            .expect("alice sync failed");
+        let replies = inbox(&pool, alice.id).await;
+        assert_eq!(replies.len(), 1);
+        assert_eq!(replies[0].in_reply_to, sent.message_id_header);
+        assert_eq!(replies[0].thread_id, sent.thread_id);
+
+        let thread = EmailMessage::find_by_thread(&pool, sent.thread_id.as_deref().unwrap())
+            .await
+            .expect("thread lookup failed");
+        // Question and reply in both mailboxes
+        assert_eq!(thread.len(), 4);
+    }
+
+    const REPLY: &str = "Message-ID: <reply-2@example.com>\r\n\
+        In-Reply-To: <root-1@example.com>\r\n\
+        References: <root-1@example.com>\r\n \
+        <middle-1@example.com>\r\n\
+        From: Ada Lovelace <ada@example.com>\r\n\
+        To: ops@example.com, Bob <bob@example.com>\r\n\
+        Subject: Re: Launch plan\r\n\
+        Date: Tue, 17 Feb 2026 10:00:00 +0000\r\n\
+        \r\n\
+        Sounds good,\r\n  see you   there.\r\n";
+
+    #[test]
+    fn parses_headers_and_threads_by_reference_root() {
+        let parsed = ParsedEmail::parse(REPLY.as_bytes()).expect("message should parse");
+
+        assert_eq!(parsed.message_id.as_deref(), Some("reply-2@example.com"));
+        assert_eq!(parsed.in_reply_to.as_deref(), Some("root-1@example.com"));
+        assert_eq!(
+            parsed.references,
+            vec!["root-1@example.com", "middle-1@example.com"]
+        );
+        assert_eq!(parsed.from_address, "ada@example.com");
+        assert_eq!(parsed.from_name.as_deref(), Some("Ada Lovelace"));
+        assert_eq!(parsed.to, vec!["ops@example.com", "bob@example.com"]);
+        assert_eq!(parsed.subject.as_deref(), Some("Re: Launch plan"));
+        assert!(parsed.date.is_some());
+        assert_eq!(parsed.thread_root().as_deref(), Some("root-1@example.com"));
+    }
+
+    #[test]
+    fn a_new_conversation_is_its_own_thread() {
+        let raw = "Message-ID: <root-1@example.com>\r\nFrom: ops@example.com\r\n\
+            To: ada@example.com\r\nSubject: Launch plan\r\n\r\nHi\r\n";
+        let parsed = ParsedEmail::parse(raw.as_bytes()).expect("message should parse");
+
+        assert!(parsed.references.is_empty());
+        assert_eq!(parsed.thread_root().as_deref(), Some("root-1@example.com"));
+    }
+
+    #[test]
+    fn folder_roles_fall_back_to_names() {
+        assert_eq!(folder_role_from_name("INBOX"), EmailFolderRole::Inbox);
+        assert_eq!(folder_role_from_name("Inbox"), EmailFolderRole::Inbox);
+        assert_eq!(
+            folder_role_from_name("[Gmail]/Sent Mail"),
+            EmailFolderRole::Sent
+        );
+        assert_eq!(folder_role_from_name("INBOX.Junk"), EmailFolderRole::Spam);
+        assert_eq!(
+            folder_role_from_name("Deleted Items"),
+            EmailFolderRole::Trash
+        );
+        assert_eq!(
+            folder_role_from_name("Clients/Acme"),
+            EmailFolderRole::Other
+        );
+    }
+
+    #[test]
+    fn uid_sets_collapse_consecutive_runs() {
+        assert_eq!(uid_set(&[1, 2, 3, 7, 9, 10]), "1:3,7,9:10");
+        assert_eq!(uid_set(&[42]), "42");
+    }
+
+    #[test]
+    fn snippets_collapse_whitespace() {
+        assert_eq!(
+            snippet("Sounds good,\r\n  see you   there."),
+            "Sounds good, see you there."
+        );
+        assert_eq!(snippet(&"a".repeat(500)).len(), SNIPPET_LEN);
+    }
+}
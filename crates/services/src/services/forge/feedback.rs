@@ -0,0 +1,160 @@
+//! Reviewer and CI feedback on an open PR/MR, and the follow-up prompt built from it
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Utc};
+use db::models::pr_feedback::{PrFeedbackKey, PrFeedbackKind};
+use serde::Serialize;
+use ts_rs::TS;
+
+/// An inline or general comment left on the PR diff
+#[derive(Debug, Clone, Serialize, TS)]
+pub struct ReviewComment {
+    pub id: String,
+    pub author: String,
+    pub body: String,
+    pub path: Option<String>,
+    pub line: Option<i64>,
+    pub url: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// A review that asked for changes before the PR can be merged
+#[derive(Debug, Clone, Serialize, TS)]
+pub struct ChangeRequest {
+    pub id: String,
+    pub author: String,
+    pub body: String,
+    pub url: Option<String>,
+}
+
+/// A CI check run, status or pipeline job that failed on the PR head
+#[derive(Debug, Clone, Serialize, TS)]
+pub struct FailedCheck {
+    pub id: String,
+    pub name: String,
+    /// Forge-reported outcome, e.g. `failure`, `timed_out`, `error`
+    pub conclusion: String,
+    pub summary: Option<String>,
+    pub details_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+pub struct PrFeedback {
+    pub head_sha: Option<String>,
+    pub change_requests: Vec<ChangeRequest>,
+    pub review_comments: Vec<ReviewComment>,
+    pub failed_checks: Vec<FailedCheck>,
+}
+
+impl PrFeedback {
+    pub fn is_empty(&self) -> bool {
+        self.change_requests.is_empty()
+            && self.review_comments.is_empty()
+            && self.failed_checks.is_empty()
+    }
+
+    pub fn keys(&self) -> Vec<PrFeedbackKey> {
+        let key = |kind, id: &String| PrFeedbackKey {
+            kind,
+            external_id: id.clone(),
+        };
+        self.change_requests
+            .iter()
+            .map(|c| key(PrFeedbackKind::ChangeRequest, &c.id))
+            .chain(
+                self.review_comments
+                    .iter()
+                    .map(|c| key(PrFeedbackKind::ReviewComment, &c.id)),
+            )
+            .chain(
+                self.failed_checks
+                    .iter()
+                    .map(|c| key(PrFeedbackKind::FailedCheck, &c.id)),
+            )
+            .collect()
+    }
+
+    /// Keep only the items whose keys are in `keys`
+    pub fn retain_keys(&mut self, keys: &[PrFeedbackKey]) {
+        let has = |kind, id: &String| keys.iter().any(|k| k.kind == kind && k.external_id == *id);
+        self.change_requests
+            .retain(|c| has(PrFeedbackKind::ChangeRequest, &c.id));
+        self.review_comments
+            .retain(|c| has(PrFeedbackKind::ReviewComment, &c.id));
+        self.failed_checks
+            .retain(|c| has(PrFeedbackKind::FailedCheck, &c.id));
+    }
+
+    /// Structured follow-up prompt asking the agent to address the feedback on
+    /// `branch`
+    pub fn to_follow_up_prompt(&self, pr_number: i64, pr_url: &str, branch: &str) -> String {
+        let mut out = format!(
+            "Pull request #{pr_number} ({pr_url}) for branch `{branch}` received feedback. \
+             Address each item below with new commits on `{branch}`; do not rewrite \
+             history that has already been pushed. If you disagree with a comment, \
+             leave the code as is and explain why in your final message.\n"
+        );
+
+        if !self.change_requests.is_empty() {
+            out.push_str("\n## Changes requested\n\n");
+            for request in &self.change_requests {
+                if request.body.trim().is_empty() {
+                    let _ = writeln!(out, "- **{}** requested changes", request.author);
+                } else {
+                    let _ = writeln!(
+                        out,
+                        "- **{}**: {}",
+                        request.author,
+                        indent_continuation(request.body.trim())
+                    );
+                }
+            }
+        }
+
+        if !self.review_comments.is_empty() {
+            out.push_str("\n## Review comments\n\n");
+            for comment in &self.review_comments {
+                let location = match (&comment.path, comment.line) {
+                    (Some(path), Some(line)) => format!("`{path}:{line}` "),
+                    (Some(path), None) => format!("`{path}` "),
+                    _ => String::new(),
+                };
+                let _ = writeln!(
+                    out,
+                    "- {location}**{}**: {}",
+                    comment.author,
+                    indent_continuation(comment.body.trim())
+                );
+            }
+        }
+
+        if !self.failed_checks.is_empty() {
+            out.push_str("\n## Failed CI checks\n\n");
+            if let Some(sha) = &self.head_sha {
+                let _ = writeln!(out, "On commit `{sha}`:\n");
+            }
+            for check in &self.failed_checks {
+                let _ = write!(out, "- **{}** ({})", check.name, check.conclusion);
+                if let Some(summary) = check.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+                    let _ = write!(out, ": {}", indent_continuation(summary.trim()));
+                }
+                if let Some(url) = &check.details_url {
+                    let _ = write!(out, " — {url}");
+                }
+                out.push('\n');
+            }
+            out.push_str(
+                "\nReproduce the failures locally where possible and fix the cause rather \
+                 than disabling the checks.\n",
+            );
+        }
+
+        out
+    }
+}
+
+/// Keep multi-line bodies inside their list item
+fn indent_continuation(text: &str) -> String {
+    text.replace('\n', "\n  ")
+}
@@ -0,0 +1,400 @@
+//! Gitea and Forgejo (including codeberg.org) via the REST API v1
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use db::models::merge::{MergeStatus, PullRequestInfo};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{
+    ChangeRequest, FailedCheck, ForgeError, ForgeProvider, ForgeRepoInfo, ForgeType, PrFeedback,
+    REPOS_PER_PAGE, ReviewComment, send_json,
+};
+use crate::services::github_service::{CreatePrRequest, RepositoryInfo};
+
+pub struct GiteaProvider {
+    client: Client,
+    api_base: String,
+    token: String,
+}
+
+impl GiteaProvider {
+    /// `base_url` is the instance root, e.g. `https://git.example.com`
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            client: Client::new(),
+            api_base: format!("{}/api/v1", base_url.trim_end_matches('/')),
+            token: token.to_string(),
+        }
+    }
+
+    fn auth_header(&self) -> String {
+        format!("token {}", self.token)
+    }
+
+    fn repo_url(&self, repo: &ForgeRepoInfo) -> String {
+        format!(
+            "{}/repos/{}/{}",
+            self.api_base,
+            urlencoding::encode(&repo.owner),
+            urlencoding::encode(&repo.repo_name)
+        )
+    }
+
+    /// Logins among `users` whose feedback may be relayed to the agent:
+    /// collaborators with write access or higher, minus the token's own user
+    async fn trusted_authors<'a>(
+        &self,
+        repo: &ForgeRepoInfo,
+        users: impl IntoIterator<Item = &'a str>,
+    ) -> Result<HashSet<String>, ForgeError> {
+        let me: GiteaOwner = send_json(ForgeType::Gitea, || {
+            self.client
+                .get(format!("{}/user", self.api_base))
+                .header("Authorization", self.auth_header())
+        })
+        .await?;
+
+        let mut checked = HashSet::new();
+        let mut trusted = HashSet::new();
+        for login in users {
+            if login.is_empty() || login == me.login || !checked.insert(login) {
+                continue;
+            }
+            let permission = send_json::<GiteaPermission, _>(ForgeType::Gitea, || {
+                self.client
+                    .get(format!(
+                        "{}/collaborators/{}/permission",
+                        self.repo_url(repo),
+                        urlencoding::encode(login)
+                    ))
+                    .header("Authorization", self.auth_header())
+            })
+            .await;
+            match permission {
+                Ok(p) if matches!(p.permission.as_str(), "write" | "admin" | "owner") => {
+                    trusted.insert(login.to_string());
+                }
+                Ok(_)
+                | Err(ForgeError::RepoNotFoundOrNoAccess | ForgeError::InsufficientPermissions) => {
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(trusted)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CreatePullRequest<'a> {
+    head: &'a str,
+    base: &'a str,
+    title: &'a str,
+    body: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaPullRequest {
+    number: i64,
+    html_url: String,
+    state: String,
+    #[serde(default)]
+    merged: bool,
+    merged_at: Option<DateTime<Utc>>,
+    merge_commit_sha: Option<String>,
+    head: Option<GiteaBranch>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaBranch {
+    sha: String,
+}
+
+impl From<GiteaPullRequest> for PullRequestInfo {
+    fn from(pr: GiteaPullRequest) -> Self {
+        let status = match pr.state.as_str() {
+            "open" => MergeStatus::Open,
+            "closed" if pr.merged || pr.merged_at.is_some() => MergeStatus::Merged,
+            "closed" => MergeStatus::Closed,
+            _ => MergeStatus::Unknown,
+        };
+        PullRequestInfo {
+            number: pr.number,
+            url: pr.html_url,
+            status,
+            merged_at: pr.merged_at,
+            merge_commit_sha: pr.merge_commit_sha,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaOwner {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaPermission {
+    permission: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaReview {
+    id: i64,
+    user: Option<GiteaOwner>,
+    #[serde(default)]
+    body: String,
+    state: String,
+    html_url: Option<String>,
+    #[serde(default)]
+    dismissed: bool,
+    #[serde(default)]
+    comments_count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaReviewComment {
+    id: i64,
+    body: String,
+    user: Option<GiteaOwner>,
+    path: Option<String>,
+    #[serde(default)]
+    position: i64,
+    html_url: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaCombinedStatus {
+    #[serde(default)]
+    statuses: Vec<GiteaCommitStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaCommitStatus {
+    id: i64,
+    status: String,
+    context: String,
+    description: Option<String>,
+    target_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GiteaRepository {
+    id: i64,
+    name: String,
+    full_name: String,
+    owner: GiteaOwner,
+    description: Option<String>,
+    clone_url: String,
+    ssh_url: String,
+    default_branch: Option<String>,
+    #[serde(default)]
+    private: bool,
+}
+
+impl From<GiteaRepository> for RepositoryInfo {
+    fn from(repo: GiteaRepository) -> Self {
+        RepositoryInfo {
+            id: repo.id,
+            name: repo.name,
+            full_name: repo.full_name,
+            owner: repo.owner.login,
+            description: repo.description.filter(|d| !d.is_empty()),
+            clone_url: repo.clone_url,
+            ssh_url: repo.ssh_url,
+            default_branch: repo.default_branch.unwrap_or_else(|| "main".to_string()),
+            private: repo.private,
+        }
+    }
+}
+
+#[async_trait]
+impl ForgeProvider for GiteaProvider {
+    fn forge_type(&self) -> ForgeType {
+        ForgeType::Gitea
+    }
+
+    async fn check_token(&self) -> Result<(), ForgeError> {
+        send_json::<serde_json::Value, _>(ForgeType::Gitea, || {
+            self.client
+                .get(format!("{}/user", self.api_base))
+                .header("Authorization", self.auth_header())
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn create_pr(
+        &self,
+        repo: &ForgeRepoInfo,
+        request: &CreatePrRequest,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        let body = CreatePullRequest {
+            head: &request.head_branch,
+            base: &request.base_branch,
+            title: &request.title,
+            body: request.body.as_deref().unwrap_or(""),
+        };
+        let pr: GiteaPullRequest = send_json(ForgeType::Gitea, || {
+            self.client
+                .post(format!("{}/pulls", self.repo_url(repo)))
+                .header("Authorization", self.auth_header())
+                .json(&body)
+        })
+        .await?;
+
+        info!(
+            "Created Gitea PR #{} for branch {} in {}",
+            pr.number,
+            request.head_branch,
+            repo.full_name()
+        );
+        Ok(pr.into())
+    }
+
+    async fn get_pr_status(
+        &self,
+        repo: &ForgeRepoInfo,
+        number: i64,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        let pr: GiteaPullRequest = send_json(ForgeType::Gitea, || {
+            self.client
+                .get(format!("{}/pulls/{}", self.repo_url(repo), number))
+                .header("Authorization", self.auth_header())
+        })
+        .await?;
+        Ok(pr.into())
+    }
+
+    async fn get_pr_feedback(
+        &self,
+        repo: &ForgeRepoInfo,
+        number: i64,
+    ) -> Result<PrFeedback, ForgeError> {
+        let pr_url = format!("{}/pulls/{}", self.repo_url(repo), number);
+        let pr: GiteaPullRequest = send_json(ForgeType::Gitea, || {
+            self.client
+                .get(&pr_url)
+                .header("Authorization", self.auth_header())
+        })
+        .await?;
+        let head_sha = pr.head.map(|head| head.sha);
+
+        let reviews: Vec<GiteaReview> = send_json(ForgeType::Gitea, || {
+            self.client
+                .get(format!("{pr_url}/reviews"))
+                .header("Authorization", self.auth_header())
+        })
+        .await?;
+
+        let reviews: Vec<GiteaReview> = reviews
+            .into_iter()
+            .filter(|r| !r.dismissed && r.state != "PENDING")
+            .collect();
+        let trusted = self
+            .trusted_authors(
+                repo,
+                reviews
+                    .iter()
+                    .filter_map(|r| r.user.as_ref().map(|u| u.login.as_str())),
+            )
+            .await?;
+
+        let mut feedback = PrFeedback {
+            head_sha,
+            ..Default::default()
+        };
+        for review in reviews {
+            let author = review
+                .user
+                .as_ref()
+                .map(|u| u.login.clone())
+                .unwrap_or_default();
+            if !trusted.contains(&author) {
+                continue;
+            }
+            if review.state == "REQUEST_CHANGES" {
+                feedback.change_requests.push(ChangeRequest {
+                    id: review.id.to_string(),
+                    author: author.clone(),
+                    body: review.body.clone(),
+                    url: review.html_url.clone(),
+                });
+            } else if !review.body.trim().is_empty() {
+                feedback.review_comments.push(ReviewComment {
+                    id: format!("review-{}", review.id),
+                    author: author.clone(),
+                    body: review.body.clone(),
+                    path: None,
+                    line: None,
+                    url: review.html_url.clone(),
+                    created_at: None,
+                });
+            }
+
+            if review.comments_count > 0 {
+                let comments: Vec<GiteaReviewComment> = send_json(ForgeType::Gitea, || {
+                    self.client
+                        .get(format!("{pr_url}/reviews/{}/comments", review.id))
+                        .header("Authorization", self.auth_header())
+                })
+                .await?;
+                feedback.review_comments.extend(
+                    comments
+                        .into_iter()
+                        .map(|c| ReviewComment {
+                            id: c.id.to_string(),
+                            author: c.user.map(|u| u.login).unwrap_or_else(|| author.clone()),
+                            body: c.body,
+                            path: c.path,
+                            line: (c.position > 0).then_some(c.position),
+                            url: c.html_url,
+                            created_at: c.created_at,
+                        })
+                        .filter(|c| trusted.contains(&c.author)),
+                );
+            }
+        }
+
+        if let Some(sha) = &feedback.head_sha {
+            let status: GiteaCombinedStatus = send_json(ForgeType::Gitea, || {
+                self.client
+                    .get(format!("{}/commits/{}/status", self.repo_url(repo), sha))
+                    .header("Authorization", self.auth_header())
+            })
+            .await?;
+            feedback.failed_checks = status
+                .statuses
+                .into_iter()
+                .filter(|s| s.status == "failure" || s.status == "error")
+                .map(|s| FailedCheck {
+                    id: s.id.to_string(),
+                    name: s.context,
+                    conclusion: s.status,
+                    summary: s.description,
+                    details_url: s.target_url,
+                })
+                .collect();
+        }
+
+        Ok(feedback)
+    }
+
+    async fn list_repositories(&self, page: u32) -> Result<Vec<RepositoryInfo>, ForgeError> {
+        let repos: Vec<GiteaRepository> = send_json(ForgeType::Gitea, || {
+            self.client
+                .get(format!("{}/user/repos", self.api_base))
+                .header("Authorization", self.auth_header())
+                .query(&[
+                    ("limit", REPOS_PER_PAGE.to_string()),
+                    ("page", page.max(1).to_string()),
+                ])
+        })
+        .await?;
+        Ok(repos.into_iter().map(Into::into).collect())
+    }
+}
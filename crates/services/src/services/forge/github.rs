@@ -0,0 +1,65 @@
+//! GitHub forge backed by the existing octocrab-based [`GitHubService`]
+
+use async_trait::async_trait;
+use db::models::merge::PullRequestInfo;
+
+use super::{ForgeError, ForgeProvider, ForgeRepoInfo, ForgeType, PrFeedback};
+use crate::services::github_service::{CreatePrRequest, GitHubService, RepositoryInfo};
+
+pub struct GitHubProvider {
+    service: GitHubService,
+}
+
+impl GitHubProvider {
+    pub fn new(token: &str) -> Result<Self, ForgeError> {
+        Ok(Self {
+            service: GitHubService::new(token)?,
+        })
+    }
+}
+
+#[async_trait]
+impl ForgeProvider for GitHubProvider {
+    fn forge_type(&self) -> ForgeType {
+        ForgeType::Github
+    }
+
+    async fn check_token(&self) -> Result<(), ForgeError> {
+        Ok(self.service.check_token().await?)
+    }
+
+    async fn create_pr(
+        &self,
+        repo: &ForgeRepoInfo,
+        request: &CreatePrRequest,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        Ok(self.service.create_pr(&repo.to_github(), request).await?)
+    }
+
+    async fn get_pr_status(
+        &self,
+        repo: &ForgeRepoInfo,
+        number: i64,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        Ok(self
+            .service
+            .update_pr_status(&repo.to_github(), number)
+            .await?)
+    }
+
+    async fn get_pr_feedback(
+        &self,
+        repo: &ForgeRepoInfo,
+        number: i64,
+    ) -> Result<PrFeedback, ForgeError> {
+        Ok(self
+            .service
+            .get_pr_feedback(&repo.to_github(), number)
+            .await?)
+    }
+
+    async fn list_repositories(&self, page: u32) -> Result<Vec<RepositoryInfo>, ForgeError> {
+        let page = u8::try_from(page).unwrap_or(u8::MAX);
+        Ok(self.service.list_repositories(page).await?)
+    }
+}
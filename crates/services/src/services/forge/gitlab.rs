@@ -0,0 +1,429 @@
+//! GitLab (gitlab.com and self-managed) via the REST API v4
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use db::models::merge::{MergeStatus, PullRequestInfo};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::{
+    ChangeRequest, FailedCheck, ForgeError, ForgeProvider, ForgeRepoInfo, ForgeType, PrFeedback,
+    REPOS_PER_PAGE, ReviewComment, send_json,
+};
+use crate::services::github_service::{CreatePrRequest, RepositoryInfo};
+
+pub struct GitLabProvider {
+    client: Client,
+    api_base: String,
+    token: String,
+}
+
+impl GitLabProvider {
+    /// `base_url` is the instance root, e.g. `https://gitlab.example.com`
+    pub fn new(base_url: &str, token: &str) -> Self {
+        Self {
+            client: Client::new(),
+            api_base: format!("{}/api/v4", base_url.trim_end_matches('/')),
+            token: token.to_string(),
+        }
+    }
+
+    /// Projects are addressed by their URL-encoded `namespace/name` path
+    fn project_url(&self, repo: &ForgeRepoInfo) -> String {
+        format!(
+            "{}/projects/{}",
+            self.api_base,
+            urlencoding::encode(&repo.full_name())
+        )
+    }
+
+    /// Usernames among `users` whose feedback may be relayed to the agent:
+    /// project members with Developer access or higher, minus the token's own
+    /// user
+    async fn trusted_authors<'a>(
+        &self,
+        repo: &ForgeRepoInfo,
+        users: impl IntoIterator<Item = &'a GitLabUser>,
+    ) -> Result<HashSet<String>, ForgeError> {
+        let me: GitLabUser = send_json(ForgeType::Gitlab, || {
+            self.client
+                .get(format!("{}/user", self.api_base))
+                .header("PRIVATE-TOKEN", &self.token)
+        })
+        .await?;
+
+        let mut checked = HashSet::new();
+        let mut trusted = HashSet::new();
+        for user in users {
+            if user.id == me.id || !checked.insert(user.id) {
+                continue;
+            }
+            // Includes members inherited from parent groups; non-members 404
+            let member = send_json::<GitLabMember, _>(ForgeType::Gitlab, || {
+                self.client
+                    .get(format!(
+                        "{}/members/all/{}",
+                        self.project_url(repo),
+                        user.id
+                    ))
+                    .header("PRIVATE-TOKEN", &self.token)
+            })
+            .await;
+            match member {
+                Ok(member) if member.access_level >= DEVELOPER_ACCESS => {
+                    trusted.insert(user.username.clone());
+                }
+                Ok(_) | Err(ForgeError::RepoNotFoundOrNoAccess) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(trusted)
+    }
+}
+
+/// GitLab's numeric access level for the Developer role
+const DEVELOPER_ACCESS: u32 = 30;
+
+#[derive(Debug, Serialize)]
+struct CreateMergeRequest<'a> {
+    source_branch: &'a str,
+    target_branch: &'a str,
+    title: &'a str,
+    description: &'a str,
+    remove_source_branch: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabMergeRequest {
+    iid: i64,
+    web_url: String,
+    state: String,
+    merged_at: Option<DateTime<Utc>>,
+    merge_commit_sha: Option<String>,
+    squash_commit_sha: Option<String>,
+    /// Head commit of the source branch
+    sha: Option<String>,
+}
+
+impl From<GitLabMergeRequest> for PullRequestInfo {
+    fn from(mr: GitLabMergeRequest) -> Self {
+        let status = match mr.state.as_str() {
+            "opened" => MergeStatus::Open,
+            "merged" => MergeStatus::Merged,
+            "closed" | "locked" => MergeStatus::Closed,
+            _ => MergeStatus::Unknown,
+        };
+        PullRequestInfo {
+            number: mr.iid,
+            url: mr.web_url,
+            status,
+            merged_at: mr.merged_at,
+            merge_commit_sha: mr.merge_commit_sha.or(mr.squash_commit_sha),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabUser {
+    id: i64,
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabMember {
+    access_level: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabDiscussion {
+    notes: Vec<GitLabNote>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabNote {
+    id: i64,
+    body: String,
+    author: GitLabUser,
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    system: bool,
+    #[serde(default)]
+    resolvable: bool,
+    #[serde(default)]
+    resolved: bool,
+    position: Option<GitLabNotePosition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabNotePosition {
+    new_path: Option<String>,
+    new_line: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabReviewer {
+    user: GitLabUser,
+    state: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabPipeline {
+    id: i64,
+    sha: String,
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabJob {
+    id: i64,
+    name: String,
+    stage: String,
+    status: String,
+    web_url: Option<String>,
+    #[serde(default)]
+    allow_failure: bool,
+    failure_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabNamespace {
+    full_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitLabProject {
+    id: i64,
+    name: String,
+    path_with_namespace: String,
+    namespace: GitLabNamespace,
+    description: Option<String>,
+    http_url_to_repo: String,
+    ssh_url_to_repo: String,
+    default_branch: Option<String>,
+    visibility: Option<String>,
+}
+
+impl From<GitLabProject> for RepositoryInfo {
+    fn from(project: GitLabProject) -> Self {
+        RepositoryInfo {
+            id: project.id,
+            name: project.name,
+            full_name: project.path_with_namespace,
+            owner: project.namespace.full_path,
+            description: project.description,
+            clone_url: project.http_url_to_repo,
+            ssh_url: project.ssh_url_to_repo,
+            default_branch: project.default_branch.unwrap_or_else(|| "main".to_string()),
+            private: project.visibility.as_deref() != Some("public"),
+        }
+    }
+}
+
+#[async_trait]
+impl ForgeProvider for GitLabProvider {
+    fn forge_type(&self) -> ForgeType {
+        ForgeType::Gitlab
+    }
+
+    async fn check_token(&self) -> Result<(), ForgeError> {
+        send_json::<serde_json::Value, _>(ForgeType::Gitlab, || {
+            self.client
+                .get(format!("{}/user", self.api_base))
+                .header("PRIVATE-TOKEN", &self.token)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn create_pr(
+        &self,
+        repo: &ForgeRepoInfo,
+        request: &CreatePrRequest,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        let body = CreateMergeRequest {
+            source_branch: &request.head_branch,
+            target_branch: &request.base_branch,
+            title: &request.title,
+            description: request.body.as_deref().unwrap_or(""),
+            remove_source_branch: false,
+        };
+        let mr: GitLabMergeRequest = send_json(ForgeType::Gitlab, || {
+            self.client
+                .post(format!("{}/merge_requests", self.project_url(repo)))
+                .header("PRIVATE-TOKEN", &self.token)
+                .json(&body)
+        })
+        .await?;
+
+        info!(
+            "Created GitLab MR !{} for branch {} in {}",
+            mr.iid,
+            request.head_branch,
+            repo.full_name()
+        );
+        Ok(mr.into())
+    }
+
+    async fn get_pr_status(
+        &self,
+        repo: &ForgeRepoInfo,
+        number: i64,
+    ) -> Result<PullRequestInfo, ForgeError> {
+        let mr: GitLabMergeRequest = send_json(ForgeType::Gitlab, || {
+            self.client
+                .get(format!(
+                    "{}/merge_requests/{}",
+                    self.project_url(repo),
+                    number
+                ))
+                .header("PRIVATE-TOKEN", &self.token)
+        })
+        .await?;
+        Ok(mr.into())
+    }
+
+    async fn get_pr_feedback(
+        &self,
+        repo: &ForgeRepoInfo,
+        number: i64,
+    ) -> Result<PrFeedback, ForgeError> {
+        let mr_url = format!("{}/merge_requests/{}", self.project_url(repo), number);
+        let mr: GitLabMergeRequest = send_json(ForgeType::Gitlab, || {
+            self.client
+                .get(&mr_url)
+                .header("PRIVATE-TOKEN", &self.token)
+        })
+        .await?;
+
+        let discussions: Vec<GitLabDiscussion> = send_json(ForgeType::Gitlab, || {
+            self.client
+                .get(format!("{mr_url}/discussions"))
+                .header("PRIVATE-TOKEN", &self.token)
+                .query(&[("per_page", "100")])
+        })
+        .await?;
+        let notes: Vec<GitLabNote> = discussions
+            .into_iter()
+            .flat_map(|d| d.notes)
+            .filter(|note| !note.system && !(note.resolvable && note.resolved))
+            .collect();
+
+        // Reviewer states exist since GitLab 16.x; older instances 404 here
+        let reviewers: Vec<GitLabReviewer> = match send_json(ForgeType::Gitlab, || {
+            self.client
+                .get(format!("{mr_url}/reviewers"))
+                .header("PRIVATE-TOKEN", &self.token)
+        })
+        .await
+        {
+            Ok(reviewers) => reviewers,
+            Err(ForgeError::RepoNotFoundOrNoAccess) => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let reviewers: Vec<GitLabReviewer> = reviewers
+            .into_iter()
+            .filter(|r| r.state == "requested_changes")
+            .collect();
+
+        let trusted = self
+            .trusted_authors(
+                repo,
+                notes
+                    .iter()
+                    .map(|n| &n.author)
+                    .chain(reviewers.iter().map(|r| &r.user)),
+            )
+            .await?;
+
+        let review_comments = notes
+            .into_iter()
+            .filter(|note| trusted.contains(&note.author.username))
+            .map(|note| ReviewComment {
+                id: note.id.to_string(),
+                url: Some(format!("{}#note_{}", mr.web_url, note.id)),
+                author: note.author.username,
+                body: note.body,
+                path: note.position.as_ref().and_then(|p| p.new_path.clone()),
+                line: note.position.as_ref().and_then(|p| p.new_line),
+                created_at: note.created_at,
+            })
+            .collect();
+
+        // Reviewer state carries no id, so key it by head commit to pick up
+        // a fresh request after new pushes
+        let head = mr.sha.clone().unwrap_or_default();
+        let change_requests = reviewers
+            .into_iter()
+            .filter(|r| trusted.contains(&r.user.username))
+            .map(|r| ChangeRequest {
+                id: format!("{}@{}", r.user.username, head),
+                author: r.user.username,
+                body: String::new(),
+                url: Some(mr.web_url.clone()),
+            })
+            .collect();
+
+        let pipelines: Vec<GitLabPipeline> = send_json(ForgeType::Gitlab, || {
+            self.client
+                .get(format!("{mr_url}/pipelines"))
+                .header("PRIVATE-TOKEN", &self.token)
+        })
+        .await?;
+        let latest = pipelines
+            .into_iter()
+            .find(|p| mr.sha.as_deref().is_none_or(|sha| p.sha == sha));
+        let failed_checks = match latest {
+            Some(pipeline) if pipeline.status == "failed" => {
+                let jobs: Vec<GitLabJob> = send_json(ForgeType::Gitlab, || {
+                    self.client
+                        .get(format!(
+                            "{}/pipelines/{}/jobs",
+                            self.project_url(repo),
+                            pipeline.id
+                        ))
+                        .header("PRIVATE-TOKEN", &self.token)
+                        .query(&[("scope[]", "failed"), ("per_page", "100")])
+                })
+                .await?;
+                jobs.into_iter()
+                    .filter(|job| !job.allow_failure)
+                    .map(|job| FailedCheck {
+                        id: job.id.to_string(),
+                        name: format!("{} / {}", job.stage, job.name),
+                        conclusion: job.failure_reason.unwrap_or(job.status),
+                        summary: None,
+                        details_url: job.web_url,
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(PrFeedback {
+            head_sha: mr.sha,
+            change_requests,
+            review_comments,
+            failed_checks,
+        })
+    }
+
+    async fn list_repositories(&self, page: u32) -> Result<Vec<RepositoryInfo>, ForgeError> {
+        let projects: Vec<GitLabProject> = send_json(ForgeType::Gitlab, || {
+            self.client
+                .get(format!("{}/projects", self.api_base))
+                .header("PRIVATE-TOKEN", &self.token)
+                .query(&[
+                    ("membership", "true".to_string()),
+                    ("order_by", "last_activity_at".to_string()),
+                    ("sort", "desc".to_string()),
+                    ("per_page", REPOS_PER_PAGE.to_string()),
+                    ("page", page.max(1).to_string()),
+                ])
+        })
+        .await?;
+        Ok(projects.into_iter().map(Into::into).collect())
+    }
+}
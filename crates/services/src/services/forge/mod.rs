@@ -0,0 +1,327 @@
+//! Code forge integrations
+//!
+//! Pull/merge request creation, status polling and repository listing behind a
+//! single [`ForgeProvider`] trait, so attempts can target GitHub, GitLab or
+//! Gitea/Forgejo depending on the project's git remote.
+
+pub mod feedback;
+pub mod gitea;
+pub mod github;
+pub mod gitlab;
+
+use std::time::Duration;
+
+use async_trait::async_trait;
+use backon::{ExponentialBuilder, Retryable};
+pub use db::models::merge::ForgeType;
+use db::models::merge::PullRequestInfo;
+pub use feedback::{ChangeRequest, FailedCheck, PrFeedback, ReviewComment};
+pub use gitea::GiteaProvider;
+pub use github::GitHubProvider;
+pub use gitlab::GitLabProvider;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+use crate::services::{
+    config::{Config, ForgeInstanceConfig},
+    github_service::{CreatePrRequest, GitHubRepoInfo, GitHubServiceError, RepositoryInfo},
+};
+
+// scheme://[user@]host[:port]/path  or  [user@]host:path (scp-like)
+static REMOTE_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:[a-z+]+://)?(?:[^@/]+@)?(?P<host>[^:/]+)(?::(?P<port>\d+))?[:/](?P<path>.+)$")
+        .unwrap()
+});
+
+#[derive(Debug, Error)]
+pub enum ForgeError {
+    #[error(transparent)]
+    GitHub(#[from] GitHubServiceError),
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("{forge} API error ({status}): {message}")]
+    Api {
+        forge: ForgeType,
+        status: u16,
+        message: String,
+    },
+    #[error("{0} token is invalid or expired.")]
+    TokenInvalid(ForgeType),
+    #[error("Insufficient permissions")]
+    InsufficientPermissions,
+    #[error("Repository not found or no access")]
+    RepoNotFoundOrNoAccess,
+    #[error("No {0} token configured for {1}")]
+    NotConfigured(ForgeType, String),
+    #[error("Unrecognized forge remote: {0}")]
+    UnknownRemote(String),
+}
+
+impl ForgeError {
+    pub fn should_retry(&self) -> bool {
+        match self {
+            ForgeError::GitHub(e) => e.should_retry(),
+            ForgeError::Http(e) => e.is_timeout() || e.is_connect(),
+            ForgeError::Api { status, .. } => *status >= 500 || *status == 429,
+            _ => false,
+        }
+    }
+}
+
+/// Repository coordinates on a forge, parsed from a git remote or PR/MR URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForgeRepoInfo {
+    pub forge: ForgeType,
+    /// Host as it appears in the remote, e.g. `gitlab.example.com`
+    pub host: String,
+    /// Owner, or the full group path on GitLab (`group/subgroup`)
+    pub owner: String,
+    pub repo_name: String,
+}
+
+impl ForgeRepoInfo {
+    /// Parse SSH, HTTPS and PR/MR web URLs. Self-hosted hosts are matched against
+    /// the configured instances first, then recognised by well-known names.
+    pub fn from_remote_url(
+        remote_url: &str,
+        instances: &[ForgeInstanceConfig],
+    ) -> Result<Self, ForgeError> {
+        Self::parse(remote_url, |host| {
+            instances
+                .iter()
+                .find(|i| i.host.eq_ignore_ascii_case(host))
+                .map(|i| i.forge)
+                .or_else(|| detect_forge(host))
+        })
+    }
+
+    /// Parse the URL of a PR/MR whose forge is already known (e.g. stored on a merge)
+    pub fn from_pr_url(pr_url: &str, forge: ForgeType) -> Result<Self, ForgeError> {
+        Self::parse(pr_url, |_| Some(forge))
+    }
+
+    fn parse(
+        url: &str,
+        resolve_forge: impl FnOnce(&str) -> Option<ForgeType>,
+    ) -> Result<Self, ForgeError> {
+        let unknown = || ForgeError::UnknownRemote(url.to_string());
+
+        let caps = REMOTE_URL.captures(url.trim()).ok_or_else(unknown)?;
+        let host = caps["host"].to_ascii_lowercase();
+        let path = caps["path"].trim_end_matches('/');
+        let forge = resolve_forge(&host).ok_or_else(unknown)?;
+
+        // Drop web suffixes: GitLab uses `/-/merge_requests/1`, GitHub `/pull/1`,
+        // Gitea `/pulls/1`
+        let path = path.split("/-/").next().unwrap_or(path);
+        let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if forge != ForgeType::Gitlab {
+            segments.truncate(2);
+        }
+        if segments.len() < 2 {
+            return Err(unknown());
+        }
+        let repo_name = segments.pop().unwrap().trim_end_matches(".git").to_string();
+
+        Ok(Self {
+            forge,
+            host,
+            owner: segments.join("/"),
+            repo_name,
+        })
+    }
+
+    pub fn full_name(&self) -> String {
+        format!("{}/{}", self.owner, self.repo_name)
+    }
+
+    pub fn to_github(&self) -> GitHubRepoInfo {
+        GitHubRepoInfo {
+            owner: self.owner.clone(),
+            repo_name: self.repo_name.clone(),
+        }
+    }
+}
+
+fn detect_forge(host: &str) -> Option<ForgeType> {
+    if host == "github.com" || host.ends_with(".github.com") {
+        Some(ForgeType::Github)
+    } else if host == "gitlab.com" || host.split('.').any(|label| label == "gitlab") {
+        Some(ForgeType::Gitlab)
+    } else if host == "codeberg.org"
+        || host
+            .split('.')
+            .any(|label| label == "gitea" || label == "forgejo")
+    {
+        Some(ForgeType::Gitea)
+    } else {
+        None
+    }
+}
+
+/// Token and endpoint for a forge, resolved from the user's config
+#[derive(Debug, Clone)]
+pub struct ForgeCredentials {
+    pub forge: ForgeType,
+    pub base_url: String,
+    pub token: String,
+}
+
+impl ForgeCredentials {
+    /// GitHub uses the GitHub login; GitLab and Gitea use the matching
+    /// `forges` entry. `GITLAB_TOKEN` / `GITEA_TOKEN` are only sent to the
+    /// public instance (gitlab.com / codeberg.org) or to configured hosts, never
+    /// to a host that was merely guessed from a remote URL.
+    pub fn resolve(repo: &ForgeRepoInfo, config: &Config) -> Result<Self, ForgeError> {
+        Self::for_host(repo.forge, &repo.host, config)
+    }
+
+    pub fn for_host(forge: ForgeType, host: &str, config: &Config) -> Result<Self, ForgeError> {
+        let not_configured = || ForgeError::NotConfigured(forge, host.to_string());
+
+        if forge == ForgeType::Github {
+            return Ok(Self {
+                forge: ForgeType::Github,
+                base_url: "https://api.github.com".to_string(),
+                token: config.github.token().ok_or_else(not_configured)?,
+            });
+        }
+
+        let instance = config
+            .forges
+            .iter()
+            .find(|i| i.forge == forge && i.host.eq_ignore_ascii_case(host));
+        let (env_var, public_host) = match forge {
+            ForgeType::Gitlab => ("GITLAB_TOKEN", "gitlab.com"),
+            _ => ("GITEA_TOKEN", "codeberg.org"),
+        };
+        if instance.is_none() && !host.eq_ignore_ascii_case(public_host) {
+            return Err(not_configured());
+        }
+        let token = instance
+            .and_then(|i| i.token.clone())
+            .or_else(|| std::env::var(env_var).ok())
+            .filter(|t| !t.is_empty())
+            .ok_or_else(not_configured)?;
+
+        Ok(Self {
+            forge,
+            base_url: instance
+                .map(|i| i.base_url())
+                .unwrap_or_else(|| format!("https://{}", host)),
+            token,
+        })
+    }
+
+    pub fn provider(&self) -> Result<Box<dyn ForgeProvider>, ForgeError> {
+        Ok(match self.forge {
+            ForgeType::Github => Box::new(GitHubProvider::new(&self.token)?),
+            ForgeType::Gitlab => Box::new(GitLabProvider::new(&self.base_url, &self.token)),
+            ForgeType::Gitea => Box::new(GiteaProvider::new(&self.base_url, &self.token)),
+        })
+    }
+}
+
+#[async_trait]
+pub trait ForgeProvider: Send + Sync {
+    fn forge_type(&self) -> ForgeType;
+
+    async fn check_token(&self) -> Result<(), ForgeError>;
+
+    /// Open a pull/merge request from `head_branch` into `base_branch`
+    async fn create_pr(
+        &self,
+        repo: &ForgeRepoInfo,
+        request: &CreatePrRequest,
+    ) -> Result<PullRequestInfo, ForgeError>;
+
+    /// Fetch the current state of a pull/merge request by its number (GitLab `iid`)
+    async fn get_pr_status(
+        &self,
+        repo: &ForgeRepoInfo,
+        number: i64,
+    ) -> Result<PullRequestInfo, ForgeError>;
+
+    /// Change requests, review comments and failed checks on the PR head.
+    /// Resolved GitLab discussions and dismissed reviews are left out, as is
+    /// anything written by the token's own user or by someone without write
+    /// access to the repository, since agent-driven tasks act on it unattended.
+    async fn get_pr_feedback(
+        &self,
+        repo: &ForgeRepoInfo,
+        number: i64,
+    ) -> Result<PrFeedback, ForgeError>;
+
+    /// Repositories the token can access, most recently updated first (1-based page)
+    async fn list_repositories(&self, page: u32) -> Result<Vec<RepositoryInfo>, ForgeError>;
+}
+
+pub(crate) const REPOS_PER_PAGE: u32 = 50;
+
+pub(crate) fn retry_policy() -> ExponentialBuilder {
+    ExponentialBuilder::default()
+        .with_min_delay(Duration::from_secs(1))
+        .with_max_delay(Duration::from_secs(30))
+        .with_max_times(3)
+        .with_jitter()
+}
+
+/// Send a request built by `build`, retrying transient failures, and decode the JSON body
+pub(crate) async fn send_json<T, F>(forge: ForgeType, build: F) -> Result<T, ForgeError>
+where
+    T: DeserializeOwned,
+    F: Fn() -> RequestBuilder,
+{
+    (|| async {
+        let response = build().send().await?;
+        check_status(forge, response)
+            .await?
+            .json::<T>()
+            .await
+            .map_err(Into::into)
+    })
+    .retry(&retry_policy())
+    .when(|e: &ForgeError| e.should_retry())
+    .notify(|err: &ForgeError, dur: Duration| {
+        tracing::warn!(
+            "{} API call failed, retrying after {:.2}s: {}",
+            forge,
+            dur.as_secs_f64(),
+            err
+        );
+    })
+    .await
+}
+
+async fn check_status(forge: ForgeType, response: Response) -> Result<Response, ForgeError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(match status {
+        StatusCode::UNAUTHORIZED => ForgeError::TokenInvalid(forge),
+        StatusCode::FORBIDDEN => ForgeError::InsufficientPermissions,
+        StatusCode::NOT_FOUND => ForgeError::RepoNotFoundOrNoAccess,
+        _ => ForgeError::Api {
+            forge,
+            status: status.as_u16(),
+            message: api_error_message(&body),
+        },
+    })
+}
+
+/// GitLab returns `message` (string, list or map) or `error`; Gitea returns `message`
+fn api_error_message(body: &str) -> String {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(body) else {
+        return body.trim().to_string();
+    };
+    match value.get("message").or_else(|| value.get("error")) {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+        None => body.trim().to_string(),
+    }
+}
@@ -13,7 +13,9 @@ use utils::diff::{Diff, DiffChangeKind, FileDiffDetails};
 // Import for file ranking functionality
 use super::file_ranker::FileStat;
 use super::git_cli::{ChangeType, GitCli, GitCliError, StatusDiffEntry, StatusDiffOptions};
-use crate::services::github_service::GitHubRepoInfo;
+use crate::services::{
+    config::ForgeInstanceConfig, forge::ForgeRepoInfo, github_service::GitHubRepoInfo,
+};
 
 #[derive(Debug, Error)]
 pub enum GitServiceError {
@@ -1620,6 +1622,26 @@ impl GitService {
         })
     }
 
+    /// Identify the forge (GitHub, GitLab, Gitea) and repository from the default remote
+    pub fn get_forge_repo_info(
+        &self,
+        repo_path: &Path,
+        instances: &[ForgeInstanceConfig],
+    ) -> Result<ForgeRepoInfo, GitServiceError> {
+        let repo = self.open_repo(repo_path)?;
+        let remote_name = self.default_remote_name(&repo);
+        let remote = repo.find_remote(&remote_name).map_err(|_| {
+            GitServiceError::InvalidRepository(format!("No '{remote_name}' remote found"))
+        })?;
+
+        let url = remote
+            .url()
+            .ok_or_else(|| GitServiceError::InvalidRepository("Remote has no URL".to_string()))?;
+        ForgeRepoInfo::from_remote_url(url, instances).map_err(|e| {
+            GitServiceError::InvalidRepository(format!("Failed to parse remote URL: {e}"))
+        })
+    }
+
     pub fn get_remote_name_from_branch_name(
         &self,
         repo_path: &Path,
@@ -1657,6 +1679,29 @@ impl GitService {
         })
     }
 
+    /// True when the local branch has commits its remote-tracking branch lacks
+    /// and can be fast-forwarded to it. Uses the last fetched remote state.
+    pub fn is_branch_ahead_of_remote(
+        &self,
+        worktree_path: &Path,
+        branch_name: &str,
+    ) -> Result<bool, GitServiceError> {
+        let repo = self.open_repo(worktree_path)?;
+        let local = repo
+            .find_branch(branch_name, BranchType::Local)?
+            .get()
+            .peel_to_commit()?
+            .id();
+        let remote_name = self.default_remote_name(&repo);
+        let Ok(remote_ref) =
+            repo.find_reference(&format!("refs/remotes/{remote_name}/{branch_name}"))
+        else {
+            return Ok(false);
+        };
+        let remote = remote_ref.peel_to_commit()?.id();
+        Ok(local != remote && repo.graph_descendant_of(local, remote)?)
+    }
+
     pub fn push_to_github(
         &self,
         worktree_path: &Path,
@@ -1700,13 +1745,24 @@ impl GitService {
     }
 
     pub fn convert_to_https_url(&self, url: &str) -> String {
-        // Convert SSH URL to HTTPS URL if necessary
-        let new_url = if url.starts_with("git@github.com:") {
-            // Convert git@github.com:owner/repo.git to https://github.com/owner/repo.git
-            url.replace("git@github.com:", "https://github.com/")
-        } else if url.starts_with("ssh://git@github.com/") {
-            // Convert ssh://git@github.com/owner/repo.git to https://github.com/owner/repo.git
-            url.replace("ssh://git@github.com/", "https://github.com/")
+        // Convert SSH URL to HTTPS URL if necessary. Works for any forge host:
+        // git@host:owner/repo.git and ssh://git@host[:port]/owner/repo.git both
+        // become https://host/owner/repo.git. The SSH port says nothing about
+        // where the forge serves HTTPS, so it is dropped
+        let new_url = if let Some(rest) = url.strip_prefix("ssh://") {
+            let rest = rest.split_once('@').map_or(rest, |(_, r)| r);
+            match rest.split_once('/') {
+                Some((host_port, path)) => {
+                    let host = host_port.split_once(':').map_or(host_port, |(h, _)| h);
+                    format!("https://{host}/{path}")
+                }
+                None => url.to_string(),
+            }
+        } else if !url.contains("://")
+            && let Some((user_host, path)) = url.split_once(':')
+        {
+            let host = user_host.split_once('@').map_or(user_host, |(_, h)| h);
+            format!("https://{host}/{path}")
         } else {
             url.to_string()
         };
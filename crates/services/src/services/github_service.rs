@@ -2,14 +2,18 @@ use std::time::Duration;
 
 use backon::{ExponentialBuilder, Retryable};
 use db::models::merge::{MergeStatus, PullRequestInfo};
-use octocrab::{Octocrab, OctocrabBuilder, models::IssueState};
+use octocrab::{Octocrab, OctocrabBuilder, Page, models::IssueState};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::info;
 use ts_rs::TS;
 
-use crate::services::{git::GitServiceError, git_cli::GitCliError};
+use crate::services::{
+    forge::{ChangeRequest, FailedCheck, PrFeedback, ReviewComment},
+    git::GitServiceError,
+    git_cli::GitCliError,
+};
 
 #[derive(Debug, Error, Serialize, Deserialize, TS)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -138,6 +142,79 @@ pub struct RepositoryInfo {
     pub private: bool,
 }
 
+#[derive(Debug, Deserialize)]
+struct GhUser {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhPullHead {
+    head: GhCommitRef,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhCommitRef {
+    sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhReview {
+    id: u64,
+    user: Option<GhUser>,
+    author_association: Option<String>,
+    body: Option<String>,
+    state: String,
+    html_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhReviewComment {
+    id: u64,
+    user: Option<GhUser>,
+    author_association: Option<String>,
+    body: String,
+    path: Option<String>,
+    line: Option<i64>,
+    html_url: Option<String>,
+    created_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhCheckRuns {
+    total_count: usize,
+    check_runs: Vec<GhCheckRun>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhCheckRun {
+    id: u64,
+    name: String,
+    conclusion: Option<String>,
+    html_url: Option<String>,
+    details_url: Option<String>,
+    output: Option<GhCheckOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhCheckOutput {
+    title: Option<String>,
+    summary: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhCombinedStatus {
+    statuses: Vec<GhCommitStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GhCommitStatus {
+    id: u64,
+    state: String,
+    context: String,
+    description: Option<String>,
+    target_url: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct GitHubService {
     client: Octocrab,
@@ -153,6 +230,17 @@ impl GitHubService {
         Ok(Self { client })
     }
 
+    /// Same as [`Self::new`] against another API root, e.g. a GitHub Enterprise
+    /// Server's `https://github.example.com/api/v3`
+    pub fn with_base_uri(github_token: &str, base_uri: &str) -> Result<Self, GitHubServiceError> {
+        let client = OctocrabBuilder::new()
+            .personal_token(github_token.to_string())
+            .base_uri(base_uri)?
+            .build()?;
+
+        Ok(Self { client })
+    }
+
     pub async fn check_token(&self) -> Result<(), GitHubServiceError> {
         self.client.current().user().await?;
         Ok(())
@@ -300,6 +388,180 @@ impl GitHubService {
         .await
     }
 
+    /// Review feedback and failed checks on the PR head commit
+    pub async fn get_pr_feedback(
+        &self,
+        repo_info: &GitHubRepoInfo,
+        pr_number: i64,
+    ) -> Result<PrFeedback, GitHubServiceError> {
+        (|| async { self.get_pr_feedback_internal(repo_info, pr_number).await })
+            .retry(
+                &ExponentialBuilder::default()
+                    .with_min_delay(Duration::from_secs(1))
+                    .with_max_delay(Duration::from_secs(30))
+                    .with_max_times(3)
+                    .with_jitter(),
+            )
+            .when(|err| err.should_retry())
+            .notify(|err: &GitHubServiceError, dur: Duration| {
+                tracing::warn!(
+                    "GitHub API call failed, retrying after {:.2}s: {}",
+                    dur.as_secs_f64(),
+                    err
+                );
+            })
+            .await
+    }
+
+    async fn get_pr_feedback_internal(
+        &self,
+        repo_info: &GitHubRepoInfo,
+        pr_number: i64,
+    ) -> Result<PrFeedback, GitHubServiceError> {
+        let repo = format!("/repos/{}/{}", repo_info.owner, repo_info.repo_name);
+        let page = [("per_page", "100")];
+
+        let pr: GhPullHead = self
+            .client
+            .get(format!("{repo}/pulls/{pr_number}"), None::<&()>)
+            .await?;
+        // Busy PRs easily pass 100 reviews or comments; follow the `Link` header
+        let reviews: Page<GhReview> = self
+            .client
+            .get(format!("{repo}/pulls/{pr_number}/reviews"), Some(&page))
+            .await?;
+        let reviews = self.client.all_pages(reviews).await?;
+        let comments: Page<GhReviewComment> = self
+            .client
+            .get(format!("{repo}/pulls/{pr_number}/comments"), Some(&page))
+            .await?;
+        let comments = self.client.all_pages(comments).await?;
+        let check_runs = self.get_all_check_runs(&repo, &pr.head.sha).await?;
+        let status: GhCombinedStatus = self
+            .client
+            .get(
+                format!("{repo}/commits/{}/status", pr.head.sha),
+                Some(&page),
+            )
+            .await?;
+
+        // Only feedback from people with write access reaches the agent, and
+        // never the token's own comments
+        let me: GhUser = self.client.get("/user", None::<&()>).await?;
+        let trusted = |user: &Option<GhUser>, association: &Option<String>| {
+            user.as_ref().is_some_and(|u| u.login != me.login)
+                && matches!(
+                    association.as_deref(),
+                    Some("OWNER" | "MEMBER" | "COLLABORATOR")
+                )
+        };
+        let login = |user: Option<GhUser>| user.map(|u| u.login).unwrap_or_default();
+        let mut feedback = PrFeedback {
+            head_sha: Some(pr.head.sha),
+            ..Default::default()
+        };
+
+        for review in reviews
+            .into_iter()
+            .filter(|r| trusted(&r.user, &r.author_association))
+        {
+            let body = review.body.unwrap_or_default();
+            match review.state.as_str() {
+                "CHANGES_REQUESTED" => feedback.change_requests.push(ChangeRequest {
+                    id: review.id.to_string(),
+                    author: login(review.user),
+                    body,
+                    url: review.html_url,
+                }),
+                // Top-level text of a plain review; its inline comments come below
+                "COMMENTED" if !body.trim().is_empty() => {
+                    feedback.review_comments.push(ReviewComment {
+                        id: format!("review-{}", review.id),
+                        author: login(review.user),
+                        body,
+                        path: None,
+                        line: None,
+                        url: review.html_url,
+                        created_at: None,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        feedback.review_comments.extend(
+            comments
+                .into_iter()
+                .filter(|c| trusted(&c.user, &c.author_association))
+                .map(|c| ReviewComment {
+                    id: c.id.to_string(),
+                    author: login(c.user),
+                    body: c.body,
+                    path: c.path,
+                    line: c.line,
+                    url: c.html_url,
+                    created_at: c.created_at,
+                }),
+        );
+
+        feedback.failed_checks = check_runs
+            .into_iter()
+            .filter(|run| {
+                matches!(
+                    run.conclusion.as_deref(),
+                    Some("failure" | "timed_out" | "action_required" | "startup_failure")
+                )
+            })
+            .map(|run| FailedCheck {
+                id: run.id.to_string(),
+                name: run.name,
+                conclusion: run.conclusion.unwrap_or_default(),
+                summary: run.output.and_then(|o| o.summary.or(o.title)),
+                details_url: run.details_url.or(run.html_url),
+            })
+            .chain(
+                status
+                    .statuses
+                    .into_iter()
+                    .filter(|s| s.state == "failure" || s.state == "error")
+                    .map(|s| FailedCheck {
+                        id: format!("status-{}", s.id),
+                        name: s.context,
+                        conclusion: s.state,
+                        summary: s.description,
+                        details_url: s.target_url,
+                    }),
+            )
+            .collect();
+
+        Ok(feedback)
+    }
+
+    /// Check runs wrap their list in an object, so page by `total_count`
+    /// rather than through [`Page`]
+    async fn get_all_check_runs(
+        &self,
+        repo: &str,
+        sha: &str,
+    ) -> Result<Vec<GhCheckRun>, GitHubServiceError> {
+        let mut runs = Vec::new();
+        for page in 1.. {
+            let batch: GhCheckRuns = self
+                .client
+                .get(
+                    format!("{repo}/commits/{sha}/check-runs"),
+                    Some(&[("per_page", "100".to_string()), ("page", page.to_string())]),
+                )
+                .await?;
+            let last = batch.check_runs.is_empty();
+            runs.extend(batch.check_runs);
+            if last || runs.len() >= batch.total_count {
+                break;
+            }
+        }
+        Ok(runs)
+    }
+
     fn map_pull_request(pr: octocrab::models::pulls::PullRequest) -> PullRequestInfo {
         let state = match pr.state {
             Some(IssueState::Open) => MergeStatus::Open,
@@ -324,7 +586,6 @@ impl GitHubService {
     }
 
     /// List repositories for the authenticated user with pagination
-    #[cfg(feature = "cloud")]
     pub async fn list_repositories(
         &self,
         page: u8,
@@ -348,7 +609,6 @@ impl GitHubService {
             .await
     }
 
-    #[cfg(feature = "cloud")]
     async fn list_repositories_internal(
         &self,
         page: u8,
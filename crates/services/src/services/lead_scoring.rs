@@ -0,0 +1,677 @@
+//! Lead scoring engine
+//!
+//! Scores every `crm_contact` from its project's [`LeadScoringRules`]: points
+//! for contact fields and lifecycle stage, decaying points for recent
+//! `crm_activity`, email replies and social mentions, a frequency and a
+//! recency bonus, and the value of the contact's open deals. Manual
+//! adjustments made through the API are added on top.
+//!
+//! Contacts are rescored when something happens to them and on a schedule so
+//! that decay is applied to contacts that have gone quiet. Every change is
+//! written to `lead_score_history` with the breakdown that produced it.
+
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::{DateTime, Utc};
+use db::models::{
+    crm_activity::{CrmActivity, CrmActivityError},
+    crm_contact::{CrmContact, CrmContactError},
+    crm_deal::{CrmDeal, CrmDealError},
+    email_message::{EmailMessage, EmailMessageError},
+    lead_scoring::{
+        FieldRule, FieldRuleOperator, LeadScoreBreakdown, LeadScoreCategory, LeadScoreComponent,
+        LeadScoreHistory, LeadScoreTrigger, LeadScoringConfig, LeadScoringError, LeadScoringRules,
+    },
+    social_mention::{SocialMention, SocialMentionError},
+};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use tokio::{task::JoinHandle, time::interval};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+/// How often projects are checked against their `recalc_interval_minutes`
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(5 * 60);
+const SECONDS_PER_DAY: f64 = 86_400.0;
+
+#[derive(Debug, Error)]
+pub enum LeadScoringServiceError {
+    #[error(transparent)]
+    Scoring(#[from] LeadScoringError),
+    #[error(transparent)]
+    Contact(#[from] CrmContactError),
+    #[error(transparent)]
+    Activity(#[from] CrmActivityError),
+    #[error(transparent)]
+    Deal(#[from] CrmDealError),
+    #[error(transparent)]
+    EmailMessage(#[from] EmailMessageError),
+    #[error(transparent)]
+    SocialMention(#[from] SocialMentionError),
+}
+
+/// The facts about a contact that the rules are applied to
+#[derive(Debug, Clone, Default)]
+pub struct ScoringInputs {
+    /// Non-empty contact fields by column name, custom fields as `custom_fields.<key>`
+    pub fields: BTreeMap<String, String>,
+    pub lifecycle_stage: String,
+    pub last_activity_at: Option<DateTime<Utc>>,
+    /// Activity type and time of each activity in the lookback window
+    pub activities: Vec<(String, DateTime<Utc>)>,
+    pub replies: Vec<DateTime<Utc>>,
+    pub mentions: Vec<DateTime<Utc>>,
+    pub open_deal_amount: f64,
+    pub manual_adjustment: i32,
+}
+
+fn round_points(points: f64) -> f64 {
+    (points * 100.0).round() / 100.0
+}
+
+/// Weight of an event that happened `at`: 1.0 now, 0.5 one half-life ago
+fn decay(rules: &LeadScoringRules, at: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let age_days = (now - at).num_seconds().max(0) as f64 / SECONDS_PER_DAY;
+    0.5_f64.powf(age_days / rules.half_life_days)
+}
+
+fn field_rule_matches(rule: &FieldRule, fields: &BTreeMap<String, String>) -> bool {
+    let value = fields.get(&rule.field).map(|v| v.to_lowercase());
+    let expected = rule.value.as_deref().unwrap_or_default().to_lowercase();
+    match rule.operator {
+        FieldRuleOperator::Present => value.is_some(),
+        FieldRuleOperator::Missing => value.is_none(),
+        FieldRuleOperator::Equals => value.is_some_and(|v| v == expected),
+        FieldRuleOperator::Contains => value.is_some_and(|v| v.contains(&expected)),
+    }
+}
+
+fn field_rule_label(rule: &FieldRule) -> String {
+    if let Some(label) = &rule.label {
+        return label.clone();
+    }
+    match (rule.operator, &rule.value) {
+        (FieldRuleOperator::Present, _) => format!("Has {}", rule.field),
+        (FieldRuleOperator::Missing, _) => format!("No {}", rule.field),
+        (FieldRuleOperator::Equals, Some(value)) => format!("{} is \"{}\"", rule.field, value),
+        (FieldRuleOperator::Contains, Some(value)) => {
+            format!("{} contains \"{}\"", rule.field, value)
+        }
+        (_, None) => rule.field.clone(),
+    }
+}
+
+/// Sum of decayed per-event points, capped
+fn decayed_events(
+    rules: &LeadScoringRules,
+    events: &[DateTime<Utc>],
+    points: f64,
+    max_points: f64,
+    now: DateTime<Utc>,
+) -> f64 {
+    events
+        .iter()
+        .map(|at| points * decay(rules, *at, now))
+        .sum::<f64>()
+        .min(max_points)
+}
+
+/// Apply the rules to a contact's inputs
+pub fn compute_score(
+    crm_contact_id: Uuid,
+    rules: &LeadScoringRules,
+    inputs: &ScoringInputs,
+    now: DateTime<Utc>,
+) -> LeadScoreBreakdown {
+    let mut components = Vec::new();
+    let mut push = |category, label: String, points: f64| {
+        let points = round_points(points);
+        if points != 0.0 {
+            components.push(LeadScoreComponent {
+                category,
+                label,
+                points,
+            });
+        }
+    };
+
+    for rule in &rules.field_rules {
+        if field_rule_matches(rule, &inputs.fields) {
+            push(
+                LeadScoreCategory::Field,
+                field_rule_label(rule),
+                f64::from(rule.points),
+            );
+        }
+    }
+
+    if let Some(points) = rules.lifecycle_points.get(&inputs.lifecycle_stage) {
+        push(
+            LeadScoreCategory::Lifecycle,
+            format!("Lifecycle stage {}", inputs.lifecycle_stage),
+            f64::from(*points),
+        );
+    }
+
+    let lookback_start = now - chrono::Duration::days(rules.lookback_days);
+    let activities: Vec<&(String, DateTime<Utc>)> = inputs
+        .activities
+        .iter()
+        .filter(|(_, at)| *at >= lookback_start)
+        .collect();
+
+    let mut by_type: BTreeMap<&str, (usize, f64)> = BTreeMap::new();
+    for (activity_type, at) in &activities {
+        if let Some(points) = rules.activity_points.get(activity_type) {
+            let entry = by_type.entry(activity_type.as_str()).or_default();
+            entry.0 += 1;
+            entry.1 += points * decay(rules, *at, now);
+        }
+    }
+    for (activity_type, (count, points)) in by_type {
+        push(
+            LeadScoreCategory::Activity,
+            format!("{} × {}", count, activity_type),
+            points,
+        );
+    }
+
+    let window_start = now - chrono::Duration::days(rules.frequency.window_days);
+    let recent = activities
+        .iter()
+        .filter(|(_, at)| *at >= window_start)
+        .count();
+    if recent > 0 {
+        push(
+            LeadScoreCategory::Frequency,
+            format!(
+                "{} activities in the last {} days",
+                recent, rules.frequency.window_days
+            ),
+            (recent as f64 * rules.frequency.points_per_activity).min(rules.frequency.max_points),
+        );
+    }
+
+    let last_activity = activities
+        .iter()
+        .map(|(_, at)| *at)
+        .chain(inputs.last_activity_at)
+        .max();
+    if let Some(last_activity) = last_activity
+        && let Some(tier) = rules
+            .recency
+            .iter()
+            .find(|tier| last_activity >= now - chrono::Duration::days(tier.within_days))
+    {
+        push(
+            LeadScoreCategory::Recency,
+            format!("Active in the last {} days", tier.within_days),
+            f64::from(tier.points),
+        );
+    }
+
+    let replies: Vec<DateTime<Utc>> = inputs
+        .replies
+        .iter()
+        .copied()
+        .filter(|at| *at >= lookback_start)
+        .collect();
+    if !replies.is_empty() {
+        push(
+            LeadScoreCategory::EmailReplies,
+            format!("{} email replies", replies.len()),
+            decayed_events(
+                rules,
+                &replies,
+                rules.email_replies.points,
+                rules.email_replies.max_points,
+                now,
+            ),
+        );
+    }
+
+    if let Some(tier) = rules
+        .deal_value
+        .iter()
+        .filter(|tier| inputs.open_deal_amount >= tier.min_amount)
+        .max_by(|a, b| a.min_amount.total_cmp(&b.min_amount))
+    {
+        push(
+            LeadScoreCategory::DealValue,
+            format!("Open deals worth {:.0}", inputs.open_deal_amount),
+            f64::from(tier.points),
+        );
+    }
+
+    let mentions: Vec<DateTime<Utc>> = inputs
+        .mentions
+        .iter()
+        .copied()
+        .filter(|at| *at >= lookback_start)
+        .collect();
+    if !mentions.is_empty() {
+        push(
+            LeadScoreCategory::SocialMentions,
+            format!("{} social mentions", mentions.len()),
+            decayed_events(
+                rules,
+                &mentions,
+                rules.social_mentions.points,
+                rules.social_mentions.max_points,
+                now,
+            ),
+        );
+    }
+
+    if inputs.manual_adjustment != 0 {
+        push(
+            LeadScoreCategory::ManualAdjustment,
+            "Manual adjustment".to_string(),
+            f64::from(inputs.manual_adjustment),
+        );
+    }
+
+    let raw_points = round_points(components.iter().map(|c| c.points).sum());
+    LeadScoreBreakdown {
+        crm_contact_id,
+        score: (raw_points.round() as i32).clamp(0, rules.max_score),
+        raw_points,
+        components,
+        computed_at: now,
+    }
+}
+
+#[derive(Clone)]
+pub struct LeadScoringService {
+    pool: SqlitePool,
+}
+
+impl LeadScoringService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Recalculate due projects in the background so scores decay over time
+    pub async fn spawn(pool: SqlitePool) -> JoinHandle<()> {
+        let service = Self::new(pool);
+        tokio::spawn(async move {
+            service.start().await;
+        })
+    }
+
+    async fn start(&self) {
+        info!(
+            "Starting lead scoring service with interval {:?}",
+            SCHEDULE_INTERVAL
+        );
+
+        let mut interval = interval(SCHEDULE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let project_ids = match LeadScoringConfig::find_due_project_ids(&self.pool).await {
+                Ok(ids) => ids,
+                Err(e) => {
+                    error!("Failed to load projects due for lead scoring: {}", e);
+                    continue;
+                }
+            };
+            for project_id in project_ids {
+                match self.run_scheduled(project_id).await {
+                    Ok(0) => {}
+                    Ok(changed) => info!(
+                        "Rescored leads of project {}: {} scores changed",
+                        project_id, changed
+                    ),
+                    Err(e) => error!("Lead scoring failed for project {}: {}", project_id, e),
+                }
+            }
+        }
+    }
+
+    async fn run_scheduled(&self, project_id: Uuid) -> Result<usize, LeadScoringServiceError> {
+        let config = LeadScoringConfig::find_or_create(&self.pool, project_id).await?;
+        let changed = self
+            .rescore_project(project_id, LeadScoreTrigger::Schedule)
+            .await?;
+        LeadScoringConfig::mark_recalculated(&self.pool, config.id).await?;
+        Ok(changed)
+    }
+
+    async fn rules_for(
+        &self,
+        project_id: Uuid,
+    ) -> Result<(LeadScoringRules, bool), LeadScoringServiceError> {
+        Ok(
+            match LeadScoringConfig::find_by_project(&self.pool, project_id).await? {
+                Some(config) => (config.rules.0, config.is_enabled),
+                None => (LeadScoringRules::default(), true),
+            },
+        )
+    }
+
+    async fn load_inputs(
+        &self,
+        contact: &CrmContact,
+        rules: &LeadScoringRules,
+        now: DateTime<Utc>,
+    ) -> Result<ScoringInputs, LeadScoringServiceError> {
+        let since = now - chrono::Duration::days(rules.lookback_days);
+
+        let activities = CrmActivity::find_by_contact_since(&self.pool, contact.id, since).await?;
+        let replies = EmailMessage::find_replies_from_contact(&self.pool, contact.id, since)
+            .await?
+            .into_iter()
+            .map(|message| message.received_at)
+            .collect();
+
+        // Mentions already on the timeline are scored as activity
+        let mut mentions = Vec::new();
+        if let Some(handle) = contact
+            .twitter_handle
+            .as_deref()
+            .filter(|h| !h.trim().is_empty())
+        {
+            for mention in
+                SocialMention::find_by_author(&self.pool, contact.project_id, handle, since).await?
+            {
+                if !activities
+                    .iter()
+                    .any(|activity| activity.social_mention_id == Some(mention.id))
+                {
+                    mentions.push(mention.received_at);
+                }
+            }
+        }
+
+        let open_deal_amount = CrmDeal::find_open_by_contact(&self.pool, contact.id)
+            .await?
+            .iter()
+            .filter_map(|deal| deal.amount)
+            .sum();
+
+        Ok(ScoringInputs {
+            fields: contact.field_values(),
+            lifecycle_stage: contact.lifecycle_stage.clone(),
+            last_activity_at: contact.last_activity_at,
+            activities: activities
+                .into_iter()
+                .map(|activity| (activity.activity_type, activity.activity_at))
+                .collect(),
+            replies,
+            mentions,
+            open_deal_amount,
+            manual_adjustment: contact.lead_score_adjustment,
+        })
+    }
+
+    /// Compute a contact's score with its breakdown without storing it
+    pub async fn explain(
+        &self,
+        contact_id: Uuid,
+    ) -> Result<LeadScoreBreakdown, LeadScoringServiceError> {
+        let contact = CrmContact::find_by_id(&self.pool, contact_id).await?;
+        let (rules, _) = self.rules_for(contact.project_id).await?;
+        let now = Utc::now();
+        let inputs = self.load_inputs(&contact, &rules, now).await?;
+        Ok(compute_score(contact.id, &rules, &inputs, now))
+    }
+
+    /// Recalculate and store a contact's score. Nothing is stored while
+    /// scoring is disabled for the project.
+    pub async fn rescore_contact(
+        &self,
+        contact_id: Uuid,
+        trigger: LeadScoreTrigger,
+    ) -> Result<LeadScoreBreakdown, LeadScoringServiceError> {
+        let contact = CrmContact::find_by_id(&self.pool, contact_id).await?;
+        let (rules, enabled) = self.rules_for(contact.project_id).await?;
+        let (breakdown, _) = self.rescore(&contact, &rules, enabled, trigger).await?;
+        Ok(breakdown)
+    }
+
+    /// Add manual points to a contact. The adjustment is kept across
+    /// recalculations and the change is recorded in the history.
+    pub async fn adjust_contact(
+        &self,
+        contact_id: Uuid,
+        score_delta: i32,
+    ) -> Result<LeadScoreBreakdown, LeadScoringServiceError> {
+        let previous_score = CrmContact::find_by_id(&self.pool, contact_id)
+            .await?
+            .lead_score;
+        CrmContact::update_lead_score(&self.pool, contact_id, score_delta).await?;
+
+        let contact = CrmContact::find_by_id(&self.pool, contact_id).await?;
+        let (rules, enabled) = self.rules_for(contact.project_id).await?;
+        let now = Utc::now();
+        let inputs = self.load_inputs(&contact, &rules, now).await?;
+        let breakdown = compute_score(contact.id, &rules, &inputs, now);
+
+        if enabled {
+            CrmContact::set_lead_score(&self.pool, contact.id, breakdown.score).await?;
+            if breakdown.score != previous_score {
+                LeadScoreHistory::create(
+                    &self.pool,
+                    contact.project_id,
+                    previous_score,
+                    LeadScoreTrigger::Manual,
+                    &breakdown,
+                )
+                .await?;
+            }
+        }
+
+        Ok(breakdown)
+    }
+
+    /// Recalculate every contact of a project; returns how many scores changed
+    pub async fn rescore_project(
+        &self,
+        project_id: Uuid,
+        trigger: LeadScoreTrigger,
+    ) -> Result<usize, LeadScoringServiceError> {
+        let (rules, enabled) = self.rules_for(project_id).await?;
+        if !enabled {
+            return Ok(0);
+        }
+
+        let mut changed = 0;
+        for contact_id in CrmContact::find_ids_by_project(&self.pool, project_id).await? {
+            let contact = match CrmContact::find_by_id(&self.pool, contact_id).await {
+                Ok(contact) => contact,
+                // Deleted while the project was being rescored
+                Err(CrmContactError::NotFound) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            match self.rescore(&contact, &rules, enabled, trigger).await {
+                Ok((_, true)) => changed += 1,
+                Ok((_, false)) => {}
+                Err(e) => warn!("Failed to score contact {}: {}", contact.id, e),
+            }
+        }
+
+        Ok(changed)
+    }
+
+    async fn rescore(
+        &self,
+        contact: &CrmContact,
+        rules: &LeadScoringRules,
+        enabled: bool,
+        trigger: LeadScoreTrigger,
+    ) -> Result<(LeadScoreBreakdown, bool), LeadScoringServiceError> {
+        let now = Utc::now();
+        let inputs = self.load_inputs(contact, rules, now).await?;
+        let breakdown = compute_score(contact.id, rules, &inputs, now);
+
+        let changed = enabled && breakdown.score != contact.lead_score;
+        if changed {
+            CrmContact::set_lead_score(&self.pool, contact.id, breakdown.score).await?;
+            LeadScoreHistory::create(
+                &self.pool,
+                contact.project_id,
+                contact.lead_score,
+                trigger,
+                &breakdown,
+            )
+            .await?;
+        }
+
+        Ok((breakdown, changed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use db::models::lead_scoring::{DealValueTier, RecencyTier};
+
+    use super::*;
+
+    fn days_ago(now: DateTime<Utc>, days: i64) -> DateTime<Utc> {
+        now - chrono::Duration::days(days)
+    }
+
+    fn points(breakdown: &LeadScoreBreakdown, category: LeadScoreCategory) -> f64 {
+        breakdown
+            .components
+            .iter()
+            .filter(|c| c.category == category)
+            .map(|c| c.points)
+            .sum()
+    }
+
+    #[test]
+    fn scores_fields_and_lifecycle() {
+        let now = Utc::now();
+        let rules = LeadScoringRules {
+            field_rules: vec![FieldRule {
+                field: "job_title".into(),
+                operator: FieldRuleOperator::Contains,
+                value: Some("Director".into()),
+                points: 8,
+                label: None,
+            }],
+            ..Default::default()
+        };
+        let inputs = ScoringInputs {
+            fields: [("job_title".to_string(), "Marketing director".to_string())].into(),
+            lifecycle_stage: "mql".into(),
+            ..Default::default()
+        };
+
+        let breakdown = compute_score(Uuid::new_v4(), &rules, &inputs, now);
+        assert_eq!(points(&breakdown, LeadScoreCategory::Field), 8.0);
+        assert_eq!(points(&breakdown, LeadScoreCategory::Lifecycle), 15.0);
+        assert_eq!(breakdown.score, 23);
+    }
+
+    #[test]
+    fn activity_points_halve_every_half_life() {
+        let now = Utc::now();
+        let rules = LeadScoringRules {
+            half_life_days: 10.0,
+            ..Default::default()
+        };
+        let inputs = ScoringInputs {
+            activities: vec![
+                ("email_received".into(), now),
+                ("email_received".into(), days_ago(now, 10)),
+                ("email_received".into(), days_ago(now, 365)),
+            ],
+            ..Default::default()
+        };
+
+        let breakdown = compute_score(Uuid::new_v4(), &rules, &inputs, now);
+        // 6 + 3, the year-old one is outside the lookback window
+        assert_eq!(points(&breakdown, LeadScoreCategory::Activity), 9.0);
+        assert_eq!(points(&breakdown, LeadScoreCategory::Frequency), 2.0);
+        assert_eq!(points(&breakdown, LeadScoreCategory::Recency), 10.0);
+    }
+
+    #[test]
+    fn caps_replies_and_frequency() {
+        let now = Utc::now();
+        let rules = LeadScoringRules::default();
+        let inputs = ScoringInputs {
+            activities: (0..50).map(|_| ("page_visited".into(), now)).collect(),
+            replies: (0..10).map(|_| now).collect(),
+            ..Default::default()
+        };
+
+        let breakdown = compute_score(Uuid::new_v4(), &rules, &inputs, now);
+        assert_eq!(
+            points(&breakdown, LeadScoreCategory::Frequency),
+            rules.frequency.max_points
+        );
+        assert_eq!(
+            points(&breakdown, LeadScoreCategory::EmailReplies),
+            rules.email_replies.max_points
+        );
+    }
+
+    #[test]
+    fn picks_first_recency_tier_and_highest_deal_tier() {
+        let now = Utc::now();
+        let rules = LeadScoringRules {
+            recency: vec![
+                RecencyTier {
+                    within_days: 7,
+                    points: 10,
+                },
+                RecencyTier {
+                    within_days: 30,
+                    points: 4,
+                },
+            ],
+            deal_value: vec![
+                DealValueTier {
+                    min_amount: 1_000.0,
+                    points: 5,
+                },
+                DealValueTier {
+                    min_amount: 10_000.0,
+                    points: 12,
+                },
+            ],
+            ..Default::default()
+        };
+        let inputs = ScoringInputs {
+            last_activity_at: Some(days_ago(now, 20)),
+            open_deal_amount: 25_000.0,
+            ..Default::default()
+        };
+
+        let breakdown = compute_score(Uuid::new_v4(), &rules, &inputs, now);
+        assert_eq!(points(&breakdown, LeadScoreCategory::Recency), 4.0);
+        assert_eq!(points(&breakdown, LeadScoreCategory::DealValue), 12.0);
+    }
+
+    #[test]
+    fn clamps_to_score_range() {
+        let now = Utc::now();
+        let rules = LeadScoringRules {
+            max_score: 20,
+            ..Default::default()
+        };
+
+        let churned = ScoringInputs {
+            lifecycle_stage: "churned".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            compute_score(Uuid::new_v4(), &rules, &churned, now).score,
+            0
+        );
+
+        let adjusted = ScoringInputs {
+            lifecycle_stage: "customer".into(),
+            manual_adjustment: 50,
+            ..Default::default()
+        };
+        let breakdown = compute_score(Uuid::new_v4(), &rules, &adjusted, now);
+        assert_eq!(breakdown.score, 20);
+        assert_eq!(breakdown.raw_points, 85.0);
+    }
+}
@@ -0,0 +1,472 @@
+//! Cold storage and retention for execution logs
+//!
+//! `execution_process_logs` keeps one JSONL blob per execution inside
+//! `db.sqlite`. Once an execution has finished and its project's
+//! `archive_after_hours` has passed, the blob is split into line-aligned
+//! segments, compressed with zstd and written under `<asset_dir>/log_archive`.
+//! The segment index lives in `execution_log_segments`. [`LogArchiveService::load_logs`]
+//! transparently reads either copy, so log replay keeps producing the same
+//! `LogMsg` stream regardless of where the log is stored.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::Utc;
+use db::models::{
+    execution_log_archive::{
+        CreateExecutionLogSegment, ExecutionLogSegment, LogRetentionPolicy, LogVacuumRun,
+    },
+    execution_process_logs::ExecutionProcessLogs,
+};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use thiserror::Error;
+use tokio::time::interval;
+use tracing::{debug, error, info, warn};
+use utils::assets::asset_dir;
+use uuid::Uuid;
+
+/// Uncompressed size a segment is cut at (always on a line boundary)
+const SEGMENT_TARGET_BYTES: usize = 4 * 1024 * 1024;
+const ZSTD_LEVEL: i32 = 9;
+/// Executions processed per retention pass
+const RETENTION_BATCH: i64 = 200;
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Only VACUUM automatically once this much of the database file is free pages
+const AUTO_VACUUM_MIN_FREE_BYTES: i64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum LogArchiveError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Corrupt log segment {path}: {reason}")]
+    CorruptSegment { path: String, reason: String },
+    #[error("Background task failed: {0}")]
+    Join(#[from] tokio::task::JoinError),
+}
+
+/// Result of moving one execution to cold storage
+#[derive(Debug, Clone)]
+pub struct ArchivedExecution {
+    pub execution_id: Uuid,
+    pub segments: usize,
+    pub uncompressed_bytes: i64,
+    pub compressed_bytes: i64,
+}
+
+/// A line-aligned slice of a JSONL log
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SegmentSlice<'a> {
+    first_line: usize,
+    line_count: usize,
+    text: &'a str,
+}
+
+/// Split JSONL into slices of roughly `target` bytes without breaking lines.
+/// A single line larger than `target` gets a segment of its own.
+fn split_segments(logs: &str, target: usize) -> Vec<SegmentSlice<'_>> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut first_line = 0;
+    let mut line_count = 0;
+    let mut pos = 0;
+
+    for line in logs.split_inclusive('\n') {
+        if line_count > 0 && pos + line.len() - start > target {
+            segments.push(SegmentSlice {
+                first_line,
+                line_count,
+                text: &logs[start..pos],
+            });
+            start = pos;
+            first_line += line_count;
+            line_count = 0;
+        }
+        pos += line.len();
+        line_count += 1;
+    }
+
+    if line_count > 0 {
+        segments.push(SegmentSlice {
+            first_line,
+            line_count,
+            text: &logs[start..pos],
+        });
+    }
+
+    segments
+}
+
+fn compress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    zstd::stream::encode_all(data, ZSTD_LEVEL)
+}
+
+fn decompress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    zstd::stream::decode_all(data)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Relative location of a segment file inside the archive root
+fn segment_relative_path(execution_id: Uuid, segment_index: usize) -> String {
+    let id = execution_id.to_string();
+    format!("{}/{}/{:05}.jsonl.zst", &id[..2], id, segment_index)
+}
+
+pub fn archive_root() -> PathBuf {
+    asset_dir().join("log_archive")
+}
+
+/// Load an execution's logs from the database or, if archived, from cold storage
+pub async fn load_execution_logs(
+    pool: &SqlitePool,
+    execution_id: Uuid,
+) -> Result<Option<ExecutionProcessLogs>, LogArchiveError> {
+    LogArchiveService::new(pool.clone())
+        .load_logs(execution_id)
+        .await
+}
+
+#[derive(Clone)]
+pub struct LogArchiveService {
+    pool: SqlitePool,
+    root: PathBuf,
+}
+
+impl LogArchiveService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self::with_root(pool, archive_root())
+    }
+
+    pub fn with_root(pool: SqlitePool, root: PathBuf) -> Self {
+        Self { pool, root }
+    }
+
+    /// Run retention every hour in the background
+    pub async fn spawn(pool: SqlitePool) -> tokio::task::JoinHandle<()> {
+        let service = Self::new(pool);
+        tokio::spawn(async move {
+            service.start().await;
+        })
+    }
+
+    async fn start(&self) {
+        info!(
+            "Starting log retention service with interval {:?}",
+            RETENTION_INTERVAL
+        );
+
+        let mut interval = interval(RETENTION_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            match self.run_retention(false).await {
+                Ok(run) => {
+                    if run.executions_archived > 0 || run.executions_deleted > 0 || run.vacuumed {
+                        info!(
+                            "Log retention: archived {} ({} -> {} bytes), deleted {}, reclaimed {} bytes",
+                            run.executions_archived,
+                            run.bytes_archived,
+                            run.bytes_compressed,
+                            run.executions_deleted,
+                            run.db_bytes_reclaimed()
+                        );
+                    }
+                }
+                Err(e) => error!("Log retention pass failed: {}", e),
+            }
+        }
+    }
+
+    /// Load the logs of an execution from whichever tier currently holds them
+    pub async fn load_logs(
+        &self,
+        execution_id: Uuid,
+    ) -> Result<Option<ExecutionProcessLogs>, LogArchiveError> {
+        if let Some(hot) =
+            ExecutionProcessLogs::find_by_execution_id(&self.pool, execution_id).await?
+        {
+            return Ok(Some(hot));
+        }
+
+        let segments = ExecutionLogSegment::find_by_execution_id(&self.pool, execution_id).await?;
+        let Some(first) = segments.first() else {
+            return Ok(None);
+        };
+        let inserted_at = first.created_at;
+
+        let mut logs = String::new();
+        for segment in &segments {
+            logs.push_str(&self.read_segment(segment).await?);
+        }
+
+        Ok(Some(ExecutionProcessLogs {
+            execution_id,
+            byte_size: logs.len() as i64,
+            logs,
+            inserted_at,
+        }))
+    }
+
+    async fn read_segment(&self, segment: &ExecutionLogSegment) -> Result<String, LogArchiveError> {
+        let path = self.root.join(&segment.path);
+        let compressed = tokio::fs::read(&path).await?;
+
+        if sha256_hex(&compressed) != segment.sha256 {
+            return Err(LogArchiveError::CorruptSegment {
+                path: segment.path.clone(),
+                reason: "checksum mismatch".to_string(),
+            });
+        }
+
+        let raw = tokio::task::spawn_blocking(move || decompress(&compressed)).await??;
+        String::from_utf8(raw).map_err(|e| LogArchiveError::CorruptSegment {
+            path: segment.path.clone(),
+            reason: e.to_string(),
+        })
+    }
+
+    /// Move a finished execution's logs to compressed segment files. Returns
+    /// `None` if there is nothing in the hot table to archive.
+    pub async fn archive_execution(
+        &self,
+        execution_id: Uuid,
+    ) -> Result<Option<ArchivedExecution>, LogArchiveError> {
+        let Some(hot) =
+            ExecutionProcessLogs::find_by_execution_id(&self.pool, execution_id).await?
+        else {
+            return Ok(None);
+        };
+
+        // A previous pass may have died between writing files and committing
+        if ExecutionLogSegment::exists_for_execution(&self.pool, execution_id).await? {
+            warn!(
+                "Execution {} has both hot and archived logs; replacing archive",
+                execution_id
+            );
+            self.delete_cold(execution_id).await?;
+        }
+
+        let root = self.root.clone();
+        let logs = hot.logs;
+        let segments =
+            tokio::task::spawn_blocking(move || write_segments(&root, execution_id, &logs))
+                .await??;
+
+        let uncompressed_bytes = segments.iter().map(|s| s.uncompressed_bytes).sum();
+        let compressed_bytes = segments.iter().map(|s| s.compressed_bytes).sum();
+
+        if let Err(e) =
+            ExecutionLogSegment::commit_archive(&self.pool, execution_id, &segments).await
+        {
+            // Leave the hot copy authoritative and clean up the orphaned files
+            for segment in &segments {
+                let _ = tokio::fs::remove_file(self.root.join(&segment.path)).await;
+            }
+            return Err(e.into());
+        }
+
+        debug!(
+            "Archived logs for execution {} into {} segment(s)",
+            execution_id,
+            segments.len()
+        );
+
+        Ok(Some(ArchivedExecution {
+            execution_id,
+            segments: segments.len(),
+            uncompressed_bytes,
+            compressed_bytes,
+        }))
+    }
+
+    async fn delete_cold(&self, execution_id: Uuid) -> Result<i64, LogArchiveError> {
+        let segments = ExecutionLogSegment::find_by_execution_id(&self.pool, execution_id).await?;
+        let mut freed = 0;
+        for segment in &segments {
+            match tokio::fs::remove_file(self.root.join(&segment.path)).await {
+                Ok(()) => freed += segment.compressed_bytes,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        ExecutionLogSegment::delete_for_execution(&self.pool, execution_id).await?;
+        if let Some(segment) = segments.first()
+            && let Some(dir) = Path::new(&segment.path).parent()
+        {
+            let _ = tokio::fs::remove_dir(self.root.join(dir)).await;
+        }
+        Ok(freed)
+    }
+
+    /// Delete an execution's logs from both tiers, returning bytes freed
+    pub async fn delete_logs(&self, execution_id: Uuid) -> Result<i64, LogArchiveError> {
+        let hot = ExecutionProcessLogs::delete(&self.pool, execution_id).await?;
+        let cold = self.delete_cold(execution_id).await?;
+        Ok(hot + cold)
+    }
+
+    async fn database_bytes(&self) -> Result<(i64, i64), sqlx::Error> {
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(&self.pool)
+            .await?;
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(&self.pool)
+            .await?;
+        let freelist: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok((page_size * page_count, page_size * freelist))
+    }
+
+    /// Archive due executions, delete expired ones and VACUUM when worthwhile
+    /// (or always, when `force_vacuum` is set). The run is recorded in
+    /// `log_vacuum_runs` and returned.
+    pub async fn run_retention(&self, force_vacuum: bool) -> Result<LogVacuumRun, LogArchiveError> {
+        let started_at = Utc::now();
+        let (db_bytes_before, _) = self.database_bytes().await?;
+        let mut run = LogVacuumRun {
+            id: Uuid::new_v4(),
+            db_bytes_before,
+            started_at: Some(started_at),
+            ..Default::default()
+        };
+
+        let mut errors = Vec::new();
+
+        for expired in LogRetentionPolicy::find_expired(&self.pool, RETENTION_BATCH).await? {
+            match self.delete_logs(expired.execution_id).await {
+                Ok(freed) => {
+                    run.executions_deleted += 1;
+                    run.bytes_deleted += freed;
+                }
+                Err(e) => errors.push(format!("delete {}: {}", expired.execution_id, e)),
+            }
+        }
+
+        for candidate in
+            LogRetentionPolicy::find_archive_candidates(&self.pool, RETENTION_BATCH).await?
+        {
+            match self.archive_execution(candidate.execution_id).await {
+                Ok(Some(archived)) => {
+                    run.executions_archived += 1;
+                    run.bytes_archived += archived.uncompressed_bytes;
+                    run.bytes_compressed += archived.compressed_bytes;
+                }
+                Ok(None) => {}
+                Err(e) => errors.push(format!("archive {}: {}", candidate.execution_id, e)),
+            }
+        }
+
+        let (_, free_bytes) = self.database_bytes().await?;
+        if force_vacuum || free_bytes >= AUTO_VACUUM_MIN_FREE_BYTES {
+            match sqlx::query("VACUUM").execute(&self.pool).await {
+                Ok(_) => run.vacuumed = true,
+                Err(e) => errors.push(format!("vacuum: {}", e)),
+            }
+        }
+
+        let (db_bytes_after, _) = self.database_bytes().await?;
+        run.db_bytes_after = db_bytes_after;
+        if !errors.is_empty() {
+            run.error = Some(errors.join("; "));
+        }
+
+        Ok(LogVacuumRun::record(&self.pool, &run).await?)
+    }
+}
+
+/// Compress and write every segment of one execution, returning index rows
+fn write_segments(
+    root: &Path,
+    execution_id: Uuid,
+    logs: &str,
+) -> std::io::Result<Vec<CreateExecutionLogSegment>> {
+    let mut written = Vec::new();
+
+    for (index, slice) in split_segments(logs, SEGMENT_TARGET_BYTES)
+        .into_iter()
+        .enumerate()
+    {
+        let relative = segment_relative_path(execution_id, index);
+        let path = root.join(&relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let compressed = compress(slice.text.as_bytes())?;
+        let tmp = path.with_extension("zst.tmp");
+        std::fs::write(&tmp, &compressed)?;
+        std::fs::rename(&tmp, &path)?;
+
+        written.push(CreateExecutionLogSegment {
+            execution_id,
+            segment_index: index as i64,
+            path: relative,
+            first_line: slice.first_line as i64,
+            line_count: slice.line_count as i64,
+            uncompressed_bytes: slice.text.len() as i64,
+            compressed_bytes: compressed.len() as i64,
+            sha256: sha256_hex(&compressed),
+        });
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_segments_respects_line_boundaries() {
+        let logs = "aaaa\nbbbb\ncccc\ndddd\n";
+        let segments = split_segments(logs, 10);
+
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "aaaa\nbbbb\n");
+        assert_eq!(segments[0].first_line, 0);
+        assert_eq!(segments[0].line_count, 2);
+        assert_eq!(segments[1].text, "cccc\ndddd\n");
+        assert_eq!(segments[1].first_line, 2);
+
+        let joined: String = segments.iter().map(|s| s.text).collect();
+        assert_eq!(joined, logs);
+    }
+
+    #[test]
+    fn oversized_lines_get_their_own_segment() {
+        let logs = "short\nthis line is much longer than the target\nx\n";
+        let segments = split_segments(logs, 8);
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(
+            segments[1].text,
+            "this line is much longer than the target\n"
+        );
+        assert!(split_segments("", 8).is_empty());
+    }
+
+    #[test]
+    fn segments_round_trip_through_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let execution_id = Uuid::new_v4();
+        let logs: String = (0..2_000)
+            .map(|i| format!("{{\"Stdout\":\"line {i}\"}}\n"))
+            .collect();
+
+        let segments = write_segments(dir.path(), execution_id, &logs).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert!(segments[0].compressed_bytes < segments[0].uncompressed_bytes);
+
+        let compressed = std::fs::read(dir.path().join(&segments[0].path)).unwrap();
+        assert_eq!(sha256_hex(&compressed), segments[0].sha256);
+        assert_eq!(decompress(&compressed).unwrap(), logs.as_bytes());
+    }
+}
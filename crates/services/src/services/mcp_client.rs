@@ -0,0 +1,623 @@
+//! MCP client connections for agent tools
+//!
+//! Servers are configured in `<asset_dir>/mcp_servers.json` and reached over
+//! stdio (a spawned child process) or streamable HTTP. On connect each
+//! server's tools are discovered and given a qualified name,
+//! `mcp__<server>__<tool>`, which is what the LLM sees. The
+//! [`AgentToolRegistry`](super::agent_tools::AgentToolRegistry) registers those
+//! names with the configured per-agent [`ToolAccess`] and routes calls back
+//! through [`McpClientManager::call_tool`].
+//!
+//! ```json
+//! {
+//!   "servers": [
+//!     {
+//!       "name": "linear",
+//!       "transport": { "type": "stdio", "command": "npx", "args": ["-y", "mcp-remote", "https://mcp.linear.app/sse"] },
+//!       "agents": ["Nora", "Compass"]
+//!     },
+//!     {
+//!       "name": "notion",
+//!       "transport": { "type": "http", "url": "https://mcp.notion.com/mcp", "headers": { "Authorization": "Bearer ${NOTION_TOKEN}" } },
+//!       "access": "Execute",
+//!       "tools": ["search", "fetch"]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Values of the form `${VAR}` in `env` and `headers` are read from the
+//! server's environment so secrets stay out of the file.
+
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rmcp::{
+    RoleClient, ServiceExt,
+    model::{CallToolRequestParam, Tool},
+    service::RunningService,
+    transport::{
+        ConfigureCommandExt, IntoTransport, StreamableHttpClientTransport, TokioChildProcess,
+        streamable_http_client::StreamableHttpClientTransportConfig,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use tokio::{process::Command, sync::RwLock};
+use utils::assets::mcp_servers_path;
+
+use super::agent_tools::{AgentId, ToolAccess};
+
+/// Prefix of every tool name that is routed to an MCP server
+pub const MCP_TOOL_PREFIX: &str = "mcp__";
+/// OpenAI and Anthropic both cap function names at 64 characters
+const MAX_TOOL_NAME_LEN: usize = 64;
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+type McpClient = RunningService<RoleClient, ()>;
+
+#[derive(Debug, Error)]
+pub enum McpClientError {
+    #[error("Failed to read MCP server config: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid MCP server config: {0}")]
+    Config(String),
+    #[error("Failed to connect to MCP server {server}: {reason}")]
+    Connect { server: String, reason: String },
+    #[error("MCP server {0} is not connected")]
+    NotConnected(String),
+    #[error("Unknown MCP tool: {0}")]
+    UnknownTool(String),
+    #[error("Tool arguments must be a JSON object")]
+    InvalidArguments,
+    #[error("MCP call to {tool} failed: {reason}")]
+    Call { tool: String, reason: String },
+    #[error("MCP call to {0} timed out")]
+    Timeout(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum McpTransportConfig {
+    /// Spawn the server and speak MCP over its stdin/stdout
+    Stdio {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        env: HashMap<String, String>,
+        cwd: Option<String>,
+    },
+    /// Streamable HTTP endpoint
+    Http {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_agents() -> Vec<AgentId> {
+    vec![AgentId::Nora]
+}
+
+fn default_access() -> ToolAccess {
+    ToolAccess::Execute
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// Short identifier, used in qualified tool names
+    pub name: String,
+    pub transport: McpTransportConfig,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Agents that get access to this server's tools
+    #[serde(default = "default_agents")]
+    pub agents: Vec<AgentId>,
+    #[serde(default = "default_access")]
+    pub access: ToolAccess,
+    /// Only expose these tools (by their name on the server); all when unset
+    pub tools: Option<Vec<String>>,
+    /// Per-call timeout
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpServersConfig {
+    #[serde(default)]
+    pub servers: Vec<McpServerConfig>,
+}
+
+impl McpServersConfig {
+    /// Load the config, treating a missing file as "no servers"
+    pub fn load(path: &Path) -> Result<Self, McpClientError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let raw = std::fs::read_to_string(path)?;
+        let config: Self =
+            serde_json::from_str(&raw).map_err(|e| McpClientError::Config(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), McpClientError> {
+        let mut seen = std::collections::HashSet::new();
+        for server in &self.servers {
+            if sanitize_name(&server.name).is_empty() {
+                return Err(McpClientError::Config(format!(
+                    "server name '{}' must contain letters or digits",
+                    server.name
+                )));
+            }
+            if !seen.insert(sanitize_name(&server.name)) {
+                return Err(McpClientError::Config(format!(
+                    "duplicate server name '{}'",
+                    server.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A tool discovered on a connected MCP server
+#[derive(Debug, Clone, Serialize)]
+pub struct McpToolInfo {
+    /// Name exposed to the LLM, e.g. `mcp__linear__create_issue`
+    pub qualified_name: String,
+    pub server: String,
+    /// Name of the tool on the server
+    pub name: String,
+    pub description: String,
+    /// JSON Schema of the tool's arguments
+    pub input_schema: Value,
+    pub agents: Vec<AgentId>,
+    pub access: ToolAccess,
+}
+
+/// Result of a routed MCP tool call
+#[derive(Debug, Clone, Serialize)]
+pub struct McpToolOutput {
+    pub is_error: bool,
+    /// Text content blocks joined with newlines
+    pub text: String,
+    pub structured: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpServerStatus {
+    pub name: String,
+    pub enabled: bool,
+    pub connected: bool,
+    pub tool_count: usize,
+    pub error: Option<String>,
+}
+
+struct McpConnection {
+    config: McpServerConfig,
+    client: McpClient,
+    tools: Vec<McpToolInfo>,
+}
+
+#[derive(Default)]
+struct McpState {
+    configs: Vec<McpServerConfig>,
+    connections: HashMap<String, Arc<McpConnection>>,
+    /// Last connection error per server
+    errors: HashMap<String, String>,
+    /// Qualified tool name -> (server, tool name on the server)
+    routes: HashMap<String, (String, String)>,
+}
+
+impl McpState {
+    /// Route the connection's tools to it, replacing any earlier connection
+    /// of the same server
+    fn add(&mut self, name: String, connection: McpConnection) {
+        self.routes.retain(|_, (server, _)| *server != name);
+        for tool in &connection.tools {
+            self.routes.insert(
+                tool.qualified_name.clone(),
+                (name.clone(), tool.name.clone()),
+            );
+        }
+        self.errors.remove(&name);
+        self.connections.insert(name, Arc::new(connection));
+    }
+}
+
+/// Owns the client connections to all configured MCP servers
+#[derive(Clone, Default)]
+pub struct McpClientManager {
+    state: Arc<RwLock<McpState>>,
+}
+
+impl McpClientManager {
+    /// Connect to the servers in `<asset_dir>/mcp_servers.json`. A broken
+    /// config or unreachable server is logged and skipped.
+    pub async fn from_config_file() -> Self {
+        let manager = Self::default();
+        if let Err(e) = manager.reload().await {
+            tracing::error!("[MCP] {}", e);
+        }
+        manager
+    }
+
+    /// Re-read the config file and reconnect every server
+    pub async fn reload(&self) -> Result<(), McpClientError> {
+        let config = McpServersConfig::load(&mcp_servers_path())?;
+        self.connect_all(config.servers).await;
+        Ok(())
+    }
+
+    /// Replace all connections with the given servers
+    pub async fn connect_all(&self, configs: Vec<McpServerConfig>) {
+        let attempts = configs
+            .iter()
+            .filter(|config| config.enabled)
+            .map(|config| async move { (config.name.clone(), connect(config).await) });
+        let results = futures::future::join_all(attempts).await;
+
+        let mut state = McpState {
+            configs,
+            ..Default::default()
+        };
+        for (name, result) in results {
+            match result {
+                Ok(connection) => {
+                    tracing::info!(
+                        "[MCP] Connected to {} ({} tools)",
+                        name,
+                        connection.tools.len()
+                    );
+                    state.add(name, connection);
+                }
+                Err(e) => {
+                    tracing::warn!("[MCP] {}", e);
+                    state.errors.insert(name, e.to_string());
+                }
+            }
+        }
+
+        // Dropping the old clients closes their transports once in-flight
+        // calls release them
+        let previous = std::mem::replace(&mut *self.state.write().await, state);
+        drop(previous);
+    }
+
+    /// Connect one more server over an already open transport, such as an
+    /// in-process server. The config's `transport` is not used.
+    pub async fn connect_transport<T, E, A>(
+        &self,
+        config: McpServerConfig,
+        transport: T,
+    ) -> Result<(), McpClientError>
+    where
+        T: IntoTransport<RoleClient, E, A>,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let client = tokio::time::timeout(CONNECT_TIMEOUT, ().serve(transport))
+            .await
+            .map_err(|_| McpClientError::Connect {
+                server: config.name.clone(),
+                reason: "timed out during initialization".to_string(),
+            })?
+            .map_err(|e| McpClientError::Connect {
+                server: config.name.clone(),
+                reason: e.to_string(),
+            })?;
+        let connection = discover(&config, client).await?;
+
+        let mut state = self.state.write().await;
+        state.configs.retain(|c| c.name != config.name);
+        state.configs.push(config.clone());
+        state.add(config.name, connection);
+        Ok(())
+    }
+
+    /// Tools of every connected server
+    pub async fn tools(&self) -> Vec<McpToolInfo> {
+        let state = self.state.read().await;
+        let mut tools: Vec<McpToolInfo> = state
+            .connections
+            .values()
+            .flat_map(|connection| connection.tools.iter().cloned())
+            .collect();
+        tools.sort_by(|a, b| a.qualified_name.cmp(&b.qualified_name));
+        tools
+    }
+
+    pub async fn statuses(&self) -> Vec<McpServerStatus> {
+        let state = self.state.read().await;
+        state
+            .configs
+            .iter()
+            .map(|config| {
+                let connection = state.connections.get(&config.name);
+                McpServerStatus {
+                    name: config.name.clone(),
+                    enabled: config.enabled,
+                    connected: connection.is_some(),
+                    tool_count: connection.map(|c| c.tools.len()).unwrap_or(0),
+                    error: state.errors.get(&config.name).cloned(),
+                }
+            })
+            .collect()
+    }
+
+    /// Whether `name` is the qualified name of a discovered MCP tool
+    pub async fn has_tool(&self, name: &str) -> bool {
+        self.state.read().await.routes.contains_key(name)
+    }
+
+    /// Route a call to the server that owns `qualified_name`
+    pub async fn call_tool(
+        &self,
+        qualified_name: &str,
+        arguments: &Value,
+    ) -> Result<McpToolOutput, McpClientError> {
+        let arguments = match arguments {
+            Value::Object(map) => Some(map.clone()),
+            Value::Null => None,
+            _ => return Err(McpClientError::InvalidArguments),
+        };
+
+        let (connection, tool) = {
+            let state = self.state.read().await;
+            let (server, tool) = state
+                .routes
+                .get(qualified_name)
+                .cloned()
+                .ok_or_else(|| McpClientError::UnknownTool(qualified_name.to_string()))?;
+            let connection = state
+                .connections
+                .get(&server)
+                .cloned()
+                .ok_or(McpClientError::NotConnected(server))?;
+            (connection, tool)
+        };
+
+        let timeout = Duration::from_secs(
+            connection
+                .config
+                .timeout_secs
+                .unwrap_or(DEFAULT_TIMEOUT_SECS),
+        );
+        let request = CallToolRequestParam {
+            name: tool.clone().into(),
+            arguments,
+        };
+        let result = tokio::time::timeout(timeout, connection.client.call_tool(request))
+            .await
+            .map_err(|_| McpClientError::Timeout(qualified_name.to_string()))?
+            .map_err(|e| McpClientError::Call {
+                tool: qualified_name.to_string(),
+                reason: e.to_string(),
+            })?;
+
+        let text = result
+            .content
+            .iter()
+            .filter_map(|content| content.as_text().map(|t| t.text.clone()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        Ok(McpToolOutput {
+            is_error: result.is_error.unwrap_or(false),
+            text,
+            structured: result.structured_content,
+        })
+    }
+}
+
+async fn connect(config: &McpServerConfig) -> Result<McpConnection, McpClientError> {
+    let connect_error = |reason: String| McpClientError::Connect {
+        server: config.name.clone(),
+        reason,
+    };
+
+    let handshake = async {
+        match &config.transport {
+            McpTransportConfig::Stdio {
+                command,
+                args,
+                env,
+                cwd,
+            } => {
+                let command = Command::new(command).configure(|cmd| {
+                    cmd.args(args);
+                    for (key, value) in env {
+                        cmd.env(key, expand_env(value));
+                    }
+                    if let Some(cwd) = cwd {
+                        cmd.current_dir(cwd);
+                    }
+                });
+                let transport =
+                    TokioChildProcess::new(command).map_err(|e| connect_error(e.to_string()))?;
+                ().serve(transport)
+                    .await
+                    .map_err(|e| connect_error(e.to_string()))
+            }
+            McpTransportConfig::Http { url, headers } => {
+                let mut header_map = HeaderMap::new();
+                for (key, value) in headers {
+                    let name = HeaderName::from_bytes(key.as_bytes())
+                        .map_err(|e| connect_error(format!("header {key}: {e}")))?;
+                    let value = HeaderValue::from_str(&expand_env(value))
+                        .map_err(|e| connect_error(format!("header {key}: {e}")))?;
+                    header_map.insert(name, value);
+                }
+                let http = reqwest::Client::builder()
+                    .default_headers(header_map)
+                    .build()
+                    .map_err(|e| connect_error(e.to_string()))?;
+                let transport = StreamableHttpClientTransport::with_client(
+                    http,
+                    StreamableHttpClientTransportConfig::with_uri(url.as_str()),
+                );
+                ().serve(transport)
+                    .await
+                    .map_err(|e| connect_error(e.to_string()))
+            }
+        }
+    };
+    let client = tokio::time::timeout(CONNECT_TIMEOUT, handshake)
+        .await
+        .map_err(|_| connect_error("timed out during initialization".to_string()))??;
+
+    discover(config, client).await
+}
+
+/// List the tools of a freshly initialized client
+async fn discover(
+    config: &McpServerConfig,
+    client: McpClient,
+) -> Result<McpConnection, McpClientError> {
+    let tools = client
+        .list_all_tools()
+        .await
+        .map_err(|e| McpClientError::Connect {
+            server: config.name.clone(),
+            reason: format!("listing tools: {e}"),
+        })?
+        .into_iter()
+        .filter(|tool| {
+            config
+                .tools
+                .as_ref()
+                .is_none_or(|allowed| allowed.iter().any(|name| name == tool.name.as_ref()))
+        })
+        .map(|tool| tool_info(config, tool))
+        .collect();
+
+    Ok(McpConnection {
+        config: config.clone(),
+        client,
+        tools,
+    })
+}
+
+fn tool_info(config: &McpServerConfig, tool: Tool) -> McpToolInfo {
+    let description = tool
+        .description
+        .as_deref()
+        .unwrap_or("No description provided");
+    McpToolInfo {
+        qualified_name: qualified_tool_name(&config.name, &tool.name),
+        server: config.name.clone(),
+        name: tool.name.to_string(),
+        description: format!("[{} MCP] {}", config.name, description),
+        input_schema: Value::Object(tool.input_schema.as_ref().clone()),
+        agents: config.agents.clone(),
+        access: config.access,
+    }
+}
+
+/// `mcp__<server>__<tool>`, restricted to the characters and length that
+/// OpenAI and Anthropic accept for function names
+pub fn qualified_tool_name(server: &str, tool: &str) -> String {
+    let mut name = format!(
+        "{MCP_TOOL_PREFIX}{}__{}",
+        sanitize_name(server),
+        sanitize_name(tool)
+    );
+    name.truncate(MAX_TOOL_NAME_LEN);
+    name
+}
+
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('_')
+        .to_string()
+}
+
+/// Resolve `${VAR}` from the environment; other values are used as-is
+fn expand_env(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let var = &rest[start + 2..start + end];
+        out.push_str(&std::env::var(var).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn qualified_names_are_function_safe() {
+        assert_eq!(
+            qualified_tool_name("linear", "create_issue"),
+            "mcp__linear__create_issue"
+        );
+        assert_eq!(
+            qualified_tool_name("My Notion", "pages.search"),
+            "mcp__My_Notion__pages_search"
+        );
+        assert!(qualified_tool_name("server", &"x".repeat(100)).len() <= MAX_TOOL_NAME_LEN);
+    }
+
+    #[test]
+    fn expands_env_placeholders() {
+        // SAFETY: test-local variable name, no other thread reads it
+        unsafe { std::env::set_var("PCG_MCP_TEST_TOKEN", "secret") };
+        assert_eq!(expand_env("Bearer ${PCG_MCP_TEST_TOKEN}"), "Bearer secret");
+        assert_eq!(expand_env("plain"), "plain");
+        assert_eq!(expand_env("${PCG_MCP_TEST_MISSING}"), "");
+    }
+
+    #[test]
+    fn parses_server_config_with_defaults() {
+        let config: McpServersConfig = serde_json::from_str(
+            r#"{"servers": [
+                {"name": "linear", "transport": {"type": "stdio", "command": "npx", "args": ["-y", "linear-mcp"]}},
+                {"name": "notion", "transport": {"type": "http", "url": "https://example.com/mcp"}, "agents": ["Scribe"], "access": "Full"}
+            ]}"#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+
+        assert!(config.servers[0].enabled);
+        assert_eq!(config.servers[0].agents, vec![AgentId::Nora]);
+        assert_eq!(config.servers[0].access, ToolAccess::Execute);
+        assert_eq!(config.servers[1].agents, vec![AgentId::Scribe]);
+        assert!(matches!(
+            config.servers[1].transport,
+            McpTransportConfig::Http { .. }
+        ));
+    }
+
+    #[test]
+    fn rejects_duplicate_server_names() {
+        let config: McpServersConfig = serde_json::from_str(
+            r#"{"servers": [
+                {"name": "a", "transport": {"type": "http", "url": "http://x"}},
+                {"name": "a", "transport": {"type": "http", "url": "http://y"}}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,777 @@
+//! Perceptual Media Fingerprinting
+//!
+//! Finds near-duplicate clips that a byte checksum misses: card copies that
+//! were re-wrapped, proxies and re-exports, trimmed duplicates and a second
+//! camera on the same moment.
+//! - Video: 64-bit DCT perceptual hash of keyframes sampled at a fixed rate
+//! - Audio: 32-bit sub-fingerprints from band-energy differences (Haitsma–Kalker)
+//! - Matching searches time offsets, so trimmed copies still line up
+//! - Matches are clustered into groups with a shared reference timeline
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use thiserror::Error;
+use tokio::process::Command;
+use uuid::Uuid;
+
+use super::scene_analysis::{DuplicateGroup, SceneAnalysisResult};
+
+/// Audio is resampled to this rate before fingerprinting
+const AUDIO_SAMPLE_RATE: u32 = 5512;
+/// Samples per audio analysis frame (~0.37s)
+const AUDIO_FRAME: usize = 2048;
+/// Samples between audio frames (~46ms)
+const AUDIO_HOP: usize = 256;
+/// Frequency range covered by the 33 fingerprint bands
+const AUDIO_BAND_MIN_HZ: f64 = 300.0;
+const AUDIO_BAND_MAX_HZ: f64 = 2000.0;
+
+#[derive(Debug, Error)]
+pub enum FingerprintError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("FFmpeg error: {0}")]
+    FFmpeg(String),
+}
+
+/// A clip in a specific batch
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ClipRef {
+    pub batch_id: Uuid,
+    pub filename: String,
+}
+
+/// Audio sub-fingerprints for a clip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioFingerprint {
+    /// Sub-fingerprints per second
+    pub frame_rate: f64,
+    pub frames: Vec<u32>,
+}
+
+/// Perceptual fingerprint of one clip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipFingerprint {
+    pub clip: ClipRef,
+    pub checksum_sha256: Option<String>,
+    pub duration: f64,
+    /// Seconds between entries in `frame_hashes`
+    pub frame_interval: f64,
+    /// DCT hash per sampled frame; 0 marks a flat frame with nothing to match on
+    pub frame_hashes: Vec<u64>,
+    pub audio: Option<AudioFingerprint>,
+}
+
+/// How two clips were found to be duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateKind {
+    /// Same bytes (matching checksums)
+    Identical,
+    /// Picture and sound both match, e.g. a card copy or re-encode
+    Perceptual,
+    /// Only the picture matches, e.g. a re-export with a new mix
+    PictureOnly,
+    /// Only the sound matches, e.g. another camera on the same moment
+    SoundOnly,
+}
+
+/// A pairwise near-duplicate match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateMatch {
+    pub a: ClipRef,
+    pub b: ClipRef,
+    /// Seconds to add to a time in `b` to reach the same moment in `a`
+    pub offset_seconds: f64,
+    pub overlap_seconds: f64,
+    pub video_similarity: Option<f64>,
+    pub audio_similarity: Option<f64>,
+    pub kind: DuplicateKind,
+}
+
+/// A member of a duplicate cluster, placed on the canonical clip's timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterMember {
+    pub clip: ClipRef,
+    /// Seconds to add to a time in this clip to reach the canonical clip
+    pub offset_seconds: f64,
+    pub kind: DuplicateKind,
+}
+
+/// Clips showing the same material, gathered around one canonical clip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    pub id: Uuid,
+    /// Earliest-ingested, then longest, clip in the group
+    pub canonical: ClipRef,
+    pub members: Vec<ClusterMember>,
+}
+
+impl DuplicateCluster {
+    /// Offset onto the canonical timeline for `clip`, if it belongs to this cluster
+    pub fn offset_of(&self, clip: &ClipRef) -> Option<f64> {
+        if &self.canonical == clip {
+            return Some(0.0);
+        }
+        self.members.iter().find(|m| &m.clip == clip).map(|m| m.offset_seconds)
+    }
+
+    pub fn touches_batch(&self, batch_id: Uuid) -> bool {
+        self.canonical.batch_id == batch_id || self.members.iter().any(|m| m.clip.batch_id == batch_id)
+    }
+}
+
+/// Sampling and matching thresholds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintSettings {
+    /// Seconds between sampled keyframes
+    pub frame_interval: f64,
+    /// Cap on sampled frames per clip
+    pub max_frames: usize,
+    /// Seconds of audio fingerprinted from the start of each clip
+    pub audio_seconds: f64,
+    /// Largest Hamming distance (of 64 bits) for two frames to match
+    pub max_hash_distance: u32,
+    /// Share of overlapping frames that must match
+    pub min_frame_match_ratio: f64,
+    /// Stricter ratio when both clips have sound and the sound differs
+    pub picture_only_match_ratio: f64,
+    /// Largest audio bit error rate for two clips to match
+    pub max_audio_bit_error: f64,
+    /// Shortest overlap considered a duplicate
+    pub min_overlap_seconds: f64,
+}
+
+impl Default for FingerprintSettings {
+    fn default() -> Self {
+        Self {
+            frame_interval: 1.0,
+            max_frames: 600,
+            audio_seconds: 180.0,
+            max_hash_distance: 12,
+            min_frame_match_ratio: 0.75,
+            picture_only_match_ratio: 0.9,
+            max_audio_bit_error: 0.3,
+            min_overlap_seconds: 3.0,
+        }
+    }
+}
+
+pub struct FingerprintEngine {
+    ffmpeg_path: PathBuf,
+    settings: FingerprintSettings,
+}
+
+impl FingerprintEngine {
+    pub fn new() -> Self {
+        Self::with_settings(FingerprintSettings::default())
+    }
+
+    pub fn with_settings(settings: FingerprintSettings) -> Self {
+        Self {
+            ffmpeg_path: PathBuf::from("ffmpeg"),
+            settings,
+        }
+    }
+
+    pub fn settings(&self) -> &FingerprintSettings {
+        &self.settings
+    }
+
+    /// Fingerprint a clip's picture and sound
+    pub async fn fingerprint_clip(
+        &self,
+        path: &Path,
+        clip: ClipRef,
+        checksum_sha256: Option<String>,
+    ) -> Result<ClipFingerprint, FingerprintError> {
+        let frame_hashes = self.frame_hashes(path).await?;
+        let audio = self.audio_fingerprint(path).await?;
+        let duration = frame_hashes.len() as f64 * self.settings.frame_interval;
+
+        Ok(ClipFingerprint {
+            clip,
+            checksum_sha256,
+            duration,
+            frame_interval: self.settings.frame_interval,
+            frame_hashes,
+            audio,
+        })
+    }
+
+    /// Decode keyframes only, resample them to a fixed rate and hash each one
+    async fn frame_hashes(&self, path: &Path) -> Result<Vec<u64>, FingerprintError> {
+        let filter = format!(
+            "fps=1/{:.3},scale=32:32:flags=area,format=gray",
+            self.settings.frame_interval
+        );
+        let output = Command::new(&self.ffmpeg_path)
+            .args(["-v", "error", "-skip_frame", "nokey", "-i"])
+            .arg(path)
+            .args(["-an", "-vf", &filter])
+            .args(["-frames:v", &self.settings.max_frames.to_string()])
+            .args(["-f", "rawvideo", "-"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(FingerprintError::FFmpeg(format!(
+                "Frame sampling failed for {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(output.stdout.chunks_exact(32 * 32).map(perceptual_hash).collect())
+    }
+
+    /// Mono 5.5 kHz PCM → sub-fingerprints; `None` for silent or soundless clips
+    async fn audio_fingerprint(&self, path: &Path) -> Result<Option<AudioFingerprint>, FingerprintError> {
+        let output = Command::new(&self.ffmpeg_path)
+            .args(["-v", "error", "-i"])
+            .arg(path)
+            .args(["-vn", "-ac", "1", "-ar", &AUDIO_SAMPLE_RATE.to_string()])
+            .args(["-t", &format!("{:.1}", self.settings.audio_seconds)])
+            .args(["-f", "s16le", "-"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await?;
+
+        // Clips without an audio stream fail here; that's not an error for us
+        if !output.status.success() {
+            return Ok(None);
+        }
+
+        let samples: Vec<f64> = output
+            .stdout
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]) as f64 / 32768.0)
+            .collect();
+        Ok(audio_fingerprint(&samples))
+    }
+
+    /// Compare two fingerprints; `None` when they are not duplicates
+    pub fn compare(&self, a: &ClipFingerprint, b: &ClipFingerprint) -> Option<DuplicateMatch> {
+        if a.checksum_sha256.is_some() && a.checksum_sha256 == b.checksum_sha256 {
+            return Some(DuplicateMatch {
+                a: a.clip.clone(),
+                b: b.clip.clone(),
+                offset_seconds: 0.0,
+                overlap_seconds: a.duration.min(b.duration),
+                video_similarity: Some(1.0),
+                audio_similarity: a.audio.as_ref().map(|_| 1.0),
+                kind: DuplicateKind::Identical,
+            });
+        }
+
+        let video = self.compare_frames(a, b);
+        let audio = match (&a.audio, &b.audio) {
+            (Some(fa), Some(fb)) => self.compare_audio(fa, fb),
+            _ => None,
+        };
+        let both_have_sound = a.audio.is_some() && b.audio.is_some();
+
+        let (kind, offset_seconds, overlap_seconds) = match (video, audio) {
+            (Some(v), Some(au)) => (DuplicateKind::Perceptual, au.offset, v.overlap.min(au.overlap)),
+            (Some(v), None) => {
+                // A static camera on different moments hashes alike; with two
+                // differing soundtracks only near-certain picture matches count
+                if both_have_sound && v.similarity < self.settings.picture_only_match_ratio {
+                    return None;
+                }
+                (DuplicateKind::PictureOnly, v.offset, v.overlap)
+            }
+            (None, Some(au)) => (DuplicateKind::SoundOnly, au.offset, au.overlap),
+            (None, None) => return None,
+        };
+
+        Some(DuplicateMatch {
+            a: a.clip.clone(),
+            b: b.clip.clone(),
+            offset_seconds,
+            overlap_seconds,
+            video_similarity: video.map(|v| v.similarity),
+            audio_similarity: audio.map(|au| au.similarity),
+            kind,
+        })
+    }
+
+    /// Best frame alignment; frames may match a neighbour of their aligned
+    /// partner since keyframe placement differs between encodes
+    fn compare_frames(&self, a: &ClipFingerprint, b: &ClipFingerprint) -> Option<Alignment> {
+        let (fa, fb) = (&a.frame_hashes, &b.frame_hashes);
+        if fa.is_empty() || fb.is_empty() || (a.frame_interval - b.frame_interval).abs() > 1e-6 {
+            return None;
+        }
+        let min_overlap = (self.settings.min_overlap_seconds / a.frame_interval).ceil() as usize;
+
+        let mut best: Option<(usize, f64, i64)> = None;
+        for shift in -(fb.len() as i64 - 1)..fa.len() as i64 {
+            let (mut compared, mut matched) = (0usize, 0usize);
+            for (j, hb) in fb.iter().enumerate() {
+                let i = j as i64 + shift;
+                if i < 0 || i >= fa.len() as i64 || *hb == 0 || fa[i as usize] == 0 {
+                    continue;
+                }
+                compared += 1;
+                let near = (j.saturating_sub(1)..(j + 2).min(fb.len()))
+                    .filter(|k| fb[*k] != 0)
+                    .any(|k| (fa[i as usize] ^ fb[k]).count_ones() <= self.settings.max_hash_distance);
+                if near {
+                    matched += 1;
+                }
+            }
+            if compared < min_overlap.max(1) {
+                continue;
+            }
+            let ratio = matched as f64 / compared as f64;
+            if ratio >= self.settings.min_frame_match_ratio && best.is_none_or(|(m, _, _)| matched > m) {
+                best = Some((matched, ratio, shift));
+            }
+        }
+
+        best.map(|(_, similarity, shift)| {
+            let overlap = (fa.len() as i64).min(fb.len() as i64 + shift) - shift.max(0);
+            Alignment {
+                offset: shift as f64 * a.frame_interval,
+                overlap: overlap as f64 * a.frame_interval,
+                similarity,
+            }
+        })
+    }
+
+    /// Vote on offsets using exactly matching sub-fingerprints, then check the
+    /// bit error rate around the winning offset
+    fn compare_audio(&self, a: &AudioFingerprint, b: &AudioFingerprint) -> Option<Alignment> {
+        if (a.frame_rate - b.frame_rate).abs() > 1e-6 {
+            return None;
+        }
+        let mut index: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (j, value) in b.frames.iter().enumerate() {
+            if *value != 0 && *value != u32::MAX {
+                index.entry(*value).or_default().push(j);
+            }
+        }
+
+        let mut votes: HashMap<i64, usize> = HashMap::new();
+        for (i, value) in a.frames.iter().enumerate() {
+            for j in index.get(value).into_iter().flatten() {
+                *votes.entry(i as i64 - *j as i64).or_insert(0) += 1;
+            }
+        }
+        let (&shift, &count) = votes.iter().max_by_key(|(shift, count)| (**count, -shift.abs()))?;
+        if count < 3 {
+            return None;
+        }
+
+        let min_overlap = (self.settings.min_overlap_seconds * a.frame_rate).ceil() as usize;
+        (shift - 1..=shift + 1)
+            .filter_map(|shift| {
+                let pairs: Vec<(u32, u32)> = b
+                    .frames
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(j, fb)| {
+                        let i = j as i64 + shift;
+                        (i >= 0 && (i as usize) < a.frames.len()).then(|| (a.frames[i as usize], *fb))
+                    })
+                    .collect();
+                if pairs.len() < min_overlap.max(1) {
+                    return None;
+                }
+                let errors: u32 = pairs.iter().map(|(x, y)| (x ^ y).count_ones()).sum();
+                let ber = errors as f64 / (pairs.len() * 32) as f64;
+                (ber <= self.settings.max_audio_bit_error).then_some(Alignment {
+                    offset: shift as f64 / a.frame_rate,
+                    overlap: pairs.len() as f64 / a.frame_rate,
+                    similarity: 1.0 - ber,
+                })
+            })
+            .max_by(|x, y| x.similarity.total_cmp(&y.similarity))
+    }
+
+    /// Compare every clip in `batch` with each other and with `history`, and
+    /// cluster the matches. Only clusters involving `batch` are returned.
+    pub fn find_duplicates(
+        &self,
+        batch: &[ClipFingerprint],
+        history: &[ClipFingerprint],
+    ) -> Vec<DuplicateCluster> {
+        let mut matches = Vec::new();
+        for (i, a) in batch.iter().enumerate() {
+            for b in batch.iter().skip(i + 1).chain(history) {
+                if let Some(m) = self.compare(a, b) {
+                    matches.push(m);
+                }
+            }
+        }
+
+        // History comes first so earlier ingests stay canonical
+        let all: Vec<&ClipFingerprint> = history.iter().chain(batch).collect();
+        cluster_matches(&all, &matches)
+    }
+}
+
+impl Default for FingerprintEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Alignment {
+    /// Seconds to add to a time in `b` to reach `a`
+    offset: f64,
+    overlap: f64,
+    similarity: f64,
+}
+
+/// Group matched clips with union–find and place every member on the
+/// canonical clip's timeline by walking the match graph from it
+fn cluster_matches(clips: &[&ClipFingerprint], matches: &[DuplicateMatch]) -> Vec<DuplicateCluster> {
+    let position: HashMap<&ClipRef, usize> = clips.iter().enumerate().map(|(i, c)| (&c.clip, i)).collect();
+    let mut parent: Vec<usize> = (0..clips.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    let mut edges: HashMap<usize, Vec<(usize, f64, DuplicateKind)>> = HashMap::new();
+    for m in matches {
+        let (Some(&a), Some(&b)) = (position.get(&m.a), position.get(&m.b)) else {
+            continue;
+        };
+        let (ra, rb) = (root(&mut parent, a), root(&mut parent, b));
+        parent[ra.max(rb)] = ra.min(rb);
+        // b + offset = a, so a - offset = b
+        edges.entry(a).or_default().push((b, m.offset_seconds, m.kind));
+        edges.entry(b).or_default().push((a, -m.offset_seconds, m.kind));
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..clips.len() {
+        if edges.contains_key(&i) {
+            let r = root(&mut parent, i);
+            groups.entry(r).or_default().push(i);
+        }
+    }
+
+    // Batches rank by first appearance, and callers pass history first
+    let mut batch_rank: HashMap<Uuid, usize> = HashMap::new();
+    for clip in clips {
+        let next = batch_rank.len();
+        batch_rank.entry(clip.clip.batch_id).or_insert(next);
+    }
+
+    let mut clusters: Vec<DuplicateCluster> = groups
+        .into_values()
+        .map(|members| {
+            // Earliest batch first, then the longest clip
+            let canonical = *members
+                .iter()
+                .min_by(|x, y| {
+                    let (cx, cy) = (clips[**x], clips[**y]);
+                    batch_rank[&cx.clip.batch_id]
+                        .cmp(&batch_rank[&cy.clip.batch_id])
+                        .then(cy.duration.total_cmp(&cx.duration))
+                        .then(x.cmp(y))
+                })
+                .unwrap_or(&members[0]);
+
+            // offset: canonical time - clip time
+            let mut offsets: HashMap<usize, (f64, DuplicateKind)> = HashMap::new();
+            let mut visited = HashSet::from([canonical]);
+            let mut queue = VecDeque::from([(canonical, 0.0)]);
+            while let Some((node, node_offset)) = queue.pop_front() {
+                for (next, edge_offset, kind) in edges.get(&node).into_iter().flatten() {
+                    if visited.insert(*next) {
+                        // next + edge_offset = node, node + node_offset = canonical
+                        let offset = edge_offset + node_offset;
+                        offsets.insert(*next, (offset, *kind));
+                        queue.push_back((*next, offset));
+                    }
+                }
+            }
+
+            let mut members: Vec<ClusterMember> = offsets
+                .into_iter()
+                .map(|(i, (offset_seconds, kind))| ClusterMember {
+                    clip: clips[i].clip.clone(),
+                    offset_seconds,
+                    kind,
+                })
+                .collect();
+            members.sort_by(|x, y| x.clip.filename.cmp(&y.clip.filename));
+
+            DuplicateCluster {
+                id: Uuid::new_v4(),
+                canonical: clips[canonical].clip.clone(),
+                members,
+            }
+        })
+        .collect();
+    clusters.sort_by(|x, y| x.canonical.filename.cmp(&y.canonical.filename));
+    clusters
+}
+
+/// Tag clips in a scene analysis with their duplicate group so assembly can
+/// treat every take of a moment as one source
+pub fn mark_duplicates(result: &mut SceneAnalysisResult, batch_id: Uuid, clusters: &[DuplicateCluster]) {
+    for clip in &mut result.clips {
+        let clip_ref = ClipRef {
+            batch_id,
+            filename: clip.filename.clone(),
+        };
+        clip.duplicate_group = clusters.iter().find_map(|cluster| {
+            cluster.offset_of(&clip_ref).map(|offset_seconds| DuplicateGroup {
+                cluster: cluster.id.to_string(),
+                offset_seconds,
+            })
+        });
+    }
+}
+
+/// 64-bit pHash of a 32x32 greyscale frame: signs of the 8x8 lowest DCT
+/// frequencies against their median. Flat frames hash to 0.
+fn perceptual_hash(pixels: &[u8]) -> u64 {
+    const N: usize = 32;
+    const K: usize = 8;
+    let basis = |k: usize, n: usize| (std::f64::consts::PI * (2 * n + 1) as f64 * k as f64 / (2 * N) as f64).cos();
+
+    // Rows first, keeping only the low frequencies
+    let mut rows = [[0.0f64; K]; N];
+    for (y, row) in rows.iter_mut().enumerate() {
+        for (u, value) in row.iter_mut().enumerate() {
+            *value = (0..N).map(|x| pixels[y * N + x] as f64 * basis(u, x)).sum();
+        }
+    }
+    let mut coefficients = [0.0f64; K * K];
+    for v in 0..K {
+        for u in 0..K {
+            coefficients[v * K + u] = (0..N).map(|y| rows[y][u] * basis(v, y)).sum();
+        }
+    }
+
+    // Median of the AC terms; the DC term only reflects brightness
+    let mut ac: Vec<f64> = coefficients[1..].to_vec();
+    ac.sort_by(|a, b| a.total_cmp(b));
+    let median = ac[ac.len() / 2];
+    let spread: f64 = ac.iter().map(|c| c.abs()).sum::<f64>() / ac.len() as f64;
+    if spread < 1.0 {
+        return 0;
+    }
+
+    coefficients
+        .iter()
+        .enumerate()
+        .fold(0u64, |hash, (i, c)| if *c > median { hash | (1 << i) } else { hash })
+}
+
+/// Haitsma–Kalker style sub-fingerprints: 33 log-spaced band energies per
+/// frame; bit m is the sign of the band-difference change over time
+fn audio_fingerprint(samples: &[f64]) -> Option<AudioFingerprint> {
+    if samples.len() < AUDIO_FRAME * 2 {
+        return None;
+    }
+    let rms = (samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64).sqrt();
+    if rms < 1e-3 {
+        return None;
+    }
+
+    let bin_hz = AUDIO_SAMPLE_RATE as f64 / AUDIO_FRAME as f64;
+    let edges: Vec<usize> = (0..=33)
+        .map(|b| {
+            let hz = AUDIO_BAND_MIN_HZ * (AUDIO_BAND_MAX_HZ / AUDIO_BAND_MIN_HZ).powf(b as f64 / 33.0);
+            (hz / bin_hz).round() as usize
+        })
+        .collect();
+    let window: Vec<f64> = (0..AUDIO_FRAME)
+        .map(|n| 0.5 - 0.5 * (2.0 * std::f64::consts::PI * n as f64 / AUDIO_FRAME as f64).cos())
+        .collect();
+
+    let mut previous: Option<[f64; 33]> = None;
+    let mut frames = Vec::new();
+    let mut start = 0;
+    while start + AUDIO_FRAME <= samples.len() {
+        let mut re: Vec<f64> = samples[start..start + AUDIO_FRAME]
+            .iter()
+            .zip(&window)
+            .map(|(s, w)| s * w)
+            .collect();
+        let mut im = vec![0.0; AUDIO_FRAME];
+        fft(&mut re, &mut im);
+
+        let mut bands = [0.0f64; 33];
+        for (b, band) in bands.iter_mut().enumerate() {
+            *band = (edges[b]..edges[b + 1].max(edges[b] + 1))
+                .map(|k| re[k] * re[k] + im[k] * im[k])
+                .sum();
+        }
+        if let Some(prev) = previous {
+            let mut bits = 0u32;
+            for m in 0..32 {
+                let delta = (bands[m] - bands[m + 1]) - (prev[m] - prev[m + 1]);
+                if delta > 0.0 {
+                    bits |= 1 << m;
+                }
+            }
+            frames.push(bits);
+        }
+        previous = Some(bands);
+        start += AUDIO_HOP;
+    }
+
+    Some(AudioFingerprint {
+        frame_rate: AUDIO_SAMPLE_RATE as f64 / AUDIO_HOP as f64,
+        frames,
+    })
+}
+
+/// In-place iterative radix-2 FFT; `re.len()` must be a power of two
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * std::f64::consts::PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(batch_id: Uuid, name: &str) -> ClipRef {
+        ClipRef { batch_id, filename: name.to_string() }
+    }
+
+    /// A 32x32 frame: a gradient under a 4x4 grid of cells lit by a hash of `seed`
+    fn frame(seed: usize) -> Vec<u8> {
+        (0..32 * 32)
+            .map(|i| {
+                let (x, y) = (i % 32, i / 32);
+                let cell = (y / 8 * 4 + x / 8) as u64;
+                let lit = (seed as u64 * 16 + cell).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 62 == 0;
+                ((x * 4 + y * 3) as u8).wrapping_add(if lit { 120 } else { 0 })
+            })
+            .collect()
+    }
+
+    fn fingerprint(clip: ClipRef, seeds: &[usize]) -> ClipFingerprint {
+        let frame_hashes: Vec<u64> = seeds.iter().map(|s| perceptual_hash(&frame(*s))).collect();
+        ClipFingerprint {
+            clip,
+            checksum_sha256: None,
+            duration: frame_hashes.len() as f64,
+            frame_interval: 1.0,
+            frame_hashes,
+            audio: None,
+        }
+    }
+
+    fn tone_sweep(seconds: f64, offset: f64) -> Vec<f64> {
+        let rate = AUDIO_SAMPLE_RATE as f64;
+        (0..(seconds * rate) as usize)
+            .map(|n| {
+                let t = n as f64 / rate + offset;
+                let f = 400.0 + 150.0 * (t * 1.7).sin() + 80.0 * (t * 5.3).cos();
+                0.4 * (2.0 * std::f64::consts::PI * f * t).sin() + 0.2 * (2.0 * std::f64::consts::PI * 1.5 * f * t).sin()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_perceptual_hash_tolerates_noise() {
+        let original = frame(1);
+        let noisy: Vec<u8> = original.iter().enumerate().map(|(i, p)| p.saturating_add((i % 7) as u8)).collect();
+        let other = frame(2);
+
+        let h = perceptual_hash(&original);
+        assert!((h ^ perceptual_hash(&noisy)).count_ones() <= 4);
+        assert!((h ^ perceptual_hash(&other)).count_ones() > 12);
+        assert_eq!(perceptual_hash(&[90u8; 32 * 32]), 0);
+    }
+
+    #[test]
+    fn test_trimmed_copy_matches_with_offset() {
+        let engine = FingerprintEngine::new();
+        let batch = Uuid::new_v4();
+        let seeds: Vec<usize> = (0..12).map(|i| i * 7 % 13).collect();
+        let master = fingerprint(clip(batch, "A001.mov"), &seeds);
+        // Re-export that starts 4 seconds in
+        let trimmed = fingerprint(clip(batch, "A001_export.mp4"), &seeds[4..]);
+        let unrelated = fingerprint(clip(batch, "B002.mov"), &[101, 102, 103, 104, 105, 106, 107, 108]);
+
+        let m = engine.compare(&master, &trimmed).expect("trimmed copy should match");
+        assert_eq!(m.kind, DuplicateKind::PictureOnly);
+        assert_eq!(m.offset_seconds, 4.0);
+        assert!(engine.compare(&master, &unrelated).is_none());
+    }
+
+    #[test]
+    fn test_audio_fingerprint_aligns_shifted_audio() {
+        let engine = FingerprintEngine::new();
+        let a = audio_fingerprint(&tone_sweep(12.0, 0.0)).unwrap();
+        let b = audio_fingerprint(&tone_sweep(8.0, 2.0)).unwrap();
+        let aligned = engine.compare_audio(&a, &b).expect("shifted audio should match");
+        assert!((aligned.offset - 2.0).abs() < 0.1, "offset {}", aligned.offset);
+        assert!(aligned.similarity > 0.9);
+        assert!(audio_fingerprint(&vec![0.0; 20_000]).is_none());
+    }
+
+    #[test]
+    fn test_clusters_prefer_history_and_chain_offsets() {
+        let engine = FingerprintEngine::new();
+        let (old, new) = (Uuid::new_v4(), Uuid::new_v4());
+        let seeds: Vec<usize> = (0..15).map(|i| i * 5 % 11).collect();
+        let history = vec![fingerprint(clip(old, "card1/C0001.mp4"), &seeds[2..])];
+        let batch = vec![
+            fingerprint(clip(new, "card2/C0001.mp4"), &seeds),
+            fingerprint(clip(new, "C0001_proxy.mp4"), &seeds[5..]),
+            fingerprint(clip(new, "C0002.mp4"), &[201, 202, 203, 204, 205, 206, 207, 208]),
+        ];
+
+        let clusters = engine.find_duplicates(&batch, &history);
+        assert_eq!(clusters.len(), 1);
+        let cluster = &clusters[0];
+        assert_eq!(cluster.canonical, clip(old, "card1/C0001.mp4"));
+        assert!(cluster.touches_batch(new));
+        assert_eq!(cluster.offset_of(&clip(new, "card2/C0001.mp4")), Some(-2.0));
+        assert_eq!(cluster.offset_of(&clip(new, "C0001_proxy.mp4")), Some(3.0));
+        assert_eq!(cluster.offset_of(&clip(new, "C0002.mp4")), None);
+    }
+}
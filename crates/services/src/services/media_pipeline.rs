@@ -13,10 +13,13 @@ use thiserror::Error;
 use tokio::{
     fs,
     io::AsyncWriteExt,
+    sync::Mutex,
     time::{Duration, sleep},
 };
 use uuid::Uuid;
 
+use super::media_fingerprint::{ClipFingerprint, ClipRef, DuplicateCluster, FingerprintEngine};
+
 #[derive(Debug, Error)]
 pub enum MediaPipelineError {
     #[error(transparent)]
@@ -48,6 +51,9 @@ struct MediaPipelineInner {
     root: PathBuf,
     client: reqwest::Client,
     db_pool: Option<SqlitePool>,
+    fingerprints: FingerprintEngine,
+    /// Serializes fingerprinting so background and on-demand runs don't race
+    fingerprint_lock: Mutex<()>,
 }
 
 impl MediaPipelineService {
@@ -69,6 +75,8 @@ impl MediaPipelineService {
                 root,
                 client: reqwest::Client::new(),
                 db_pool,
+                fingerprints: FingerprintEngine::new(),
+                fingerprint_lock: Mutex::new(()),
             }),
         })
     }
@@ -106,7 +114,17 @@ impl MediaPipelineService {
             // Local directory — process inline so batch is Ready before returning
             let batch_id = batch.id;
             match self.process_local_directory(batch_id, request).await {
-                Ok(ready_batch) => return Ok(ready_batch),
+                Ok(ready_batch) => {
+                    // Fingerprint in the background; analysis waits for it if needed
+                    let service = self.clone();
+                    let fingerprint_batch = ready_batch.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = service.fingerprint_batch(&fingerprint_batch).await {
+                            tracing::warn!("Fingerprinting failed for {}: {}", batch_id, err);
+                        }
+                    });
+                    return Ok(ready_batch);
+                }
                 Err(err) => {
                     tracing::error!("Local ingest failed for {}: {}", batch_id, err);
                     let _ = self
@@ -160,17 +178,21 @@ impl MediaPipelineService {
             })
             .collect();
 
+        let duplicates = self.find_duplicates(&batch).await?;
+
         let analysis = MediaBatchAnalysis {
             id: Uuid::new_v4(),
             batch_id: batch.id,
             brief: request.brief.clone(),
             summary: format!(
-                "Analyzed {} assets for brief '{}', identified {} hero moments",
+                "Analyzed {} assets for brief '{}', identified {} hero moments and {} near-duplicate groups",
                 batch.files.len(),
                 request.brief,
-                hero_moments.len()
+                hero_moments.len(),
+                duplicates.len()
             ),
             hero_moments,
+            duplicates,
             recommended_deliverables: request.deliverable_targets.clone(),
             passes_completed: request.passes.max(1),
             created_at: Utc::now(),
@@ -282,6 +304,115 @@ impl MediaPipelineService {
         self.inner.root.join("visual_qc").join(batch_id.to_string())
     }
 
+    /// Near-duplicate groups involving this batch, matched within the batch
+    /// and against earlier batches of the same project
+    pub async fn duplicate_clusters(
+        &self,
+        batch_id: Uuid,
+    ) -> Result<Vec<DuplicateCluster>, MediaPipelineError> {
+        let batch = self.load_batch_for_qc(batch_id).await?;
+        self.find_duplicates(&batch).await
+    }
+
+    async fn find_duplicates(
+        &self,
+        batch: &MediaBatch,
+    ) -> Result<Vec<DuplicateCluster>, MediaPipelineError> {
+        let fingerprints = self.fingerprint_batch(batch).await?;
+        let history = self.project_fingerprints(batch).await?;
+        Ok(self
+            .inner
+            .fingerprints
+            .find_duplicates(&fingerprints, &history))
+    }
+
+    /// Perceptual fingerprints for every clip in the batch, computing any
+    /// that aren't cached in `fingerprints.json` yet. Clips ffmpeg can't read
+    /// are skipped with a warning.
+    async fn fingerprint_batch(
+        &self,
+        batch: &MediaBatch,
+    ) -> Result<Vec<ClipFingerprint>, MediaPipelineError> {
+        let _guard = self.inner.fingerprint_lock.lock().await;
+        let path = self.fingerprints_path(batch.id);
+        let mut fingerprints: Vec<ClipFingerprint> = if path.exists() {
+            serde_json::from_str(&fs::read_to_string(&path).await?)?
+        } else {
+            Vec::new()
+        };
+
+        let mut computed = 0;
+        for file in &batch.files {
+            if fingerprints
+                .iter()
+                .any(|f| f.clip.filename == file.filename)
+            {
+                continue;
+            }
+            let clip = ClipRef {
+                batch_id: batch.id,
+                filename: file.filename.clone(),
+            };
+            let file_path = self.batch_dir(batch.id).join(&file.filename);
+            match self
+                .inner
+                .fingerprints
+                .fingerprint_clip(&file_path, clip, file.checksum_sha256.clone())
+                .await
+            {
+                Ok(fingerprint) => {
+                    fingerprints.push(fingerprint);
+                    computed += 1;
+                }
+                Err(err) => {
+                    tracing::warn!("Could not fingerprint {}: {}", file.filename, err);
+                }
+            }
+        }
+
+        if computed > 0 {
+            self.persist_json(&path, &fingerprints).await?;
+        }
+        Ok(fingerprints)
+    }
+
+    /// Cached fingerprints from the project's other batches, oldest first
+    async fn project_fingerprints(
+        &self,
+        batch: &MediaBatch,
+    ) -> Result<Vec<ClipFingerprint>, MediaPipelineError> {
+        let Some(project_id) = batch.project_id else {
+            return Ok(Vec::new());
+        };
+
+        let mut batches = Vec::new();
+        let mut entries = fs::read_dir(self.batches_dir()).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let Ok(id) = Uuid::parse_str(&entry.file_name().to_string_lossy()) else {
+                continue;
+            };
+            if id == batch.id {
+                continue;
+            }
+            match self.load_batch(id).await {
+                Ok(other) if other.project_id == Some(project_id) => batches.push(other),
+                _ => {}
+            }
+        }
+        batches.sort_by_key(|b| b.created_at);
+
+        let mut fingerprints = Vec::new();
+        for other in batches {
+            let path = self.fingerprints_path(other.id);
+            if path.exists() {
+                let cached: Vec<ClipFingerprint> =
+                    serde_json::from_str(&fs::read_to_string(&path).await?)?;
+                fingerprints.extend(cached);
+            }
+        }
+        Ok(fingerprints)
+    }
+
     async fn process_download(
         &self,
         batch_id: Uuid,
@@ -730,6 +861,7 @@ impl MediaPipelineService {
         let hero_moments = serde_json::to_string(&analysis.hero_moments)?;
         let insights = json!({
             "insightsPath": analysis.insights_path.to_string_lossy(),
+            "duplicateClusters": analysis.duplicates.len(),
         })
         .to_string();
         let passes_completed = analysis.passes_completed as i64;
@@ -873,6 +1005,10 @@ impl MediaPipelineService {
         self.batch_dir(batch_id).join("analysis")
     }
 
+    fn fingerprints_path(&self, batch_id: Uuid) -> PathBuf {
+        self.batch_dir(batch_id).join("fingerprints.json")
+    }
+
     fn normalize_dropbox_url(url: &str) -> String {
         if url.contains("dropbox.com") {
             if url.contains("?dl=") {
@@ -982,6 +1118,9 @@ pub struct MediaBatchAnalysis {
     pub brief: String,
     pub summary: String,
     pub hero_moments: Vec<HeroMoment>,
+    /// Near-duplicate clip groups within the batch and project history
+    #[serde(default)]
+    pub duplicates: Vec<DuplicateCluster>,
     pub recommended_deliverables: Vec<String>,
     pub passes_completed: u32,
     pub created_at: DateTime<Utc>,
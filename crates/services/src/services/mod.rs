@@ -1,5 +1,7 @@
 pub mod agent_registry;
+pub mod agent_tools;
 pub mod airtable_service;
+pub mod airtable_sync;
 pub mod analytics;
 pub mod apn_bridge;
 pub mod aptos;
@@ -8,8 +10,14 @@ pub mod artifacts;
 pub mod auth;
 pub mod autonomy;
 pub mod bowser;
+pub mod cms_export;
+pub mod cms_publishing;
 pub mod config;
 pub mod container;
+pub mod crm_enrichment;
+pub mod editron;
+pub mod email_sequence;
+pub mod email_sync;
 pub mod events;
 pub mod execution_control;
 pub mod execution_summary;
@@ -17,10 +25,15 @@ pub mod file_ranker;
 pub mod file_search_cache;
 pub mod filesystem;
 pub mod filesystem_watcher;
+pub mod forge;
 pub mod git;
 pub mod git_cli;
 pub mod github_service;
 pub mod image;
+pub mod lead_scoring;
+pub mod log_archive;
+pub mod mcp_client;
+pub mod media_fingerprint;
 pub mod media_pipeline;
 pub mod notification;
 pub mod pcg_policy;
@@ -29,9 +42,11 @@ pub mod sentry;
 pub mod slot_manager;
 pub mod topos_scanner;
 pub mod worktree_manager;
+pub mod zoho;
 pub mod social;
 pub mod vibe_pricing;
 pub mod visual_qc;
 pub mod scene_analysis;
 pub mod beat_analysis;
 pub mod recap_assembly;
+pub mod session_export;
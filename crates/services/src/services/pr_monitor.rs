@@ -1,9 +1,17 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use db::{
     DBService,
     models::{
+        execution_process::{ExecutionProcess, ExecutionProcessStatus},
+        follow_up_draft::{FollowUpDraft, UpsertFollowUpDraft},
         merge::{Merge, MergeStatus, PrMerge},
+        pr_feedback::PrFeedbackItem,
         task::{Task, TaskStatus},
         task_attempt::{TaskAttempt, TaskAttemptError},
     },
@@ -11,38 +19,65 @@ use db::{
 use sqlx::error::Error as SqlxError;
 use thiserror::Error;
 use tokio::{sync::RwLock, time::interval};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
 use crate::services::{
+    autonomy::{AutonomyError, AutonomyMode, AutonomyService},
     config::Config,
-    github_service::{GitHubRepoInfo, GitHubService, GitHubServiceError},
+    container::{ContainerError, ContainerService},
+    forge::{ForgeCredentials, ForgeError, ForgeProvider, ForgeRepoInfo},
+    git::GitServiceError,
 };
 
+/// Feedback rounds started without a human before further rounds are only
+/// saved as drafts, so a PR that keeps failing CI cannot loop forever
+const MAX_AUTO_FOLLOW_UPS_PER_PR: i64 = 5;
+
 #[derive(Debug, Error)]
 enum PrMonitorError {
-    #[error("No GitHub token configured")]
-    NoGitHubToken,
     #[error(transparent)]
-    GitHubServiceError(#[from] GitHubServiceError),
+    Forge(#[from] ForgeError),
     #[error(transparent)]
     TaskAttemptError(#[from] TaskAttemptError),
     #[error(transparent)]
     Sqlx(#[from] SqlxError),
+    #[error(transparent)]
+    Autonomy(#[from] AutonomyError),
+    #[error(transparent)]
+    Container(#[from] ContainerError),
+    #[error(transparent)]
+    Git(#[from] GitServiceError),
 }
 
-/// Service to monitor GitHub PRs and update task status when they are merged
-pub struct PrMonitorService {
+/// Service to monitor PRs/MRs on any supported forge. Merged PRs move their task
+/// to done; review comments, change requests and failed checks on open PRs are
+/// relayed back to the attempt's coding agent as a follow-up.
+pub struct PrMonitorService<C> {
     db: DBService,
     config: Arc<RwLock<Config>>,
+    container: C,
     poll_interval: Duration,
+    /// Last revision pushed for each attempt, so an unchanged branch is not
+    /// pushed again on every poll
+    pushed_revisions: Mutex<HashMap<Uuid, String>>,
 }
 
-impl PrMonitorService {
-    pub async fn spawn(db: DBService, config: Arc<RwLock<Config>>) -> tokio::task::JoinHandle<()> {
+impl<C> PrMonitorService<C>
+where
+    C: ContainerService + Send + Sync + 'static,
+{
+    pub async fn spawn(
+        db: DBService,
+        config: Arc<RwLock<Config>>,
+        container: C,
+    ) -> tokio::task::JoinHandle<()> {
         let service = Self {
             db,
             config,
+            container,
             poll_interval: Duration::from_secs(60), // Check every minute
+            pushed_revisions: Mutex::new(HashMap::new()),
         };
         tokio::spawn(async move {
             service.start().await;
@@ -65,7 +100,7 @@ impl PrMonitorService {
         }
     }
 
-    /// Check all open PRs for updates with the provided GitHub token
+    /// Check all open PRs for updates with the configured forge tokens
     async fn check_all_open_prs(&self) -> Result<(), PrMonitorError> {
         let open_prs = Merge::get_open_prs(&self.db.pool).await?;
 
@@ -89,15 +124,13 @@ impl PrMonitorService {
 
     /// Check the status of a specific PR
     async fn check_pr_status(&self, pr_merge: &PrMerge) -> Result<(), PrMonitorError> {
-        let github_config = self.config.read().await.github.clone();
-        let github_token = github_config.token().ok_or(PrMonitorError::NoGitHubToken)?;
-
-        let github_service = GitHubService::new(&github_token)?;
-
-        let repo_info = GitHubRepoInfo::from_remote_url(&pr_merge.pr_info.url)?;
+        let config = self.config.read().await.clone();
+        let repo_info = ForgeRepoInfo::from_pr_url(&pr_merge.pr_info.url, pr_merge.forge)?;
 
-        let pr_status = github_service
-            .update_pr_status(&repo_info, pr_merge.pr_info.number)
+        let credentials = ForgeCredentials::resolve(&repo_info, &config)?;
+        let provider = credentials.provider()?;
+        let pr_status = provider
+            .get_pr_status(&repo_info, pr_merge.pr_info.number)
             .await?;
 
         debug!(
@@ -107,7 +140,11 @@ impl PrMonitorService {
 
         // Update the PR status in the database
         if !matches!(&pr_status.status, MergeStatus::Open) {
-            // Update merge status with the latest information from GitHub
+            self.pushed_revisions
+                .lock()
+                .unwrap()
+                .remove(&pr_merge.task_attempt_id);
+            // Update merge status with the latest information from the forge
             Merge::update_status(
                 &self.db.pool,
                 pr_merge.id,
@@ -127,8 +164,169 @@ impl PrMonitorService {
                 );
                 Task::update_status(&self.db.pool, task_attempt.task_id, TaskStatus::Done).await?;
             }
+            return Ok(());
         }
 
+        let Some(task_attempt) =
+            TaskAttempt::find_by_id(&self.db.pool, pr_merge.task_attempt_id).await?
+        else {
+            return Ok(());
+        };
+        self.relay_feedback(pr_merge, &task_attempt, &repo_info, provider.as_ref())
+            .await?;
+        self.push_agent_revisions(&task_attempt, &credentials.token)
+            .await
+    }
+
+    /// Turn review comments, change requests and failed checks that have not been
+    /// relayed yet into a follow-up for the attempt's agent. Agent-driven tasks
+    /// run it right away (or queue it behind the running process); other modes
+    /// leave it as a draft for a human to review and send. Providers only return
+    /// feedback from people with write access, so a drive-by commenter cannot
+    /// steer what gets pushed to the branch.
+    async fn relay_feedback(
+        &self,
+        pr_merge: &PrMerge,
+        task_attempt: &TaskAttempt,
+        repo_info: &ForgeRepoInfo,
+        provider: &dyn ForgeProvider,
+    ) -> Result<(), PrMonitorError> {
+        let pool = &self.db.pool;
+        let mut feedback = provider
+            .get_pr_feedback(repo_info, pr_merge.pr_info.number)
+            .await?;
+        let unseen = PrFeedbackItem::filter_unseen(pool, pr_merge.id, feedback.keys()).await?;
+        if unseen.is_empty() {
+            return Ok(());
+        }
+        feedback.retain_keys(&unseen);
+
+        let existing = FollowUpDraft::find_by_task_attempt_id(pool, task_attempt.id).await?;
+        if existing.as_ref().is_some_and(|d| d.sending) {
+            // A follow-up is being sent right now; pick the feedback up next poll
+            return Ok(());
+        }
+
+        let Some(branch) = task_attempt.branch.as_deref() else {
+            return Ok(());
+        };
+        let mut prompt =
+            feedback.to_follow_up_prompt(pr_merge.pr_info.number, &pr_merge.pr_info.url, branch);
+        if let Some(draft) = existing.as_ref().filter(|d| !d.prompt.trim().is_empty()) {
+            prompt = format!("{}\n\n{}", draft.prompt.trim_end(), prompt);
+        }
+
+        let mode = AutonomyService::new(self.db.clone())
+            .get_task_autonomy_mode(task_attempt.task_id)
+            .await?;
+        let auto_rounds = PrFeedbackItem::count_auto_started_batches(pool, pr_merge.id).await?;
+        let variant = existing.as_ref().and_then(|d| d.variant.clone());
+        let image_ids = existing.as_ref().and_then(|d| d.image_ids.clone());
+        // Drafts with images need a human to send them so the images get attached
+        let auto = mode == AutonomyMode::AgentDriven
+            && auto_rounds < MAX_AUTO_FOLLOW_UPS_PER_PR
+            && image_ids.is_none();
+        let running = ExecutionProcess::find_by_task_attempt_id(pool, task_attempt.id, false)
+            .await?
+            .iter()
+            .any(|p| matches!(p.status, ExecutionProcessStatus::Running));
+
+        if auto && !running {
+            self.container
+                .start_follow_up(task_attempt, prompt, variant)
+                .await?;
+            FollowUpDraft::clear_after_send(pool, task_attempt.id).await?;
+        } else {
+            // A queued draft is started by the container when the current
+            // process finishes
+            FollowUpDraft::upsert(
+                pool,
+                &UpsertFollowUpDraft {
+                    task_attempt_id: task_attempt.id,
+                    prompt,
+                    queued: auto || existing.as_ref().is_some_and(|d| d.queued),
+                    variant,
+                    image_ids,
+                },
+            )
+            .await?;
+        }
+
+        PrFeedbackItem::record_batch(pool, pr_merge.id, &unseen, auto).await?;
+        info!(
+            "Relayed {} new feedback item(s) on PR #{} to attempt {} ({})",
+            unseen.len(),
+            pr_merge.pr_info.number,
+            task_attempt.id,
+            if auto {
+                "follow-up started"
+            } else {
+                "saved as draft"
+            }
+        );
+        Ok(())
+    }
+
+    /// Agent-driven tasks push the agent's revisions to the PR branch once it is
+    /// idle; other modes leave pushing to a human.
+    async fn push_agent_revisions(
+        &self,
+        task_attempt: &TaskAttempt,
+        token: &str,
+    ) -> Result<(), PrMonitorError> {
+        let pool = &self.db.pool;
+        let (Some(branch), Some(container_ref)) = (
+            task_attempt.branch.as_deref(),
+            task_attempt.container_ref.as_deref(),
+        ) else {
+            return Ok(());
+        };
+        let worktree = Path::new(container_ref);
+        if !worktree.exists() {
+            return Ok(());
+        }
+
+        let mode = AutonomyService::new(self.db.clone())
+            .get_task_autonomy_mode(task_attempt.task_id)
+            .await?;
+        if mode != AutonomyMode::AgentDriven {
+            return Ok(());
+        }
+        let running = ExecutionProcess::find_by_task_attempt_id(pool, task_attempt.id, false)
+            .await?
+            .iter()
+            .any(|p| matches!(p.status, ExecutionProcessStatus::Running));
+        if running {
+            return Ok(());
+        }
+
+        let git = self.container.git();
+        let revision = git.get_branch_oid(worktree, branch)?;
+        let already_pushed = self
+            .pushed_revisions
+            .lock()
+            .unwrap()
+            .get(&task_attempt.id)
+            .is_some_and(|pushed| *pushed == revision);
+        if already_pushed || !git.is_branch_ahead_of_remote(worktree, branch)? {
+            return Ok(());
+        }
+        if !git.is_worktree_clean(worktree)? {
+            warn!(
+                "Not pushing revisions for attempt {}: worktree has uncommitted changes",
+                task_attempt.id
+            );
+            return Ok(());
+        }
+        git.push_to_github(worktree, branch, token)?;
+        self.pushed_revisions
+            .lock()
+            .unwrap()
+            .insert(task_attempt.id, revision);
+        info!(
+            "Pushed agent revisions on {} for attempt {}",
+            branch, task_attempt.id
+        );
         Ok(())
     }
 }
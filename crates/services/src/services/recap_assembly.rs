@@ -343,10 +343,13 @@ impl RecapAssemblyEngine {
             }
         }
 
-        // Assign clips to slots — match energy, avoid repeats
+        // Assign clips to slots — match energy, avoid repeats.
+        // Near-duplicate takes share a moment key, so a card copy or re-export
+        // counts as the same footage as the clip it duplicates.
         let mut placements = Vec::new();
         let mut used_clip_counts: HashMap<String, u32> = HashMap::new();
-        // Track which source ranges have been used per clip to avoid identical segments
+        // Track which source ranges have been used per moment (on the duplicate
+        // group's timeline) to avoid identical segments
         let mut used_source_ranges: HashMap<String, Vec<(f64, f64)>> = HashMap::new();
 
         let mut prev_section_idx: Option<usize> = None;
@@ -370,9 +373,11 @@ impl RecapAssemblyEngine {
             );
 
             if let Some(clip) = best_clip {
-                // Calculate source range — use a different part if this clip was used before
-                let prev_ranges = used_source_ranges.get(&clip.filename)
-                    .cloned().unwrap_or_default();
+                // Calculate source range — use a different part if this moment was used before
+                let offset = Self::moment_offset(clip);
+                let prev_ranges: Vec<(f64, f64)> = used_source_ranges.get(Self::moment_key(clip))
+                    .map(|ranges| ranges.iter().map(|(s, e)| (s - offset, e - offset)).collect())
+                    .unwrap_or_default();
                 let (src_in, src_out) = Self::varied_source_range(clip, section, slot_dur, &prev_ranges);
 
                 // Layer 3: Transition
@@ -398,8 +403,9 @@ impl RecapAssemblyEngine {
                     speed: 1.0,
                 });
 
-                *used_clip_counts.entry(clip.filename.clone()).or_insert(0) += 1;
-                used_source_ranges.entry(clip.filename.clone()).or_default().push((src_in, src_out));
+                let key = Self::moment_key(clip).to_string();
+                *used_clip_counts.entry(key.clone()).or_insert(0) += 1;
+                used_source_ranges.entry(key).or_default().push((src_in + offset, src_out + offset));
             }
 
             shot_idx_in_section += 1;
@@ -425,7 +431,7 @@ impl RecapAssemblyEngine {
 
                 // Heavy penalty for reuse — each use drops score by 1.5
                 // This forces variety: algorithm must exhaust all clips before reusing
-                let uses = used_counts.get(&clip.filename).copied().unwrap_or(0);
+                let uses = used_counts.get(Self::moment_key(clip)).copied().unwrap_or(0);
                 score -= uses as f64 * 1.5;
 
                 // Bonus if clip is long enough for this slot
@@ -441,6 +447,17 @@ impl RecapAssemblyEngine {
         candidates.first().map(|(clip, _)| *clip)
     }
 
+    /// Footage identity for reuse tracking: the duplicate group if the clip has
+    /// near-duplicates, otherwise the clip itself.
+    fn moment_key(clip: &ClipAnalysis) -> &str {
+        clip.duplicate_group.as_ref().map(|g| g.cluster.as_str()).unwrap_or(&clip.filename)
+    }
+
+    /// Offset from the clip's own time onto its moment key's timeline
+    fn moment_offset(clip: &ClipAnalysis) -> f64 {
+        clip.duplicate_group.as_ref().map(|g| g.offset_seconds).unwrap_or(0.0)
+    }
+
     /// Calculate source in/out, picking a different segment if this clip was used before.
     fn varied_source_range(
         clip: &ClipAnalysis,
@@ -940,7 +957,7 @@ fn escape_xml(s: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::services::scene_analysis::SegmentAnalysis;
+    use crate::services::scene_analysis::{DuplicateGroup, SegmentAnalysis};
 
     fn make_beat(ts: f64, num: u32, bar: u32, beat_in_bar: u32) -> BeatMarker {
         BeatMarker {
@@ -1000,6 +1017,44 @@ mod tests {
         assert!(cuts.len() <= 3, "Intro should have few long shots, got {}", cuts.len());
     }
 
+    fn make_clip(name: &str, energy: f64, group: Option<(&str, f64)>) -> ClipAnalysis {
+        ClipAnalysis {
+            filename: name.to_string(),
+            path: PathBuf::from(format!("/media/{}", name)),
+            duration: 20.0, width: 1920, height: 1080, fps: 29.97,
+            segments: vec![],
+            overall_energy: energy,
+            peak_energy_timestamp: 0.0,
+            dominant_content_type: ContentType::HighEnergy,
+            usable: true,
+            duplicate_group: group.map(|(cluster, offset_seconds)| DuplicateGroup {
+                cluster: cluster.to_string(),
+                offset_seconds,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_takes_count_as_one_moment() {
+        let peak = MusicSection {
+            name: "Chorus".to_string(),
+            start: 0.0, end: 10.0,
+            energy_level: 0.9,
+            suggested_content: SuggestedContent::Peak,
+        };
+        let card_a = make_clip("A001.mov", 0.9, Some(("dup-1", 0.0)));
+        let card_b = make_clip("A001_copy.mov", 0.9, Some(("dup-1", -2.0)));
+        let other = make_clip("B002.mov", 0.7, None);
+
+        // The card A take was used once; its copy must not look fresh
+        let mut used = HashMap::new();
+        used.insert(RecapAssemblyEngine::moment_key(&card_a).to_string(), 1);
+        let clips = vec![&card_a, &card_b, &other];
+        let pick = RecapAssemblyEngine::pick_clip_for_slot(&clips, &peak, 2.0, &used).unwrap();
+        assert_eq!(pick.filename, "B002.mov");
+        assert_eq!(RecapAssemblyEngine::moment_offset(&card_b), -2.0);
+    }
+
     #[test]
     fn test_default_duration_59s() {
         assert_eq!(DEFAULT_RECAP_DURATION, 59.0);
@@ -65,6 +65,18 @@ pub struct ClipAnalysis {
     pub peak_energy_timestamp: f64,
     pub dominant_content_type: ContentType,
     pub usable: bool,
+    /// Near-duplicate group this clip belongs to (see `media_fingerprint`)
+    #[serde(default)]
+    pub duplicate_group: Option<DuplicateGroup>,
+}
+
+/// Shared timeline for clips that show the same material
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Cluster id shared by every take of the material
+    pub cluster: String,
+    /// Seconds to add to a time in this clip to reach the group's timeline
+    pub offset_seconds: f64,
 }
 
 /// Result of analyzing an entire batch
@@ -263,6 +275,7 @@ impl SceneAnalysisEngine {
                 peak_energy_timestamp: 0.0,
                 dominant_content_type: ContentType::Ambient,
                 usable: false,
+                duplicate_group: None,
             });
         }
 
@@ -324,6 +337,7 @@ impl SceneAnalysisEngine {
             peak_energy_timestamp,
             dominant_content_type,
             usable: duration >= 2.0,
+            duplicate_group: None,
         })
     }
 
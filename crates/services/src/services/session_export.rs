@@ -0,0 +1,1040 @@
+//! Export of a task attempt's agent session as a shareable transcript.
+//!
+//! A [`SessionTranscript`] gathers every coding-agent run of an attempt as a
+//! [`NormalizedConversation`], plus the attempt's diff. It can then be rendered
+//! as self-contained Markdown or HTML for post-mortems and client hand-off, or
+//! as a JSONL trajectory that can be replayed or scored offline.
+
+use std::{collections::BTreeMap, fmt::Write as _, future, path::Path, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use db::models::{
+    execution_process::{ExecutionProcess, ExecutionProcessRunReason, ExecutionProcessStatus},
+    executor_session::ExecutorSession,
+    merge::Merge,
+    task_attempt::TaskAttempt,
+};
+use executors::{
+    actions::ExecutorActionType,
+    logs::{
+        ActionType, FileChange, NormalizedConversation, NormalizedEntry, NormalizedEntryType,
+        ToolStatus, utils::patch::extract_normalized_entry_from_patch,
+    },
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use ts_rs::TS;
+use utils::{
+    diff::{Diff, DiffChangeKind, create_unified_diff},
+    log_msg::LogMsg,
+};
+use uuid::Uuid;
+
+use super::{
+    container::ContainerService,
+    git::{DiffTarget, GitService, GitServiceError},
+};
+
+/// Version of the JSONL trajectory layout, bumped on breaking changes
+pub const TRAJECTORY_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum SessionExportError {
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Serialization(#[from] serde_json::Error),
+    #[error("Task for attempt {0} not found")]
+    TaskNotFound(Uuid),
+    #[error("Project for attempt {0} not found")]
+    ProjectNotFound(Uuid),
+    #[error("Unknown export format: {0}")]
+    UnknownFormat(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Jsonl => "jsonl",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Html => "text/html; charset=utf-8",
+            ExportFormat::Jsonl => "application/x-ndjson",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = SessionExportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "html" | "htm" => Ok(ExportFormat::Html),
+            "jsonl" | "trajectory" => Ok(ExportFormat::Jsonl),
+            other => Err(SessionExportError::UnknownFormat(other.to_string())),
+        }
+    }
+}
+
+/// One coding-agent execution within the attempt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptRun {
+    pub execution_id: Uuid,
+    pub status: ExecutionProcessStatus,
+    pub exit_code: Option<i64>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub conversation: NormalizedConversation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionTranscript {
+    pub task_attempt_id: Uuid,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub task_description: Option<String>,
+    pub project_name: String,
+    pub executor: String,
+    pub branch: Option<String>,
+    pub base_branch: String,
+    pub attempt_created_at: DateTime<Utc>,
+    pub exported_at: DateTime<Utc>,
+    pub runs: Vec<TranscriptRun>,
+    pub diffs: Vec<Diff>,
+    /// Set when the diff could not be computed (e.g. branch deleted)
+    pub diff_error: Option<String>,
+}
+
+impl SessionTranscript {
+    /// Collect the transcript for an attempt from its execution logs and git state
+    pub async fn collect<C>(
+        container: &C,
+        task_attempt: &TaskAttempt,
+    ) -> Result<Self, SessionExportError>
+    where
+        C: ContainerService + Sync + ?Sized,
+    {
+        let pool = &container.db().pool;
+        let task = task_attempt
+            .parent_task(pool)
+            .await?
+            .ok_or(SessionExportError::TaskNotFound(task_attempt.id))?;
+        let project = task
+            .parent_project(pool)
+            .await?
+            .ok_or(SessionExportError::ProjectNotFound(task_attempt.id))?;
+
+        let processes = ExecutionProcess::find_by_task_attempt_id(pool, task_attempt.id, false)
+            .await?
+            .into_iter()
+            .filter(|p| p.run_reason == ExecutionProcessRunReason::CodingAgent);
+
+        let mut runs = Vec::new();
+        for process in processes {
+            let session = ExecutorSession::find_by_execution_process_id(pool, process.id).await?;
+            let (executor_type, action_prompt) = match process.executor_action().map(|a| a.typ()) {
+                Ok(ExecutorActionType::CodingAgentInitialRequest(req)) => (
+                    req.executor_profile_id.executor.to_string(),
+                    Some(req.prompt.clone()),
+                ),
+                Ok(ExecutorActionType::CodingAgentFollowUpRequest(req)) => (
+                    req.executor_profile_id.executor.to_string(),
+                    Some(req.prompt.clone()),
+                ),
+                _ => (task_attempt.executor.clone(), None),
+            };
+
+            let entries = collect_normalized_entries(container, process.id).await;
+            let (session_id, session_prompt, summary) = match session {
+                Some(s) => (s.session_id, s.prompt, s.summary),
+                None => (None, None, None),
+            };
+
+            runs.push(TranscriptRun {
+                execution_id: process.id,
+                status: process.status,
+                exit_code: process.exit_code,
+                started_at: process.started_at,
+                completed_at: process.completed_at,
+                conversation: NormalizedConversation {
+                    entries,
+                    session_id,
+                    executor_type,
+                    prompt: action_prompt.or(session_prompt),
+                    summary,
+                },
+            });
+        }
+
+        let (diffs, diff_error) =
+            match collect_diffs(container, &project.git_repo_path, task_attempt).await {
+                Ok(diffs) => (diffs, None),
+                Err(e) => {
+                    tracing::warn!("Exporting attempt {} without diff: {}", task_attempt.id, e);
+                    (Vec::new(), Some(e.to_string()))
+                }
+            };
+
+        Ok(Self {
+            task_attempt_id: task_attempt.id,
+            task_id: task.id,
+            task_title: task.title,
+            task_description: task.description,
+            project_name: project.name,
+            executor: task_attempt.executor.clone(),
+            branch: task_attempt.branch.clone(),
+            base_branch: task_attempt.base_branch.clone(),
+            attempt_created_at: task_attempt.created_at,
+            exported_at: Utc::now(),
+            runs,
+            diffs,
+            diff_error,
+        })
+    }
+
+    /// Suggested download file name, e.g. `attempt-1a2b3c4d.md`
+    pub fn file_name(&self, format: ExportFormat) -> String {
+        let short_id: String = self.task_attempt_id.to_string().chars().take(8).collect();
+        format!("attempt-{short_id}.{}", format.extension())
+    }
+
+    pub fn render(&self, format: ExportFormat) -> Result<String, SessionExportError> {
+        match format {
+            ExportFormat::Markdown => Ok(self.to_markdown()),
+            ExportFormat::Html => Ok(self.to_html()),
+            ExportFormat::Jsonl => self.to_jsonl(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n", self.task_title);
+        for (label, value) in self.metadata() {
+            let _ = writeln!(out, "- **{label}:** {value}");
+        }
+        out.push('\n');
+        if let Some(description) = self.task_description.as_deref().filter(|d| !d.is_empty()) {
+            let _ = writeln!(out, "## Task\n\n{description}\n");
+        }
+
+        for (i, run) in self.runs.iter().enumerate() {
+            let _ = writeln!(out, "## Run {} · {}\n", i + 1, run_heading(run));
+            if let Some(prompt) = &run.conversation.prompt {
+                let _ = writeln!(out, "### Prompt\n\n{}\n", fenced(prompt, "text"));
+            }
+            for entry in &run.conversation.entries {
+                let Some(view) = EntryView::from_entry(entry) else {
+                    continue;
+                };
+                let _ = writeln!(out, "#### {}\n", view.label);
+                for block in &view.blocks {
+                    match block {
+                        Block::Text(text) => {
+                            let _ = writeln!(out, "{text}\n");
+                        }
+                        Block::Quote(text) => {
+                            for line in text.lines() {
+                                let _ = writeln!(out, "> {line}");
+                            }
+                            out.push('\n');
+                        }
+                        Block::Code { lang, body } => {
+                            let _ = writeln!(out, "{}\n", fenced(body, lang));
+                        }
+                    }
+                }
+            }
+        }
+
+        out.push_str("## Changes\n\n");
+        if let Some(err) = &self.diff_error {
+            let _ = writeln!(out, "_Diff unavailable: {err}_\n");
+        } else if self.diffs.is_empty() {
+            out.push_str("_No file changes._\n\n");
+        }
+        for diff in &self.diffs {
+            let view = DiffView::from_diff(diff);
+            let _ = writeln!(
+                out,
+                "### `{}` ({}, +{} −{})\n",
+                view.path, view.change, view.additions, view.deletions
+            );
+            match &view.unified {
+                Some(unified) => {
+                    let _ = writeln!(out, "{}\n", fenced(unified, "diff"));
+                }
+                None => {
+                    let _ = writeln!(out, "_{}_\n", view.omitted_reason());
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut body = String::new();
+        let _ = writeln!(body, "<h1>{}</h1>", escape_html(&self.task_title));
+        body.push_str("<dl class=\"meta\">");
+        for (label, value) in self.metadata() {
+            let _ = write!(
+                body,
+                "<dt>{}</dt><dd>{}</dd>",
+                escape_html(label),
+                escape_html(&value)
+            );
+        }
+        body.push_str("</dl>\n");
+        if let Some(description) = self.task_description.as_deref().filter(|d| !d.is_empty()) {
+            let _ = writeln!(
+                body,
+                "<section><h2>Task</h2><div class=\"text\">{}</div></section>",
+                escape_html(description)
+            );
+        }
+
+        for (i, run) in self.runs.iter().enumerate() {
+            let _ = writeln!(
+                body,
+                "<section class=\"run\"><h2>Run {} · {}</h2>",
+                i + 1,
+                escape_html(&run_heading(run))
+            );
+            if let Some(prompt) = &run.conversation.prompt {
+                let _ = writeln!(
+                    body,
+                    "<div class=\"entry user\"><div class=\"label\">Prompt</div><div class=\"text\">{}</div></div>",
+                    escape_html(prompt)
+                );
+            }
+            for entry in &run.conversation.entries {
+                let Some(view) = EntryView::from_entry(entry) else {
+                    continue;
+                };
+                let _ = write!(
+                    body,
+                    "<div class=\"entry {}\"><div class=\"label\">{}</div>",
+                    view.class,
+                    escape_html(&view.label)
+                );
+                for block in &view.blocks {
+                    match block {
+                        Block::Text(text) => {
+                            let _ = write!(body, "<div class=\"text\">{}</div>", escape_html(text));
+                        }
+                        Block::Quote(text) => {
+                            let _ = write!(body, "<blockquote>{}</blockquote>", escape_html(text));
+                        }
+                        Block::Code { lang, body: code } => {
+                            body.push_str(&html_code_block(code, lang));
+                        }
+                    }
+                }
+                body.push_str("</div>\n");
+            }
+            body.push_str("</section>\n");
+        }
+
+        body.push_str("<section class=\"changes\"><h2>Changes</h2>\n");
+        if let Some(err) = &self.diff_error {
+            let _ = writeln!(
+                body,
+                "<p><em>Diff unavailable: {}</em></p>",
+                escape_html(err)
+            );
+        } else if self.diffs.is_empty() {
+            body.push_str("<p><em>No file changes.</em></p>\n");
+        }
+        for diff in &self.diffs {
+            let view = DiffView::from_diff(diff);
+            let _ = write!(
+                body,
+                "<details open><summary><code>{}</code> {} <span class=\"add\">+{}</span> <span class=\"del\">−{}</span></summary>",
+                escape_html(&view.path),
+                view.change,
+                view.additions,
+                view.deletions
+            );
+            match &view.unified {
+                Some(unified) => body.push_str(&html_code_block(unified, "diff")),
+                None => {
+                    let _ = write!(body, "<p><em>{}</em></p>", view.omitted_reason());
+                }
+            }
+            body.push_str("</details>\n");
+        }
+        body.push_str("</section>\n");
+
+        format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape_html(&self.task_title),
+            HTML_STYLE,
+            body
+        )
+    }
+
+    /// One JSON object per line: a header, then each run followed by its steps,
+    /// then one record per changed file
+    pub fn to_jsonl(&self) -> Result<String, SessionExportError> {
+        let mut out = String::new();
+        for record in self.trajectory() {
+            out.push_str(&serde_json::to_string(&record)?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    pub fn trajectory(&self) -> Vec<TrajectoryRecord> {
+        let mut records = vec![TrajectoryRecord::Header {
+            version: TRAJECTORY_VERSION,
+            task_attempt_id: self.task_attempt_id,
+            task_id: self.task_id,
+            task_title: self.task_title.clone(),
+            task_description: self.task_description.clone(),
+            project_name: self.project_name.clone(),
+            executor: self.executor.clone(),
+            branch: self.branch.clone(),
+            base_branch: self.base_branch.clone(),
+            exported_at: self.exported_at,
+        }];
+
+        for (run_index, run) in self.runs.iter().enumerate() {
+            records.push(TrajectoryRecord::Run {
+                run_index,
+                execution_id: run.execution_id,
+                executor: run.conversation.executor_type.clone(),
+                session_id: run.conversation.session_id.clone(),
+                prompt: run.conversation.prompt.clone(),
+                summary: run.conversation.summary.clone(),
+                status: run.status.clone(),
+                exit_code: run.exit_code,
+                started_at: run.started_at,
+                completed_at: run.completed_at,
+            });
+            records.extend(run.conversation.entries.iter().enumerate().map(
+                |(step_index, entry)| TrajectoryRecord::Step {
+                    run_index,
+                    step_index,
+                    entry: entry.clone(),
+                },
+            ));
+        }
+
+        records.extend(self.diffs.iter().map(|diff| {
+            let view = DiffView::from_diff(diff);
+            TrajectoryRecord::Diff {
+                path: view.path,
+                change: diff.change.clone(),
+                additions: view.additions,
+                deletions: view.deletions,
+                unified_diff: view.unified,
+            }
+        }));
+        records
+    }
+
+    fn metadata(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![
+            ("Project", self.project_name.clone()),
+            ("Attempt", self.task_attempt_id.to_string()),
+            ("Executor", self.executor.clone()),
+        ];
+        if let Some(branch) = &self.branch {
+            rows.push(("Branch", format!("{branch} → {}", self.base_branch)));
+        }
+        rows.push(("Started", self.attempt_created_at.to_rfc3339()));
+        rows.push(("Exported", self.exported_at.to_rfc3339()));
+        rows
+    }
+}
+
+/// A single line of the JSONL trajectory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TrajectoryRecord {
+    Header {
+        version: u32,
+        task_attempt_id: Uuid,
+        task_id: Uuid,
+        task_title: String,
+        task_description: Option<String>,
+        project_name: String,
+        executor: String,
+        branch: Option<String>,
+        base_branch: String,
+        exported_at: DateTime<Utc>,
+    },
+    Run {
+        run_index: usize,
+        execution_id: Uuid,
+        executor: String,
+        session_id: Option<String>,
+        prompt: Option<String>,
+        summary: Option<String>,
+        status: ExecutionProcessStatus,
+        exit_code: Option<i64>,
+        started_at: DateTime<Utc>,
+        completed_at: Option<DateTime<Utc>>,
+    },
+    Step {
+        run_index: usize,
+        step_index: usize,
+        entry: NormalizedEntry,
+    },
+    Diff {
+        path: String,
+        change: DiffChangeKind,
+        additions: usize,
+        deletions: usize,
+        unified_diff: Option<String>,
+    },
+}
+
+/// Parse a JSONL trajectory back into records, e.g. for offline replay or evaluation
+pub fn parse_trajectory(jsonl: &str) -> Result<Vec<TrajectoryRecord>, serde_json::Error> {
+    jsonl
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// Snapshot the normalized entries of an execution. Live executions are read
+/// from their in-memory history so the export does not wait for them to finish.
+pub async fn collect_normalized_entries<C>(
+    container: &C,
+    execution_id: Uuid,
+) -> Vec<NormalizedEntry>
+where
+    C: ContainerService + Sync + ?Sized,
+{
+    let messages: Vec<LogMsg> =
+        if let Some(store) = container.get_msg_store_by_id(&execution_id).await {
+            store.get_history()
+        } else if let Some(stream) = container.stream_normalized_logs(&execution_id).await {
+            stream
+                .take_while(|msg| future::ready(!matches!(msg, Ok(LogMsg::Finished))))
+                .filter_map(|msg| future::ready(msg.ok()))
+                .collect()
+                .await
+        } else {
+            Vec::new()
+        };
+
+    let mut entries = BTreeMap::new();
+    for msg in messages {
+        if let LogMsg::JsonPatch(patch) = msg
+            && let Some((index, entry)) = extract_normalized_entry_from_patch(&patch)
+        {
+            entries.insert(index, entry);
+        }
+    }
+    entries.into_values().collect()
+}
+
+/// Diff of the attempt: the live worktree when it still exists, otherwise the
+/// committed branch, falling back to the merge commit once the branch is gone.
+pub async fn collect_diffs<C>(
+    container: &C,
+    repo_path: &Path,
+    task_attempt: &TaskAttempt,
+) -> Result<Vec<Diff>, GitServiceError>
+where
+    C: ContainerService + Sync + ?Sized,
+{
+    let git: &GitService = container.git();
+    let merge_commit = Merge::find_latest_by_task_attempt_id(&container.db().pool, task_attempt.id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|m| m.merge_commit());
+
+    let Some(branch) = task_attempt.branch.as_deref() else {
+        return Ok(Vec::new());
+    };
+
+    let branch_result = match task_attempt.container_ref.as_deref() {
+        Some(worktree) if !task_attempt.worktree_deleted && Path::new(worktree).exists() => git
+            .get_base_commit(repo_path, branch, &task_attempt.base_branch)
+            .and_then(|base_commit| {
+                git.get_diffs(
+                    DiffTarget::Worktree {
+                        worktree_path: Path::new(worktree),
+                        base_commit: &base_commit,
+                    },
+                    None,
+                )
+            }),
+        _ => git.get_diffs(
+            DiffTarget::Branch {
+                repo_path,
+                branch_name: branch,
+                base_branch: &task_attempt.base_branch,
+            },
+            None,
+        ),
+    };
+
+    match (branch_result, merge_commit) {
+        (Ok(diffs), _) => Ok(diffs),
+        (Err(_), Some(commit_sha)) => git.get_diffs(
+            DiffTarget::Commit {
+                repo_path,
+                commit_sha: &commit_sha,
+            },
+            None,
+        ),
+        (Err(e), None) => Err(e),
+    }
+}
+
+enum Block {
+    Text(String),
+    Quote(String),
+    Code { lang: &'static str, body: String },
+}
+
+/// Format-agnostic view of a normalized entry, shared by the Markdown and HTML renderers
+struct EntryView {
+    label: String,
+    class: &'static str,
+    blocks: Vec<Block>,
+}
+
+impl EntryView {
+    fn from_entry(entry: &NormalizedEntry) -> Option<Self> {
+        let content = entry.content.trim();
+        let (label, class, blocks) = match &entry.entry_type {
+            NormalizedEntryType::Loading => return None,
+            NormalizedEntryType::UserMessage => (
+                "User".to_string(),
+                "user",
+                vec![Block::Text(content.to_string())],
+            ),
+            NormalizedEntryType::AssistantMessage => (
+                "Assistant".to_string(),
+                "assistant",
+                vec![Block::Text(content.to_string())],
+            ),
+            NormalizedEntryType::Thinking => (
+                "Thinking".to_string(),
+                "thinking",
+                vec![Block::Quote(content.to_string())],
+            ),
+            NormalizedEntryType::SystemMessage => (
+                "System".to_string(),
+                "system",
+                vec![Block::Quote(content.to_string())],
+            ),
+            NormalizedEntryType::ErrorMessage => (
+                "Error".to_string(),
+                "error",
+                vec![Block::Code {
+                    lang: "text",
+                    body: content.to_string(),
+                }],
+            ),
+            NormalizedEntryType::ToolUse {
+                tool_name,
+                action_type,
+                status,
+            } => {
+                let label = match status_suffix(status) {
+                    Some(suffix) => format!("Tool · {tool_name} ({suffix})"),
+                    None => format!("Tool · {tool_name}"),
+                };
+                (label, "tool", action_blocks(action_type, content))
+            }
+        };
+        Some(Self {
+            label,
+            class,
+            blocks,
+        })
+    }
+}
+
+fn status_suffix(status: &ToolStatus) -> Option<String> {
+    match status {
+        ToolStatus::Created | ToolStatus::Success => None,
+        ToolStatus::Failed => Some("failed".to_string()),
+        ToolStatus::Denied {
+            reason: Some(reason),
+        } => Some(format!("denied: {reason}")),
+        ToolStatus::Denied { reason: None } => Some("denied".to_string()),
+        ToolStatus::PendingApproval { .. } => Some("pending approval".to_string()),
+        ToolStatus::TimedOut => Some("timed out".to_string()),
+    }
+}
+
+fn action_blocks(action: &ActionType, content: &str) -> Vec<Block> {
+    match action {
+        ActionType::FileRead { path } => vec![Block::Text(format!("Read `{path}`"))],
+        ActionType::FileEdit { path, changes } => {
+            let mut blocks = vec![Block::Text(format!("Edit `{path}`"))];
+            for change in changes {
+                blocks.push(match change {
+                    FileChange::Write { content } => Block::Code {
+                        lang: "text",
+                        body: content.clone(),
+                    },
+                    FileChange::Delete => Block::Text("Deleted file".to_string()),
+                    FileChange::Rename { new_path } => {
+                        Block::Text(format!("Renamed to `{new_path}`"))
+                    }
+                    FileChange::Edit { unified_diff, .. } => Block::Code {
+                        lang: "diff",
+                        body: unified_diff.clone(),
+                    },
+                });
+            }
+            blocks
+        }
+        ActionType::CommandRun { command, result } => {
+            let mut blocks = vec![Block::Code {
+                lang: "sh",
+                body: command.clone(),
+            }];
+            if let Some(output) = result.as_ref().and_then(|r| r.output.as_deref())
+                && !output.trim().is_empty()
+            {
+                blocks.push(Block::Code {
+                    lang: "text",
+                    body: output.to_string(),
+                });
+            }
+            blocks
+        }
+        ActionType::Search { query } => vec![Block::Text(format!("Search `{query}`"))],
+        ActionType::WebFetch { url } => vec![Block::Text(format!("Fetch {url}"))],
+        ActionType::Tool {
+            arguments, result, ..
+        } => {
+            let mut blocks = vec![Block::Text(content.to_string())];
+            if let Some(args) = arguments {
+                blocks.push(Block::Code {
+                    lang: "json",
+                    body: serde_json::to_string_pretty(args).unwrap_or_default(),
+                });
+            }
+            if let Some(result) = result {
+                let body = match &result.value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => serde_json::to_string_pretty(other).unwrap_or_default(),
+                };
+                blocks.push(Block::Code { lang: "text", body });
+            }
+            blocks
+        }
+        ActionType::TaskCreate { description } => {
+            vec![Block::Text(format!("Delegated task: {description}"))]
+        }
+        ActionType::PlanPresentation { plan } => vec![Block::Text(plan.clone())],
+        ActionType::TodoManagement { todos, .. } => {
+            let list = todos
+                .iter()
+                .map(|todo| {
+                    let mark = if todo.status == "completed" { "x" } else { " " };
+                    format!("- [{mark}] {}", todo.content)
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            vec![Block::Text(list)]
+        }
+        ActionType::Other { .. } => vec![Block::Text(content.to_string())],
+    }
+}
+
+struct DiffView {
+    path: String,
+    change: &'static str,
+    additions: usize,
+    deletions: usize,
+    unified: Option<String>,
+    omitted: bool,
+}
+
+impl DiffView {
+    fn from_diff(diff: &Diff) -> Self {
+        let path = GitService::diff_path(diff);
+        let unified =
+            if diff.content_omitted || (diff.old_content.is_none() && diff.new_content.is_none()) {
+                None
+            } else {
+                Some(create_unified_diff(
+                    &path,
+                    diff.old_content.as_deref().unwrap_or(""),
+                    diff.new_content.as_deref().unwrap_or(""),
+                ))
+            };
+
+        let (counted_add, counted_del) = unified.as_deref().map(count_changes).unwrap_or((0, 0));
+        Self {
+            change: change_label(&diff.change),
+            additions: diff.additions.unwrap_or(counted_add),
+            deletions: diff.deletions.unwrap_or(counted_del),
+            omitted: diff.content_omitted,
+            path,
+            unified,
+        }
+    }
+
+    fn omitted_reason(&self) -> &'static str {
+        if self.omitted {
+            "Content omitted (too large)"
+        } else {
+            "Binary or empty file"
+        }
+    }
+}
+
+fn change_label(change: &DiffChangeKind) -> &'static str {
+    match change {
+        DiffChangeKind::Added => "added",
+        DiffChangeKind::Deleted => "deleted",
+        DiffChangeKind::Modified => "modified",
+        DiffChangeKind::Renamed => "renamed",
+        DiffChangeKind::Copied => "copied",
+        DiffChangeKind::PermissionChange => "mode changed",
+    }
+}
+
+fn count_changes(unified: &str) -> (usize, usize) {
+    unified
+        .lines()
+        .filter(|l| !l.starts_with("+++ b/") && !l.starts_with("--- a/"))
+        .fold((0, 0), |(add, del), line| match line.as_bytes().first() {
+            Some(b'+') => (add + 1, del),
+            Some(b'-') => (add, del + 1),
+            _ => (add, del),
+        })
+}
+
+fn run_heading(run: &TranscriptRun) -> String {
+    let status = match run.status {
+        ExecutionProcessStatus::Running => "running",
+        ExecutionProcessStatus::Completed => "completed",
+        ExecutionProcessStatus::Failed => "failed",
+        ExecutionProcessStatus::Killed => "stopped",
+    };
+    format!(
+        "{} · {} · {}",
+        run.conversation.executor_type,
+        status,
+        run.started_at.format("%Y-%m-%d %H:%M UTC")
+    )
+}
+
+/// Wrap `body` in a Markdown code fence longer than any backtick run it contains
+fn fenced(body: &str, lang: &str) -> String {
+    let longest = body.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}{lang}\n{}\n{fence}", body.trim_end_matches('\n'))
+}
+
+fn html_code_block(body: &str, lang: &str) -> String {
+    if lang != "diff" {
+        return format!(
+            "<pre class=\"code\"><code>{}</code></pre>",
+            escape_html(body)
+        );
+    }
+    let lines = body
+        .lines()
+        .map(|line| {
+            let class = match line.as_bytes().first() {
+                _ if line.starts_with("+++ b/") || line.starts_with("--- a/") => "hdr",
+                Some(b'+') => "add",
+                Some(b'-') => "del",
+                Some(b'@') => "hunk",
+                _ => "ctx",
+            };
+            format!("<span class=\"{class}\">{}</span>", escape_html(line))
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("<pre class=\"code diff\"><code>{lines}</code></pre>")
+}
+
+pub(crate) fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = "\
+body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Helvetica,Arial,sans-serif;\
+max-width:960px;margin:2rem auto;padding:0 1rem;color:#1f2328;line-height:1.5}\
+h1{margin-bottom:.5rem}h2{border-bottom:1px solid #d0d7de;padding-bottom:.25rem;margin-top:2rem}\
+dl.meta{display:grid;grid-template-columns:max-content 1fr;gap:.15rem 1rem;color:#57606a}\
+dl.meta dt{font-weight:600}dl.meta dd{margin:0}\
+.entry{border-left:3px solid #d0d7de;padding:.25rem .75rem;margin:.75rem 0}\
+.entry .label{font-size:.8rem;font-weight:600;text-transform:uppercase;color:#57606a}\
+.entry.user{border-color:#0969da}.entry.assistant{border-color:#1a7f37}\
+.entry.tool{border-color:#8250df}.entry.error{border-color:#cf222e}\
+.entry.thinking,.entry.system{border-color:#afb8c1;color:#57606a}\
+.text{white-space:pre-wrap}blockquote{margin:0;white-space:pre-wrap;font-style:italic}\
+pre.code{background:#f6f8fa;padding:.75rem;overflow-x:auto;border-radius:6px;font-size:.85rem}\
+.diff .add{color:#1a7f37;background:#dafbe1}.diff .del{color:#cf222e;background:#ffebe9}\
+.diff .hunk{color:#8250df}.diff .hdr{color:#57606a}\
+summary .add{color:#1a7f37}summary .del{color:#cf222e}details{margin:1rem 0}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(entry_type: NormalizedEntryType, content: &str) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type,
+            content: content.to_string(),
+            metadata: None,
+        }
+    }
+
+    fn sample_transcript() -> SessionTranscript {
+        let now = Utc::now();
+        SessionTranscript {
+            task_attempt_id: Uuid::new_v4(),
+            task_id: Uuid::new_v4(),
+            task_title: "Fix <script> escaping".to_string(),
+            task_description: Some("Make the widget safe".to_string()),
+            project_name: "demo".to_string(),
+            executor: "CLAUDE_CODE".to_string(),
+            branch: Some("pcg/fix-escaping".to_string()),
+            base_branch: "main".to_string(),
+            attempt_created_at: now,
+            exported_at: now,
+            runs: vec![TranscriptRun {
+                execution_id: Uuid::new_v4(),
+                status: ExecutionProcessStatus::Completed,
+                exit_code: Some(0),
+                started_at: now,
+                completed_at: Some(now),
+                conversation: NormalizedConversation {
+                    entries: vec![
+                        entry(NormalizedEntryType::UserMessage, "please fix it"),
+                        entry(
+                            NormalizedEntryType::ToolUse {
+                                tool_name: "bash".to_string(),
+                                action_type: ActionType::CommandRun {
+                                    command: "cargo test".to_string(),
+                                    result: None,
+                                },
+                                status: ToolStatus::Success,
+                            },
+                            "cargo test",
+                        ),
+                        entry(NormalizedEntryType::Loading, ""),
+                        entry(NormalizedEntryType::AssistantMessage, "Done: ```ok```"),
+                    ],
+                    session_id: Some("sess-1".to_string()),
+                    executor_type: "CLAUDE_CODE".to_string(),
+                    prompt: Some("Fix the escaping".to_string()),
+                    summary: Some("Done".to_string()),
+                },
+            }],
+            diffs: vec![Diff {
+                change: DiffChangeKind::Modified,
+                old_path: Some("src/a.rs".to_string()),
+                new_path: Some("src/a.rs".to_string()),
+                old_content: Some("let a = 1;\n".to_string()),
+                new_content: Some("let a = 2;\n".to_string()),
+                content_omitted: false,
+                additions: None,
+                deletions: None,
+            }],
+            diff_error: None,
+        }
+    }
+
+    #[test]
+    fn markdown_includes_conversation_and_diff() {
+        let md = sample_transcript().to_markdown();
+        assert!(md.starts_with("# Fix <script> escaping"));
+        assert!(md.contains("#### User\n\nplease fix it"));
+        assert!(md.contains("```sh\ncargo test\n```"));
+        assert!(md.contains("### `src/a.rs` (modified, +1 −1)"));
+        assert!(md.contains("-let a = 1;\n+let a = 2;"));
+        assert!(!md.contains("#### Loading"));
+    }
+
+    #[test]
+    fn html_is_escaped_and_self_contained() {
+        let html = sample_transcript().to_html();
+        assert!(html.contains("<title>Fix &lt;script&gt; escaping</title>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<style>"));
+        assert!(!html.contains("<link"));
+        assert!(html.contains("<span class=\"add\">+let a = 2;</span>"));
+    }
+
+    #[test]
+    fn jsonl_round_trips() {
+        let transcript = sample_transcript();
+        let jsonl = transcript.to_jsonl().unwrap();
+        let records = parse_trajectory(&jsonl).unwrap();
+
+        // header + run + 4 steps + 1 diff
+        assert_eq!(records.len(), 7);
+        assert!(matches!(
+            records[0],
+            TrajectoryRecord::Header {
+                version: TRAJECTORY_VERSION,
+                ..
+            }
+        ));
+        match &records[3] {
+            TrajectoryRecord::Step {
+                run_index: 0,
+                step_index: 1,
+                entry,
+            } => assert!(matches!(
+                entry.entry_type,
+                NormalizedEntryType::ToolUse { .. }
+            )),
+            other => panic!("unexpected record: {other:?}"),
+        }
+        match records.last().unwrap() {
+            TrajectoryRecord::Diff {
+                additions: 1,
+                deletions: 1,
+                ..
+            } => {}
+            other => panic!("unexpected record: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn fence_outgrows_embedded_backticks() {
+        assert_eq!(fenced("a ```` b", "text"), "`````text\na ```` b\n`````");
+        assert_eq!(fenced("plain", "sh"), "```sh\nplain\n```");
+    }
+}
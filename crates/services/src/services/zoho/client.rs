@@ -0,0 +1,389 @@
+//! Minimal Zoho CRM v2 REST client
+//!
+//! Covers what the sync needs: refreshing OAuth access tokens, listing a
+//! module's records modified since a point in time, and creating or updating
+//! single records. Base URLs are injectable so tests can run against a stub.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::{Client, Response, StatusCode, header};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use super::ZohoError;
+
+/// A Zoho record as returned by the API, keyed by field API name
+pub type ZohoRecord = Map<String, Value>;
+
+const PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Clone)]
+pub struct ZohoClient {
+    http: Client,
+    accounts_url: String,
+    api_url: String,
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ZohoTokens {
+    pub access_token: String,
+    pub expires_in: Option<i64>,
+}
+
+/// Result of creating or updating a record
+#[derive(Debug, Clone)]
+pub struct ZohoSaved {
+    pub id: String,
+    pub modified_at: Option<DateTime<Utc>>,
+}
+
+impl ZohoClient {
+    /// Client for a Zoho data centre (`com`, `eu`, `in`, `com.au`, `jp`),
+    /// using the app credentials from `ZOHO_CLIENT_ID` / `ZOHO_CLIENT_SECRET`
+    pub fn for_domain(domain: &str) -> Result<Self, ZohoError> {
+        let client_id = std::env::var("ZOHO_CLIENT_ID")
+            .map_err(|_| ZohoError::Config("ZOHO_CLIENT_ID not configured".into()))?;
+        let client_secret = std::env::var("ZOHO_CLIENT_SECRET")
+            .map_err(|_| ZohoError::Config("ZOHO_CLIENT_SECRET not configured".into()))?;
+        Ok(Self::with_urls(
+            format!("https://accounts.zoho.{}", domain),
+            format!("https://www.zohoapis.{}", domain),
+            client_id,
+            client_secret,
+        ))
+    }
+
+    pub fn with_urls(
+        accounts_url: impl Into<String>,
+        api_url: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            http: Client::new(),
+            accounts_url: accounts_url.into().trim_end_matches('/').to_string(),
+            api_url: api_url.into().trim_end_matches('/').to_string(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+        }
+    }
+
+    /// Exchange a refresh token for a new access token
+    pub async fn refresh_access_token(&self, refresh_token: &str) -> Result<ZohoTokens, ZohoError> {
+        let response = self
+            .http
+            .post(format!("{}/oauth/v2/token", self.accounts_url))
+            .form(&[
+                ("refresh_token", refresh_token),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("grant_type", "refresh_token"),
+            ])
+            .send()
+            .await?;
+        let status = response.status();
+        let body: Value = response.json().await?;
+
+        // Zoho answers 200 with an `error` field for rejected grants
+        if let Some(error) = body.get("error").and_then(Value::as_str) {
+            return Err(ZohoError::Revoked(error.to_string()));
+        }
+        if !status.is_success() {
+            return Err(ZohoError::Auth(format!(
+                "token refresh failed ({})",
+                status
+            )));
+        }
+        serde_json::from_value(body)
+            .map_err(|e| ZohoError::InvalidResponse(format!("token response: {}", e)))
+    }
+
+    /// All records of a module modified after `since`, oldest change first
+    pub async fn list_modified(
+        &self,
+        access_token: &str,
+        module: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ZohoRecord>, ZohoError> {
+        let mut records = Vec::new();
+        let mut page = 1;
+        loop {
+            let mut request = self
+                .http
+                .get(format!("{}/crm/v2/{}", self.api_url, module))
+                .header(header::AUTHORIZATION, auth_header(access_token))
+                .query(&[
+                    ("page", page.to_string()),
+                    ("per_page", PAGE_SIZE.to_string()),
+                    ("sort_by", "Modified_Time".to_string()),
+                    ("sort_order", "asc".to_string()),
+                ]);
+            if let Some(since) = since {
+                request = request.header(
+                    "If-Modified-Since",
+                    since.to_rfc3339_opts(SecondsFormat::Secs, false),
+                );
+            }
+
+            let response = request.send().await?;
+            // No (more) records modified since `since`
+            if matches!(
+                response.status(),
+                StatusCode::NOT_MODIFIED | StatusCode::NO_CONTENT
+            ) {
+                break;
+            }
+            let body = check(response).await?;
+
+            if let Some(data) = body.get("data").and_then(Value::as_array) {
+                records.extend(data.iter().filter_map(|r| r.as_object().cloned()));
+            }
+            let more = body
+                .pointer("/info/more_records")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            if !more {
+                break;
+            }
+            page += 1;
+        }
+        Ok(records)
+    }
+
+    pub async fn create(
+        &self,
+        access_token: &str,
+        module: &str,
+        record: ZohoRecord,
+    ) -> Result<ZohoSaved, ZohoError> {
+        let response = self
+            .http
+            .post(format!("{}/crm/v2/{}", self.api_url, module))
+            .header(header::AUTHORIZATION, auth_header(access_token))
+            .json(&json!({ "data": [record] }))
+            .send()
+            .await?;
+        saved(check(response).await?)
+    }
+
+    pub async fn update(
+        &self,
+        access_token: &str,
+        module: &str,
+        id: &str,
+        record: ZohoRecord,
+    ) -> Result<ZohoSaved, ZohoError> {
+        let response = self
+            .http
+            .put(format!("{}/crm/v2/{}/{}", self.api_url, module, id))
+            .header(header::AUTHORIZATION, auth_header(access_token))
+            .json(&json!({ "data": [record] }))
+            .send()
+            .await?;
+        saved(check(response).await?)
+    }
+}
+
+fn auth_header(access_token: &str) -> String {
+    format!("Zoho-oauthtoken {}", access_token)
+}
+
+/// Turn error statuses into errors and parse the body
+async fn check(response: Response) -> Result<Value, ZohoError> {
+    let status = response.status();
+    let body = response.text().await?;
+    if status == StatusCode::UNAUTHORIZED {
+        return Err(ZohoError::Auth(body));
+    }
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        return Err(ZohoError::RateLimited);
+    }
+    if !status.is_success() {
+        return Err(ZohoError::Api {
+            status: status.as_u16(),
+            message: body,
+        });
+    }
+    serde_json::from_str(&body).map_err(|e| ZohoError::InvalidResponse(e.to_string()))
+}
+
+/// Read the single-record result of a create or update
+fn saved(body: Value) -> Result<ZohoSaved, ZohoError> {
+    let result = body
+        .pointer("/data/0")
+        .ok_or_else(|| ZohoError::InvalidResponse("missing data".into()))?;
+    if result.get("status").and_then(Value::as_str) != Some("success") {
+        return Err(ZohoError::Api {
+            status: 400,
+            message: result.to_string(),
+        });
+    }
+    let id = result
+        .pointer("/details/id")
+        .and_then(Value::as_str)
+        .ok_or_else(|| ZohoError::InvalidResponse("missing record id".into()))?;
+    Ok(ZohoSaved {
+        id: id.to_string(),
+        modified_at: result
+            .pointer("/details/Modified_Time")
+            .and_then(Value::as_str)
+            .and_then(parse_zoho_time),
+    })
+}
+
+/// Zoho timestamps carry the org's UTC offset, e.g. `2026-02-20T10:15:00+05:30`
+pub fn parse_zoho_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use axum::{
+        Form, Json, Router,
+        extract::{Query, State},
+        http::HeaderMap,
+        routing::{get, post},
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Seen {
+        since: Vec<Option<String>>,
+        auth: Vec<Option<String>>,
+    }
+
+    /// Serve `router` on a local port and return its base URL
+    pub(crate) async fn stub(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn refreshes_tokens_and_reports_revoked_grants() {
+        let router = Router::new().route(
+            "/oauth/v2/token",
+            post(|Form(form): Form<HashMap<String, String>>| async move {
+                if form.get("refresh_token").map(String::as_str) == Some("good") {
+                    Json(json!({ "access_token": "fresh", "expires_in": 3600 }))
+                } else {
+                    Json(json!({ "error": "invalid_code" }))
+                }
+            }),
+        );
+        let url = stub(router).await;
+        let client = ZohoClient::with_urls(&url, &url, "id", "secret");
+
+        let tokens = client.refresh_access_token("good").await.unwrap();
+        assert_eq!(tokens.access_token, "fresh");
+        assert_eq!(tokens.expires_in, Some(3600));
+        assert!(matches!(
+            client.refresh_access_token("stale").await,
+            Err(ZohoError::Revoked(code)) if code == "invalid_code"
+        ));
+    }
+
+    #[tokio::test]
+    async fn pages_through_modified_records() {
+        let seen = Arc::new(Mutex::new(Seen::default()));
+        let router = Router::new()
+            .route(
+                "/crm/v2/Contacts",
+                get(
+                    |State(seen): State<Arc<Mutex<Seen>>>,
+                     headers: HeaderMap,
+                     Query(query): Query<HashMap<String, String>>| async move {
+                        let header = |name: &str| {
+                            headers
+                                .get(name)
+                                .and_then(|v| v.to_str().ok())
+                                .map(str::to_string)
+                        };
+                        let mut seen = seen.lock().unwrap();
+                        seen.since.push(header("if-modified-since"));
+                        seen.auth.push(header("authorization"));
+                        let page = query["page"].as_str();
+                        Json(json!({
+                            "data": [{ "id": format!("z-{}", page), "Last_Name": "Lovelace" }],
+                            "info": { "more_records": page == "1" }
+                        }))
+                    },
+                ),
+            )
+            .with_state(seen.clone());
+        let url = stub(router).await;
+        let client = ZohoClient::with_urls(&url, &url, "id", "secret");
+
+        let since = DateTime::parse_from_rfc3339("2026-02-20T10:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let records = client
+            .list_modified("token", "Contacts", Some(since))
+            .await
+            .unwrap();
+
+        let ids: Vec<_> = records.iter().map(|r| r["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["z-1", "z-2"]);
+        let seen = seen.lock().unwrap();
+        assert_eq!(
+            seen.since,
+            vec![Some("2026-02-20T10:00:00+00:00".to_string()); 2]
+        );
+        assert_eq!(seen.auth[0].as_deref(), Some("Zoho-oauthtoken token"));
+    }
+
+    #[tokio::test]
+    async fn reads_saved_record_and_errors() {
+        let router = Router::new()
+            .route(
+                "/crm/v2/Contacts",
+                post(|| async {
+                    Json(json!({ "data": [{
+                        "code": "SUCCESS",
+                        "status": "success",
+                        "details": { "id": "z-9", "Modified_Time": "2026-02-20T15:30:00+05:30" }
+                    }]}))
+                }),
+            )
+            .route(
+                "/crm/v2/Deals",
+                get(|| async { axum::http::StatusCode::NOT_MODIFIED }).post(|| async {
+                    Json(json!({ "data": [{
+                        "code": "MANDATORY_NOT_FOUND",
+                        "status": "error",
+                        "details": { "api_name": "Deal_Name" }
+                    }]}))
+                }),
+            );
+        let url = stub(router).await;
+        let client = ZohoClient::with_urls(&url, &url, "id", "secret");
+
+        let saved = client
+            .create("token", "Contacts", ZohoRecord::new())
+            .await
+            .unwrap();
+        assert_eq!(saved.id, "z-9");
+        assert_eq!(saved.modified_at, parse_zoho_time("2026-02-20T10:00:00Z"));
+        assert!(matches!(
+            client.create("token", "Deals", ZohoRecord::new()).await,
+            Err(ZohoError::Api { .. })
+        ));
+        assert!(
+            client
+                .list_modified("token", "Deals", Some(Utc::now()))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,498 @@
+//! Field mapping and three-way merge between local records and Zoho
+//!
+//! Every mapped field is compared against the value both sides agreed on at
+//! the last sync (the snapshot). A field that changed on one side flows to
+//! the other; a field that changed on both is a conflict, settled by the
+//! integration's per-field [`ConflictStrategy`].
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use db::models::{
+    crm_deal::CrmDeal,
+    custom_field_definition::{CustomFieldDefinition, CustomFieldType},
+    zoho_integration::{ConflictRules, ConflictStrategy, ConflictWinner, ZohoSyncDirection},
+};
+use serde_json::Value;
+
+use super::client::ZohoRecord;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Text,
+    Number,
+    Boolean,
+    /// Compared and sent as `YYYY-MM-DD`
+    Date,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMapping {
+    /// Local field name, as in [`db::models::crm_contact::CrmContact::field_values`]
+    pub local: String,
+    /// Zoho field API name
+    pub zoho: String,
+    pub kind: FieldKind,
+}
+
+const CONTACT_FIELDS: &[(&str, &str, FieldKind)] = &[
+    ("first_name", "First_Name", FieldKind::Text),
+    ("last_name", "Last_Name", FieldKind::Text),
+    ("email", "Email", FieldKind::Text),
+    ("phone", "Phone", FieldKind::Text),
+    ("mobile", "Mobile", FieldKind::Text),
+    ("job_title", "Title", FieldKind::Text),
+    ("department", "Department", FieldKind::Text),
+    ("twitter_handle", "Twitter", FieldKind::Text),
+    ("city", "Mailing_City", FieldKind::Text),
+    ("state", "Mailing_State", FieldKind::Text),
+    ("country", "Mailing_Country", FieldKind::Text),
+];
+
+const DEAL_FIELDS: &[(&str, &str, FieldKind)] = &[
+    ("name", "Deal_Name", FieldKind::Text),
+    ("description", "Description", FieldKind::Text),
+    ("amount", "Amount", FieldKind::Number),
+    ("stage", "Stage", FieldKind::Text),
+    ("expected_close_date", "Closing_Date", FieldKind::Date),
+];
+
+/// Mapped contact fields: the integration's mapping (or the defaults) plus
+/// custom fields whose definition names a Zoho field
+pub fn contact_mappings(
+    overrides: Option<BTreeMap<String, String>>,
+    custom_fields: &[CustomFieldDefinition],
+) -> Vec<FieldMapping> {
+    build_mappings(CONTACT_FIELDS, overrides, custom_fields, "Contacts")
+}
+
+pub fn deal_mappings(
+    overrides: Option<BTreeMap<String, String>>,
+    custom_fields: &[CustomFieldDefinition],
+) -> Vec<FieldMapping> {
+    build_mappings(DEAL_FIELDS, overrides, custom_fields, "Deals")
+}
+
+fn build_mappings(
+    defaults: &[(&str, &str, FieldKind)],
+    overrides: Option<BTreeMap<String, String>>,
+    custom_fields: &[CustomFieldDefinition],
+    module: &str,
+) -> Vec<FieldMapping> {
+    let default_kind = |local: &str| {
+        defaults
+            .iter()
+            .find(|(name, _, _)| *name == local)
+            .map(|(_, _, kind)| *kind)
+            .unwrap_or(FieldKind::Text)
+    };
+
+    let mut mappings: Vec<FieldMapping> = match overrides {
+        Some(overrides) => overrides
+            .into_iter()
+            .map(|(local, zoho)| FieldMapping {
+                kind: default_kind(&local),
+                local,
+                zoho,
+            })
+            .collect(),
+        None => defaults
+            .iter()
+            .map(|(local, zoho, kind)| FieldMapping {
+                local: local.to_string(),
+                zoho: zoho.to_string(),
+                kind: *kind,
+            })
+            .collect(),
+    };
+
+    // Custom fields opt in with `{"zoho_field": "Api_Name", "zoho_module": "Contacts"}`
+    for definition in custom_fields {
+        let Some(metadata) = &definition.metadata else {
+            continue;
+        };
+        let Some(zoho) = metadata.get("zoho_field").and_then(Value::as_str) else {
+            continue;
+        };
+        let for_module = metadata
+            .get("zoho_module")
+            .and_then(Value::as_str)
+            .is_none_or(|m| m.eq_ignore_ascii_case(module));
+        if !for_module || mappings.iter().any(|m| m.zoho == zoho) {
+            continue;
+        }
+        mappings.push(FieldMapping {
+            local: format!("custom_fields.{}", definition.name),
+            zoho: zoho.to_string(),
+            kind: match definition.field_type {
+                CustomFieldType::Number => FieldKind::Number,
+                CustomFieldType::Checkbox => FieldKind::Boolean,
+                CustomFieldType::Date => FieldKind::Date,
+                _ => FieldKind::Text,
+            },
+        });
+    }
+
+    mappings
+}
+
+/// Non-empty deal fields by name, with custom fields as `custom_fields.<key>`
+pub fn deal_values(deal: &CrmDeal) -> BTreeMap<String, String> {
+    let mut fields = BTreeMap::new();
+    fields.insert("name".to_string(), deal.name.clone());
+    fields.insert("stage".to_string(), deal.stage.clone());
+    fields.insert("currency".to_string(), deal.currency.clone());
+    if let Some(description) = deal.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        fields.insert("description".to_string(), description.to_string());
+    }
+    if let Some(amount) = deal.amount {
+        fields.insert("amount".to_string(), amount.to_string());
+    }
+    if let Some(date) = deal.expected_close_date {
+        fields.insert(
+            "expected_close_date".to_string(),
+            date.format("%Y-%m-%d").to_string(),
+        );
+    }
+
+    let custom: serde_json::Map<String, Value> = deal
+        .custom_fields
+        .as_deref()
+        .and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_default();
+    for (key, value) in custom {
+        if let Some(value) = text(&value) {
+            fields.insert(format!("custom_fields.{}", key), value);
+        }
+    }
+    fields
+}
+
+/// Comparable text of a JSON value; lookups compare by name
+fn text(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::Null => return None,
+        Value::String(s) => s.trim().to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Object(map) => return map.get("name").and_then(text),
+        Value::Array(items) => items.iter().filter_map(text).collect::<Vec<_>>().join(", "),
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// Bring a value into a form where equal values compare equal
+pub fn normalize(kind: FieldKind, value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    Some(match kind {
+        FieldKind::Text => value.to_string(),
+        FieldKind::Number => match value.parse::<f64>() {
+            Ok(n) => n.to_string(),
+            Err(_) => value.to_string(),
+        },
+        FieldKind::Boolean => {
+            matches!(value.to_lowercase().as_str(), "true" | "1" | "yes").to_string()
+        }
+        FieldKind::Date => value.chars().take(10).collect(),
+    })
+}
+
+/// The value of a mapped field on a Zoho record
+pub fn zoho_value(record: &ZohoRecord, mapping: &FieldMapping) -> Option<String> {
+    record
+        .get(&mapping.zoho)
+        .and_then(text)
+        .and_then(|v| normalize(mapping.kind, &v))
+}
+
+/// JSON to send to Zoho for a local value
+pub fn to_zoho(mapping: &FieldMapping, value: Option<&str>) -> Value {
+    let Some(value) = value else {
+        return Value::Null;
+    };
+    match mapping.kind {
+        FieldKind::Number => value
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .unwrap_or(Value::Null),
+        FieldKind::Boolean => Value::Bool(value == "true"),
+        FieldKind::Text | FieldKind::Date => Value::String(value.to_string()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldConflict {
+    pub field: String,
+    pub local: Option<String>,
+    pub zoho: Option<String>,
+    pub strategy: ConflictStrategy,
+    pub winner: ConflictWinner,
+}
+
+/// What to write where after comparing a local record with Zoho
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Local field -> value taken from Zoho
+    pub to_local: BTreeMap<String, String>,
+    /// Zoho field -> value to send
+    pub to_zoho: BTreeMap<String, Option<String>>,
+    pub conflicts: Vec<FieldConflict>,
+    /// Zoho field -> value both sides hold afterwards
+    pub snapshot: BTreeMap<String, String>,
+}
+
+/// Both sides of one record, plus what they agreed on last time
+pub struct MergeInput<'a> {
+    pub local: &'a BTreeMap<String, String>,
+    pub local_modified_at: DateTime<Utc>,
+    /// `None` when Zoho has not changed the record since the last sync
+    pub zoho: Option<&'a ZohoRecord>,
+    pub zoho_modified_at: Option<DateTime<Utc>>,
+    pub snapshot: &'a BTreeMap<String, String>,
+}
+
+pub fn reconcile(
+    mappings: &[FieldMapping],
+    input: &MergeInput<'_>,
+    rules: &ConflictRules,
+    direction: ZohoSyncDirection,
+) -> Reconciliation {
+    let mut result = Reconciliation::default();
+
+    for mapping in mappings {
+        let local = input
+            .local
+            .get(&mapping.local)
+            .and_then(|v| normalize(mapping.kind, v));
+        let previous = input
+            .snapshot
+            .get(&mapping.zoho)
+            .and_then(|v| normalize(mapping.kind, v));
+        let zoho = match input.zoho {
+            Some(record) => zoho_value(record, mapping),
+            None => previous.clone(),
+        };
+
+        let winner = if local == zoho {
+            None
+        } else if !direction.pushes() {
+            Some(ConflictWinner::Zoho)
+        } else if !direction.pulls() {
+            Some(ConflictWinner::Local)
+        } else if local == previous {
+            Some(ConflictWinner::Zoho)
+        } else if zoho == previous {
+            Some(ConflictWinner::Local)
+        } else {
+            let strategy = rules.strategy_for(&mapping.local);
+            let winner = match strategy {
+                ConflictStrategy::SourceWins => ConflictWinner::Zoho,
+                ConflictStrategy::NewestWins => match input.zoho_modified_at {
+                    Some(zoho_at) if zoho_at > input.local_modified_at => ConflictWinner::Zoho,
+                    _ => ConflictWinner::Local,
+                },
+            };
+            result.conflicts.push(FieldConflict {
+                field: mapping.local.clone(),
+                local: local.clone(),
+                zoho: zoho.clone(),
+                strategy,
+                winner,
+            });
+            Some(winner)
+        };
+
+        let agreed = match winner {
+            None => local,
+            Some(ConflictWinner::Local) => {
+                result.to_zoho.insert(mapping.zoho.clone(), local.clone());
+                local
+            }
+            // Clearing a field in Zoho does not clear it locally; remember
+            // the local value so the cleared field is not pushed back
+            Some(ConflictWinner::Zoho) => match zoho {
+                Some(value) => {
+                    result.to_local.insert(mapping.local.clone(), value.clone());
+                    Some(value)
+                }
+                None => local,
+            },
+        };
+        if let Some(agreed) = agreed {
+            result.snapshot.insert(mapping.zoho.clone(), agreed);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn mappings() -> Vec<FieldMapping> {
+        contact_mappings(None, &[])
+    }
+
+    fn map(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(&format!("2026-02-20T{:02}:00:00Z", hour))
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn one_sided_changes_flow_to_the_other_side() {
+        let local = map(&[("email", "ada@example.com"), ("phone", "+44 1")]);
+        let snapshot = map(&[("Email", "ada@example.com"), ("Phone", "+44 0")]);
+        let zoho = json!({ "Email": "ada@zoho.example", "Phone": "+44 0" });
+
+        let result = reconcile(
+            &mappings(),
+            &MergeInput {
+                local: &local,
+                local_modified_at: at(9),
+                zoho: zoho.as_object(),
+                zoho_modified_at: Some(at(10)),
+                snapshot: &snapshot,
+            },
+            &ConflictRules::default(),
+            ZohoSyncDirection::Bidirectional,
+        );
+
+        assert_eq!(result.to_local, map(&[("email", "ada@zoho.example")]));
+        assert_eq!(
+            result.to_zoho,
+            [("Phone".to_string(), Some("+44 1".to_string()))].into()
+        );
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.snapshot,
+            map(&[("Email", "ada@zoho.example"), ("Phone", "+44 1")])
+        );
+    }
+
+    #[test]
+    fn conflicts_follow_per_field_strategy() {
+        let local = map(&[("email", "local@example.com"), ("phone", "+1 local")]);
+        let snapshot = map(&[("Email", "old@example.com"), ("Phone", "+1 old")]);
+        let zoho = json!({ "Email": "zoho@example.com", "Phone": "+1 zoho" });
+        let rules = ConflictRules {
+            default: ConflictStrategy::NewestWins,
+            fields: [("email".to_string(), ConflictStrategy::SourceWins)].into(),
+        };
+
+        // Local was edited after Zoho, so newest-wins keeps the local phone
+        let result = reconcile(
+            &mappings(),
+            &MergeInput {
+                local: &local,
+                local_modified_at: at(11),
+                zoho: zoho.as_object(),
+                zoho_modified_at: Some(at(10)),
+                snapshot: &snapshot,
+            },
+            &rules,
+            ZohoSyncDirection::Bidirectional,
+        );
+
+        assert_eq!(result.to_local, map(&[("email", "zoho@example.com")]));
+        assert_eq!(
+            result.to_zoho,
+            [("Phone".to_string(), Some("+1 local".to_string()))].into()
+        );
+        assert_eq!(result.conflicts.len(), 2);
+        let email = result
+            .conflicts
+            .iter()
+            .find(|c| c.field == "email")
+            .unwrap();
+        assert_eq!(email.strategy, ConflictStrategy::SourceWins);
+        assert_eq!(email.winner, ConflictWinner::Zoho);
+        let phone = result
+            .conflicts
+            .iter()
+            .find(|c| c.field == "phone")
+            .unwrap();
+        assert_eq!(phone.winner, ConflictWinner::Local);
+    }
+
+    #[test]
+    fn new_records_push_everything_and_one_way_syncs_never_conflict() {
+        let local = map(&[("first_name", "Ada"), ("last_name", "Lovelace")]);
+        let empty = BTreeMap::new();
+
+        let created = reconcile(
+            &mappings(),
+            &MergeInput {
+                local: &local,
+                local_modified_at: at(9),
+                zoho: None,
+                zoho_modified_at: None,
+                snapshot: &empty,
+            },
+            &ConflictRules::default(),
+            ZohoSyncDirection::Bidirectional,
+        );
+        assert_eq!(created.to_zoho.len(), 2);
+        assert!(created.to_local.is_empty());
+
+        let zoho = json!({ "First_Name": "Augusta", "Last_Name": "Lovelace" });
+        let pulled = reconcile(
+            &mappings(),
+            &MergeInput {
+                local: &local,
+                local_modified_at: at(12),
+                zoho: zoho.as_object(),
+                zoho_modified_at: Some(at(9)),
+                snapshot: &empty,
+            },
+            &ConflictRules::default(),
+            ZohoSyncDirection::FromZoho,
+        );
+        assert_eq!(pulled.to_local, map(&[("first_name", "Augusta")]));
+        assert!(pulled.to_zoho.is_empty());
+        assert!(pulled.conflicts.is_empty());
+    }
+
+    #[test]
+    fn normalizes_numbers_dates_and_lookups() {
+        let deal = deal_mappings(None, &[]);
+        let amount = deal.iter().find(|m| m.local == "amount").unwrap();
+        let closing = deal
+            .iter()
+            .find(|m| m.local == "expected_close_date")
+            .unwrap();
+        let record = json!({ "Amount": 1500, "Closing_Date": "2026-03-31", "Account_Name": { "name": "Acme", "id": "1" } });
+        let record = record.as_object().unwrap();
+
+        assert_eq!(
+            zoho_value(record, amount),
+            normalize(FieldKind::Number, "1500.0")
+        );
+        assert_eq!(
+            zoho_value(record, closing),
+            normalize(FieldKind::Date, "2026-03-31T00:00:00Z")
+        );
+        assert_eq!(to_zoho(amount, Some("1500")), json!(1500.0));
+        let account = FieldMapping {
+            local: "company_name".into(),
+            zoho: "Account_Name".into(),
+            kind: FieldKind::Text,
+        };
+        assert_eq!(zoho_value(record, &account).as_deref(), Some("Acme"));
+    }
+}
@@ -0,0 +1,52 @@
+//! Zoho CRM Synchronisation
+//!
+//! Keeps a project's CRM contacts, deals and call/meeting activities in step
+//! with a connected Zoho CRM org:
+//! - Pulls records modified in Zoho since the last sync
+//! - Pushes records created or edited locally
+//! - Resolves fields edited on both sides with per-field conflict rules and
+//!   logs each resolution
+
+pub mod client;
+pub mod mapping;
+pub mod sync;
+
+pub use client::ZohoClient;
+use db::models::{
+    crm_activity::CrmActivityError, crm_contact::CrmContactError, crm_deal::CrmDealError,
+    crm_pipeline::CrmPipelineError, zoho_integration::ZohoIntegrationError,
+};
+pub use sync::{ZohoSyncReport, ZohoSyncService};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ZohoError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("Zoho is not configured: {0}")]
+    Config(String),
+    #[error("Zoho rejected the access token: {0}")]
+    Auth(String),
+    #[error("Zoho access was revoked: {0}")]
+    Revoked(String),
+    #[error("Rate limited by Zoho")]
+    RateLimited,
+    #[error("Zoho API error ({status}): {message}")]
+    Api { status: u16, message: String },
+    #[error("Unexpected Zoho response: {0}")]
+    InvalidResponse(String),
+    #[error("Project is not connected to Zoho")]
+    NotConnected,
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Integration(#[from] ZohoIntegrationError),
+    #[error(transparent)]
+    Contact(#[from] CrmContactError),
+    #[error(transparent)]
+    Deal(#[from] CrmDealError),
+    #[error(transparent)]
+    Activity(#[from] CrmActivityError),
+    #[error(transparent)]
+    Pipeline(#[from] CrmPipelineError),
+}
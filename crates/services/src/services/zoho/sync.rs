@@ -0,0 +1,1349 @@
+//! Scheduled two-way sync between the local CRM and Zoho CRM
+//!
+//! Each run pulls what Zoho changed since the module's last sync, merges it
+//! into the matching local record (linked earlier, or found by email), then
+//! pushes local records created or edited since their last sync. Activities
+//! are append-only: calls and meetings are copied across once and not kept
+//! in step afterwards.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use db::models::{
+    crm_activity::{CreateCrmActivity, CrmActivity, CrmActivityType},
+    crm_contact::{ContactSource, CreateCrmContact, CrmContact, UpdateCrmContact},
+    crm_deal::{CreateCrmDeal, CrmDeal, UpdateCrmDeal},
+    crm_pipeline::{CrmPipeline, CrmPipelineStage},
+    custom_field_definition::CustomFieldDefinition,
+    zoho_integration::{
+        ConflictRules, CreateZohoSyncConflict, ZohoEntity, ZohoIntegration, ZohoSyncConflict,
+        ZohoSyncDirection, ZohoSyncRecord,
+    },
+};
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use sqlx::SqlitePool;
+use tokio::{task::JoinHandle, time::interval};
+use tracing::{error, info, warn};
+use ts_rs::TS;
+use uuid::Uuid;
+
+use super::{
+    ZohoError,
+    client::{ZohoClient, ZohoRecord, parse_zoho_time},
+    mapping::{self, FieldMapping, MergeInput, Reconciliation},
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(600);
+
+const CONTACTS: &str = "Contacts";
+const DEALS: &str = "Deals";
+const CALLS: &str = "Calls";
+const EVENTS: &str = "Events";
+
+/// Local activity types copied to Zoho as calls or meetings
+const PUSHED_ACTIVITY_TYPES: &[&str] = &[
+    "call_made",
+    "call_received",
+    "meeting_scheduled",
+    "meeting_completed",
+];
+
+#[derive(Debug, Clone, Default, Serialize, TS)]
+#[ts(export)]
+pub struct ZohoSyncReport {
+    pub contacts_pulled: u32,
+    pub contacts_pushed: u32,
+    pub deals_pulled: u32,
+    pub deals_pushed: u32,
+    pub activities_pulled: u32,
+    pub activities_pushed: u32,
+    pub conflicts: u32,
+}
+
+/// Everything a run needs about the integration it is syncing
+struct SyncRun<'a> {
+    client: ZohoClient,
+    token: String,
+    integration: &'a ZohoIntegration,
+    rules: ConflictRules,
+    direction: ZohoSyncDirection,
+}
+
+/// One Zoho record, or a local record Zoho has not changed, ready to merge
+struct Merge<'a> {
+    local_id: Uuid,
+    local: BTreeMap<String, String>,
+    local_modified_at: DateTime<Utc>,
+    zoho: Option<&'a ZohoRecord>,
+    link: Option<ZohoSyncRecord>,
+}
+
+#[derive(Clone)]
+pub struct ZohoSyncService {
+    pool: SqlitePool,
+    /// Fixed client for tests; otherwise built from the integration's domain
+    client: Option<ZohoClient>,
+}
+
+impl ZohoSyncService {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool, client: None }
+    }
+
+    pub fn with_client(pool: SqlitePool, client: ZohoClient) -> Self {
+        Self {
+            pool,
+            client: Some(client),
+        }
+    }
+
+    /// Sync every connected project in the background
+    pub async fn spawn(pool: SqlitePool) -> JoinHandle<()> {
+        let service = Self::new(pool);
+        tokio::spawn(async move {
+            service.start().await;
+        })
+    }
+
+    async fn start(&self) {
+        info!(
+            "Starting Zoho sync service with interval {:?}",
+            SYNC_INTERVAL
+        );
+
+        let mut interval = interval(SYNC_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let integrations = match ZohoIntegration::find_active(&self.pool).await {
+                Ok(integrations) => integrations,
+                Err(e) => {
+                    error!("Failed to load Zoho integrations: {}", e);
+                    continue;
+                }
+            };
+            for integration in integrations {
+                match self.sync_integration(&integration).await {
+                    Ok(report) => info!(
+                        "Zoho sync for project {}: {:?}",
+                        integration.project_id, report
+                    ),
+                    Err(e) => warn!(
+                        "Zoho sync for project {} failed: {}",
+                        integration.project_id, e
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Sync one project now
+    pub async fn sync_project(&self, project_id: Uuid) -> Result<ZohoSyncReport, ZohoError> {
+        let integration = ZohoIntegration::find_by_project(&self.pool, project_id)
+            .await?
+            .filter(|i| i.refresh_token.is_some())
+            .ok_or(ZohoError::NotConnected)?;
+        self.sync_integration(&integration).await
+    }
+
+    /// Run a full sync and record its outcome on the integration
+    pub async fn sync_integration(
+        &self,
+        integration: &ZohoIntegration,
+    ) -> Result<ZohoSyncReport, ZohoError> {
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => ZohoClient::for_domain(integration.zoho_domain.as_deref().unwrap_or("com"))?,
+        };
+
+        let token = match self.access_token(&client, integration).await {
+            Ok(token) => token,
+            Err(ZohoError::Revoked(reason)) => {
+                ZohoIntegration::revoke(&self.pool, integration.id, &reason).await?;
+                return Err(ZohoError::Revoked(reason));
+            }
+            Err(e) => {
+                ZohoIntegration::set_result(&self.pool, integration.id, Some(&e.to_string()))
+                    .await?;
+                return Err(e);
+            }
+        };
+
+        let run = SyncRun {
+            client,
+            token,
+            integration,
+            rules: integration.conflict_rules(),
+            direction: integration.direction(),
+        };
+        let result = self.run(&run).await;
+
+        match &result {
+            Ok(_) => ZohoIntegration::set_result(&self.pool, integration.id, None).await?,
+            Err(e) => {
+                // A rejected access token is refreshed on the next run
+                if matches!(e, ZohoError::Auth(_)) {
+                    ZohoIntegration::update_tokens(
+                        &self.pool,
+                        integration.id,
+                        &run.token,
+                        Some(Utc::now()),
+                    )
+                    .await?;
+                }
+                ZohoIntegration::set_result(&self.pool, integration.id, Some(&e.to_string()))
+                    .await?;
+            }
+        }
+        result
+    }
+
+    async fn access_token(
+        &self,
+        client: &ZohoClient,
+        integration: &ZohoIntegration,
+    ) -> Result<String, ZohoError> {
+        if !integration.needs_token_refresh()
+            && let Some(token) = &integration.access_token
+        {
+            return Ok(token.clone());
+        }
+
+        let refresh_token = integration
+            .refresh_token
+            .as_deref()
+            .ok_or(ZohoError::NotConnected)?;
+        let tokens = client.refresh_access_token(refresh_token).await?;
+        let expires_at = tokens
+            .expires_in
+            .map(|secs| Utc::now() + chrono::Duration::seconds(secs));
+        ZohoIntegration::update_tokens(
+            &self.pool,
+            integration.id,
+            &tokens.access_token,
+            expires_at,
+        )
+        .await?;
+
+        Ok(tokens.access_token)
+    }
+
+    async fn run(&self, run: &SyncRun<'_>) -> Result<ZohoSyncReport, ZohoError> {
+        let integration = run.integration;
+        let custom_fields =
+            CustomFieldDefinition::list_by_project(&self.pool, integration.project_id).await?;
+        let mut report = ZohoSyncReport::default();
+
+        if integration.syncs(ZohoEntity::Contact) {
+            let mappings = mapping::contact_mappings(integration.contact_mapping(), &custom_fields);
+            self.sync_contacts(run, &mappings, &mut report).await?;
+        }
+        if integration.syncs(ZohoEntity::Deal) {
+            let mappings = mapping::deal_mappings(integration.deal_mapping(), &custom_fields);
+            self.sync_deals(run, &mappings, &mut report).await?;
+        }
+        if integration.syncs(ZohoEntity::Activity) {
+            self.sync_activities(run, &mut report).await?;
+        }
+
+        Ok(report)
+    }
+
+    async fn sync_contacts(
+        &self,
+        run: &SyncRun<'_>,
+        mappings: &[FieldMapping],
+        report: &mut ZohoSyncReport,
+    ) -> Result<(), ZohoError> {
+        let integration = run.integration;
+        let started = Utc::now();
+        let mut handled = HashSet::new();
+
+        if run.direction.pulls() {
+            let records = run
+                .client
+                .list_modified(
+                    &run.token,
+                    CONTACTS,
+                    integration.last_synced_at(ZohoEntity::Contact),
+                )
+                .await?;
+
+            for record in &records {
+                let Some(zoho_id) = record_id(record) else {
+                    continue;
+                };
+                let link = self
+                    .link_by_zoho_id(run, ZohoEntity::Contact, zoho_id)
+                    .await?;
+                if is_echo(link.as_ref(), record) {
+                    continue;
+                }
+
+                let existing = match &link {
+                    Some(link) => CrmContact::find_by_id(&self.pool, link.local_id).await.ok(),
+                    None => self.match_contact(run, zoho_id, record).await?,
+                };
+                let contact = match existing {
+                    Some(contact) => contact,
+                    None => {
+                        CrmContact::create(
+                            &self.pool,
+                            CreateCrmContact {
+                                project_id: integration.project_id,
+                                first_name: None,
+                                last_name: None,
+                                email: None,
+                                phone: None,
+                                mobile: None,
+                                avatar_url: None,
+                                company_name: None,
+                                job_title: None,
+                                department: None,
+                                linkedin_url: None,
+                                twitter_handle: None,
+                                website: None,
+                                source: Some(ContactSource::ZohoSync),
+                                lifecycle_stage: None,
+                                tags: None,
+                                custom_fields: None,
+                                zoho_contact_id: Some(zoho_id.to_string()),
+                                gmail_contact_id: None,
+                            },
+                        )
+                        .await?
+                    }
+                };
+
+                handled.insert(contact.id);
+                self.merge_contact(run, mappings, &contact, Some(record), link, report)
+                    .await?;
+            }
+
+            ZohoIntegration::mark_synced(&self.pool, integration.id, ZohoEntity::Contact, started)
+                .await?;
+        }
+
+        if run.direction.pushes() {
+            for contact in ZohoSyncRecord::changed_contacts(&self.pool, integration).await? {
+                if handled.contains(&contact.id) {
+                    continue;
+                }
+                let link = ZohoSyncRecord::find_by_local(
+                    &self.pool,
+                    integration.id,
+                    ZohoEntity::Contact,
+                    contact.id,
+                )
+                .await?;
+                self.merge_contact(run, mappings, &contact, None, link, report)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// An unlinked local contact for a Zoho contact: one stamped with its
+    /// Zoho id, or else one with the same email
+    async fn match_contact(
+        &self,
+        run: &SyncRun<'_>,
+        zoho_id: &str,
+        record: &ZohoRecord,
+    ) -> Result<Option<CrmContact>, ZohoError> {
+        let project_id = run.integration.project_id;
+        if let Some(contact) = CrmContact::find_by_zoho_id(&self.pool, zoho_id)
+            .await?
+            .filter(|c| c.project_id == project_id)
+        {
+            return Ok(Some(contact));
+        }
+        match record.get("Email").and_then(Value::as_str) {
+            Some(email) => Ok(CrmContact::find_by_email(&self.pool, project_id, email).await?),
+            None => Ok(None),
+        }
+    }
+
+    async fn merge_contact(
+        &self,
+        run: &SyncRun<'_>,
+        mappings: &[FieldMapping],
+        contact: &CrmContact,
+        zoho: Option<&ZohoRecord>,
+        link: Option<ZohoSyncRecord>,
+        report: &mut ZohoSyncReport,
+    ) -> Result<(), ZohoError> {
+        let merge = Merge {
+            local_id: contact.id,
+            local: contact.field_values(),
+            local_modified_at: contact.updated_at,
+            zoho,
+            link,
+        };
+        let result = reconcile(run, mappings, &merge);
+
+        if !result.to_local.is_empty() {
+            let mut update = UpdateCrmContact::default();
+            let mut custom = custom_fields(contact.custom_fields.as_deref());
+            for mapping in mappings {
+                let Some(value) = result.to_local.get(&mapping.local) else {
+                    continue;
+                };
+                if let Some(key) = mapping.local.strip_prefix("custom_fields.") {
+                    custom.insert(key.to_string(), mapping::to_zoho(mapping, Some(value)));
+                } else if !set_contact_field(&mut update, &mapping.local, value.clone()) {
+                    warn!(
+                        "Zoho field {} maps to unknown contact field {}",
+                        mapping.zoho, mapping.local
+                    );
+                }
+            }
+            if result
+                .to_local
+                .keys()
+                .any(|f| f.starts_with("custom_fields."))
+            {
+                update.custom_fields = Some(Value::Object(custom));
+            }
+            CrmContact::update(&self.pool, contact.id, update).await?;
+            report.contacts_pulled += 1;
+        }
+
+        let mut body = request_body(mappings, &result);
+        let zoho_id = merge
+            .link
+            .as_ref()
+            .map(|l| l.zoho_id.clone())
+            .or_else(|| zoho.and_then(record_id).map(str::to_string))
+            .or_else(|| contact.zoho_contact_id.clone());
+        if zoho_id.is_none() && !body.contains_key("Last_Name") {
+            // Zoho refuses contacts without a last name
+            let fallback = contact
+                .full_name
+                .clone()
+                .or_else(|| contact.email.clone())
+                .unwrap_or_else(|| "Unknown".to_string());
+            body.insert("Last_Name".to_string(), Value::String(fallback));
+        }
+
+        let (zoho_id, zoho_modified_at) = self
+            .push(
+                run,
+                CONTACTS,
+                zoho_id,
+                zoho,
+                body,
+                &mut report.contacts_pushed,
+            )
+            .await?;
+        self.finish_merge(
+            run,
+            ZohoEntity::Contact,
+            &merge,
+            &zoho_id,
+            zoho_modified_at,
+            result,
+            report,
+        )
+        .await
+    }
+
+    async fn sync_deals(
+        &self,
+        run: &SyncRun<'_>,
+        mappings: &[FieldMapping],
+        report: &mut ZohoSyncReport,
+    ) -> Result<(), ZohoError> {
+        let integration = run.integration;
+        let started = Utc::now();
+        let mut handled = HashSet::new();
+
+        if run.direction.pulls() {
+            let records = run
+                .client
+                .list_modified(
+                    &run.token,
+                    DEALS,
+                    integration.last_synced_at(ZohoEntity::Deal),
+                )
+                .await?;
+
+            for record in &records {
+                let Some(zoho_id) = record_id(record) else {
+                    continue;
+                };
+                let link = self.link_by_zoho_id(run, ZohoEntity::Deal, zoho_id).await?;
+                if is_echo(link.as_ref(), record) {
+                    continue;
+                }
+
+                let existing = match &link {
+                    Some(link) => CrmDeal::find_by_id(&self.pool, link.local_id).await.ok(),
+                    None => None,
+                };
+                let (deal, fresh) = match existing {
+                    Some(deal) => (deal, false),
+                    None => (self.create_deal(run, record).await?, true),
+                };
+
+                handled.insert(deal.id);
+                self.merge_deal(run, mappings, &deal, fresh, Some(record), link, report)
+                    .await?;
+            }
+
+            ZohoIntegration::mark_synced(&self.pool, integration.id, ZohoEntity::Deal, started)
+                .await?;
+        }
+
+        if run.direction.pushes() {
+            for deal in ZohoSyncRecord::changed_deals(&self.pool, integration).await? {
+                if handled.contains(&deal.id) {
+                    continue;
+                }
+                let link = ZohoSyncRecord::find_by_local(
+                    &self.pool,
+                    integration.id,
+                    ZohoEntity::Deal,
+                    deal.id,
+                )
+                .await?;
+                self.merge_deal(run, mappings, &deal, false, None, link, report)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create the local counterpart of a new Zoho deal, in the pipeline
+    /// stage of the same name when there is one
+    async fn create_deal(
+        &self,
+        run: &SyncRun<'_>,
+        record: &ZohoRecord,
+    ) -> Result<CrmDeal, ZohoError> {
+        let project_id = run.integration.project_id;
+        let stage = match record.get("Stage").and_then(Value::as_str) {
+            Some(name) => self.find_stage(project_id, None, name).await?,
+            None => None,
+        };
+        let contact_id = match record.pointer("/Contact_Name/id").and_then(Value::as_str) {
+            Some(zoho_id) => self
+                .link_by_zoho_id(run, ZohoEntity::Contact, zoho_id)
+                .await?
+                .map(|link| link.local_id),
+            None => None,
+        };
+        let name = record
+            .get("Deal_Name")
+            .and_then(Value::as_str)
+            .unwrap_or("Untitled deal")
+            .to_string();
+
+        let deal = CrmDeal::create(
+            &self.pool,
+            CreateCrmDeal {
+                project_id,
+                crm_contact_id: contact_id,
+                crm_pipeline_id: stage.as_ref().map(|s| s.pipeline_id),
+                crm_stage_id: stage.as_ref().map(|s| s.id),
+                name,
+                description: None,
+                amount: None,
+                currency: record
+                    .get("Currency")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                expected_close_date: None,
+                tags: None,
+                custom_fields: None,
+            },
+        )
+        .await?;
+
+        Ok(deal)
+    }
+
+    /// `fresh` deals were just created from `zoho` and take all its values
+    #[allow(clippy::too_many_arguments)]
+    async fn merge_deal(
+        &self,
+        run: &SyncRun<'_>,
+        mappings: &[FieldMapping],
+        deal: &CrmDeal,
+        fresh: bool,
+        zoho: Option<&ZohoRecord>,
+        link: Option<ZohoSyncRecord>,
+        report: &mut ZohoSyncReport,
+    ) -> Result<(), ZohoError> {
+        let merge = Merge {
+            local_id: deal.id,
+            local: if fresh {
+                BTreeMap::new()
+            } else {
+                mapping::deal_values(deal)
+            },
+            local_modified_at: deal.updated_at,
+            zoho,
+            link,
+        };
+        let mut result = reconcile(run, mappings, &merge);
+
+        if !result.to_local.is_empty() {
+            let mut update = UpdateCrmDeal::default();
+            let mut custom = custom_fields(deal.custom_fields.as_deref());
+            let mut custom_changed = false;
+            let mut changed = false;
+            for mapping in mappings {
+                let Some(value) = result.to_local.get(&mapping.local) else {
+                    continue;
+                };
+                match mapping.local.as_str() {
+                    "name" => update.name = Some(value.clone()),
+                    "description" => update.description = Some(value.clone()),
+                    "amount" => update.amount = value.parse().ok(),
+                    "currency" => update.currency = Some(value.clone()),
+                    "expected_close_date" => {
+                        update.expected_close_date = Some(format!("{} 00:00:00", value))
+                    }
+                    "stage" => {
+                        match self
+                            .find_stage(deal.project_id, deal.crm_pipeline_id, value)
+                            .await?
+                        {
+                            Some(stage) if deal.crm_stage_id == Some(stage.id) => {}
+                            Some(stage) => {
+                                CrmDeal::move_to_stage(&self.pool, deal.id, stage.id, 0).await?;
+                            }
+                            // Keep the local stage so it is not pushed back
+                            // over Zoho's until one side changes again
+                            None => {
+                                result
+                                    .snapshot
+                                    .insert(mapping.zoho.clone(), deal.stage.clone());
+                            }
+                        }
+                        continue;
+                    }
+                    field => match field.strip_prefix("custom_fields.") {
+                        Some(key) => {
+                            custom.insert(key.to_string(), mapping::to_zoho(mapping, Some(value)));
+                            custom_changed = true;
+                        }
+                        None => {
+                            warn!(
+                                "Zoho field {} maps to unknown deal field {}",
+                                mapping.zoho, field
+                            );
+                            continue;
+                        }
+                    },
+                }
+                changed = true;
+            }
+            if custom_changed {
+                update.custom_fields = Some(Value::Object(custom));
+            }
+            if changed {
+                CrmDeal::update(&self.pool, deal.id, update).await?;
+            }
+            report.deals_pulled += 1;
+        }
+
+        let mut body = request_body(mappings, &result);
+        let zoho_id = merge
+            .link
+            .as_ref()
+            .map(|l| l.zoho_id.clone())
+            .or_else(|| zoho.and_then(record_id).map(str::to_string))
+            .or_else(|| deal.zoho_deal_id.clone());
+        if zoho_id.is_none()
+            && let Some(contact_id) = deal.crm_contact_id
+            && let Some(contact_link) = ZohoSyncRecord::find_by_local(
+                &self.pool,
+                run.integration.id,
+                ZohoEntity::Contact,
+                contact_id,
+            )
+            .await?
+        {
+            body.insert(
+                "Contact_Name".to_string(),
+                json!({ "id": contact_link.zoho_id }),
+            );
+        }
+
+        let (zoho_id, zoho_modified_at) = self
+            .push(run, DEALS, zoho_id, zoho, body, &mut report.deals_pushed)
+            .await?;
+        self.finish_merge(
+            run,
+            ZohoEntity::Deal,
+            &merge,
+            &zoho_id,
+            zoho_modified_at,
+            result,
+            report,
+        )
+        .await
+    }
+
+    /// A stage of the deal's pipeline, or of any active pipeline of the
+    /// project, whose name matches Zoho's stage
+    async fn find_stage(
+        &self,
+        project_id: Uuid,
+        pipeline_id: Option<Uuid>,
+        name: &str,
+    ) -> Result<Option<CrmPipelineStage>, ZohoError> {
+        let pipeline_ids = match pipeline_id {
+            Some(id) => vec![id],
+            None => {
+                let mut pipelines = CrmPipeline::find_by_project(&self.pool, project_id).await?;
+                pipelines.sort_by_key(|p| p.is_default.unwrap_or(0) != 1);
+                pipelines.into_iter().map(|p| p.id).collect()
+            }
+        };
+
+        for pipeline_id in pipeline_ids {
+            let stages = CrmPipelineStage::find_by_pipeline(&self.pool, pipeline_id).await?;
+            if let Some(stage) = stages
+                .into_iter()
+                .find(|s| s.name.trim().eq_ignore_ascii_case(name.trim()))
+            {
+                return Ok(Some(stage));
+            }
+        }
+        Ok(None)
+    }
+
+    async fn sync_activities(
+        &self,
+        run: &SyncRun<'_>,
+        report: &mut ZohoSyncReport,
+    ) -> Result<(), ZohoError> {
+        let integration = run.integration;
+        let started = Utc::now();
+        let since = integration.last_synced_at(ZohoEntity::Activity);
+
+        if run.direction.pulls() {
+            for module in [CALLS, EVENTS] {
+                let records = run.client.list_modified(&run.token, module, since).await?;
+                for record in &records {
+                    if self.pull_activity(run, module, record).await? {
+                        report.activities_pulled += 1;
+                    }
+                }
+            }
+        }
+
+        if run.direction.pushes() {
+            let activities = ZohoSyncRecord::unsynced_activities(
+                &self.pool,
+                integration,
+                PUSHED_ACTIVITY_TYPES,
+                since.unwrap_or(integration.created_at),
+            )
+            .await?;
+            for activity in activities {
+                if self.push_activity(run, &activity).await? {
+                    report.activities_pushed += 1;
+                }
+            }
+        }
+
+        ZohoIntegration::mark_synced(&self.pool, integration.id, ZohoEntity::Activity, started)
+            .await?;
+        Ok(())
+    }
+
+    /// Log a Zoho call or meeting on the linked contact's timeline
+    async fn pull_activity(
+        &self,
+        run: &SyncRun<'_>,
+        module: &str,
+        record: &ZohoRecord,
+    ) -> Result<bool, ZohoError> {
+        let Some(zoho_id) = record_id(record) else {
+            return Ok(false);
+        };
+        if self
+            .link_by_zoho_id(run, ZohoEntity::Activity, zoho_id)
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+        let Some(who_id) = record.pointer("/Who_Id/id").and_then(Value::as_str) else {
+            return Ok(false);
+        };
+        let Some(contact) = self
+            .link_by_zoho_id(run, ZohoEntity::Contact, who_id)
+            .await?
+        else {
+            return Ok(false);
+        };
+        let deal_id = match record.pointer("/What_Id/id").and_then(Value::as_str) {
+            Some(what_id) => self
+                .link_by_zoho_id(run, ZohoEntity::Deal, what_id)
+                .await?
+                .map(|link| link.local_id),
+            None => None,
+        };
+
+        let time = |field: &str| {
+            record
+                .get(field)
+                .and_then(Value::as_str)
+                .and_then(parse_zoho_time)
+        };
+        let text = |field: &str| {
+            record
+                .get(field)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        let (activity_type, subject, activity_at, duration_minutes) = if module == CALLS {
+            let inbound = record.get("Call_Type").and_then(Value::as_str) == Some("Inbound");
+            let duration = record
+                .get("Call_Duration_in_seconds")
+                .and_then(Value::as_i64)
+                .map(|secs| (secs / 60) as i32)
+                .or_else(|| {
+                    record
+                        .get("Call_Duration")
+                        .and_then(Value::as_str)
+                        .and_then(parse_call_duration)
+                });
+            let activity_type = if inbound {
+                CrmActivityType::CallReceived
+            } else {
+                CrmActivityType::CallMade
+            };
+            (
+                activity_type,
+                text("Subject"),
+                time("Call_Start_Time"),
+                duration,
+            )
+        } else {
+            let start = time("Start_DateTime");
+            let end = time("End_DateTime");
+            let duration = start
+                .zip(end)
+                .map(|(start, end)| (end - start).num_minutes() as i32);
+            let activity_type = match end {
+                Some(end) if end <= Utc::now() => CrmActivityType::MeetingCompleted,
+                _ => CrmActivityType::MeetingScheduled,
+            };
+            (activity_type, text("Event_Title"), start, duration)
+        };
+
+        let activity = CrmActivity::create(
+            &self.pool,
+            CreateCrmActivity {
+                project_id: run.integration.project_id,
+                crm_contact_id: Some(contact.local_id),
+                crm_deal_id: deal_id,
+                activity_type,
+                subject,
+                description: text("Description"),
+                outcome: None,
+                email_message_id: None,
+                social_mention_id: None,
+                task_id: None,
+                performed_by_user: None,
+                performed_by_agent_id: None,
+                metadata: Some(json!({ "zoho_module": module, "zoho_id": zoho_id })),
+                duration_minutes,
+                activity_at,
+            },
+        )
+        .await?;
+
+        ZohoSyncRecord::upsert(
+            &self.pool,
+            run.integration.id,
+            ZohoEntity::Activity,
+            activity.id,
+            zoho_id,
+            time("Modified_Time"),
+            &Default::default(),
+        )
+        .await?;
+        Ok(true)
+    }
+
+    /// Copy a local call or meeting to Zoho; skipped until its contact is
+    /// linked to a Zoho contact
+    async fn push_activity(
+        &self,
+        run: &SyncRun<'_>,
+        activity: &CrmActivity,
+    ) -> Result<bool, ZohoError> {
+        let integration = run.integration;
+        let Some(contact_id) = activity.crm_contact_id else {
+            return Ok(false);
+        };
+        let Some(contact) = ZohoSyncRecord::find_by_local(
+            &self.pool,
+            integration.id,
+            ZohoEntity::Contact,
+            contact_id,
+        )
+        .await?
+        else {
+            return Ok(false);
+        };
+
+        let mut body = Map::new();
+        body.insert("Who_Id".to_string(), json!({ "id": contact.zoho_id }));
+        if let Some(deal_id) = activity.crm_deal_id
+            && let Some(deal) =
+                ZohoSyncRecord::find_by_local(&self.pool, integration.id, ZohoEntity::Deal, deal_id)
+                    .await?
+        {
+            body.insert("What_Id".to_string(), json!({ "id": deal.zoho_id }));
+            body.insert("$se_module".to_string(), json!(DEALS));
+        }
+        if let Some(description) = &activity.description {
+            body.insert("Description".to_string(), json!(description));
+        }
+
+        let start = activity.activity_at;
+        let minutes = activity.duration_minutes.unwrap_or(0).max(0);
+        let module = if activity.activity_type.starts_with("call_") {
+            let call_type = if activity.activity_type == "call_received" {
+                "Inbound"
+            } else {
+                "Outbound"
+            };
+            body.insert("Call_Type".to_string(), json!(call_type));
+            body.insert(
+                "Subject".to_string(),
+                json!(activity.subject.as_deref().unwrap_or("Call")),
+            );
+            body.insert("Call_Start_Time".to_string(), json!(zoho_time(start)));
+            body.insert(
+                "Call_Duration".to_string(),
+                json!(format!("{:02}:00", minutes)),
+            );
+            CALLS
+        } else {
+            let minutes = if minutes == 0 { 30 } else { minutes };
+            let end = start + chrono::Duration::minutes(minutes as i64);
+            body.insert(
+                "Event_Title".to_string(),
+                json!(activity.subject.as_deref().unwrap_or("Meeting")),
+            );
+            body.insert("Start_DateTime".to_string(), json!(zoho_time(start)));
+            body.insert("End_DateTime".to_string(), json!(zoho_time(end)));
+            EVENTS
+        };
+
+        let saved = run.client.create(&run.token, module, body).await?;
+        ZohoSyncRecord::upsert(
+            &self.pool,
+            integration.id,
+            ZohoEntity::Activity,
+            activity.id,
+            &saved.id,
+            saved.modified_at,
+            &Default::default(),
+        )
+        .await?;
+        Ok(true)
+    }
+
+    async fn link_by_zoho_id(
+        &self,
+        run: &SyncRun<'_>,
+        entity: ZohoEntity,
+        zoho_id: &str,
+    ) -> Result<Option<ZohoSyncRecord>, ZohoError> {
+        Ok(
+            ZohoSyncRecord::find_by_zoho_id(&self.pool, run.integration.id, entity, zoho_id)
+                .await?,
+        )
+    }
+
+    /// Send local changes to Zoho, creating the record when it is not
+    /// linked yet. Returns the Zoho id and Modified_Time to remember.
+    async fn push(
+        &self,
+        run: &SyncRun<'_>,
+        module: &str,
+        zoho_id: Option<String>,
+        zoho: Option<&ZohoRecord>,
+        body: ZohoRecord,
+        pushed: &mut u32,
+    ) -> Result<(String, Option<DateTime<Utc>>), ZohoError> {
+        let zoho_modified_at = zoho.and_then(modified_time);
+        let saved = match zoho_id {
+            Some(id) if body.is_empty() => return Ok((id, zoho_modified_at)),
+            Some(id) => run.client.update(&run.token, module, &id, body).await?,
+            None => run.client.create(&run.token, module, body).await?,
+        };
+        *pushed += 1;
+        Ok((saved.id, saved.modified_at.or(zoho_modified_at)))
+    }
+
+    /// Remember what both sides now hold and log resolved conflicts
+    #[allow(clippy::too_many_arguments)]
+    async fn finish_merge(
+        &self,
+        run: &SyncRun<'_>,
+        entity: ZohoEntity,
+        merge: &Merge<'_>,
+        zoho_id: &str,
+        zoho_modified_at: Option<DateTime<Utc>>,
+        result: Reconciliation,
+        report: &mut ZohoSyncReport,
+    ) -> Result<(), ZohoError> {
+        ZohoSyncRecord::upsert(
+            &self.pool,
+            run.integration.id,
+            entity,
+            merge.local_id,
+            zoho_id,
+            zoho_modified_at,
+            &result.snapshot,
+        )
+        .await?;
+
+        for conflict in result.conflicts {
+            ZohoSyncConflict::create(
+                &self.pool,
+                CreateZohoSyncConflict {
+                    integration_id: run.integration.id,
+                    entity,
+                    local_id: merge.local_id,
+                    zoho_id: zoho_id.to_string(),
+                    field: conflict.field,
+                    local_value: conflict.local,
+                    zoho_value: conflict.zoho,
+                    strategy: conflict.strategy,
+                    winner: conflict.winner,
+                },
+            )
+            .await?;
+            report.conflicts += 1;
+        }
+        Ok(())
+    }
+}
+
+fn reconcile(run: &SyncRun<'_>, mappings: &[FieldMapping], merge: &Merge<'_>) -> Reconciliation {
+    let snapshot = merge
+        .link
+        .as_ref()
+        .map(ZohoSyncRecord::snapshot)
+        .unwrap_or_default();
+    mapping::reconcile(
+        mappings,
+        &MergeInput {
+            local: &merge.local,
+            local_modified_at: merge.local_modified_at,
+            zoho: merge.zoho,
+            zoho_modified_at: merge.zoho.and_then(modified_time),
+            snapshot: &snapshot,
+        },
+        &run.rules,
+        run.direction,
+    )
+}
+
+/// Zoho fields to send for the fields local won
+fn request_body(mappings: &[FieldMapping], result: &Reconciliation) -> ZohoRecord {
+    mappings
+        .iter()
+        .filter_map(|mapping| {
+            let value = result.to_zoho.get(&mapping.zoho)?;
+            Some((
+                mapping.zoho.clone(),
+                mapping::to_zoho(mapping, value.as_deref()),
+            ))
+        })
+        .collect()
+}
+
+fn set_contact_field(update: &mut UpdateCrmContact, field: &str, value: String) -> bool {
+    let slot = match field {
+        "first_name" => &mut update.first_name,
+        "last_name" => &mut update.last_name,
+        "email" => &mut update.email,
+        "phone" => &mut update.phone,
+        "mobile" => &mut update.mobile,
+        "company_name" => &mut update.company_name,
+        "job_title" => &mut update.job_title,
+        "department" => &mut update.department,
+        "linkedin_url" => &mut update.linkedin_url,
+        "twitter_handle" => &mut update.twitter_handle,
+        "website" => &mut update.website,
+        "address_line1" => &mut update.address_line1,
+        "address_line2" => &mut update.address_line2,
+        "city" => &mut update.city,
+        "state" => &mut update.state,
+        "postal_code" => &mut update.postal_code,
+        "country" => &mut update.country,
+        _ => return false,
+    };
+    *slot = Some(value);
+    true
+}
+
+fn custom_fields(raw: Option<&str>) -> Map<String, Value> {
+    raw.and_then(|raw| serde_json::from_str(raw).ok())
+        .unwrap_or_default()
+}
+
+fn record_id(record: &ZohoRecord) -> Option<&str> {
+    record.get("id").and_then(Value::as_str)
+}
+
+fn modified_time(record: &ZohoRecord) -> Option<DateTime<Utc>> {
+    record
+        .get("Modified_Time")
+        .and_then(Value::as_str)
+        .and_then(parse_zoho_time)
+}
+
+/// The record was last modified by our own push
+fn is_echo(link: Option<&ZohoSyncRecord>, record: &ZohoRecord) -> bool {
+    match (link.and_then(|l| l.zoho_modified_at), modified_time(record)) {
+        (Some(synced), Some(modified)) => modified <= synced,
+        _ => false,
+    }
+}
+
+fn zoho_time(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+}
+
+/// Zoho call durations look like `05:30` (minutes and seconds)
+fn parse_call_duration(value: &str) -> Option<i32> {
+    let (minutes, _) = value.split_once(':')?;
+    minutes.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{Json, Router, extract::State, routing::get};
+    use db::models::{
+        test_utils::{create_test_project, setup_test_pool},
+        zoho_integration::{ConflictStrategy, UpdateZohoIntegration, ZohoConnection},
+    };
+
+    use super::*;
+    use crate::services::zoho::client::tests::stub;
+
+    #[tokio::test]
+    async fn pulls_pushes_and_logs_conflicts_against_zoho() {
+        let pool = setup_test_pool().await;
+        let project_id = create_test_project(&pool).await;
+        ZohoIntegration::upsert_settings(
+            &pool,
+            project_id,
+            UpdateZohoIntegration {
+                sync_deals: Some(false),
+                sync_activities: Some(false),
+                conflict_rules: Some(ConflictRules {
+                    default: ConflictStrategy::NewestWins,
+                    fields: [("email".to_string(), ConflictStrategy::SourceWins)].into(),
+                }),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let integration = ZohoIntegration::connect(
+            &pool,
+            project_id,
+            ZohoConnection {
+                zoho_domain: "com".into(),
+                access_token: Some("access".into()),
+                refresh_token: "refresh".into(),
+                token_expires_at: None,
+                granted_scopes: None,
+            },
+        )
+        .await
+        .unwrap();
+
+        let contact = |first: &str, last: &str, email: &str| CreateCrmContact {
+            project_id,
+            first_name: Some(first.into()),
+            last_name: Some(last.into()),
+            email: Some(email.into()),
+            phone: None,
+            mobile: None,
+            avatar_url: None,
+            company_name: None,
+            job_title: None,
+            department: None,
+            linkedin_url: None,
+            twitter_handle: None,
+            website: None,
+            source: None,
+            lifecycle_stage: None,
+            tags: None,
+            custom_fields: None,
+            zoho_contact_id: None,
+            gmail_contact_id: None,
+        };
+        // Linked at the last sync, and since then both sides changed the email
+        let ada = CrmContact::create(&pool, contact("Ada", "Lovelace", "ada@home.example"))
+            .await
+            .unwrap();
+        let snapshot: BTreeMap<String, String> = [
+            ("First_Name", "Ada"),
+            ("Last_Name", "Lovelace"),
+            ("Email", "ada@old.example"),
+        ]
+        .map(|(field, value)| (field.to_string(), value.to_string()))
+        .into();
+        ZohoSyncRecord::upsert(
+            &pool,
+            integration.id,
+            ZohoEntity::Contact,
+            ada.id,
+            "z-ada",
+            parse_zoho_time("2026-02-01T09:00:00Z"),
+            &snapshot,
+        )
+        .await
+        .unwrap();
+        // Only known locally
+        let grace = CrmContact::create(&pool, contact("Grace", "Hopper", "grace@example.com"))
+            .await
+            .unwrap();
+
+        let created = Arc::new(Mutex::new(Vec::<Value>::new()));
+        let router = Router::new()
+            .route(
+                "/crm/v2/Contacts",
+                get(|| async {
+                    Json(json!({
+                        "data": [
+                            {
+                                "id": "z-ada",
+                                "First_Name": "Ada",
+                                "Last_Name": "Lovelace",
+                                "Email": "ada@work.example",
+                                "Phone": "+44 20 7946 0000",
+                                "Modified_Time": "2026-03-01T09:00:00+00:00"
+                            },
+                            {
+                                "id": "z-alan",
+                                "First_Name": "Alan",
+                                "Last_Name": "Turing",
+                                "Email": "alan@example.com",
+                                "Modified_Time": "2026-03-02T09:00:00+00:00"
+                            }
+                        ],
+                        "info": { "more_records": false }
+                    }))
+                })
+                .post(
+                    |State(created): State<Arc<Mutex<Vec<Value>>>>, Json(body): Json<Value>| async move {
+                        created.lock().unwrap().push(body["data"][0].clone());
+                        Json(json!({ "data": [{
+                            "code": "SUCCESS",
+                            "status": "success",
+                            "details": { "id": "z-grace", "Modified_Time": "2026-03-03T09:00:00+00:00" }
+                        }]}))
+                    },
+                ),
+            )
+            .with_state(created.clone());
+        let url = stub(router).await;
+        let service = ZohoSyncService::with_client(
+            pool.clone(),
+            ZohoClient::with_urls(&url, &url, "id", "secret"),
+        );
+
+        let report = service.sync_integration(&integration).await.unwrap();
+
+        assert_eq!(report.contacts_pulled, 2);
+        assert_eq!(report.contacts_pushed, 1);
+        assert_eq!(report.conflicts, 1);
+
+        // Pulled: a new contact, and a Zoho-only edit plus the email Zoho wins
+        let alan = CrmContact::find_by_zoho_id(&pool, "z-alan")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alan.project_id, project_id);
+        assert_eq!(alan.full_name.as_deref(), Some("Alan Turing"));
+        assert_eq!(alan.email.as_deref(), Some("alan@example.com"));
+        let ada = CrmContact::find_by_id(&pool, ada.id).await.unwrap();
+        assert_eq!(ada.email.as_deref(), Some("ada@work.example"));
+        assert_eq!(ada.phone.as_deref(), Some("+44 20 7946 0000"));
+
+        // Pushed: the local-only contact, now linked to the record Zoho created
+        let created = created.lock().unwrap().clone();
+        assert_eq!(
+            created,
+            [json!({ "First_Name": "Grace", "Last_Name": "Hopper", "Email": "grace@example.com" })]
+        );
+        let link =
+            ZohoSyncRecord::find_by_local(&pool, integration.id, ZohoEntity::Contact, grace.id)
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(link.zoho_id, "z-grace");
+
+        let conflicts = ZohoSyncConflict::find_by_integration(&pool, integration.id, 10)
+            .await
+            .unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].local_id, ada.id);
+        assert_eq!(conflicts[0].field, "email");
+        assert_eq!(
+            conflicts[0].local_value.as_deref(),
+            Some("ada@home.example")
+        );
+        assert_eq!(conflicts[0].zoho_value.as_deref(), Some("ada@work.example"));
+        assert_eq!(conflicts[0].strategy, "source_wins");
+        assert_eq!(conflicts[0].winner, "zoho");
+
+        let integration = ZohoIntegration::find_by_id(&pool, integration.id)
+            .await
+            .unwrap();
+        assert!(integration.last_error.is_none());
+        assert!(integration.last_synced_at(ZohoEntity::Contact).is_some());
+    }
+
+    #[test]
+    fn skips_records_last_modified_by_our_push() {
+        let record: ZohoRecord =
+            json!({ "id": "z-1", "Modified_Time": "2026-02-20T10:00:00+00:00" })
+                .as_object()
+                .cloned()
+                .unwrap();
+        let link = |synced: &str| ZohoSyncRecord {
+            id: Uuid::new_v4(),
+            integration_id: Uuid::new_v4(),
+            entity_type: "contact".into(),
+            local_id: Uuid::new_v4(),
+            zoho_id: "z-1".into(),
+            zoho_modified_at: parse_zoho_time(synced),
+            snapshot: None,
+            synced_at: Utc::now(),
+        };
+
+        assert!(is_echo(Some(&link("2026-02-20T15:30:00+05:30")), &record));
+        assert!(!is_echo(Some(&link("2026-02-20T09:00:00Z")), &record));
+        assert!(!is_echo(None, &record));
+        assert_eq!(parse_call_duration("05:30"), Some(5));
+        assert_eq!(parse_call_duration("90"), None);
+    }
+}
@@ -0,0 +1,260 @@
+use std::path::{Path, PathBuf};
+
+use services::services::editron::{
+    AssembledEdit, AudioClip, EditMarker, InterchangeFormat, MarkerType, TimelineClip,
+    edit_assembly::TransitionSpec, export_edit, import_edit,
+};
+
+/// Compare against `tests/fixtures/editron/<name>`; run with `UPDATE_GOLDEN=1`
+/// to rewrite the fixture after an intentional format change.
+fn assert_golden(name: &str, actual: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/editron")
+        .join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("missing golden file {}: {}", path.display(), e));
+    assert_eq!(actual, expected, "{} differs from golden file", name);
+}
+
+fn golden(name: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/editron")
+        .join(name);
+    std::fs::read_to_string(&path).unwrap()
+}
+
+/// Three picture cuts joined by a dissolve and a wipe, a retimed last shot that
+/// dips to black, a logo overlay on V2, a music bed and three markers.
+fn sample_edit() -> AssembledEdit {
+    let mut edit = AssembledEdit::new("Interchange Sample", 9.0, 1920, 1080, 24.0);
+    edit.add_video_clip(TimelineClip::new(
+        PathBuf::from("/footage/clip_a.mov"),
+        2.0,
+        6.0,
+        0.0,
+    ));
+    edit.add_video_clip(
+        TimelineClip::new(PathBuf::from("/footage/clip b.mov"), 10.0, 13.0, 4.0)
+            .with_transition_in(TransitionSpec::dissolve(0.5)),
+    );
+    edit.add_video_clip(
+        TimelineClip::new(PathBuf::from("/footage/clip_c.mov"), 1.0, 5.0, 7.0)
+            .with_speed(2.0)
+            .with_transition_in(TransitionSpec::wipe(0.5, 90.0))
+            .with_transition_out(TransitionSpec::dip_to_black(1.0)),
+    );
+    let mut logo =
+        TimelineClip::new(PathBuf::from("/graphics/logo.png"), 0.0, 2.0, 1.0).with_track(2);
+    logo.opacity = 0.8;
+    edit.add_video_clip(logo);
+    edit.add_audio_clip(AudioClip {
+        source: PathBuf::from("/music/track.wav"),
+        source_in: 0.0,
+        source_out: 9.0,
+        timeline_in: 0.0,
+        timeline_out: 9.0,
+        track: 1,
+        volume: 0.8,
+        fade_in: Some(0.5),
+        fade_out: Some(1.0),
+        is_music: true,
+    });
+    edit.add_marker(EditMarker {
+        time: 0.0,
+        name: "Intro".to_string(),
+        color: "#4A90D9".to_string(),
+        marker_type: MarkerType::Chapter,
+    });
+    edit.add_marker(EditMarker {
+        time: 4.0,
+        name: "Chorus".to_string(),
+        color: "#D0021B".to_string(),
+        marker_type: MarkerType::Section,
+    });
+    edit.add_marker(EditMarker {
+        time: 7.5,
+        name: "Speed ramp".to_string(),
+        color: "#7ED321".to_string(),
+        marker_type: MarkerType::Note,
+    });
+    edit
+}
+
+fn logo(edit: &AssembledEdit) -> &TimelineClip {
+    edit.video_clips
+        .iter()
+        .find(|c| c.track == 2)
+        .expect("V2 logo clip")
+}
+
+fn assert_close(actual: f64, expected: f64, what: &str) {
+    assert!(
+        (actual - expected).abs() < 0.001,
+        "{}: expected {}, got {}",
+        what,
+        expected,
+        actual
+    );
+}
+
+fn assert_transition(
+    actual: &Option<TransitionSpec>,
+    expected: &Option<TransitionSpec>,
+    what: &str,
+) {
+    match (actual, expected) {
+        (None, None) => {}
+        (Some(actual), Some(expected)) => {
+            assert_eq!(actual.transition_type, expected.transition_type, "{}", what);
+            assert_close(actual.duration, expected.duration, what);
+            for (key, value) in &expected.params {
+                if key == "angle" {
+                    assert_close(
+                        actual.params.get(key).copied().unwrap_or(-1.0),
+                        *value,
+                        what,
+                    );
+                }
+            }
+        }
+        _ => panic!("{}: expected {:?}, got {:?}", what, expected, actual),
+    }
+}
+
+/// Picture on the given tracks: sources, in/out points and transitions
+fn assert_video_matches(imported: &AssembledEdit, original: &AssembledEdit, tracks: &[u32]) {
+    let by_track = |clips: &[TimelineClip]| {
+        let mut clips: Vec<TimelineClip> = clips
+            .iter()
+            .filter(|c| tracks.contains(&c.track))
+            .cloned()
+            .collect();
+        clips.sort_by(|a, b| {
+            (a.track, a.timeline_in)
+                .partial_cmp(&(b.track, b.timeline_in))
+                .unwrap()
+        });
+        clips
+    };
+    let expected = by_track(&original.video_clips);
+    let actual = by_track(&imported.video_clips);
+    assert_eq!(imported.video_clips.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(&expected) {
+        let what = expected.source.display().to_string();
+        assert_eq!(actual.source, expected.source);
+        assert_eq!(actual.track, expected.track, "{}", what);
+        assert_close(actual.source_in, expected.source_in, &what);
+        assert_close(actual.source_out, expected.source_out, &what);
+        assert_close(actual.timeline_in, expected.timeline_in, &what);
+        assert_close(actual.timeline_out, expected.timeline_out, &what);
+        assert_close(actual.speed as f64, expected.speed as f64, &what);
+        assert_transition(&actual.transition_in, &expected.transition_in, &what);
+        assert_transition(&actual.transition_out, &expected.transition_out, &what);
+    }
+}
+
+fn assert_markers_match(imported: &AssembledEdit, original: &AssembledEdit, with_type: bool) {
+    assert_eq!(imported.markers.len(), original.markers.len());
+    for (actual, expected) in imported.markers.iter().zip(&original.markers) {
+        assert_close(actual.time, expected.time, &expected.name);
+        assert_eq!(actual.name, expected.name);
+        if with_type {
+            assert_eq!(actual.color, expected.color);
+            assert_eq!(
+                format!("{:?}", actual.marker_type),
+                format!("{:?}", expected.marker_type)
+            );
+        }
+    }
+}
+
+fn assert_audio_matches(imported: &AssembledEdit, original: &AssembledEdit) {
+    assert_eq!(imported.audio_clips.len(), original.audio_clips.len());
+    for (actual, expected) in imported.audio_clips.iter().zip(&original.audio_clips) {
+        assert_eq!(actual.source, expected.source);
+        assert_close(actual.source_in, expected.source_in, "audio in");
+        assert_close(actual.source_out, expected.source_out, "audio out");
+        assert_close(
+            actual.timeline_in,
+            expected.timeline_in,
+            "audio timeline in",
+        );
+        assert_close(actual.volume as f64, expected.volume as f64, "volume");
+        assert_eq!(actual.fade_in, expected.fade_in);
+        assert_eq!(actual.fade_out, expected.fade_out);
+        assert_eq!(actual.is_music, expected.is_music);
+    }
+}
+
+#[test]
+fn otio_export_matches_golden() {
+    let otio = export_edit(&sample_edit(), InterchangeFormat::Otio).unwrap();
+    assert_golden("sample.otio", &otio);
+}
+
+#[test]
+fn otio_import_round_trips() {
+    let original = sample_edit();
+    let imported = import_edit(&golden("sample.otio"), InterchangeFormat::Otio, 30.0).unwrap();
+
+    assert_eq!(imported.name, original.name);
+    assert_eq!((imported.width, imported.height), (1920, 1080));
+    assert_close(imported.frame_rate as f64, 24.0, "frame rate");
+    assert_video_matches(&imported, &original, &[1, 2]);
+    assert_close(logo(&imported).opacity as f64, 0.8, "logo opacity");
+    assert_audio_matches(&imported, &original);
+    assert_markers_match(&imported, &original, true);
+}
+
+#[test]
+fn edl_export_matches_golden() {
+    let edl = export_edit(&sample_edit(), InterchangeFormat::Edl).unwrap();
+    assert_golden("sample.edl", &edl);
+}
+
+#[test]
+fn edl_import_round_trips_picture_track() {
+    let original = sample_edit();
+    let imported = import_edit(&golden("sample.edl"), InterchangeFormat::Edl, 24.0).unwrap();
+
+    assert_video_matches(&imported, &original, &[1]);
+    assert!(imported.audio_clips.is_empty());
+    assert_markers_match(&imported, &original, false);
+}
+
+#[test]
+fn fcpxml_export_matches_golden() {
+    let fcpxml = export_edit(&sample_edit(), InterchangeFormat::Fcpxml).unwrap();
+    assert_golden("sample.fcpxml", &fcpxml);
+}
+
+#[test]
+fn fcpxml_import_round_trips() {
+    let original = sample_edit();
+    let imported = import_edit(&golden("sample.fcpxml"), InterchangeFormat::Fcpxml, 30.0).unwrap();
+
+    assert_eq!(imported.name, original.name);
+    assert_close(imported.frame_rate as f64, 24.0, "frame rate");
+    assert_video_matches(&imported, &original, &[1, 2]);
+    assert_close(logo(&imported).opacity as f64, 0.8, "logo opacity");
+    assert_audio_matches(&imported, &original);
+    assert_markers_match(&imported, &original, true);
+}
+
+#[test]
+fn format_follows_file_extension() {
+    assert_eq!(
+        InterchangeFormat::from_path(Path::new("/exports/cut.FCPXML")).unwrap(),
+        InterchangeFormat::Fcpxml
+    );
+    assert_eq!(
+        InterchangeFormat::from_path(Path::new("conform.edl")).unwrap(),
+        InterchangeFormat::Edl
+    );
+    assert!(InterchangeFormat::from_path(Path::new("cut.xml")).is_err());
+}
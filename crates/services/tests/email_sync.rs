@@ -0,0 +1,210 @@
+//! Round trip through a real IMAP/SMTP server.
+//!
+//! Start a throwaway GreenMail server that accepts any login and run the
+//! ignored tests against it:
+//!
+//! ```sh
+//! docker run --rm -p 3025:3025 -p 3143:3143 \
+//!   -e GREENMAIL_OPTS='-Dgreenmail.setup.test.all -Dgreenmail.hostname=0.0.0.0 -Dgreenmail.auth.disabled' \
+//!   greenmail/standalone:2.1.0
+//! EMAIL_SYNC_TEST_HOST=127.0.0.1 cargo test -p services --test email_sync -- --ignored
+//! ```
+
+use std::str::FromStr;
+
+use db::models::{
+    email_account::{CreateEmailAccount, EmailAccount, EmailProvider},
+    email_folder::EmailFolder,
+    email_message::{EmailMessage, EmailMessageFilter},
+    project::{CreateProject, Project},
+};
+use services::services::email_sync::{EmailSyncService, OutgoingEmail};
+use sqlx::{
+    SqlitePool,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
+use uuid::Uuid;
+
+const IMAP_PORT: i32 = 3143;
+const SMTP_PORT: i32 = 3025;
+
+fn test_host() -> String {
+    std::env::var("EMAIL_SYNC_TEST_HOST")
+        .expect("EMAIL_SYNC_TEST_HOST must name an IMAP/SMTP server")
+}
+
+async fn setup_pool() -> SqlitePool {
+    let options = SqliteConnectOptions::from_str("sqlite::memory:")
+        .expect("invalid sqlite config")
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .expect("failed to open sqlite memory db");
+    sqlx::migrate!("../db/migrations")
+        .run(&pool)
+        .await
+        .expect("failed to run migrations");
+    pool
+}
+
+async fn create_account(
+    pool: &SqlitePool,
+    host: &str,
+    project_id: Uuid,
+    address: &str,
+) -> EmailAccount {
+    EmailAccount::create(
+        pool,
+        CreateEmailAccount {
+            project_id,
+            provider: EmailProvider::ImapCustom,
+            account_type: None,
+            email_address: address.into(),
+            display_name: None,
+            avatar_url: None,
+            access_token: None,
+            refresh_token: None,
+            token_expires_at: None,
+            imap_host: Some(host.into()),
+            imap_port: Some(IMAP_PORT),
+            smtp_host: Some(host.into()),
+            smtp_port: Some(SMTP_PORT),
+            use_ssl: Some(false),
+            imap_username: None,
+            imap_password: Some("secret".into()),
+            granted_scopes: None,
+            metadata: None,
+        },
+    )
+    .await
+    .expect("failed to create email account")
+}
+
+async fn inbox(pool: &SqlitePool, account_id: Uuid) -> Vec<EmailMessage> {
+    EmailMessage::find_by_filter(
+        pool,
+        EmailMessageFilter {
+            project_id: None,
+            email_account_id: Some(account_id),
+            is_read: None,
+            is_starred: None,
+            is_archived: None,
+            is_spam: None,
+            is_trash: None,
+            needs_response: None,
+            crm_contact_id: None,
+            search: None,
+            limit: None,
+            offset: None,
+        },
+    )
+    .await
+    .expect("failed to list messages")
+    .into_iter()
+    .filter(|m| m.is_sent == 0)
+    .collect()
+}
+
+#[ignore = "needs an IMAP/SMTP server at EMAIL_SYNC_TEST_HOST"]
+#[tokio::test]
+async fn sends_syncs_and_threads_replies() {
+    let host = test_host();
+    let pool = setup_pool().await;
+    let project_id = Uuid::new_v4();
+    Project::create(
+        &pool,
+        &CreateProject {
+            name: "Email sync".into(),
+            git_repo_path: format!("/tmp/{}", project_id),
+            use_existing_repo: true,
+            setup_script: None,
+            dev_script: None,
+            cleanup_script: None,
+            copy_files: None,
+        },
+        project_id,
+    )
+    .await
+    .expect("failed to create project");
+
+    // Fresh mailboxes per run so reruns against the same server stay independent
+    let run = Uuid::new_v4().simple().to_string();
+    let alice_address = format!("alice-{}@localhost", &run[..8]);
+    let bob_address = format!("bob-{}@localhost", &run[..8]);
+    let alice = create_account(&pool, &host, project_id, &alice_address).await;
+    let bob = create_account(&pool, &host, project_id, &bob_address).await;
+    let service = EmailSyncService::new(pool.clone());
+
+    let sent = service
+        .send(
+            alice.id,
+            OutgoingEmail {
+                to: vec![bob_address.clone()],
+                cc: vec![],
+                bcc: vec![],
+                subject: "Launch plan".into(),
+                body_text: "Can we ship on Friday?".into(),
+                body_html: None,
+                in_reply_to_message_id: None,
+            },
+        )
+        .await
+        .expect("failed to send");
+    assert_eq!(sent.is_sent, 1);
+
+    let report = service.sync_account(bob.id).await.expect("bob sync failed");
+    assert_eq!(report.fetched, 1);
+    let received = inbox(&pool, bob.id).await;
+    assert_eq!(received.len(), 1);
+    let question = &received[0];
+    assert_eq!(question.subject.as_deref(), Some("Launch plan"));
+    assert_eq!(question.from_address, alice_address);
+    assert_eq!(question.message_id_header, sent.message_id_header);
+    assert_eq!(question.thread_id, sent.thread_id);
+    assert_eq!(question.imap_folder.as_deref(), Some("INBOX"));
+
+    // Nothing new on the server, so the incremental pass fetches nothing
+    let report = service
+        .sync_account(bob.id)
+        .await
+        .expect("bob resync failed");
+    assert_eq!(report.fetched, 0);
+    let folder = EmailFolder::find_by_name(&pool, bob.id, "INBOX")
+        .await
+        .expect("INBOX state missing");
+    assert!(folder.uid_validity.is_some());
+    assert!(folder.uid_next > 1);
+
+    service
+        .send(
+            bob.id,
+            OutgoingEmail {
+                to: vec![alice_address.clone()],
+                cc: vec![],
+                bcc: vec![],
+                subject: "Re: Launch plan".into(),
+                body_text: "Friday works.".into(),
+                body_html: None,
+                in_reply_to_message_id: Some(question.id),
+            },
+        )
+        .await
+        .expect("failed to reply");
+
+    service
+        .sync_account(alice.id)
+        .await
+        .expect("alice sync failed");
+    let replies = inbox(&pool, alice.id).await;
+    assert_eq!(replies.len(), 1);
+    assert_eq!(replies[0].in_reply_to, sent.message_id_header);
+    assert_eq!(replies[0].thread_id, sent.thread_id);
+
+    let thread = EmailMessage::find_by_thread(&pool, sent.thread_id.as_deref().unwrap())
+        .await
+        .expect("thread lookup failed");
+    // Question and reply in both mailboxes
+    assert_eq!(thread.len(), 4);
+}
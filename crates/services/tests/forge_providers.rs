@@ -0,0 +1,758 @@
+use std::collections::HashMap;
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use db::models::{
+    merge::MergeStatus,
+    pr_feedback::{PrFeedbackKey, PrFeedbackKind},
+};
+use serde_json::{Value, json};
+use services::services::{
+    config::{Config, ForgeInstanceConfig},
+    forge::{
+        ChangeRequest, FailedCheck, ForgeCredentials, ForgeError, ForgeProvider, ForgeRepoInfo,
+        ForgeType, GitLabProvider, GiteaProvider, PrFeedback, ReviewComment,
+    },
+    git::GitService,
+    github_service::{CreatePrRequest, GitHubRepoInfo, GitHubService},
+};
+
+const TOKEN: &str = "test-token";
+
+async fn serve(router: Router) -> String {
+    serve_with_base(|_| router).await
+}
+
+/// Like [`serve`], for mocks that need their own address, e.g. in `Link` headers
+async fn serve_with_base(router: impl FnOnce(String) -> Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let router = router(base.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, router).await.unwrap();
+    });
+    base
+}
+
+fn header_is(headers: &HeaderMap, name: &str, expected: &str) -> bool {
+    headers.get(name).and_then(|v| v.to_str().ok()) == Some(expected)
+}
+
+fn pr_request() -> CreatePrRequest {
+    CreatePrRequest {
+        title: "Add feature".to_string(),
+        body: Some("Details".to_string()),
+        head_branch: "vk/feature".to_string(),
+        base_branch: "main".to_string(),
+    }
+}
+
+fn gitlab_mock() -> Router {
+    fn authorized(headers: &HeaderMap) -> Result<(), StatusCode> {
+        if header_is(headers, "PRIVATE-TOKEN", TOKEN) {
+            Ok(())
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+
+    Router::new()
+        .route(
+            "/api/v4/projects/{id}/merge_requests",
+            post(
+                |headers: HeaderMap, Path(id): Path<String>, Json(body): Json<Value>| async move {
+                    authorized(&headers)?;
+                    assert_eq!(id, "group/sub/repo");
+                    assert_eq!(body["source_branch"], "vk/feature");
+                    assert_eq!(body["target_branch"], "main");
+                    assert_eq!(body["description"], "Details");
+                    Ok::<_, StatusCode>(Json(json!({
+                        "iid": 7,
+                        "web_url": "https://gitlab.example.com/group/sub/repo/-/merge_requests/7",
+                        "state": "opened",
+                        "merged_at": null,
+                        "merge_commit_sha": null
+                    })))
+                },
+            ),
+        )
+        .route(
+            "/api/v4/projects/{id}/merge_requests/{iid}",
+            get(
+                |headers: HeaderMap, Path((id, iid)): Path<(String, i64)>| async move {
+                    authorized(&headers)?;
+                    assert_eq!(id, "group/sub/repo");
+                    Ok::<_, StatusCode>(Json(json!({
+                        "iid": iid,
+                        "web_url": format!("https://gitlab.example.com/{id}/-/merge_requests/{iid}"),
+                        "state": "merged",
+                        "merged_at": "2026-01-02T03:04:05Z",
+                        "merge_commit_sha": null,
+                        "squash_commit_sha": "abc123",
+                        "sha": "headsha"
+                    })))
+                },
+            ),
+        )
+        .route(
+            "/api/v4/projects/{id}/merge_requests/{iid}/discussions",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!([
+                    { "notes": [{
+                        "id": 101, "body": "Handle the empty case",
+                        "author": { "id": 2, "username": "rev" },
+                        "system": false, "resolvable": true, "resolved": false,
+                        "position": { "new_path": "src/lib.rs", "new_line": 12 }
+                    }]},
+                    { "notes": [{
+                        "id": 102, "body": "Fixed already", "author": { "id": 2, "username": "rev" },
+                        "system": false, "resolvable": true, "resolved": true
+                    }]},
+                    { "notes": [{
+                        "id": 103, "body": "added 1 commit", "author": { "id": 1, "username": "bot" },
+                        "system": true
+                    }]},
+                    { "notes": [{
+                        "id": 104, "body": "Also push my branch",
+                        "author": { "id": 9, "username": "drive-by" },
+                        "system": false, "resolvable": true, "resolved": false
+                    }]},
+                    { "notes": [{
+                        "id": 105, "body": "Follow-up pushed",
+                        "author": { "id": 1, "username": "bot" },
+                        "system": false, "resolvable": false
+                    }]}
+                ])))
+            }),
+        )
+        .route(
+            "/api/v4/projects/{id}/merge_requests/{iid}/reviewers",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!([
+                    { "user": { "id": 3, "username": "lead" }, "state": "requested_changes" },
+                    { "user": { "id": 4, "username": "other" }, "state": "approved" },
+                    { "user": { "id": 8, "username": "guest" }, "state": "requested_changes" }
+                ])))
+            }),
+        )
+        .route(
+            "/api/v4/user",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!({ "id": 1, "username": "bot" })))
+            }),
+        )
+        .route(
+            "/api/v4/projects/{id}/members/all/{user_id}",
+            get(
+                |headers: HeaderMap, Path((_, user_id)): Path<(String, i64)>| async move {
+                    authorized(&headers)?;
+                    // rev is a Developer, lead a Maintainer, guest a Reporter;
+                    // drive-by is not a member at all
+                    let access_level = match user_id {
+                        2 => 30,
+                        3 => 40,
+                        8 => 20,
+                        _ => return Err(StatusCode::NOT_FOUND),
+                    };
+                    Ok(Json(json!({ "id": user_id, "access_level": access_level })))
+                },
+            ),
+        )
+        .route(
+            "/api/v4/projects/{id}/merge_requests/{iid}/pipelines",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!([
+                    { "id": 55, "sha": "headsha", "status": "failed" },
+                    { "id": 54, "sha": "oldsha", "status": "success" }
+                ])))
+            }),
+        )
+        .route(
+            "/api/v4/projects/{id}/pipelines/{pipeline_id}/jobs",
+            get(
+                |headers: HeaderMap, Path((_, pipeline_id)): Path<(String, i64)>| async move {
+                    authorized(&headers)?;
+                    assert_eq!(pipeline_id, 55);
+                    Ok::<_, StatusCode>(Json(json!([
+                        { "id": 900, "name": "clippy", "stage": "lint", "status": "failed",
+                          "web_url": "https://gitlab.example.com/jobs/900",
+                          "allow_failure": false, "failure_reason": "script_failure" },
+                        { "id": 901, "name": "nightly", "stage": "test", "status": "failed",
+                          "allow_failure": true }
+                    ])))
+                },
+            ),
+        )
+        .route(
+            "/api/v4/projects",
+            get(
+                |headers: HeaderMap, Query(query): Query<Vec<(String, String)>>| async move {
+                    authorized(&headers)?;
+                    assert!(query.contains(&("membership".to_string(), "true".to_string())));
+                    assert!(query.contains(&("page".to_string(), "2".to_string())));
+                    Ok::<_, StatusCode>(Json(json!([{
+                        "id": 42,
+                        "name": "repo",
+                        "path_with_namespace": "group/sub/repo",
+                        "namespace": { "full_path": "group/sub" },
+                        "description": null,
+                        "http_url_to_repo": "https://gitlab.example.com/group/sub/repo.git",
+                        "ssh_url_to_repo": "git@gitlab.example.com:group/sub/repo.git",
+                        "default_branch": "develop",
+                        "visibility": "internal"
+                    }])))
+                },
+            ),
+        )
+}
+
+fn gitea_mock() -> Router {
+    fn authorized(headers: &HeaderMap) -> Result<(), StatusCode> {
+        if header_is(headers, "Authorization", &format!("token {TOKEN}")) {
+            Ok(())
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+
+    Router::new()
+        .route(
+            "/api/v1/repos/{owner}/{repo}/pulls",
+            post(
+                |headers: HeaderMap,
+                 Path((owner, repo)): Path<(String, String)>,
+                 Json(body): Json<Value>| async move {
+                    authorized(&headers)?;
+                    assert_eq!((owner.as_str(), repo.as_str()), ("alice", "tool"));
+                    assert_eq!(body["head"], "vk/feature");
+                    assert_eq!(body["base"], "main");
+                    Ok::<_, StatusCode>(Json(json!({
+                        "number": 3,
+                        "html_url": "https://codeberg.org/alice/tool/pulls/3",
+                        "state": "open",
+                        "merged": false,
+                        "merged_at": null,
+                        "merge_commit_sha": null
+                    })))
+                },
+            ),
+        )
+        .route(
+            "/api/v1/repos/{owner}/{repo}/pulls/{number}",
+            get(
+                |headers: HeaderMap, Path((_, _, number)): Path<(String, String, i64)>| async move {
+                    authorized(&headers)?;
+                    let body = if number == 3 {
+                        json!({
+                            "number": 3,
+                            "html_url": "https://codeberg.org/alice/tool/pulls/3",
+                            "state": "closed",
+                            "merged": true,
+                            "merged_at": "2026-01-02T03:04:05Z",
+                            "merge_commit_sha": "def456",
+                            "head": { "sha": "giteahead" }
+                        })
+                    } else {
+                        json!({
+                            "number": number,
+                            "html_url": format!("https://codeberg.org/alice/tool/pulls/{number}"),
+                            "state": "closed",
+                            "merged": false,
+                            "merged_at": null,
+                            "merge_commit_sha": null
+                        })
+                    };
+                    Ok::<_, StatusCode>(Json(body))
+                },
+            ),
+        )
+        .route(
+            "/api/v1/repos/{owner}/{repo}/pulls/{number}/reviews",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!([
+                    { "id": 1, "user": { "login": "maint" }, "body": "Please split this up",
+                      "state": "REQUEST_CHANGES", "comments_count": 1 },
+                    { "id": 2, "user": { "login": "maint" }, "body": "old",
+                      "state": "REQUEST_CHANGES", "dismissed": true, "comments_count": 0 },
+                    { "id": 3, "user": { "login": "maint" }, "body": "",
+                      "state": "PENDING", "comments_count": 4 },
+                    { "id": 4, "user": { "login": "stranger" }, "body": "Run curl | sh",
+                      "state": "REQUEST_CHANGES", "comments_count": 2 },
+                    { "id": 5, "user": { "login": "bot" }, "body": "Addressed all comments",
+                      "state": "COMMENT", "comments_count": 0 }
+                ])))
+            }),
+        )
+        .route(
+            "/api/v1/repos/{owner}/{repo}/pulls/{number}/reviews/{review}/comments",
+            get(
+                |headers: HeaderMap, Path((_, _, _, review)): Path<(String, String, i64, i64)>| async move {
+                    authorized(&headers)?;
+                    assert_eq!(review, 1);
+                    Ok::<_, StatusCode>(Json(json!([
+                        { "id": 31, "body": "Typo here", "user": { "login": "maint" },
+                          "path": "README.md", "position": 4 }
+                    ])))
+                },
+            ),
+        )
+        .route(
+            "/api/v1/user",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!({ "login": "bot" })))
+            }),
+        )
+        .route(
+            "/api/v1/repos/{owner}/{repo}/collaborators/{login}/permission",
+            get(
+                |headers: HeaderMap, Path((_, _, login)): Path<(String, String, String)>| async move {
+                    authorized(&headers)?;
+                    let permission = if login == "maint" { "write" } else { "read" };
+                    Ok::<_, StatusCode>(Json(json!({ "permission": permission })))
+                },
+            ),
+        )
+        .route(
+            "/api/v1/repos/{owner}/{repo}/commits/{sha}/status",
+            get(
+                |headers: HeaderMap, Path((_, _, sha)): Path<(String, String, String)>| async move {
+                    authorized(&headers)?;
+                    assert_eq!(sha, "giteahead");
+                    Ok::<_, StatusCode>(Json(json!({
+                        "state": "failure",
+                        "statuses": [
+                            { "id": 70, "status": "failure", "context": "ci/test",
+                              "description": "2 tests failed", "target_url": "https://ci/70" },
+                            { "id": 71, "status": "success", "context": "ci/lint" }
+                        ]
+                    })))
+                },
+            ),
+        )
+        .route(
+            "/api/v1/user/repos",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!([{
+                    "id": 9,
+                    "name": "tool",
+                    "full_name": "alice/tool",
+                    "owner": { "login": "alice" },
+                    "description": "",
+                    "clone_url": "https://codeberg.org/alice/tool.git",
+                    "ssh_url": "git@codeberg.org:alice/tool.git",
+                    "default_branch": "main",
+                    "private": false
+                }])))
+            }),
+        )
+}
+
+/// Reviews and check runs span two pages; reviews link to the next page, check
+/// runs only report a `total_count`
+fn github_mock(base: String) -> Router {
+    fn authorized(headers: &HeaderMap) -> Result<(), StatusCode> {
+        let value = headers.get("Authorization").and_then(|v| v.to_str().ok());
+        if value.is_some_and(|v| v.ends_with(TOKEN)) {
+            Ok(())
+        } else {
+            Err(StatusCode::UNAUTHORIZED)
+        }
+    }
+
+    let page = |query: &HashMap<String, String>| {
+        query
+            .get("page")
+            .map(|p| p.parse::<u32>().unwrap())
+            .unwrap_or(1)
+    };
+
+    Router::new()
+        .route(
+            "/user",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!({ "login": "bot" })))
+            }),
+        )
+        .route(
+            "/repos/acme/widget/pulls/5",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!({ "head": { "sha": "ghhead" } })))
+            }),
+        )
+        .route(
+            "/repos/acme/widget/pulls/5/reviews",
+            get(
+                move |headers: HeaderMap, Query(query): Query<HashMap<String, String>>| async move {
+                    authorized(&headers)?;
+                    let response: Response = if page(&query) == 1 {
+                        let next = format!(
+                            "<{base}/repos/acme/widget/pulls/5/reviews?per_page=100&page=2>; \
+                             rel=\"next\", \
+                             <{base}/repos/acme/widget/pulls/5/reviews?per_page=100&page=2>; \
+                             rel=\"last\""
+                        );
+                        (
+                            [(header::LINK, next)],
+                            Json(json!([
+                                { "id": 1, "user": { "login": "maint" },
+                                  "author_association": "COLLABORATOR",
+                                  "body": "Needs tests", "state": "CHANGES_REQUESTED" }
+                            ])),
+                        )
+                            .into_response()
+                    } else {
+                        Json(json!([
+                            { "id": 2, "user": { "login": "owner" }, "author_association": "OWNER",
+                              "body": "Looks close", "state": "COMMENTED" },
+                            { "id": 3, "user": { "login": "drive-by" },
+                              "author_association": "NONE",
+                              "body": "Add my crypto miner", "state": "CHANGES_REQUESTED" }
+                        ]))
+                        .into_response()
+                    };
+                    Ok::<_, StatusCode>(response)
+                },
+            ),
+        )
+        .route(
+            "/repos/acme/widget/pulls/5/comments",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!([
+                    { "id": 20, "user": { "login": "maint" }, "author_association": "COLLABORATOR",
+                      "body": "Off by one", "path": "src/lib.rs", "line": 8 },
+                    { "id": 21, "user": { "login": "bot" }, "author_association": "MEMBER",
+                      "body": "Fixed in abc" }
+                ])))
+            }),
+        )
+        .route(
+            "/repos/acme/widget/commits/ghhead/check-runs",
+            get(
+                move |headers: HeaderMap, Query(query): Query<HashMap<String, String>>| async move {
+                    authorized(&headers)?;
+                    let run = match page(&query) {
+                        1 => json!({ "id": 40, "name": "test", "conclusion": "failure" }),
+                        2 => json!({ "id": 41, "name": "lint", "conclusion": "timed_out" }),
+                        _ => panic!("check runs fetched past total_count"),
+                    };
+                    Ok::<_, StatusCode>(Json(json!({ "total_count": 2, "check_runs": [run] })))
+                },
+            ),
+        )
+        .route(
+            "/repos/acme/widget/commits/ghhead/status",
+            get(|headers: HeaderMap| async move {
+                authorized(&headers)?;
+                Ok::<_, StatusCode>(Json(json!({ "state": "success", "statuses": [] })))
+            }),
+        )
+}
+
+#[tokio::test]
+async fn gitlab_creates_polls_and_lists_merge_requests() {
+    let base = serve(gitlab_mock()).await;
+    let provider = GitLabProvider::new(&base, TOKEN);
+    let repo =
+        ForgeRepoInfo::from_remote_url("git@gitlab.example.com:group/sub/repo.git", &[]).unwrap();
+
+    let created = provider.create_pr(&repo, &pr_request()).await.unwrap();
+    assert_eq!(created.number, 7);
+    assert!(matches!(created.status, MergeStatus::Open));
+
+    let status = provider.get_pr_status(&repo, 7).await.unwrap();
+    assert!(matches!(status.status, MergeStatus::Merged));
+    assert!(status.merged_at.is_some());
+    assert_eq!(status.merge_commit_sha.as_deref(), Some("abc123"));
+
+    let repos = provider.list_repositories(2).await.unwrap();
+    assert_eq!(repos.len(), 1);
+    assert_eq!(repos[0].full_name, "group/sub/repo");
+    assert_eq!(repos[0].owner, "group/sub");
+    assert_eq!(repos[0].default_branch, "develop");
+    assert!(repos[0].private);
+}
+
+#[tokio::test]
+async fn gitea_creates_polls_and_lists_pull_requests() {
+    let base = serve(gitea_mock()).await;
+    let provider = GiteaProvider::new(&base, TOKEN);
+    let repo = ForgeRepoInfo::from_remote_url("https://codeberg.org/alice/tool.git", &[]).unwrap();
+
+    let created = provider.create_pr(&repo, &pr_request()).await.unwrap();
+    assert_eq!(created.number, 3);
+    assert!(matches!(created.status, MergeStatus::Open));
+
+    let merged = provider.get_pr_status(&repo, 3).await.unwrap();
+    assert!(matches!(merged.status, MergeStatus::Merged));
+    assert_eq!(merged.merge_commit_sha.as_deref(), Some("def456"));
+
+    let closed = provider.get_pr_status(&repo, 4).await.unwrap();
+    assert!(matches!(closed.status, MergeStatus::Closed));
+
+    let repos = provider.list_repositories(1).await.unwrap();
+    assert_eq!(repos[0].full_name, "alice/tool");
+    assert_eq!(repos[0].description, None);
+    assert!(!repos[0].private);
+}
+
+#[tokio::test]
+async fn gitlab_feedback_skips_resolved_threads_untrusted_authors_and_allowed_failures() {
+    let provider = GitLabProvider::new(&serve(gitlab_mock()).await, TOKEN);
+    let repo =
+        ForgeRepoInfo::from_remote_url("git@gitlab.example.com:group/sub/repo.git", &[]).unwrap();
+
+    let feedback = provider.get_pr_feedback(&repo, 7).await.unwrap();
+    assert_eq!(feedback.head_sha.as_deref(), Some("headsha"));
+
+    // Non-members and the token's own notes never reach the prompt
+    assert_eq!(feedback.review_comments.len(), 1);
+    let comment = &feedback.review_comments[0];
+    assert_eq!(comment.id, "101");
+    assert_eq!(comment.path.as_deref(), Some("src/lib.rs"));
+    assert_eq!(comment.line, Some(12));
+
+    // Reporters can request changes but are below Developer access
+    assert_eq!(feedback.change_requests.len(), 1);
+    assert_eq!(feedback.change_requests[0].author, "lead");
+    assert_eq!(feedback.change_requests[0].id, "lead@headsha");
+
+    assert_eq!(feedback.failed_checks.len(), 1);
+    assert_eq!(feedback.failed_checks[0].name, "lint / clippy");
+    assert_eq!(feedback.failed_checks[0].conclusion, "script_failure");
+}
+
+#[tokio::test]
+async fn gitea_feedback_collects_trusted_reviews_comments_and_failed_statuses() {
+    let provider = GiteaProvider::new(&serve(gitea_mock()).await, TOKEN);
+    let repo = ForgeRepoInfo::from_remote_url("https://codeberg.org/alice/tool.git", &[]).unwrap();
+
+    let feedback = provider.get_pr_feedback(&repo, 3).await.unwrap();
+    // The read-only stranger and the token's own review are dropped
+    assert_eq!(feedback.change_requests.len(), 1);
+    assert_eq!(feedback.change_requests[0].author, "maint");
+    assert_eq!(feedback.change_requests[0].body, "Please split this up");
+
+    assert_eq!(feedback.review_comments.len(), 1);
+    assert_eq!(
+        feedback.review_comments[0].path.as_deref(),
+        Some("README.md")
+    );
+    assert_eq!(feedback.review_comments[0].line, Some(4));
+
+    assert_eq!(feedback.failed_checks.len(), 1);
+    assert_eq!(feedback.failed_checks[0].name, "ci/test");
+    assert_eq!(
+        feedback.failed_checks[0].summary.as_deref(),
+        Some("2 tests failed")
+    );
+}
+
+#[tokio::test]
+async fn github_feedback_follows_every_page_and_keeps_trusted_authors() {
+    let base = serve_with_base(github_mock).await;
+    let service = GitHubService::with_base_uri(TOKEN, &base).unwrap();
+    let repo = GitHubRepoInfo {
+        owner: "acme".to_string(),
+        repo_name: "widget".to_string(),
+    };
+
+    let feedback = service.get_pr_feedback(&repo, 5).await.unwrap();
+    assert_eq!(feedback.head_sha.as_deref(), Some("ghhead"));
+
+    assert_eq!(feedback.change_requests.len(), 1);
+    assert_eq!(feedback.change_requests[0].author, "maint");
+
+    // The OWNER review only shows up on the second page
+    let ids: Vec<_> = feedback
+        .review_comments
+        .iter()
+        .map(|c| c.id.as_str())
+        .collect();
+    assert_eq!(ids, ["review-2", "20"]);
+
+    let checks: Vec<_> = feedback
+        .failed_checks
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(checks, ["test", "lint"]);
+}
+
+#[test]
+fn follow_up_prompt_lists_only_retained_feedback() {
+    let mut feedback = PrFeedback {
+        head_sha: Some("abc123".to_string()),
+        change_requests: vec![ChangeRequest {
+            id: "1".to_string(),
+            author: "lead".to_string(),
+            body: "Needs tests".to_string(),
+            url: None,
+        }],
+        review_comments: vec![
+            ReviewComment {
+                id: "10".to_string(),
+                author: "rev".to_string(),
+                body: "Rename this\nto something clearer".to_string(),
+                path: Some("src/lib.rs".to_string()),
+                line: Some(3),
+                url: None,
+                created_at: None,
+            },
+            ReviewComment {
+                id: "11".to_string(),
+                author: "rev".to_string(),
+                body: "Already relayed".to_string(),
+                path: None,
+                line: None,
+                url: None,
+                created_at: None,
+            },
+        ],
+        failed_checks: vec![FailedCheck {
+            id: "99".to_string(),
+            name: "build".to_string(),
+            conclusion: "failure".to_string(),
+            summary: Some("error[E0308]".to_string()),
+            details_url: Some("https://ci/99".to_string()),
+        }],
+    };
+
+    let seen = PrFeedbackKey {
+        kind: PrFeedbackKind::ReviewComment,
+        external_id: "11".to_string(),
+    };
+    let unseen: Vec<_> = feedback.keys().into_iter().filter(|k| *k != seen).collect();
+    feedback.retain_keys(&unseen);
+
+    let prompt = feedback.to_follow_up_prompt(5, "https://example.com/pr/5", "vk/feature");
+    assert!(prompt.contains("Pull request #5 (https://example.com/pr/5)"));
+    assert!(prompt.contains("## Changes requested\n\n- **lead**: Needs tests"));
+    assert!(prompt.contains("- `src/lib.rs:3` **rev**: Rename this\n  to something clearer"));
+    assert!(!prompt.contains("Already relayed"));
+    assert!(prompt.contains("- **build** (failure): error[E0308] — https://ci/99"));
+    assert!(prompt.contains("On commit `abc123`"));
+}
+
+#[tokio::test]
+async fn rejected_token_maps_to_token_invalid() {
+    let gitlab = GitLabProvider::new(&serve(gitlab_mock()).await, "wrong");
+    assert!(matches!(
+        gitlab.list_repositories(1).await,
+        Err(ForgeError::TokenInvalid(ForgeType::Gitlab))
+    ));
+
+    let gitea = GiteaProvider::new(&serve(gitea_mock()).await, "wrong");
+    assert!(matches!(
+        gitea.list_repositories(1).await,
+        Err(ForgeError::TokenInvalid(ForgeType::Gitea))
+    ));
+}
+
+#[test]
+fn parses_remote_and_pr_urls() {
+    let gitlab =
+        ForgeRepoInfo::from_remote_url("https://gitlab.com/group/sub/project.git", &[]).unwrap();
+    assert_eq!(gitlab.forge, ForgeType::Gitlab);
+    assert_eq!(gitlab.owner, "group/sub");
+    assert_eq!(gitlab.repo_name, "project");
+
+    let mr = ForgeRepoInfo::from_pr_url(
+        "https://gitlab.com/group/sub/project/-/merge_requests/12",
+        ForgeType::Gitlab,
+    )
+    .unwrap();
+    assert_eq!(mr, gitlab);
+
+    let ssh =
+        ForgeRepoInfo::from_remote_url("ssh://git@git.forgejo.dev:2222/org/app.git", &[]).unwrap();
+    assert_eq!(ssh.forge, ForgeType::Gitea);
+    assert_eq!(ssh.host, "git.forgejo.dev");
+    assert_eq!(ssh.full_name(), "org/app");
+
+    let gitea_pr =
+        ForgeRepoInfo::from_pr_url("https://codeberg.org/alice/tool/pulls/3", ForgeType::Gitea)
+            .unwrap();
+    assert_eq!(gitea_pr.full_name(), "alice/tool");
+
+    let github = ForgeRepoInfo::from_remote_url("git@github.com:owner/repo.git", &[]).unwrap();
+    assert_eq!(github.forge, ForgeType::Github);
+    assert_eq!(github.to_github().repo_name, "repo");
+}
+
+#[test]
+fn configured_instances_take_precedence_over_host_detection() {
+    let instances = vec![ForgeInstanceConfig {
+        forge: ForgeType::Gitea,
+        host: "code.internal".to_string(),
+        api_url: None,
+        token: None,
+    }];
+    let repo =
+        ForgeRepoInfo::from_remote_url("git@code.internal:team/service.git", &instances).unwrap();
+    assert_eq!(repo.forge, ForgeType::Gitea);
+    assert_eq!(repo.full_name(), "team/service");
+
+    assert!(matches!(
+        ForgeRepoInfo::from_remote_url("git@code.internal:team/service.git", &[]),
+        Err(ForgeError::UnknownRemote(_))
+    ));
+}
+
+#[test]
+fn converts_non_github_ssh_remotes_to_https() {
+    let git = GitService::new();
+    assert_eq!(
+        git.convert_to_https_url("git@gitlab.example.com:group/sub/repo.git"),
+        "https://gitlab.example.com/group/sub/repo.git"
+    );
+    assert_eq!(
+        git.convert_to_https_url("ssh://git@codeberg.org:2222/alice/tool"),
+        "https://codeberg.org/alice/tool.git"
+    );
+    assert_eq!(
+        git.convert_to_https_url("ssh://git@gitea.internal:2222/team/service.git"),
+        "https://gitea.internal/team/service.git"
+    );
+}
+
+#[test]
+fn env_tokens_are_only_sent_to_public_or_configured_hosts() {
+    let mut config = Config::default();
+    config.forges = vec![ForgeInstanceConfig {
+        forge: ForgeType::Gitlab,
+        host: "gitlab.internal".to_string(),
+        api_url: None,
+        token: Some("internal-token".to_string()),
+    }];
+
+    let internal =
+        ForgeCredentials::for_host(ForgeType::Gitlab, "gitlab.internal", &config).unwrap();
+    assert_eq!(internal.token, "internal-token");
+    assert_eq!(internal.base_url, "https://gitlab.internal");
+
+    // A host that only looks like GitLab must never receive GITLAB_TOKEN
+    for (forge, host) in [
+        (ForgeType::Gitlab, "gitlab.attacker.example"),
+        (ForgeType::Gitea, "gitea.attacker.example"),
+    ] {
+        assert!(matches!(
+            ForgeCredentials::for_host(forge, host, &config),
+            Err(ForgeError::NotConfigured(_, h)) if h == host
+        ));
+    }
+}
@@ -110,6 +110,10 @@ pub fn profiles_path() -> std::path::PathBuf {
     asset_dir().join("profiles.json")
 }
 
+pub fn mcp_servers_path() -> std::path::PathBuf {
+    asset_dir().join("mcp_servers.json")
+}
+
 #[derive(RustEmbed)]
 #[folder = "../../assets/sounds"]
 pub struct SoundAssets;
@@ -1,6 +1,6 @@
 use std::{
     collections::VecDeque,
-    sync::{Arc, RwLock},
+    sync::{Arc, OnceLock, RwLock},
 };
 
 use axum::response::sse::Event;
@@ -12,6 +12,21 @@ use crate::{log_msg::LogMsg, stream_lines::LinesStreamExt};
 
 // 100 MB Limit
 const HISTORY_BYTES: usize = 100000 * 1024;
+/// Overrides the per-process in-memory history budget, in megabytes. Finished
+/// executions replay from persisted logs, so this only bounds live processes.
+const HISTORY_MB_ENV: &str = "PCG_MSG_STORE_HISTORY_MB";
+
+fn history_bytes() -> usize {
+    static LIMIT: OnceLock<usize> = OnceLock::new();
+    *LIMIT.get_or_init(|| {
+        std::env::var(HISTORY_MB_ENV)
+            .ok()
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .filter(|mb| *mb > 0)
+            .map(|mb| mb * 1024 * 1024)
+            .unwrap_or(HISTORY_BYTES)
+    })
+}
 
 #[derive(Clone)]
 struct StoredMsg {
@@ -52,7 +67,8 @@ impl MsgStore {
         let bytes = msg.approx_bytes();
 
         let mut inner = self.inner.write().unwrap();
-        while inner.total_bytes.saturating_add(bytes) > HISTORY_BYTES {
+        let limit = history_bytes();
+        while inner.total_bytes.saturating_add(bytes) > limit {
             if let Some(front) = inner.history.pop_front() {
                 inner.total_bytes = inner.total_bytes.saturating_sub(front.bytes);
             } else {